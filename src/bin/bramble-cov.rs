@@ -0,0 +1,50 @@
+use std::process::exit;
+
+use bramble_lang::project::CoverageMapping;
+
+/**
+A tool for turning the coverage counters written by a program which was compiled
+with `--coverage` into a report of how many times each line of its source code was
+run.  Takes the mapping file that was written by the compiler, followed by one or
+more counters files.  The counts from every run in every counters file are summed.
+ */
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        eprintln!("Usage: bramble-cov <coverage mapping> <counters file>...");
+        exit(1)
+    }
+
+    let mapping = std::fs::File::open(&args[1])
+        .map_err(|e| format!("{}", e))
+        .and_then(|mut f| CoverageMapping::read(&mut f).map_err(|e| format!("{}", e)))
+        .unwrap_or_else(|e| {
+            eprintln!("Could not read coverage mapping {}: {}", args[1], e);
+            exit(1)
+        });
+
+    let mut totals: Option<Vec<u64>> = None;
+    for path in &args[2..] {
+        let counts = std::fs::read(path)
+            .map_err(|e| format!("{}", e))
+            .and_then(|counts| mapping.merge(&counts))
+            .unwrap_or_else(|e| {
+                eprintln!("Could not read counters {}: {}", path, e);
+                exit(1)
+            });
+
+        totals = Some(match totals {
+            Some(totals) => totals.iter().zip(counts).map(|(t, c)| t + c).collect(),
+            None => counts,
+        });
+    }
+
+    let totals = totals.unwrap_or_default();
+    match mapping.report(&totals, |file| std::fs::read_to_string(file)) {
+        Ok(report) => print!("{}", report),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1)
+        }
+    }
+}
@@ -2,25 +2,43 @@ extern crate log;
 extern crate simplelog;
 
 use std::fs::File;
+use std::io::{Stderr, Write};
 use std::path::{Path, PathBuf};
-use std::str::FromStr;
 use std::time::Instant;
 
 use bramble_lang::compiler::diagnostics::Logger;
-use bramble_lang::compiler::import::Import;
-use bramble_lang::compiler::semantics::semanticnode::SemanticContext;
-use bramble_lang::compiler::{transform, MirProject};
-use bramble_lang::diagnostics::{write_source_map, ConsoleWriter, JsonWriter};
+use bramble_lang::compiler::fuel::Fuel;
+use bramble_lang::compiler::parser::grammar;
+use bramble_lang::compiler::passes::PassRegistry;
+use bramble_lang::compiler::pipeline::{CompileError, Pipeline};
+use bramble_lang::compiler::semantics::complexity::{check_complexity, ComplexityReport};
+use bramble_lang::compiler::semantics::dead_branch::{prune_dead_branches, Defines};
+use bramble_lang::compiler::semantics::explain::explain_item_types;
+use bramble_lang::compiler::semantics::format::check_formats;
+use bramble_lang::compiler::semantics::lint::{
+    lint, lint_mut_assignments, lint_trailing_commas, Lint,
+};
+use bramble_lang::compiler::semantics::query::SemanticQueries;
+use bramble_lang::compiler::transform::TransformError;
+use bramble_lang::compiler::verify::{verify_mir, verify_semantic_ast};
+use bramble_lang::compiler::{CoverageMap, MirProject};
+use bramble_lang::diagnostics::{write_source_map, ConsoleWriter, JsonWriter, Reporter};
 use inkwell::context::Context;
 
 use bramble_lang::project::*;
 use bramble_lang::*;
 
-use bramble_lang::compiler::ast::{Module, MAIN_MODULE};
+use bramble_lang::compiler::ast::{print_ast, MAIN_MODULE};
+use bramble_lang::compiler::{
+    Column, CompilerDisplayError, CompilerError, LineNumber, SourceMap, Span,
+};
 
 const BRAID_FILE_EXT: &str = "br";
 const USER_MAIN_FN: &str = "my_main";
 
+/// The output path which means that the object code is written to stdout
+const STDOUT_PATH: &str = "-";
+
 fn main() -> Result<(), i32> {
     let config = configure_cli().get_matches();
 
@@ -28,20 +46,72 @@ fn main() -> Result<(), i32> {
         configure_logging(level).expect("Failed to configure logger.")
     }
 
-    let string_table = StringTable::new();
+    if list_targets(&config) {
+        for (name, description) in llvm::registered_targets() {
+            println!("{:<16} {}", name, description);
+        }
+        return Ok(());
+    }
+
+    let error_limit = get_error_limit(&config).unwrap();
+    let mut reporter = Reporter::stderr(error_limit).with_timings(print_timings(&config));
 
-    let input = config
-        .value_of("input")
+    // Every stage of the compilation shares the same budget of work
+    let fuel = Fuel::new(get_fuel(&config).unwrap(), get_timeout(&config).unwrap());
+
+    // Every input is read from the filesystem
+    let files = FsProvider;
+
+    // The project is named after the first input
+    let src_paths = get_inputs(&config);
+    let src_path = *src_paths
+        .first()
         .expect("Expected an input source file to compile");
-    let src_path = Path::new(input);
-    let project_name =
-        get_project_name(src_path).unwrap_or_else(|_| panic!("Could not open {:?}", src_path));
-    let source_map = build_source_map(src_path, BRAID_FILE_EXT).unwrap();
+    let project_name = get_project_name(&files, src_path)
+        .unwrap_or_else(|_| panic!("Could not open {:?}", src_path));
+
+    // Every file that is written is named after the project, so that compiling several
+    // projects into the same output directory does not overwrite any of their files
+    let out_dir = get_out_dir(&config);
+    if let Err(e) = std::fs::create_dir_all(out_dir) {
+        let _ = reporter.error(format!(
+            "Could not create output directory {}: {}",
+            out_dir.display(),
+            e
+        ));
+        return Err(ERR_OUTPUT_DIR_ERROR);
+    }
+    let out_stem = output_file_stem(project_name);
+    let out_file = |ext: &str| out_dir.join(format!("{}.{}", out_stem, ext));
+
+    // The grammar does not depend on the input, so it is written before compiling
+    if emit_grammar(&config) {
+        if let Err(e) = write_emitted(&out_file("ebnf"), Ok(grammar())) {
+            let _ = reporter.error(format!("Failed to write grammar file: {}", e));
+            return Err(ERR_EMIT_WRITE_ERROR);
+        }
+    }
 
-    let manifests: Vec<_> = match read_manifests(&config) {
+    let mut source_map = match build_source_map(&files, &src_paths, BRAID_FILE_EXT) {
+        Ok(sm) => sm,
+        Err(e) => {
+            let _ = reporter.error(e);
+            return Err(ERR_SOURCE_ERROR);
+        }
+    };
+    for (from, to) in get_path_remaps(&config).unwrap() {
+        source_map.remap_path_prefix(from, to);
+    }
+
+    // Pre-size the string table, assuming roughly one distinct string for every
+    // 32 bytes of source code
+    let source_size = source_map.span().map_or(0, |s| s.high().as_u32() as usize);
+    let string_table = StringTable::with_capacity(source_size / 32);
+
+    let manifests: Vec<_> = match read_manifests(&config, &files) {
         Ok(imports) => imports,
         Err(errs) => {
-            print_errs(&errs, &source_map, &string_table);
+            print_errs(&errs, error_limit, &source_map, &string_table);
             return Err(ERR_IMPORT_ERROR);
         }
     };
@@ -61,52 +131,110 @@ fn main() -> Result<(), i32> {
     }
 
     // Setup JSON Trace writer
-    let trace_file = File::create("./target/trace.json").unwrap();
+    let trace_file = File::create(out_file("trace.json")).unwrap();
     let json_writer = JsonWriter::new(trace_file, &source_map, &string_table);
     if enable_json_tracing(&config) {
         tracer.add_writer(&json_writer);
 
-        let source_map_file = File::create("./target/sourcemap.json").unwrap();
+        let source_map_file = File::create(out_file("sourcemap.json")).unwrap();
         write_source_map(source_map_file, &source_map);
     }
 
     let tokenize_time = Instant::now();
-    let token_sets = match tokenize_source_map(&source_map, src_path, &string_table, &tracer) {
-        Ok(ts) => ts,
-        Err(errs) => {
-            print_errs(&errs, &source_map, &string_table);
-            return Err(ERR_LEXER_ERROR);
+    let token_sets =
+        match tokenize_source_map(&files, &source_map, &src_paths, &string_table, &tracer) {
+            Ok(ts) => ts,
+            Err(errs) => {
+                print_errs(&errs, error_limit, &source_map, &string_table);
+                return Err(ERR_LEXER_ERROR);
+            }
+        };
+    let _ = reporter.timing("Lexer", tokenize_time.elapsed());
+
+    if emit_tokens(&config) {
+        let tokens = print_tokens(&token_sets, &source_map, &string_table);
+        if let Err(e) = write_emitted(&out_file("tokens"), tokens) {
+            let _ = reporter.error(format!("Failed to write tokens file: {}", e));
+            return Err(ERR_EMIT_WRITE_ERROR);
         }
-    };
-    let tokenize_duration = tokenize_time.elapsed();
-    eprintln!("Lexer: {}", tokenize_duration.as_secs_f32());
+    }
 
     if stop_stage == Some(Stage::Lexer) {
         return Ok(());
     }
 
+    // Style lints can only be found in the tokens, so check for them before parsing
+    let mut style_lints: Vec<CompilerError<Lint>> = vec![];
+    for unit in &token_sets {
+        if enable_trailing_comma_lint(&config) {
+            style_lints.extend(lint_trailing_commas(unit.data()));
+        }
+        if enable_mut_assignment_lint(&config) {
+            style_lints.extend(lint_mut_assignments(unit.data()));
+        }
+    }
+
     let parse_time = Instant::now();
     let project_name_id = string_table.insert(project_name.into());
-    let root = match parse_project(
+    let mut root = match parse_project(
         project_name_id,
         token_sets,
         &source_map,
         &string_table,
         &tracer,
+        &fuel,
     ) {
         Ok(root) => root,
         Err(errs) => {
-            print_errs(&errs, &source_map, &string_table);
-            return Err(ERR_PARSER_ERROR);
+            print_errs(&errs, error_limit, &source_map, &string_table);
+            return Err(exhausted_or(&fuel, ERR_PARSER_ERROR));
         }
     };
-    let parse_duration = parse_time.elapsed();
-    eprintln!("Parser: {}", parse_duration.as_secs_f32());
+    let _ = reporter.timing("Parser", parse_time.elapsed());
+
+    // An empty library is valid, but a program must at least define its main function
+    if is_empty_project(&root) && !config.is_present("manifest") {
+        let _ = reporter.error(format!(
+            "{} does not define any items. A program must define `{}`, or use --manifest to build an empty library.",
+            project_name, USER_MAIN_FN
+        ));
+        return Err(ERR_EMPTY_PROJECT);
+    }
+
+    if emit_ast(&config) {
+        let ast = print_ast(&root, &source_map, &string_table);
+        if let Err(e) = write_emitted(&out_file("ast"), ast) {
+            let _ = reporter.error(format!("Failed to write AST file: {}", e));
+            return Err(ERR_EMIT_WRITE_ERROR);
+        }
+    }
+
+    if emit_ast_json(&config) {
+        match AstDump::extract(&root, &source_map, &string_table)
+            .map_err(|e| format!("{:?}", e))
+            .and_then(|ast| {
+                std::fs::File::create(out_file("ast.json"))
+                    .map_err(|e| format!("{}", e))
+                    .and_then(|mut f| ast.write(&mut f).map_err(|e| format!("{}", e)))
+            }) {
+            Ok(()) => (),
+            Err(e) => {
+                let _ = reporter.error(format!("Failed to write AST file: {}", e));
+                return Err(ERR_EMIT_WRITE_ERROR);
+            }
+        }
+    }
 
     if stop_stage == Some(Stage::Parser) {
         return Ok(());
     }
 
+    let defines = Defines::new(&string_table, &get_defines(&config));
+    if let Err(msg) = prune_dead_branches(&mut root, &defines) {
+        print_errs(&[msg], error_limit, &source_map, &string_table);
+        return Err(ERR_TYPE_CHECK);
+    }
+
     // Type Check
     let imports: Result<Vec<_>, _> = manifests
         .into_iter()
@@ -116,31 +244,212 @@ fn main() -> Result<(), i32> {
     let imports = match imports {
         Ok(im) => im,
         Err(msg) => {
-            print_errs(&[msg], &source_map, &string_table);
+            print_errs(&[msg], error_limit, &source_map, &string_table);
             return Err(ERR_IMPORT_ERROR);
         }
     };
 
-    let main_mod_id = string_table.insert(MAIN_MODULE.into());
     let main_fn_id = string_table.insert(USER_MAIN_FN.into());
-    let semantic_time = Instant::now();
-    let semantic_ast =
-        match resolve_types_with_imports(&root, main_mod_id, main_fn_id, &imports, &tracer) {
-            Ok(ast) => ast,
+
+    // Only the item which contains the expression is resolved, so that an expression
+    // can be explained even while other parts of the program do not type check
+    if let Some(target) = get_explain_target(&config).unwrap() {
+        let span = match explain_target_span(&target, &source_map) {
+            Some(span) => span,
+            None => {
+                let _ = reporter.error("No source code at the location given to --explain-types");
+                return Err(ERR_EXPLAIN_ERROR);
+            }
+        };
+
+        let main_mod_id = string_table.insert(MAIN_MODULE.into());
+        let mut queries = match SemanticQueries::new(
+            &root,
+            main_mod_id,
+            main_fn_id,
+            &imports,
+            &string_table,
+            &tracer,
+        ) {
+            Ok(queries) => queries,
             Err(msg) => {
-                print_errs(&[msg], &source_map, &string_table);
+                print_errs(&[msg], error_limit, &source_map, &string_table);
+                return Err(ERR_TYPE_CHECK);
+            }
+        };
+
+        let item = match queries.item_at(span) {
+            Some(path) => queries.type_of(&path),
+            None => None,
+        };
+        let explained = match item {
+            Some(Ok(item)) => explain_item_types(item, span, &source_map, &string_table),
+            Some(Err(msg)) => {
+                print_errs(
+                    std::slice::from_ref(msg),
+                    error_limit,
+                    &source_map,
+                    &string_table,
+                );
                 return Err(ERR_TYPE_CHECK);
             }
+            None => Ok(None),
         };
-    let semantic_duration = semantic_time.elapsed();
-    eprintln!("Semantic: {}", semantic_duration.as_secs_f32());
+
+        match explained {
+            Ok(Some(explanation)) => print!("{}", explanation),
+            Ok(None) => {
+                let _ = reporter.error("No expression at the location given to --explain-types");
+                return Err(ERR_EXPLAIN_ERROR);
+            }
+            Err(e) => {
+                let _ = reporter.error(format!("Failed to explain types: {:?}", e));
+                return Err(ERR_EXPLAIN_ERROR);
+            }
+        }
+        return Ok(());
+    }
+
+    // The compiler does not add any passes of its own, but a program which drives the
+    // compiler through the library registers its passes with the pipeline it runs
+    let mut passes = PassRegistry::new();
+    let mut pipeline = Pipeline::new(&mut passes, &source_map, &string_table, &tracer)
+        .with_imports(&imports)
+        .with_fuel(&fuel);
+
+    let semantic_time = Instant::now();
+    let analyzed = pipeline.analyze(root, main_fn_id);
+    let semantic_ast = report_stage(
+        analyzed,
+        &mut pipeline,
+        &fuel,
+        &mut reporter,
+        error_limit,
+        &source_map,
+        &string_table,
+    )?;
+    let _ = reporter.timing("Semantic", semantic_time.elapsed());
+
+    let verify = verify_ir(&config);
+    if verify {
+        let errs = verify_semantic_ast(&semantic_ast, &imports);
+        if !errs.is_empty() {
+            print_errs(&errs, error_limit, &source_map, &string_table);
+            return Err(ERR_VERIFY_ERROR);
+        }
+    }
+
+    let format_errs = check_formats(&semantic_ast, &string_table);
+    if !format_errs.is_empty() {
+        print_errs(&format_errs, error_limit, &source_map, &string_table);
+        return Err(ERR_TYPE_CHECK);
+    }
+
+    let complexity_limits = get_complexity_limits(&config).unwrap();
+    let mut lints = style_lints;
+    lints.extend(lint(&semantic_ast));
+    lints.extend(check_complexity(&semantic_ast, &complexity_limits));
+    print_warnings(&lints, error_limit, &source_map, &string_table);
+
+    if emit_complexity(&config) {
+        match ComplexityReport::new(
+            &semantic_ast,
+            &complexity_limits,
+            &source_map,
+            &string_table,
+        )
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|report| {
+            std::fs::File::create(out_file("complexity.json"))
+                .map_err(|e| format!("{}", e))
+                .and_then(|mut f| report.write(&mut f).map_err(|e| format!("{}", e)))
+        }) {
+            Ok(()) => (),
+            Err(e) => {
+                let _ = reporter.error(format!("Failed to write complexity file: {}", e));
+                return Err(ERR_COMPLEXITY_WRITE_ERROR);
+            }
+        }
+    }
+
+    if emit_symbols(&config) {
+        match SymbolList::extract(&semantic_ast, &source_map, &string_table)
+            .map_err(|e| format!("{:?}", e))
+            .and_then(|symbols| {
+                std::fs::File::create(out_file("symbols.json"))
+                    .map_err(|e| format!("{}", e))
+                    .and_then(|mut f| symbols.write(&mut f).map_err(|e| format!("{}", e)))
+            }) {
+            Ok(()) => (),
+            Err(e) => {
+                let _ = reporter.error(format!("Failed to write symbols file: {}", e));
+                return Err(ERR_SYMBOLS_WRITE_ERROR);
+            }
+        }
+    }
 
     if stop_stage == Some(Stage::Semantic) {
         return Ok(());
     }
 
+    // Code generation does work in proportion to the size of the program, so its fuel
+    // is burned before it starts
+    if let Err(e) = fuel
+        .burn(semantic_ast.iter_preorder().count() as u64)
+        .and_then(|_| fuel.check_time())
+    {
+        let _ = reporter.error(format!("Code generation was stopped because {}", e));
+        return Err(ERR_RESOURCE_EXHAUSTED);
+    }
+
     // Configure the compiler
-    let output_target = config.value_of("output").unwrap_or("./target/output.asm");
+    // When the object code is written to stdout, LLVM still writes it to a file first
+    let to_stdout = config.value_of("output") == Some(STDOUT_PATH);
+    let output_target = match config.value_of("output") {
+        Some(output) if !to_stdout => PathBuf::from(output),
+        _ => out_file("obj"),
+    };
+    if let Some(parent) = output_target.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            let _ = reporter.error(format!(
+                "Could not create output directory {}: {}",
+                parent.display(),
+                e
+            ));
+            return Err(ERR_OUTPUT_DIR_ERROR);
+        }
+    }
+    let codegen_options = llvm::CodegenOptions {
+        zero_init_locals: zero_init_locals(&config),
+        sanitizers: get_sanitizers(&config)
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect(),
+        target_triple: get_target(&config).map(|triple| triple.into()),
+        target_cpu: get_target_cpu(&config).map(|cpu| cpu.into()),
+        target_features: get_target_features(&config)
+            .iter()
+            .map(|f| f.to_string())
+            .collect(),
+        continue_on_ice: continue_on_ice(&config),
+        bounds_checks: bounds_checks(&config),
+        debug_info: debug_info(&config),
+        optimize: optimize(&config),
+        signal_handlers: signal_handlers(&config),
+    };
+
+    if emit_stats(&config) {
+        match llvm::target_stats(&codegen_options) {
+            Ok(stats) => {
+                let _ = reporter.message(stats);
+            }
+            Err(msg) => {
+                let _ = reporter.error(msg);
+                return Err(ERR_LLVM_IR_ERROR);
+            }
+        }
+    }
+
     if !enable_mir_beta(&config) {
         let llvm_time = Instant::now();
         let context = Context::create();
@@ -151,37 +460,111 @@ fn main() -> Result<(), i32> {
             &source_map,
             &string_table,
             &tracer,
+            codegen_options.clone(),
         );
         match llvm.ingest(&semantic_ast, main_fn_id) {
             Ok(()) => (),
             Err(msg) => {
-                println!("LLVM IR translation failed: {}", msg);
+                let _ = reporter.error(format!("LLVM IR translation failed: {}", msg));
                 return Err(ERR_LLVM_IR_ERROR);
             }
         }
 
+        if !llvm.internal_errors().is_empty() {
+            print_errs(
+                llvm.internal_errors(),
+                error_limit,
+                &source_map,
+                &string_table,
+            );
+            return Err(ERR_INTERNAL_ERROR);
+        }
+
+        if verify {
+            if let Err(msg) = llvm.verify() {
+                let _ = reporter.error(format!("LLVM IR failed verification: {}", msg));
+                return Err(ERR_VERIFY_ERROR);
+            }
+        }
+
+        let remarks = emit_remarks(&config).then(|| llvm.collect_remarks());
+        llvm.optimize();
+
         if emit_llvm_ir(&config) {
-            llvm.emit_llvm_ir(Path::new(&format!("./target/{}.ll", project_name)));
+            llvm.emit_llvm_ir(&out_file("ll"));
+        }
+
+        if emit_header(&config) {
+            write_header(
+                &out_file("h"),
+                project_name,
+                llvm.data_section(),
+                &mut reporter,
+            )?;
         }
 
-        llvm.emit_object_code(Path::new(output_target), emit_asm(&config))
+        llvm.emit_object_code(&output_target, emit_asm(&config))
             .unwrap();
 
-        let llvm_duration = llvm_time.elapsed();
-        eprintln!("LLVM: {}", llvm_duration.as_secs_f32());
-    } else {
-        eprintln!("MIR BETA!! :D");
+        if let Some(remarks) = remarks {
+            let remarks = remarks.finish(&source_map);
+            print_warnings(&remarks, error_limit, &source_map, &string_table);
+        }
 
+        let _ = reporter.timing("LLVM", llvm_time.elapsed());
+    } else {
         let mir_time = Instant::now();
-        let mir = gen_mir(&semantic_ast, &imports);
-        let mir_duration = mir_time.elapsed();
-        eprintln!("MIR Generation: {}", mir_duration.as_secs_f32());
+        let pointer_width = match llvm::pointer_width(&codegen_options) {
+            Ok(width) => width,
+            Err(msg) => {
+                let _ = reporter.error(msg);
+                return Err(ERR_LLVM_IR_ERROR);
+            }
+        };
+        let lowered = pipeline.lower(&semantic_ast, pointer_width as u64);
+        let mir = report_stage(
+            lowered,
+            &mut pipeline,
+            &fuel,
+            &mut reporter,
+            error_limit,
+            &source_map,
+            &string_table,
+        )?;
+        let _ = reporter.timing("MIR Generation", mir_time.elapsed());
 
         if emit_mir(&config) {
-            println!("=== MIR ===\n\n{}", mir);
+            if let Err(e) = std::fs::write(out_file("mir"), mir.to_string()) {
+                let _ = reporter.error(format!("Failed to write MIR file: {}", e));
+                return Err(ERR_EMIT_WRITE_ERROR);
+            }
+        }
+
+        if verify {
+            let errs = verify_mir(&mir);
+            if !errs.is_empty() {
+                print_errs(&errs, error_limit, &source_map, &string_table);
+                return Err(ERR_VERIFY_ERROR);
+            }
+        }
+
+        let coverage_map = coverage(&config).then(|| CoverageMap::new(&mir));
+        if let Some(coverage_map) = &coverage_map {
+            match CoverageMapping::extract(coverage_map, &source_map, &string_table)
+                .map_err(|e| format!("{:?}", e))
+                .and_then(|mapping| {
+                    std::fs::File::create(out_file("coverage.json"))
+                        .map_err(|e| format!("{}", e))
+                        .and_then(|mut f| mapping.write(&mut f).map_err(|e| format!("{}", e)))
+                }) {
+                Ok(()) => (),
+                Err(e) => {
+                    let _ = reporter.error(format!("Failed to write coverage mapping file: {}", e));
+                    return Err(ERR_COVERAGE_WRITE_ERROR);
+                }
+            }
         }
 
-        let path = Path::new(output_target);
         let llvm_time = Instant::now();
         gen_llvm(
             project_name,
@@ -189,24 +572,37 @@ fn main() -> Result<(), i32> {
             main_fn_id,
             &source_map,
             &string_table,
-            path,
-            emit_llvm_ir(&config),
+            &output_target,
+            emit_llvm_ir(&config).then(|| out_file("ll")).as_deref(),
+            emit_header(&config).then(|| out_file("h")).as_deref(),
             emit_asm(&config),
-        );
+            emit_remarks(&config),
+            verify,
+            &mut reporter,
+            error_limit,
+            codegen_options,
+            coverage_map.as_ref(),
+        )?;
+
+        let _ = reporter.timing("MIR 2 LLVM", llvm_time.elapsed());
+    }
 
-        let llvm_duration = llvm_time.elapsed();
-        eprintln!("MIR 2 LLVM: {}", llvm_duration.as_secs_f32());
+    if to_stdout {
+        if let Err(e) = copy_to_stdout(&output_target) {
+            let _ = reporter.error(format!("Failed to write object code to stdout: {}", e));
+            return Err(ERR_EMIT_WRITE_ERROR);
+        }
     }
 
     if config.is_present("manifest") {
         let manifest = Manifest::extract(&semantic_ast, &source_map, &string_table).unwrap();
-        match std::fs::File::create(format!("./target/{}.manifest", project_name))
+        match std::fs::File::create(out_file("manifest"))
             .map_err(|e| format!("{}", e))
             .and_then(|mut f| manifest.write(&mut f).map_err(|e| format!("{}", e)))
         {
             Ok(()) => (),
             Err(e) => {
-                println!("Failed to write manifest file: {}", e);
+                let _ = reporter.error(format!("Failed to write manifest file: {}", e));
                 return Err(ERR_MANIFEST_WRITE_ERROR);
             }
         }
@@ -215,10 +611,96 @@ fn main() -> Result<(), i32> {
     Ok(())
 }
 
-fn gen_mir(module: &Module<SemanticContext>, imports: &[Import]) -> MirProject {
-    let mut project = MirProject::new();
-    transform::transform(module, imports, &mut project).unwrap();
-    project
+/// Returns the span of the source code at the location given to `--explain-types`.
+/// The end of the location is the last character of the source code, so it is
+/// included in the span.
+fn explain_target_span(target: &ExplainTarget, sm: &SourceMap) -> Option<Span> {
+    let offset = |(line, col): (u32, u32)| {
+        sm.offset_at(
+            target.file.as_deref(),
+            LineNumber::new(line),
+            Column::new(col),
+        )
+    };
+
+    let low = offset(target.start)?;
+    let mut high = offset(target.end.unwrap_or(target.start))?;
+    high += 1;
+    Some(Span::new(low, high))
+}
+
+/// Prints the warnings which custom passes reported during a stage of the pipeline and,
+/// if the stage failed, why it failed, and returns the exit code for the failure.
+fn report_stage<T>(
+    result: Result<T, CompileError>,
+    pipeline: &mut Pipeline,
+    fuel: &Fuel,
+    reporter: &mut Reporter<Stderr>,
+    error_limit: usize,
+    sm: &SourceMap,
+    st: &StringTable,
+) -> Result<T, i32> {
+    print_warnings(&pipeline.take_warnings(), error_limit, sm, st);
+    match result {
+        Ok(v) => Ok(v),
+        Err(CompileError::Pass(errs)) => {
+            print_errs(&errs, error_limit, sm, st);
+            Err(ERR_PASS_ERROR)
+        }
+        Err(CompileError::Semantic(msg)) => {
+            print_errs(&[msg], error_limit, sm, st);
+            Err(exhausted_or(fuel, ERR_TYPE_CHECK))
+        }
+        Err(CompileError::Reflection(e)) => {
+            let _ = reporter.error(format!("Failed to evaluate reflection builtins: {:?}", e));
+            Err(ERR_TYPE_CHECK)
+        }
+        Err(CompileError::Transform(TransformError::Unsupported(err))) => {
+            print_errs(&[err], error_limit, sm, st);
+            Err(ERR_MIR_UNSUPPORTED)
+        }
+        Err(CompileError::Transform(e)) => {
+            let _ = reporter.error(format!("MIR generation failed: {:?}", e));
+            Err(ERR_INTERNAL_ERROR)
+        }
+    }
+}
+
+/// Returns the exit code for a stage which failed, which is the given code unless the
+/// stage was stopped because the compilation ran out of fuel or time.
+fn exhausted_or(fuel: &Fuel, code: i32) -> i32 {
+    match fuel.exhausted() {
+        Some(_) => ERR_RESOURCE_EXHAUSTED,
+        None => code,
+    }
+}
+
+/// Writes the text rendering of an intermediate representation to the given file.
+fn write_emitted(path: &Path, text: Result<String, CompilerDisplayError>) -> Result<(), String> {
+    let text = text.map_err(|e| format!("{:?}", e))?;
+    std::fs::write(path, text).map_err(|e| format!("{}", e))
+}
+
+/// Copies the contents of the given file to stdout.
+fn copy_to_stdout(path: &Path) -> std::io::Result<()> {
+    let mut file = File::open(path)?;
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    std::io::copy(&mut file, &mut out)?;
+    out.flush()
+}
+
+/// Writes the C header which lists the global data of the program to the given file.
+fn write_header(
+    path: &Path,
+    name: &str,
+    data: &llvm::DataSection,
+    reporter: &mut Reporter<Stderr>,
+) -> Result<(), i32> {
+    std::fs::write(path, data.header(name)).map_err(|e| {
+        let _ = reporter.error(format!("Failed to write header file: {}", e));
+        ERR_EMIT_WRITE_ERROR
+    })
 }
 
 fn gen_llvm(
@@ -228,30 +710,66 @@ fn gen_llvm(
     sm: &compiler::SourceMap,
     table: &StringTable,
     output: &Path,
-    emit_ir: bool,
+    llvm_ir_file: Option<&Path>,
+    header_file: Option<&Path>,
     emit_asm: bool,
-) {
+    emit_remarks: bool,
+    verify: bool,
+    reporter: &mut Reporter<Stderr>,
+    error_limit: usize,
+    options: llvm::CodegenOptions,
+    coverage: Option<&CoverageMap>,
+) -> Result<(), i32> {
     let context = Context::create();
     let module = context.create_module(name);
     let builder = context.create_builder();
 
-    let mut xfmr = llvm::LlvmProgramBuilder::new(&context, &module, &builder, sm, table, main_name);
+    let continue_on_ice = options.continue_on_ice;
+    let mut xfmr =
+        llvm::LlvmProgramBuilder::new(&context, &module, &builder, sm, table, main_name, options);
 
-    let proj_traverser = compiler::ProgramTraverser::new(mir, sm, table);
+    let mut proj_traverser = compiler::ProgramTraverser::new(mir, sm, table);
+    proj_traverser.set_continue_on_ice(continue_on_ice);
+    if let Some(coverage) = coverage {
+        xfmr.add_coverage_counters(coverage.len());
+        proj_traverser.set_coverage(coverage);
+    }
 
     // Traverser is given a MirProject
     // call traverser.map(llvm) this will use the llvm xfmr to map MirProject to LlvmProject
-    proj_traverser.map(&mut xfmr);
+    if let Err(errs) = proj_traverser.map(&mut xfmr) {
+        print_errs(&errs, error_limit, sm, table);
+        return Err(ERR_INTERNAL_ERROR);
+    }
 
     let llvm = xfmr.complete();
 
-    if emit_ir {
-        llvm.emit_llvm_ir(Path::new(&format!("./target/{}.ll", name)))
-            .unwrap();
+    if verify {
+        if let Err(msg) = llvm.verify() {
+            let _ = reporter.error(format!("LLVM IR failed verification: {}", msg));
+            return Err(ERR_VERIFY_ERROR);
+        }
     }
 
-    let p = PathBuf::from_str(&format!("./target/{}.s", name)).unwrap();
+    let remarks = emit_remarks.then(|| llvm.collect_remarks());
+    llvm.optimize();
+
+    if let Some(llvm_ir_file) = llvm_ir_file {
+        llvm.emit_llvm_ir(llvm_ir_file).unwrap();
+    }
+
+    if let Some(header_file) = header_file {
+        write_header(header_file, name, llvm.data_section(), reporter)?;
+    }
+
+    // The assembly is written next to the object file, as it is by the AST code generator
+    let p = output.with_extension("s");
     let asm_file = if emit_asm { Some(p.as_path()) } else { None };
 
-    llvm.emit_object_code(asm_file, output)
+    llvm.emit_object_code(asm_file, output);
+
+    if let Some(remarks) = remarks {
+        print_warnings(&remarks.finish(sm), error_limit, sm, table);
+    }
+    Ok(())
 }
@@ -1,9 +1,16 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
 use clap::{App, Arg, ArgMatches};
 use log::LevelFilter;
 use simplelog::*;
 
 use crate::{
-    compiler::{CompilerDisplay, CompilerDisplayError, SourceMap},
+    compiler::{
+        semantics::complexity::{ComplexityLimits, Metric},
+        CompilerDisplay, CompilerDisplayError, SourceMap,
+    },
+    diagnostics::Reporter,
     StringTable,
 };
 
@@ -15,11 +22,47 @@ pub const ERR_LLVM_IR_ERROR: i32 = 4;
 pub const ERR_LEXER_ERROR: i32 = 5;
 pub const ERR_IMPORT_ERROR: i32 = 6;
 pub const ERR_MANIFEST_WRITE_ERROR: i32 = 7;
+pub const ERR_SOURCE_ERROR: i32 = 8;
+pub const ERR_SYMBOLS_WRITE_ERROR: i32 = 9;
+pub const ERR_OUTPUT_DIR_ERROR: i32 = 10;
+pub const ERR_VERIFY_ERROR: i32 = 11;
+pub const ERR_COMPLEXITY_WRITE_ERROR: i32 = 12;
+pub const ERR_EMIT_WRITE_ERROR: i32 = 13;
+pub const ERR_EMPTY_PROJECT: i32 = 14;
+pub const ERR_INTERNAL_ERROR: i32 = 15;
+pub const ERR_EXPLAIN_ERROR: i32 = 16;
+pub const ERR_PASS_ERROR: i32 = 17;
+pub const ERR_COVERAGE_WRITE_ERROR: i32 = 18;
+pub const ERR_RESOURCE_EXHAUSTED: i32 = 19;
+pub const ERR_MIR_UNSUPPORTED: i32 = 20;
 
-pub fn print_errs<E: CompilerDisplay>(errs: &[E], sm: &SourceMap, st: &StringTable) {
-    for e in errs {
-        println!("Error: {}", e.fmt(sm, st).unwrap());
-    }
+/// The directory that output files are written to, if the user does not set `--out-dir`
+pub const DEFAULT_OUT_DIR: &str = "./target";
+
+/// The number of errors that will be printed, if the user does not set `--error-limit`
+pub const DEFAULT_ERROR_LIMIT: usize = 20;
+
+/// Prints at most `limit` errors and then a summary of how many errors were not printed.
+/// If `limit` is 0, then every error is printed.
+///
+/// Errors are written to stderr, so that stdout only contains the output of the compiler.
+pub fn print_errs<E: CompilerDisplay>(errs: &[E], limit: usize, sm: &SourceMap, st: &StringTable) {
+    // If stderr cannot be written to, then there is nowhere left to report the failure
+    let _ = Reporter::stderr(limit).errors(errs, sm, st);
+}
+
+/// Prints at most `limit` warnings and then a summary of how many warnings were not printed.
+/// If `limit` is 0, then every warning is printed.
+///
+/// Warnings are written to stderr so that they do not mix with the output of a successful
+/// compilation.
+pub fn print_warnings<E: CompilerDisplay>(
+    warnings: &[E],
+    limit: usize,
+    sm: &SourceMap,
+    st: &StringTable,
+) {
+    let _ = Reporter::stderr(limit).warnings(warnings, sm, st);
 }
 
 #[derive(PartialEq)]
@@ -29,6 +72,20 @@ pub enum Stage {
     Semantic,
 }
 
+/// The source code which `--explain-types` explains the type of
+#[derive(Debug, PartialEq)]
+pub struct ExplainTarget {
+    /// The file the source code is in; if not given then it is in the first input
+    pub file: Option<PathBuf>,
+
+    /// The line and column of the first character of the source code
+    pub start: (u32, u32),
+
+    /// The line and column of the last character of the source code; if not given
+    /// then the source code is the character at `start`
+    pub end: Option<(u32, u32)>,
+}
+
 /// The different compilation results that the compiler can output
 #[derive(PartialEq)]
 pub enum FileType {
@@ -44,14 +101,18 @@ pub fn configure_cli() -> clap::App<'static, 'static> {
     let app = App::new("Bramble Compiler")
         .version("0.1.0")
         .author("Erich Ess")
-        .about("Compiles Bramble language files into x86 assembly for use by the NASM assembler")
+        .about("Compiles Bramble language files into object code with LLVM")
         .arg(
             Arg::with_name("input")
                 .short("i")
                 .long("input")
                 .takes_value(true)
-                .required(true)
-                .help("Source code file to compile"),
+                .multiple(true)
+                .number_of_values(1)
+                .required_unless("list-targets")
+                .help("Source code file or project directory to compile. Can be given more than once, \
+                every input is compiled into the same project, which is named after the first input. \
+                Use - to read the source code from stdin."),
         )
         .arg(
             Arg::with_name("import")
@@ -66,8 +127,16 @@ pub fn configure_cli() -> clap::App<'static, 'static> {
                 .short("o")
                 .long("output")
                 .takes_value(true)
-                .required(true)
-                .help("Name the output file that the assembly will be written to"),
+                .help("Name the output file that the object code will be written to. Defaults to \
+                the name of the input project in the output directory. Use - to write the object code \
+                to stdout.")
+        )
+        .arg(
+            Arg::with_name("out-dir")
+                .long("out-dir")
+                .takes_value(true)
+                .help("The directory that output files (object code, manifests, LLVM IR, traces) \
+                are written to. It is created if it does not exist. Defaults to ./target")
         )
         .arg(
             Arg::with_name("mir-beta")
@@ -84,10 +153,138 @@ pub fn configure_cli() -> clap::App<'static, 'static> {
             Arg::with_name("emit")
                 .long("emit")
                 .takes_value(true)
-                .possible_values(&["llvm-ir", "asm", "mir"])
-                .max_values(2)
+                .possible_values(&["tokens", "ast", "ast-json", "llvm-ir", "asm", "mir", "stats", "symbols", "complexity", "header", "grammar", "remarks"])
+                .max_values(12)
                 .help("When set, this will output different types of IR (LLVM, assembly, etc.)")
         )
+        .arg(
+            Arg::with_name("zero-init-locals")
+                .long("zero-init-locals")
+                .takes_value(false)
+                .help("Zero initialize every local variable and temporary value when it is allocated on the stack. \
+                This makes bugs caused by reading uninitialized memory reproducible.")
+        )
+        .arg(
+            Arg::with_name("bounds-checks")
+                .long("bounds-checks")
+                .takes_value(false)
+                .help("Check that every index into an array, slice, or range is less than its length, \
+                and that every range used to take part of one is within it, and trap if not.")
+        )
+        .arg(
+            Arg::with_name("debug-info")
+                .long("debug-info")
+                .short("g")
+                .takes_value(false)
+                .help("Include DWARF debug information in the generated code so that it can be stepped through \
+                in a debugger.")
+        )
+        .arg(
+            Arg::with_name("optimize")
+                .long("optimize")
+                .short("O")
+                .takes_value(false)
+                .help("Optimize the generated code: keep local variables in registers rather than on the \
+                stack, inline small functions, simplify and combine instructions, remove redundant code, \
+                and vectorize loops.")
+        )
+        .arg(
+            Arg::with_name("signal-handlers")
+                .long("signal-handlers")
+                .takes_value(false)
+                .help("Handle the signals raised by runtime errors (segmentation faults, division \
+                by zero, and failed runtime checks) by writing a description of the error and a \
+                stack trace before the program exits. Link with `-rdynamic` for the stack trace to \
+                name functions.")
+        )
+        .arg(
+            Arg::with_name("coverage")
+                .long("coverage")
+                .takes_value(false)
+                .requires("mir-beta")
+                .help("Count how many times each basic block is run. The counters are appended to \
+                <project>.counters when the program exits, and <project>.coverage.json maps them to the \
+                source code. Use bramble-cov to turn them into a report. Requires --mir-beta.")
+        )
+        .arg(
+            Arg::with_name("continue-on-ice")
+                .long("continue-on-ice")
+                .takes_value(false)
+                .help("If generating code for a function causes an internal compiler error, skip the function \
+                and continue with the next one, so that every function which triggers a compiler bug is reported.")
+        )
+        .arg(
+            Arg::with_name("verify-ir")
+                .long("verify-ir")
+                .takes_value(false)
+                .help("Check the compiler's internal invariants after each phase and run LLVM's module verifier \
+                over the generated LLVM IR. Any violation is a bug in the compiler.")
+        )
+        .arg(
+            Arg::with_name("lint-trailing-commas")
+                .long("lint-trailing-commas")
+                .takes_value(false)
+                .help("Warn about every list which ends with a trailing comma.")
+        )
+        .arg(
+            Arg::with_name("lint-mut-assignments")
+                .long("lint-mut-assignments")
+                .takes_value(false)
+                .help("Warn about every assignment which uses the old `mut <exp> := <value>` syntax \
+                rather than `<exp> = <value>`.")
+        )
+        .arg(
+            Arg::with_name("sanitize")
+                .long("sanitize")
+                .takes_value(true)
+                .possible_values(&["undefined"])
+                .multiple(true)
+                .use_delimiter(true)
+                .help("Instrument the generated code with the given sanitizers. `undefined` traps on \
+                integer division by zero.")
+        )
+        .arg(
+            Arg::with_name("target")
+                .long("target")
+                .takes_value(true)
+                .help("The target triple to generate code for, e.g. `i686-unknown-linux-gnu` or \
+                `wasm32-unknown-unknown` (default is the machine running the compiler). The width \
+                of `usize` and `isize` is the width of a pointer on the target.")
+        )
+        .arg(
+            Arg::with_name("target-cpu")
+                .long("target-cpu")
+                .takes_value(true)
+                .help("The CPU to generate code for (default generic). Use `native` to generate code \
+                for the CPU of the machine running the compiler.")
+        )
+        .arg(
+            Arg::with_name("target-feature")
+                .long("target-feature")
+                .takes_value(true)
+                .multiple(true)
+                .use_delimiter(true)
+                .validator(validate_target_feature)
+                .help("Enable (`+`) or disable (`-`) target features, e.g. `+avx2,+fma`. These are \
+                applied in addition to the features of the target CPU.")
+        )
+        .arg(
+            Arg::with_name("complexity-limit")
+                .long("complexity-limit")
+                .takes_value(true)
+                .multiple(true)
+                .use_delimiter(true)
+                .validator(|limit| parse_complexity_limit(&limit).map(|_| ()))
+                .help("Set the largest size of a routine before it is reported as a warning, e.g. \
+                `nodes=5000,depth=100,blocks=500,locals=200`. This is useful for tools which generate \
+                Bramble code.")
+        )
+        .arg(
+            Arg::with_name("list-targets")
+                .long("list-targets")
+                .takes_value(false)
+                .help("Print the targets which are supported by LLVM and exit")
+        )
         .arg(
             Arg::with_name("manifest")
                 .long("manifest")
@@ -100,9 +297,53 @@ pub fn configure_cli() -> clap::App<'static, 'static> {
                 .long("platform")
                 .possible_values(&["linux", "machos"])
                 .takes_value(true)
-                .required(true)
+                .required_unless("list-targets")
                 .help("The target Operation System that this will be compiled for: Linux or Mac (Mac is still unreliable and being worked on)"),
         )
+        .arg(
+            Arg::with_name("define")
+                .long("define")
+                .takes_value(true)
+                .multiple(true)
+                .use_delimiter(true)
+                .help("Define names which can be checked with `cfg(NAME)`. An `if` whose condition uses \
+                `cfg` is resolved at compile time and its dead arm is not compiled. The name of the \
+                platform is always defined. `NAME=VALUE` also gives the name a value, which \
+                `env(\"NAME\")` is replaced with instead of the environment variable NAME.")
+        )
+        .arg(
+            Arg::with_name("remap-path-prefix")
+                .long("remap-path-prefix")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .validator(|remap| parse_path_remap(&remap).map(|_| ()))
+                .help("Write source paths which start with FROM as starting with TO instead, given as \
+                `FROM=TO`, in every diagnostic and output file. This keeps paths which are specific to \
+                the machine, such as the directory the project was checked out to, out of the build.")
+        )
+        .arg(
+            Arg::with_name("error-limit")
+                .long("error-limit")
+                .takes_value(true)
+                .help("The maximum number of errors to print (default 20). Any further errors are \
+                summarized with a count of how many were suppressed. Set to 0 to print every error."),
+        )
+        .arg(
+            Arg::with_name("fuel")
+                .long("fuel")
+                .takes_value(true)
+                .help("The maximum number of units of work that the parser, type checker, and \
+                code generation may do. A unit is roughly one expression or statement. If the \
+                fuel runs out, compilation stops with a resource exhausted error."),
+        )
+        .arg(
+            Arg::with_name("timeout")
+                .long("timeout")
+                .takes_value(true)
+                .help("The maximum number of seconds that compilation may run for. If the time \
+                runs out, compilation stops with a resource exhausted error."),
+        )
         .arg(
             Arg::with_name("log")
                 .long("log")
@@ -116,12 +357,28 @@ pub fn configure_cli() -> clap::App<'static, 'static> {
                 .takes_value(false)
                 .help("Traces source code as it moves through the compiler")
         )
+        .arg(
+            Arg::with_name("timings")
+                .long("timings")
+                .takes_value(false)
+                .help("Prints how long each stage of the compiler took to stderr")
+        )
         .arg(
             Arg::with_name("json-trace")
                 .long("json-trace")
                 .takes_value(false)
                 .help("Writes a JSON file with the trace results to the target directory")
         )
+        .arg(
+            Arg::with_name("explain-types")
+                .long("explain-types")
+                .takes_value(true)
+                .validator(|target| parse_explain_target(&target).map(|_| ()))
+                .help("Print how the type of the expression at the given location was derived \
+                (how each literal was typed, which coercions were applied, and which operators and \
+                routines were used) and exit. The location is given as `[FILE:]LINE:COL[-LINE:COL]`; \
+                the smallest expression which covers it is explained.")
+        )
         .arg(
             Arg::with_name("stage")
             .long("stage")
@@ -132,6 +389,13 @@ pub fn configure_cli() -> clap::App<'static, 'static> {
     app
 }
 
+/// Returns every source file and directory that the configuration says to compile
+pub fn get_inputs<'a>(args: &'a ArgMatches) -> Vec<&'a Path> {
+    args.values_of("input")
+        .map(|inputs| inputs.map(Path::new).collect())
+        .unwrap_or_default()
+}
+
 pub fn get_imports<'a>(args: &'a ArgMatches) -> Vec<&'a str> {
     match args.value_of("import") {
         None => vec![],
@@ -163,6 +427,243 @@ pub fn enable_json_tracing<'a>(args: &'a ArgMatches) -> bool {
     args.is_present("json-trace")
 }
 
+/// Returns true if the configuration says to print how long each stage of the compiler took
+pub fn print_timings<'a>(args: &'a ArgMatches) -> bool {
+    args.is_present("timings")
+}
+
+/// Returns true if the configuration says to zero initialize all stack allocations
+pub fn zero_init_locals<'a>(args: &'a ArgMatches) -> bool {
+    args.is_present("zero-init-locals")
+}
+
+/// Returns true if the configuration says to check every index into an array or slice
+pub fn bounds_checks<'a>(args: &'a ArgMatches) -> bool {
+    args.is_present("bounds-checks")
+}
+
+/// Returns true if the configuration says to include debug information in the generated code.
+/// Printing optimization remarks requires debug information, because LLVM uses it to find
+/// the source code which each remark is about.
+pub fn debug_info<'a>(args: &'a ArgMatches) -> bool {
+    args.is_present("debug-info") || emit_remarks(args)
+}
+
+/// Returns true if the configuration says to optimize the generated code
+pub fn optimize<'a>(args: &'a ArgMatches) -> bool {
+    args.is_present("optimize")
+}
+
+/// Returns true if the configuration says to install handlers which report runtime errors
+pub fn signal_handlers<'a>(args: &'a ArgMatches) -> bool {
+    args.is_present("signal-handlers")
+}
+
+/// Returns true if the configuration says to instrument the generated code with coverage counters
+pub fn coverage<'a>(args: &'a ArgMatches) -> bool {
+    args.is_present("coverage")
+}
+
+/// Returns true if the configuration says to keep generating code after an internal compiler error
+pub fn continue_on_ice<'a>(args: &'a ArgMatches) -> bool {
+    args.is_present("continue-on-ice")
+}
+
+/// Returns true if the configuration says to check the compiler's invariants after each phase
+pub fn verify_ir<'a>(args: &'a ArgMatches) -> bool {
+    args.is_present("verify-ir")
+}
+
+/// Returns true if the configuration says to warn about trailing commas
+pub fn enable_trailing_comma_lint<'a>(args: &'a ArgMatches) -> bool {
+    args.is_present("lint-trailing-commas")
+}
+
+/// Returns true if the configuration says to warn about assignments with the old syntax
+pub fn enable_mut_assignment_lint<'a>(args: &'a ArgMatches) -> bool {
+    args.is_present("lint-mut-assignments")
+}
+
+/// Returns the names of every sanitizer that the configuration says to enable
+pub fn get_sanitizers<'a>(args: &'a ArgMatches) -> Vec<&'a str> {
+    match args.values_of("sanitize") {
+        None => vec![],
+        Some(values) => values.collect(),
+    }
+}
+
+/// Returns the directory that the configuration says to write output files to
+pub fn get_out_dir<'a>(args: &'a ArgMatches) -> &'a Path {
+    Path::new(args.value_of("out-dir").unwrap_or(DEFAULT_OUT_DIR))
+}
+
+/// Returns the target triple that the configuration says to generate code for
+pub fn get_target<'a>(args: &'a ArgMatches) -> Option<&'a str> {
+    args.value_of("target")
+}
+
+/// Returns the CPU that the configuration says to generate code for
+pub fn get_target_cpu<'a>(args: &'a ArgMatches) -> Option<&'a str> {
+    args.value_of("target-cpu")
+}
+
+/// Returns every target feature that the configuration says to enable or disable
+pub fn get_target_features<'a>(args: &'a ArgMatches) -> Vec<&'a str> {
+    match args.values_of("target-feature") {
+        None => vec![],
+        Some(values) => values.collect(),
+    }
+}
+
+/// Returns every name which the configuration says to define for `cfg(NAME)`, which
+/// includes the name of the target platform.  A name may be followed by `=VALUE`.
+pub fn get_defines<'a>(args: &'a ArgMatches) -> Vec<&'a str> {
+    let mut defines: Vec<_> = args.value_of("platform").into_iter().collect();
+    if let Some(values) = args.values_of("define") {
+        defines.extend(values)
+    }
+    defines
+}
+
+/// Returns true if the configuration says to list the supported targets
+pub fn list_targets<'a>(args: &'a ArgMatches) -> bool {
+    args.is_present("list-targets")
+}
+
+/// A target feature must name the feature and say whether it is enabled (`+`) or
+/// disabled (`-`).
+fn validate_target_feature(feature: String) -> Result<(), String> {
+    match feature.strip_prefix(|c| c == '+' || c == '-') {
+        Some(name) if !name.is_empty() => Ok(()),
+        _ => Err(format!(
+            "Invalid target feature {}: expected +feature or -feature",
+            feature
+        )),
+    }
+}
+
+/// Parses a complexity limit of the form `METRIC=LIMIT`
+fn parse_complexity_limit(limit: &str) -> Result<(Metric, usize), String> {
+    let (metric, value) = limit
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid complexity limit {}: expected METRIC=LIMIT", limit))?;
+    let value = value
+        .parse()
+        .map_err(|_| format!("Invalid complexity limit {}: expected a number", limit))?;
+    Ok((metric.parse()?, value))
+}
+
+/// Returns the limits on the size of routines, using the default for every limit
+/// which the configuration does not set
+pub fn get_complexity_limits<'a>(args: &'a ArgMatches) -> Result<ComplexityLimits, String> {
+    let mut limits = ComplexityLimits::default();
+    for limit in args.values_of("complexity-limit").into_iter().flatten() {
+        let (metric, value) = parse_complexity_limit(limit)?;
+        limits.set(metric, value);
+    }
+    Ok(limits)
+}
+
+/// Parses a path remapping of the form `FROM=TO`.  The paths are split at the last
+/// `=`, so that FROM may contain an `=`.
+fn parse_path_remap(remap: &str) -> Result<(PathBuf, PathBuf), String> {
+    match remap.rsplit_once('=') {
+        Some((from, to)) if !from.is_empty() => Ok((from.into(), to.into())),
+        _ => Err(format!(
+            "Invalid path remapping {}: expected FROM=TO",
+            remap
+        )),
+    }
+}
+
+/// Returns every source path prefix which the configuration says to remap, in the
+/// order that they were given
+pub fn get_path_remaps<'a>(args: &'a ArgMatches) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+    args.values_of("remap-path-prefix")
+        .into_iter()
+        .flatten()
+        .map(parse_path_remap)
+        .collect()
+}
+
+/// Returns the maximum number of errors that should be printed
+pub fn get_error_limit<'a>(args: &'a ArgMatches) -> Result<usize, String> {
+    match args.value_of("error-limit") {
+        None => Ok(DEFAULT_ERROR_LIMIT),
+        Some(limit) => limit
+            .parse()
+            .map_err(|_| format!("Invalid error limit: {}", limit)),
+    }
+}
+
+/// Returns the number of units of fuel that compilation may burn, if it is limited.
+pub fn get_fuel<'a>(args: &'a ArgMatches) -> Result<Option<u64>, String> {
+    match args.value_of("fuel") {
+        None => Ok(None),
+        Some(fuel) => fuel
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("Invalid fuel: {}", fuel)),
+    }
+}
+
+/// Returns how long compilation may run for, if it is limited.
+pub fn get_timeout<'a>(args: &'a ArgMatches) -> Result<Option<Duration>, String> {
+    match args.value_of("timeout") {
+        None => Ok(None),
+        Some(timeout) => timeout
+            .parse::<f64>()
+            .ok()
+            .filter(|secs| secs.is_finite() && *secs >= 0.)
+            .map(|secs| Some(Duration::from_secs_f64(secs)))
+            .ok_or_else(|| format!("Invalid timeout: {}", timeout)),
+    }
+}
+
+/// Parses a line and a column, which are both counted from 1
+fn parse_line_col(line: &str, col: &str) -> Option<(u32, u32)> {
+    match (line.parse(), col.parse()) {
+        (Ok(line), Ok(col)) if line > 0 && col > 0 => Some((line, col)),
+        _ => None,
+    }
+}
+
+/// Parses a location of the form `[FILE:]LINE:COL[-LINE:COL]`.  The location is split
+/// from the end, so that FILE may contain a `:` or a `-`.
+fn parse_explain_target(target: &str) -> Result<ExplainTarget, String> {
+    let invalid = || {
+        format!(
+            "Invalid location {}: expected [FILE:]LINE:COL[-LINE:COL]",
+            target
+        )
+    };
+
+    let end = target.rsplit_once('-').and_then(|(start, end)| {
+        let (line, col) = end.split_once(':')?;
+        parse_line_col(line, col).map(|end| (start, end))
+    });
+    let (start, end) = match end {
+        Some((start, end)) => (start, Some(end)),
+        None => (target, None),
+    };
+
+    let (rest, col) = start.rsplit_once(':').ok_or_else(invalid)?;
+    let (file, line) = match rest.rsplit_once(':') {
+        Some((file, line)) => (Some(PathBuf::from(file)), line),
+        None => (None, rest),
+    };
+    let start = parse_line_col(line, col).ok_or_else(invalid)?;
+
+    Ok(ExplainTarget { file, start, end })
+}
+
+/// Returns the source code whose type the configuration says to explain
+pub fn get_explain_target<'a>(args: &'a ArgMatches) -> Result<Option<ExplainTarget>, String> {
+    args.value_of("explain-types")
+        .map(parse_explain_target)
+        .transpose()
+}
+
 pub fn get_stage<'a>(args: &'a ArgMatches) -> Result<Option<Stage>, String> {
     if let Some(stage) = args.value_of("stage") {
         match stage {
@@ -176,6 +677,34 @@ pub fn get_stage<'a>(args: &'a ArgMatches) -> Result<Option<Stage>, String> {
     }
 }
 
+/// Returns true if the configuration says to emit the tokens produced by the lexer
+pub fn emit_tokens<'a>(args: &'a ArgMatches) -> bool {
+    if let Some(mut values) = args.values_of("emit") {
+        values.any(|v| v == "tokens")
+    } else {
+        false
+    }
+}
+
+/// Returns true if the configuration says to emit the AST produced by the parser
+pub fn emit_ast<'a>(args: &'a ArgMatches) -> bool {
+    if let Some(mut values) = args.values_of("emit") {
+        values.any(|v| v == "ast")
+    } else {
+        false
+    }
+}
+
+/// Returns true if the configuration says to emit the AST produced by the parser as
+/// JSON
+pub fn emit_ast_json<'a>(args: &'a ArgMatches) -> bool {
+    if let Some(mut values) = args.values_of("emit") {
+        values.any(|v| v == "ast-json")
+    } else {
+        false
+    }
+}
+
 /// Returns true if the configuration says to emit LLVM IR
 pub fn emit_llvm_ir<'a>(args: &'a ArgMatches) -> bool {
     if let Some(mut values) = args.values_of("emit") {
@@ -194,6 +723,61 @@ pub fn emit_asm<'a>(args: &'a ArgMatches) -> bool {
     }
 }
 
+/// Returns true if the configuration says to emit statistics about the compilation
+pub fn emit_stats<'a>(args: &'a ArgMatches) -> bool {
+    if let Some(mut values) = args.values_of("emit") {
+        values.any(|v| v == "stats")
+    } else {
+        false
+    }
+}
+
+/// Returns true if the configuration says to emit the list of items in the project
+pub fn emit_symbols<'a>(args: &'a ArgMatches) -> bool {
+    if let Some(mut values) = args.values_of("emit") {
+        values.any(|v| v == "symbols")
+    } else {
+        false
+    }
+}
+
+/// Returns true if the configuration says to emit the size of every routine
+pub fn emit_complexity<'a>(args: &'a ArgMatches) -> bool {
+    if let Some(mut values) = args.values_of("emit") {
+        values.any(|v| v == "complexity")
+    } else {
+        false
+    }
+}
+
+/// Returns true if the configuration says to emit a C header which lists the global
+/// data of the program
+pub fn emit_header<'a>(args: &'a ArgMatches) -> bool {
+    if let Some(mut values) = args.values_of("emit") {
+        values.any(|v| v == "header")
+    } else {
+        false
+    }
+}
+
+/// Returns true if the configuration says to emit the grammar of the language as EBNF
+pub fn emit_grammar<'a>(args: &'a ArgMatches) -> bool {
+    if let Some(mut values) = args.values_of("emit") {
+        values.any(|v| v == "grammar")
+    } else {
+        false
+    }
+}
+
+/// Returns true if the configuration says to print LLVM's optimization remarks
+pub fn emit_remarks<'a>(args: &'a ArgMatches) -> bool {
+    if let Some(mut values) = args.values_of("emit") {
+        values.any(|v| v == "remarks")
+    } else {
+        false
+    }
+}
+
 /// Returns true if the configuration says to emit mir
 pub fn emit_mir<'a>(args: &'a ArgMatches) -> bool {
     if let Some(mut values) = args.values_of("emit") {
@@ -207,7 +791,7 @@ pub fn configure_logging(level: LevelFilter) -> Result<(), log::SetLoggerError>
     CombinedLogger::init(vec![TermLogger::new(
         level,
         Config::default(),
-        TerminalMode::Mixed,
+        TerminalMode::Stderr,
         ColorChoice::Auto,
     )])
 }
@@ -0,0 +1,632 @@
+//! An arena based representation of expression trees.
+//!
+//! Rather than allocating every child node in its own [`Box`], an [`Arena`] stores
+//! all of the expressions and statements of a tree in flat vectors, and nodes refer
+//! to their children by [`NodeId`] or [`StmtId`].  This keeps a tree in a small
+//! number of contiguous allocations and lets a pass visit every node by walking a
+//! `Vec` rather than chasing pointers.
+//!
+//! The arena is not yet used by every compiler pass; the complexity measurements of
+//! [`semantics::complexity`](crate::compiler::semantics::complexity) are the first to
+//! be ported.  To allow passes to be ported one at a time,
+//! [`Arena::insert_expression`] copies an existing boxed [`Expression`] tree into the
+//! arena and [`Arena::to_expression`] rebuilds the boxed tree from the arena, so a
+//! ported pass can sit between two unported passes.
+
+use crate::StringId;
+
+use super::{
+    expression::{BinaryOperator, Expression, RoutineCall, UnaryOperator},
+    node::{Context, Node},
+    path::Path,
+    statement::{Bind, Break, Continue, Defer, Discard, Mutate, Return, Statement, YieldReturn},
+    ty::Type,
+};
+
+/// Reference to an expression which is stored in an [`Arena`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Reference to a statement which is stored in an [`Arena`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StmtId(u32);
+
+impl StmtId {
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// An expression node within an [`Arena`].  This mirrors [`Expression`] except
+/// that child expressions and statements are referenced by ID.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArenaExpression<M> {
+    /// An expression which has no child expressions (literals, identifiers, paths,
+    /// etc.).  These are stored as is.
+    Leaf(Expression<M>),
+    ArrayExpression(M, Vec<NodeId>, usize),
+    ArrayAt {
+        context: M,
+        array: NodeId,
+        index: NodeId,
+    },
+    MemberAccess(M, NodeId, StringId),
+    RoutineCall(M, RoutineCall, Box<Path>, Vec<NodeId>),
+    StructExpression(M, Box<Path>, Vec<(StringId, NodeId)>),
+    If {
+        context: M,
+        cond: NodeId,
+        if_arm: NodeId,
+        else_arm: Option<NodeId>,
+    },
+    While {
+        context: M,
+        label: Option<StringId>,
+        cond: NodeId,
+        body: NodeId,
+    },
+    ExpressionBlock(M, Vec<StmtId>, Option<NodeId>),
+    BinaryOp(M, BinaryOperator, NodeId, NodeId),
+    TypeCast(M, NodeId, Box<Type>),
+    UnaryOp(M, UnaryOperator, NodeId),
+    Yield(M, NodeId),
+}
+
+impl<M: Context> ArenaExpression<M> {
+    pub fn context(&self) -> &M {
+        use ArenaExpression::*;
+        match self {
+            Leaf(e) => e.context(),
+            ArrayExpression(m, ..)
+            | MemberAccess(m, ..)
+            | RoutineCall(m, ..)
+            | StructExpression(m, ..)
+            | ExpressionBlock(m, ..)
+            | BinaryOp(m, ..)
+            | TypeCast(m, ..)
+            | UnaryOp(m, ..)
+            | Yield(m, ..) => m,
+            ArrayAt { context, .. } | If { context, .. } | While { context, .. } => context,
+        }
+    }
+
+    pub fn get_context_mut(&mut self) -> &mut M {
+        use ArenaExpression::*;
+        match self {
+            Leaf(e) => e.get_context_mut(),
+            ArrayExpression(m, ..)
+            | MemberAccess(m, ..)
+            | RoutineCall(m, ..)
+            | StructExpression(m, ..)
+            | ExpressionBlock(m, ..)
+            | BinaryOp(m, ..)
+            | TypeCast(m, ..)
+            | UnaryOp(m, ..)
+            | Yield(m, ..) => m,
+            ArrayAt { context, .. } | If { context, .. } | While { context, .. } => context,
+        }
+    }
+}
+
+/// A statement node within an [`Arena`].  This mirrors [`Statement`] except that
+/// the expressions which a statement contains are referenced by ID.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArenaStatement<M> {
+    Bind {
+        context: M,
+        id: StringId,
+        ty: Type,
+        mutable: bool,
+        rhs: NodeId,
+    },
+    Mutate {
+        context: M,
+        lhs: NodeId,
+        rhs: NodeId,
+    },
+    Discard {
+        context: M,
+        value: NodeId,
+    },
+    Defer {
+        context: M,
+        value: NodeId,
+    },
+    Break {
+        context: M,
+        label: Option<StringId>,
+    },
+    Continue {
+        context: M,
+        label: Option<StringId>,
+    },
+    YieldReturn {
+        context: M,
+        value: Option<NodeId>,
+    },
+    Return {
+        context: M,
+        value: Option<NodeId>,
+    },
+    Expression(NodeId),
+}
+
+/// Stores the expressions and statements of one or more trees.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arena<M> {
+    expressions: Vec<ArenaExpression<M>>,
+    statements: Vec<ArenaStatement<M>>,
+}
+
+impl<M> Default for Arena<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> Arena<M> {
+    pub fn new() -> Arena<M> {
+        Arena {
+            expressions: vec![],
+            statements: vec![],
+        }
+    }
+
+    /// Returns the number of expressions stored in the arena.
+    pub fn len(&self) -> usize {
+        self.expressions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.expressions.is_empty()
+    }
+
+    /// Adds an expression node to the arena.  The children of the node must
+    /// already have been added.
+    pub fn alloc(&mut self, exp: ArenaExpression<M>) -> NodeId {
+        let id = NodeId(self.expressions.len() as u32);
+        self.expressions.push(exp);
+        id
+    }
+
+    /// Adds a statement node to the arena.  The expressions of the statement
+    /// must already have been added.
+    pub fn alloc_statement(&mut self, stm: ArenaStatement<M>) -> StmtId {
+        let id = StmtId(self.statements.len() as u32);
+        self.statements.push(stm);
+        id
+    }
+
+    pub fn get(&self, id: NodeId) -> &ArenaExpression<M> {
+        &self.expressions[id.index()]
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> &mut ArenaExpression<M> {
+        &mut self.expressions[id.index()]
+    }
+
+    pub fn get_statement(&self, id: StmtId) -> &ArenaStatement<M> {
+        &self.statements[id.index()]
+    }
+
+    pub fn get_statement_mut(&mut self, id: StmtId) -> &mut ArenaStatement<M> {
+        &mut self.statements[id.index()]
+    }
+
+    /// Iterates over every expression in the arena in the order in which they
+    /// were added.  Because children are added before their parents, this is a
+    /// post order traversal of each tree.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &ArenaExpression<M>)> {
+        self.expressions
+            .iter()
+            .enumerate()
+            .map(|(idx, e)| (NodeId(idx as u32), e))
+    }
+
+    /// Iterates over every statement in the arena in the order in which they were
+    /// added.
+    pub fn iter_statements(&self) -> impl Iterator<Item = (StmtId, &ArenaStatement<M>)> {
+        self.statements
+            .iter()
+            .enumerate()
+            .map(|(idx, s)| (StmtId(idx as u32), s))
+    }
+
+    /// Returns the IDs of the expressions which are direct children of the given
+    /// expression.  The expressions of any statements within an expression block
+    /// are included.
+    pub fn children(&self, id: NodeId) -> Vec<NodeId> {
+        use ArenaExpression::*;
+        match self.get(id) {
+            Leaf(_) => vec![],
+            ArrayExpression(_, elements, _) => elements.clone(),
+            ArrayAt { array, index, .. } => vec![*array, *index],
+            MemberAccess(_, e, _) | TypeCast(_, e, _) | UnaryOp(_, _, e) | Yield(_, e) => vec![*e],
+            RoutineCall(_, _, _, params) => params.clone(),
+            StructExpression(_, _, fields) => fields.iter().map(|(_, e)| *e).collect(),
+            If {
+                cond,
+                if_arm,
+                else_arm,
+                ..
+            } => {
+                let mut children = vec![*cond, *if_arm];
+                children.extend(else_arm);
+                children
+            }
+            While { cond, body, .. } => vec![*cond, *body],
+            ExpressionBlock(_, body, final_exp) => {
+                let mut children: Vec<_> = body
+                    .iter()
+                    .flat_map(|stm| self.statement_children(*stm))
+                    .collect();
+                children.extend(final_exp);
+                children
+            }
+            BinaryOp(_, _, l, r) => vec![*l, *r],
+        }
+    }
+
+    /// Returns the IDs of the expressions which are contained by the given statement.
+    pub fn statement_children(&self, id: StmtId) -> Vec<NodeId> {
+        use ArenaStatement::*;
+        match self.get_statement(id) {
+            Bind { rhs, .. } => vec![*rhs],
+            Mutate { lhs, rhs, .. } => vec![*lhs, *rhs],
+            Discard { value, .. } | Defer { value, .. } => vec![*value],
+            Break { .. } | Continue { .. } => vec![],
+            YieldReturn { value, .. } | Return { value, .. } => value.iter().copied().collect(),
+            Expression(e) => vec![*e],
+        }
+    }
+}
+
+impl<M: Context + Clone> Arena<M> {
+    /// Copies a boxed [`Expression`] tree into the arena and returns the ID of
+    /// its root.
+    pub fn insert_expression(&mut self, exp: &Expression<M>) -> NodeId {
+        use Expression::*;
+        let node = match exp {
+            ArrayExpression(m, elements, len) => ArenaExpression::ArrayExpression(
+                m.clone(),
+                elements.iter().map(|e| self.insert_expression(e)).collect(),
+                *len,
+            ),
+            ArrayAt {
+                context,
+                array,
+                index,
+            } => ArenaExpression::ArrayAt {
+                context: context.clone(),
+                array: self.insert_expression(array),
+                index: self.insert_expression(index),
+            },
+            MemberAccess(m, src, member) => {
+                ArenaExpression::MemberAccess(m.clone(), self.insert_expression(src), *member)
+            }
+            RoutineCall(m, call, path, params) => ArenaExpression::RoutineCall(
+                m.clone(),
+                *call,
+                path.clone(),
+                params.iter().map(|p| self.insert_expression(p)).collect(),
+            ),
+            StructExpression(m, path, fields) => ArenaExpression::StructExpression(
+                m.clone(),
+                path.clone(),
+                fields
+                    .iter()
+                    .map(|(f, e)| (*f, self.insert_expression(e)))
+                    .collect(),
+            ),
+            If {
+                context,
+                cond,
+                if_arm,
+                else_arm,
+            } => ArenaExpression::If {
+                context: context.clone(),
+                cond: self.insert_expression(cond),
+                if_arm: self.insert_expression(if_arm),
+                else_arm: else_arm.as_ref().map(|e| self.insert_expression(e)),
+            },
+            While {
+                context,
+                label,
+                cond,
+                body,
+            } => ArenaExpression::While {
+                context: context.clone(),
+                label: *label,
+                cond: self.insert_expression(cond),
+                body: self.insert_expression(body),
+            },
+            ExpressionBlock(m, body, final_exp) => ArenaExpression::ExpressionBlock(
+                m.clone(),
+                body.iter().map(|s| self.insert_statement(s)).collect(),
+                final_exp.as_ref().map(|e| self.insert_expression(e)),
+            ),
+            BinaryOp(m, op, l, r) => {
+                let l = self.insert_expression(l);
+                let r = self.insert_expression(r);
+                ArenaExpression::BinaryOp(m.clone(), *op, l, r)
+            }
+            TypeCast(m, src, target) => {
+                ArenaExpression::TypeCast(m.clone(), self.insert_expression(src), target.clone())
+            }
+            UnaryOp(m, op, operand) => {
+                ArenaExpression::UnaryOp(m.clone(), *op, self.insert_expression(operand))
+            }
+            Yield(m, e) => ArenaExpression::Yield(m.clone(), self.insert_expression(e)),
+            leaf => ArenaExpression::Leaf(leaf.clone()),
+        };
+        self.alloc(node)
+    }
+
+    /// Copies a boxed [`Statement`] into the arena and returns its ID.
+    pub fn insert_statement(&mut self, stm: &Statement<M>) -> StmtId {
+        let node = match stm {
+            Statement::Bind(b) => ArenaStatement::Bind {
+                context: b.context().clone(),
+                id: b.get_id(),
+                ty: b.get_type().clone(),
+                mutable: b.is_mutable(),
+                rhs: self.insert_expression(b.get_rhs()),
+            },
+            Statement::Mutate(m) => ArenaStatement::Mutate {
+                context: m.context().clone(),
+                lhs: self.insert_expression(m.get_lhs()),
+                rhs: self.insert_expression(m.get_rhs()),
+            },
+            Statement::Discard(d) => ArenaStatement::Discard {
+                context: d.context().clone(),
+                value: self.insert_expression(d.get_value()),
+            },
+            Statement::Defer(d) => ArenaStatement::Defer {
+                context: d.context().clone(),
+                value: self.insert_expression(d.get_value()),
+            },
+            Statement::Break(b) => ArenaStatement::Break {
+                context: b.context().clone(),
+                label: b.get_label(),
+            },
+            Statement::Continue(c) => ArenaStatement::Continue {
+                context: c.context().clone(),
+                label: c.get_label(),
+            },
+            Statement::YieldReturn(yr) => ArenaStatement::YieldReturn {
+                context: yr.context().clone(),
+                value: yr.get_value().as_ref().map(|v| self.insert_expression(v)),
+            },
+            Statement::Return(r) => ArenaStatement::Return {
+                context: r.context().clone(),
+                value: r.get_value().as_ref().map(|v| self.insert_expression(v)),
+            },
+            Statement::Expression(e) => ArenaStatement::Expression(self.insert_expression(e)),
+        };
+        self.alloc_statement(node)
+    }
+
+    /// Rebuilds the boxed [`Expression`] tree which is rooted at the given node.
+    pub fn to_expression(&self, id: NodeId) -> Expression<M> {
+        let boxed = |id: &NodeId| Box::new(self.to_expression(*id));
+        match self.get(id) {
+            ArenaExpression::Leaf(e) => e.clone(),
+            ArenaExpression::ArrayExpression(m, elements, len) => Expression::ArrayExpression(
+                m.clone(),
+                elements.iter().map(|e| self.to_expression(*e)).collect(),
+                *len,
+            ),
+            ArenaExpression::ArrayAt {
+                context,
+                array,
+                index,
+            } => Expression::ArrayAt {
+                context: context.clone(),
+                array: boxed(array),
+                index: boxed(index),
+            },
+            ArenaExpression::MemberAccess(m, src, member) => {
+                Expression::MemberAccess(m.clone(), boxed(src), *member)
+            }
+            ArenaExpression::RoutineCall(m, call, path, params) => Expression::RoutineCall(
+                m.clone(),
+                *call,
+                path.clone(),
+                params.iter().map(|p| self.to_expression(*p)).collect(),
+            ),
+            ArenaExpression::StructExpression(m, path, fields) => Expression::StructExpression(
+                m.clone(),
+                path.clone(),
+                fields
+                    .iter()
+                    .map(|(f, e)| (*f, self.to_expression(*e)))
+                    .collect(),
+            ),
+            ArenaExpression::If {
+                context,
+                cond,
+                if_arm,
+                else_arm,
+            } => Expression::If {
+                context: context.clone(),
+                cond: boxed(cond),
+                if_arm: boxed(if_arm),
+                else_arm: else_arm.as_ref().map(boxed),
+            },
+            ArenaExpression::While {
+                context,
+                label,
+                cond,
+                body,
+            } => Expression::While {
+                context: context.clone(),
+                label: *label,
+                cond: boxed(cond),
+                body: boxed(body),
+            },
+            ArenaExpression::ExpressionBlock(m, body, final_exp) => Expression::ExpressionBlock(
+                m.clone(),
+                body.iter().map(|s| self.to_statement(*s)).collect(),
+                final_exp.as_ref().map(boxed),
+            ),
+            ArenaExpression::BinaryOp(m, op, l, r) => {
+                Expression::BinaryOp(m.clone(), *op, boxed(l), boxed(r))
+            }
+            ArenaExpression::TypeCast(m, src, target) => {
+                Expression::TypeCast(m.clone(), boxed(src), target.clone())
+            }
+            ArenaExpression::UnaryOp(m, op, operand) => {
+                Expression::UnaryOp(m.clone(), *op, boxed(operand))
+            }
+            ArenaExpression::Yield(m, e) => Expression::Yield(m.clone(), boxed(e)),
+        }
+    }
+
+    /// Rebuilds the boxed [`Statement`] which has the given ID.
+    pub fn to_statement(&self, id: StmtId) -> Statement<M> {
+        match self.get_statement(id) {
+            ArenaStatement::Bind {
+                context,
+                id,
+                ty,
+                mutable,
+                rhs,
+            } => Statement::Bind(Box::new(Bind::new(
+                context.clone(),
+                *id,
+                ty.clone(),
+                *mutable,
+                self.to_expression(*rhs),
+            ))),
+            ArenaStatement::Mutate { context, lhs, rhs } => {
+                Statement::Mutate(Box::new(Mutate::new(
+                    context.clone(),
+                    self.to_expression(*lhs),
+                    self.to_expression(*rhs),
+                )))
+            }
+            ArenaStatement::Discard { context, value } => Statement::Discard(Box::new(
+                Discard::new(context.clone(), self.to_expression(*value)),
+            )),
+            ArenaStatement::Defer { context, value } => Statement::Defer(Box::new(Defer::new(
+                context.clone(),
+                self.to_expression(*value),
+            ))),
+            ArenaStatement::Break { context, label } => {
+                Statement::Break(Box::new(Break::new(context.clone(), *label)))
+            }
+            ArenaStatement::Continue { context, label } => {
+                Statement::Continue(Box::new(Continue::new(context.clone(), *label)))
+            }
+            ArenaStatement::YieldReturn { context, value } => Statement::YieldReturn(Box::new(
+                YieldReturn::new(context.clone(), value.map(|v| self.to_expression(v))),
+            )),
+            ArenaStatement::Return { context, value } => Statement::Return(Box::new(Return::new(
+                context.clone(),
+                value.map(|v| self.to_expression(v)),
+            ))),
+            ArenaStatement::Expression(e) => {
+                Statement::Expression(Box::new(self.to_expression(*e)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_arena {
+    use super::*;
+    use crate::{compiler::ast::Element, StringTable};
+
+    fn sample(table: &StringTable) -> Expression<i64> {
+        let x = table.insert("x".into());
+        let f = table.insert("f".into());
+
+        Expression::ExpressionBlock(
+            1,
+            vec![
+                Statement::Bind(Box::new(Bind::new(
+                    2,
+                    x,
+                    Type::I64,
+                    true,
+                    Expression::BinaryOp(
+                        3,
+                        BinaryOperator::Add,
+                        Box::new(Expression::I64(4, 1)),
+                        Box::new(Expression::I64(5, 2)),
+                    ),
+                ))),
+                Statement::Return(Box::new(Return::new(6, None))),
+            ],
+            Some(Box::new(Expression::If {
+                context: 7,
+                cond: Box::new(Expression::Boolean(8, true)),
+                if_arm: Box::new(Expression::RoutineCall(
+                    9,
+                    RoutineCall::Function,
+                    Box::new(vec![Element::Id(f)].into()),
+                    vec![Expression::Identifier(10, x)],
+                )),
+                else_arm: Some(Box::new(Expression::I64(11, 0))),
+            })),
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        let table = StringTable::new();
+        let exp = sample(&table);
+
+        let mut arena = Arena::new();
+        let root = arena.insert_expression(&exp);
+        assert_eq!(arena.len(), 9);
+        assert_eq!(arena.to_expression(root), exp);
+    }
+
+    #[test]
+    fn children_are_allocated_before_parents() {
+        let table = StringTable::new();
+        let exp = sample(&table);
+
+        let mut arena = Arena::new();
+        let root = arena.insert_expression(&exp);
+
+        let contexts: Vec<i64> = arena.iter().map(|(_, e)| *e.context()).collect();
+        assert_eq!(contexts, vec![4, 5, 3, 8, 10, 9, 11, 7, 1]);
+
+        let children: Vec<i64> = arena
+            .children(root)
+            .iter()
+            .map(|c| *arena.get(*c).context())
+            .collect();
+        assert_eq!(children, vec![3, 7]);
+    }
+
+    #[test]
+    fn mutate_in_place() {
+        let table = StringTable::new();
+        let exp = sample(&table);
+
+        let mut arena = Arena::new();
+        let root = arena.insert_expression(&exp);
+        let ids: Vec<_> = arena.iter().map(|(id, _)| id).collect();
+        for id in ids {
+            *arena.get_mut(id).get_context_mut() *= 10;
+        }
+
+        let rebuilt = arena.to_expression(root);
+        assert_eq!(*rebuilt.context(), 10);
+        if let Expression::ExpressionBlock(_, body, _) = rebuilt {
+            // Statement contexts are not expressions and are left unchanged
+            assert_eq!(*body[0].context(), 2);
+        } else {
+            panic!("Expected an expression block")
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{
+    compiler::{source::SourceIr, Span},
+    StringId,
+};
+use serde::{Deserialize, Serialize};
+
+/// An attribute which annotates the item that follows it and asks the compiler to do
+/// something for that item.  For example, `#[derive(print, eq)]` before a structure
+/// generates routines which print and compare values of the structure.  The name of
+/// the attribute is `derive` and its arguments are `print` and `eq`.
+///
+/// An attribute can instead be given a value, e.g. `#[link_name = "SDL_Init"]`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Attribute {
+    span: Span,
+    name: StringId,
+    args: Vec<StringId>,
+    value: Option<StringId>,
+}
+
+impl SourceIr for Attribute {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl Attribute {
+    pub fn new(span: Span, name: StringId, args: Vec<StringId>) -> Attribute {
+        Attribute {
+            span,
+            name,
+            args,
+            value: None,
+        }
+    }
+
+    /// Sets the string literal which is assigned to this attribute with `=`
+    pub fn with_value(self, value: StringId) -> Attribute {
+        Attribute {
+            value: Some(value),
+            ..self
+        }
+    }
+
+    pub fn get_name(&self) -> StringId {
+        self.name
+    }
+
+    /// Returns the identifiers listed in the parentheses after the name
+    pub fn get_args(&self) -> &[StringId] {
+        &self.args
+    }
+
+    /// Returns the string literal after the `=`, if the attribute was given a value
+    pub fn get_value(&self) -> Option<StringId> {
+        self.value
+    }
+}
+
+impl std::fmt::Display for Attribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.value {
+            Some(value) => f.write_fmt(format_args!("#[{} = \"{}\"]", self.name, value)),
+            None => {
+                let args: Vec<_> = self.args.iter().map(|a| a.to_string()).collect();
+                f.write_fmt(format_args!("#[{}({})]", self.name, args.join(", ")))
+            }
+        }
+    }
+}
@@ -0,0 +1,492 @@
+//! A small DSL for constructing ASTs in unit tests and in the passes which generate
+//! code (e.g. `#[derive]`).
+//!
+//! Building an AST by hand requires interning every name, boxing every sub-expression,
+//! and giving every node a context.  The [`Builder`] handles all of that so that a
+//! test can describe the AST it needs in roughly the same shape as the source code
+//! it represents:
+//!
+//! ```ignore
+//! let b = Builder::new(&table, || ParserContext::new(Span::zero()));
+//! let add = b
+//!     .fn_def("add")
+//!     .param("x", Type::I64)
+//!     .param("y", Type::I64)
+//!     .ret_ty(Type::I64)
+//!     .body(vec![b.ret(Some(b.bin(BinaryOperator::Add, b.var("x"), b.var("y"))))])
+//!     .build();
+//! ```
+
+use crate::{StringId, StringTable};
+
+use super::{
+    BinaryOperator, Bind, Context, Element, Expression, Extern, Module, Mutate, Parameter, Path,
+    Return, RoutineCall, RoutineDef, Statement, StructDef, Type, UnaryOperator, YieldReturn,
+};
+
+/// Constructs AST nodes whose names are interned in a [`StringTable`] and whose
+/// contexts are created by a given function.
+pub struct Builder<'a, M> {
+    table: &'a StringTable,
+    new_ctx: Box<dyn Fn() -> M + 'a>,
+}
+
+impl<'a, M> Builder<'a, M> {
+    /// Creates a new builder which will intern names in `table` and call `new_ctx` to
+    /// create the context of every node.
+    pub fn new<F: Fn() -> M + 'a>(table: &'a StringTable, new_ctx: F) -> Builder<'a, M> {
+        Builder {
+            table,
+            new_ctx: Box::new(new_ctx),
+        }
+    }
+
+    fn id(&self, name: &str) -> StringId {
+        self.table.insert(name.into())
+    }
+
+    fn ctx(&self) -> M {
+        (self.new_ctx)()
+    }
+
+    /// Creates a path from its elements.  If the first element is `project` then the
+    /// path is canonical.
+    pub fn path(&self, elements: &[&str]) -> Path {
+        elements
+            .iter()
+            .enumerate()
+            .map(|(idx, el)| match *el {
+                super::CANONICAL_ROOT if idx == 0 => Element::CanonicalRoot,
+                super::ROOT_PATH if idx == 0 => Element::FileRoot,
+                super::SELF if idx == 0 => Element::Selph,
+                super::SUPER => Element::Super,
+                name => Element::Id(self.id(name)),
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Start building a function
+    pub fn fn_def(&self, name: &str) -> RoutineBuilder<'_, 'a, M> {
+        RoutineBuilder {
+            builder: self,
+            routine: RoutineDef::new_function(
+                self.id(name),
+                self.ctx(),
+                vec![],
+                Type::Unit,
+                vec![],
+            ),
+        }
+    }
+
+    /// Start building a coroutine
+    pub fn co_def(&self, name: &str) -> RoutineBuilder<'_, 'a, M> {
+        RoutineBuilder {
+            builder: self,
+            routine: RoutineDef::new_coroutine(
+                self.id(name),
+                self.ctx(),
+                vec![],
+                Type::Unit,
+                vec![],
+            ),
+        }
+    }
+
+    /// Creates a structure definition with the given fields
+    pub fn struct_def(&self, name: &str, fields: &[(&str, Type)]) -> StructDef<M> {
+        StructDef::new(self.id(name), self.ctx(), self.params(fields))
+    }
+
+    /// Creates an extern function declaration
+    pub fn extern_def(
+        &self,
+        name: &str,
+        params: &[(&str, Type)],
+        has_varargs: bool,
+        ret_ty: Type,
+    ) -> Extern<M> {
+        Extern::new(
+            self.id(name),
+            self.ctx(),
+            self.params(params),
+            has_varargs,
+            ret_ty,
+        )
+    }
+
+    fn params(&self, params: &[(&str, Type)]) -> Vec<Parameter<M>> {
+        params
+            .iter()
+            .map(|(name, ty)| Parameter::new(self.ctx(), self.id(name), ty))
+            .collect()
+    }
+
+    /// `let name: ty := rhs;`
+    pub fn let_(&self, name: &str, ty: Type, rhs: Expression<M>) -> Statement<M> {
+        Statement::Bind(Box::new(Bind::new(
+            self.ctx(),
+            self.id(name),
+            ty,
+            false,
+            rhs,
+        )))
+    }
+
+    /// `let mut name: ty := rhs;`
+    pub fn let_mut(&self, name: &str, ty: Type, rhs: Expression<M>) -> Statement<M> {
+        Statement::Bind(Box::new(Bind::new(
+            self.ctx(),
+            self.id(name),
+            ty,
+            true,
+            rhs,
+        )))
+    }
+
+    /// `mut lhs := rhs;`
+    pub fn mutate(&self, lhs: Expression<M>, rhs: Expression<M>) -> Statement<M> {
+        Statement::Mutate(Box::new(Mutate::new(self.ctx(), lhs, rhs)))
+    }
+
+    /// `return value;`
+    pub fn ret(&self, value: Option<Expression<M>>) -> Statement<M> {
+        Statement::Return(Box::new(Return::new(self.ctx(), value)))
+    }
+
+    /// `yret value;`
+    pub fn yret(&self, value: Option<Expression<M>>) -> Statement<M> {
+        Statement::YieldReturn(Box::new(YieldReturn::new(self.ctx(), value)))
+    }
+
+    /// An expression used as a statement: `value;`
+    pub fn expr(&self, value: Expression<M>) -> Statement<M> {
+        Statement::Expression(Box::new(value))
+    }
+
+    pub fn i8(&self, v: i8) -> Expression<M> {
+        Expression::I8(self.ctx(), v)
+    }
+
+    pub fn i16(&self, v: i16) -> Expression<M> {
+        Expression::I16(self.ctx(), v)
+    }
+
+    pub fn i32(&self, v: i32) -> Expression<M> {
+        Expression::I32(self.ctx(), v)
+    }
+
+    pub fn i64(&self, v: i64) -> Expression<M> {
+        Expression::I64(self.ctx(), v)
+    }
+
+    pub fn u8(&self, v: u8) -> Expression<M> {
+        Expression::U8(self.ctx(), v)
+    }
+
+    pub fn u16(&self, v: u16) -> Expression<M> {
+        Expression::U16(self.ctx(), v)
+    }
+
+    pub fn u32(&self, v: u32) -> Expression<M> {
+        Expression::U32(self.ctx(), v)
+    }
+
+    pub fn u64(&self, v: u64) -> Expression<M> {
+        Expression::U64(self.ctx(), v)
+    }
+
+    pub fn f64(&self, v: f64) -> Expression<M> {
+        Expression::F64(self.ctx(), v)
+    }
+
+    pub fn bool(&self, v: bool) -> Expression<M> {
+        Expression::Boolean(self.ctx(), v)
+    }
+
+    pub fn string(&self, s: &str) -> Expression<M> {
+        Expression::StringLiteral(self.ctx(), self.id(s))
+    }
+
+    pub fn null(&self) -> Expression<M> {
+        Expression::Null(self.ctx())
+    }
+
+    pub fn none(&self) -> Expression<M> {
+        Expression::OptionNone(self.ctx())
+    }
+
+    /// A reference to a local variable
+    pub fn var(&self, name: &str) -> Expression<M> {
+        Expression::Identifier(self.ctx(), self.id(name))
+    }
+
+    /// `base.member`
+    pub fn member(&self, base: Expression<M>, member: &str) -> Expression<M> {
+        Expression::MemberAccess(self.ctx(), Box::new(base), self.id(member))
+    }
+
+    /// `array[index]`
+    pub fn index(&self, array: Expression<M>, index: Expression<M>) -> Expression<M> {
+        Expression::ArrayAt {
+            context: self.ctx(),
+            array: Box::new(array),
+            index: Box::new(index),
+        }
+    }
+
+    /// `path(args, ...)`
+    pub fn call(&self, path: &[&str], args: Vec<Expression<M>>) -> Expression<M> {
+        Expression::RoutineCall(
+            self.ctx(),
+            RoutineCall::Function,
+            Box::new(self.path(path)),
+            args,
+        )
+    }
+
+    /// `init path(args, ...)`
+    pub fn init(&self, path: &[&str], args: Vec<Expression<M>>) -> Expression<M> {
+        Expression::RoutineCall(
+            self.ctx(),
+            RoutineCall::CoroutineInit,
+            Box::new(self.path(path)),
+            args,
+        )
+    }
+
+    /// `path{field: value, ...}`
+    pub fn struct_expr(&self, path: &[&str], fields: Vec<(&str, Expression<M>)>) -> Expression<M> {
+        Expression::StructExpression(
+            self.ctx(),
+            Box::new(self.path(path)),
+            fields
+                .into_iter()
+                .map(|(name, value)| (self.id(name), value))
+                .collect(),
+        )
+    }
+
+    pub fn bin(&self, op: BinaryOperator, l: Expression<M>, r: Expression<M>) -> Expression<M> {
+        Expression::BinaryOp(self.ctx(), op, Box::new(l), Box::new(r))
+    }
+
+    pub fn unary(&self, op: UnaryOperator, operand: Expression<M>) -> Expression<M> {
+        Expression::UnaryOp(self.ctx(), op, Box::new(operand))
+    }
+
+    /// `{stmts; ... final}`
+    pub fn block(
+        &self,
+        stmts: Vec<Statement<M>>,
+        final_exp: Option<Expression<M>>,
+    ) -> Expression<M> {
+        Expression::ExpressionBlock(self.ctx(), stmts, final_exp.map(Box::new))
+    }
+
+    /// `if (cond) if_arm else else_arm`
+    pub fn if_(
+        &self,
+        cond: Expression<M>,
+        if_arm: Expression<M>,
+        else_arm: Option<Expression<M>>,
+    ) -> Expression<M> {
+        Expression::If {
+            context: self.ctx(),
+            cond: Box::new(cond),
+            if_arm: Box::new(if_arm),
+            else_arm: else_arm.map(Box::new),
+        }
+    }
+
+    /// `while (cond) body`
+    pub fn while_(&self, cond: Expression<M>, body: Expression<M>) -> Expression<M> {
+        Expression::While {
+            context: self.ctx(),
+            label: None,
+            cond: Box::new(cond),
+            body: Box::new(body),
+        }
+    }
+
+    /// `yield value`
+    pub fn yield_(&self, value: Expression<M>) -> Expression<M> {
+        Expression::Yield(self.ctx(), Box::new(value))
+    }
+
+    /// `value as ty`
+    pub fn cast(&self, value: Expression<M>, ty: Type) -> Expression<M> {
+        Expression::TypeCast(self.ctx(), Box::new(value), Box::new(ty))
+    }
+}
+
+impl<'a, M: Context> Builder<'a, M> {
+    /// Start building a module
+    pub fn module(&self, name: &str) -> ModuleBuilder<'_, 'a, M> {
+        ModuleBuilder {
+            builder: self,
+            module: Module::new(self.id(name), self.ctx()),
+        }
+    }
+}
+
+/// Builds a [`Module`]
+pub struct ModuleBuilder<'b, 'a, M> {
+    builder: &'b Builder<'a, M>,
+    module: Module<M>,
+}
+
+impl<'b, 'a, M: Context> ModuleBuilder<'b, 'a, M> {
+    pub fn module(mut self, m: Module<M>) -> Self {
+        self.module.add_module(m);
+        self
+    }
+
+    pub fn routine(mut self, r: RoutineDef<M>) -> Self {
+        let result = if r.def == super::RoutineDefType::Coroutine {
+            self.module.add_coroutine(r)
+        } else {
+            self.module.add_function(r)
+        };
+        result.expect("Routine is already defined in the module");
+        self
+    }
+
+    pub fn structdef(mut self, s: StructDef<M>) -> Self {
+        self.module
+            .add_struct(s)
+            .expect("Structure is already defined in the module");
+        self
+    }
+
+    pub fn extern_def(mut self, e: Extern<M>) -> Self {
+        self.module
+            .add_extern(e)
+            .expect("Extern is already defined in the module");
+        self
+    }
+
+    /// Adds a submodule which is built by the given function
+    pub fn submodule<F>(self, name: &str, f: F) -> Self
+    where
+        F: FnOnce(ModuleBuilder<'b, 'a, M>) -> ModuleBuilder<'b, 'a, M>,
+    {
+        let sub = f(self.builder.module(name)).build();
+        self.module(sub)
+    }
+
+    pub fn build(self) -> Module<M> {
+        self.module
+    }
+}
+
+/// Builds a [`RoutineDef`]
+pub struct RoutineBuilder<'b, 'a, M> {
+    builder: &'b Builder<'a, M>,
+    routine: RoutineDef<M>,
+}
+
+impl<'b, 'a, M> RoutineBuilder<'b, 'a, M> {
+    pub fn param(mut self, name: &str, ty: Type) -> Self {
+        let b = self.builder;
+        self.routine
+            .params
+            .push(Parameter::new(b.ctx(), b.id(name), &ty));
+        self
+    }
+
+    pub fn ret_ty(mut self, ty: Type) -> Self {
+        self.routine.ret_ty = ty;
+        self
+    }
+
+    pub fn body(mut self, body: Vec<Statement<M>>) -> Self {
+        self.routine.body = body;
+        self
+    }
+
+    pub fn build(self) -> RoutineDef<M> {
+        self.routine
+    }
+}
+
+#[cfg(test)]
+mod test_build {
+    use super::*;
+
+    #[test]
+    fn test_fn_def() {
+        let table = StringTable::new();
+        let b = Builder::new(&table, || 0i32);
+
+        let f = b
+            .fn_def("add")
+            .param("x", Type::I64)
+            .param("y", Type::I64)
+            .ret_ty(Type::I64)
+            .body(vec![b.ret(Some(b.bin(
+                BinaryOperator::Add,
+                b.var("x"),
+                b.var("y"),
+            )))])
+            .build();
+
+        let x = table.find("x").unwrap();
+        let y = table.find("y").unwrap();
+        let expected = RoutineDef::new_function(
+            table.find("add").unwrap(),
+            0,
+            vec![
+                Parameter::new(0, x, &Type::I64),
+                Parameter::new(0, y, &Type::I64),
+            ],
+            Type::I64,
+            vec![Statement::Return(Box::new(Return::new(
+                0,
+                Some(Expression::BinaryOp(
+                    0,
+                    BinaryOperator::Add,
+                    Box::new(Expression::Identifier(0, x)),
+                    Box::new(Expression::Identifier(0, y)),
+                )),
+            )))],
+        );
+        assert_eq!(f, expected);
+    }
+
+    #[test]
+    fn test_module() {
+        let table = StringTable::new();
+        let b = Builder::new(&table, || 0i32);
+
+        let m = b
+            .module("m")
+            .routine(b.fn_def("f").build())
+            .routine(b.co_def("c").build())
+            .structdef(b.struct_def("S", &[("a", Type::Bool)]))
+            .submodule("inner", |m| m.routine(b.fn_def("g").build()))
+            .build();
+
+        assert_eq!(m.get_functions().len(), 1);
+        assert_eq!(m.get_coroutines().len(), 1);
+        assert_eq!(m.get_structs().len(), 1);
+
+        let inner = m.get_module(table.find("inner").unwrap()).unwrap();
+        assert!(inner.get_item(table.find("g").unwrap()).is_some());
+    }
+
+    #[test]
+    fn test_path() {
+        let table = StringTable::new();
+        let b = Builder::new(&table, || 0i32);
+
+        let canonical = b.path(&["project", "m", "f"]);
+        assert!(canonical.is_canonical());
+        assert_eq!(canonical.len(), 2);
+
+        let relative = b.path(&["super", "f"]);
+        assert!(!relative.is_canonical());
+        assert_eq!(relative[0], Element::Super);
+    }
+}
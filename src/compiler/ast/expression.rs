@@ -1,7 +1,8 @@
 use crate::{
-    compiler::{source::SourceIr, Span},
+    compiler::{lexer::tokens::Token, source::SourceIr, Span},
     StringId,
 };
+use serde::{Deserialize, Serialize};
 
 use super::{
     node::{
@@ -12,9 +13,12 @@ use super::{
     ty::Type,
 };
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Expression<I> {
     Null(I),
+    /// The empty option, `none`.  Its type is the option type which is expected where
+    /// it is used.
+    OptionNone(I),
     U8(I, u8),
     U16(I, u16),
     U32(I, u32),
@@ -33,13 +37,21 @@ pub enum Expression<I> {
         index: Box<Expression<I>>,
     },
     SizeOf(I, Box<Type>),
+
+    /// The name and type of every field of a structure, as an array of `[name, type]`
+    /// pairs.  This is replaced with an array expression by type resolution.
+    FieldsOf(I, Box<Type>),
+
+    /// The name of a type, as a string.  This is replaced with a string literal after
+    /// type resolution.
+    NameOf(I, Box<Type>),
     CustomType(I, Path),
     Identifier(I, StringId),
     Path(I, Path),
     MemberAccess(I, Box<Expression<I>>, StringId),
-    IdentifierDeclare(I, StringId, Type),
-    RoutineCall(I, RoutineCall, Path, Vec<Expression<I>>),
-    StructExpression(I, Path, Vec<(StringId, Expression<I>)>),
+    IdentifierDeclare(I, StringId, Box<Type>),
+    RoutineCall(I, RoutineCall, Box<Path>, Vec<Expression<I>>),
+    StructExpression(I, Box<Path>, Vec<(StringId, Expression<I>)>),
     If {
         context: I,
         cond: Box<Expression<I>>,
@@ -48,16 +60,23 @@ pub enum Expression<I> {
     },
     While {
         context: I,
+        /// The label which `break` and `continue` can use to target this loop
+        label: Option<StringId>,
         cond: Box<Expression<I>>,
         body: Box<Expression<I>>,
     },
     ExpressionBlock(I, Vec<Statement<I>>, Option<Box<Expression<I>>>),
 
     BinaryOp(I, BinaryOperator, Box<Expression<I>>, Box<Expression<I>>),
-    TypeCast(I, Box<Expression<I>>, Type),
+    TypeCast(I, Box<Expression<I>>, Box<Type>),
     UnaryOp(I, UnaryOperator, Box<Expression<I>>),
 
     Yield(I, Box<Expression<I>>),
+
+    /// A call to a macro, `name!(tokens)`, with the tokens between its parentheses.  This
+    /// is replaced with the expression which the macro expands to before types are
+    /// resolved.
+    MacroCall(I, StringId, Vec<Token>),
 }
 
 impl<M: Context> SourceIr for Expression<M> {
@@ -71,6 +90,7 @@ impl<M: Context> Node<M> for Expression<M> {
         use Expression::*;
         match self {
             Null(m)
+            | OptionNone(m)
             | U8(m, ..)
             | U16(m, ..)
             | U32(m, ..)
@@ -83,6 +103,8 @@ impl<M: Context> Node<M> for Expression<M> {
             | Boolean(m, ..)
             | StringLiteral(m, ..)
             | SizeOf(m, ..)
+            | FieldsOf(m, ..)
+            | NameOf(m, ..)
             | CustomType(m, ..)
             | Identifier(m, ..)
             | IdentifierDeclare(m, ..)
@@ -95,6 +117,7 @@ impl<M: Context> Node<M> for Expression<M> {
             | ExpressionBlock(m, ..)
             | Yield(m, ..)
             | TypeCast(m, ..)
+            | MacroCall(m, ..)
             | RoutineCall(m, ..) => m,
             StructExpression(m, ..) => m,
             ArrayExpression(m, _, _) => m,
@@ -106,6 +129,7 @@ impl<M: Context> Node<M> for Expression<M> {
         use Expression::*;
         match self {
             Null(m)
+            | OptionNone(m)
             | U8(m, ..)
             | U16(m, ..)
             | U32(m, ..)
@@ -118,6 +142,8 @@ impl<M: Context> Node<M> for Expression<M> {
             | Boolean(m, ..)
             | StringLiteral(m, ..)
             | SizeOf(m, ..)
+            | FieldsOf(m, ..)
+            | NameOf(m, ..)
             | CustomType(m, ..)
             | Identifier(m, ..)
             | IdentifierDeclare(m, ..)
@@ -130,6 +156,7 @@ impl<M: Context> Node<M> for Expression<M> {
             | ExpressionBlock(m, ..)
             | Yield(m, ..)
             | TypeCast(m, ..)
+            | MacroCall(m, ..)
             | RoutineCall(m, ..) => m,
             StructExpression(m, ..) => m,
             ArrayExpression(m, _, _) => m,
@@ -194,6 +221,7 @@ impl<M: Context> Node<M> for Expression<M> {
                 o
             }
             Null(..)
+            | OptionNone(..)
             | U8(..)
             | U16(..)
             | U32(..)
@@ -207,9 +235,12 @@ impl<M: Context> Node<M> for Expression<M> {
             | StringLiteral(..)
             | ArrayExpression(_, _, _)
             | SizeOf(..)
+            | FieldsOf(..)
+            | NameOf(..)
             | CustomType(..)
             | Identifier(..)
             | IdentifierDeclare(..)
+            | MacroCall(..)
             | Path(..) => vec![],
             TypeCast(_, e, _) => vec![e.as_ref()],
         }
@@ -239,6 +270,7 @@ impl<I> Expression<I> {
         use Expression::*;
         match self {
             Null(_) => "null".into(),
+            OptionNone(_) => "none".into(),
             U8(_, v) => format!("{}u8", v),
             U16(_, v) => format!("{}u16", v),
             U32(_, v) => format!("{}u32", v),
@@ -259,6 +291,8 @@ impl<I> Expression<I> {
             ),
             ArrayAt { array, index, .. } => format!("{}[{}]", array, index),
             SizeOf(_, ty) => format!("size_of({})", ty),
+            FieldsOf(_, ty) => format!("fields_of({})", ty),
+            NameOf(_, ty) => format!("name_of({})", ty),
             CustomType(_, v) => format!("{}", v),
             Identifier(_, v) => format!("{}", v),
             IdentifierDeclare(_, v, p) => format!("{}:{}", v, p),
@@ -273,11 +307,12 @@ impl<I> Expression<I> {
             ExpressionBlock(..) => "expression block".into(),
             Yield(_, _) => "yield".into(),
             TypeCast(_, _e, _ty) => "type cast".into(),
+            MacroCall(_, name, _) => format!("{}!()", name),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum BinaryOperator {
     Add,
     Sub,
@@ -292,6 +327,13 @@ pub enum BinaryOperator {
     Gr,
     GrEq,
     RawPointerOffset,
+    WrappingAdd,
+    WrappingSub,
+    WrappingMul,
+    SaturatingAdd,
+    SaturatingSub,
+    /// Creates a range from its start and its end (`a..b`)
+    Range,
 }
 
 impl std::fmt::Display for BinaryOperator {
@@ -311,17 +353,58 @@ impl std::fmt::Display for BinaryOperator {
             Gr => f.write_str(">"),
             GrEq => f.write_str(">="),
             RawPointerOffset => f.write_str("@"),
+            WrappingAdd => f.write_str("wrapping_add"),
+            WrappingSub => f.write_str("wrapping_sub"),
+            WrappingMul => f.write_str("wrapping_mul"),
+            SaturatingAdd => f.write_str("saturating_add"),
+            SaturatingSub => f.write_str("saturating_sub"),
+            Range => f.write_str(".."),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum UnaryOperator {
     Negate,
     Not,
     AddressConst,
     AddressMut,
     DerefRawPointer,
+    Length,
+    /// Wraps a value in an option (`some(x)`)
+    OptionSome,
+    /// Tests whether an option holds a value.  This has no syntax; it is only made by
+    /// the parser when desugaring `if (let some(x) := o)`.
+    OptionIsSome,
+    /// Takes the value out of an option which is known to hold one.  Like
+    /// [`UnaryOperator::OptionIsSome`], this is only made by the parser.
+    OptionUnwrap,
+    /// Wraps a value in the success case of a result (`ok(x)`)
+    ResultOk,
+    /// Wraps a value in the error case of a result (`err(e)`)
+    ResultErr,
+    /// Tests whether a result holds a success value.  This has no syntax; it is only made
+    /// by the parser when desugaring `if (let ok(x) := r)` and `if (let err(e) := r)`.
+    ResultIsOk,
+    /// Takes the success value out of a result which is known to hold one.  This is only
+    /// made by the parser.
+    ResultUnwrapOk,
+    /// Takes the error value out of a result which is known to hold one.  This is only
+    /// made by the parser.
+    ResultUnwrapErr,
+    /// Propagates an empty option or an error result out of the current function, and
+    /// otherwise evaluates to the value which it holds (`e?`)
+    Try,
+    /// The bytes of an integer, least significant first, as a `[u8; N]` (`to_le_bytes(x)`)
+    ToLeBytes,
+    /// The bytes of an integer, most significant first, as a `[u8; N]` (`to_be_bytes(x)`)
+    ToBeBytes,
+    /// The unsigned integer whose bytes, least significant first, are the elements of a
+    /// `[u8; N]` (`from_le_bytes(b)`)
+    FromLeBytes,
+    /// The unsigned integer whose bytes, most significant first, are the elements of a
+    /// `[u8; N]` (`from_be_bytes(b)`)
+    FromBeBytes,
 }
 
 impl std::fmt::Display for UnaryOperator {
@@ -333,15 +416,53 @@ impl std::fmt::Display for UnaryOperator {
             AddressConst => f.write_str("@const "),
             AddressMut => f.write_str("@mut "),
             DerefRawPointer => f.write_str("^"),
+            Length => f.write_str("len"),
+            OptionSome => f.write_str("some"),
+            OptionIsSome => f.write_str("is_some"),
+            OptionUnwrap => f.write_str("unwrap"),
+            ResultOk => f.write_str("ok"),
+            ResultErr => f.write_str("err"),
+            ResultIsOk => f.write_str("is_ok"),
+            ResultUnwrapOk => f.write_str("unwrap_ok"),
+            ResultUnwrapErr => f.write_str("unwrap_err"),
+            Try => f.write_str("?"),
+            ToLeBytes => f.write_str("to_le_bytes"),
+            ToBeBytes => f.write_str("to_be_bytes"),
+            FromLeBytes => f.write_str("from_le_bytes"),
+            FromBeBytes => f.write_str("from_be_bytes"),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum RoutineCall {
     Function,
     CoroutineInit,
     Extern,
+
+    /// A call to a method of a structure: `x.m(...)`. The path is just the name of the
+    /// method and the first parameter is the value the method is called on. Semantic
+    /// analysis resolves the method and turns this into a [`RoutineCall::Function`].
+    Method,
+
+    /// A step of the iterator protocol, which a `for` loop uses to iterate over a sequence.
+    /// The path is empty and the only parameter is the sequence.  Semantic analysis turns
+    /// this into the builtin operation for ranges, slices, and arrays, and into a call to
+    /// the method named by the step for structures.
+    Iterator(IteratorStep),
+}
+
+/// The steps of the iterator protocol, which are written as methods on a sequence
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum IteratorStep {
+    /// `is_empty(self) -> bool`: true if there are no more elements in the sequence
+    IsEmpty,
+
+    /// `first(self) -> T`: the next element of the sequence
+    First,
+
+    /// `rest(self) -> S`: the sequence without its first element
+    Rest,
 }
 
 impl std::fmt::Display for RoutineCall {
@@ -351,6 +472,20 @@ impl std::fmt::Display for RoutineCall {
             CoroutineInit => f.write_str("init"),
             Function => f.write_str("call"),
             Extern => f.write_str("call extern"),
+            Method => f.write_str("call method"),
+            Iterator(_) => f.write_str("call iterator"),
         }
     }
 }
+
+#[cfg(test)]
+mod test_expression {
+    use super::*;
+
+    #[test]
+    fn test_payload_size() {
+        // Expressions are cloned and rebuilt throughout semantic analysis, so large
+        // payloads (e.g. a Path or Type) should be boxed rather than stored inline.
+        assert!(std::mem::size_of::<Expression<()>>() <= 40);
+    }
+}
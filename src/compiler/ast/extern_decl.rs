@@ -1,6 +1,8 @@
 use crate::{compiler::Span, StringId};
+use serde::{Deserialize, Serialize};
 
 use super::{
+    attribute::Attribute,
     node::{
         Context, Node, NodeType, {PostOrderIter, PreOrderIter},
     },
@@ -10,13 +12,19 @@ use super::{
 
 pub type HasVarArgs = bool;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Extern<M> {
     pub context: M,
     pub name: StringId,
     pub params: Vec<Parameter<M>>,
     pub has_varargs: HasVarArgs,
     pub ty: Type,
+
+    /// The attributes written before the extern (e.g. `#[link_name = "SDL_Init"]`)
+    pub(super) attributes: Vec<Attribute>,
+
+    /// The symbol given by a `#[link_name]` attribute, if the extern has one
+    pub(super) link_name: Option<StringId>,
 }
 
 impl<M: Context> crate::compiler::source::SourceIr for Extern<M> {
@@ -75,6 +83,8 @@ impl<M> Extern<M> {
             params,
             has_varargs,
             ty,
+            attributes: vec![],
+            link_name: None,
         }
     }
 
@@ -90,6 +100,26 @@ impl<M> Extern<M> {
         &self.ty
     }
 
+    pub fn get_attributes(&self) -> &Vec<Attribute> {
+        &self.attributes
+    }
+
+    pub fn get_attributes_mut(&mut self) -> &mut Vec<Attribute> {
+        &mut self.attributes
+    }
+
+    /// The name of the symbol which this extern is linked to.  An extern can be declared
+    /// in any module, but its symbol is the name exported by the library which defines
+    /// it rather than a label derived from its canonical path.  The symbol is the name
+    /// of the extern, unless a different one is given with `#[link_name = "..."]`.
+    pub fn link_name(&self) -> StringId {
+        self.link_name.unwrap_or(self.name)
+    }
+
+    pub fn set_link_name(&mut self, name: StringId) {
+        self.link_name = Some(name)
+    }
+
     pub fn root_str(&self) -> String {
         format!("extern fn {}", self.name)
     }
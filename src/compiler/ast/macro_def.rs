@@ -0,0 +1,84 @@
+use crate::{
+    compiler::{
+        lexer::tokens::{Lex, Token},
+        source::SourceIr,
+        Span,
+    },
+    StringId,
+};
+use serde::{Deserialize, Serialize};
+
+/// A macro definition, which is a list of rules that each rewrite the tokens passed to
+/// the macro into an expression:
+///
+/// ```text
+/// macro square {
+///     ($x:expr) => { $x * $x };
+/// }
+/// ```
+///
+/// Calling the macro, `square!(y + 1)`, uses the first rule whose pattern matches the
+/// arguments and replaces the call with the body of that rule.  The body is expanded
+/// into a block, so it can bind variables before its final expression.  Macros are
+/// expanded after parsing and before types are resolved.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MacroDef {
+    span: Span,
+    name: StringId,
+    rules: Vec<MacroRule>,
+}
+
+impl SourceIr for MacroDef {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl MacroDef {
+    pub fn new(span: Span, name: StringId, rules: Vec<MacroRule>) -> MacroDef {
+        MacroDef { span, name, rules }
+    }
+
+    pub fn get_name(&self) -> StringId {
+        self.name
+    }
+
+    pub fn get_rules(&self) -> &[MacroRule] {
+        &self.rules
+    }
+}
+
+/// A single rule of a macro: `(pattern) => { body }`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MacroRule {
+    pattern: Vec<MacroPattern>,
+    body: Vec<Token>,
+}
+
+impl MacroRule {
+    pub fn new(pattern: Vec<MacroPattern>, body: Vec<Token>) -> MacroRule {
+        MacroRule { pattern, body }
+    }
+
+    pub fn get_pattern(&self) -> &[MacroPattern] {
+        &self.pattern
+    }
+
+    /// Returns the tokens which the call is replaced with.  A `$name` in the body is
+    /// replaced with the tokens which the fragment `name` matched.
+    pub fn get_body(&self) -> &[Token] {
+        &self.body
+    }
+}
+
+/// An element of the pattern of a macro rule.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MacroPattern {
+    /// A token which must be matched exactly
+    Token(Lex),
+
+    /// `$name:kind`, which matches a fragment of the given kind.  The kind is either
+    /// `expr`, which matches every token up to the next token of the pattern or the
+    /// next comma, or `ident`, which matches a single identifier.
+    Fragment(StringId, StringId),
+}
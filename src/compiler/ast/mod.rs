@@ -1,24 +1,41 @@
+mod arena;
+mod attribute;
+pub mod build;
 mod expression;
 mod extern_decl;
+mod macro_def;
 mod module;
 mod node;
 mod parameter;
 mod path;
+mod pathtable;
+mod print;
 mod routinedef;
 mod statement;
+mod static_assert;
 mod structdef;
 mod ty;
+mod use_decl;
 
-pub use self::expression::{BinaryOperator, Expression, RoutineCall, UnaryOperator};
+pub use self::arena::{Arena, ArenaExpression, ArenaStatement, NodeId, StmtId};
+pub use self::attribute::Attribute;
+pub use self::expression::{BinaryOperator, Expression, IteratorStep, RoutineCall, UnaryOperator};
 pub use self::extern_decl::{Extern, HasVarArgs};
+pub use self::macro_def::{MacroDef, MacroPattern, MacroRule};
 pub use self::module::{Item, Module};
 pub use self::node::{Context, MapPreOrder, Node, NodeType, PostOrderIter, PreOrderIter};
 pub use self::parameter::Parameter;
 pub use self::path::{Element, Path, CANONICAL_ROOT, ROOT_PATH, SELF, SUPER};
+pub use self::pathtable::{PathId, PathMap, PathTable};
+pub use self::print::print_ast;
 pub use self::routinedef::{RoutineDef, RoutineDefType};
-pub use self::statement::{Bind, Mutate, Return, Statement, YieldReturn};
+pub use self::statement::{
+    Bind, Break, Continue, Defer, Discard, Mutate, Return, Statement, YieldReturn,
+};
+pub use self::static_assert::StaticAssert;
 pub use self::structdef::StructDef;
 pub use self::ty::*;
+pub use self::use_decl::Use;
 
 use super::{CompilerDisplay, CompilerDisplayError, SourceMap};
 use crate::StringId;
@@ -29,6 +46,8 @@ pub const MAIN_MODULE: &str = "main";
 #[derive(Clone, Debug, PartialEq)]
 pub enum AstError {
     ModuleAlreadyContains(StringId),
+    ImplStructNotFound(StringId),
+    StructAlreadyContains(StringId, StringId),
 }
 
 impl CompilerDisplay for AstError {
@@ -38,6 +57,18 @@ impl CompilerDisplay for AstError {
                 let s = st.get(*sid)?;
                 Ok(format!("{} already exists in module", s))
             }
+            AstError::ImplStructNotFound(sid) => {
+                let s = st.get(*sid)?;
+                Ok(format!(
+                    "impl for {} must be in the same module as the structure",
+                    s
+                ))
+            }
+            AstError::StructAlreadyContains(sd, sid) => {
+                let sd = st.get(*sd)?;
+                let s = st.get(*sid)?;
+                Ok(format!("{} already exists in structure {}", s, sd))
+            }
         }
     }
 }
@@ -45,17 +76,17 @@ impl CompilerDisplay for AstError {
 /// Errors that can be generated when using or creating Path values.
 #[derive(Clone, Debug, PartialEq)]
 pub enum PathCanonizationError {
-    /// A relative path contains enough [Element::Super] elements that it would go post the
-    /// root of the absolute path.
+    /// A path contains enough [Element::Super] elements that it would go past the
+    /// root module.
     SubceedingRoot,
 }
 
 impl CompilerDisplay for PathCanonizationError {
     fn fmt(&self, _: &SourceMap, _st: &crate::StringTable) -> Result<String, CompilerDisplayError> {
         match self {
-            PathCanonizationError::SubceedingRoot => Ok(format!(
-                "Use of super would exceed the current depth of the path"
-            )),
+            PathCanonizationError::SubceedingRoot => {
+                Ok("super cannot refer to the parent of the root module".into())
+            }
         }
     }
 }
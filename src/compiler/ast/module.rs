@@ -1,19 +1,23 @@
 use super::{
     extern_decl::Extern,
+    macro_def::MacroDef,
     node::{
         Context, Node, NodeType, {PostOrderIter, PreOrderIter},
     },
     path::{Element, Path},
     routinedef::{RoutineDef, RoutineDefType},
+    static_assert::StaticAssert,
     structdef::StructDef,
+    use_decl::Use,
     AstError,
 };
 use crate::compiler::{source::SourceIr, CompilerError, Span};
 use crate::StringId;
+use serde::{Deserialize, Serialize};
 
 type AstResult<T> = Result<T, CompilerError<AstError>>;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Module<M> {
     context: M,
     name: StringId,
@@ -22,6 +26,16 @@ pub struct Module<M> {
     coroutines: Vec<Item<M>>,
     structs: Vec<Item<M>>,
     externs: Vec<Item<M>>,
+
+    /// The `use` declarations in this module
+    uses: Vec<Use>,
+
+    /// The macros defined in this module, which can be called from this module and
+    /// its submodules
+    macros: Vec<MacroDef>,
+
+    /// The static assertions in this module
+    static_asserts: Vec<StaticAssert<M>>,
 }
 
 impl<M: Context> SourceIr for Module<M> {
@@ -95,6 +109,9 @@ where
             coroutines: Vec::new(),
             structs: Vec::new(),
             externs: Vec::new(),
+            uses: Vec::new(),
+            macros: Vec::new(),
+            static_asserts: Vec::new(),
         }
     }
 
@@ -103,42 +120,99 @@ where
     }
 
     pub fn add_function(&mut self, f: RoutineDef<M>) -> AstResult<()> {
-        let fname = f.get_name();
-        if self.get_item(fname).is_none() {
-            self.functions.push(Item::Routine(f));
-            Ok(())
-        } else {
-            err!(f.span(), AstError::ModuleAlreadyContains(fname))
-        }
+        self.check_name_is_free(f.get_name(), f.span())?;
+        self.functions.push(Item::Routine(f));
+        Ok(())
     }
 
     pub fn add_coroutine(&mut self, c: RoutineDef<M>) -> AstResult<()> {
-        let cname = c.get_name();
-        if self.get_item(cname).is_none() {
-            self.coroutines.push(Item::Routine(c));
-            Ok(())
-        } else {
-            err!(c.span(), AstError::ModuleAlreadyContains(cname))
-        }
+        self.check_name_is_free(c.get_name(), c.span())?;
+        self.coroutines.push(Item::Routine(c));
+        Ok(())
     }
 
     pub fn add_struct(&mut self, s: StructDef<M>) -> AstResult<()> {
-        let name = s.get_name();
-        if self.get_item(name).is_none() {
-            self.structs.push(Item::Struct(s));
-            Ok(())
-        } else {
-            err!(s.span(), AstError::ModuleAlreadyContains(name))
-        }
+        self.check_name_is_free(s.get_name(), s.span())?;
+        self.structs.push(Item::Struct(s));
+        Ok(())
     }
 
     pub fn add_extern(&mut self, e: Extern<M>) -> AstResult<()> {
-        let name = e.get_name();
-        if self.get_item(name).is_none() {
-            self.externs.push(Item::Extern(e));
-            Ok(())
-        } else {
-            err!(e.span(), AstError::ModuleAlreadyContains(name))
+        self.check_name_is_free(e.get_name(), e.span())?;
+        self.externs.push(Item::Extern(e));
+        Ok(())
+    }
+
+    /// Functions, coroutines, structures, and externs share a single namespace within a
+    /// module. Returns an error, with a note pointing to the existing definition, if an
+    /// item named `name` has already been added to this module.
+    fn check_name_is_free(&self, name: StringId, span: Span) -> AstResult<()> {
+        match self.get_item(name) {
+            Some(existing) => Err(
+                CompilerError::new(span, AstError::ModuleAlreadyContains(name)).with_note(
+                    existing.span(),
+                    &format!("previous {} definition here", existing.node_type()),
+                ),
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Adds a method to the structure named `struct_name`.  The methods of a structure
+    /// must be defined in the same module as the structure and share a namespace with
+    /// its fields.
+    pub fn add_method(&mut self, struct_name: StringId, method: RoutineDef<M>) -> AstResult<()> {
+        let sd = self
+            .structs
+            .iter_mut()
+            .find_map(|s| match s {
+                Item::Struct(sd) if sd.get_name() == struct_name => Some(sd),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                CompilerError::new(method.span(), AstError::ImplStructNotFound(struct_name))
+            })?;
+
+        let name = method.get_name();
+        let existing = sd
+            .get_fields()
+            .iter()
+            .find(|f| f.name == name)
+            .map(|f| (f.span(), "field"))
+            .or_else(|| sd.get_method(name).map(|m| (m.span(), "method")));
+        match existing {
+            Some((span, kind)) => Err(CompilerError::new(
+                method.span(),
+                AstError::StructAlreadyContains(struct_name, name),
+            )
+            .with_note(span, &format!("previous {} definition here", kind))),
+            None => {
+                sd.get_methods_mut().push(method);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn add_use(&mut self, u: Use) {
+        self.uses.push(u);
+    }
+
+    pub fn add_static_assert(&mut self, sa: StaticAssert<M>) {
+        self.static_asserts.push(sa);
+    }
+
+    /// Macros have their own namespace, because they can only be called with `name!()`
+    pub fn add_macro(&mut self, m: MacroDef) -> AstResult<()> {
+        match self.get_macro(m.get_name()) {
+            Some(existing) => Err(CompilerError::new(
+                m.span(),
+                AstError::ModuleAlreadyContains(m.get_name()),
+            )
+            .with_note(existing.span(), "previous macro definition here")),
+            None => {
+                self.macros.push(m);
+                Ok(())
+            }
         }
     }
 
@@ -194,6 +268,21 @@ where
         funcs
     }
 
+    /// Gets the methods of every structure in this module
+    pub fn get_methods(&self) -> impl Iterator<Item = &RoutineDef<M>> {
+        self.structs.iter().flat_map(|s| match s {
+            Item::Struct(sd) => sd.get_methods().iter(),
+            _ => panic!("Non StructDef returned by get_structs"),
+        })
+    }
+
+    pub fn get_methods_mut(&mut self) -> impl Iterator<Item = &mut RoutineDef<M>> {
+        self.structs.iter_mut().flat_map(|s| match s {
+            Item::Struct(sd) => sd.get_methods_mut().iter_mut(),
+            _ => panic!("Non StructDef returned by get_structs"),
+        })
+    }
+
     pub fn get_coroutines(&self) -> &Vec<Item<M>> {
         &self.coroutines
     }
@@ -239,6 +328,22 @@ where
         &mut self.externs
     }
 
+    pub fn get_uses(&self) -> &Vec<Use> {
+        &self.uses
+    }
+
+    pub fn get_macros(&self) -> &Vec<MacroDef> {
+        &self.macros
+    }
+
+    pub fn get_static_asserts(&self) -> &Vec<StaticAssert<M>> {
+        &self.static_asserts
+    }
+
+    pub fn get_macro(&self, name: StringId) -> Option<&MacroDef> {
+        self.macros.iter().find(|m| m.get_name() == name)
+    }
+
     pub fn get_module(&self, name: StringId) -> Option<&Module<M>> {
         self.modules.iter().find(|m| m.name == name)
     }
@@ -291,7 +396,7 @@ where
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Item<M> {
     Routine(RoutineDef<M>),
     Struct(StructDef<M>),
@@ -418,6 +523,7 @@ mod test {
             context: 1,
             name: func,
             def: RoutineDefType::Function,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -439,6 +545,7 @@ mod test {
             context: 1,
             name: func,
             def: RoutineDefType::Function,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -460,6 +567,7 @@ mod test {
             context: 1,
             name: func,
             def: RoutineDefType::Function,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -468,7 +576,10 @@ mod test {
         let result = module.add_function(fdef.clone());
         assert_eq!(
             result,
-            err!(module.span(), AstError::ModuleAlreadyContains(func))
+            Err(
+                CompilerError::new(module.span(), AstError::ModuleAlreadyContains(func))
+                    .with_note(module.span(), "previous fn definition here")
+            )
         );
     }
 
@@ -484,6 +595,7 @@ mod test {
             context: 1,
             name: cor,
             def: RoutineDefType::Coroutine,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -505,6 +617,7 @@ mod test {
             context: 1,
             name: cor,
             def: RoutineDefType::Coroutine,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -513,7 +626,10 @@ mod test {
         let result = module.add_coroutine(cdef.clone());
         assert_eq!(
             result,
-            err!(module.span(), AstError::ModuleAlreadyContains(cor))
+            Err(
+                CompilerError::new(module.span(), AstError::ModuleAlreadyContains(cor))
+                    .with_note(module.span(), "previous co definition here")
+            )
         );
     }
 
@@ -529,6 +645,7 @@ mod test {
             context: 1,
             name: dupe,
             def: RoutineDefType::Function,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -539,6 +656,7 @@ mod test {
             context: 1,
             name: dupe,
             def: RoutineDefType::Coroutine,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -546,7 +664,10 @@ mod test {
         let result = module.add_coroutine(cdef.clone());
         assert_eq!(
             result,
-            err!(module.span(), AstError::ModuleAlreadyContains(dupe))
+            Err(
+                CompilerError::new(module.span(), AstError::ModuleAlreadyContains(dupe))
+                    .with_note(module.span(), "previous fn definition here")
+            )
         );
     }
 
@@ -562,6 +683,7 @@ mod test {
             context: 1,
             name: dupe,
             def: RoutineDefType::Coroutine,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -572,6 +694,7 @@ mod test {
             context: 1,
             name: dupe,
             def: RoutineDefType::Function,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -579,7 +702,10 @@ mod test {
         let result = module.add_function(fdef.clone());
         assert_eq!(
             result,
-            err!(module.span(), AstError::ModuleAlreadyContains(dupe))
+            Err(
+                CompilerError::new(module.span(), AstError::ModuleAlreadyContains(dupe))
+                    .with_note(module.span(), "previous co definition here")
+            )
         );
     }
 
@@ -596,6 +722,7 @@ mod test {
             context: 1,
             name: func,
             def: RoutineDefType::Function,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -617,6 +744,7 @@ mod test {
             context: 1,
             name: func,
             def: RoutineDefType::Function,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -638,6 +766,7 @@ mod test {
             context: 1,
             name: co,
             def: RoutineDefType::Coroutine,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -660,6 +789,7 @@ mod test {
             context: 1,
             name: co,
             def: RoutineDefType::Coroutine,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -701,7 +831,10 @@ mod test {
         let result = module.add_extern(edef.clone());
         assert_eq!(
             result,
-            err!(module.span(), AstError::ModuleAlreadyContains(puts))
+            Err(
+                CompilerError::new(module.span(), AstError::ModuleAlreadyContains(puts))
+                    .with_note(module.span(), "previous extern definition here")
+            )
         );
     }
 
@@ -717,6 +850,7 @@ mod test {
             context: 1,
             name: dupe,
             def: RoutineDefType::Function,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -727,7 +861,10 @@ mod test {
         let result = module.add_extern(edef.clone());
         assert_eq!(
             result,
-            err!(module.span(), AstError::ModuleAlreadyContains(dupe))
+            Err(
+                CompilerError::new(module.span(), AstError::ModuleAlreadyContains(dupe))
+                    .with_note(module.span(), "previous fn definition here")
+            )
         );
     }
 
@@ -746,6 +883,7 @@ mod test {
             context: 1,
             name: dupe,
             def: RoutineDefType::Function,
+            is_const: false,
             params: vec![],
             ret_ty: Type::I64,
             body: vec![],
@@ -753,7 +891,40 @@ mod test {
         let result = module.add_function(fdef.clone());
         assert_eq!(
             result,
-            err!(module.span(), AstError::ModuleAlreadyContains(dupe))
+            Err(
+                CompilerError::new(module.span(), AstError::ModuleAlreadyContains(dupe))
+                    .with_note(module.span(), "previous extern definition here")
+            )
+        );
+    }
+
+    #[test]
+    pub fn test_add_struct_with_same_name_as_function() {
+        let table = StringTable::new();
+
+        let test = table.insert("test".into());
+        let dupe = table.insert("dupe".into());
+
+        let mut module = Module::new(test, 1);
+        let fdef = RoutineDef {
+            context: 1,
+            name: dupe,
+            def: RoutineDefType::Function,
+            is_const: false,
+            params: vec![],
+            ret_ty: Type::I64,
+            body: vec![],
+        };
+        module.add_function(fdef.clone()).unwrap();
+
+        let sdef = StructDef::new(dupe, 1, vec![]);
+        let result = module.add_struct(sdef);
+        assert_eq!(
+            result,
+            Err(
+                CompilerError::new(module.span(), AstError::ModuleAlreadyContains(dupe))
+                    .with_note(module.span(), "previous fn definition here")
+            )
         );
     }
 }
@@ -244,7 +244,7 @@ mod test_preorder {
                         Some(Box::new(Expression::RoutineCall(
                             14,
                             expression::RoutineCall::Function,
-                            vec![Element::Id(test)].into(),
+                            Box::new(vec![Element::Id(test)].into()),
                             vec![Expression::Identifier(15, p)],
                         ))),
                     )),
@@ -391,7 +391,7 @@ mod test_postorder {
                         Some(Box::new(Expression::RoutineCall(
                             14,
                             RoutineCall::Function,
-                            vec![Element::Id(func)].into(),
+                            Box::new(vec![Element::Id(func)].into()),
                             vec![Expression::Identifier(15, y)],
                         ))),
                     )),
@@ -7,6 +7,8 @@ use crate::compiler::ast::statement::*;
 use crate::compiler::ast::structdef::*;
 use crate::compiler::ast::Expression;
 use crate::compiler::ast::Extern;
+use crate::compiler::ast::StaticAssert;
+use crate::compiler::source::SourceIr;
 
 use super::{super::node::Node, super::parameter::Parameter, Context};
 
@@ -72,6 +74,20 @@ where
         let b = self.transform(m);
         let mut m2 = Module::new(m.get_name(), b);
 
+        for u in m.get_uses() {
+            m2.add_use(u.clone());
+        }
+
+        for mac in m.get_macros() {
+            m2.add_macro(mac.clone())
+                .expect("Macro names were already checked to be unique");
+        }
+
+        for sa in m.get_static_asserts() {
+            let cond = self.for_expression(sa.get_cond());
+            m2.add_static_assert(StaticAssert::new(sa.span(), cond, sa.get_message()));
+        }
+
         for child_module in m.get_modules().iter() {
             m2.add_module(self.for_module(child_module));
         }
@@ -103,13 +119,24 @@ where
     fn for_extern(&mut self, ex: &Extern<A>) -> Extern<B> {
         let b = self.transform(ex);
         let params = self.for_parameters(&ex.params);
-        Extern::new(ex.get_name(), b, params, ex.has_varargs, ex.ty.clone())
+        let mut ex2 = Extern::new(ex.get_name(), b, params, ex.has_varargs, ex.ty.clone());
+        ex2.attributes = ex.attributes.clone();
+        ex2.link_name = ex.link_name;
+        ex2
     }
 
     fn for_structdef(&mut self, sd: &StructDef<A>) -> StructDef<B> {
         let b = self.transform(sd);
         let fields = self.for_parameters(&sd.fields);
-        StructDef::new(sd.get_name(), b, fields)
+        let mut sd2 = StructDef::new(sd.get_name(), b, fields);
+        sd2.public_fields = sd.public_fields.clone();
+        sd2.attributes = sd.attributes.clone();
+        sd2.derived_eq = sd.derived_eq;
+        for m in sd.get_methods() {
+            let m2 = self.for_routinedef(m);
+            sd2.get_methods_mut().push(m2);
+        }
+        sd2
     }
 
     fn for_routinedef(&mut self, rd: &RoutineDef<A>) -> RoutineDef<B> {
@@ -126,6 +153,7 @@ where
         RoutineDef {
             name: rd.name.clone(),
             def: rd.def,
+            is_const: rd.is_const,
             context: b,
             params,
             ret_ty: rd.ret_ty.clone(),
@@ -151,6 +179,10 @@ where
                 Statement::YieldReturn(Box::new(self.for_yieldreturn(yr)))
             }
             Statement::Expression(e) => Statement::Expression(Box::new(self.for_expression(e))),
+            Statement::Discard(d) => Statement::Discard(Box::new(self.for_discard(d))),
+            Statement::Defer(d) => Statement::Defer(Box::new(self.for_defer(d))),
+            Statement::Break(b) => Statement::Break(Box::new(self.for_break(b))),
+            Statement::Continue(c) => Statement::Continue(Box::new(self.for_continue(c))),
         };
         s
     }
@@ -174,6 +206,28 @@ where
         Mutate::new(b, lhs, rhs)
     }
 
+    fn for_discard(&mut self, discard: &Discard<A>) -> Discard<B> {
+        let b = self.transform(discard);
+        let value = self.for_expression(discard.get_value());
+        Discard::new(b, value)
+    }
+
+    fn for_defer(&mut self, defer: &Defer<A>) -> Defer<B> {
+        let b = self.transform(defer);
+        let value = self.for_expression(defer.get_value());
+        Defer::new(b, value)
+    }
+
+    fn for_break(&mut self, brk: &Break<A>) -> Break<B> {
+        let b = self.transform(brk);
+        Break::new(b, brk.get_label())
+    }
+
+    fn for_continue(&mut self, cont: &Continue<A>) -> Continue<B> {
+        let b = self.transform(cont);
+        Continue::new(b, cont.get_label())
+    }
+
     fn for_yieldreturn(&mut self, yr: &YieldReturn<A>) -> YieldReturn<B> {
         let b = self.transform(yr);
         let value = yr.get_value().as_ref().map(|rv| self.for_expression(rv));
@@ -191,6 +245,7 @@ where
 
         match exp {
             Null(_) => Null(self.transform(exp)),
+            OptionNone(_) => OptionNone(self.transform(exp)),
             U8(_, i) => U8(self.transform(exp), *i),
             U16(_, i) => U16(self.transform(exp), *i),
             U32(_, i) => U32(self.transform(exp), *i),
@@ -205,6 +260,8 @@ where
             ArrayExpression(_, _, _) => self.for_array_expression(exp),
             ArrayAt { .. } => self.for_array_at(exp),
             SizeOf(_, ty) => SizeOf(self.transform(exp), ty.clone()),
+            FieldsOf(_, ty) => FieldsOf(self.transform(exp), ty.clone()),
+            NameOf(_, ty) => NameOf(self.transform(exp), ty.clone()),
             CustomType(_, name) => CustomType(self.transform(exp), name.clone()),
             Identifier(_, id) => Identifier(self.transform(exp), id.clone()),
             Path(_, path) => Path(self.transform(exp), path.clone()),
@@ -221,6 +278,7 @@ where
             StructExpression(..) => self.for_struct_expression(exp),
             ExpressionBlock(..) => self.for_expression_block(exp),
             TypeCast(..) => self.for_cast(exp),
+            MacroCall(_, name, tokens) => MacroCall(self.transform(exp), *name, tokens.clone()),
         }
     }
 
@@ -309,12 +367,16 @@ where
     }
 
     fn for_while(&mut self, while_exp: &Expression<A>) -> Expression<B> {
-        if let Expression::While { cond, body, .. } = while_exp {
+        if let Expression::While {
+            label, cond, body, ..
+        } = while_exp
+        {
             let b = self.transform(while_exp);
             let cond = self.for_expression(cond);
             let body = self.for_expression(body);
             Expression::While {
                 context: b,
+                label: *label,
                 cond: Box::new(cond),
                 body: Box::new(body),
             }
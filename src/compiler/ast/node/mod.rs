@@ -28,7 +28,7 @@ pub trait Node<M: Context>: SourceIr {
 /// Describes the specific type of an AST node.  This is used because when
 /// writing code against the [`Node`] trait, the specific node type information
 /// is lost.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum NodeType {
     Module,
     RoutineDef(RoutineDefType),
@@ -2,6 +2,7 @@ use crate::{
     compiler::{source::SourceIr, Span},
     StringId,
 };
+use serde::{Deserialize, Serialize};
 
 use super::{
     node::{
@@ -10,7 +11,7 @@ use super::{
     ty::Type,
 };
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Parameter<M> {
     pub context: M,
     pub name: StringId,
@@ -1,6 +1,7 @@
 use crate::compiler::diagnostics::Writable;
 use crate::compiler::{CompilerDisplay, CompilerDisplayError, SourceMap};
 use crate::{StringId, StringTable};
+use serde::{Deserialize, Serialize};
 
 use super::PathCanonizationError;
 
@@ -9,7 +10,7 @@ pub const ROOT_PATH: &str = "root";
 pub const SELF: &str = "self";
 pub const SUPER: &str = "super";
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Element {
     FileRoot,
     CanonicalRoot,
@@ -59,7 +60,7 @@ Keywords:
 current file.
 - `self`: if a path begins with this keyword, it starts at the current module.
 */
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Path {
     path: Vec<Element>,
     is_canonical: bool,
@@ -133,6 +134,11 @@ impl Path {
         }
     }
 
+    /// Returns true if the first steps of this path are the steps of `prefix`
+    pub fn starts_with(&self, prefix: &Path) -> bool {
+        self.path.starts_with(&prefix.path)
+    }
+
     pub fn parent(&self) -> Path {
         let mut path = Path {
             path: self.path.clone(),
@@ -146,47 +152,104 @@ impl Path {
     Converts this path into a canonical path by merging it
     with the given current path.
 
-    - If this is already a canonical path (i.e. begins with `root`) then nothing will change.
+    - If this is already a canonical path (i.e. begins with `project`) then only its `super`
+    steps are resolved.
+    - If this path begins with `root` then it is merged with the root module of `current_path`.
     - If this path begins with `self` then `self` will be replaced with `current_path`
-    - occurances of `super` will move up the current path
+    - occurances of `super` will move up to the parent module. It is an error for `super`
+    to move above the root module.
     */
     pub fn to_canonical(&self, current_path: &Path) -> Result<Path, PathCanonizationError> {
-        // TODO: make this method move "self"?
         if !current_path.is_canonical() {
             panic!("Current path is not canonical: {}", current_path);
         }
-        if self.is_canonical() {
-            Ok(self.clone())
+
+        let (base, path): (&[Element], &[Element]) = if self.is_canonical() {
+            (&[], &self.path)
+        } else if self.path[0] == Element::Selph {
+            (&current_path.path, &self.path[1..])
+        } else if self.path[0] == Element::FileRoot {
+            (&current_path.path[..1], &self.path[1..])
         } else {
-            let mut uses_root = false;
-            let path = if self.path[0] == Element::Selph {
-                &self.path[1..]
-            } else if self.path[0] == Element::FileRoot {
-                uses_root = true;
-                &self.path[1..]
-            } else {
-                &self.path
-            };
-            let current_path = if uses_root {
-                &current_path.path[..1]
-            } else {
-                &current_path.path
-            };
-            let mut merged: Vec<Element> = current_path.into();
-            for step in path.iter() {
-                if *step == Element::Super {
-                    merged.pop().ok_or(PathCanonizationError::SubceedingRoot)?;
-                    if merged.is_empty() {
-                        return Err(PathCanonizationError::SubceedingRoot);
-                    }
-                } else {
-                    merged.push(*step);
+            (&current_path.path, &self.path)
+        };
+
+        let mut merged: Vec<Element> = base.into();
+        for step in path.iter() {
+            if *step == Element::Super {
+                merged.pop().ok_or(PathCanonizationError::SubceedingRoot)?;
+                if merged.is_empty() {
+                    return Err(PathCanonizationError::SubceedingRoot);
                 }
+            } else {
+                merged.push(*step);
             }
-            Ok(Path {
-                path: merged,
-                is_canonical: true,
-            })
+        }
+        Ok(Path {
+            path: merged,
+            is_canonical: true,
+        })
+    }
+
+    /**
+    Converts this canonical path into the shortest path which refers to the same
+    item when written in the source code of `module`.  This is used to show paths
+    to the user in the form that they would write them, rather than as the internal
+    canonical form.
+
+    - If the item is in `module` or one of its submodules then the path is relative to `module`
+    - Otherwise, the shortest of a path using `super`, a path starting with `root`, or a path
+    starting with `project` is used.
+
+    If this path or `module` is not canonical then the path is returned unchanged.
+    */
+    pub fn to_source_path(&self, module: &Path) -> Path {
+        if !self.is_canonical() || !module.is_canonical() || self.is_empty() {
+            return self.clone();
+        }
+
+        // The item name must always be part of the source path
+        let common = self
+            .iter()
+            .zip(module.iter())
+            .take_while(|(l, r)| l == r)
+            .count()
+            .min(self.len() - 1);
+
+        let mut candidates = vec![];
+        if common == module.len() {
+            candidates.push(self.path[common..].to_vec());
+        } else if common > 0 {
+            let supers = module.len() - common;
+            candidates.push(
+                std::iter::repeat(Element::Super)
+                    .take(supers)
+                    .chain(self.path[common..].iter().copied())
+                    .collect(),
+            );
+            candidates.push(
+                std::iter::once(Element::FileRoot)
+                    .chain(self.path[1..].iter().copied())
+                    .collect(),
+            );
+        }
+        candidates.push(
+            std::iter::once(Element::CanonicalRoot)
+                .chain(self.path.iter().copied())
+                .collect(),
+        );
+
+        // The candidates are in order of preference, so the first of the shortest is used
+        let shortest = candidates
+            .into_iter()
+            .min_by_key(|c| c.len())
+            .expect("There is always at least one candidate path");
+
+        // The `project` keyword is kept as an element so that the path is displayed as
+        // it would be written in source code, rather than with the canonical `$` prefix
+        Path {
+            path: shortest,
+            is_canonical: false,
         }
     }
 
@@ -362,6 +425,92 @@ mod test_path {
         assert_eq!(canonized_path, Ok(expected));
     }
 
+    #[test]
+    fn test_canonical_with_super_to_canonical() {
+        let table = StringTable::new();
+        let relative_id = Element::Id(table.insert("relative".into()));
+        let current_id = Element::Id(table.insert("current".into()));
+        let test_id = Element::Id(table.insert("test".into()));
+
+        let path: Path = vec![
+            Element::CanonicalRoot,
+            test_id,
+            current_id,
+            Element::Super,
+            relative_id,
+        ]
+        .into();
+        let current = vec![Element::CanonicalRoot, test_id, current_id].into();
+        let canonized_path = path.to_canonical(&current);
+        let expected = vec![Element::CanonicalRoot, test_id, relative_id].into();
+        assert_eq!(canonized_path, Ok(expected));
+    }
+
+    #[test]
+    fn test_canonical_super_past_root() {
+        let table = StringTable::new();
+        let relative_id = Element::Id(table.insert("relative".into()));
+        let test_id = Element::Id(table.insert("test".into()));
+
+        let path: Path = vec![Element::CanonicalRoot, test_id, Element::Super, relative_id].into();
+        let current = vec![Element::CanonicalRoot, test_id].into();
+        let canonized_path = path.to_canonical(&current);
+        assert_eq!(canonized_path, Err(PathCanonizationError::SubceedingRoot));
+    }
+
+    #[test]
+    fn test_root_with_super_to_canonical() {
+        let table = StringTable::new();
+        let relative_id = Element::Id(table.insert("relative".into()));
+        let current_id = Element::Id(table.insert("current".into()));
+        let test_id = Element::Id(table.insert("test".into()));
+
+        let path: Path = vec![Element::FileRoot, current_id, Element::Super, relative_id].into();
+        let current = vec![Element::CanonicalRoot, test_id, current_id].into();
+        let canonized_path = path.to_canonical(&current);
+        let expected = vec![Element::CanonicalRoot, test_id, relative_id].into();
+        assert_eq!(canonized_path, Ok(expected));
+    }
+
+    #[test]
+    fn test_root_super_past_root() {
+        let table = StringTable::new();
+        let relative_id = Element::Id(table.insert("relative".into()));
+        let current_id = Element::Id(table.insert("current".into()));
+        let test_id = Element::Id(table.insert("test".into()));
+
+        let path: Path = vec![Element::FileRoot, Element::Super, relative_id].into();
+        let current = vec![Element::CanonicalRoot, test_id, current_id].into();
+        let canonized_path = path.to_canonical(&current);
+        assert_eq!(canonized_path, Err(PathCanonizationError::SubceedingRoot));
+    }
+
+    #[test]
+    fn test_self_with_super_to_canonical() {
+        let table = StringTable::new();
+        let relative_id = Element::Id(table.insert("relative".into()));
+        let current_id = Element::Id(table.insert("current".into()));
+        let test_id = Element::Id(table.insert("test".into()));
+
+        let path: Path = vec![Element::Selph, Element::Super, relative_id].into();
+        let current = vec![Element::CanonicalRoot, test_id, current_id].into();
+        let canonized_path = path.to_canonical(&current);
+        let expected = vec![Element::CanonicalRoot, test_id, relative_id].into();
+        assert_eq!(canonized_path, Ok(expected));
+    }
+
+    #[test]
+    fn test_self_super_past_root() {
+        let table = StringTable::new();
+        let relative_id = Element::Id(table.insert("relative".into()));
+        let test_id = Element::Id(table.insert("test".into()));
+
+        let path: Path = vec![Element::Selph, Element::Super, relative_id].into();
+        let current = vec![Element::CanonicalRoot, test_id].into();
+        let canonized_path = path.to_canonical(&current);
+        assert_eq!(canonized_path, Err(PathCanonizationError::SubceedingRoot));
+    }
+
     #[test]
     fn test_push_step() {
         let table = StringTable::new();
@@ -414,4 +563,57 @@ mod test_path {
         let expected = vec![Element::Selph].into();
         assert_eq!(path.parent(), expected);
     }
+
+    #[test]
+    fn test_to_source_path() {
+        let table = StringTable::new();
+        let project = Element::Id(table.insert("project".into()));
+        let a = Element::Id(table.insert("a".into()));
+        let b = Element::Id(table.insert("b".into()));
+        let c = Element::Id(table.insert("c".into()));
+        let item = Element::Id(table.insert("item".into()));
+        let std = Element::Id(table.insert("std".into()));
+
+        let module: Path = vec![Element::CanonicalRoot, project, a].into();
+        for (path, expected) in vec![
+            // Item in the same module
+            (vec![Element::CanonicalRoot, project, a, item], vec![item]),
+            // Item in a submodule
+            (
+                vec![Element::CanonicalRoot, project, a, b, item],
+                vec![b, item],
+            ),
+            // Item in the parent module
+            (
+                vec![Element::CanonicalRoot, project, item],
+                vec![Element::Super, item],
+            ),
+            // Item in a sibling module
+            (
+                vec![Element::CanonicalRoot, project, c, item],
+                vec![Element::Super, c, item],
+            ),
+            // Item in another project
+            (
+                vec![Element::CanonicalRoot, std, item],
+                vec![Element::CanonicalRoot, std, item],
+            ),
+        ] {
+            let path: Path = path.into();
+            let expected = Path {
+                path: expected,
+                is_canonical: false,
+            };
+            assert_eq!(path.to_source_path(&module), expected);
+        }
+
+        // From deep within a module tree, starting at the root is shorter than `super`
+        let module: Path = vec![Element::CanonicalRoot, project, a, b, c].into();
+        let path: Path = vec![Element::CanonicalRoot, project, item].into();
+        let expected = Path {
+            path: vec![Element::FileRoot, item],
+            is_canonical: false,
+        };
+        assert_eq!(path.to_source_path(&module), expected);
+    }
 }
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use super::path::Path;
+
+/**
+Interns [`Path`]s so that they can be referred to with a [`PathId`].
+
+Comparing or hashing a [`Path`] requires walking every element of the path, while
+a [`PathId`] can be compared and hashed in constant time.  Any table which is
+frequently searched by path (e.g. the table of imported symbols or of structure
+definitions) should be a [`PathMap`], keyed by [`PathId`], rather than be keyed by
+[`Path`] or by the rendered label of the path.  A caller which will use a path more
+than once should find its [`PathId`] once and hold on to it.
+
+Like the [`StringTable`](crate::StringTable), inserting a path which is already
+in the table returns the [`PathId`] that was previously assigned to that path.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PathTable {
+    /// The interned paths, indexed by [`PathId`]
+    paths: Vec<Path>,
+
+    /// Table mapping paths to their [`PathId`]s.
+    ids: HashMap<Path, PathId>,
+}
+
+impl PathTable {
+    pub fn new() -> PathTable {
+        Self::default()
+    }
+
+    /// Inserts a path into the table and returns the ID assigned to that path.  If
+    /// the path is already in the table, then the already assigned ID is returned.
+    pub fn insert(&mut self, path: &Path) -> PathId {
+        if let Some(id) = self.find(path) {
+            return id;
+        }
+
+        let id = PathId(self.paths.len() as u32);
+        self.paths.push(path.clone());
+        self.ids.insert(path.clone(), id);
+        id
+    }
+
+    /// Search the table for the given path and, if found, return the associated
+    /// [`PathId`]. If not found, then return [`None`](Option::None).
+    pub fn find(&self, path: &Path) -> Option<PathId> {
+        self.ids.get(path).copied()
+    }
+
+    /// Returns the path which has been assigned the given ID.
+    pub fn get(&self, id: PathId) -> Option<&Path> {
+        self.paths.get(id.0 as usize)
+    }
+
+    /// Returns the number of distinct paths in the table.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+}
+
+/// Handle to a [`Path`] which has been interned in a [`PathTable`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PathId(u32);
+
+impl std::fmt::Display for PathId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}", self.0))
+    }
+}
+
+/// A table of values keyed by [`PathId`].  The IDs which a [`PathTable`] assigns are
+/// dense, so a value is found by indexing, without hashing its path again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathMap<T> {
+    values: Vec<Option<T>>,
+}
+
+impl<T> PathMap<T> {
+    pub fn new() -> PathMap<T> {
+        PathMap { values: vec![] }
+    }
+
+    /// Sets the value of the given ID and returns its previous value, if it had one.
+    pub fn insert(&mut self, id: PathId, value: T) -> Option<T> {
+        let idx = id.0 as usize;
+        if idx >= self.values.len() {
+            self.values.resize_with(idx + 1, || None);
+        }
+        self.values[idx].replace(value)
+    }
+
+    pub fn get(&self, id: PathId) -> Option<&T> {
+        self.values.get(id.0 as usize).and_then(|v| v.as_ref())
+    }
+}
+
+impl<T> Default for PathMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test_pathtable {
+    use super::*;
+    use crate::{compiler::ast::Element, StringTable};
+
+    #[test]
+    fn test_insert_and_get() {
+        let table = StringTable::new();
+        let a = table.insert("a".into());
+        let b = table.insert("b".into());
+
+        let mut paths = PathTable::new();
+        let ab: Path = vec![Element::CanonicalRoot, Element::Id(a), Element::Id(b)].into();
+        let ba: Path = vec![Element::CanonicalRoot, Element::Id(b), Element::Id(a)].into();
+
+        let ab_id = paths.insert(&ab);
+        let ba_id = paths.insert(&ba);
+        assert_ne!(ab_id, ba_id);
+        assert_eq!(paths.get(ab_id), Some(&ab));
+        assert_eq!(paths.get(ba_id), Some(&ba));
+
+        // Inserting the same path again returns the same ID
+        assert_eq!(paths.insert(&ab.clone()), ab_id);
+        assert_eq!(paths.len(), 2);
+    }
+
+    #[test]
+    fn test_canonical_and_relative_paths_are_distinct() {
+        let table = StringTable::new();
+        let a = table.insert("a".into());
+
+        let mut paths = PathTable::new();
+        let canonical: Path = vec![Element::CanonicalRoot, Element::Id(a)].into();
+        let relative: Path = vec![Element::Id(a)].into();
+
+        let canonical_id = paths.insert(&canonical);
+        assert_eq!(paths.find(&relative), None);
+        assert_ne!(paths.insert(&relative), canonical_id);
+    }
+
+    #[test]
+    fn test_path_map() {
+        let table = StringTable::new();
+        let a = table.insert("a".into());
+        let b = table.insert("b".into());
+
+        let mut paths = PathTable::new();
+        let a_id = paths.insert(&vec![Element::CanonicalRoot, Element::Id(a)].into());
+        let b_id = paths.insert(&vec![Element::CanonicalRoot, Element::Id(b)].into());
+
+        let mut map = PathMap::new();
+        assert_eq!(map.insert(b_id, 2), None);
+        assert_eq!(map.get(a_id), None);
+        assert_eq!(map.get(b_id), Some(&2));
+        assert_eq!(map.insert(b_id, 3), Some(2));
+        assert_eq!(map.get(b_id), Some(&3));
+    }
+
+    #[test]
+    fn test_tables_are_sync() {
+        fn is_sync<T: Send + Sync>() {}
+        is_sync::<PathTable>();
+        is_sync::<PathMap<Path>>();
+    }
+}
@@ -0,0 +1,430 @@
+//! Renders an AST as an indented tree, with one node on each line.  This is used by
+//! `--emit ast` so that the AST which the parser produced can be inspected.
+
+use crate::{
+    compiler::{CompilerDisplay, CompilerDisplayError, SourceMap},
+    StringTable,
+};
+
+use super::{
+    Context, Expression, Item, Module, Parameter, RoutineDef, RoutineDefType, Statement, Type,
+};
+
+/// The number of spaces that each level of the tree is indented by.
+const INDENT: usize = 2;
+
+/// Renders the given module, and all of its items and submodules, as a tree.
+pub fn print_ast<M: Context>(
+    m: &Module<M>,
+    sm: &SourceMap,
+    st: &StringTable,
+) -> Result<String, CompilerDisplayError> {
+    let mut printer = AstPrinter {
+        sm,
+        st,
+        depth: 0,
+        text: String::new(),
+    };
+    printer.module(m)?;
+    Ok(printer.text)
+}
+
+struct AstPrinter<'a> {
+    sm: &'a SourceMap,
+    st: &'a StringTable,
+
+    /// The depth of the node currently being printed
+    depth: usize,
+    text: String,
+}
+
+impl<'a> AstPrinter<'a> {
+    /// Adds a line for a node, at the current depth, to the output.
+    fn line(&mut self, label: &str) {
+        self.text.push_str(&format!(
+            "{:indent$}{}\n",
+            "",
+            label,
+            indent = self.depth * INDENT
+        ));
+    }
+
+    /// Prints the given line and then prints the children of the node, with `f`, one
+    /// level deeper.
+    fn node<F>(&mut self, label: &str, f: F) -> Result<(), CompilerDisplayError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), CompilerDisplayError>,
+    {
+        self.line(label);
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn name(&self, id: crate::StringId) -> Result<String, CompilerDisplayError> {
+        Ok(self.st.get(id)?.to_string())
+    }
+
+    /// Labels a node with a keyword followed by the loop label which it has, if any.
+    fn labeled(
+        &self,
+        keyword: &str,
+        label: Option<crate::StringId>,
+    ) -> Result<String, CompilerDisplayError> {
+        match label {
+            Some(label) => Ok(format!("{} {}", keyword, self.name(label)?)),
+            None => Ok(keyword.into()),
+        }
+    }
+
+    fn ty(&self, ty: &Type) -> Result<String, CompilerDisplayError> {
+        ty.fmt(self.sm, self.st)
+    }
+
+    fn params<M>(&self, params: &[Parameter<M>]) -> Result<String, CompilerDisplayError> {
+        let params = params
+            .iter()
+            .map(|p| Ok(format!("{}: {}", self.name(p.name)?, self.ty(&p.ty)?)))
+            .collect::<Result<Vec<_>, CompilerDisplayError>>()?;
+        Ok(params.join(", "))
+    }
+
+    fn module<M: Context>(&mut self, m: &Module<M>) -> Result<(), CompilerDisplayError> {
+        let label = format!("mod {}", self.name(m.get_name())?);
+        self.node(&label, |p| {
+            for u in m.get_uses() {
+                let label = format!("use {}", u.get_path().fmt(p.sm, p.st)?);
+                p.line(&label);
+            }
+
+            for item in m
+                .get_structs()
+                .iter()
+                .chain(m.get_externs())
+                .chain(m.get_functions())
+                .chain(m.get_coroutines())
+            {
+                p.item(item)?;
+            }
+
+            for sm in m.get_modules() {
+                p.module(sm)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn item<M: Context>(&mut self, item: &Item<M>) -> Result<(), CompilerDisplayError> {
+        match item {
+            Item::Routine(r) => self.routine(r),
+            Item::Struct(s) => {
+                let label = format!(
+                    "struct {} {{{}}}",
+                    self.name(s.get_name())?,
+                    self.params(s.get_fields())?
+                );
+                self.line(&label);
+
+                if s.get_methods().is_empty() {
+                    Ok(())
+                } else {
+                    let label = format!("impl {}", self.name(s.get_name())?);
+                    self.node(&label, |p| {
+                        for m in s.get_methods() {
+                            p.routine(m)?;
+                        }
+                        Ok(())
+                    })
+                }
+            }
+            Item::Extern(e) => {
+                let label = format!(
+                    "extern fn {}({}) -> {}",
+                    self.name(e.get_name())?,
+                    self.params(e.get_params())?,
+                    self.ty(e.get_return_type())?
+                );
+                self.line(&label);
+                Ok(())
+            }
+        }
+    }
+
+    fn routine<M: Context>(&mut self, r: &RoutineDef<M>) -> Result<(), CompilerDisplayError> {
+        let kind = match r.def {
+            RoutineDefType::Function if r.is_const => "const fn",
+            RoutineDefType::Function => "fn",
+            RoutineDefType::Coroutine => "co",
+        };
+        let label = format!(
+            "{} {}({}) -> {}",
+            kind,
+            self.name(r.name)?,
+            self.params(&r.params)?,
+            self.ty(&r.ret_ty)?
+        );
+        self.node(&label, |p| {
+            for stm in &r.body {
+                p.statement(stm)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn statement<M: Context>(&mut self, stm: &Statement<M>) -> Result<(), CompilerDisplayError> {
+        match stm {
+            Statement::Bind(b) => {
+                let label = format!(
+                    "let {}{}: {} :=",
+                    if b.is_mutable() { "mut " } else { "" },
+                    self.name(b.get_id())?,
+                    self.ty(b.get_type())?
+                );
+                self.node(&label, |p| p.expression(b.get_rhs()))
+            }
+            Statement::Mutate(m) => self.node("mut :=", |p| {
+                p.expression(m.get_lhs())?;
+                p.expression(m.get_rhs())
+            }),
+            Statement::Discard(d) => self.node("_ :=", |p| p.expression(d.get_value())),
+            Statement::Defer(d) => self.node("defer", |p| p.expression(d.get_value())),
+            Statement::Break(b) => {
+                let label = self.labeled("break", b.get_label())?;
+                self.line(&label);
+                Ok(())
+            }
+            Statement::Continue(c) => {
+                let label = self.labeled("continue", c.get_label())?;
+                self.line(&label);
+                Ok(())
+            }
+            Statement::YieldReturn(yr) => self.node("yret", |p| match yr.get_value() {
+                Some(v) => p.expression(v),
+                None => Ok(()),
+            }),
+            Statement::Return(r) => self.node("return", |p| match r.get_value() {
+                Some(v) => p.expression(v),
+                None => Ok(()),
+            }),
+            Statement::Expression(e) => self.expression(e),
+        }
+    }
+
+    fn expression<M: Context>(&mut self, exp: &Expression<M>) -> Result<(), CompilerDisplayError> {
+        match exp {
+            Expression::StringLiteral(_, s) => {
+                let label = format!("\"{}\"", self.name(*s)?);
+                self.line(&label)
+            }
+            Expression::ArrayExpression(_, elements, _) => self.node("array", |p| {
+                for e in elements {
+                    p.expression(e)?;
+                }
+                Ok(())
+            })?,
+            Expression::ArrayAt { array, index, .. } => self.node("index", |p| {
+                p.expression(array)?;
+                p.expression(index)
+            })?,
+            Expression::SizeOf(_, ty) => {
+                let label = format!("size_of({})", self.ty(ty)?);
+                self.line(&label)
+            }
+            Expression::FieldsOf(_, ty) => {
+                let label = format!("fields_of({})", self.ty(ty)?);
+                self.line(&label)
+            }
+            Expression::NameOf(_, ty) => {
+                let label = format!("name_of({})", self.ty(ty)?);
+                self.line(&label)
+            }
+            Expression::CustomType(_, path) | Expression::Path(_, path) => {
+                let label = path.fmt(self.sm, self.st)?;
+                self.line(&label)
+            }
+            Expression::Identifier(_, id) => {
+                let label = self.name(*id)?;
+                self.line(&label)
+            }
+            Expression::IdentifierDeclare(_, id, ty) => {
+                let label = format!("{}: {}", self.name(*id)?, self.ty(ty)?);
+                self.line(&label)
+            }
+            Expression::MemberAccess(_, src, member) => {
+                let label = format!(".{}", self.name(*member)?);
+                self.node(&label, |p| p.expression(src))?
+            }
+            Expression::RoutineCall(_, call, path, args) => {
+                let label = format!("{} {}", call, path.fmt(self.sm, self.st)?);
+                self.node(&label, |p| {
+                    for a in args {
+                        p.expression(a)?;
+                    }
+                    Ok(())
+                })?
+            }
+            Expression::StructExpression(_, path, fields) => {
+                let label = format!("struct {}", path.fmt(self.sm, self.st)?);
+                self.node(&label, |p| {
+                    for (name, value) in fields {
+                        let label = format!("{}:", p.name(*name)?);
+                        p.node(&label, |p| p.expression(value))?;
+                    }
+                    Ok(())
+                })?
+            }
+            Expression::If {
+                cond,
+                if_arm,
+                else_arm,
+                ..
+            } => self.node("if", |p| {
+                p.expression(cond)?;
+                p.expression(if_arm)?;
+                match else_arm {
+                    Some(else_arm) => p.node("else", |p| p.expression(else_arm)),
+                    None => Ok(()),
+                }
+            })?,
+            Expression::While {
+                label, cond, body, ..
+            } => {
+                let label = self.labeled("while", *label)?;
+                self.node(&label, |p| {
+                    p.expression(cond)?;
+                    p.expression(body)
+                })?
+            }
+            Expression::ExpressionBlock(_, body, final_exp) => self.node("block", |p| {
+                for stm in body {
+                    p.statement(stm)?;
+                }
+                match final_exp {
+                    Some(fe) => p.expression(fe),
+                    None => Ok(()),
+                }
+            })?,
+            Expression::BinaryOp(_, op, l, r) => self.node(&op.to_string(), |p| {
+                p.expression(l)?;
+                p.expression(r)
+            })?,
+            Expression::TypeCast(_, value, ty) => {
+                let label = format!("as {}", self.ty(ty)?);
+                self.node(&label, |p| p.expression(value))?
+            }
+            Expression::UnaryOp(_, op, value) => {
+                self.node(&op.to_string(), |p| p.expression(value))?
+            }
+            Expression::Yield(_, value) => self.node("yield", |p| p.expression(value))?,
+            Expression::MacroCall(_, name, tokens) => {
+                let tokens = tokens
+                    .iter()
+                    .map(|t| t.fmt(self.sm, self.st))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let label = format!("{}!({})", self.name(*name)?, tokens.join(" "));
+                self.line(&label)
+            }
+            Expression::Null(_)
+            | Expression::OptionNone(_)
+            | Expression::U8(..)
+            | Expression::U16(..)
+            | Expression::U32(..)
+            | Expression::U64(..)
+            | Expression::I8(..)
+            | Expression::I16(..)
+            | Expression::I32(..)
+            | Expression::I64(..)
+            | Expression::F64(..)
+            | Expression::Boolean(..) => self.line(&exp.root_str()),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compiler::{diagnostics::Logger, lexer::tokens::Token, parser::Parser, Lexer, SourceMap},
+        StringTable,
+    };
+
+    use super::print_ast;
+
+    #[test]
+    fn print_parsed_ast() {
+        let text = "struct S {
+    a: i64,
+}
+
+extern fn puts(s: string) -> i32;
+
+fn test(x: i64) -> i64 {
+    let mut s: S := S{a: x * 2};
+    mut s.a := s.a + 1;
+    return if (s.a > 10) {inner::id(s.a)} else {0};
+}
+
+mod inner {
+    fn id(n: i64) -> i64 {
+        return n as i64;
+    }
+}
+";
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let mut table = StringTable::new();
+        let main = table.insert("main".into());
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let parser = Parser::new(&logger);
+        let ast = parser.parse(main, &tokens).unwrap().unwrap();
+
+        let expected = "mod main
+  struct S {a: i64}
+  extern fn puts(s: string) -> i32
+  fn test(x: i64) -> i64
+    let mut s: S :=
+      struct S
+        a:
+          *
+            x
+            2i64
+    mut :=
+      .a
+        s
+      +
+        .a
+          s
+        1i64
+    return
+      if
+        >
+          .a
+            s
+          10i64
+        block
+          call inner::id
+            .a
+              s
+        else
+          block
+            0i64
+  mod inner
+    fn id(n: i64) -> i64
+      return
+        as i64
+          n
+";
+        assert_eq!(print_ast(&ast, &sm, &table).unwrap(), expected);
+    }
+}
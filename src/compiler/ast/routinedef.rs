@@ -2,6 +2,7 @@ use crate::{
     compiler::{source::SourceIr, Span},
     StringId,
 };
+use serde::{Deserialize, Serialize};
 
 use super::{
     node::{
@@ -12,10 +13,13 @@ use super::{
     ty::Type,
 };
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RoutineDef<M> {
     pub context: M,
     pub def: RoutineDefType,
+
+    /// `true` if this is a `const fn`, which can be evaluated at compile time
+    pub is_const: bool,
     pub name: StringId,
     pub params: Vec<Parameter<M>>,
     pub ret_ty: Type,
@@ -83,6 +87,7 @@ impl<M> RoutineDef<M> {
         RoutineDef {
             context,
             def: RoutineDefType::Function,
+            is_const: false,
             name,
             params,
             ret_ty: ty,
@@ -100,6 +105,7 @@ impl<M> RoutineDef<M> {
         RoutineDef {
             context,
             def: RoutineDefType::Coroutine,
+            is_const: false,
             name,
             params,
             ret_ty: ty,
@@ -139,12 +145,16 @@ impl<M> RoutineDef<M> {
         &self.def
     }
 
+    pub fn is_const(&self) -> bool {
+        self.is_const
+    }
+
     pub fn root_str(&self) -> String {
         format!("{} {}", self.def, self.name)
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum RoutineDefType {
     Function,
     Coroutine,
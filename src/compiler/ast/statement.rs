@@ -2,6 +2,7 @@ use crate::{
     compiler::{source::SourceIr, Span},
     StringId,
 };
+use serde::{Deserialize, Serialize};
 
 use super::{
     expression::Expression,
@@ -11,14 +12,18 @@ use super::{
     ty::Type,
 };
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Statement<M> {
     Bind(Box<Bind<M>>),
     Mutate(Box<Mutate<M>>),
 
     YieldReturn(Box<YieldReturn<M>>),
     Expression(Box<Expression<M>>),
+    Discard(Box<Discard<M>>),
+    Defer(Box<Defer<M>>),
 
+    Break(Box<Break<M>>),
+    Continue(Box<Continue<M>>),
     Return(Box<Return<M>>),
 }
 
@@ -38,6 +43,10 @@ impl<M: Context> Node<M> for Statement<M> {
             Expression(e) => e.context(),
             Bind(b) => b.context(),
             Mutate(m) => m.context(),
+            Discard(d) => d.context(),
+            Defer(d) => d.context(),
+            Break(b) => b.context(),
+            Continue(c) => c.context(),
         }
     }
 
@@ -50,6 +59,10 @@ impl<M: Context> Node<M> for Statement<M> {
             Expression(e) => e.get_context_mut(),
             Bind(b) => b.get_context_mut(),
             Mutate(m) => m.get_context_mut(),
+            Discard(d) => d.get_context_mut(),
+            Defer(d) => d.get_context_mut(),
+            Break(b) => b.get_context_mut(),
+            Continue(c) => c.get_context_mut(),
         }
     }
 
@@ -66,6 +79,10 @@ impl<M: Context> Node<M> for Statement<M> {
             Expression(e) => e.children(),
             Bind(b) => b.children(),
             Mutate(m) => m.children(),
+            Discard(d) => d.children(),
+            Defer(d) => d.children(),
+            Break(b) => b.children(),
+            Continue(c) => c.children(),
         }
     }
 
@@ -78,6 +95,10 @@ impl<M: Context> Node<M> for Statement<M> {
             Expression(e) => e.name(),
             Bind(b) => b.name(),
             Mutate(m) => m.name(),
+            Discard(d) => d.name(),
+            Defer(d) => d.name(),
+            Break(b) => b.name(),
+            Continue(c) => c.name(),
         }
     }
 
@@ -110,11 +131,15 @@ impl<M> Statement<M> {
             Expression(e) => e.root_str(),
             Bind(b) => b.root_str(),
             Mutate(m) => m.root_str(),
+            Discard(d) => d.root_str(),
+            Defer(d) => d.root_str(),
+            Break(b) => b.root_str(),
+            Continue(c) => c.root_str(),
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Bind<M> {
     context: M,
     id: StringId,
@@ -205,7 +230,7 @@ impl<M> Bind<M> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Mutate<M> {
     context: M,
     lhs: Expression<M>,
@@ -276,7 +301,273 @@ impl<M> Mutate<M> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+/// Evaluates an expression and explicitly throws away its value: `_ := exp;`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Discard<M> {
+    context: M,
+    value: Expression<M>,
+}
+
+impl<M: Context> SourceIr for Discard<M> {
+    fn span(&self) -> Span {
+        self.context.span()
+    }
+}
+
+impl<M: Context> Node<M> for Discard<M> {
+    fn context(&self) -> &M {
+        &self.context
+    }
+
+    fn get_context_mut(&mut self) -> &mut M {
+        &mut self.context
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::Statement
+    }
+
+    fn children(&self) -> Vec<&dyn Node<M>> {
+        vec![&self.value]
+    }
+
+    fn name(&self) -> Option<StringId> {
+        None
+    }
+
+    fn iter_postorder(&self) -> PostOrderIter<M> {
+        PostOrderIter::new(self)
+    }
+
+    fn iter_preorder(&self) -> PreOrderIter<M> {
+        PreOrderIter::new(self)
+    }
+}
+
+impl<M> std::fmt::Display for Discard<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        f.write_str(&self.root_str())
+    }
+}
+
+impl<M> Discard<M> {
+    pub fn new(context: M, value: Expression<M>) -> Self {
+        Discard { context, value }
+    }
+
+    pub fn get_value(&self) -> &Expression<M> {
+        &self.value
+    }
+
+    pub fn get_value_mut(&mut self) -> &mut Expression<M> {
+        &mut self.value
+    }
+
+    pub fn root_str(&self) -> String {
+        "discard".into()
+    }
+}
+
+/// Evaluates an expression when the scope which contains this statement is exited, by
+/// reaching its end, by a `break` or `continue`, or by returning from the function:
+/// `defer exp;`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Defer<M> {
+    context: M,
+    value: Expression<M>,
+}
+
+impl<M: Context> SourceIr for Defer<M> {
+    fn span(&self) -> Span {
+        self.context.span()
+    }
+}
+
+impl<M: Context> Node<M> for Defer<M> {
+    fn context(&self) -> &M {
+        &self.context
+    }
+
+    fn get_context_mut(&mut self) -> &mut M {
+        &mut self.context
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::Statement
+    }
+
+    fn children(&self) -> Vec<&dyn Node<M>> {
+        vec![&self.value]
+    }
+
+    fn name(&self) -> Option<StringId> {
+        None
+    }
+
+    fn iter_postorder(&self) -> PostOrderIter<M> {
+        PostOrderIter::new(self)
+    }
+
+    fn iter_preorder(&self) -> PreOrderIter<M> {
+        PreOrderIter::new(self)
+    }
+}
+
+impl<M> std::fmt::Display for Defer<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        f.write_str(&self.root_str())
+    }
+}
+
+impl<M> Defer<M> {
+    pub fn new(context: M, value: Expression<M>) -> Self {
+        Defer { context, value }
+    }
+
+    pub fn get_value(&self) -> &Expression<M> {
+        &self.value
+    }
+
+    pub fn get_value_mut(&mut self) -> &mut Expression<M> {
+        &mut self.value
+    }
+
+    pub fn root_str(&self) -> String {
+        "defer".into()
+    }
+}
+
+/// Immediately exits the loop with the given label: `break ['label];`.  Without a label
+/// this exits the innermost loop.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Break<M> {
+    context: M,
+    label: Option<StringId>,
+}
+
+impl<M: Context> SourceIr for Break<M> {
+    fn span(&self) -> Span {
+        self.context.span()
+    }
+}
+
+impl<M: Context> Node<M> for Break<M> {
+    fn context(&self) -> &M {
+        &self.context
+    }
+
+    fn get_context_mut(&mut self) -> &mut M {
+        &mut self.context
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::Statement
+    }
+
+    fn children(&self) -> Vec<&dyn Node<M>> {
+        vec![]
+    }
+
+    fn name(&self) -> Option<StringId> {
+        None
+    }
+
+    fn iter_postorder(&self) -> PostOrderIter<M> {
+        PostOrderIter::new(self)
+    }
+
+    fn iter_preorder(&self) -> PreOrderIter<M> {
+        PreOrderIter::new(self)
+    }
+}
+
+impl<M> std::fmt::Display for Break<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        f.write_str(&self.root_str())
+    }
+}
+
+impl<M> Break<M> {
+    pub fn new(context: M, label: Option<StringId>) -> Self {
+        Self { context, label }
+    }
+
+    /// The label of the loop which this break targets, if one was given
+    pub fn get_label(&self) -> Option<StringId> {
+        self.label
+    }
+
+    pub fn root_str(&self) -> String {
+        "break".into()
+    }
+}
+
+/// Skips the rest of the body of the loop with the given label and starts its next
+/// iteration: `continue ['label];`.  Without a label this targets the innermost loop.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Continue<M> {
+    context: M,
+    label: Option<StringId>,
+}
+
+impl<M: Context> SourceIr for Continue<M> {
+    fn span(&self) -> Span {
+        self.context.span()
+    }
+}
+
+impl<M: Context> Node<M> for Continue<M> {
+    fn context(&self) -> &M {
+        &self.context
+    }
+
+    fn get_context_mut(&mut self) -> &mut M {
+        &mut self.context
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::Statement
+    }
+
+    fn children(&self) -> Vec<&dyn Node<M>> {
+        vec![]
+    }
+
+    fn name(&self) -> Option<StringId> {
+        None
+    }
+
+    fn iter_postorder(&self) -> PostOrderIter<M> {
+        PostOrderIter::new(self)
+    }
+
+    fn iter_preorder(&self) -> PreOrderIter<M> {
+        PreOrderIter::new(self)
+    }
+}
+
+impl<M> std::fmt::Display for Continue<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        f.write_str(&self.root_str())
+    }
+}
+
+impl<M> Continue<M> {
+    pub fn new(context: M, label: Option<StringId>) -> Self {
+        Self { context, label }
+    }
+
+    /// The label of the loop which this continue targets, if one was given
+    pub fn get_label(&self) -> Option<StringId> {
+        self.label
+    }
+
+    pub fn root_str(&self) -> String {
+        "continue".into()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct YieldReturn<M> {
     context: M,
     value: Option<Expression<M>>,
@@ -345,7 +636,7 @@ impl<M> YieldReturn<M> {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Return<M> {
     context: M,
     value: Option<Expression<M>>,
@@ -0,0 +1,39 @@
+use crate::{
+    compiler::{source::SourceIr, Span},
+    StringId,
+};
+use serde::{Deserialize, Serialize};
+
+use super::expression::Expression;
+
+/// A static assertion, `static_assert(cond, "message");`, which fails compilation with
+/// the given message if its condition, which must be a compile time constant, is
+/// `false`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StaticAssert<M> {
+    span: Span,
+    cond: Expression<M>,
+    msg: StringId,
+}
+
+impl<M> SourceIr for StaticAssert<M> {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl<M> StaticAssert<M> {
+    pub fn new(span: Span, cond: Expression<M>, msg: StringId) -> StaticAssert<M> {
+        StaticAssert { span, cond, msg }
+    }
+
+    /// Returns the condition which must be `true`
+    pub fn get_cond(&self) -> &Expression<M> {
+        &self.cond
+    }
+
+    /// Returns the message which is reported if the condition is `false`
+    pub fn get_message(&self) -> StringId {
+        self.msg
+    }
+}
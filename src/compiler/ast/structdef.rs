@@ -1,8 +1,10 @@
 use super::{
+    attribute::Attribute,
     node::{
         Context, Node, NodeType, {PostOrderIter, PreOrderIter},
     },
     parameter::Parameter,
+    routinedef::RoutineDef,
     ty::Type,
 };
 use crate::{
@@ -10,12 +12,27 @@ use crate::{
     result::Result,
     StringId,
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct StructDef<M> {
     context: M,
     name: StringId,
     pub(super) fields: Vec<Parameter<M>>,
+
+    /// The fields which are declared `pub` and so can be used outside of the module
+    /// which defines this structure
+    pub(super) public_fields: Vec<StringId>,
+
+    /// The routines defined for this structure in `impl` blocks
+    pub(super) methods: Vec<RoutineDef<M>>,
+
+    /// The attributes written before the structure (e.g. `#[derive(print)]`)
+    pub(super) attributes: Vec<Attribute>,
+
+    /// The name of the function, generated by `#[derive(eq)]`, which `==` calls to
+    /// compare two values of this structure
+    pub(super) derived_eq: Option<StringId>,
 }
 
 impl<M: Context> SourceIr for StructDef<M> {
@@ -42,6 +59,9 @@ impl<M: Context> Node<M> for StructDef<M> {
         for f in self.fields.iter() {
             v.push(f);
         }
+        for m in self.methods.iter() {
+            v.push(m);
+        }
         v
     }
 
@@ -70,6 +90,10 @@ impl<M> StructDef<M> {
             context,
             name,
             fields,
+            public_fields: vec![],
+            methods: vec![],
+            attributes: vec![],
+            derived_eq: None,
         }
     }
 
@@ -89,6 +113,19 @@ impl<M> StructDef<M> {
         self.fields.iter().find(|f| f.name == field).map(|f| &f.ty)
     }
 
+    /// Returns true if `field` is declared `pub`
+    pub fn is_public(&self, field: StringId) -> bool {
+        self.public_fields.contains(&field)
+    }
+
+    pub fn get_public_fields(&self) -> &Vec<StringId> {
+        &self.public_fields
+    }
+
+    pub fn get_public_fields_mut(&mut self) -> &mut Vec<StringId> {
+        &mut self.public_fields
+    }
+
     pub fn get_field_idx(&self, field: StringId) -> Option<usize> {
         self.fields
             .iter()
@@ -109,6 +146,37 @@ impl<M> StructDef<M> {
         }
     }
 
+    pub fn get_methods(&self) -> &Vec<RoutineDef<M>> {
+        &self.methods
+    }
+
+    pub fn get_methods_mut(&mut self) -> &mut Vec<RoutineDef<M>> {
+        &mut self.methods
+    }
+
+    pub fn get_method(&self, name: StringId) -> Option<&RoutineDef<M>> {
+        self.methods.iter().find(|m| m.get_name() == name)
+    }
+
+    pub fn get_attributes(&self) -> &Vec<Attribute> {
+        &self.attributes
+    }
+
+    pub fn get_attributes_mut(&mut self) -> &mut Vec<Attribute> {
+        &mut self.attributes
+    }
+
+    /// Returns the name of the function which compares two values of this structure,
+    /// if one was generated by `#[derive(eq)]`.  The function is in the same module
+    /// as the structure.
+    pub fn get_derived_eq(&self) -> Option<StringId> {
+        self.derived_eq
+    }
+
+    pub fn set_derived_eq(&mut self, name: StringId) {
+        self.derived_eq = Some(name)
+    }
+
     pub fn root_str(&self) -> String {
         format!("struct {}", self.name)
     }
@@ -2,6 +2,7 @@ use crate::{
     compiler::{CompilerDisplay, CompilerDisplayError, SourceMap},
     StringId, StringTable,
 };
+use serde::{Deserialize, Serialize};
 
 use super::{path::Path, HasVarArgs};
 
@@ -11,7 +12,7 @@ with aggregate types (the array and the structure).  This also includes the `Unk
 type, which is used when a type for a value has not yet been resolved by the
 Semantic Analyzer.
  */
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Type {
     Null,
     U8,
@@ -22,12 +23,39 @@ pub enum Type {
     I16,
     I32,
     I64,
+    /// An unsigned integer which is as wide as a pointer on the target machine, and
+    /// so can hold the index of any element in memory.
+    USize,
+    /// A signed integer which is as wide as a pointer on the target machine.
+    ISize,
     F64,
     Bool,
     StringLiteral,
+    /// A string which is built at run time (e.g. by concatenation).  Unlike a
+    /// [`Type::StringLiteral`], a `String` knows its own length.
+    String,
     RawPointer(PointerMut, Box<Type>),
     Array(Box<Type>, usize),
+    /// A view of a sequence of elements whose length is only known at run time (e.g. a
+    /// `[i64]` which refers to an `[i64; 5]`).  An array can be used wherever a slice
+    /// of the same element type is expected.
+    Slice(Box<Type>),
+    /// The integers from a start, inclusive, to an end, exclusive (e.g. `0..10`).  The
+    /// range is a value, made of its two bounds, and the type is the type of the bounds.
+    Range(Box<Type>),
+    /// A value of the inner type which may be absent (e.g. `opt i64`).  An option is
+    /// made with `some(x)` or `none` and its value can only be used after checking
+    /// that it is present.
+    Option(Box<Type>),
+    /// Either a value of the first type, made with `ok(x)`, or an error of the second type,
+    /// made with `err(e)` (e.g. `res i64, String`).  Like an option, the value or the error
+    /// can only be used after checking which of them the result holds.
+    Result(Box<Type>, Box<Type>),
     Unit,
+    /// The type of an expression which never produces a value because evaluating it
+    /// always diverges (e.g. a block which ends with a `return`). A value of this
+    /// type can be used wherever any other type is expected.
+    Never,
     Custom(Path),
     StructDef(Vec<(StringId, Type)>),
     FunctionDef(Vec<Type>, Box<Type>),
@@ -37,7 +65,7 @@ pub enum Type {
     Unknown,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum PointerMut {
     Mut,
     Const,
@@ -59,10 +87,14 @@ impl Type {
     /// If this is [`Type::Null`] then this will always return
     /// false, because there is no addressable value which has typ
     /// [`Type::Null`].
+    ///
+    /// A [`Type::Never`] value can be assigned to any other type,
+    /// because it will never actually be produced.
     pub fn can_be_assigned(&self, r: &Self) -> bool {
         match self {
-            Self::RawPointer(..) => r == &Self::Null || self == r,
             Self::Null => r == &Self::Null || r.can_be_assigned(&Self::Null),
+            _ if r == &Self::Never => true,
+            Self::RawPointer(..) => r == &Self::Null || self == r,
             Self::Array(ty, sz) => {
                 if let Self::Array(rty, rsz) = r {
                     sz == rsz && ty.can_be_assigned(rty)
@@ -76,9 +108,13 @@ impl Type {
 
     /// Returns `true` if these types can be compared with
     /// each other
+    ///
+    /// An option or a result cannot be compared, because which
+    /// value it holds must be checked before the value is used.
     pub fn can_be_compared(&self, r: &Self) -> bool {
         match self {
             Self::RawPointer(..) => r == &Self::Null || self == r,
+            Self::Option(_) | Self::Result(..) => false,
             Self::Null => r == &Self::Null || r.can_be_compared(&Self::Null),
             _ => self == r,
         }
@@ -95,12 +131,20 @@ impl Type {
             Type::I16 => true,
             Type::I32 => true,
             Type::I64 => true,
+            Type::USize => true,
+            Type::ISize => true,
             Type::F64 => true,
             Type::Bool => true,
             Type::StringLiteral => false,
+            Type::String => false,
             Type::RawPointer(_, _) => true,
             Type::Array(_, _) => false,
+            Type::Slice(_) => false,
+            Type::Range(_) => false,
+            Type::Option(_) => false,
+            Type::Result(..) => false,
             Type::Unit => false,
+            Type::Never => false,
             Type::Custom(_) => false,
             Type::StructDef(_) => false,
             Type::FunctionDef(_, _) => false,
@@ -112,8 +156,29 @@ impl Type {
     }
 
     /// Returns whether this type can be cast to the target type.
+    ///
+    /// A `bool` can be cast to an integer, `true` becomes `1` and `false` becomes `0`.
+    /// An integer can be cast to a `bool`, which is `true` if the integer is not `0`.
+    ///
+    /// A `string` can be cast to a `String`, which copies the literal, and a `String`
+    /// can be cast to a `string`, so that it can be given to functions (e.g. `printf`)
+    /// which expect a string literal.  A `string` can also be cast to `*const u8`, which
+    /// points to its characters; string literals are always NUL-terminated, so the
+    /// pointer can be given to C functions which expect a C string.
+    ///
+    /// An array can be cast to a slice with the same element type.
     pub fn can_cast_to(&self, r: &Self) -> bool {
-        if self.can_be_cast() && r.can_be_cast() {
+        if matches!(
+            (self, r),
+            (Type::StringLiteral, Type::String) | (Type::String, Type::StringLiteral)
+        ) {
+            true
+        } else if let (Type::StringLiteral, Type::RawPointer(PointerMut::Const, el_ty)) = (self, r)
+        {
+            **el_ty == Type::U8
+        } else if let (Type::Array(el_ty, _), Type::Slice(target_el_ty)) = (self, r) {
+            el_ty == target_el_ty
+        } else if self.can_be_cast() && r.can_be_cast() {
             match self {
                 Type::U8
                 | Type::U16
@@ -123,7 +188,9 @@ impl Type {
                 | Type::I16
                 | Type::I32
                 | Type::I64
-                | Type::Bool => r.is_number() || r.is_raw_const_pointer(),
+                | Type::USize
+                | Type::ISize => r.is_number() || r.is_raw_const_pointer() || r == Type::Bool,
+                Type::Bool => r.is_number() || r.is_raw_const_pointer(),
                 Type::RawPointer(PointerMut::Mut, _) => {
                     r.is_raw_pointer() || r.is_integral() || r == Type::StringLiteral
                 }
@@ -132,9 +199,15 @@ impl Type {
                 }
                 Type::F64 => r.is_number(),
                 Type::StringLiteral => false,
+                Type::String => false,
                 Type::Null => false,
                 Type::Array(_, _) => false,
+                Type::Slice(_) => false,
+                Type::Range(_) => false,
+                Type::Option(_) => false,
+                Type::Result(..) => false,
                 Type::Unit => false,
+                Type::Never => false,
                 Type::Custom(_) => false,
                 Type::StructDef(_) => false,
                 Type::FunctionDef(_, _) => false,
@@ -155,6 +228,44 @@ impl Type {
         }
     }
 
+    /// Converts every canonical path in this type to the path which a user would
+    /// write to refer to the same type from within `module`.  See
+    /// [`Path::to_source_path`].
+    pub fn to_source_paths(&self, module: &Path) -> Type {
+        let convert = |tys: &Vec<Type>| tys.iter().map(|ty| ty.to_source_paths(module)).collect();
+        match self {
+            Type::Custom(path) => Type::Custom(path.to_source_path(module)),
+            Type::RawPointer(m, ty) => Type::RawPointer(*m, Box::new(ty.to_source_paths(module))),
+            Type::Array(ty, sz) => Type::Array(Box::new(ty.to_source_paths(module)), *sz),
+            Type::Slice(ty) => Type::Slice(Box::new(ty.to_source_paths(module))),
+            Type::Range(ty) => Type::Range(Box::new(ty.to_source_paths(module))),
+            Type::Option(ty) => Type::Option(Box::new(ty.to_source_paths(module))),
+            Type::Result(ty, err_ty) => Type::Result(
+                Box::new(ty.to_source_paths(module)),
+                Box::new(err_ty.to_source_paths(module)),
+            ),
+            Type::Coroutine(ty) => Type::Coroutine(Box::new(ty.to_source_paths(module))),
+            Type::StructDef(fields) => Type::StructDef(
+                fields
+                    .iter()
+                    .map(|(name, ty)| (*name, ty.to_source_paths(module)))
+                    .collect(),
+            ),
+            Type::FunctionDef(params, ret_ty) => {
+                Type::FunctionDef(convert(params), Box::new(ret_ty.to_source_paths(module)))
+            }
+            Type::CoroutineDef(params, ret_ty) => {
+                Type::CoroutineDef(convert(params), Box::new(ret_ty.to_source_paths(module)))
+            }
+            Type::ExternDecl(params, has_varargs, ret_ty) => Type::ExternDecl(
+                convert(params),
+                *has_varargs,
+                Box::new(ret_ty.to_source_paths(module)),
+            ),
+            _ => self.clone(),
+        }
+    }
+
     pub fn get_members(&self) -> Option<&Vec<(StringId, Type)>> {
         match self {
             Type::StructDef(members) => Some(members),
@@ -178,13 +289,21 @@ impl Type {
             | Type::I8
             | Type::I16
             | Type::I32
-            | Type::I64 => true,
+            | Type::I64
+            | Type::USize
+            | Type::ISize => true,
             Type::Null
             | Type::Bool
             | Type::StringLiteral
+            | Type::String
             | Type::RawPointer(..)
             | Type::Array(_, _)
+            | Type::Slice(_)
+            | Type::Range(_)
+            | Type::Option(_)
+            | Type::Result(..)
             | Type::Unit
+            | Type::Never
             | Type::Custom(_)
             | Type::StructDef(_)
             | Type::FunctionDef(_, _)
@@ -204,14 +323,22 @@ impl Type {
             | Type::I8
             | Type::I16
             | Type::I32
-            | Type::I64 => true,
+            | Type::I64
+            | Type::USize
+            | Type::ISize => true,
             Type::Null
             | Type::Bool
             | Type::F64
             | Type::StringLiteral
+            | Type::String
             | Type::RawPointer(..)
             | Type::Array(_, _)
+            | Type::Slice(_)
+            | Type::Range(_)
+            | Type::Option(_)
+            | Type::Result(..)
             | Type::Unit
+            | Type::Never
             | Type::Custom(_)
             | Type::StructDef(_)
             | Type::FunctionDef(_, _)
@@ -234,11 +361,19 @@ impl Type {
             | Type::I16
             | Type::I32
             | Type::I64
+            | Type::USize
+            | Type::ISize
             | Type::Bool
             | Type::StringLiteral
+            | Type::String
             | Type::RawPointer(..)
             | Type::Array(_, _)
+            | Type::Slice(_)
+            | Type::Range(_)
+            | Type::Option(_)
+            | Type::Result(..)
             | Type::Unit
+            | Type::Never
             | Type::Custom(_)
             | Type::StructDef(_)
             | Type::FunctionDef(_, _)
@@ -251,17 +386,26 @@ impl Type {
 
     pub fn is_unsigned_int(&self) -> bool {
         match self {
-            Type::RawPointer(..) | Type::U8 | Type::U16 | Type::U32 | Type::U64 => true,
+            Type::RawPointer(..) | Type::U8 | Type::U16 | Type::U32 | Type::U64 | Type::USize => {
+                true
+            }
             Type::Null
             | Type::I8
             | Type::I16
             | Type::I32
             | Type::I64
+            | Type::ISize
             | Type::F64
             | Type::Bool
             | Type::StringLiteral
+            | Type::String
             | Type::Array(_, _)
+            | Type::Slice(_)
+            | Type::Range(_)
+            | Type::Option(_)
+            | Type::Result(..)
             | Type::Unit
+            | Type::Never
             | Type::Custom(_)
             | Type::StructDef(_)
             | Type::FunctionDef(_, _)
@@ -274,17 +418,24 @@ impl Type {
 
     pub fn is_signed_int(&self) -> bool {
         match self {
-            Type::I8 | Type::I16 | Type::I32 | Type::I64 => true,
+            Type::I8 | Type::I16 | Type::I32 | Type::I64 | Type::ISize => true,
             Type::Null
             | Type::U8
             | Type::U16
             | Type::U32
             | Type::U64
+            | Type::USize
             | Type::F64
             | Type::Bool
             | Type::StringLiteral
+            | Type::String
             | Type::Array(_, _)
+            | Type::Slice(_)
+            | Type::Range(_)
+            | Type::Option(_)
+            | Type::Result(..)
             | Type::Unit
+            | Type::Never
             | Type::Custom(_)
             | Type::StructDef(_)
             | Type::FunctionDef(_, _)
@@ -296,6 +447,11 @@ impl Type {
         }
     }
 
+    /// Returns `true` if this is either a string literal or a `String`.
+    pub fn is_string(&self) -> bool {
+        matches!(self, Type::StringLiteral | Type::String)
+    }
+
     pub fn is_raw_pointer(&self) -> bool {
         match self {
             Type::RawPointer(..) => true,
@@ -310,6 +466,9 @@ impl Type {
         }
     }
 
+    /// Returns the number of bits in a value of this type.  The width of
+    /// [`Type::USize`] and [`Type::ISize`] depends upon the target machine, so they,
+    /// like aggregate types, have a width of `0`.
     pub fn bit_width(&self) -> u8 {
         match self {
             Type::Null => 64,
@@ -321,12 +480,20 @@ impl Type {
             Type::I16 => 16,
             Type::I32 => 32,
             Type::I64 => 64,
+            Type::USize => 0,
+            Type::ISize => 0,
             Type::F64 => 64,
             Type::Bool => 8,
             Type::StringLiteral => 0,
+            Type::String => 0,
             Type::RawPointer(_, _) => 64,
             Type::Array(_, _) => 0,
+            Type::Slice(_) => 0,
+            Type::Range(_) => 0,
+            Type::Option(_) => 0,
+            Type::Result(..) => 0,
             Type::Unit => 0,
+            Type::Never => 0,
             Type::Custom(_) => 0,
             Type::StructDef(_) => 0,
             Type::FunctionDef(_, _) => 0,
@@ -339,17 +506,24 @@ impl Type {
 
     pub fn is_signed(&self) -> bool {
         match self {
-            Type::I8 | Type::I16 | Type::I32 | Type::I64 | Type::F64 => true,
+            Type::I8 | Type::I16 | Type::I32 | Type::I64 | Type::ISize | Type::F64 => true,
             Type::Null
             | Type::U8
             | Type::U16
             | Type::U32
             | Type::U64
+            | Type::USize
             | Type::Bool
             | Type::StringLiteral
+            | Type::String
             | Type::RawPointer(_, _)
             | Type::Array(_, _)
+            | Type::Slice(_)
+            | Type::Range(_)
+            | Type::Option(_)
+            | Type::Result(..)
             | Type::Unit
+            | Type::Never
             | Type::Custom(_)
             | Type::StructDef(_)
             | Type::FunctionDef(_, _)
@@ -379,6 +553,12 @@ impl CompilerDisplay for Type {
             Type::Custom(path) => path.fmt(sm, st),
             Type::Coroutine(ty) => Ok(format!("co<{}>", ty.fmt(sm, st)?)),
             Type::Array(ty, sz) => Ok(format!("[{}; {}]", ty.fmt(sm, st)?, sz)),
+            Type::Slice(ty) => Ok(format!("[{}]", ty.fmt(sm, st)?)),
+            Type::Range(ty) => Ok(format!("Range<{}>", ty.fmt(sm, st)?)),
+            Type::Option(ty) => Ok(format!("opt {}", ty.fmt(sm, st)?)),
+            Type::Result(ty, err_ty) => {
+                Ok(format!("res {}, {}", ty.fmt(sm, st)?, err_ty.fmt(sm, st)?))
+            }
             Type::RawPointer(m, ty) => Ok(format!("*{} {}", m, ty.fmt(sm, st)?)),
             Type::ExternDecl(params, has_varargs, ret_ty) => {
                 let mut params = params
@@ -389,7 +569,7 @@ impl CompilerDisplay for Type {
                 if *has_varargs {
                     params += ", ...";
                 }
-                Ok(format!("extern fn ({}) -> {}", params, ret_ty))
+                Ok(format!("extern fn ({}) -> {}", params, ret_ty.fmt(sm, st)?))
             }
             Type::StructDef(fields) => {
                 let fields = fields
@@ -410,7 +590,7 @@ impl CompilerDisplay for Type {
                     .collect::<Result<Vec<String>, _>>()?
                     .join(",");
 
-                Ok(format!("fn ({}) -> {}", params, ret_ty))
+                Ok(format!("fn ({}) -> {}", params, ret_ty.fmt(sm, st)?))
             }
             Type::CoroutineDef(params, ret_ty) => {
                 let params = params
@@ -419,7 +599,7 @@ impl CompilerDisplay for Type {
                     .collect::<Result<Vec<String>, _>>()?
                     .join(",");
 
-                Ok(format!("co ({}) -> {}", params, ret_ty))
+                Ok(format!("co ({}) -> {}", params, ret_ty.fmt(sm, st)?))
             }
             _ => Ok(format!("{}", self)),
         }
@@ -439,9 +619,12 @@ impl std::fmt::Display for Type {
             I16 => f.write_str("i16"),
             I32 => f.write_str("i32"),
             I64 => f.write_str("i64"),
+            USize => f.write_str("usize"),
+            ISize => f.write_str("isize"),
             F64 => f.write_str("f64"),
             Bool => f.write_str("bool"),
             StringLiteral => f.write_str("string"),
+            String => f.write_str("String"),
             RawPointer(mutability, ty) => {
                 if *mutability == PointerMut::Mut {
                     f.write_str(&format!("*mut {}", ty))
@@ -450,13 +633,18 @@ impl std::fmt::Display for Type {
                 }
             }
             Array(ty, len) => f.write_str(&format!("[{}; {}]", ty, len)),
+            Slice(ty) => f.write_str(&format!("[{}]", ty)),
+            Range(ty) => f.write_str(&format!("Range<{}>", ty)),
+            Option(ty) => f.write_str(&format!("opt {}", ty)),
+            Result(ty, err_ty) => f.write_str(&format!("res {}, {}", ty, err_ty)),
             Unit => f.write_str("unit"),
+            Never => f.write_str("never"),
             Custom(path) => f.write_str(&format!("{}", path)),
             StructDef(members) => {
                 let members = members
                     .iter()
                     .map(|m| format!("{}: {}", m.0, m.1))
-                    .collect::<Vec<String>>()
+                    .collect::<Vec<_>>()
                     .join(",");
                 f.write_fmt(format_args!("StructDef({})", &members))
             }
@@ -464,7 +652,7 @@ impl std::fmt::Display for Type {
                 let params = params
                     .iter()
                     .map(|p| format!("{}", p))
-                    .collect::<Vec<String>>()
+                    .collect::<Vec<_>>()
                     .join(",");
                 f.write_fmt(format_args!("co ({}) -> {}", params, ret_ty))
             }
@@ -473,7 +661,7 @@ impl std::fmt::Display for Type {
                 let params = params
                     .iter()
                     .map(|p| format!("{}", p))
-                    .collect::<Vec<String>>()
+                    .collect::<Vec<_>>()
                     .join(",");
                 f.write_fmt(format_args!("fn ({}) -> {}", params, ret_ty))
             }
@@ -481,7 +669,7 @@ impl std::fmt::Display for Type {
                 let mut params = params
                     .iter()
                     .map(|p| format!("{}", p))
-                    .collect::<Vec<String>>()
+                    .collect::<Vec<_>>()
                     .join(",");
                 if *has_varargs {
                     params += ", ...";
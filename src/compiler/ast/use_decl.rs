@@ -0,0 +1,47 @@
+use crate::{
+    compiler::{source::SourceIr, Span},
+    StringId,
+};
+use serde::{Deserialize, Serialize};
+
+use super::path::Path;
+
+/// A `use` declaration, which allows the item or module at the end of a path to be
+/// referred to by its name alone within the module which contains the declaration.
+/// For example, after `use root::std::io::write;`, `write("x")` calls
+/// `root::std::io::write`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Use {
+    span: Span,
+    path: Path,
+}
+
+impl SourceIr for Use {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl Use {
+    pub fn new(span: Span, path: Path) -> Use {
+        Use { span, path }
+    }
+
+    /// Returns the path which this declaration makes available
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the name which the path can be referred to by
+    pub fn get_name(&self) -> StringId {
+        self.path
+            .item()
+            .expect("The path of a use declaration must end with a name")
+    }
+}
+
+impl std::fmt::Display for Use {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("use {}", self.path))
+    }
+}
@@ -39,6 +39,32 @@ pub struct CompilerError<IE: CompilerDisplay> {
 
     /// An inner error value that contains more specific error information
     inner: IE,
+
+    /// Secondary information which helps explain the error
+    notes: Vec<Note>,
+}
+
+/// Additional information attached to a [`CompilerError`].  A note either points to
+/// another location in the source code which is related to the error (e.g. the
+/// previous definition of a name which has been defined twice) or, if it has no
+/// span, gives the user a hint on how to fix the error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Note {
+    /// The span of source code that this note refers to
+    span: Option<Span>,
+
+    /// The message describing how this note relates to the error
+    message: String,
+}
+
+impl Note {
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
 }
 
 impl<IE> CompilerError<IE>
@@ -46,7 +72,30 @@ where
     IE: CompilerDisplay,
 {
     pub fn new(span: Span, inner: IE) -> Self {
-        CompilerError { span, inner }
+        CompilerError {
+            span,
+            inner,
+            notes: vec![],
+        }
+    }
+
+    /// Adds a note which points to another location in the source code that is
+    /// related to this error.
+    pub fn with_note(mut self, span: Span, message: &str) -> Self {
+        self.notes.push(Note {
+            span: Some(span),
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Adds a hint which suggests how the user could fix this error.
+    pub fn with_hint(mut self, message: &str) -> Self {
+        self.notes.push(Note {
+            span: None,
+            message: message.into(),
+        });
+        self
     }
 
     /// Moves the Line number and Inner error out of the wrapping [CompilerError].
@@ -57,11 +106,31 @@ where
         (self.span, self.inner)
     }
 
+    /// Replaces the inner error with the result of applying `f` to it, keeping the
+    /// span and notes of this error.
+    pub fn map_inner<IE2: CompilerDisplay, F: FnOnce(IE) -> IE2>(self, f: F) -> CompilerError<IE2> {
+        CompilerError {
+            span: self.span,
+            inner: f(self.inner),
+            notes: self.notes,
+        }
+    }
+
     /// Get the [`Span`] that caused this error
     pub fn span(&self) -> Span {
         self.span
     }
 
+    /// Get the inner error
+    pub fn inner(&self) -> &IE {
+        &self.inner
+    }
+
+    /// Get the notes which have been added to this error
+    pub fn notes(&self) -> &[Note] {
+        &self.notes
+    }
+
     pub fn to_compilerdisplay(&self) -> &dyn CompilerDisplay {
         self
     }
@@ -83,24 +152,42 @@ where
     ///
     /// If the span covers only one file, then format as "{Lines}"
     /// If the span covers multiple files, format as "{File}:{Lines}"
+    ///
+    /// Each note is formatted on its own indented line following the error.
     fn fmt(&self, sm: &SourceMap, st: &StringTable) -> Result<String, CompilerDisplayError> {
         let inner = self.inner.fmt(sm, st)?;
 
-        let lines_by_file = sm.lines_in_span(self.span).into_iter().map(|(f, lines)| {
-            let line = format_line_set(&lines).expect("Span covers no indexed source code");
-            (f, line)
-        });
+        let mut formatted = format!("{}: {}", format_span(sm, self.span), inner);
+        for note in &self.notes {
+            match note.span {
+                Some(span) => formatted.push_str(&format!(
+                    "\n    {}: note: {}",
+                    format_span(sm, span),
+                    note.message
+                )),
+                None => formatted.push_str(&format!("\n    hint: {}", note.message)),
+            }
+        }
 
-        let formatted_span = if lines_by_file.len() == 1 {
-            lines_by_file.map(|(_, lines)| lines).collect()
-        } else {
-            lines_by_file
-                .map(|(f, lines)| format!("{:?}:{}", f, lines))
-                .collect::<Vec<_>>()
-                .join("; ")
-        };
+        Ok(formatted)
+    }
+}
 
-        Ok(format!("{}: {}", formatted_span, inner))
+/// Formats the lines that a span covers. If the span covers multiple files, then
+/// the lines in each file are prefixed by the file name.
+pub(crate) fn format_span(sm: &SourceMap, span: Span) -> String {
+    let lines_by_file = sm.lines_in_span(span).into_iter().map(|(f, lines)| {
+        let line = format_line_set(&lines).expect("Span covers no indexed source code");
+        (f, line)
+    });
+
+    if lines_by_file.len() == 1 {
+        lines_by_file.map(|(_, lines)| lines).collect()
+    } else {
+        lines_by_file
+            .map(|(f, lines)| format!("{:?}:{}", f, lines))
+            .collect::<Vec<_>>()
+            .join("; ")
     }
 }
 
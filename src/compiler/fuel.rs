@@ -0,0 +1,190 @@
+//! Limits on how much work the compiler will do for a single compilation.
+//!
+//! A service which compiles untrusted source code (e.g. a playground) cannot let a
+//! pathological program hang the compiler.  [`Fuel`] is shared by every stage of a
+//! compilation, and each stage burns units of fuel for the work that it does:
+//! - The parser burns a unit for every expression and statement that it tries to parse,
+//! including the attempts which it backtracks out of and the expansions of macros.
+//! - The type resolver burns a unit for every expression that it analyzes.
+//! - Code generation does work in proportion to the size of the program, so it burns a
+//! unit for every node of the program before it starts.
+//!
+//! Once the fuel runs out, or the time limit passes, every further burn fails and the
+//! stage stops with a resource exhausted diagnostic.
+
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+/// The clock is only read once every this many burns, so that checking a time limit
+/// does not slow down the compiler.
+const CLOCK_INTERVAL: u64 = 1024;
+
+/// The budget of work that a compilation may do.
+#[derive(Debug)]
+pub struct Fuel {
+    /// The number of units of fuel which the compilation started with, if it is limited
+    limit: Option<u64>,
+
+    /// The number of units of fuel which have been burned
+    burned: Cell<u64>,
+
+    /// How long the compilation may run for and when that time runs out
+    timeout: Option<(Duration, Instant)>,
+
+    /// Set once the fuel or the time runs out, so every later burn fails the same way
+    exhausted: Cell<Option<FuelError>>,
+}
+
+/// Why a compilation was stopped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FuelError {
+    /// Every one of the given units of fuel was burned
+    OutOfFuel(u64),
+
+    /// The compilation ran for longer than the given time
+    Timeout(Duration),
+}
+
+impl std::fmt::Display for FuelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FuelError::OutOfFuel(limit) => write!(f, "it used all {} units of fuel", limit),
+            FuelError::Timeout(timeout) => {
+                write!(f, "it ran for longer than {}s", timeout.as_secs_f64())
+            }
+        }
+    }
+}
+
+impl Fuel {
+    /// Creates fuel which never runs out.
+    pub fn unlimited() -> Fuel {
+        Fuel::new(None, None)
+    }
+
+    /// Creates fuel with the given number of units, if any, which runs out once the
+    /// given time, if any, has passed from now.
+    pub fn new(limit: Option<u64>, timeout: Option<Duration>) -> Fuel {
+        Fuel {
+            limit,
+            burned: Cell::new(0),
+            timeout: timeout.map(|t| (t, Instant::now() + t)),
+            exhausted: Cell::new(None),
+        }
+    }
+
+    /// Burns the given number of units of fuel.  Returns an error if there was not enough
+    /// fuel left or if the time limit has passed.
+    pub fn burn(&self, units: u64) -> Result<(), FuelError> {
+        if let Some(err) = self.exhausted.get() {
+            return Err(err);
+        }
+
+        let before = self.burned.get();
+        let burned = before.saturating_add(units);
+        self.burned.set(burned);
+
+        let err = match (self.limit, self.timeout) {
+            (Some(limit), _) if burned > limit => Some(FuelError::OutOfFuel(limit)),
+            // Only read the clock when the count crosses a multiple of the interval
+            (_, Some((timeout, deadline)))
+                if before / CLOCK_INTERVAL != burned / CLOCK_INTERVAL
+                    && Instant::now() >= deadline =>
+            {
+                Some(FuelError::Timeout(timeout))
+            }
+            _ => None,
+        };
+
+        match err {
+            Some(err) => {
+                self.exhausted.set(Some(err));
+                Err(err)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Returns an error if the time limit has passed.  This reads the clock, so it is
+    /// used between the stages of a compilation rather than within them.
+    pub fn check_time(&self) -> Result<(), FuelError> {
+        if let Some(err) = self.exhausted.get() {
+            return Err(err);
+        }
+
+        match self.timeout {
+            Some((timeout, deadline)) if Instant::now() >= deadline => {
+                let err = FuelError::Timeout(timeout);
+                self.exhausted.set(Some(err));
+                Err(err)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns why the compilation was stopped, if it ran out of fuel or time.
+    pub fn exhausted(&self) -> Option<FuelError> {
+        self.exhausted.get()
+    }
+
+    /// Returns the number of units of fuel which have been burned.
+    pub fn burned(&self) -> u64 {
+        self.burned.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Fuel, FuelError};
+
+    #[test]
+    fn unlimited() {
+        let fuel = Fuel::unlimited();
+        for _ in 0..10_000 {
+            fuel.burn(1).unwrap();
+        }
+        assert_eq!(fuel.burned(), 10_000);
+        assert_eq!(fuel.exhausted(), None);
+    }
+
+    #[test]
+    fn out_of_fuel() {
+        let fuel = Fuel::new(Some(10), None);
+        fuel.burn(4).unwrap();
+        fuel.burn(6).unwrap();
+        assert_eq!(fuel.burn(1), Err(FuelError::OutOfFuel(10)));
+
+        // Once exhausted, every burn fails
+        assert_eq!(fuel.burn(0), Err(FuelError::OutOfFuel(10)));
+        assert_eq!(fuel.exhausted(), Some(FuelError::OutOfFuel(10)));
+    }
+
+    #[test]
+    fn timeout() {
+        let fuel = Fuel::new(None, Some(Duration::from_secs(0)));
+        assert_eq!(
+            fuel.check_time(),
+            Err(FuelError::Timeout(Duration::from_secs(0)))
+        );
+
+        let fuel = Fuel::new(None, Some(Duration::from_secs(0)));
+        let result = (0..super::CLOCK_INTERVAL).try_for_each(|_| fuel.burn(1));
+        assert_eq!(result, Err(FuelError::Timeout(Duration::from_secs(0))));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            FuelError::OutOfFuel(500).to_string(),
+            "it used all 500 units of fuel"
+        );
+        assert_eq!(
+            FuelError::Timeout(Duration::from_millis(1500)).to_string(),
+            "it ran for longer than 1.5s"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Localizes internal compiler errors (ICEs) which happen during code generation.
+//!
+//! The code generators assume that their input has been fully validated, so any
+//! unexpected state is a bug in the compiler and is reported by panicking.  On its own,
+//! a panic gives no indication of which part of the user's program was being compiled.
+//! An [`IceContext`] records the item and the nodes within it that code is currently
+//! being generated for, so that a panic can be caught and reported as a
+//! [`CompilerError`] that points at the node which triggered it.
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    panic::{catch_unwind, AssertUnwindSafe},
+    rc::Rc,
+};
+
+use crate::StringTable;
+
+use super::{ast::Path, CompilerDisplay, CompilerDisplayError, CompilerError, SourceMap, Span};
+
+/// A bug in the compiler which was found while generating code for an item.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InternalCompilerError {
+    /// The canonical path of the item which was being compiled
+    path: Path,
+
+    /// The message that the compiler panicked with
+    message: String,
+}
+
+impl InternalCompilerError {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl CompilerDisplay for InternalCompilerError {
+    fn fmt(&self, sm: &SourceMap, st: &StringTable) -> Result<String, CompilerDisplayError> {
+        Ok(format!(
+            "Internal compiler error while generating code for {}: {}",
+            self.path.fmt(sm, st)?,
+            self.message
+        ))
+    }
+}
+
+/// Tracks the spans of the nodes which code is currently being generated for.  This
+/// is shared, like the [`EventStack`](super::diagnostics::EventStack), so that it can be
+/// updated from anywhere within a code generator.
+#[derive(Clone, Debug, Default)]
+pub struct IceContext(Rc<RefCell<Vec<Span>>>);
+
+impl IceContext {
+    pub fn new() -> IceContext {
+        IceContext::default()
+    }
+
+    /// Marks that code is being generated for the node with the given span until the
+    /// returned guard is dropped.
+    pub fn enter(&self, span: Span) -> NodeGuard {
+        self.0.borrow_mut().push(span);
+        NodeGuard(self.clone())
+    }
+
+    /// Runs `f`, which generates the code for the item at `path`.  If `f` panics, then
+    /// the panic is caught and returned as an [`InternalCompilerError`] located at the
+    /// innermost node that was being compiled when the panic happened.
+    pub fn catch<T, F: FnOnce() -> T>(
+        &self,
+        path: &Path,
+        span: Span,
+        f: F,
+    ) -> Result<T, CompilerError<InternalCompilerError>> {
+        self.0.borrow_mut().clear();
+        let result = catch_unwind(AssertUnwindSafe(f));
+        let node = self.0.borrow_mut().drain(..).last();
+
+        result.map_err(|payload| {
+            let ice = InternalCompilerError {
+                path: path.clone(),
+                message: panic_message(payload),
+            };
+            match node {
+                Some(node) if node != span => CompilerError::new(node, ice)
+                    .with_note(span, "while generating code for this item"),
+                _ => CompilerError::new(span, ice),
+            }
+        })
+    }
+}
+
+/// Removes a node from its [`IceContext`] when code generation for the node finishes.
+/// If the node is dropped because of a panic, then it is left in the context so that
+/// the panic can be located.
+pub struct NodeGuard(IceContext);
+
+impl Drop for NodeGuard {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            (self.0).0.borrow_mut().pop();
+        }
+    }
+}
+
+/// Panics carry either a `&str` or a `String`, depending upon whether the message was
+/// formatted.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(msg) => *msg,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(msg) => msg.to_string(),
+            Err(_) => "unknown panic".into(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::{ast::Element, source::Offset, Span};
+
+    use super::*;
+
+    fn span(low: u32, high: u32) -> Span {
+        Span::new(Offset::new(low), Offset::new(high))
+    }
+
+    #[test]
+    fn no_panic() {
+        let ctx = IceContext::new();
+        let path: Path = vec![Element::CanonicalRoot].into();
+        let result = ctx.catch(&path, span(0, 10), || {
+            let _node = ctx.enter(span(2, 4));
+            5
+        });
+        assert_eq!(result, Ok(5));
+    }
+
+    #[test]
+    fn panic_is_located_at_innermost_node() {
+        let ctx = IceContext::new();
+        let path: Path = vec![Element::CanonicalRoot].into();
+        let result = ctx.catch(&path, span(0, 10), || {
+            let _outer = ctx.enter(span(1, 8));
+            {
+                let _done = ctx.enter(span(1, 2));
+            }
+            let _inner = ctx.enter(span(3, 5));
+            panic!("bad {}", "state")
+        });
+
+        let err = result.unwrap_err();
+        assert_eq!(err.span(), span(3, 5));
+        assert_eq!(err.inner().message(), "bad state");
+        assert_eq!(err.notes()[0].span(), Some(span(0, 10)));
+    }
+
+    #[test]
+    fn panic_outside_of_any_node() {
+        let ctx = IceContext::new();
+        let path: Path = vec![Element::CanonicalRoot].into();
+        let result: Result<(), _> = ctx.catch(&path, span(0, 10), || panic!("bad state"));
+
+        let err = result.unwrap_err();
+        assert_eq!(err.span(), span(0, 10));
+        assert!(err.notes().is_empty());
+    }
+}
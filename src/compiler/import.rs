@@ -57,11 +57,26 @@ pub struct ImportStructDef {
 
     /// The field list of this structure
     fields: Vec<(StringId, Type)>,
+
+    /// The fields which are not `pub`, and so cannot be used by the importing project
+    private_fields: Vec<StringId>,
 }
 
 impl ImportStructDef {
     pub fn new(path: Path, fields: Vec<(StringId, Type)>) -> ImportStructDef {
-        ImportStructDef { path, fields }
+        ImportStructDef {
+            path,
+            fields,
+            private_fields: vec![],
+        }
+    }
+
+    /// Marks the given fields of this structure as not `pub`
+    pub fn with_private_fields(self, private_fields: Vec<StringId>) -> ImportStructDef {
+        ImportStructDef {
+            private_fields,
+            ..self
+        }
     }
 
     /// The canonical path of this structure within it's host module
@@ -73,4 +88,9 @@ impl ImportStructDef {
     pub fn fields(&self) -> &[(StringId, Type)] {
         &self.fields
     }
+
+    /// The fields of this structure which are not `pub`
+    pub fn private_fields(&self) -> &[StringId] {
+        &self.private_fields
+    }
 }
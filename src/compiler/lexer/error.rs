@@ -1,7 +1,7 @@
 use std::num::{ParseFloatError, ParseIntError};
 
 use crate::compiler::{
-    source::SourceChar, CompilerDisplay, CompilerDisplayError, SourceError, SourceMap,
+    messages, source::SourceChar, CompilerDisplay, CompilerDisplayError, SourceError, SourceMap,
 };
 
 use super::tokens::Primitive;
@@ -13,32 +13,62 @@ pub enum LexerError {
     Locked(Option<SourceChar>),
     InvalidEscapeSequence(SourceChar),
     ExpectedEscapeCharacter,
+    NulInCString,
     InvalidNumber,
     InvalidSuffixOnFloat,
-    ParseIntError(Primitive, ParseIntError),
-    ParseFloatError(Primitive, ParseFloatError),
+    MisplacedUnderscore,
+    ExpectedLabelName,
+    ParseIntError(String, Primitive, ParseIntError),
+    ParseFloatError(String, Primitive, ParseFloatError),
     UnexpectedSuffixType(Primitive),
     SourceError, // TODO: make this more descriptive
 }
 
+impl LexerError {
+    /// Returns the code which identifies this error and its message in the
+    /// [message catalog](crate::compiler::messages).
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexerError::UnexpectedEof => "L0001",
+            LexerError::Locked(..) => "L0002",
+            LexerError::InvalidEscapeSequence(..) => "L0003",
+            LexerError::ExpectedEscapeCharacter => "L0004",
+            LexerError::NulInCString => "L0005",
+            LexerError::InvalidNumber => "L0006",
+            LexerError::InvalidSuffixOnFloat => "L0007",
+            LexerError::MisplacedUnderscore => "L0008",
+            LexerError::ExpectedLabelName => "L0009",
+            LexerError::ParseIntError(..) => "L0010",
+            LexerError::ParseFloatError(..) => "L0011",
+            LexerError::UnexpectedSuffixType(..) => "L0012",
+            LexerError::SourceError => "L0013",
+        }
+    }
+}
+
 impl CompilerDisplay for LexerError {
     fn fmt(&self, sm: &SourceMap, st: &crate::StringTable) -> Result<String, CompilerDisplayError> {
         use LexerError::*;
-        let msg = match self {
-            Locked(None) => "Lexer locked on EOF".into(),
-            Locked(Some(c)) => format!("Lexer locked on {}", c),
-            InvalidEscapeSequence(c) => format!("Invalid escape sequence \\{}", c),
-            ExpectedEscapeCharacter => "Expected an escape character after \\".into(),
-            InvalidNumber => "Invalid number".into(),
-            UnexpectedSuffixType(ref prim) => format!("Invalid type suffix: {}", prim.fmt(sm, st)?),
-            SourceError => "Error reading characters from source code".into(),
-            UnexpectedEof => "Unexpected EOF".into(),
-            InvalidSuffixOnFloat => "Invalid suffix after float literal.".into(),
-            ParseIntError(p, e) => format!("{} of {}", e, p),
-            ParseFloatError(p, e) => format!("{} of {}", e, p),
+        let args = match self {
+            // A lexer which is locked on a character has a different message than one
+            // which is locked on the end of the source code
+            Locked(Some(c)) => return Ok(messages::message("L0002.char", &[c.to_string()])),
+            InvalidEscapeSequence(c) => vec![c.to_string()],
+            UnexpectedSuffixType(ref prim) => vec![prim.fmt(sm, st)?],
+            ParseIntError(lit, p, e) => vec![p.to_string(), lit.clone(), e.to_string()],
+            ParseFloatError(lit, p, e) => vec![p.to_string(), lit.clone(), e.to_string()],
+            Locked(None)
+            | ExpectedEscapeCharacter
+            | NulInCString
+            | InvalidNumber
+            | SourceError
+            | UnexpectedEof
+            | InvalidSuffixOnFloat
+            | MisplacedUnderscore
+            | ExpectedLabelName => vec![],
         };
 
-        Ok(msg)
+        Ok(messages::message(self.code(), &args))
     }
 }
 
@@ -13,6 +13,68 @@ use super::{
 };
 use Lex::*;
 
+/// The character which is used as a byte order mark at the start of a file
+const BYTE_ORDER_MARK: char = '\u{feff}';
+
+/// The words which are reserved by the language.
+///
+/// Ordering of these keywords matters: if one keyword is a prefix to another keyword then the
+/// longer keyword must be placed first; otherwise the shorter keyword will incorrectly match.
+pub(crate) const KEYWORDS: &[&str] = &[
+    "let",
+    "mut",
+    "return",
+    "break",
+    "continue",
+    "yield",
+    "yret",
+    "fn",
+    "const",
+    "co",
+    "mod",
+    "use",
+    "struct",
+    "pub",
+    "impl",
+    "extern",
+    "macro",
+    "static_assert",
+    "init",
+    "if",
+    "else",
+    "while",
+    "for",
+    "in",
+    "defer",
+    "self",
+    "super",
+    "root",
+    "project",
+    "size_of",
+    "fields_of",
+    "name_of",
+    "len",
+    "Range",
+    "opt",
+    "some",
+    "none",
+    "res",
+    "ok",
+    "err",
+    "wrapping_add",
+    "wrapping_sub",
+    "wrapping_mul",
+    "saturating_add",
+    "saturating_sub",
+    "to_le_bytes",
+    "to_be_bytes",
+    "from_le_bytes",
+    "from_be_bytes",
+    "null",
+    "as",
+    "_",
+];
+
 struct LexerBranch<'a, 'st> {
     lexer: &'a mut Lexer<'st>,
     index: usize,
@@ -67,11 +129,11 @@ impl<'a, 'st> LexerBranch<'a, 'st> {
     }
 
     /// Starting from the current position, this will move
-    /// the cursor next until it encounters a non-digit
-    /// character
+    /// the cursor next until it encounters a character which
+    /// is not a digit or an underscore
     fn consume_digit_string(&mut self) {
         while let Some(c) = self.peek() {
-            if !c.is_digit() {
+            if !c.is_digit() && c != '_' {
                 break;
             }
             self.next();
@@ -192,9 +254,18 @@ impl<'a> Lexer<'a> {
         logger: &'a Logger,
     ) -> Result<Lexer<'a>, LexerError> {
         let end_offset = text.high();
+
+        // Some editors start a file with a byte order mark, which is not part of the
+        // source code
+        let index = if text.len() > 0 && text[0].char() == BYTE_ORDER_MARK {
+            1
+        } else {
+            0
+        };
+
         Ok(Lexer {
             chars: text,
-            index: 0,
+            index,
             end_offset,
             string_table,
             logger,
@@ -255,6 +326,7 @@ impl<'a> Lexer<'a> {
             .or_else(|| self.consume_keyword().transpose())
             .or_else(|| self.consume_boolean().transpose())
             .or_else(|| self.consume_literal().transpose())
+            .or_else(|| self.consume_label().transpose())
             .or_else(|| self.consume_identifier().transpose())
             .or_else(|| self.consume_operator().transpose())
             .transpose()
@@ -264,7 +336,8 @@ impl<'a> Lexer<'a> {
         let mut branch = LexerBranch::from(self);
         if branch.next_if_word("//") {
             while let Some(c) = branch.next() {
-                if c == '\n' {
+                // A line can also end with a lone `\r`
+                if c == '\n' || c == '\r' {
                     break;
                 }
             }
@@ -302,9 +375,14 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Parses a string literal.  A string literal prefixed with `c` (e.g. `c"hello"`) is a
+    /// C string literal, which has type `*const u8` so that it can be given directly to
+    /// extern functions.  String literals are always stored with a NUL terminator, so a
+    /// C string literal may not contain a `\0`, which would truncate it.
     fn consume_string_literal(&mut self) -> LexerResult<Option<Token>> {
         let mut branch = LexerBranch::from(self);
         let mut closed = false;
+        let is_cstr = branch.peek_ifn("c\"") && branch.next_if('c');
         if branch.next_if('"') {
             while let Some(c) = branch.next() {
                 if c == '"' {
@@ -315,6 +393,16 @@ impl<'a> Lexer<'a> {
                 // Parse escape sequence
                 if c == '\\' {
                     match branch.next() {
+                        Some(c) if is_cstr && c == '0' => {
+                            return err!(
+                                self.span_from_index_to_char(c).unwrap(),
+                                LexerError::NulInCString
+                            )
+                            .map_err(|err| {
+                                self.record(err.span(), Err(&err));
+                                err
+                            });
+                        }
                         Some(c) if Self::is_escape_code(c) => (),
                         Some(c) => {
                             return err!(
@@ -341,13 +429,20 @@ impl<'a> Lexer<'a> {
             }
             let (s, span) = branch.merge().unwrap();
             if closed {
-                // Remove the quotes from the string
+                // Remove the prefix and quotes from the string
                 let mut s: String = self.string_table.get(s).unwrap();
+                if is_cstr {
+                    s.remove(0);
+                }
                 s.remove(0);
                 s.pop();
                 let id = self.string_table.insert(s);
 
-                Ok(Some(Token::new(Lex::StringLiteral(id), span)))
+                if is_cstr {
+                    Ok(Some(Token::new(Lex::CStringLiteral(id), span)))
+                } else {
+                    Ok(Some(Token::new(Lex::StringLiteral(id), span)))
+                }
             } else {
                 err!(span, LexerError::UnexpectedEof)
             }
@@ -372,9 +467,10 @@ impl<'a> Lexer<'a> {
         // read until a non-digit is hit
         branch.consume_digit_string();
 
-        // Check if number is a floating point number
+        // Check if number is a floating point number.  A `..` after the digits is a
+        // range (e.g. `0..10`) and not a decimal point.
         let mut is_float = false;
-        if branch.next_if('.') {
+        if !branch.peek_ifn("..") && branch.next_if('.') {
             is_float = true;
             branch.consume_digit_string();
         }
@@ -408,10 +504,13 @@ impl<'a> Lexer<'a> {
         // reached the end of the token); otherwise this is not a valid integer literal and an
         // error should be thrown.
         if branch.peek().map(Self::is_delimiter).unwrap_or(true) {
-            let (_, span) = branch.merge().unwrap();
+            let (literal, span) = branch.merge().unwrap();
+            let literal = self.string_table.get(literal).unwrap();
             let int_text = self.string_table.get(int_token).unwrap();
 
-            Self::create_number_literal(span, &int_text, type_suffix)
+            Self::check_number_underscores(span, &int_text).and_then(|_| {
+                Self::create_number_literal(span, &literal, &int_text.replace('_', ""), type_suffix)
+            })
         } else {
             let span = self.current_char_span().unwrap();
             err!(
@@ -459,7 +558,9 @@ impl<'a> Lexer<'a> {
         let mut branch = LexerBranch::from(self);
         let mut operators = vec![
             ("...", VarArgs),
+            ("..", Range),
             ("->", LArrow),
+            ("=>", FatArrow),
             ("&&", BAnd),
             ("||", BOr),
             ("::", PathSeparator),
@@ -487,6 +588,10 @@ impl<'a> Lexer<'a> {
             ("!", Not),
             ("@", At),
             ("^", Hat),
+            ("?", Question),
+            ("#", Hash),
+            ("$", Dollar),
+            ("=", Equals),
         ];
         operators.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
 
@@ -538,6 +643,46 @@ impl<'a> Lexer<'a> {
         })
     }
 
+    /// Parses the label of a loop, which is a `'` followed by an identifier (e.g. `'outer`).
+    fn consume_label(&mut self) -> LexerResult<Option<Token>> {
+        let mut branch = LexerBranch::from(self);
+        if !branch.next_if('\'') {
+            return Ok(None);
+        }
+
+        if !branch
+            .peek()
+            .map_or(false, |c| c.is_alphabetic() || c == '_')
+        {
+            return err!(
+                self.current_char_span().unwrap(),
+                LexerError::ExpectedLabelName
+            )
+            .map_err(|err| {
+                self.record(err.span(), Err(&err));
+                err
+            });
+        }
+
+        while branch
+            .peek()
+            .map_or(false, |c| c.is_alphanumeric() || c == '_')
+        {
+            branch.next();
+        }
+
+        match branch.merge() {
+            None => Ok(None),
+            Some((id, span)) => Ok(Some(Token::new(Lex::Label(id), span))),
+        }
+        .map(|ok| {
+            ok.map(|token| {
+                self.record(token.span, Ok("Label"));
+                token
+            })
+        })
+    }
+
     fn consume_boolean(&mut self) -> LexerResult<Option<Token>> {
         let mut branch = LexerBranch::from(self);
 
@@ -565,17 +710,10 @@ impl<'a> Lexer<'a> {
     }
 
     fn consume_keyword(&mut self) -> LexerResult<Option<Token>> {
+        let string_table = self.string_table;
         let mut branch = LexerBranch::from(self);
 
-        // Ordering of these keywords matters: if one keyword is a prefix to another keyword then the
-        // longer keyword must be placed first; otherwise the shorter keyword will incorrectly match.
-        let keywords = [
-            "let", "mut", "return", "yield", "yret", "fn", "const", "co", "mod", "struct",
-            "extern", "init", "if", "else", "while", "self", "super", "root", "project", "size_of",
-            "null", "as",
-        ];
-
-        Ok(match branch.next_if_one_of(&keywords) {
+        Ok(match branch.next_if_one_of(KEYWORDS) {
             Some(w) if branch.peek().map(Self::is_delimiter).unwrap_or(true) => {
                 let (_, span) = branch.merge().unwrap();
 
@@ -583,25 +721,55 @@ impl<'a> Lexer<'a> {
                     "let" => Token::new(Let, span),
                     "mut" => Token::new(Mut, span),
                     "return" => Token::new(Return, span),
+                    "break" => Token::new(Break, span),
+                    "continue" => Token::new(Continue, span),
                     "yield" => Token::new(Yield, span),
                     "yret" => Token::new(YieldReturn, span),
                     "fn" => Token::new(FunctionDef, span),
                     "co" => Token::new(CoroutineDef, span),
                     "mod" => Token::new(ModuleDef, span),
+                    "use" => Token::new(Use, span),
                     "struct" => Token::new(Struct, span),
+                    "pub" => Token::new(Pub, span),
+                    "impl" => Token::new(Impl, span),
                     "extern" => Token::new(Extern, span),
+                    "macro" => Token::new(Macro, span),
+                    "static_assert" => Token::new(StaticAssert, span),
                     "init" => Token::new(Init, span),
                     "if" => Token::new(If, span),
                     "else" => Token::new(Else, span),
                     "while" => Token::new(While, span),
-                    "self" => Token::new(PathSelf, span),
+                    "for" => Token::new(For, span),
+                    "in" => Token::new(In, span),
+                    "defer" => Token::new(Defer, span),
+                    "self" => Token::new(PathSelf(string_table.insert("self".into())), span),
                     "super" => Token::new(PathSuper, span),
                     "root" => Token::new(PathFileRoot, span),
                     "project" => Token::new(PathProjectRoot, span),
                     "const" => Token::new(Const, span),
                     "size_of" => Token::new(SizeOf, span),
+                    "fields_of" => Token::new(FieldsOf, span),
+                    "name_of" => Token::new(NameOf, span),
+                    "len" => Token::new(Len, span),
+                    "Range" => Token::new(RangeType, span),
+                    "opt" => Token::new(OptionType, span),
+                    "some" => Token::new(OptionSome, span),
+                    "none" => Token::new(OptionNone, span),
+                    "res" => Token::new(ResultType, span),
+                    "ok" => Token::new(ResultOk, span),
+                    "err" => Token::new(ResultErr, span),
+                    "wrapping_add" => Token::new(WrappingAdd, span),
+                    "wrapping_sub" => Token::new(WrappingSub, span),
+                    "wrapping_mul" => Token::new(WrappingMul, span),
+                    "saturating_add" => Token::new(SaturatingAdd, span),
+                    "saturating_sub" => Token::new(SaturatingSub, span),
+                    "to_le_bytes" => Token::new(ToLeBytes, span),
+                    "to_be_bytes" => Token::new(ToBeBytes, span),
+                    "from_le_bytes" => Token::new(FromLeBytes, span),
+                    "from_be_bytes" => Token::new(FromBeBytes, span),
                     "null" => Token::new(Null, span),
                     "as" => Token::new(As, span),
+                    "_" => Token::new(Underscore, span),
                     _ => panic!("Matched a keyword which does not exist: {}", w),
                 })
             }
@@ -619,7 +787,8 @@ impl<'a> Lexer<'a> {
         let mut branch = LexerBranch::from(self);
 
         let primitives = [
-            "u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64", "f64", "bool", "string",
+            "u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64", "usize", "isize", "f64", "bool",
+            "string", "String",
         ];
 
         Ok(match branch.next_if_one_of(&primitives) {
@@ -635,9 +804,12 @@ impl<'a> Lexer<'a> {
                     "i16" => Token::new(Primitive(Primitive::I16), span),
                     "i32" => Token::new(Primitive(Primitive::I32), span),
                     "i64" => Token::new(Primitive(Primitive::I64), span),
+                    "usize" => Token::new(Primitive(Primitive::USize), span),
+                    "isize" => Token::new(Primitive(Primitive::ISize), span),
                     "f64" => Token::new(Primitive(Primitive::F64), span),
                     "bool" => Token::new(Primitive(Primitive::Bool), span),
                     "string" => Token::new(Primitive(Primitive::StringLiteral), span),
+                    "String" => Token::new(Primitive(Primitive::String), span),
                     _ => panic!("Matched a primitive which does not exist: {}", w),
                 })
             }
@@ -700,67 +872,125 @@ impl<'a> Lexer<'a> {
         c == 'n' || c == 'r' || c == 't' || c == '"' || c == '0' || c == '\\'
     }
 
+    /// Underscores can be used to make a number literal easier to read (e.g. `1_000_000`),
+    /// but each underscore must be placed between two digits.  If an underscore is
+    /// misplaced then this returns an error whose span covers just that underscore.
+    fn check_number_underscores(span: Span, text: &str) -> LexerResult<()> {
+        let chars: Vec<char> = text.chars().collect();
+        for (idx, c) in chars.iter().enumerate() {
+            if *c != '_' {
+                continue;
+            }
+
+            let after_digit = idx > 0 && chars[idx - 1].is_ascii_digit();
+            let before_digit = chars.get(idx + 1).map_or(false, |c| c.is_ascii_digit());
+            if !after_digit || !before_digit {
+                // Number literals are made up of ASCII characters, so each character is one byte
+                let low = Offset::new(span.low().as_u32() + idx as u32);
+                let high = Offset::new(low.as_u32() + 1);
+                return err!(Span::new(low, high), LexerError::MisplacedUnderscore);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Converts the text of a number, with any underscores removed, into a literal of
+    /// the given primitive type.  `literal` is the original text of the number as it
+    /// appears in the source code and is used when reporting errors.
     fn create_number_literal(
         span: Span,
+        literal: &str,
         int_token: &str,
         prim: Primitive,
     ) -> LexerResult<Option<Token>> {
         match prim {
             Primitive::U8 => Ok(Some(Token::new(
                 U8(int_token.parse::<u8>().map_err(|e| {
-                    CompilerError::new(span, LexerError::ParseIntError(Primitive::U8, e))
+                    CompilerError::new(
+                        span,
+                        LexerError::ParseIntError(literal.into(), Primitive::U8, e),
+                    )
                 })?),
                 span,
             ))),
             Primitive::U16 => Ok(Some(Token::new(
                 U16(int_token.parse::<u16>().map_err(|e| {
-                    CompilerError::new(span, LexerError::ParseIntError(Primitive::U16, e))
+                    CompilerError::new(
+                        span,
+                        LexerError::ParseIntError(literal.into(), Primitive::U16, e),
+                    )
                 })?),
                 span,
             ))),
             Primitive::U32 => Ok(Some(Token::new(
                 U32(int_token.parse::<u32>().map_err(|e| {
-                    CompilerError::new(span, LexerError::ParseIntError(Primitive::U32, e))
+                    CompilerError::new(
+                        span,
+                        LexerError::ParseIntError(literal.into(), Primitive::U32, e),
+                    )
                 })?),
                 span,
             ))),
             Primitive::U64 => Ok(Some(Token::new(
                 U64(int_token.parse::<u64>().map_err(|e| {
-                    CompilerError::new(span, LexerError::ParseIntError(Primitive::U64, e))
+                    CompilerError::new(
+                        span,
+                        LexerError::ParseIntError(literal.into(), Primitive::U64, e),
+                    )
                 })?),
                 span,
             ))),
             Primitive::I8 => Ok(Some(Token::new(
                 I8(int_token.parse::<i8>().map_err(|e| {
-                    CompilerError::new(span, LexerError::ParseIntError(Primitive::I8, e))
+                    CompilerError::new(
+                        span,
+                        LexerError::ParseIntError(literal.into(), Primitive::I8, e),
+                    )
                 })?),
                 span,
             ))),
             Primitive::I16 => Ok(Some(Token::new(
                 I16(int_token.parse::<i16>().map_err(|e| {
-                    CompilerError::new(span, LexerError::ParseIntError(Primitive::I16, e))
+                    CompilerError::new(
+                        span,
+                        LexerError::ParseIntError(literal.into(), Primitive::I16, e),
+                    )
                 })?),
                 span,
             ))),
             Primitive::I32 => Ok(Some(Token::new(
                 I32(int_token.parse::<i32>().map_err(|e| {
-                    CompilerError::new(span, LexerError::ParseIntError(Primitive::I32, e))
+                    CompilerError::new(
+                        span,
+                        LexerError::ParseIntError(literal.into(), Primitive::I32, e),
+                    )
                 })?),
                 span,
             ))),
             Primitive::I64 => Ok(Some(Token::new(
                 I64(int_token.parse::<i64>().map_err(|e| {
-                    CompilerError::new(span, LexerError::ParseIntError(Primitive::I64, e))
+                    CompilerError::new(
+                        span,
+                        LexerError::ParseIntError(literal.into(), Primitive::I64, e),
+                    )
                 })?),
                 span,
             ))),
             Primitive::F64 => Ok(Some(Token::new(
                 F64(int_token.parse::<f64>().map_err(|e| {
-                    CompilerError::new(span, LexerError::ParseFloatError(Primitive::F64, e))
+                    CompilerError::new(
+                        span,
+                        LexerError::ParseFloatError(literal.into(), Primitive::F64, e),
+                    )
                 })?),
                 span,
             ))),
-            Primitive::Bool | Primitive::StringLiteral => {
+            Primitive::USize
+            | Primitive::ISize
+            | Primitive::Bool
+            | Primitive::StringLiteral
+            | Primitive::String => {
                 err!(span, LexerError::UnexpectedSuffixType(prim))
             }
         }
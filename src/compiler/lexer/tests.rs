@@ -8,7 +8,7 @@ mod tests {
                 LexerError,
             },
             source::Offset,
-            CompilerError, SourceMap, Span,
+            CompilerDisplay, CompilerError, SourceMap, Span,
         },
         StringTable,
     };
@@ -38,6 +38,76 @@ mod tests {
         assert_eq!(token, Token::new(I64(5), new_span(0, 1)));
     }
 
+    #[test]
+    fn test_byte_order_mark() {
+        let text = "\u{feff}5";
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text.into(), "/tst".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let mut table = StringTable::new();
+        let logger = Logger::new();
+        let mut lexer = Lexer::new(src, &mut table, &logger).unwrap();
+
+        let tokens = lexer.tokenize();
+        assert_eq!(tokens.len(), 1);
+        let token = tokens[0].clone().expect("Expected valid token");
+        assert_eq!(token, Token::new(I64(5), new_span(3, 4)));
+    }
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let text = "5\r\n6\r\n";
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text.into(), "/tst".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let mut table = StringTable::new();
+        let logger = Logger::new();
+        let mut lexer = Lexer::new(src, &mut table, &logger).unwrap();
+
+        let tokens: Vec<_> = lexer
+            .tokenize()
+            .into_iter()
+            .map(|t| t.expect("Expected valid token"))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(I64(5), new_span(0, 1)),
+                Token::new(I64(6), new_span(3, 4))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_comment_with_cr_line_endings() {
+        let text = "// comment\r5\r\n// comment\r\n6";
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text.into(), "/tst".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let mut table = StringTable::new();
+        let logger = Logger::new();
+        let mut lexer = Lexer::new(src, &mut table, &logger).unwrap();
+
+        let tokens: Vec<_> = lexer
+            .tokenize()
+            .into_iter()
+            .map(|t| t.expect("Expected valid token"))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(I64(5), new_span(11, 12)),
+                Token::new(I64(6), new_span(26, 27))
+            ]
+        );
+    }
+
     #[test]
     fn test_integer8() {
         let text = "5i8";
@@ -227,6 +297,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_c_string_literal() {
+        let text = "c\"text\"";
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+
+        let mut table = StringTable::new();
+        let src = sm.get(0).unwrap().read().unwrap();
+        let logger = Logger::new();
+        let mut lexer = Lexer::new(src, &mut table, &logger).unwrap();
+        let tokens = lexer.tokenize();
+
+        assert_eq!(tokens.len(), 1, "{:?}", tokens);
+        let token = tokens[0].clone().expect("Expected valid token");
+        assert_eq!(
+            token,
+            Token::new(CStringLiteral(table.insert("text".into())), new_span(0, 7))
+        );
+    }
+
+    #[test]
+    fn test_c_string_literal_with_nul() {
+        let text = "c\"te\\0xt\"";
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+
+        let mut table = StringTable::new();
+        let src = sm.get(0).unwrap().read().unwrap();
+        let logger = Logger::new();
+        let mut lexer = Lexer::new(src, &mut table, &logger).unwrap();
+        let tokens = lexer.tokenize();
+
+        let err = tokens[0].clone().expect_err("Expected error");
+        assert_eq!(
+            err,
+            CompilerError::new(new_span(0, 5), LexerError::NulInCString)
+        );
+    }
+
     #[test]
     fn test_invalid_string_literal() {
         let text = "\"text";
@@ -249,7 +358,17 @@ mod tests {
 
     #[test]
     fn test_identifier() {
-        for text in ["x", "y", "x_5"].iter() {
+        for text in [
+            "x",
+            "y",
+            "x_5",
+            "_x",
+            "__",
+            "wrapping_adder",
+            "saturating_sub_total",
+        ]
+        .iter()
+        {
             let mut sm = SourceMap::new();
             sm.add_string(text, "/test".into()).unwrap();
 
@@ -270,6 +389,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_label() {
+        for text in ["'outer", "'x_5", "'_"].iter() {
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+
+            let mut table = StringTable::new();
+            let src = sm.get(0).unwrap().read().unwrap();
+            let logger = Logger::new();
+            let mut lexer = Lexer::new(src, &mut table, &logger).unwrap();
+            let tokens = lexer.tokenize();
+            assert_eq!(tokens.len(), 1);
+            let token = tokens[0].clone().expect("Expected valid token");
+            assert_eq!(
+                token,
+                Token::new(
+                    Label(table.insert((*text).into())),
+                    new_span(0, text.len() as u32)
+                )
+            );
+        }
+
+        for text in ["'", "' x", "'5"].iter() {
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+
+            let mut table = StringTable::new();
+            let src = sm.get(0).unwrap().read().unwrap();
+            let logger = Logger::new();
+            let mut lexer = Lexer::new(src, &mut table, &logger).unwrap();
+            let tokens = lexer.tokenize();
+            let err = tokens[0].clone().expect_err("Expected error");
+            assert_eq!(
+                err,
+                CompilerError::new(new_span(0, 1), LexerError::ExpectedLabelName),
+                "{}",
+                text
+            );
+        }
+    }
+
     #[test]
     fn test_invalid_number() {
         for text in ["5x"].iter() {
@@ -288,10 +448,98 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_number_underscores() {
+        for (text, expected) in [
+            ("1_000", I64(1000)),
+            ("1_000_000i64", I64(1000000)),
+            ("2_5u8", U8(25)),
+            ("1_0.2_5", F64(10.25)),
+            ("1_0.5e1_0", F64(10.5e10)),
+        ]
+        .iter()
+        {
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+
+            let mut table = StringTable::new();
+            let src = sm.get(0).unwrap().read().unwrap();
+            let logger = Logger::new();
+            let mut lexer = Lexer::new(src, &mut table, &logger).unwrap();
+            let tokens = lexer.tokenize();
+            assert_eq!(tokens.len(), 1, "{}", text);
+            let token = tokens[0].clone().expect("Expected valid token");
+            assert_eq!(
+                token,
+                Token::new(expected.clone(), new_span(0, text.len() as u32)),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_misplaced_number_underscores() {
+        for (text, span) in [
+            ("1_", new_span(1, 2)),
+            ("1__0", new_span(1, 2)),
+            ("1_i8", new_span(1, 2)),
+            ("10_.5", new_span(2, 3)),
+            ("10._5", new_span(3, 4)),
+            ("1.5e_5", new_span(4, 5)),
+        ]
+        .iter()
+        {
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+
+            let mut table = StringTable::new();
+            let src = sm.get(0).unwrap().read().unwrap();
+            let logger = Logger::new();
+            let mut lexer = Lexer::new(src, &mut table, &logger).unwrap();
+            let tokens = lexer.tokenize();
+            let err = tokens[0].clone().expect_err("Expected error");
+            assert_eq!(
+                err,
+                CompilerError::new(*span, LexerError::MisplacedUnderscore),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_number_out_of_range() {
+        for (text, msg) in [
+            (
+                "1_000i8",
+                "L1: Invalid i8 literal 1_000i8: number too large to fit in target type",
+            ),
+            (
+                "300u8",
+                "L1: Invalid u8 literal 300u8: number too large to fit in target type",
+            ),
+        ]
+        .iter()
+        {
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+
+            let mut table = StringTable::new();
+            let src = sm.get(0).unwrap().read().unwrap();
+            let logger = Logger::new();
+            let mut lexer = Lexer::new(src, &mut table, &logger).unwrap();
+            let tokens = lexer.tokenize();
+            let err = tokens[0].clone().expect_err("Expected error");
+            assert_eq!(err.fmt(&sm, &table).unwrap(), *msg);
+        }
+    }
+
     #[test]
     fn test_operator() {
         for (text, expected_symbol) in [
             ("...", VarArgs),
+            ("..", Range),
             ("*", Mul),
             ("/", Div),
             ("+", Add),
@@ -315,11 +563,16 @@ mod tests {
             (".", MemberAccess),
             ("::", PathSeparator),
             ("->", LArrow),
+            ("=>", FatArrow),
             (":", Colon),
             (",", Comma),
             (";", Semicolon),
             ("@", At),
             ("^", Hat),
+            ("?", Question),
+            ("#", Hash),
+            ("$", Dollar),
+            ("=", Equals),
         ]
         .iter()
         {
@@ -367,6 +620,33 @@ mod tests {
         assert_eq!(token, Token::new(Yield, new_span(8, 13)));
     }
 
+    #[test]
+    fn test_range() {
+        let text = "0..10 5...";
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+
+        let mut table = StringTable::new();
+        let src = sm.get(0).unwrap().read().unwrap();
+        let logger = Logger::new();
+        let mut lexer = Lexer::new(src, &mut table, &logger).unwrap();
+        let tokens: Vec<_> = lexer
+            .tokenize()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(I64(0), new_span(0, 1)),
+                Token::new(Range, new_span(1, 3)),
+                Token::new(I64(10), new_span(3, 5)),
+                Token::new(I64(5), new_span(6, 7)),
+                Token::new(VarArgs, new_span(7, 10)),
+            ]
+        );
+    }
+
     #[test]
     fn test_keywords() {
         for (text, expected_symbol) in [
@@ -374,20 +654,49 @@ mod tests {
             ("let", Let),
             ("mut", Mut),
             ("return", Return),
+            ("break", Break),
+            ("continue", Continue),
             ("yield", Yield),
             ("yret", YieldReturn),
             ("init", Init),
             ("co", CoroutineDef),
             ("fn", FunctionDef),
             ("extern", Extern),
+            ("macro", Macro),
+            ("static_assert", StaticAssert),
             ("mod", ModuleDef),
+            ("use", Use),
             ("struct", Struct),
+            ("pub", Pub),
             ("if", If),
             ("else", Else),
             ("while", While),
+            ("for", For),
+            ("in", In),
+            ("defer", Defer),
             ("size_of", SizeOf),
+            ("fields_of", FieldsOf),
+            ("name_of", NameOf),
+            ("len", Len),
+            ("Range", RangeType),
+            ("opt", OptionType),
+            ("some", OptionSome),
+            ("none", OptionNone),
+            ("res", ResultType),
+            ("ok", ResultOk),
+            ("err", ResultErr),
+            ("wrapping_add", WrappingAdd),
+            ("wrapping_sub", WrappingSub),
+            ("wrapping_mul", WrappingMul),
+            ("saturating_add", SaturatingAdd),
+            ("saturating_sub", SaturatingSub),
+            ("to_le_bytes", ToLeBytes),
+            ("to_be_bytes", ToBeBytes),
+            ("from_le_bytes", FromLeBytes),
+            ("from_be_bytes", FromBeBytes),
             ("null", Null),
             ("as", As),
+            ("_", Underscore),
         ]
         .iter()
         {
@@ -417,9 +726,12 @@ mod tests {
             ("i16", Primitive(Primitive::I16)),
             ("i32", Primitive(Primitive::I32)),
             ("i64", Primitive(Primitive::I64)),
+            ("usize", Primitive(Primitive::USize)),
+            ("isize", Primitive(Primitive::ISize)),
             ("f64", Primitive(Primitive::F64)),
             ("bool", Primitive(Primitive::Bool)),
             ("string", Primitive(Primitive::StringLiteral)),
+            ("String", Primitive(Primitive::String)),
         ]
         .iter()
         {
@@ -2,8 +2,9 @@ use crate::{
     compiler::{source::SourceIr, CompilerDisplay, CompilerDisplayError, SourceMap, Span},
     StringId,
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Primitive {
     U8,
     U16,
@@ -13,9 +14,12 @@ pub enum Primitive {
     I16,
     I32,
     I64,
+    USize,
+    ISize,
     F64,
     Bool,
     StringLiteral,
+    String,
 }
 
 impl std::fmt::Display for Primitive {
@@ -29,9 +33,12 @@ impl std::fmt::Display for Primitive {
             Primitive::I16 => f.write_str("i16"),
             Primitive::I32 => f.write_str("i32"),
             Primitive::I64 => f.write_str("i64"),
+            Primitive::USize => f.write_str("usize"),
+            Primitive::ISize => f.write_str("isize"),
             Primitive::F64 => f.write_str("f64"),
             Primitive::Bool => f.write_str("bool"),
             Primitive::StringLiteral => f.write_str("string"),
+            Primitive::String => f.write_str("String"),
         }
     }
 }
@@ -42,7 +49,7 @@ impl CompilerDisplay for Primitive {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Lex {
     U8(u8),
     U16(u16),
@@ -55,7 +62,10 @@ pub enum Lex {
     F64(f64),
     Bool(bool),
     Identifier(StringId),
+    /// The label of a loop, `'name`, which includes the leading `'`
+    Label(StringId),
     StringLiteral(StringId),
+    CStringLiteral(StringId),
     VarArgs,
     Mul,
     Div,
@@ -71,11 +81,14 @@ pub enum Lex {
     Eq,
     NEq,
     Assign,
+    Equals,
     Semicolon,
     Comma,
     Let,
     Mut,
     Return,
+    Break,
+    Continue,
     LParen,
     RParen,
     LBrace,
@@ -88,32 +101,65 @@ pub enum Lex {
     CoroutineDef,
     FunctionDef,
     ModuleDef,
+    Use,
     Struct,
+    Pub,
+    Impl,
     Extern,
     If,
     Else,
     While,
+    For,
+    In,
+    Defer,
+    Range,
+    RangeType,
+    OptionType,
+    OptionSome,
+    OptionNone,
+    ResultType,
+    ResultOk,
+    ResultErr,
     Colon,
     MemberAccess,
     PathSeparator,
     LArrow,
     Primitive(Primitive),
-    PathSelf,
+    PathSelf(StringId),
     PathSuper,
     PathProjectRoot,
     PathFileRoot,
     Const,
     At,
     Hat,
+    Question,
+    Hash,
+    Dollar,
+    FatArrow,
+    Macro,
+    StaticAssert,
     SizeOf,
+    FieldsOf,
+    NameOf,
+    Len,
+    WrappingAdd,
+    WrappingSub,
+    WrappingMul,
+    SaturatingAdd,
+    SaturatingSub,
+    ToLeBytes,
+    ToBeBytes,
+    FromLeBytes,
+    FromBeBytes,
     Null,
     As,
+    Underscore,
 }
 
 impl Lex {
     pub fn get_str(&self) -> Option<StringId> {
         match self {
-            Lex::StringLiteral(s) | Lex::Identifier(s) => Some(*s),
+            Lex::StringLiteral(s) | Lex::CStringLiteral(s) | Lex::Identifier(s) => Some(*s),
             _ => None,
         }
     }
@@ -134,13 +180,15 @@ impl std::fmt::Display for Lex {
             F64(v) => f.write_str(&format!("f64 literal {}", v)),
             Bool(b) => f.write_str(&format!("bool literal {}", b)),
             Identifier(id) => f.write_str(&format!("identifier {}", id)),
+            Label(id) => f.write_str(&format!("label {}", id)),
             StringLiteral(str) => f.write_str(&format!("literal \"{}\"", str)),
+            CStringLiteral(str) => f.write_str(&format!("literal c\"{}\"", str)),
             VarArgs => f.write_str("..."),
             Ls => f.write_str("<"),
             LsEq => f.write_str("<="),
             Gr => f.write_str(">"),
             GrEq => f.write_str(">="),
-            Eq => f.write_str("="),
+            Eq => f.write_str("=="),
             NEq => f.write_str("!="),
             Mul => f.write_str("*"),
             Div => f.write_str("/"),
@@ -150,11 +198,14 @@ impl std::fmt::Display for Lex {
             BAnd => f.write_str("&&"),
             BOr => f.write_str("||"),
             Assign => f.write_str(":="),
+            Equals => f.write_str("="),
             Semicolon => f.write_str(";"),
             Comma => f.write_str(","),
             Let => f.write_str("let"),
             Mut => f.write_str("mut"),
             Return => f.write_str("return"),
+            Break => f.write_str("break"),
+            Continue => f.write_str("continue"),
             LParen => f.write_str("("),
             RParen => f.write_str(")"),
             LBrace => f.write_str("{"),
@@ -167,26 +218,59 @@ impl std::fmt::Display for Lex {
             CoroutineDef => f.write_str("co"),
             FunctionDef => f.write_str("fn"),
             ModuleDef => f.write_str("mod"),
+            Use => f.write_str("use"),
             Struct => f.write_str("struct"),
+            Pub => f.write_str("pub"),
+            Impl => f.write_str("impl"),
             Extern => f.write_str("extern"),
             If => f.write_str("if"),
             While => f.write_str("while"),
+            For => f.write_str("for"),
+            In => f.write_str("in"),
+            Defer => f.write_str("defer"),
+            Range => f.write_str(".."),
+            RangeType => f.write_str("Range"),
+            OptionType => f.write_str("opt"),
+            OptionSome => f.write_str("some"),
+            OptionNone => f.write_str("none"),
+            ResultType => f.write_str("res"),
+            ResultOk => f.write_str("ok"),
+            ResultErr => f.write_str("err"),
             Else => f.write_str("else"),
             Colon => f.write_str(":"),
             MemberAccess => f.write_str("."),
             PathSeparator => f.write_str("::"),
             LArrow => f.write_str("->"),
             Primitive(p) => f.write_str(&format!("{}", p)),
-            PathSelf => f.write_str("self"),
+            PathSelf(_) => f.write_str("self"),
             PathSuper => f.write_str("super"),
             PathFileRoot => f.write_str("root"),
             PathProjectRoot => f.write_str("project"),
             Const => f.write_str("const"),
             At => f.write_str("@"),
             Hat => f.write_str("^"),
+            Question => f.write_str("?"),
+            Hash => f.write_str("#"),
+            Dollar => f.write_str("$"),
+            FatArrow => f.write_str("=>"),
+            Macro => f.write_str("macro"),
+            StaticAssert => f.write_str("static_assert"),
             SizeOf => f.write_str("size_of"),
+            FieldsOf => f.write_str("fields_of"),
+            NameOf => f.write_str("name_of"),
+            Len => f.write_str("len"),
+            WrappingAdd => f.write_str("wrapping_add"),
+            WrappingSub => f.write_str("wrapping_sub"),
+            WrappingMul => f.write_str("wrapping_mul"),
+            SaturatingAdd => f.write_str("saturating_add"),
+            SaturatingSub => f.write_str("saturating_sub"),
+            ToLeBytes => f.write_str("to_le_bytes"),
+            ToBeBytes => f.write_str("to_be_bytes"),
+            FromLeBytes => f.write_str("from_le_bytes"),
+            FromBeBytes => f.write_str("from_be_bytes"),
             Null => f.write_str("null"),
             As => f.write_str("as"),
+            Underscore => f.write_str("_"),
         }
     }
 }
@@ -195,13 +279,15 @@ impl CompilerDisplay for Lex {
     fn fmt(&self, _: &SourceMap, st: &crate::StringTable) -> Result<String, CompilerDisplayError> {
         match self {
             Lex::Identifier(sid) => Ok(format!("identifier {}", st.get(*sid)?)),
+            Lex::Label(sid) => Ok(format!("label {}", st.get(*sid)?)),
             Lex::StringLiteral(sid) => Ok(format!("string literal {}", st.get(*sid)?)),
+            Lex::CStringLiteral(sid) => Ok(format!("c string literal {}", st.get(*sid)?)),
             _ => Ok(format!("{}", self)),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Token {
     /// The value of the token
     pub sym: Lex,
@@ -279,14 +365,26 @@ impl Token {
                 Lex::Identifier(_) => true,
                 _ => false,
             },
+            Lex::Label(_) => match a {
+                Lex::Label(_) => true,
+                _ => false,
+            },
             Lex::StringLiteral(_) => match a {
                 Lex::StringLiteral(_) => true,
                 _ => false,
             },
+            Lex::CStringLiteral(_) => match a {
+                Lex::CStringLiteral(_) => true,
+                _ => false,
+            },
             Lex::Primitive(_) => match a {
                 Lex::Primitive(_) => true,
                 _ => false,
             },
+            Lex::PathSelf(_) => match a {
+                Lex::PathSelf(_) => true,
+                _ => false,
+            },
             Lex::VarArgs
             | Lex::Mul
             | Lex::Div
@@ -302,11 +400,14 @@ impl Token {
             | Lex::Eq
             | Lex::NEq
             | Lex::Assign
+            | Lex::Equals
             | Lex::Semicolon
             | Lex::Comma
             | Lex::Let
             | Lex::Mut
             | Lex::Return
+            | Lex::Break
+            | Lex::Continue
             | Lex::LParen
             | Lex::RParen
             | Lex::LBrace
@@ -319,24 +420,56 @@ impl Token {
             | Lex::CoroutineDef
             | Lex::FunctionDef
             | Lex::ModuleDef
+            | Lex::Use
             | Lex::Struct
+            | Lex::Pub
+            | Lex::Impl
             | Lex::Extern
             | Lex::If
             | Lex::Else
             | Lex::While
+            | Lex::For
+            | Lex::In
+            | Lex::Defer
+            | Lex::Range
+            | Lex::RangeType
+            | Lex::OptionType
+            | Lex::OptionSome
+            | Lex::OptionNone
+            | Lex::ResultType
+            | Lex::ResultOk
+            | Lex::ResultErr
             | Lex::Colon
             | Lex::MemberAccess
             | Lex::PathSeparator
-            | Lex::PathSelf
             | Lex::PathSuper
             | Lex::PathFileRoot
             | Lex::PathProjectRoot
             | Lex::Const
             | Lex::At
             | Lex::Hat
+            | Lex::Question
+            | Lex::Hash
+            | Lex::Dollar
+            | Lex::FatArrow
+            | Lex::Macro
+            | Lex::StaticAssert
             | Lex::SizeOf
+            | Lex::FieldsOf
+            | Lex::NameOf
+            | Lex::Len
+            | Lex::WrappingAdd
+            | Lex::WrappingSub
+            | Lex::WrappingMul
+            | Lex::SaturatingAdd
+            | Lex::SaturatingSub
+            | Lex::ToLeBytes
+            | Lex::ToBeBytes
+            | Lex::FromLeBytes
+            | Lex::FromBeBytes
             | Lex::Null
             | Lex::As
+            | Lex::Underscore
             | Lex::LArrow => *a == self.sym,
         }
     }
@@ -0,0 +1,104 @@
+//! Rules for passing Bramble values to, and returning them from, functions under the
+//! platform's C calling convention.  Both code generators apply these rules so that
+//! separately compiled code, including C code, agrees on how a value is passed (see
+//! `design/internals/calling-convention.md`).
+
+use inkwell::{
+    attributes::{Attribute, AttributeLoc},
+    context::Context,
+    values::FunctionValue,
+};
+
+use crate::compiler::{ast::Type, mir::MirBaseType};
+
+/// How an integer which is narrower than a register is widened when it is passed to,
+/// or returned from, a function.
+///
+/// The C ABI expects `bool`, `u8`, and `u16` values to be zero extended and `i8` and
+/// `i16` values to be sign extended.  LLVM does not do this on its own: the function
+/// declaration must say, with the `zeroext` or `signext` attribute, how each parameter
+/// and the result are extended, and LLVM then adds the extension to both the caller
+/// and the callee.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Extension {
+    /// The value is passed as is
+    None,
+
+    /// The value is zero extended
+    Zero,
+
+    /// The value is sign extended
+    Sign,
+}
+
+impl Extension {
+    /// Returns how a value of the given Bramble type is extended
+    pub fn from_ast(ty: &Type) -> Extension {
+        match ty {
+            Type::Bool | Type::U8 | Type::U16 => Extension::Zero,
+            Type::I8 | Type::I16 => Extension::Sign,
+            _ => Extension::None,
+        }
+    }
+
+    /// Returns how a value of the given MIR base type is extended
+    pub fn from_mir(ty: &MirBaseType) -> Extension {
+        match ty {
+            MirBaseType::Bool | MirBaseType::U8 | MirBaseType::U16 => Extension::Zero,
+            MirBaseType::I8 | MirBaseType::I16 => Extension::Sign,
+            _ => Extension::None,
+        }
+    }
+
+    /// The name of the LLVM attribute which marks a value with this extension
+    fn attribute(&self) -> Option<&'static str> {
+        match self {
+            Extension::None => None,
+            Extension::Zero => Some("zeroext"),
+            Extension::Sign => Some("signext"),
+        }
+    }
+}
+
+/// Marks how every parameter of `function`, and its result, is extended.  `params`
+/// has one entry for each LLVM parameter of the function, including the out parameter
+/// of functions which return an aggregate.
+pub(super) fn add_extension_attributes<'ctx>(
+    context: &'ctx Context,
+    function: FunctionValue<'ctx>,
+    params: &[Extension],
+    ret: Extension,
+) {
+    for (idx, ext) in params.iter().enumerate() {
+        add_extension_attribute(context, function, AttributeLoc::Param(idx as u32), *ext)
+    }
+    add_extension_attribute(context, function, AttributeLoc::Return, ret)
+}
+
+/// Marks the first parameter of `function` as the out parameter which its result, a
+/// structure or an array, is written to.  The `sret` attribute tells LLVM that the
+/// parameter is the hidden pointer which the platform's C ABI uses to return an
+/// aggregate, so the pointer is passed, and returned, in the registers that the ABI
+/// expects.
+pub(super) fn add_out_param_attributes<'ctx>(
+    context: &'ctx Context,
+    function: FunctionValue<'ctx>,
+) {
+    let kind = Attribute::get_named_enum_kind_id("sret");
+    function.add_attribute(
+        AttributeLoc::Param(0),
+        context.create_enum_attribute(kind, 0),
+    );
+}
+
+fn add_extension_attribute<'ctx>(
+    context: &'ctx Context,
+    function: FunctionValue<'ctx>,
+    loc: AttributeLoc,
+    ext: Extension,
+) {
+    if let Some(name) = ext.attribute() {
+        let kind = Attribute::get_named_enum_kind_id(name);
+        function.add_attribute(loc, context.create_enum_attribute(kind, 0));
+    }
+}
@@ -0,0 +1,142 @@
+//! Records the global data which code generation adds to a module so that it can be
+//! reported to the people embedding a Bramble program (`--emit header`).
+
+/// The global variables, in the order that they were added, which hold the static data
+/// of a compiled module.  Today this is the contents of the string pool.
+#[derive(Debug, Default)]
+pub struct DataSection {
+    globals: Vec<GlobalData>,
+}
+
+/// A single global variable in the data section.
+#[derive(Debug, PartialEq)]
+struct GlobalData {
+    /// The name of the symbol that the global is bound to in the object file
+    symbol: String,
+
+    /// The initial value of the global, exactly as it is laid out in memory
+    bytes: Vec<u8>,
+}
+
+impl DataSection {
+    pub fn new() -> DataSection {
+        DataSection::default()
+    }
+
+    /// Records that a global, bound to `symbol`, which is initialized with `bytes`, was
+    /// added to the module.
+    pub fn add(&mut self, symbol: &str, bytes: &[u8]) {
+        self.globals.push(GlobalData {
+            symbol: symbol.into(),
+            bytes: bytes.into(),
+        })
+    }
+
+    /// The total number of bytes of global data
+    pub fn size(&self) -> usize {
+        self.globals.iter().map(|g| g.bytes.len()).sum()
+    }
+
+    /// Renders the data section as a C header which declares every global with its
+    /// size and, in a comment, its contents.  Every global is an array of bytes, so
+    /// each one is aligned to 1 byte.
+    pub fn header(&self, name: &str) -> String {
+        let guard = format!(
+            "BRAMBLE_{}_DATA_H",
+            name.chars()
+                .map(|c| if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                })
+                .collect::<String>()
+        );
+
+        let mut text = format!(
+            "/* Global data of {}: {} bytes in {} symbols */\n#ifndef {}\n#define {}\n\n",
+            name,
+            self.size(),
+            self.globals.len(),
+            guard,
+            guard
+        );
+
+        for g in &self.globals {
+            text.push_str(&format!(
+                "/* {} bytes: \"{}\" */\nextern const char {}[{}];\n\n",
+                g.bytes.len(),
+                escape(&g.bytes),
+                g.symbol,
+                g.bytes.len(),
+            ));
+        }
+
+        text.push_str(&format!("#endif /* {} */\n", guard));
+        text
+    }
+}
+
+/// Writes `bytes` as the contents of a C string literal.  The terminating null byte is
+/// left off, as it is implied by the literal, and a `/` which follows a `*` is escaped
+/// so that the literal cannot end the comment which it is written in.
+fn escape(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+    let mut s = String::new();
+    let mut prev = 0;
+    for &b in bytes {
+        match b {
+            b'"' => s.push_str("\\\""),
+            b'\\' => s.push_str("\\\\"),
+            b'\n' => s.push_str("\\n"),
+            b'\t' => s.push_str("\\t"),
+            b'/' if prev == b'*' => s.push_str("\\057"),
+            0x20..=0x7e => s.push(b as char),
+            _ => s.push_str(&format!("\\{:03o}", b)),
+        }
+        prev = b;
+    }
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn empty_header() {
+        let data = DataSection::new();
+        assert_eq!(
+            data.header("my-proj"),
+            "/* Global data of my-proj: 0 bytes in 0 symbols */
+#ifndef BRAMBLE_MY_PROJ_DATA_H
+#define BRAMBLE_MY_PROJ_DATA_H
+
+#endif /* BRAMBLE_MY_PROJ_DATA_H */
+"
+        );
+    }
+
+    #[test]
+    fn header_lists_globals_in_order() {
+        let mut data = DataSection::new();
+        data.add("str_p_0", b"hello\n\0");
+        data.add("str_p_1", b"say \"*/\"\x01\0");
+
+        assert_eq!(data.size(), 17);
+        assert_eq!(
+            data.header("p"),
+            "/* Global data of p: 17 bytes in 2 symbols */
+#ifndef BRAMBLE_P_DATA_H
+#define BRAMBLE_P_DATA_H
+
+/* 7 bytes: \"hello\\n\" */
+extern const char str_p_0[7];
+
+/* 10 bytes: \"say \\\"*\\057\\\"\\001\" */
+extern const char str_p_1[10];
+
+#endif /* BRAMBLE_P_DATA_H */
+"
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! Describes the generated code to debuggers (e.g. gdb and lldb) with DWARF debug
+//! information, so that a Bramble program can be stepped through line by line and its
+//! variables can be inspected.  Both code generators use a [`DebugInfo`] to describe
+//! their functions, the source location of each instruction, and their variables.
+//!
+//! Only the base types and pointers to them are described, so variables which hold a
+//! structure or an array are not visible to a debugger yet.
+
+use std::collections::HashMap;
+
+use inkwell::{
+    builder::Builder,
+    context::Context,
+    debug_info::{
+        AsDIScope, DIFile, DIFlags, DIFlagsConstants, DIScope, DISubprogram, DIType,
+        DWARFEmissionKind, DWARFSourceLanguage, DebugInfoBuilder,
+    },
+    module::{FlagBehavior, Module},
+    values::{FunctionValue, PointerValue},
+    AddressSpace,
+};
+
+use crate::compiler::{ast::Type, mir::MirBaseType, source::Offset, SourceMap, Span};
+
+/// The version of the debug information metadata which LLVM expects.  Without this
+/// module flag LLVM discards the debug information.
+const DEBUG_METADATA_VERSION: u64 = 3;
+
+/// The version of DWARF which is emitted.
+const DWARF_VERSION: u64 = 4;
+
+/// Pointers are 64 bits on every supported target
+const POINTER_BITS: u64 = 64;
+
+/// The DWARF encodings (`DW_ATE_*`) of the base types
+const DW_ATE_BOOLEAN: u32 = 0x02;
+const DW_ATE_FLOAT: u32 = 0x04;
+const DW_ATE_SIGNED: u32 = 0x05;
+const DW_ATE_UNSIGNED: u32 = 0x07;
+
+/// Generates the debug information for a single LLVM module.
+pub(super) struct DebugInfo<'ctx> {
+    context: &'ctx Context,
+    builder: DebugInfoBuilder<'ctx>,
+    source_map: &'ctx SourceMap,
+
+    /// The debug information file and the start of every line of each source file,
+    /// keyed by the index of the file in the [`SourceMap`].  A file is added the first
+    /// time that code from it is described.
+    files: HashMap<usize, (DIFile<'ctx>, Vec<Offset>)>,
+
+    /// The function which code is currently being generated for
+    subprogram: Option<DISubprogram<'ctx>>,
+}
+
+impl<'ctx> DebugInfo<'ctx> {
+    /// Starts the debug information for `module`, which holds the code of the project
+    /// `name`.  The directory of the compile unit is always `.`, so that the debug
+    /// information does not depend upon where the compiler was run.
+    pub fn new(
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+        name: &str,
+        source_map: &'ctx SourceMap,
+    ) -> DebugInfo<'ctx> {
+        let i32_type = context.i32_type();
+        module.add_basic_value_flag(
+            "Debug Info Version",
+            FlagBehavior::Warning,
+            i32_type.const_int(DEBUG_METADATA_VERSION, false),
+        );
+        module.add_basic_value_flag(
+            "Dwarf Version",
+            FlagBehavior::Warning,
+            i32_type.const_int(DWARF_VERSION, false),
+        );
+
+        let (builder, _) = module.create_debug_info_builder(
+            true,
+            DWARFSourceLanguage::C,
+            name,
+            ".",
+            "bramblec",
+            false,
+            "",
+            0,
+            "",
+            DWARFEmissionKind::Full,
+            0,
+            false,
+            false,
+            "",
+            "",
+        );
+
+        DebugInfo {
+            context,
+            builder,
+            source_map,
+            files: HashMap::new(),
+            subprogram: None,
+        }
+    }
+
+    /// Describes `function`, which is named `name` in the source code and is defined
+    /// at `span`, and marks the instructions which `builder` builds from now on as
+    /// coming from the start of the function.  Until [`end_function`](Self::end_function)
+    /// is called, every location and variable is described as part of this function.
+    pub fn begin_function(
+        &mut self,
+        builder: &Builder<'ctx>,
+        function: FunctionValue<'ctx>,
+        name: &str,
+        span: Span,
+    ) {
+        self.end_function(builder);
+        let (file, line, _) = match self.position(span) {
+            Some(pos) => pos,
+            None => return,
+        };
+
+        let ty = self
+            .builder
+            .create_subroutine_type(file, None, &[], DIFlags::PUBLIC);
+        let linkage_name = function.get_name().to_string_lossy().into_owned();
+        let subprogram = self.builder.create_function(
+            file.as_debug_info_scope(),
+            name,
+            Some(&linkage_name),
+            file,
+            line,
+            ty,
+            false,
+            true,
+            line,
+            DIFlags::PUBLIC,
+            false,
+        );
+        function.set_subprogram(subprogram);
+        self.subprogram = Some(subprogram);
+        self.set_location(builder, span);
+    }
+
+    /// Marks that code generation for the current function is finished.  Instructions
+    /// which are built after this have no source location.
+    pub fn end_function(&mut self, builder: &Builder<'ctx>) {
+        self.subprogram = None;
+        builder.unset_current_debug_location();
+    }
+
+    /// Marks every instruction which `builder` builds from now on as coming from the
+    /// code at `span`.
+    pub fn set_location(&mut self, builder: &Builder<'ctx>, span: Span) {
+        if let (Some(scope), Some((_, line, column))) = (self.scope(), self.position(span)) {
+            let loc = self
+                .builder
+                .create_debug_location(self.context, line, column, scope, None);
+            builder.set_current_debug_location(self.context, loc);
+        }
+    }
+
+    /// Describes a variable, called `name` and declared at `span`, whose value is
+    /// stored at `ptr`.  Parameters are numbered, from 1, by `arg_no`.  If the type of
+    /// the variable cannot be described, then the variable is left out.
+    pub fn declare_variable(
+        &mut self,
+        builder: &Builder<'ctx>,
+        ptr: PointerValue<'ctx>,
+        name: &str,
+        ty: Option<DIType<'ctx>>,
+        span: Span,
+        arg_no: Option<u32>,
+    ) {
+        let (scope, (file, line, column), ty) = match (self.scope(), self.position(span), ty) {
+            (Some(scope), Some(pos), Some(ty)) => (scope, pos, ty),
+            _ => return,
+        };
+        let block = match builder.get_insert_block() {
+            Some(block) => block,
+            None => return,
+        };
+
+        let var = match arg_no {
+            Some(arg_no) => self.builder.create_parameter_variable(
+                scope,
+                name,
+                arg_no,
+                file,
+                line,
+                ty,
+                true,
+                DIFlags::ZERO,
+            ),
+            None => self.builder.create_auto_variable(
+                scope,
+                name,
+                file,
+                line,
+                ty,
+                true,
+                DIFlags::ZERO,
+                0,
+            ),
+        };
+        let loc = self
+            .builder
+            .create_debug_location(self.context, line, column, scope, None);
+        self.builder
+            .insert_declare_at_end(ptr, Some(var), None, loc, block);
+    }
+
+    /// Returns the debug information type of a value of the given Bramble type
+    pub fn ast_type(&self, ty: &Type) -> Option<DIType<'ctx>> {
+        match ty {
+            Type::Bool => self.base_type(ty, 8, DW_ATE_BOOLEAN),
+            Type::U8 => self.base_type(ty, 8, DW_ATE_UNSIGNED),
+            Type::U16 => self.base_type(ty, 16, DW_ATE_UNSIGNED),
+            Type::U32 => self.base_type(ty, 32, DW_ATE_UNSIGNED),
+            Type::U64 => self.base_type(ty, 64, DW_ATE_UNSIGNED),
+            Type::I8 => self.base_type(ty, 8, DW_ATE_SIGNED),
+            Type::I16 => self.base_type(ty, 16, DW_ATE_SIGNED),
+            Type::I32 => self.base_type(ty, 32, DW_ATE_SIGNED),
+            Type::I64 => self.base_type(ty, 64, DW_ATE_SIGNED),
+            Type::USize => self.base_type(ty, POINTER_BITS, DW_ATE_UNSIGNED),
+            Type::ISize => self.base_type(ty, POINTER_BITS, DW_ATE_SIGNED),
+            Type::F64 => self.base_type(ty, 64, DW_ATE_FLOAT),
+            Type::StringLiteral => {
+                let u8_ty = self.ast_type(&Type::U8)?;
+                Some(self.pointer_type(ty, u8_ty))
+            }
+            Type::RawPointer(_, target) => {
+                let target = self.ast_type(target)?;
+                Some(self.pointer_type(ty, target))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the debug information type of a value of the given MIR base type
+    pub fn mir_type(&self, ty: &MirBaseType) -> Option<DIType<'ctx>> {
+        match ty {
+            MirBaseType::Bool => self.base_type(ty, 8, DW_ATE_BOOLEAN),
+            MirBaseType::U8 => self.base_type(ty, 8, DW_ATE_UNSIGNED),
+            MirBaseType::U16 => self.base_type(ty, 16, DW_ATE_UNSIGNED),
+            MirBaseType::U32 => self.base_type(ty, 32, DW_ATE_UNSIGNED),
+            MirBaseType::U64 => self.base_type(ty, 64, DW_ATE_UNSIGNED),
+            MirBaseType::I8 => self.base_type(ty, 8, DW_ATE_SIGNED),
+            MirBaseType::I16 => self.base_type(ty, 16, DW_ATE_SIGNED),
+            MirBaseType::I32 => self.base_type(ty, 32, DW_ATE_SIGNED),
+            MirBaseType::I64 => self.base_type(ty, 64, DW_ATE_SIGNED),
+            MirBaseType::USize => self.base_type(ty, POINTER_BITS, DW_ATE_UNSIGNED),
+            MirBaseType::ISize => self.base_type(ty, POINTER_BITS, DW_ATE_SIGNED),
+            MirBaseType::F64 => self.base_type(ty, 64, DW_ATE_FLOAT),
+            MirBaseType::StringLiteral => {
+                let u8_ty = self.mir_type(&MirBaseType::U8)?;
+                Some(self.pointer_type(ty, u8_ty))
+            }
+            MirBaseType::Unit | MirBaseType::Null => None,
+        }
+    }
+
+    /// Completes the debug information.  This must be called before the module is
+    /// verified or compiled.
+    pub fn finalize(&self) {
+        self.builder.finalize()
+    }
+
+    fn base_type<T: std::fmt::Display>(
+        &self,
+        ty: &T,
+        bits: u64,
+        encoding: u32,
+    ) -> Option<DIType<'ctx>> {
+        self.builder
+            .create_basic_type(&ty.to_string(), bits, encoding, DIFlags::PUBLIC)
+            .ok()
+            .map(|ty| ty.as_type())
+    }
+
+    fn pointer_type<T: std::fmt::Display>(&self, ty: &T, target: DIType<'ctx>) -> DIType<'ctx> {
+        self.builder
+            .create_pointer_type(
+                &ty.to_string(),
+                target,
+                POINTER_BITS,
+                POINTER_BITS as u32,
+                AddressSpace::Generic,
+            )
+            .as_type()
+    }
+
+    fn scope(&self) -> Option<DIScope<'ctx>> {
+        self.subprogram.map(|sp| sp.as_debug_info_scope())
+    }
+
+    /// Returns the file, line, and column of the start of `span`.  Columns are counted
+    /// in bytes from 1, which is what debuggers expect.
+    fn position(&mut self, span: Span) -> Option<(DIFile<'ctx>, u32, u32)> {
+        let offset = span.low();
+        let idx = (0..self.source_map.len()).find(|idx| {
+            let entry_span = self.source_map.get(*idx).unwrap().span();
+            entry_span.low() <= offset && offset < entry_span.high()
+        })?;
+
+        if !self.files.contains_key(&idx) {
+            let entry = self.source_map.get(idx)?;
+            let path = entry.remapped_path();
+            let file = self.builder.create_file(
+                &path
+                    .file_name()
+                    .map_or_else(String::new, |f| f.to_string_lossy().into_owned()),
+                &path
+                    .parent()
+                    .map_or_else(String::new, |d| d.to_string_lossy().into_owned()),
+            );
+            self.files.insert(idx, (file, entry.line_starts().ok()?));
+        }
+
+        let (file, starts) = &self.files[&idx];
+        let line = match starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let column = offset.as_u32() - starts[line].as_u32() + 1;
+        Some((*file, line as u32 + 1, column))
+    }
+}
+
+/// Returns the span of the character at the given line and column of the source file
+/// named `file`, which is the inverse of how [`DebugInfo`] describes a location: the
+/// file is named without its directory and lines and columns are counted from 1, with
+/// columns counted in bytes.  Returns [`None`] if there is no such file or location.
+pub(super) fn span_at(sm: &SourceMap, file: &str, line: u32, column: u32) -> Option<Span> {
+    if line == 0 || column == 0 {
+        return None;
+    }
+
+    let entry = (0..sm.len()).filter_map(|idx| sm.get(idx)).find(|entry| {
+        entry
+            .remapped_path()
+            .file_name()
+            .map_or(false, |f| f == file)
+    })?;
+    let starts = entry.line_starts().ok()?;
+    let start = starts.get(line as usize - 1)?;
+
+    let low = Offset::new(start.as_u32() + column - 1);
+    let high = Offset::new(low.as_u32() + 1);
+    (low < entry.span().high()).then(|| Span::new(low, high))
+}
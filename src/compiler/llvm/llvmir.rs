@@ -6,30 +6,25 @@
 /// This uses the LLVM C API to interface with LLVM and construct the
 /// Module. Resulting IR can then be fed into the LLVM Compiler to compile
 /// into native assembly or into a JIT.
-use std::{
-    cell::RefCell,
-    collections::{HashMap, HashSet},
-    convert::TryFrom,
-    error::Error,
-    rc::Rc,
-};
+use std::{cell::RefCell, collections::HashSet, convert::TryFrom, error::Error, rc::Rc};
 
 use inkwell::{
+    basic_block::BasicBlock,
     builder::Builder,
     context,
     execution_engine::{ExecutionEngine, JitFunction},
     module::Module,
     passes::PassManager,
-    targets::{CodeModel, InitializationConfig, RelocMode, Target},
     types::*,
     values::*,
-    AddressSpace, FloatPredicate, IntPredicate, OptimizationLevel,
+    AddressSpace, FloatPredicate, IntPredicate,
 };
 
 use crate::{
     compiler::{
         ast::{Element, Parameter, StructDef},
         diagnostics::{Event, EventId, EventStack, Logger, View, Writable},
+        ice::{IceContext, InternalCompilerError},
         import::{Import, ImportRoutineDef, ImportStructDef},
         parser::{ParserContext, ParserError},
         source::SourceIr,
@@ -42,14 +37,24 @@ use crate::{
 use crate::{
     compiler::{
         ast::{Context, Node, Path, Type},
-        semantics::semanticnode::SemanticContext,
+        semantics::{semanticnode::SemanticContext, typed::TypedModule},
     },
     project::manifest::Manifest,
 };
 
 use super::ast;
 
-use super::{scopestack::RegisterLookup, stringpool::StringPool};
+use super::{
+    abi::{self, Extension},
+    datasection::DataSection,
+    debuginfo::DebugInfo,
+    options::CodegenOptions,
+    remarks::RemarkCollector,
+    runtime,
+    scopestack::RegisterLookup,
+    stringpool::StringPool,
+    target,
+};
 
 const MEM_ALIGNMENT: u64 = 8;
 
@@ -60,14 +65,45 @@ pub struct IrGen<'ctx> {
     module: Module<'ctx>,
     builder: Builder<'ctx>,
     imports: &'ctx [Import],
-    string_pool: StringPool<'ctx>,
+    string_pool: StringPool,
+
+    /// The globals which hold the contents of the string pool
+    data_section: DataSection,
+
     registers: RegisterLookup<'ctx>,
-    struct_table: HashMap<String, ast::StructDef<SemanticContext>>,
+
+    /// The expressions deferred within each scope that is open, from the body of the
+    /// function to the innermost block, with the depth of the scope's registers.
+    defers: Vec<(usize, Vec<ast::Expression<SemanticContext>>)>,
+
+    /// The loops which enclose the current block, from the outermost to the innermost, with
+    /// the label of the loop, the block which checks its condition, the block which follows
+    /// it, and the number of scopes with deferred expressions which enclose the loop.
+    loops: Vec<(Option<StringId>, BasicBlock<'ctx>, BasicBlock<'ctx>, usize)>,
+    struct_table: ast::PathMap<ast::StructDef<SemanticContext>>,
+
+    /// The symbol which each extern is linked to, keyed by the canonical path of the extern
+    link_names: ast::PathMap<StringId>,
+    paths: ast::PathTable,
     fn_use_out_param: HashSet<String>,
     string_table: &'ctx StringTable,
     source_map: &'ctx SourceMap,
     logger: &'ctx Logger<'ctx>,
     event_stack: EventStack,
+    options: CodegenOptions,
+
+    /// The width, in bits, of a pointer on the target machine, which is also the width
+    /// of `usize` and `isize`
+    pointer_width: u32,
+
+    /// The item and nodes which code is currently being generated for
+    ice_context: IceContext,
+
+    /// Every internal compiler error which was caught while generating code
+    internal_errors: Vec<CompilerError<InternalCompilerError>>,
+
+    /// Describes the generated code to debuggers, if debug information was requested
+    debug_info: Option<DebugInfo<'ctx>>,
 }
 
 impl<'ctx> IrGen<'ctx> {
@@ -78,52 +114,85 @@ impl<'ctx> IrGen<'ctx> {
         source_map: &'ctx SourceMap,
         string_table: &'ctx StringTable,
         logger: &'ctx Logger,
+        options: CodegenOptions,
     ) -> IrGen<'ctx> {
+        let name = module;
+        let module = ctx.create_module(name);
+        let pointer_width = target::pointer_width(&options).unwrap();
+        let debug_info = if options.debug_info {
+            Some(DebugInfo::new(ctx, &module, name, source_map))
+        } else {
+            None
+        };
+
         IrGen {
             context: ctx,
-            module: ctx.create_module(module),
+            module,
             builder: ctx.create_builder(),
             imports,
-            string_pool: StringPool::new(string_table),
+            string_pool: StringPool::new(),
+            data_section: DataSection::new(),
             registers: RegisterLookup::new(),
-            struct_table: HashMap::new(),
+            defers: vec![],
+            loops: vec![],
+            struct_table: ast::PathMap::new(),
+            link_names: ast::PathMap::new(),
+            paths: ast::PathTable::new(),
             fn_use_out_param: HashSet::new(),
             source_map,
             string_table,
             logger,
             event_stack: EventStack::new(),
+            options,
+            pointer_width,
+            ice_context: IceContext::new(),
+            internal_errors: vec![],
+            debug_info,
         }
     }
 
+    /// Returns every internal compiler error which was caught while generating code.
+    /// If this is not empty then the generated LLVM IR is incomplete and must not be
+    /// used.
+    pub fn internal_errors(&self) -> &[CompilerError<InternalCompilerError>] {
+        &self.internal_errors
+    }
+
+    /// Returns the global data which was added to the module
+    pub fn data_section(&self) -> &DataSection {
+        &self.data_section
+    }
+
     /// Print the LLVM IR to stderr
     pub fn print_err(&self) {
         self.module.print_to_stderr();
     }
 
+    /// Runs LLVM's module verifier over the generated LLVM IR.  Returns the
+    /// verifier's description of every problem that it found.
+    pub fn verify(&self) -> std::result::Result<(), String> {
+        self.module.verify().map_err(|e| e.to_string())
+    }
+
     /// Print the LLVM IR to the given file
     pub fn emit_llvm_ir(&self, path: &std::path::Path) {
         self.module.print_to_file(path).unwrap()
     }
 
+    /// Run LLVM's optimization passes over the module, if optimization is enabled
+    pub fn optimize(&self) {
+        target::optimize_module(&self.module, &self.options)
+    }
+
+    /// Starts collecting the optimization remarks which LLVM reports while the module
+    /// is optimized and compiled
+    pub fn collect_remarks(&self) -> RemarkCollector<'ctx> {
+        RemarkCollector::install(&self.module)
+    }
+
     /// Compile the LLVM IR into an object file for the target platform
     pub fn emit_object_code(&self, path: &std::path::Path, emit_asm: bool) -> Result<()> {
-        // Get target for current machine
-        let triple = inkwell::targets::TargetMachine::get_default_triple();
-
-        let config = InitializationConfig::default();
-        inkwell::targets::Target::initialize_all(&config);
-        let target = inkwell::targets::Target::from_triple(&triple).unwrap();
-
-        let machine = target
-            .create_target_machine(
-                &triple,
-                "generic",
-                "",
-                OptimizationLevel::None,
-                RelocMode::Default,
-                CodeModel::Default,
-            )
-            .ok_or("Could not create a target machine for compilation")?;
+        let (machine, triple) = target::create_target_machine(&self.options)?;
         let data = machine.get_target_data();
 
         // Configure the module
@@ -149,6 +218,7 @@ impl<'ctx> IrGen<'ctx> {
     }
 
     /// Take the given Bramble AST to compile it to LLVM IR and add it to the LLVM module.
+    /// Only a [`TypedModule`] can be compiled, so every node is known to have a type.
     ///
     /// All user input is expected to be fully validated and correct by the time it reaches
     /// the compiler phase (via syntactic and semantic analysis).  Therefore, if anything
@@ -157,12 +227,7 @@ impl<'ctx> IrGen<'ctx> {
     /// error at this stage is unrecoverable; since its a bug in the compiler itself it cannot
     /// be trusted. So, if any unexpected state is encountered or any error happens this module
     /// will panic at that point in code and crash the compiler.
-    pub fn ingest(
-        &mut self,
-        m: &'ctx ast::Module<SemanticContext>,
-        user_main: StringId,
-    ) -> Result<()> {
-        self.compile_string_pool(m);
+    pub fn ingest(&mut self, m: &'ctx TypedModule, user_main: StringId) -> Result<()> {
         self.add_imports();
 
         self.add_mod_items(m);
@@ -176,6 +241,13 @@ impl<'ctx> IrGen<'ctx> {
             Some(_) => panic!("Expected None when compiling a Module"),
         };
 
+        if let Some(di) = &mut self.debug_info {
+            di.end_function(&self.builder);
+            di.finalize();
+        }
+
+        runtime::define_allocator_hooks(self.context, &self.module, &self.builder);
+
         Ok(())
     }
 
@@ -236,6 +308,12 @@ impl<'ctx> IrGen<'ctx> {
         let main = self.module.add_function("main", main_type, None);
         let entry_bb = self.context.append_basic_block(main, "entry");
         self.builder.position_at_end(entry_bb);
+        runtime::build_install_signal_handlers(
+            self.context,
+            &self.module,
+            &self.builder,
+            &self.options,
+        );
 
         let user_main_name = path.to_label(self.source_map, self.string_table);
         let user_main = self
@@ -300,6 +378,10 @@ impl<'ctx> IrGen<'ctx> {
             }
         }
 
+        for rd in m.get_methods() {
+            self.add_fn_def_decl(rd);
+        }
+
         for ex in m.get_externs() {
             if let ast::Item::Extern(ex) = ex {
                 self.add_extern_fn_decl(ex);
@@ -332,17 +414,21 @@ impl<'ctx> IrGen<'ctx> {
     fn add_extern_fn_decl(&mut self, ex: &'ctx ast::Extern<SemanticContext>) {
         // Declare external function
         let params: Vec<_> = ex.get_params().iter().map(|p| p.ty.clone()).collect();
-        let label = &ex
-            .context()
-            .canonical_path()
-            .to_label(self.source_map, self.string_table);
-        self.add_fn_decl(
-            label,
-            &params,
-            ex.has_varargs,
-            ex.get_return_type(),
-            ex.span(),
-        );
+        let link_name = ex.link_name();
+        let id = self.paths.insert(ex.context().canonical_path());
+        self.link_names.insert(id, link_name);
+
+        // Externs which are declared in more than one module are linked to the same symbol
+        let label = &self.string_table.get(link_name).unwrap();
+        if self.module.get_function(label).is_none() {
+            self.add_fn_decl(
+                label,
+                &params,
+                ex.has_varargs,
+                ex.get_return_type(),
+                ex.span(),
+            );
+        }
 
         let llvm_fn_decl = self.module.get_function(label).unwrap();
         self.record_terminal(ex.span(), &llvm_fn_decl);
@@ -362,12 +448,19 @@ impl<'ctx> IrGen<'ctx> {
         span: Span,
     ) {
         let mut llvm_params = vec![];
+        let mut param_exts = vec![];
 
         // If the return type is a structure, then update the function to use
         // a return parameter and make the function a void
         let llvm_ty = match ret_ty {
-            ast::Type::Custom(_) | ast::Type::Array(..) => {
+            ast::Type::Custom(_)
+            | ast::Type::Array(..)
+            | ast::Type::Slice(_)
+            | ast::Type::Range(_)
+            | ast::Type::Option(_)
+            | ast::Type::Result(..) => {
                 self.fn_use_out_param.insert(name.into());
+                param_exts.push(Extension::None);
 
                 let ptr_ty = ret_ty
                     .to_llvm_ir(self)
@@ -387,6 +480,7 @@ impl<'ctx> IrGen<'ctx> {
         .unwrap();
 
         for p in params {
+            param_exts.push(Extension::from_ast(p));
             let ty_llvm = p
                 .to_llvm_ir(self)
                 .map_err(|e| format!("S{}: {}", span, e))
@@ -408,17 +502,29 @@ impl<'ctx> IrGen<'ctx> {
             AnyTypeEnum::VoidType(vty) => vty.fn_type(&llvm_params, has_var_arg),
             _ => panic!("Unexpected type: {:?}", llvm_ty),
         };
-        self.module.add_function(name, fn_type, None);
+        let function = self.module.add_function(name, fn_type, None);
+        if self.fn_use_out_param.contains(name) {
+            abi::add_out_param_attributes(self.context, function);
+        }
+        abi::add_extension_attributes(
+            self.context,
+            function,
+            &param_exts,
+            Extension::from_ast(ret_ty),
+        );
+    }
+
+    /// Look up the definition of the struct with the given canonical path.
+    fn get_struct_def(&self, path: &ast::Path) -> Option<&ast::StructDef<SemanticContext>> {
+        self.paths
+            .find(path)
+            .and_then(|id| self.struct_table.get(id))
     }
 
     /// Add a struct definition to the LLVM context and module.
     fn add_struct_def(&mut self, sd: &'ctx ast::StructDef<SemanticContext>) {
-        self.struct_table.insert(
-            sd.context()
-                .canonical_path()
-                .to_label(self.source_map, self.string_table),
-            sd.clone(),
-        );
+        let id = self.paths.insert(sd.context().canonical_path());
+        self.struct_table.insert(id, sd.clone());
         let name = sd
             .context()
             .canonical_path()
@@ -464,26 +570,11 @@ impl<'ctx> IrGen<'ctx> {
             })
             .collect();
 
-        self.struct_table.insert(name, sd.into());
+        let id = self.paths.insert(sd.path());
+        self.struct_table.insert(id, sd.into());
         struct_ty.set_body(&fields_llvm, false);
     }
 
-    /// Add all string literals to the data section of the assemby output
-    fn compile_string_pool(&mut self, m: &ast::Module<SemanticContext>) {
-        self.string_pool.extract_from_module(m);
-
-        for (s, id) in self.string_pool.pool.iter() {
-            let escaped_s = convert_esc_seq_to_ascii(s).unwrap();
-            let len_w_null = escaped_s.len() + 1;
-            let g = self.module.add_global(
-                self.context.i8_type().array_type(len_w_null as u32),
-                None,
-                &self.get_stringpool_label(*id),
-            );
-            g.set_initializer(&self.context.const_string(escaped_s.as_bytes(), true));
-        }
-    }
-
     fn build_memcpy(&self, dest: PointerValue<'ctx>, src: PointerValue<'ctx>, span: Span) {
         let dest_align = get_ptr_alignment(dest);
         let src_align = get_ptr_alignment(src);
@@ -500,6 +591,41 @@ impl<'ctx> IrGen<'ctx> {
         self.record_terminal(span, &mc);
     }
 
+    /// Allocates space on the stack for a value of type `ty`.  If zero initialization
+    /// of locals is enabled, then the allocated memory will be set to zero.
+    fn build_alloca<T: BasicType<'ctx>>(&self, ty: T, name: &str) -> PointerValue<'ctx> {
+        let ptr = self.builder.build_alloca(ty, name);
+        if self.options.zero_init_locals {
+            build_zero_init(self.context, &self.builder, ptr);
+        }
+        ptr
+    }
+
+    /// If debug information is being generated, then marks the instructions which are
+    /// built from now on as coming from the code at `span`.
+    fn set_debug_location(&mut self, span: Span) {
+        if let Some(di) = &mut self.debug_info {
+            di.set_location(&self.builder, span)
+        }
+    }
+
+    /// If debug information is being generated, then describes the variable `name`,
+    /// of type `ty`, which is stored at `ptr`.  Parameters are numbered, from 1, by
+    /// `arg_no`.
+    fn declare_debug_variable(
+        &mut self,
+        ptr: PointerValue<'ctx>,
+        name: &str,
+        ty: &Type,
+        span: Span,
+        arg_no: Option<u32>,
+    ) {
+        if let Some(di) = &mut self.debug_info {
+            let di_ty = di.ast_type(ty);
+            di.declare_variable(&self.builder, ptr, name, di_ty, span, arg_no)
+        }
+    }
+
     /// If the LLVM builder cursor is currently within a function, this will
     /// return that function.  Otherwise it will return `None`.
     fn get_current_fn(&self) -> Option<FunctionValue> {
@@ -508,13 +634,334 @@ impl<'ctx> IrGen<'ctx> {
             .and_then(|bb| bb.get_parent())
     }
 
-    /// Will look for `s` in the string pool, if found, it will return the
-    /// name of the global variable that is bound to that string. Otherwise,
-    /// it will return `None`
-    fn get_str_var(&self, s: &str) -> Option<String> {
-        self.string_pool
-            .get(s)
-            .map(|id| self.get_stringpool_label(*id))
+    /// Returns the name of the global variable that is bound to the string `s`.  The
+    /// first time a string is used, it is added to the string pool and a global
+    /// variable holding the string is added to the data section of the output.
+    ///
+    /// Every pooled string is NUL-terminated, which is what allows a string literal to be
+    /// cast to `*const u8` (e.g. `c"hello"`) and given to C functions.
+    fn get_str_var(&mut self, s: StringId) -> String {
+        let (id, is_new) = self.string_pool.insert(s);
+        let label = self.get_stringpool_label(id);
+
+        if is_new {
+            let val = self.string_table.get(s).unwrap();
+            let escaped_s = convert_esc_seq_to_ascii(&val).unwrap();
+            let len_w_null = escaped_s.len() + 1;
+            let g = self.module.add_global(
+                self.context.i8_type().array_type(len_w_null as u32),
+                None,
+                &label,
+            );
+            g.set_initializer(&self.context.const_string(escaped_s.as_bytes(), true));
+            self.data_section
+                .add(&label, format!("{}\0", escaped_s).as_bytes());
+        }
+
+        label
+    }
+
+    /// Returns a pointer to the characters of the string `s`, which has type `ty`, and
+    /// the length of the string.
+    fn build_string_parts(
+        &self,
+        s: BasicValueEnum<'ctx>,
+        ty: &Type,
+    ) -> (PointerValue<'ctx>, IntValue<'ctx>) {
+        let s = s.into_pointer_value();
+        match ty {
+            Type::String => runtime::build_string_parts(self.context, &self.builder, s),
+            Type::StringLiteral => {
+                runtime::build_string_literal_parts(self.context, &self.module, &self.builder, s)
+            }
+            _ => panic!("Expected a string but found {}", ty),
+        }
+    }
+
+    /// Returns a pointer to the element at `index` of `array`, which is either an array or
+    /// a slice.  If bounds checks are enabled, then the index is checked first.
+    fn build_element_ptr(
+        &mut self,
+        array: &ast::Expression<SemanticContext>,
+        index: &ast::Expression<SemanticContext>,
+    ) -> PointerValue<'ctx> {
+        // evalute the array to get the ptr to the array
+        let llvm_array_ptr = match array.to_llvm_ir(self) {
+            Some(a) if a.is_pointer_value() => a.into_pointer_value(),
+            Some(a) => panic!("Unexpected type for array: {:?}", a),
+            None => panic!("Could not convert type {} to LLVM type", array),
+        };
+
+        // evaluate the index to get the index value
+        let llvm_index = index.to_llvm_ir(self).unwrap().into_int_value();
+        let is_signed = index.get_type().is_signed();
+
+        // Compute the GEP
+        match array.get_type() {
+            Type::Slice(_) => {
+                let (data, len) = runtime::build_slice_parts(&self.builder, llvm_array_ptr);
+                self.build_bounds_check(llvm_index, is_signed, len);
+                unsafe { self.builder.build_gep(data, &[llvm_index], "") }
+            }
+            Type::Array(_, len) => {
+                let len = self.context.i64_type().const_int(*len as u64, false);
+                self.build_bounds_check(llvm_index, is_signed, len);
+                let outer_idx = self.context.i64_type().const_int(0, false);
+                unsafe {
+                    self.builder
+                        .build_gep(llvm_array_ptr, &[outer_idx, llvm_index], "")
+                }
+            }
+            ty => panic!("Expected an array or slice but found {}", ty),
+        }
+    }
+
+    /// Returns the elements of `array`, which is an array, a slice, or a range, from the
+    /// start of the range `index` up to its end.  The result, of type `ty`, is a slice of
+    /// an array or slice and a range of a range.  If bounds checks are enabled, then the
+    /// range is checked against the length of `array` first.
+    fn build_subsequence(
+        &mut self,
+        ty: &Type,
+        array: &ast::Expression<SemanticContext>,
+        index: &ast::Expression<SemanticContext>,
+    ) -> PointerValue<'ctx> {
+        let llvm_array_ptr = array.to_llvm_ir(self).unwrap().into_pointer_value();
+        let llvm_index = index.to_llvm_ir(self).unwrap().into_pointer_value();
+        let is_signed = match index.get_type() {
+            Type::Range(el_ty) => el_ty.is_signed(),
+            ty => panic!("Expected a range but found {}", ty),
+        };
+
+        let (start, end) = runtime::build_range_parts(&self.builder, llvm_index);
+        let start = self.build_int_to_i64(start, is_signed);
+        let end = self.build_int_to_i64(end, is_signed);
+
+        match array.get_type() {
+            Type::Array(_, len) => {
+                let len = self.context.i64_type().const_int(*len as u64, false);
+                self.build_range_check(start, end, len);
+                let outer_idx = self.context.i64_type().const_int(0, false);
+                let data = unsafe {
+                    self.builder
+                        .build_gep(llvm_array_ptr, &[outer_idx, start], "")
+                };
+                let len = self.builder.build_int_sub(end, start, "");
+                self.build_slice(ty, data, len)
+            }
+            Type::Slice(_) => {
+                let (data, len) = runtime::build_slice_parts(&self.builder, llvm_array_ptr);
+                self.build_range_check(start, end, len);
+                let data = unsafe { self.builder.build_gep(data, &[start], "") };
+                let len = self.builder.build_int_sub(end, start, "");
+                self.build_slice(ty, data, len)
+            }
+            Type::Range(el_ty) => {
+                let len = self.build_range_len(llvm_array_ptr, el_ty.is_signed());
+                self.build_range_check(start, end, len);
+
+                // The bounds of the new range are offsets from the start of the old range
+                let (first, _) = runtime::build_range_parts(&self.builder, llvm_array_ptr);
+                let start =
+                    self.builder
+                        .build_int_truncate_or_bit_cast(start, first.get_type(), "");
+                let end = self
+                    .builder
+                    .build_int_truncate_or_bit_cast(end, first.get_type(), "");
+                self.build_range(
+                    self.builder.build_int_add(first, start, ""),
+                    self.builder.build_int_add(first, end, ""),
+                )
+            }
+            ty => panic!("Expected an array, slice, or range but found {}", ty),
+        }
+    }
+
+    /// Returns the element at `index` of `range`.  If bounds checks are enabled, then the
+    /// index is checked against the length of the range first.
+    fn build_range_element(
+        &mut self,
+        range: &ast::Expression<SemanticContext>,
+        index: &ast::Expression<SemanticContext>,
+    ) -> IntValue<'ctx> {
+        let llvm_range_ptr = range.to_llvm_ir(self).unwrap().into_pointer_value();
+        let llvm_index = index.to_llvm_ir(self).unwrap().into_int_value();
+        let is_signed = index.get_type().is_signed();
+        let el_signed = match range.get_type() {
+            Type::Range(el_ty) => el_ty.is_signed(),
+            ty => panic!("Expected a range but found {}", ty),
+        };
+
+        let len = self.build_range_len(llvm_range_ptr, el_signed);
+        self.build_bounds_check(llvm_index, is_signed, len);
+
+        let (start, _) = runtime::build_range_parts(&self.builder, llvm_range_ptr);
+        let offset = self.build_int_to_i64(llvm_index, is_signed);
+        let offset = self
+            .builder
+            .build_int_truncate_or_bit_cast(offset, start.get_type(), "");
+        self.builder.build_int_add(start, offset, "")
+    }
+
+    /// Returns a new range from `start` to `end`.
+    fn build_range(&self, start: IntValue<'ctx>, end: IntValue<'ctx>) -> PointerValue<'ctx> {
+        let range_ty = runtime::range_type(self.context, start.get_type().into());
+        let range = self.build_entry_alloca(range_ty);
+
+        let start_ptr = self.builder.build_struct_gep(range, 0, "").unwrap();
+        self.builder.build_store(start_ptr, start);
+        let end_ptr = self.builder.build_struct_gep(range, 1, "").unwrap();
+        self.builder.build_store(end_ptr, end);
+        range
+    }
+
+    /// Returns the number of elements in `range`, as an `i64`.  A range whose end is not
+    /// greater than its start is empty.
+    fn build_range_len(&self, range: PointerValue<'ctx>, is_signed: bool) -> IntValue<'ctx> {
+        let (start, end) = runtime::build_range_parts(&self.builder, range);
+        let start = self.build_int_to_i64(start, is_signed);
+        let end = self.build_int_to_i64(end, is_signed);
+
+        let greater = if is_signed {
+            IntPredicate::SGT
+        } else {
+            IntPredicate::UGT
+        };
+        let is_not_empty = self.builder.build_int_compare(greater, end, start, "");
+        let len = self.builder.build_int_sub(end, start, "");
+        let zero = self.context.i64_type().const_zero();
+        self.builder
+            .build_select(is_not_empty, len, zero, "")
+            .into_int_value()
+    }
+
+    /// Returns a new slice, of type `ty`, of the `len` elements starting at `data`.
+    fn build_slice(
+        &self,
+        ty: &Type,
+        data: PointerValue<'ctx>,
+        len: IntValue<'ctx>,
+    ) -> PointerValue<'ctx> {
+        let slice_ty = ty.to_llvm_ir(self).unwrap().into_struct_type();
+        let slice = self.build_entry_alloca(slice_ty);
+
+        let data_ptr = self.builder.build_struct_gep(slice, 0, "").unwrap();
+        self.builder.build_store(data_ptr, data);
+        let len_ptr = self.builder.build_struct_gep(slice, 1, "").unwrap();
+        self.builder.build_store(len_ptr, len);
+        slice
+    }
+
+    /// Returns a new option or result, of type `ty`, with the given tag, which holds `value`
+    /// in the field with the given index (see [`IrGen::store_tagged`]).
+    fn build_tagged(
+        &self,
+        ty: &Type,
+        tag: bool,
+        value: Option<(u32, BasicValueEnum<'ctx>)>,
+        span: Span,
+    ) -> PointerValue<'ctx> {
+        let tagged_ty = ty.to_llvm_ir(self).unwrap().into_struct_type();
+        let tagged = self.build_entry_alloca(tagged_ty);
+        self.store_tagged(tagged, tag, value, span);
+        tagged
+    }
+
+    /// Stores an option or result into `dest`.  The tag is `true` if an option holds a
+    /// value or if a result holds a success value, and `value` is stored into the field with
+    /// the given index: 1 for the value of an option or the success value of a result, and 2
+    /// for the error value of a result.  A value of an aggregate type is given as a pointer
+    /// to it, and is copied.
+    fn store_tagged(
+        &self,
+        dest: PointerValue<'ctx>,
+        tag: bool,
+        value: Option<(u32, BasicValueEnum<'ctx>)>,
+        span: Span,
+    ) {
+        let tag_ptr = self.builder.build_struct_gep(dest, 0, "").unwrap();
+        let tag = self.context.bool_type().const_int(tag as u64, false);
+        self.builder.build_store(tag_ptr, tag);
+
+        if let Some((idx, value)) = value {
+            let value_ptr = self.builder.build_struct_gep(dest, idx, "").unwrap();
+            if value_ptr.get_type().get_element_type().is_aggregate_type() {
+                self.build_memcpy(value_ptr, value.into_pointer_value(), span);
+            } else {
+                self.builder.build_store(value_ptr, value);
+            }
+        }
+    }
+
+    /// Returns the field, with the given index, of the option or result that `tagged`
+    /// points to.  A field of an aggregate type is returned as a pointer to it.
+    fn build_tagged_field(&self, tagged: PointerValue<'ctx>, idx: u32) -> BasicValueEnum<'ctx> {
+        let field_ptr = self.builder.build_struct_gep(tagged, idx, "").unwrap();
+        if field_ptr.get_type().get_element_type().is_aggregate_type() {
+            field_ptr.into()
+        } else {
+            self.builder.build_load(field_ptr, "")
+        }
+    }
+
+    /// Allocates space on the stack, in the entry block of the current function, for a
+    /// value of type `ty`.
+    ///
+    /// Ranges, slices, and options are often built within loops (e.g. by `for`, which takes
+    /// the rest of a sequence on every iteration), so their space is allocated once for the
+    /// function, rather than once for each iteration.  Like [`IrGen::build_alloca`], the
+    /// space is set to zero if zero initialization of locals is enabled.
+    fn build_entry_alloca<T: BasicType<'ctx>>(&self, ty: T) -> PointerValue<'ctx> {
+        let entry = self
+            .get_current_fn()
+            .and_then(|f| f.get_first_basic_block())
+            .unwrap();
+        let entry_builder = self.context.create_builder();
+        match entry.get_first_instruction() {
+            Some(first) => entry_builder.position_before(&first),
+            None => entry_builder.position_at_end(entry),
+        }
+        let ptr = entry_builder.build_alloca(ty, "");
+        if self.options.zero_init_locals {
+            build_zero_init(self.context, &entry_builder, ptr);
+        }
+        ptr
+    }
+
+    /// Extends `value` to an `i64`, with a sign extension if `is_signed` is `true`.
+    fn build_int_to_i64(&self, value: IntValue<'ctx>, is_signed: bool) -> IntValue<'ctx> {
+        let i64_ty = self.context.i64_type();
+        if is_signed {
+            self.builder
+                .build_int_s_extend_or_bit_cast(value, i64_ty, "")
+        } else {
+            self.builder
+                .build_int_z_extend_or_bit_cast(value, i64_ty, "")
+        }
+    }
+
+    fn build_range_check(&self, start: IntValue<'ctx>, end: IntValue<'ctx>, len: IntValue<'ctx>) {
+        runtime::build_range_check(
+            self.context,
+            &self.module,
+            &self.builder,
+            start,
+            end,
+            len,
+            &self.options,
+        )
+    }
+
+    fn build_bounds_check(&self, index: IntValue<'ctx>, is_signed: bool, len: IntValue<'ctx>) {
+        runtime::build_bounds_check(
+            self.context,
+            &self.module,
+            &self.builder,
+            index,
+            is_signed,
+            len,
+            &self.options,
+        )
     }
 
     /// Convert the ID of a string to the name of the global variable that
@@ -542,6 +989,114 @@ impl<'ctx> IrGen<'ctx> {
 
     /// Start a new event with no set Result. Events created after this poing and
     /// before this [`Event`] is dropped will be descendents of this [`Event`].
+    /// Returns `true` if the block that the builder is currently inserting into already
+    /// ends with a terminator instruction (e.g. because of a return statement).
+    fn is_terminated(&self) -> bool {
+        self.builder
+            .get_insert_block()
+            .and_then(|bb| bb.get_terminator())
+            .is_some()
+    }
+
+    /// Starts collecting the expressions which are deferred within the innermost scope of
+    /// registers.
+    fn open_defer_scope(&mut self) {
+        self.defers.push((self.registers.depth(), vec![]));
+    }
+
+    /// Exits the innermost scope by evaluating the expressions that were deferred within
+    /// it, unless the end of the scope cannot be reached.
+    fn close_defer_scope(&mut self) {
+        if !self.is_terminated() {
+            self.build_defers(self.defers.len() - 1);
+        }
+        self.defers.pop();
+    }
+
+    /// Evaluates the expressions which were deferred within every open scope, from the
+    /// innermost out, because the function is returning.
+    fn build_all_defers(&mut self) {
+        for depth in (0..self.defers.len()).rev() {
+            self.build_defers(depth);
+        }
+    }
+
+    /// Evaluates the expressions deferred within the scope at `depth`, the most recently
+    /// deferred first, with identifiers looked up as they were in that scope.
+    fn build_defers(&mut self, depth: usize) {
+        let (registers, defers) = self.defers[depth].clone();
+        let inner = self.registers.suspend(registers);
+        for exp in defers.iter().rev() {
+            exp.to_llvm_ir(self);
+        }
+        self.registers.reopen(inner);
+    }
+
+    /// Leaves the innermost loop with the given label, or the innermost loop if there is
+    /// no label, by evaluating the expressions deferred within the loop and then branching
+    /// to either the condition of the loop (`continue`) or the block which follows it
+    /// (`break`).
+    fn build_leave_loop(&mut self, label: Option<StringId>, to_cond: bool, span: Span) {
+        let &(_, cond_bb, after_bb, depth) = self
+            .loops
+            .iter()
+            .rev()
+            .find(|(l, ..)| label.is_none() || *l == label)
+            .expect("break or continue outside of a loop");
+
+        for depth in (depth..self.defers.len()).rev() {
+            self.build_defers(depth);
+        }
+
+        let event = self.new_event(span);
+        let br = self
+            .builder
+            .build_unconditional_branch(if to_cond { cond_bb } else { after_bb });
+        self.record(event, &br);
+    }
+
+    /// Code which follows a return is unreachable, but LLVM does not allow instructions
+    /// after a terminator. So, if the current block has been terminated, this moves the
+    /// builder to a new block which the unreachable code can be placed into.
+    fn skip_terminator(&self) {
+        if self.is_terminated() {
+            let current_fn = self.get_current_fn().unwrap();
+            let unreachable_bb = self.context.append_basic_block(current_fn, "unreachable");
+            self.builder.position_at_end(unreachable_bb);
+        }
+    }
+
+    /// Completes the current arm of an if expression by branching to `merge_bb`. Returns the
+    /// value of the arm and the block that it exits from, or `None` if the arm diverged and
+    /// so will never reach `merge_bb` or if the arm has no value.
+    fn build_if_arm_exit(
+        &self,
+        arm_val: Option<BasicValueEnum<'ctx>>,
+        arm_ty: &ast::Type,
+        merge_bb: BasicBlock<'ctx>,
+        span: Span,
+    ) -> Option<(BasicValueEnum<'ctx>, BasicBlock<'ctx>)> {
+        if self.is_terminated() {
+            return None;
+        }
+
+        let event = self.new_event(span);
+
+        // An arm of type never, which has not been terminated, cannot be reached
+        if *arm_ty == ast::Type::Never {
+            let unreachable = self.builder.build_unreachable();
+            self.record(event, &unreachable);
+            return None;
+        }
+
+        // The builders position may change while compiling the arm
+        let arm_bb = self.builder.get_insert_block().unwrap();
+        let br = self.builder.build_unconditional_branch(merge_bb);
+        self.record(event, &br);
+
+        arm_val.map(|v| (v, arm_bb))
+    }
+
     fn new_event<'a, IR: Writable>(&self, span: Span) -> Event<'a, IR, ParserError> {
         Event::new("llvm", span, self.event_stack.clone())
     }
@@ -575,11 +1130,32 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Module<SemanticContext> {
             m.to_llvm_ir(llvm);
         }
 
-        for f in self.get_functions() {
-            if let ast::Item::Routine(rdef) = f {
-                let fn_val = rdef
-                    .to_llvm_ir(llvm)
-                    .expect("Expected Function Value from RoutineDef");
+        let routines = self
+            .get_functions()
+            .iter()
+            .filter_map(|f| f.to_routine())
+            .chain(self.get_methods());
+        for rdef in routines {
+            // Unless asked to continue, stop generating code after the first
+            // internal compiler error
+            if !llvm.internal_errors.is_empty() && !llvm.options.continue_on_ice {
+                break;
+            }
+
+            let ice_context = llvm.ice_context.clone();
+            let path = rdef.context().canonical_path();
+            match ice_context.catch(path, rdef.span(), || rdef.to_llvm_ir(llvm)) {
+                Ok(fn_val) => {
+                    fn_val.expect("Expected Function Value from RoutineDef");
+                }
+                Err(ice) => {
+                    // Discard the registers and deferred expressions of the function
+                    // that failed so that the next function starts from a clean state
+                    llvm.registers = RegisterLookup::new();
+                    llvm.defers.clear();
+                    llvm.loops.clear();
+                    llvm.internal_errors.push(ice);
+                }
             }
         }
 
@@ -606,8 +1182,13 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::RoutineDef<SemanticContext> {
             .expect("Could not find function");
         let entry_bb = llvm.context.append_basic_block(fn_value, "entry");
         llvm.builder.position_at_end(entry_bb);
+        if let Some(di) = &mut llvm.debug_info {
+            let name = llvm.string_table.get(self.name).unwrap();
+            di.begin_function(&llvm.builder, fn_value, &name, self.span());
+        }
 
         llvm.registers.open_fn().unwrap();
+        llvm.open_defer_scope();
         let llvm_params = fn_value.get_params();
         let num_params = llvm_params.len();
 
@@ -627,12 +1208,17 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::RoutineDef<SemanticContext> {
             let pname = llvm.string_table.get(*pid).unwrap();
 
             // move parameter into the stack
-            let pptr = llvm
-                .builder
-                .build_alloca(llvm_params[pi].get_type(), &pname);
+            let pptr = llvm.build_alloca(llvm_params[pi].get_type(), &pname);
             llvm.record_terminal(param.span(), &pptr);
             let st = llvm.builder.build_store(pptr, llvm_params[pi]);
             llvm.record_terminal(param.span(), &st);
+            llvm.declare_debug_variable(
+                pptr,
+                &pname,
+                &param.ty,
+                param.span(),
+                Some((pi - start + 1) as u32),
+            );
             llvm.registers.insert(&pname, pptr.into()).unwrap();
         }
 
@@ -641,7 +1227,11 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::RoutineDef<SemanticContext> {
             let value = stm.to_llvm_ir(llvm);
         }
 
+        llvm.close_defer_scope();
         llvm.registers.close_fn().unwrap();
+        if let Some(di) = &mut llvm.debug_info {
+            di.end_function(&llvm.builder);
+        }
         llvm.record(event, &fn_value);
 
         Some(fn_value)
@@ -652,11 +1242,32 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Statement<SemanticContext> {
     type Value = AnyValueEnum<'ctx>;
 
     fn to_llvm_ir(&self, llvm: &mut IrGen<'ctx>) -> Option<Self::Value> {
+        let _node = llvm.ice_context.enter(self.span());
+        llvm.skip_terminator();
+        llvm.set_debug_location(self.span());
+
         match self {
             ast::Statement::Return(ret) => ret.to_llvm_ir(llvm).map(|i| i.into()),
             ast::Statement::Expression(exp) => exp.to_llvm_ir(llvm).map(|v| v.into()),
             ast::Statement::Bind(bind) => bind.to_llvm_ir(llvm).map(|i| i.into()),
             ast::Statement::Mutate(mutate) => mutate.to_llvm_ir(llvm).map(|i| i.into()),
+            ast::Statement::Discard(discard) => {
+                discard.get_value().to_llvm_ir(llvm);
+                None
+            }
+            ast::Statement::Defer(defer) => {
+                let (_, defers) = llvm.defers.last_mut().unwrap();
+                defers.push(defer.get_value().clone());
+                None
+            }
+            ast::Statement::Break(b) => {
+                llvm.build_leave_loop(b.get_label(), false, b.span());
+                None
+            }
+            ast::Statement::Continue(c) => {
+                llvm.build_leave_loop(c.get_label(), true, c.span());
+                None
+            }
             ast::Statement::YieldReturn(_) => todo!("Coroutines not yet implemented: {}", self),
         }
     }
@@ -677,15 +1288,27 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Bind<SemanticContext> {
             .unwrap()
             .into_basic_type()
         {
+            Ok(ty) if self.get_rhs().get_type() == ast::Type::Never => {
+                // The rhs diverges, so there is no value to store in the variable
+                let alloca_event = llvm.new_event(self.span());
+                let ptr = llvm.build_alloca(ty, &name);
+                self.get_rhs().to_llvm_ir(llvm);
+
+                llvm.declare_debug_variable(ptr, &name, self.get_type(), self.span(), None);
+                llvm.registers.insert(&name, ptr.into()).unwrap();
+                llvm.record(alloca_event, &ptr);
+                Some(ptr)
+            }
             Ok(ty) if ty.is_aggregate_type() => {
                 let rhs = self.get_rhs().to_llvm_ir(llvm).unwrap();
                 let rhs_ptr = rhs.into_pointer_value();
 
                 let alloca_event = llvm.new_event(self.span());
-                let dest = llvm.builder.build_alloca(ty, &name);
+                let dest = llvm.build_alloca(ty, &name);
 
                 llvm.build_memcpy(dest, rhs_ptr, self.span());
 
+                llvm.declare_debug_variable(dest, &name, self.get_type(), self.span(), None);
                 llvm.registers.insert(&name, dest.into()).unwrap();
                 llvm.record(alloca_event, &dest);
                 Some(dest)
@@ -693,11 +1316,12 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Bind<SemanticContext> {
             Ok(ty) => {
                 let store_event = llvm.new_event(self.span());
                 let alloca_event = llvm.new_event(self.span());
-                let ptr = llvm.builder.build_alloca(ty, &name);
+                let ptr = llvm.build_alloca(ty, &name);
 
                 let rhs = self.get_rhs().to_llvm_ir(llvm).unwrap();
                 let st = llvm.builder.build_store(ptr, rhs);
 
+                llvm.declare_debug_variable(ptr, &name, self.get_type(), self.span(), None);
                 llvm.registers.insert(&name, ptr.into()).unwrap();
                 llvm.record(alloca_event, &ptr);
                 llvm.record(store_event, &st);
@@ -715,11 +1339,20 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Mutate<SemanticContext> {
     fn to_llvm_ir(&self, llvm: &mut IrGen<'ctx>) -> Option<Self::Value> {
         let event = llvm.new_event(self.span());
 
-        let rhs = self.get_rhs().to_llvm_ir(llvm).unwrap();
+        let rhs = self.get_rhs().to_llvm_ir(llvm);
+        if self.get_rhs().get_type() == ast::Type::Never {
+            // The rhs diverges, so there is no value to store
+            return None;
+        }
+        let rhs = rhs.unwrap();
         let lhs_ptr = self.get_lhs().to_address(llvm).unwrap();
 
-        let st = llvm.builder.build_store(lhs_ptr, rhs);
-        llvm.record(event, &st);
+        if lhs_ptr.get_type().get_element_type().is_aggregate_type() {
+            llvm.build_memcpy(lhs_ptr, rhs.into_pointer_value(), self.span());
+        } else {
+            let st = llvm.builder.build_store(lhs_ptr, rhs);
+            llvm.record(event, &st);
+        }
 
         Some(lhs_ptr)
     }
@@ -731,23 +1364,40 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Return<SemanticContext> {
     fn to_llvm_ir(&self, llvm: &mut IrGen<'ctx>) -> Option<Self::Value> {
         let event = llvm.new_event(self.span());
         Some(match self.get_value() {
-            None => llvm.builder.build_return(None),
+            None => {
+                llvm.build_all_defers();
+                llvm.builder.build_return(None)
+            }
             Some(val) => {
                 match val.get_type() {
+                    // The returned expression diverges before producing a value, so this return
+                    // can never be reached
+                    ast::Type::Never => {
+                        val.to_llvm_ir(llvm);
+                        llvm.skip_terminator();
+                        llvm.builder.build_unreachable()
+                    }
                     // Instead of type use the table that indicates the out parameter was added
                     // TODO: I think that this can be linked to the `llvm.fn_out_params` table. I do it with Return
-                    ast::Type::Custom(_) | ast::Type::Array(..) => {
+                    ast::Type::Custom(_)
+                    | ast::Type::Array(..)
+                    | ast::Type::Slice(_)
+                    | ast::Type::Range(_)
+                    | ast::Type::Option(_)
+                    | ast::Type::Result(..) => {
                         let out = llvm.registers.get(".out").unwrap().into_pointer_value();
                         let src_ptr = val.to_llvm_ir(llvm).unwrap().into_pointer_value();
                         llvm.build_memcpy(out, src_ptr, self.span());
 
                         // Use the return parameter as a ptr to memory to store the struct and copy it there
+                        llvm.build_all_defers();
                         llvm.builder.build_return(None)
                     }
                     _ => {
                         let val = val
                             .to_llvm_ir(llvm)
                             .expect("Return expression did not compile to an LLVM value");
+                        llvm.build_all_defers();
                         llvm.builder.build_return(Some(&val))
                     }
                 }
@@ -761,6 +1411,8 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
     type Value = BasicValueEnum<'ctx>;
 
     fn to_address(&self, llvm: &mut IrGen<'ctx>) -> Option<PointerValue<'ctx>> {
+        let _node = llvm.ice_context.enter(self.span());
+
         // If this value is not addressable then do _not_ return a pointer
         // The semantic analyzer should prevent this situation from happening but just in case
         if !self.context().is_addressable() {
@@ -778,36 +1430,11 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
                 context: ctx,
                 array,
                 index,
-            } => {
-                // evalute the array to get the ptr to the array
-                // Check the array type, if it's not a pointer then get the GEP
-                let llvm_array_ptr = match array.to_llvm_ir(llvm) {
-                    Some(a) if a.is_pointer_value() => a.into_pointer_value(),
-                    Some(a) => panic!("Unexpected type for array: {:?}", a),
-                    None => panic!("Could not convert type {} to LLVM type", array),
-                };
-
-                // evaluate the index to get the index value
-                let llvm_index = index.to_llvm_ir(llvm).unwrap().into_int_value();
-
-                // Compute the GEP
-                let outer_idx = llvm.context.i64_type().const_int(0, false);
-                let el_ptr = unsafe {
-                    llvm.builder
-                        .build_gep(llvm_array_ptr, &[outer_idx, llvm_index], "")
-                };
-                Some(el_ptr)
-            }
+            } => Some(llvm.build_element_ptr(array, index)),
             ast::Expression::MemberAccess(ctx, val, field) => {
                 let event = llvm.new_event(self.span());
                 let sdef = llvm
-                    .struct_table
-                    .get(
-                        &val.get_type()
-                            .get_path()
-                            .unwrap()
-                            .to_label(llvm.source_map, llvm.string_table),
-                    )
+                    .get_struct_def(val.get_type().get_path().unwrap())
                     .unwrap();
 
                 let field_idx = sdef.get_field_idx(*field).unwrap();
@@ -831,6 +1458,7 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
     }
 
     fn to_llvm_ir(&self, llvm: &mut IrGen<'ctx>) -> Option<Self::Value> {
+        let _node = llvm.ice_context.enter(self.span());
         match self {
             ast::Expression::Null(_) => {
                 let zero = llvm.context.i64_type().const_zero();
@@ -844,6 +1472,9 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
                         .into(),
                 )
             }
+            ast::Expression::OptionNone(ctx) => {
+                Some(llvm.build_tagged(ctx.ty(), false, None, self.span()).into())
+            }
             ast::Expression::U8(_, i) => {
                 let u8t = llvm.context.i8_type();
                 Some(u8t.const_int(*i as u64, false).into())
@@ -896,8 +1527,7 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
                 Some(bt.const_int(*b as u64, true).into()).view(|ir| llvm.record(event, ir))
             }
             ast::Expression::StringLiteral(_, s) => {
-                let val = llvm.string_table.get(*s).unwrap();
-                let str_id = llvm.get_str_var(&val).unwrap();
+                let str_id = llvm.get_str_var(*s);
                 let val = llvm.module.get_global(&str_id).unwrap();
                 let val_ptr = val.as_pointer_value();
                 let bitcast = llvm.builder.build_bitcast(
@@ -925,7 +1555,9 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
                     Some(val).view(|ir| llvm.record_terminal(self.span(), ir))
                 }
             }
-            ast::Expression::UnaryOp(_, op, exp) => Some(op.to_llvm_ir(llvm, exp, self.span())),
+            ast::Expression::UnaryOp(ctx, op, exp) => {
+                Some(op.to_llvm_ir(llvm, ctx.ty(), exp, self.span()))
+            }
             ast::Expression::BinaryOp(_, op, l, r) => Some(op.to_llvm_ir(llvm, l, r, self.span())),
             ast::Expression::RoutineCall(meta, call, name, params) => call
                 .to_llvm_ir(llvm, name, params, self.get_type(), self.span())
@@ -933,10 +1565,35 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
                 .unwrap(),
             ast::Expression::ExpressionBlock(meta, stmts, exp) => {
                 llvm.registers.open_local().unwrap();
+                llvm.open_defer_scope();
                 for stmt in stmts {
                     stmt.to_llvm_ir(llvm);
                 }
-                let val = exp.as_ref().map(|e| e.to_llvm_ir(llvm)).flatten();
+                let mut val = exp
+                    .as_ref()
+                    .map(|e| {
+                        llvm.skip_terminator();
+                        e.to_llvm_ir(llvm)
+                    })
+                    .flatten();
+
+                // Copy the value of the block, so that a deferred expression cannot change it
+                let deferred = !llvm.defers.last().unwrap().1.is_empty();
+                if let Some(v) = val.filter(|_| deferred && !llvm.is_terminated()) {
+                    match meta
+                        .ty()
+                        .to_llvm_ir(llvm)
+                        .and_then(|ty| ty.into_basic_type())
+                    {
+                        Ok(ty) if ty.is_aggregate_type() => {
+                            let copy = llvm.build_alloca(ty, "");
+                            llvm.build_memcpy(copy, v.into_pointer_value(), self.span());
+                            val = Some(copy.into());
+                        }
+                        _ => (),
+                    }
+                }
+                llvm.close_defer_scope();
                 llvm.registers.close_local().unwrap();
                 val
             }
@@ -959,37 +1616,48 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
                         .build_conditional_branch(cond_val, then_bb, else_bb),
                 );
 
-                let event = llvm.new_event(self.span());
                 llvm.builder.position_at_end(then_bb);
                 let then_arm_val = then_arm.to_llvm_ir(llvm);
-                let then_bb = llvm.builder.get_insert_block().unwrap(); // The builders position may change after compiling the then block
-                let br = llvm.builder.build_unconditional_branch(merge_bb);
-                llvm.record(event, &br);
+                let then_exit = llvm.build_if_arm_exit(
+                    then_arm_val,
+                    then_arm.get_type(),
+                    merge_bb,
+                    self.span(),
+                );
 
-                let event = llvm.new_event(self.span());
                 llvm.builder.position_at_end(else_bb);
                 let else_arm_val = else_arm.as_ref().map(|ea| ea.to_llvm_ir(llvm)).flatten();
-                let else_bb = llvm.builder.get_insert_block().unwrap(); // The builders position may changing after compiling the else block
-                let br = llvm.builder.build_unconditional_branch(merge_bb);
-                llvm.record(event, &br);
+                let else_ty = else_arm
+                    .as_ref()
+                    .map_or(&ast::Type::Unit, |ea| ea.get_type());
+                let else_exit =
+                    llvm.build_if_arm_exit(else_arm_val, else_ty, merge_bb, self.span());
 
                 llvm.builder.position_at_end(merge_bb);
 
-                match (then_arm_val, else_arm_val) {
-                    (Some(then_arm_val), Some(else_arm_val)) => {
-                        // create phi to unify the branches
-                        let phi = llvm.builder.build_phi(then_arm_val.get_type(), "phi");
-                        phi.add_incoming(&[(&then_arm_val, then_bb), (&else_arm_val, else_bb)]);
-                        Some(phi.as_basic_value())
+                // An if expression of type Unit or never has no value, so there is nothing to unify.
+                // Otherwise, unify the values of the arms which reach the merge block. An arm which
+                // diverges (e.g. with a return statement) never reaches the merge block.
+                if self.get_type() == ast::Type::Unit || self.get_type() == ast::Type::Never {
+                    None
+                } else {
+                    let incoming: Vec<_> = then_exit.into_iter().chain(else_exit).collect();
+                    match incoming.first() {
+                        Some((first_val, _)) => {
+                            // create phi to unify the branches
+                            let phi = llvm.builder.build_phi(first_val.get_type(), "phi");
+                            for (val, bb) in &incoming {
+                                phi.add_incoming(&[(val, *bb)]);
+                            }
+                            Some(phi.as_basic_value())
+                        }
+                        None => None,
                     }
-                    (None, None) => None,
-                    _ => panic!(
-                        "Mismatching arms on if expression: {:?}, {:?}",
-                        then_arm_val, else_arm_val
-                    ),
                 }
             }
-            ast::Expression::While { cond, body, .. } => {
+            ast::Expression::While {
+                label, cond, body, ..
+            } => {
                 let current_fn = llvm.get_current_fn().unwrap();
 
                 // Construct the three components of the while loop
@@ -1017,9 +1685,16 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
                 // Emit the code that will evaluate the loop body
                 let event = llvm.new_event(self.span());
                 llvm.builder.position_at_end(body_bb);
+                llvm.loops
+                    .push((*label, loop_bb, after_bb, llvm.defers.len()));
                 body.to_llvm_ir(llvm); // The result of the body is not used for anything so ignore it
-                let br = llvm.builder.build_unconditional_branch(loop_bb);
-                llvm.record(event, &br);
+                llvm.loops.pop();
+
+                // If the body diverged (e.g. with a return) then it has already left the loop
+                if !llvm.is_terminated() {
+                    let br = llvm.builder.build_unconditional_branch(loop_bb);
+                    llvm.record(event, &br);
+                }
 
                 // Position the LLVM Builder cursor to be immediately after the loop
                 llvm.builder.position_at_end(after_bb);
@@ -1029,13 +1704,7 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
             ast::Expression::MemberAccess(_, val, field) => {
                 let event = llvm.new_event(self.span());
                 let sdef = llvm
-                    .struct_table
-                    .get(
-                        &val.get_type()
-                            .get_path()
-                            .unwrap()
-                            .to_label(llvm.source_map, llvm.string_table),
-                    )
+                    .get_struct_def(val.get_type().get_path().unwrap())
                     .unwrap();
 
                 let field_idx = sdef.get_field_idx(*field).unwrap();
@@ -1061,22 +1730,18 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
             }
             ast::Expression::StructExpression(_, name, fields) => {
                 let event = llvm.new_event(self.span());
-                let sname = self
-                    .context()
-                    .ty()
-                    .get_path()
-                    .unwrap()
-                    .to_label(llvm.source_map, llvm.string_table);
+                let spath = self.context().ty().get_path().unwrap();
+                let sname = spath.to_label(llvm.source_map, llvm.string_table);
                 let sdef = llvm
-                    .struct_table
-                    .get(&sname)
+                    .get_struct_def(spath)
                     .unwrap_or_else(|| panic!("Cannot find {} in {:?}", sname, llvm.struct_table));
                 let sdef_llvm = llvm.module.get_struct_type(&sname).unwrap();
-                let s_ptr = llvm.builder.build_alloca(sdef_llvm, "");
+                let s_ptr = llvm.build_alloca(sdef_llvm, "");
                 llvm.record(event, &s_ptr);
 
                 // convert field names to field indexes (order of fields in expression may not
-                // be the same as in the defintion)
+                // be the same as in the defintion).  The fields are initialized in the order
+                // they are written in the expression, which must match the MIR transformer.
                 let idx_fields: Vec<(usize, &ast::Expression<SemanticContext>)> = fields
                     .iter()
                     .map(|(n, v)| (sdef.get_field_idx(*n).unwrap(), v))
@@ -1114,7 +1779,7 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
                     .into_basic_type()
                     .unwrap();
                 let alloca_event = llvm.new_event(self.span());
-                let a_ptr = llvm.builder.build_alloca(a_llvm_ty, "");
+                let a_ptr = llvm.build_alloca(a_llvm_ty, "");
                 llvm.record(alloca_event, &a_ptr);
 
                 // Compute the results for each element of the array value
@@ -1148,29 +1813,31 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
                 // The arch value of this expression is the ptr to the array
                 Some(a_ptr.into()).view(|ir| llvm.record(event, ir))
             }
+            ast::Expression::ArrayAt {
+                context: meta,
+                array,
+                index,
+            } if matches!(index.get_type(), Type::Range(_)) => {
+                let event = llvm.new_event(self.span());
+                let seq = llvm.build_subsequence(meta.ty(), array, index);
+                Some(seq.into()).view(|ir| llvm.record(event, ir))
+            }
+            ast::Expression::ArrayAt {
+                context: meta,
+                array,
+                index,
+            } if matches!(array.get_type(), Type::Range(_)) => {
+                let event = llvm.new_event(self.span());
+                let el = llvm.build_range_element(array, index);
+                Some(el.into()).view(|ir| llvm.record(event, ir))
+            }
             ast::Expression::ArrayAt {
                 context: meta,
                 array,
                 index,
             } => {
                 let event = llvm.new_event(self.span());
-                // evalute the array to get the ptr to the array
-                // Check the array type, if it's not a pointer then get the GEP
-                let llvm_array_ptr = match array.to_llvm_ir(llvm) {
-                    Some(a) if a.is_pointer_value() => a.into_pointer_value(),
-                    Some(a) => panic!("Unexpected type for array: {:?}", a),
-                    None => panic!("Could not convert type {} to LLVM type", array),
-                };
-
-                // evaluate the index to get the index value
-                let llvm_index = index.to_llvm_ir(llvm).unwrap().into_int_value();
-
-                // Compute the GEP
-                let outer_idx = llvm.context.i64_type().const_int(0, false);
-                let el_ptr = unsafe {
-                    llvm.builder
-                        .build_gep(llvm_array_ptr, &[outer_idx, llvm_index], "")
-                };
+                let el_ptr = llvm.build_element_ptr(array, index);
                 llvm.record(event, &el_ptr);
 
                 // Load the value pointed to by GEP and return that
@@ -1195,6 +1862,12 @@ impl<'ctx> ToLlvmIr<'ctx> for ast::Expression<SemanticContext> {
             ast::Expression::Path(..) => {
                 panic!("Path nodes should be resolved and removed before the compiler stage.")
             }
+            ast::Expression::FieldsOf(..) | ast::Expression::NameOf(..) => {
+                panic!("Reflection builtins should be evaluated and removed before the compiler stage.")
+            }
+            ast::Expression::MacroCall(..) => {
+                panic!("Macros should be expanded and removed before the compiler stage.")
+            }
             ast::Expression::IdentifierDeclare(..) => {
                 panic!("IdentifierDelcare nodes should be resolved and removed before the compiler stage")
             }
@@ -1221,9 +1894,58 @@ impl ast::Expression<SemanticContext> {
         let target_signed = target_ty.is_signed();
         let target_width = target_ty.bit_width();
         let op = match (src_llvm, target_ty_llvm) {
+            // string literal to String
+            (BasicValueEnum::PointerValue(_), AnyTypeEnum::PointerType(_))
+                if src.get_type() == Type::StringLiteral && *target_ty == Type::String =>
+            {
+                let (data, len) = llvm.build_string_parts(src_llvm, src.get_type());
+                let empty = (data, llvm.context.i64_type().const_zero());
+                runtime::build_string_concat(
+                    llvm.context,
+                    &llvm.module,
+                    &llvm.builder,
+                    (data, len),
+                    empty,
+                )
+                .into()
+            }
+            // array to slice
+            (BasicValueEnum::PointerValue(arr), AnyTypeEnum::StructType(_))
+                if matches!(target_ty, Type::Slice(_)) =>
+            {
+                let len = match src.get_type() {
+                    Type::Array(_, len) => *len as u64,
+                    ty => panic!("Expected an array but found {}", ty),
+                };
+                let zero = llvm.context.i64_type().const_zero();
+                let data = unsafe { llvm.builder.build_gep(arr, &[zero, zero], "") };
+                let len = llvm.context.i64_type().const_int(len, false);
+                llvm.build_slice(target_ty, data, len).into()
+            }
+            // String to string literal
+            (BasicValueEnum::PointerValue(_), AnyTypeEnum::PointerType(tty))
+                if src.get_type() == Type::String =>
+            {
+                let (data, _) = llvm.build_string_parts(src_llvm, src.get_type());
+                llvm.builder.build_bitcast(data, tty, "")
+            }
+            // int to bool
+            (BasicValueEnum::IntValue(iv), AnyTypeEnum::IntType(_)) if *target_ty == Type::Bool => {
+                let zero = iv.get_type().const_zero();
+                llvm.builder
+                    .build_int_compare(IntPredicate::NE, iv, zero, "")
+                    .into()
+            }
+            // bool to int
+            (BasicValueEnum::IntValue(iv), AnyTypeEnum::IntType(tty))
+                if src.get_type() == Type::Bool =>
+            {
+                llvm.builder.build_int_z_extend(iv, tty, "").into()
+            }
             (BasicValueEnum::IntValue(iv), AnyTypeEnum::IntType(tty)) => {
-                // if upcasting
-                if src_width < target_width {
+                // if upcasting.  The widths are taken from the LLVM types because the
+                // width of `usize` and `isize` depends upon the target machine.
+                if iv.get_type().get_bit_width() < tty.get_bit_width() {
                     match (src_signed, target_signed) {
                         (false, false) | (false, true) => {
                             llvm.builder.build_int_z_extend(iv, tty, "")
@@ -1296,6 +2018,7 @@ impl ast::UnaryOperator {
     fn to_llvm_ir<'ctx>(
         &self,
         llvm: &mut IrGen<'ctx>,
+        ty: &ast::Type,
         right: &ast::Expression<SemanticContext>,
         span: Span,
     ) -> BasicValueEnum<'ctx> {
@@ -1331,6 +2054,130 @@ impl ast::UnaryOperator {
                     llvm.builder.build_load(ptr, "")
                 }
             }
+            (ast::UnaryOperator::Length, false) => match right.get_type() {
+                ast::Type::Array(_, len) => {
+                    // The operand is still evaluated for its side effects
+                    right.to_llvm_ir(llvm);
+                    llvm.context.i64_type().const_int(*len as u64, false).into()
+                }
+                ast::Type::Slice(_) => {
+                    let r = right.to_llvm_ir(llvm).expect("Expected a value");
+                    let (_, len) =
+                        runtime::build_slice_parts(&llvm.builder, r.into_pointer_value());
+                    len.into()
+                }
+                ast::Type::Range(el_ty) => {
+                    let r = right.to_llvm_ir(llvm).expect("Expected a value");
+                    llvm.build_range_len(r.into_pointer_value(), el_ty.is_signed())
+                        .into()
+                }
+                _ => {
+                    let r = right.to_llvm_ir(llvm).expect("Expected a value");
+                    let (_, len) = llvm.build_string_parts(r, right.get_type());
+                    len.into()
+                }
+            },
+            (ast::UnaryOperator::OptionSome, _) | (ast::UnaryOperator::ResultOk, _) => {
+                let r = right.to_llvm_ir(llvm).expect("Expected a value");
+                llvm.build_tagged(ty, true, Some((1, r)), span).into()
+            }
+            (ast::UnaryOperator::ResultErr, _) => {
+                let r = right.to_llvm_ir(llvm).expect("Expected a value");
+                llvm.build_tagged(ty, false, Some((2, r)), span).into()
+            }
+            (ast::UnaryOperator::OptionIsSome, false) | (ast::UnaryOperator::ResultIsOk, false) => {
+                let r = right.to_llvm_ir(llvm).expect("Expected a value");
+                llvm.build_tagged_field(r.into_pointer_value(), 0)
+            }
+            (ast::UnaryOperator::OptionUnwrap, false)
+            | (ast::UnaryOperator::ResultUnwrapOk, false) => {
+                let r = right.to_llvm_ir(llvm).expect("Expected a value");
+                llvm.build_tagged_field(r.into_pointer_value(), 1)
+            }
+            (ast::UnaryOperator::ResultUnwrapErr, false) => {
+                let r = right.to_llvm_ir(llvm).expect("Expected a value");
+                llvm.build_tagged_field(r.into_pointer_value(), 2)
+            }
+            (ast::UnaryOperator::Try, false) => {
+                // If the operand is empty or an error, then it is copied into the return value
+                // of the current function, which returns; otherwise, the value is taken out
+                let r = right
+                    .to_llvm_ir(llvm)
+                    .expect("Expected a value")
+                    .into_pointer_value();
+                let tag = llvm.build_tagged_field(r, 0).into_int_value();
+
+                let current_fn = llvm.get_current_fn().unwrap();
+                let ok_bb = llvm.context.append_basic_block(current_fn, "try_ok");
+                let fail_bb = llvm.context.append_basic_block(current_fn, "try_fail");
+                llvm.builder.build_conditional_branch(tag, ok_bb, fail_bb);
+
+                llvm.builder.position_at_end(fail_bb);
+                let out = llvm.registers.get(".out").unwrap().into_pointer_value();
+                let err = match right.get_type() {
+                    ast::Type::Result(..) => Some((2, llvm.build_tagged_field(r, 2))),
+                    _ => None,
+                };
+                llvm.store_tagged(out, false, err, span);
+                llvm.build_all_defers();
+                llvm.builder.build_return(None);
+
+                llvm.builder.position_at_end(ok_bb);
+                llvm.build_tagged_field(r, 1)
+            }
+            (ast::UnaryOperator::ToLeBytes, false) | (ast::UnaryOperator::ToBeBytes, false) => {
+                let r = right.to_llvm_ir(llvm).expect("Expected a value");
+                let mut value = r.into_int_value();
+                if *self == ast::UnaryOperator::ToBeBytes {
+                    value = build_bswap(&llvm.module, &llvm.builder, value);
+                }
+
+                // Byte `idx` of the array is the value shifted right by `idx` bytes
+                let int_ty = value.get_type();
+                let len = int_ty.get_bit_width() / 8;
+                let bytes = llvm.build_entry_alloca(llvm.context.i8_type().array_type(len));
+                let outer_idx = llvm.context.i64_type().const_int(0, false);
+                for idx in 0..len {
+                    let shift = int_ty.const_int(8 * idx as u64, false);
+                    let shifted = llvm.builder.build_right_shift(value, shift, false, "");
+                    let byte = llvm
+                        .builder
+                        .build_int_truncate(shifted, llvm.context.i8_type(), "");
+                    let llvm_idx = llvm.context.i64_type().const_int(idx as u64, false);
+                    let el_ptr =
+                        unsafe { llvm.builder.build_gep(bytes, &[outer_idx, llvm_idx], "") };
+                    llvm.builder.build_store(el_ptr, byte);
+                }
+                bytes.into()
+            }
+            (ast::UnaryOperator::FromLeBytes, false) | (ast::UnaryOperator::FromBeBytes, false) => {
+                let r = right.to_llvm_ir(llvm).expect("Expected a value");
+                let bytes = r.into_pointer_value();
+
+                // Each byte is shifted left into its place and combined with the others
+                let len = match right.get_type() {
+                    ast::Type::Array(_, len) => *len as u32,
+                    ty => panic!("Expected a byte array but found {}", ty),
+                };
+                let int_ty = llvm.context.custom_width_int_type(8 * len);
+                let outer_idx = llvm.context.i64_type().const_int(0, false);
+                let mut value = int_ty.const_zero();
+                for idx in 0..len {
+                    let llvm_idx = llvm.context.i64_type().const_int(idx as u64, false);
+                    let el_ptr =
+                        unsafe { llvm.builder.build_gep(bytes, &[outer_idx, llvm_idx], "") };
+                    let byte = llvm.builder.build_load(el_ptr, "").into_int_value();
+                    let byte = llvm.builder.build_int_z_extend(byte, int_ty, "");
+                    let shift = int_ty.const_int(8 * idx as u64, false);
+                    let shifted = llvm.builder.build_left_shift(byte, shift, "");
+                    value = llvm.builder.build_or(value, shifted, "");
+                }
+
+                if *self == ast::UnaryOperator::FromBeBytes {
+                    value = build_bswap(&llvm.module, &llvm.builder, value);
+                }
+                value.into()
+            }
             _ => panic!("Invalid operator"),
         };
 
@@ -1353,7 +2200,14 @@ impl ast::BinaryOperator {
         let is_pointer = left.get_type().is_raw_pointer() || right.get_type().is_raw_pointer();
         let l = left.to_llvm_ir(llvm).expect("Expected a value");
         let r = right.to_llvm_ir(llvm).expect("Expected a value");
-        let op = if is_float {
+        let op = if *self == ast::BinaryOperator::Range {
+            llvm.build_range(l.into_int_value(), r.into_int_value())
+                .into()
+        } else if *self == ast::BinaryOperator::Add && left.get_type().is_string() {
+            let l = llvm.build_string_parts(l, left.get_type());
+            let r = llvm.build_string_parts(r, right.get_type());
+            runtime::build_string_concat(llvm.context, &llvm.module, &llvm.builder, l, r).into()
+        } else if is_float {
             // With the current design, the difference between float and integer arithmetic is
             // a hardware difference and falls squarely within the field of the LLVM generator
             // module.  But this violates the precept that this module makes no decisions and only
@@ -1429,6 +2283,13 @@ impl ast::BinaryOperator {
                     // transcribes exactly what it is given.  Ultimately, I need to capture the notion
                     // of operators for each operand type in the language layer; especially when I get
                     // to implementing FP values and operations.
+                    runtime::build_div_zero_check(
+                        llvm.context,
+                        &llvm.module,
+                        &llvm.builder,
+                        rv,
+                        &llvm.options,
+                    );
                     if left.get_type().is_unsigned_int() {
                         llvm.builder.build_int_unsigned_div(lv, rv, "")
                     } else {
@@ -1436,6 +2297,20 @@ impl ast::BinaryOperator {
                     }
                     .into()
                 }
+                ast::BinaryOperator::WrappingAdd => llvm.builder.build_int_add(lv, rv, "").into(),
+                ast::BinaryOperator::WrappingSub => llvm.builder.build_int_sub(lv, rv, "").into(),
+                ast::BinaryOperator::WrappingMul => llvm.builder.build_int_mul(lv, rv, "").into(),
+                ast::BinaryOperator::SaturatingAdd | ast::BinaryOperator::SaturatingSub => {
+                    let is_unsigned = left.get_type().is_unsigned_int();
+                    let intrinsic = match (self, is_unsigned) {
+                        (ast::BinaryOperator::SaturatingAdd, false) => "llvm.sadd.sat",
+                        (ast::BinaryOperator::SaturatingAdd, true) => "llvm.uadd.sat",
+                        (ast::BinaryOperator::SaturatingSub, false) => "llvm.ssub.sat",
+                        (ast::BinaryOperator::SaturatingSub, true) => "llvm.usub.sat",
+                        _ => unreachable!(),
+                    };
+                    build_int_sat_op(&llvm.module, &llvm.builder, intrinsic, lv, rv).into()
+                }
                 ast::BinaryOperator::BAnd => llvm.builder.build_and(lv, rv, "").into(),
                 ast::BinaryOperator::BOr => llvm.builder.build_or(lv, rv, "").into(),
                 ast::BinaryOperator::Eq => llvm
@@ -1462,7 +2337,7 @@ impl ast::BinaryOperator {
                     .builder
                     .build_int_compare(IntPredicate::SGE, lv, rv, "")
                     .into(),
-                ast::BinaryOperator::RawPointerOffset => {
+                ast::BinaryOperator::RawPointerOffset | ast::BinaryOperator::Range => {
                     panic!("Should be impossible to reach this arm")
                 }
             }
@@ -1480,10 +2355,17 @@ impl ast::RoutineCall {
             ast::RoutineCall::Function | ast::RoutineCall::CoroutineInit => {
                 target.to_label(llvm.source_map, llvm.string_table)
             }
-            ast::RoutineCall::Extern => llvm
-                .string_table
-                .get(target.item().expect("Extern call must have a target path"))
-                .unwrap(),
+            ast::RoutineCall::Extern => {
+                let link_name = llvm
+                    .paths
+                    .find(target)
+                    .and_then(|id| llvm.link_names.get(id))
+                    .unwrap_or_else(|| panic!("Could not find extern {}", target));
+                llvm.string_table.get(*link_name).unwrap()
+            }
+            ast::RoutineCall::Method | ast::RoutineCall::Iterator(_) => {
+                panic!("Method calls must be resolved by semantic analysis")
+            }
         }
     }
 
@@ -1504,9 +2386,7 @@ impl ast::RoutineCall {
                 panic!("Expected an aggregate type but got {}. Out parameters should only be used with LLVM Aggregate Types (arrays, structs).", ret_ty);
             }
 
-            let ptr = llvm
-                .builder
-                .build_alloca(out_ty, &format!("_out_{}", target));
+            let ptr = llvm.build_alloca(out_ty, &format!("_out_{}", target));
             llvm.record_terminal(span, &ptr);
             Ok(Some(ptr))
         } else {
@@ -1555,6 +2435,9 @@ impl ast::RoutineCall {
                 }
             }
             ast::RoutineCall::CoroutineInit => todo!("Not yet implemented"),
+            ast::RoutineCall::Method | ast::RoutineCall::Iterator(_) => {
+                panic!("Method calls must be resolved by semantic analysis")
+            }
         }
     }
 }
@@ -1567,15 +2450,22 @@ impl ast::Type {
             ast::Type::U16 | ast::Type::I16 => llvm.context.i16_type().into(),
             ast::Type::U32 | ast::Type::I32 => llvm.context.i32_type().into(),
             ast::Type::U64 | ast::Type::I64 => llvm.context.i64_type().into(),
+            ast::Type::USize | ast::Type::ISize => llvm
+                .context
+                .custom_width_int_type(llvm.pointer_width)
+                .into(),
             ast::Type::F64 => llvm.context.f64_type().into(),
             ast::Type::Bool => llvm.context.bool_type().into(),
-            ast::Type::Unit => llvm.context.void_type().into(),
+            ast::Type::Unit | ast::Type::Never => llvm.context.void_type().into(),
             ast::Type::StringLiteral => llvm
                 .context
                 .i8_type()
                 .array_type(0)
                 .ptr_type(AddressSpace::Generic)
                 .into(),
+            ast::Type::String => runtime::string_type(llvm.context)
+                .ptr_type(AddressSpace::Generic)
+                .into(),
             ast::Type::Custom(name) => {
                 let label = name.to_label(llvm.source_map, llvm.string_table);
                 llvm.module
@@ -1593,6 +2483,23 @@ impl ast::Type {
                 let len = *len as u32;
                 el_ty.into_basic_type().unwrap().array_type(len).into()
             }
+            ast::Type::Slice(a) => {
+                let el_ty = a.to_llvm_ir(llvm)?.into_basic_type()?;
+                runtime::slice_type(llvm.context, el_ty).into()
+            }
+            ast::Type::Range(a) => {
+                let el_ty = a.to_llvm_ir(llvm)?.into_basic_type()?;
+                runtime::range_type(llvm.context, el_ty).into()
+            }
+            ast::Type::Option(a) => {
+                let value_ty = a.to_llvm_ir(llvm)?.into_basic_type()?;
+                runtime::option_type(llvm.context, value_ty).into()
+            }
+            ast::Type::Result(ok, err) => {
+                let ok_ty = ok.to_llvm_ir(llvm)?.into_basic_type()?;
+                let err_ty = err.to_llvm_ir(llvm)?.into_basic_type()?;
+                runtime::result_type(llvm.context, ok_ty, err_ty).into()
+            }
             ast::Type::StructDef(_)
             | ast::Type::FunctionDef(_, _)
             | ast::Type::CoroutineDef(_, _)
@@ -1633,6 +2540,67 @@ pub fn convert_esc_seq_to_ascii(s: &str) -> Result<String> {
     Ok(escaped_str)
 }
 
+/// Sets every byte of the memory that `ptr` points to to zero.
+pub fn build_zero_init<'ctx>(
+    context: &'ctx context::Context,
+    builder: &Builder<'ctx>,
+    ptr: PointerValue<'ctx>,
+) {
+    let zero = context.i8_type().const_zero();
+    let size = ptr.get_type().get_element_type().size_of().unwrap();
+    builder
+        .build_memset(ptr, get_ptr_alignment(ptr), zero, size)
+        .unwrap();
+}
+
+/// Calls one of LLVM's saturating arithmetic intrinsics (e.g. `llvm.sadd.sat`) on two
+/// integers of the same type, declaring the intrinsic for that type if needed.
+pub fn build_int_sat_op<'ctx>(
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    intrinsic: &str,
+    l: IntValue<'ctx>,
+    r: IntValue<'ctx>,
+) -> IntValue<'ctx> {
+    let int_ty = l.get_type();
+    let name = format!("{}.i{}", intrinsic, int_ty.get_bit_width());
+    let intrinsic_fn = module.get_function(&name).unwrap_or_else(|| {
+        let fn_ty = int_ty.fn_type(&[int_ty.into(), int_ty.into()], false);
+        module.add_function(&name, fn_ty, None)
+    });
+    builder
+        .build_call(intrinsic_fn, &[l.into(), r.into()], "")
+        .try_as_basic_value()
+        .left()
+        .expect("Saturating intrinsics must return a value")
+        .into_int_value()
+}
+
+/// Reverses the order of the bytes of an integer with LLVM's `llvm.bswap` intrinsic,
+/// declaring the intrinsic for that type if needed.  A single byte is returned as it is.
+pub fn build_bswap<'ctx>(
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    v: IntValue<'ctx>,
+) -> IntValue<'ctx> {
+    let int_ty = v.get_type();
+    if int_ty.get_bit_width() == 8 {
+        return v;
+    }
+
+    let name = format!("llvm.bswap.i{}", int_ty.get_bit_width());
+    let intrinsic_fn = module.get_function(&name).unwrap_or_else(|| {
+        let fn_ty = int_ty.fn_type(&[int_ty.into()], false);
+        module.add_function(&name, fn_ty, None)
+    });
+    builder
+        .build_call(intrinsic_fn, &[v.into()], "")
+        .try_as_basic_value()
+        .left()
+        .expect("bswap must return a value")
+        .into_int_value()
+}
+
 pub fn get_ptr_alignment(ptr: PointerValue) -> u32 {
     ptr.get_type()
         .get_alignment()
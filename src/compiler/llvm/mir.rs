@@ -1,15 +1,18 @@
 //! Transforms the MIR representation into LLVM
 
-use std::collections::{hash_map::Entry, HashMap, VecDeque};
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap, VecDeque},
+};
 
 use inkwell::{
     builder::Builder,
     context::Context,
+    debug_info::DIType,
     module::Module,
-    targets::{CodeModel, InitializationConfig, RelocMode},
     types::{AnyTypeEnum, BasicType, BasicTypeEnum, FunctionType},
     values::*,
-    AddressSpace, IntPredicate, OptimizationLevel,
+    AddressSpace, IntPredicate,
 };
 use log::debug;
 
@@ -25,7 +28,18 @@ use crate::{
     StringId, StringTable,
 };
 
-use super::llvmir::{get_ptr_alignment, LlvmIsAggregateType, LlvmToBasicTypeEnum};
+use super::{
+    abi::{self, Extension},
+    datasection::DataSection,
+    debuginfo::DebugInfo,
+    llvmir::{
+        build_bswap, build_int_sat_op, build_zero_init, get_ptr_alignment, LlvmIsAggregateType,
+        LlvmToBasicTypeEnum,
+    },
+    options::CodegenOptions,
+    remarks::RemarkCollector,
+    runtime, target,
+};
 
 /// Use the [`Generic`](AddressSpace::Generic) address space for all memory operations.
 /// This is done because this seems to be the safest choice and because I cannot find
@@ -107,6 +121,12 @@ impl<'ctx> Location<'ctx> {
 pub struct LlvmProgram<'module, 'ctx> {
     /// LLVM Module
     module: &'module Module<'ctx>,
+
+    /// Configures the target machine which the module is compiled for.
+    options: CodegenOptions,
+
+    /// The globals which hold the string literals of the program
+    data_section: DataSection,
 }
 
 impl<'module, 'ctx> LlvmProgram<'module, 'ctx> {
@@ -117,24 +137,7 @@ impl<'module, 'ctx> LlvmProgram<'module, 'ctx> {
 
     /// Print the assembly representation of this Bramble program to `stdout`
     pub fn print_asm(&self) {
-        // Get target for current machine
-        let triple = inkwell::targets::TargetMachine::get_default_triple();
-
-        let config = InitializationConfig::default();
-        inkwell::targets::Target::initialize_all(&config);
-        let target = inkwell::targets::Target::from_triple(&triple).unwrap();
-
-        let machine = target
-            .create_target_machine(
-                &triple,
-                "generic",
-                "",
-                OptimizationLevel::None,
-                RelocMode::Default,
-                CodeModel::Default,
-            )
-            .ok_or("Could not create a target machine for compilation")
-            .unwrap();
+        let (machine, triple) = target::create_target_machine(&self.options).unwrap();
         let data = machine.get_target_data();
 
         // Configure the module
@@ -151,25 +154,19 @@ impl<'module, 'ctx> LlvmProgram<'module, 'ctx> {
         println!("{contents}");
     }
 
-    pub fn emit_object_code(&self, emit_asm: Option<&std::path::Path>, file: &std::path::Path) {
-        // Get target for current machine
-        let triple = inkwell::targets::TargetMachine::get_default_triple();
+    /// Runs LLVM's optimization passes over the module, if optimization is enabled
+    pub fn optimize(&self) {
+        target::optimize_module(self.module, &self.options)
+    }
 
-        let config = InitializationConfig::default();
-        inkwell::targets::Target::initialize_all(&config);
-        let target = inkwell::targets::Target::from_triple(&triple).unwrap();
+    /// Starts collecting the optimization remarks which LLVM reports while this
+    /// program is optimized and compiled.
+    pub fn collect_remarks(&self) -> RemarkCollector<'ctx> {
+        RemarkCollector::install(self.module)
+    }
 
-        let machine = target
-            .create_target_machine(
-                &triple,
-                "generic",
-                "",
-                OptimizationLevel::None,
-                RelocMode::Default,
-                CodeModel::Default,
-            )
-            .ok_or("Could not create a target machine for compilation")
-            .unwrap();
+    pub fn emit_object_code(&self, emit_asm: Option<&std::path::Path>, file: &std::path::Path) {
+        let (machine, triple) = target::create_target_machine(&self.options).unwrap();
         let data = machine.get_target_data();
 
         // Configure the module
@@ -185,9 +182,20 @@ impl<'module, 'ctx> LlvmProgram<'module, 'ctx> {
             .unwrap();
     }
 
+    /// Returns the global data which was added to the module
+    pub fn data_section(&self) -> &DataSection {
+        &self.data_section
+    }
+
     pub fn emit_llvm_ir(&self, file: &std::path::Path) -> Result<(), inkwell::support::LLVMString> {
         self.module.print_to_file(file)
     }
+
+    /// Runs LLVM's module verifier over the generated LLVM IR.  Returns the
+    /// verifier's description of every problem that it found.
+    pub fn verify(&self) -> Result<(), String> {
+        self.module.verify().map_err(|e| e.to_string())
+    }
 }
 
 /// Groups the data which describes an LLVM function together.
@@ -201,6 +209,10 @@ pub struct FunctionData<'ctx> {
 
     /// This is true if the function has the special main function name.
     is_main: bool,
+
+    /// The name of the function in the source code and where it is defined
+    name: Option<StringId>,
+    span: Span,
 }
 
 /// Specifies the method that will be used to pass the result of this
@@ -244,8 +256,35 @@ pub struct LlvmProgramBuilder<'module, 'ctx> {
     /// Table mapping [`TypeId`] to the LLVM IR associated type.
     ty_table: HashMap<TypeId, AnyTypeEnum<'ctx>>,
 
+    /// Table mapping the [`TypeId`] of each base type to how its values are extended
+    /// when they are passed to, or returned from, a function.
+    ext_table: HashMap<TypeId, Extension>,
+
     /// Defines the special name that is reserved for the main function
     main_name: StringId,
+
+    /// Options which control how LLVM IR is generated
+    options: CodegenOptions,
+
+    /// The width, in bits, of a pointer on the target machine, which is also the width
+    /// of `usize` and `isize`.
+    pointer_width: u32,
+
+    /// The globals which hold the string literals of the program.  These are added
+    /// by the function builders, which only borrow the program builder.
+    data_section: RefCell<DataSection>,
+
+    /// Describes the generated code to debuggers, if debug information was requested.
+    /// Like the data section, this is updated by the function builders.
+    debug_info: Option<RefCell<DebugInfo<'ctx>>>,
+
+    /// Table mapping the [`TypeId`] of each base type to the type which describes its
+    /// values to debuggers.
+    di_type_table: HashMap<TypeId, DIType<'ctx>>,
+
+    /// The global array of coverage counters, if the program is instrumented for
+    /// coverage.
+    coverage: Option<GlobalValue<'ctx>>,
 }
 
 impl<'module, 'ctx> LlvmProgramBuilder<'module, 'ctx> {
@@ -256,9 +295,19 @@ impl<'module, 'ctx> LlvmProgramBuilder<'module, 'ctx> {
         source_map: &'ctx SourceMap,
         table: &'ctx StringTable,
         main_name: StringId,
+        options: CodegenOptions,
     ) -> Self {
         debug!("Creating LLVM Program Transformer");
 
+        let debug_info = if options.debug_info {
+            let name = module.get_name().to_string_lossy().into_owned();
+            Some(RefCell::new(DebugInfo::new(ctx, module, &name, source_map)))
+        } else {
+            None
+        };
+
+        let pointer_width = target::pointer_width(&options).unwrap();
+
         Self {
             context: ctx,
             module,
@@ -267,13 +316,42 @@ impl<'module, 'ctx> LlvmProgramBuilder<'module, 'ctx> {
             source_map,
             str_table: table,
             ty_table: HashMap::new(),
+            ext_table: HashMap::new(),
             main_name,
+            options,
+            pointer_width,
+            data_section: RefCell::new(DataSection::new()),
+            debug_info,
+            di_type_table: HashMap::new(),
+            coverage: None,
         }
     }
 
+    /// Instruments the program for coverage with `count` counters.  The counters are
+    /// written to `<module name>.counters`, in the working directory of the program,
+    /// when the program exits.
+    pub fn add_coverage_counters(&mut self, count: usize) {
+        let path = format!("{}.counters", self.module.get_name().to_string_lossy());
+        self.coverage = Some(runtime::add_coverage_counters(
+            self.context,
+            self.module,
+            self.builder,
+            count,
+            &path,
+        ));
+    }
+
     /// Transforms this into the final [`LlvmProgram`] result, which can be used to
     /// actually generate the object code necessary for linking and final compilation.
     pub fn complete(mut self) -> LlvmProgram<'module, 'ctx> {
+        if let Some(di) = &self.debug_info {
+            let mut di = di.borrow_mut();
+            di.end_function(self.builder);
+            di.finalize();
+        }
+
+        runtime::define_allocator_hooks(self.context, self.module, self.builder);
+
         match self.find_user_main().unwrap() {
             Some(user_main) => {
                 let fv = user_main.function;
@@ -284,6 +362,8 @@ impl<'module, 'ctx> LlvmProgramBuilder<'module, 'ctx> {
 
         LlvmProgram {
             module: self.module,
+            options: self.options,
+            data_section: self.data_section.into_inner(),
         }
     }
 
@@ -293,6 +373,12 @@ impl<'module, 'ctx> LlvmProgramBuilder<'module, 'ctx> {
         let main = self.module.add_function("main", main_type, None);
         let entry_bb = self.context.append_basic_block(main, "entry");
         self.builder.position_at_end(entry_bb);
+        runtime::build_install_signal_handlers(
+            self.context,
+            self.module,
+            self.builder,
+            &self.options,
+        );
 
         let status = self
             .builder
@@ -424,11 +510,16 @@ impl<'p, 'module, 'ctx>
         &mut self,
         func_id: DefId,
         canonical_path: &Path,
+        link_name: Option<StringId>,
+        span: Span,
         args: &[ArgDecl],
         is_variadic: bool,
         ret_ty: TypeId,
     ) -> Result<(), TransformerError> {
-        let name = self.to_label(canonical_path);
+        let name = match link_name {
+            Some(link_name) => self.str_table.get(link_name).unwrap(),
+            None => self.to_label(canonical_path),
+        };
 
         debug!(
             "Adding function to Module: {} (var_args: {})",
@@ -439,13 +530,29 @@ impl<'p, 'module, 'ctx>
         // Set the return channel property for the function
         let (fn_type, ret_method) = self.fn_type(args, is_variadic, ret_ty)?;
 
-        let function = self.module.add_function(&name, fn_type, None);
+        // Externs which are declared in more than one module are linked to the same symbol
+        let function = self
+            .module
+            .get_function(&name)
+            .unwrap_or_else(|| self.module.add_function(&name, fn_type, None));
+
+        // Mark how narrow integer parameters and results are extended
+        let ext = |ty| self.ext_table.get(&ty).copied().unwrap_or(Extension::None);
+        let mut param_exts = vec![];
+        if ret_method == ReturnMethod::OutParam {
+            abi::add_out_param_attributes(self.context, function);
+            param_exts.push(Extension::None);
+        }
+        param_exts.extend(args.iter().map(|a| ext(a.ty())));
+        abi::add_extension_attributes(self.context, function, &param_exts, ext(ret_ty));
 
         // Add function to function table
         let function = FunctionData {
             ret_method,
             function,
             is_main: self.is_main_function(canonical_path),
+            name: canonical_path.item(),
+            span,
         };
 
         match self.fn_table.insert(func_id, function) {
@@ -497,11 +604,25 @@ impl<'p, 'module, 'ctx>
                     }
                     MirStructDef::Declared => Err(TransformerError::StructUndefined),
                 },
-                MirTypeDef::Base(_) | MirTypeDef::Array { .. } | MirTypeDef::RawPointer { .. } => {
-                    Err(TransformerError::TypeAlreadyDefined)
-                }
+                MirTypeDef::Base(_)
+                | MirTypeDef::Array { .. }
+                | MirTypeDef::RawPointer { .. }
+                | MirTypeDef::Option { .. }
+                | MirTypeDef::Result { .. } => Err(TransformerError::TypeAlreadyDefined),
             }
         } else {
+            if let MirTypeDef::Base(base) = ty {
+                self.ext_table.insert(id, Extension::from_mir(base));
+
+                let di_ty = self
+                    .debug_info
+                    .as_ref()
+                    .and_then(|di| di.borrow().mir_type(base));
+                if let Some(di_ty) = di_ty {
+                    self.di_type_table.insert(id, di_ty);
+                }
+            }
+
             let llvm_ty = ty.into_basic_type_enum(self);
             self.ty_table.insert(id, llvm_ty);
             Ok(())
@@ -550,7 +671,7 @@ impl MirTypeDef {
         p: &LlvmProgramBuilder<'module, 'ctx>,
     ) -> AnyTypeEnum<'ctx> {
         match self {
-            MirTypeDef::Base(base) => base.into_basic_type_enum(p.context),
+            MirTypeDef::Base(base) => base.into_basic_type_enum(p.context, p.pointer_width),
             MirTypeDef::Array { ty, sz } => {
                 let el_llvm_ty = p.get_type(*ty).unwrap();
                 let len = *sz as u32;
@@ -593,18 +714,39 @@ impl MirTypeDef {
             MirTypeDef::Structure { .. } => {
                 panic!("Attempting to add a structure which has not been defined")
             }
+            MirTypeDef::Option { ty } => {
+                // An option is a tag, which is true if there is a value, followed by the value
+                let tag: BasicTypeEnum = p.context.bool_type().into();
+                let value = p.get_type(*ty).unwrap().into_basic_type().unwrap();
+                p.context.struct_type(&[tag, value], false).into()
+            }
+            MirTypeDef::Result { ok, err } => {
+                // A result is a tag, which is true if it is ok, followed by the ok and the err
+                let tag: BasicTypeEnum = p.context.bool_type().into();
+                let ok = p.get_type(*ok).unwrap().into_basic_type().unwrap();
+                let err = p.get_type(*err).unwrap().into_basic_type().unwrap();
+                p.context.struct_type(&[tag, ok, err], false).into()
+            }
         }
     }
 }
 
 impl MirBaseType {
     /// Convert into the corresponding LLVM type and then wrap that in an [`AnyTypeEnum`] variant.
-    fn into_basic_type_enum<'ctx>(&self, context: &'ctx Context) -> AnyTypeEnum<'ctx> {
+    /// `pointer_width` is the width, in bits, of `usize` and `isize` on the target machine.
+    fn into_basic_type_enum<'ctx>(
+        &self,
+        context: &'ctx Context,
+        pointer_width: u32,
+    ) -> AnyTypeEnum<'ctx> {
         match self {
             MirBaseType::U8 | MirBaseType::I8 => context.i8_type().into(),
             MirBaseType::U16 | MirBaseType::I16 => context.i16_type().into(),
             MirBaseType::U32 | MirBaseType::I32 => context.i32_type().into(),
             MirBaseType::U64 | MirBaseType::I64 => context.i64_type().into(),
+            MirBaseType::USize | MirBaseType::ISize => {
+                context.custom_width_int_type(pointer_width).into()
+            }
             MirBaseType::F64 => context.f64_type().into(),
             MirBaseType::Bool => context.bool_type().into(),
             MirBaseType::Unit => context.void_type().into(),
@@ -665,6 +807,20 @@ enum ReturnPointer<'ctx> {
 impl<'p, 'module, 'ctx> LlvmFunctionBuilder<'p, 'module, 'ctx> {
     fn new(function: FunctionData<'ctx>, program: &'p LlvmProgramBuilder<'module, 'ctx>) -> Self {
         debug!("Creating LLVM Function Transformer for function");
+
+        if let Some(di) = &program.debug_info {
+            let name = match function.name {
+                Some(name) => program.str_table.get(name).unwrap(),
+                None => function.function.get_name().to_string_lossy().into_owned(),
+            };
+            di.borrow_mut().begin_function(
+                program.builder,
+                function.function,
+                &name,
+                function.span,
+            );
+        }
+
         let ret_ptr = match function.ret_method {
             ReturnMethod::OutParam => {
                 let out_ptr = Self::get_out_param(&function).unwrap();
@@ -757,6 +913,16 @@ impl<'p, 'module, 'ctx> LlvmFunctionBuilder<'p, 'module, 'ctx> {
             .unwrap();
     }
 
+    /// Allocates space on the stack for a value of type `ty`.  If zero initialization
+    /// of locals is enabled, then the allocated memory will be set to zero.
+    fn build_alloca(&self, ty: BasicTypeEnum<'ctx>, name: &str) -> PointerValue<'ctx> {
+        let ptr = self.program.builder.build_alloca(ty, name);
+        if self.program.options.zero_init_locals {
+            build_zero_init(self.program.context, self.program.builder, ptr);
+        }
+        ptr
+    }
+
     fn build_ptr_compare(
         &self,
         op: IntPredicate,
@@ -817,6 +983,33 @@ impl<'p, 'module, 'ctx> FunctionBuilder<Location<'ctx>, BasicValueEnum<'ctx>>
         }
     }
 
+    fn set_location(&mut self, span: Span) {
+        if let Some(di) = &self.program.debug_info {
+            di.borrow_mut().set_location(self.program.builder, span)
+        }
+    }
+
+    fn increment_counter(&mut self, counter: usize) -> Result<(), TransformerError> {
+        let counters = self
+            .program
+            .coverage
+            .expect("Coverage counters must be added before instrumenting a function");
+        let i64_ty = self.program.context.i64_type();
+        let builder = self.program.builder;
+
+        let ptr = unsafe {
+            builder.build_in_bounds_gep(
+                counters.as_pointer_value(),
+                &[i64_ty.const_zero(), i64_ty.const_int(counter as u64, false)],
+                "",
+            )
+        };
+        let count = builder.build_load(ptr, "").into_int_value();
+        let count = builder.build_int_add(count, i64_ty.const_int(1, false), "");
+        builder.build_store(ptr, count);
+        Ok(())
+    }
+
     fn alloc_arg(&mut self, arg_id: ArgId, decl: &ArgDecl) -> Result<(), TransformerError> {
         let name = self.arg_label(decl);
         let arg_value = self.get_arg(arg_id)?;
@@ -852,11 +1045,21 @@ impl<'p, 'module, 'ctx> FunctionBuilder<Location<'ctx>, BasicValueEnum<'ctx>>
             Entry::Vacant(ve) => {
                 // and add a mapping from VarID to the pointer in the local var table
                 let ty = self.program.get_type(decl.ty())?;
-                let ptr = self
-                    .program
-                    .builder
-                    .build_alloca(ty.into_basic_type().unwrap(), &name);
+                let ptr = self.build_alloca(ty.into_basic_type().unwrap(), &name);
                 ve.insert(Location::Pointer(ptr));
+
+                if let Some(di) = &self.program.debug_info {
+                    let name = self.program.str_table.get(decl.name()).unwrap();
+                    let di_ty = self.program.di_type_table.get(&decl.ty()).copied();
+                    di.borrow_mut().declare_variable(
+                        self.program.builder,
+                        ptr,
+                        &name,
+                        di_ty,
+                        decl.span(),
+                        None,
+                    );
+                }
                 Ok(())
             }
         }
@@ -874,7 +1077,7 @@ impl<'p, 'module, 'ctx> FunctionBuilder<Location<'ctx>, BasicValueEnum<'ctx>>
             Entry::Vacant(ve) => {
                 // and add a mapping from TempID to the pointer in the local var table
                 let loc = if let Ok(ty) = self.program.get_type(vd.ty())?.into_basic_type() {
-                    let ptr = self.program.builder.build_alloca(ty, &name);
+                    let ptr = self.build_alloca(ty, &name);
                     Location::Pointer(ptr)
                 } else {
                     Location::Void
@@ -1176,6 +1379,8 @@ impl<'p, 'module, 'ctx> FunctionBuilder<Location<'ctx>, BasicValueEnum<'ctx>>
             None => {
                 let s = self.program.str_table.get(id).unwrap();
                 let escaped_s = super::llvmir::convert_esc_seq_to_ascii(&s).unwrap();
+                // Every pooled string is NUL-terminated, so that a string literal can be
+                // cast to `*const u8` (e.g. `c"hello"`) and given to C functions
                 let len_w_null = escaped_s.len() + 1;
                 let g = self.program.module.add_global(
                     self.program.context.i8_type().array_type(len_w_null as u32),
@@ -1188,6 +1393,10 @@ impl<'p, 'module, 'ctx> FunctionBuilder<Location<'ctx>, BasicValueEnum<'ctx>>
                         .context
                         .const_string(escaped_s.as_bytes(), true),
                 );
+                self.program
+                    .data_section
+                    .borrow_mut()
+                    .add(&label, format!("{}\0", escaped_s).as_bytes());
                 let ptr = g.as_pointer_value();
                 ptr.into()
             }
@@ -1246,6 +1455,14 @@ impl<'p, 'module, 'ctx> FunctionBuilder<Location<'ctx>, BasicValueEnum<'ctx>>
     ) -> Result<BasicValueEnum<'ctx>, TransformerError> {
         match (a, b) {
             (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
+                runtime::build_div_zero_check(
+                    self.program.context,
+                    self.program.module,
+                    self.program.builder,
+                    r,
+                    &self.program.options,
+                );
+
                 // With the current design, the difference between signed and unsigned division is
                 // a hardware difference and falls squarely within the field of the LLVM generator
                 // module.  But this violates the precept that this module makes no decisions and only
@@ -1267,6 +1484,14 @@ impl<'p, 'module, 'ctx> FunctionBuilder<Location<'ctx>, BasicValueEnum<'ctx>>
     ) -> Result<BasicValueEnum<'ctx>, TransformerError> {
         match (a, b) {
             (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
+                runtime::build_div_zero_check(
+                    self.program.context,
+                    self.program.module,
+                    self.program.builder,
+                    r,
+                    &self.program.options,
+                );
+
                 // With the current design, the difference between signed and unsigned division is
                 // a hardware difference and falls squarely within the field of the LLVM generator
                 // module.  But this violates the precept that this module makes no decisions and only
@@ -1281,6 +1506,86 @@ impl<'p, 'module, 'ctx> FunctionBuilder<Location<'ctx>, BasicValueEnum<'ctx>>
         }
     }
 
+    fn si_sat_add(
+        &self,
+        a: BasicValueEnum<'ctx>,
+        b: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, TransformerError> {
+        match (a, b) {
+            (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => Ok(build_int_sat_op(
+                self.program.module,
+                self.program.builder,
+                "llvm.sadd.sat",
+                l,
+                r,
+            )
+            .into()),
+            _ => Err(TransformerError::Internal(
+                &LlvmBuilderError::InvalidArithmeticOperands,
+            )),
+        }
+    }
+
+    fn ui_sat_add(
+        &self,
+        a: BasicValueEnum<'ctx>,
+        b: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, TransformerError> {
+        match (a, b) {
+            (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => Ok(build_int_sat_op(
+                self.program.module,
+                self.program.builder,
+                "llvm.uadd.sat",
+                l,
+                r,
+            )
+            .into()),
+            _ => Err(TransformerError::Internal(
+                &LlvmBuilderError::InvalidArithmeticOperands,
+            )),
+        }
+    }
+
+    fn si_sat_sub(
+        &self,
+        a: BasicValueEnum<'ctx>,
+        b: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, TransformerError> {
+        match (a, b) {
+            (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => Ok(build_int_sat_op(
+                self.program.module,
+                self.program.builder,
+                "llvm.ssub.sat",
+                l,
+                r,
+            )
+            .into()),
+            _ => Err(TransformerError::Internal(
+                &LlvmBuilderError::InvalidArithmeticOperands,
+            )),
+        }
+    }
+
+    fn ui_sat_sub(
+        &self,
+        a: BasicValueEnum<'ctx>,
+        b: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, TransformerError> {
+        match (a, b) {
+            (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => Ok(build_int_sat_op(
+                self.program.module,
+                self.program.builder,
+                "llvm.usub.sat",
+                l,
+                r,
+            )
+            .into()),
+            _ => Err(TransformerError::Internal(
+                &LlvmBuilderError::InvalidArithmeticOperands,
+            )),
+        }
+    }
+
     fn f_add(
         &self,
         a: BasicValueEnum<'ctx>,
@@ -1697,6 +2002,49 @@ impl<'p, 'module, 'ctx> FunctionBuilder<Location<'ctx>, BasicValueEnum<'ctx>>
         }
     }
 
+    fn i_shl(
+        &self,
+        a: BasicValueEnum<'ctx>,
+        b: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, TransformerError> {
+        match (a, b) {
+            (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
+                Ok(self.program.builder.build_left_shift(l, r, "").into())
+            }
+            _ => Err(TransformerError::Internal(
+                &LlvmBuilderError::InvalidArithmeticOperands,
+            )),
+        }
+    }
+
+    fn i_lshr(
+        &self,
+        a: BasicValueEnum<'ctx>,
+        b: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, TransformerError> {
+        match (a, b) {
+            (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => Ok(self
+                .program
+                .builder
+                .build_right_shift(l, r, false, "")
+                .into()),
+            _ => Err(TransformerError::Internal(
+                &LlvmBuilderError::InvalidArithmeticOperands,
+            )),
+        }
+    }
+
+    fn i_bswap(&self, a: BasicValueEnum<'ctx>) -> Result<BasicValueEnum<'ctx>, TransformerError> {
+        match a {
+            BasicValueEnum::IntValue(a) => {
+                Ok(build_bswap(self.program.module, self.program.builder, a).into())
+            }
+            _ => Err(TransformerError::Internal(
+                &LlvmBuilderError::InvalidArithmeticOperands,
+            )),
+        }
+    }
+
     fn cast(
         &self,
         l: BasicValueEnum<'ctx>,
@@ -1709,6 +2057,23 @@ impl<'p, 'module, 'ctx> FunctionBuilder<Location<'ctx>, BasicValueEnum<'ctx>>
         let target_ty = self.program.get_type(target)?.into_basic_type().unwrap();
 
         let op = match (l, target_ty) {
+            // A bool is an i1, so it cannot be resized using the width of its MIR type.
+            // int to bool
+            (BasicValueEnum::IntValue(iv), BasicTypeEnum::IntType(tty))
+                if tty.get_bit_width() == 1 =>
+            {
+                let zero = iv.get_type().const_zero();
+                self.program
+                    .builder
+                    .build_int_compare(IntPredicate::NE, iv, zero, "")
+                    .into()
+            }
+            // bool to int
+            (BasicValueEnum::IntValue(iv), BasicTypeEnum::IntType(tty))
+                if iv.get_type().get_bit_width() == 1 =>
+            {
+                self.program.builder.build_int_z_extend(iv, tty, "").into()
+            }
             (BasicValueEnum::IntValue(iv), BasicTypeEnum::IntType(tty)) => {
                 // if upcasting
                 if l_sz < target_sz {
@@ -17,16 +17,17 @@ mod mir2llvm_tests_visual {
 
     use crate::{
         compiler::{
-            ast::{Element, Module, Path, Type, MAIN_MODULE},
+            ast::{Element, Path, Type, MAIN_MODULE},
             diagnostics::Logger,
+            fuel::Fuel,
             import::{Import, ImportRoutineDef, ImportStructDef},
             lexer::{tokens::Token, LexerError},
-            mir::{transform, MirProject, ProgramTraverser},
-            parser::Parser,
-            semantics::semanticnode::SemanticContext,
-            CompilerDisplay, CompilerError, Lexer, SourceMap,
+            mir::{transform, CoverageMap, MirProject, ProgramTraverser},
+            parser::{apply_link_names, expand_derives, expand_macros, Parser},
+            semantics::{reflect::eval_reflection, typed::TypedModule},
+            CompilerDisplay, CompilerError, Lexer, LineNumber, SourceMap,
         },
-        llvm::mir::LlvmProgramBuilder,
+        llvm::{mir::LlvmProgramBuilder, CodegenOptions, Sanitizer},
         resolve_types, resolve_types_with_imports, StringTable,
     };
 
@@ -307,6 +308,100 @@ mod mir2llvm_tests_visual {
         assert_eq!(true, r);
     }
 
+    #[test]
+    fn wrapping_arithmetic() {
+        let r: u8 = compile_and_run(
+            "
+            fn test() -> u8 {
+                let x: u8 := wrapping_add(250u8, 10u8);
+                let y: u8 := wrapping_sub(x, 5u8);
+                return wrapping_mul(y, 2u8);
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(254, r);
+    }
+
+    #[test]
+    fn saturating_arithmetic() {
+        let r: u8 = compile_and_run(
+            "
+            fn test() -> u8 {
+                return saturating_add(250u8, 10u8);
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(255, r);
+
+        let r: u8 = compile_and_run(
+            "
+            fn test() -> u8 {
+                return saturating_sub(5u8, 10u8);
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(0, r);
+
+        let r: i8 = compile_and_run(
+            "
+            fn test() -> i8 {
+                return saturating_sub(-100i8, 100i8);
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(-128, r);
+    }
+
+    #[test]
+    fn byte_conversion() {
+        let r: u32 = compile_and_run(
+            "
+            fn test() -> u32 {
+                let b: [u8; 4] := to_le_bytes(305419896u32);
+                return b[0] as u32 * 1000u32 + b[3] as u32;
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(0x78 * 1000 + 0x12, r);
+
+        let r: u32 = compile_and_run(
+            "
+            fn test() -> u32 {
+                let b: [u8; 4] := to_be_bytes(305419896u32);
+                return b[0] as u32 * 1000u32 + b[3] as u32;
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(0x12 * 1000 + 0x78, r);
+
+        let r: u16 = compile_and_run(
+            "
+            fn test() -> u16 {
+                let b: [u8; 2] := [1u8, 2u8];
+                return from_le_bytes(b) - from_be_bytes(b);
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(0x0201 - 0x0102, r);
+
+        let r: i64 = compile_and_run(
+            "
+            fn test() -> i64 {
+                return from_be_bytes(to_be_bytes(-2)) as i64;
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(-2, r);
+    }
+
     #[test]
     fn float_comparison() {
         let text = "
@@ -727,6 +822,58 @@ mod mir2llvm_tests_visual {
         compile_and_print_llvm(text, &[], &[]);
     }
 
+    #[test]
+    fn struct_methods() {
+        let r: i64 = compile_and_run(
+            "
+            struct Rect {w: i64, h: i64}
+
+            impl Rect {
+                fn area(self) -> i64 {
+                    return self.w * self.h;
+                }
+
+                fn scaled_area(self, k: i64) -> i64 {
+                    return self.area() * k;
+                }
+            }
+
+            fn area() -> i64 {
+                return 0;
+            }
+
+            fn test() -> i64 {
+                let r: Rect := Rect{w: 3, h: 4};
+                return r.scaled_area(2) + area();
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(24, r);
+    }
+
+    #[test]
+    fn struct_expression_initializer_order() {
+        let r: i64 = compile_and_run(
+            "
+            struct S {a: i64, b: i64}
+
+            fn next(c: *mut i64) -> i64 {
+                mut ^c := ^c + 1;
+                return ^c;
+            }
+
+            fn test() -> i64 {
+                let mut c: i64 := 0;
+                let s: S := S{b: next(@mut c), a: next(@mut c)};
+                return s.a * 10 + s.b;
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(21, r);
+    }
+
     #[test]
     fn struct_member_access() {
         let text = "
@@ -847,6 +994,84 @@ mod mir2llvm_tests_visual {
         assert_eq!(18, r);
     }
 
+    #[test]
+    fn if_expr_with_early_return() {
+        let r: i64 = compile_and_run(
+            "
+            fn run() -> i64 {
+                let a: i64 := test(0);
+                let b: i64 := test(20);
+                let c: i64 := test(5);
+
+                return a * 100 + b * 10 + c;
+            }
+
+            fn test(x: i64) -> i64 {
+                if (x < 1) {
+                    return 1;
+                };
+
+                let mut y: i64 := 0;
+                if (x > 10) {
+                    return 2;
+                } else {
+                    mut y := x - 2;
+                };
+
+                return y;
+            }
+        ",
+            "main_run",
+        );
+        assert_eq!(123, r);
+    }
+
+    #[test]
+    fn if_expr_both_arms_return() {
+        let r: i64 = compile_and_run(
+            "
+            fn run() -> i64 {
+                return test(true) * 10 + test(false);
+            }
+
+            fn test(b: bool) -> i64 {
+                if (b) {
+                    return 1;
+                } else {
+                    return 2;
+                };
+
+                return 3;
+            }
+        ",
+            "main_run",
+        );
+        assert_eq!(12, r);
+    }
+
+    #[test]
+    fn if_expr_with_diverging_arm() {
+        let r: i64 = compile_and_run(
+            "
+            fn run() -> i64 {
+                return test(true) * 10 + test(false);
+            }
+
+            fn test(b: bool) -> i64 {
+                let x: i64 := if (b) {
+                    5
+                } else {
+                    return 3;
+                };
+
+                return x;
+            }
+        ",
+            "main_run",
+        );
+        assert_eq!(53, r);
+    }
+
     #[test]
     fn while_expr() {
         let r: i64 = compile_and_run(
@@ -998,6 +1223,499 @@ mod mir2llvm_tests_visual {
         assert_eq!(2, result);
     }
 
+    #[test]
+    fn zero_init_locals() {
+        let text = "
+            struct S {
+                a: i64,
+                b: [i64; 2],
+            }
+
+            fn foo() -> i64 {
+                let s: S := S{a: 1, b: [2, 3]};
+                let arr: [i64; 2] := [s.a, s.b[1]];
+                return arr[0] + arr[1];
+            }
+        ";
+        let options = CodegenOptions {
+            zero_init_locals: true,
+            ..Default::default()
+        };
+        let result: i64 = compile_and_run_with_options(text, "main_foo", options.clone());
+        assert_eq!(4, result);
+
+        // Every stack allocation is zeroed with a memset, which is not emitted when
+        // zeroing is disabled
+        let zeroes = |ir: &str| {
+            function_ir(ir, "main_foo")
+                .lines()
+                .filter(|l| l.contains("call void @llvm.memset") && l.contains(", i8 0,"))
+                .count()
+        };
+        let zeroed = compile_to_llvm_ir(text, options);
+        let allocas = function_ir(&zeroed, "main_foo")
+            .matches(" = alloca ")
+            .count();
+        assert!(allocas > 0);
+        assert_eq!(zeroes(&zeroed), allocas);
+
+        let not_zeroed = compile_to_llvm_ir(text, CodegenOptions::default());
+        assert_eq!(zeroes(&not_zeroed), 0);
+    }
+
+    #[test]
+    fn sanitize_undefined_division() {
+        let result: i64 = compile_and_run_with_options(
+            "
+            fn foo() -> i64 {
+                let a: i64 := 12;
+                let b: u8 := 4u8;
+                return a / 3 + (b / 2u8) as i64;
+            }
+        ",
+            "main_foo",
+            CodegenOptions {
+                sanitizers: vec![Sanitizer::Undefined],
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(6, result);
+    }
+
+    #[test]
+    fn sanitize_undefined_division_by_runtime_zero() {
+        // The program is not run, because the trap would end the test process
+        let ir = compile_to_llvm_ir(
+            "
+            fn foo(d: i64) -> i64 {
+                return 12 / d;
+            }
+
+            fn bar() -> i64 {
+                let zero: i64 := 0;
+                return foo(zero);
+            }
+        ",
+            CodegenOptions {
+                sanitizers: vec![Sanitizer::Undefined],
+                ..Default::default()
+            },
+        );
+
+        let foo = function_ir(&ir, "main_foo");
+        let check = foo.find("call void @__bramble_check_div_zero(").unwrap();
+        let div = foo.find("sdiv").unwrap();
+        assert!(check < div);
+
+        let check_fn = function_ir(&ir, "__bramble_check_div_zero");
+        assert!(check_fn.contains("icmp eq i64 %0, 0"));
+        assert!(check_fn.contains("call void @llvm.trap()"));
+    }
+
+    #[test]
+    fn debug_info() {
+        let result: i64 = compile_and_run_with_options(
+            "
+            fn foo() -> i64 {
+                let a: i64 := 12;
+                let s: string := \"hello\";
+                let p: *const i64 := @const a;
+                let arr: [i64; 2] := [1, 2];
+                return bar(^p, arr[1]);
+            }
+
+            fn bar(x: i64, y: i64) -> i64 {
+                let z: i64 := x * y;
+                return z;
+            }
+        ",
+            "main_foo",
+            CodegenOptions {
+                debug_info: true,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(24, result);
+    }
+
+    #[test]
+    fn optimize() {
+        let result: i64 = compile_and_run_with_options(
+            "
+            struct Pair {a: i64, b: i64}
+
+            fn foo() -> i64 {
+                let mut total: i64 := 0;
+                let mut i: i64 := 0;
+                while (i < 10) {
+                    let p: Pair := Pair{a: i, b: i * 2};
+                    mut total := total + bar(p.a, p.b);
+                    mut i := i + 1;
+                };
+                return total;
+            }
+
+            fn bar(x: i64, y: i64) -> i64 {
+                let z: i64 := x + y;
+                return z;
+            }
+        ",
+            "main_foo",
+            CodegenOptions {
+                optimize: true,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(135, result);
+    }
+
+    #[test]
+    fn signal_handlers() {
+        let (sm, table, module, _) = compile("fn my_main() -> i64 { return 0; }", &[], &[]);
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let context = Context::create();
+        let module = context.create_module("test");
+        let builder = context.create_builder();
+        let main_name = table.insert("my_main".into());
+        let options = CodegenOptions {
+            signal_handlers: true,
+            ..Default::default()
+        };
+        let mut xfmr =
+            LlvmProgramBuilder::new(&context, &module, &builder, &sm, &table, main_name, options);
+        ProgramTraverser::new(&project, &sm, &table)
+            .map(&mut xfmr)
+            .unwrap();
+        xfmr.complete();
+
+        // The handlers are not run here, because they would stay installed in the test
+        // process after the JIT compiled code which they point to is freed
+        module.verify().unwrap();
+        let main = module.get_function("main").unwrap().print_to_string();
+        assert!(main
+            .to_string()
+            .contains("call void @__bramble_install_signal_handlers()"));
+    }
+
+    #[test]
+    fn optimization_remarks() {
+        let text = "
+            fn foo() -> i64 {
+                return bar(2, 3);
+            }
+
+            fn bar(x: i64, y: i64) -> i64 {
+                return x * y;
+            }
+        ";
+        let (sm, table, module, _) = compile(text, &[], &[]);
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let context = Context::create();
+        let module = context.create_module("test");
+        let builder = context.create_builder();
+        let main_name = table.insert("my_main".into());
+        let options = CodegenOptions {
+            debug_info: true,
+            optimize: true,
+            ..Default::default()
+        };
+        let mut xfmr =
+            LlvmProgramBuilder::new(&context, &module, &builder, &sm, &table, main_name, options);
+        ProgramTraverser::new(&project, &sm, &table)
+            .map(&mut xfmr)
+            .unwrap();
+        let llvm = xfmr.complete();
+
+        let remarks = llvm.collect_remarks();
+        llvm.optimize();
+        let remarks = remarks.finish(&sm);
+
+        // The call to bar is inlined and the remark points at the line of the call
+        let inlined = remarks
+            .iter()
+            .find(|r| r.message().contains("inlined into"))
+            .unwrap();
+        let (_, line, _) = sm.location(inlined.span().unwrap().low()).unwrap();
+        assert_eq!(line, LineNumber::new(3));
+    }
+
+    #[test]
+    fn defer() {
+        let result: i64 = compile_and_run(
+            "
+            fn foo() -> i64 {
+                let mut log: i64 := 0;
+                let r: i64 := bar(@mut log, 1);
+                let s: i64 := bar(@mut log, 5);
+                return log * 100 + r * 10 + s;
+            }
+
+            fn bar(log: *mut i64, x: i64) -> i64 {
+                defer {mut ^log := ^log * 10 + 1;};
+                let y: i64 := {
+                    defer {mut ^log := ^log * 10 + 2;};
+                    if (x > 3) {
+                        return x;
+                    };
+                    x * 2
+                };
+                return y;
+            }
+        ",
+            "main_foo",
+        );
+
+        // The first call leaves the block and then returns, the second returns from
+        // within the block, and both run the deferred expressions innermost first
+        assert_eq!(212125, result);
+    }
+
+    #[test]
+    fn defer_is_evaluated_in_its_scope() {
+        let result: i64 = compile_and_run(
+            "
+            fn foo() -> i64 {
+                let mut out: i64 := 0;
+                let r: i64 := bar(@mut out);
+                return out * 10 + r;
+            }
+
+            fn bar(out: *mut i64) -> i64 {
+                let mut x: i64 := 7;
+                defer {mut ^out := x;};
+                let y: i64 := {
+                    defer {mut x := 1;};
+                    x
+                };
+                {
+                    let x: i64 := 3;
+                    return x + y;
+                };
+                return 0;
+            }
+        ",
+            "main_foo",
+        );
+
+        // The value of the block is taken before its deferred expression runs, and the
+        // `x` which is deferred is not the `x` which is declared in the inner block
+        assert_eq!(20, result);
+    }
+
+    #[test]
+    fn options() {
+        let result: i64 = compile_and_run(
+            "
+            fn foo() -> i64 {
+                let a: opt i64 := even(8);
+                let b: opt i64 := even(3);
+                let mut r: i64 := 0;
+                if (let some(v) := a) {
+                    mut r := r + v * 10;
+                };
+                if (let some(v) := b) {
+                    mut r := r + v;
+                } else {
+                    mut r := r + 1;
+                };
+                return r;
+            }
+
+            fn even(x: i64) -> opt i64 {
+                return if (x / 2 * 2 == x) {some(x)} else {none};
+            }
+        ",
+            "main_foo",
+        );
+        assert_eq!(81, result);
+    }
+
+    #[test]
+    fn try_option_runs_defers() {
+        let result: i64 = compile_and_run(
+            "
+            fn foo() -> i64 {
+                let mut log: i64 := 0;
+                let a: opt i64 := half(@mut log, 8);
+                let b: opt i64 := half(@mut log, 3);
+                let mut r: i64 := log * 100;
+                if (let some(v) := a) {
+                    mut r := r + v * 10;
+                };
+                if (let some(v) := b) {
+                    mut r := r + v;
+                };
+                return r;
+            }
+
+            fn half(log: *mut i64, x: i64) -> opt i64 {
+                defer {mut ^log := ^log * 10 + 1;};
+                let h: i64 := even(x)?;
+                return some(h / 2);
+            }
+
+            fn even(x: i64) -> opt i64 {
+                return if (x / 2 * 2 == x) {some(x)} else {none};
+            }
+        ",
+            "main_foo",
+        );
+
+        // Both calls run the deferred expression, including the one where `?` returns
+        // early, and only the first call has a value
+        assert_eq!(1140, result);
+    }
+
+    #[test]
+    fn results_and_try() {
+        let result: i64 = compile_and_run(
+            "
+            fn foo() -> i64 {
+                let a: res i64, i64 := check(5);
+                let b: res i64, i64 := check(-3);
+                let mut r: i64 := 0;
+                if (let ok(v) := a) {
+                    mut r := r + v * 100;
+                };
+                if (let err(e) := b) {
+                    mut r := r + e;
+                };
+                return r;
+            }
+
+            fn check(x: i64) -> res i64, i64 {
+                let y: i64 := positive(x)?;
+                return ok(y + 1);
+            }
+
+            fn positive(x: i64) -> res i64, i64 {
+                if (x < 0) {
+                    return err(0 - x);
+                };
+                return ok(x);
+            }
+        ",
+            "main_foo",
+        );
+
+        // `?` returns the err of a failed result from the calling routine
+        assert_eq!(603, result);
+    }
+
+    #[test]
+    fn break_and_continue() {
+        let result: i64 = compile_and_run(
+            "
+            fn foo() -> i64 {
+                let mut sum: i64 := 0;
+                for (i in 0..10) {
+                    if (i == 7) {
+                        break;
+                    };
+                    if (i / 2 * 2 == i) {
+                        continue;
+                    };
+                    mut sum := sum + i;
+                };
+                return sum;
+            }
+        ",
+            "main_foo",
+        );
+
+        // `continue` still steps the for loop, so only the odd numbers below 7 are summed
+        assert_eq!(9, result);
+    }
+
+    #[test]
+    fn labeled_loops() {
+        let result: i64 = compile_and_run(
+            "
+            fn foo() -> i64 {
+                let mut log: i64 := 0;
+                let mut i: i64 := 0;
+                'outer: while (i < 3) {
+                    mut i := i + 1;
+                    defer {mut log := log * 10 + 9;};
+                    let mut j: i64 := 0;
+                    while (true) {
+                        mut j := j + 1;
+                        if (j > i) {
+                            continue 'outer;
+                        };
+                        if (i == 3) {
+                            break 'outer;
+                        };
+                        mut log := log * 10 + j;
+                    };
+                };
+                return log;
+            }
+        ",
+            "main_foo",
+        );
+
+        // Leaving the outer loop from within the inner loop runs the outer loop's defer
+        assert_eq!(191299, result);
+    }
+
+    #[test]
+    fn parse_digits_of_string() {
+        let result: i64 = compile_and_run(
+            "
+            fn foo() -> i64 {
+                return parse(\"-1234\") + parse(\"56\") * 100000;
+            }
+
+            fn parse(s: string) -> i64 {
+                let p: *const u8 := s as *const u8;
+                let negative: bool := ^p == 45u8;
+                let mut i: i64 := if (negative) {1} else {0};
+                let mut value: i64 := 0;
+                while (^(p@i) != 0u8) {
+                    mut value := value * 10 + (^(p@i) - 48u8) as i64;
+                    mut i := i + 1;
+                };
+                return if (negative) {-value} else {value};
+            }
+        ",
+            "main_foo",
+        );
+
+        assert_eq!(5598766, result);
+    }
+
+    #[test]
+    fn coverage() {
+        let result: i64 = compile_and_run_instrumented(
+            "
+            fn foo() -> i64 {
+                let mut i: i64 := 0;
+                while (i < 3) {
+                    i = i + 1;
+                };
+                return if (i > 2) {bar(i)} else {0};
+            }
+
+            fn bar(x: i64) -> i64 {
+                return x * 2;
+            }
+        ",
+            "main_foo",
+            CodegenOptions::default(),
+            true,
+        );
+
+        assert_eq!(6, result);
+    }
+
     #[test]
     fn function_array_argument() {
         compile_and_print_llvm(
@@ -1071,6 +1789,41 @@ mod mir2llvm_tests_visual {
         assert_eq!(2, r);
     }
 
+    #[test]
+    fn extern_fn_link_name() {
+        let r: u64 = compile_and_run(
+            "
+            mod ffi {
+                #[link_name = \"llabs\"]
+                extern fn abs(i: i64) -> u64;
+            }
+
+            fn foo() -> u64 {
+                return ffi::abs(-2);
+            }
+        ",
+            "main_foo",
+        );
+
+        assert_eq!(2, r);
+    }
+
+    #[test]
+    fn extern_fn_c_string() {
+        let r: u64 = compile_and_run(
+            "
+            extern fn strlen(s: *const u8) -> u64;
+
+            fn foo() -> u64 {
+                return strlen(c\"hello\") + strlen(\"world\" as *const u8);
+            }
+        ",
+            "main_foo",
+        );
+
+        assert_eq!(10, r);
+    }
+
     #[test]
     fn extern_fn_complex() {
         let r: f64 = compile_and_run(
@@ -1111,6 +1864,100 @@ mod mir2llvm_tests_visual {
         assert_eq!("hello, world".len(), r as usize);
     }
 
+    #[test]
+    fn reflection_builtins() {
+        let r: u64 = compile_and_run(
+            "
+            extern fn strlen(s: string) -> u64;
+
+            struct S {
+                ab: i64,
+                c: bool,
+            }
+
+            fn foo() -> u64 {
+                let fields: [[string; 2]; 2] := fields_of(S);
+                return strlen(fields[0][0]) * 100u64
+                    + strlen(fields[1][1]) * 10u64
+                    + strlen(name_of(i64));
+            }
+        ",
+            "main_foo",
+        );
+
+        // `ab`, `bool`, and `i64`
+        assert_eq!(r, 243);
+    }
+
+    #[test]
+    fn derive_eq() {
+        let r: u64 = compile_and_run(
+            "
+            #[derive(eq)]
+            struct Inner {
+                b: bool,
+            }
+
+            #[derive(eq)]
+            struct S {
+                x: i64,
+                inner: Inner,
+                xs: [[u8; 2]; 2],
+            }
+
+            fn foo() -> u64 {
+                let s: S := S{x: 1, inner: Inner{b: true}, xs: [[1u8, 2u8], [3u8, 4u8]]};
+                let t: S := S{x: 1, inner: Inner{b: true}, xs: [[1u8, 2u8], [3u8, 4u8]]};
+                let u: S := S{x: 1, inner: Inner{b: false}, xs: [[1u8, 2u8], [3u8, 4u8]]};
+                let v: S := S{x: 1, inner: Inner{b: true}, xs: [[1u8, 2u8], [3u8, 5u8]]};
+
+                let mut r: u64 := 0u64;
+                if (s == t) { mut r := r + 1000u64; };
+                if (s != u) { mut r := r + 100u64; };
+                if (s != v) { mut r := r + 10u64; };
+                if (eq_S(s, s)) { mut r := r + 1u64; };
+                return r;
+            }
+        ",
+            "main_foo",
+        );
+        assert_eq!(r, 1111);
+    }
+
+    #[test]
+    fn macros() {
+        let r: u64 = compile_and_run(
+            "
+            macro max {
+                ($a:expr, $b:expr) => {
+                    let a: u64 := $a;
+                    let b: u64 := $b;
+                    if (a > b) {a} else {b}
+                };
+            }
+
+            macro sum {
+                ($x:expr) => { $x };
+                ($x:expr, $y:expr) => { $x + $y };
+                ($x:expr, $y:expr, $z:expr) => { $x + sum!($y, $z) };
+            }
+
+            macro inc {
+                ($v:ident) => { mut $v := $v + 1u64; };
+            }
+
+            fn foo() -> u64 {
+                let mut a: u64 := 1u64;
+                inc!(a);
+                let b: u64 := 10u64;
+                return max!(b, a * 1000u64) + sum!(1u64, 2u64, 3u64);
+            }
+        ",
+            "main_foo",
+        );
+        assert_eq!(r, 2006);
+    }
+
     #[test]
     fn cast_int_to_uint() {
         let r: u64 = compile_and_run(
@@ -1186,6 +2033,45 @@ mod mir2llvm_tests_visual {
         assert_eq!(-9.0, r);
     }
 
+    #[test]
+    fn cast_bool_to_int() {
+        let r: i64 = compile_and_run(
+            "
+            fn test() -> i64 {
+                let t: bool := true;
+                let f: bool := false;
+
+                return t as i64 * 10 + f as i64;
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(10, r);
+    }
+
+    #[test]
+    fn cast_int_to_bool() {
+        let r: i64 = compile_and_run(
+            "
+            fn test() -> i64 {
+                let x: i64 := -9;
+                let y: u8 := 0u8;
+                let mut r: i64 := 0;
+                if (x as bool) {
+                    mut r := r + 10;
+                };
+                if (y as bool) {
+                    mut r := r + 1;
+                };
+
+                return r;
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(10, r);
+    }
+
     #[test]
     fn size_of() {
         let r: u64 = compile_and_run(
@@ -1212,6 +2098,21 @@ mod mir2llvm_tests_visual {
         assert_eq!(16, r);
     }
 
+    #[test]
+    fn usize_is_pointer_width() {
+        let r: u64 = compile_and_run(
+            "
+            fn test() -> u64 {
+                let i: usize := 3 as usize;
+                let arr: [i64; 4] := [1, 2, 3, 4];
+                return (arr[i] as usize + size_of(*const i64) as usize) as u64;
+            }
+        ",
+            "main_test",
+        );
+        assert_eq!(12, r);
+    }
+
     #[test]
     fn import_function() {
         compile_and_print_llvm(
@@ -1263,15 +2164,24 @@ mod mir2llvm_tests_visual {
         let builder = context.create_builder();
 
         let main_name = table.insert("my_main".into());
-        let mut xfmr = LlvmProgramBuilder::new(&context, &module, &builder, &sm, &table, main_name);
+        let mut xfmr = LlvmProgramBuilder::new(
+            &context,
+            &module,
+            &builder,
+            &sm,
+            &table,
+            main_name,
+            CodegenOptions::default(),
+        );
 
         let proj_traverser = ProgramTraverser::new(&project, &sm, &table);
 
         // Traverser is given a MirProject
         // call traverser.map(llvm) this will use the llvm xfmr to map MirProject to LlvmProject
-        proj_traverser.map(&mut xfmr);
+        proj_traverser.map(&mut xfmr).unwrap();
 
         let llvm = xfmr.complete();
+        llvm.optimize();
 
         // Print LLVM
         println!("=== LLVM IR ===:");
@@ -1281,7 +2191,60 @@ mod mir2llvm_tests_visual {
         llvm.print_asm();
     }
 
+    /// Compiles the given program with the given options and returns the LLVM IR of the
+    /// module, before it is optimized.
+    fn compile_to_llvm_ir(text: &str, options: CodegenOptions) -> String {
+        let (sm, table, module, _) = compile(text, &[], &[]);
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let context = Context::create();
+        let module = context.create_module("test");
+        let builder = context.create_builder();
+        let main_name = table.insert("my_main".into());
+        let mut xfmr =
+            LlvmProgramBuilder::new(&context, &module, &builder, &sm, &table, main_name, options);
+        ProgramTraverser::new(&project, &sm, &table)
+            .map(&mut xfmr)
+            .unwrap();
+        xfmr.complete();
+
+        module.verify().unwrap();
+        module.print_to_string().to_string()
+    }
+
+    /// Returns the LLVM IR of the definition of the function with the given name.
+    fn function_ir<'a>(ir: &'a str, name: &str) -> &'a str {
+        let signature = format!("@{}(", name);
+        let start = ir
+            .match_indices("define ")
+            .map(|(at, _)| at)
+            .find(|&at| ir[at..].lines().next().unwrap().contains(&signature))
+            .unwrap();
+        let end = start + ir[start..].find("\n}\n").unwrap();
+        &ir[start..end]
+    }
+
     fn compile_and_run<R: std::fmt::Debug>(text: &str, func_name: &str) -> R {
+        compile_and_run_with_options(text, func_name, CodegenOptions::default())
+    }
+
+    fn compile_and_run_with_options<R: std::fmt::Debug>(
+        text: &str,
+        func_name: &str,
+        options: CodegenOptions,
+    ) -> R {
+        compile_and_run_instrumented(text, func_name, options, false)
+    }
+
+    /// Compiles and runs the given function.  If `coverage` is true, then every basic
+    /// block is instrumented with a coverage counter.
+    fn compile_and_run_instrumented<R: std::fmt::Debug>(
+        text: &str,
+        func_name: &str,
+        options: CodegenOptions,
+        coverage: bool,
+    ) -> R {
         let (sm, table, module, _) = compile(text, &[], &[]);
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
@@ -1295,13 +2258,19 @@ mod mir2llvm_tests_visual {
 
         let main_name = table.insert("my_main".into());
 
-        let mut xfmr = LlvmProgramBuilder::new(&context, &module, &builder, &sm, &table, main_name);
+        let mut xfmr =
+            LlvmProgramBuilder::new(&context, &module, &builder, &sm, &table, main_name, options);
 
-        let proj_traverser = ProgramTraverser::new(&project, &sm, &table);
+        let coverage_map = CoverageMap::new(&project);
+        let mut proj_traverser = ProgramTraverser::new(&project, &sm, &table);
+        if coverage {
+            xfmr.add_coverage_counters(coverage_map.len());
+            proj_traverser.set_coverage(&coverage_map);
+        }
 
         // Traverser is given a MirProject
         // call traverser.map(llvm) this will use the llvm xfmr to map MirProject to LlvmProject
-        proj_traverser.map(&mut xfmr);
+        proj_traverser.map(&mut xfmr).unwrap();
 
         let llvm = xfmr.complete();
 
@@ -1324,7 +2293,7 @@ mod mir2llvm_tests_visual {
         input: &str,
         import_funcs: &[(&str, &[(&str, Type)], Type)],
         import_structs: &[(&str, &[(&str, Type)])],
-    ) -> (SourceMap, StringTable, Module<SemanticContext>, Vec<Import>) {
+    ) -> (SourceMap, StringTable, TypedModule, Vec<Import>) {
         let table = StringTable::new();
 
         // Create an import
@@ -1376,14 +2345,26 @@ mod mir2llvm_tests_visual {
             .unwrap();
 
         let parser = Parser::new(&logger);
-        let ast = match parser.parse(main, &tokens) {
-            Ok(ast) => ast.unwrap(),
+        let ast = match parser.parse(main, &tokens).and_then(|ast| {
+            let mut ast = ast.unwrap();
+            expand_macros(&mut ast, &table, &logger, &Fuel::unlimited())?;
+            expand_derives(&mut ast, &table)?;
+            apply_link_names(&mut ast, &table)?;
+            Ok(ast)
+        }) {
+            Ok(ast) => ast,
             Err(err) => {
                 panic!("{}", err.fmt(&sm, &table).unwrap());
             }
         };
-        match resolve_types_with_imports(&ast, main_mod, main_fn, &imports, &logger) {
-            Ok(module) => (sm, table, module, imports),
+        let fuel = Fuel::unlimited();
+        match resolve_types_with_imports(&ast, main_mod, main_fn, &imports, &table, &logger, &fuel)
+        {
+            Ok(module) => {
+                let mut module = module.into_inner();
+                eval_reflection(&mut module, &sm, &table).unwrap();
+                (sm, table, TypedModule::check(module).unwrap(), imports)
+            }
             Err(err) => {
                 panic!("{}", err.fmt(&sm, &table).unwrap());
             }
@@ -1,16 +1,27 @@
+mod abi;
+mod datasection;
+mod debuginfo;
 mod import;
 /**
    Translate Bramble into LLVM IR.
 */
 mod llvmir;
 mod mir;
+mod options;
+mod remarks;
+mod runtime;
 mod scopestack;
 mod stringpool;
+mod target;
 mod writable;
 
 use super::ast;
+pub use datasection::DataSection;
 pub use llvmir::IrGen;
 pub use mir::LlvmProgramBuilder;
+pub use options::{CodegenOptions, Sanitizer};
+pub use remarks::{Remark, RemarkCollector};
+pub use target::{pointer_width, registered_targets, target_stats, TargetStats};
 
 #[cfg(test)]
 mod mir_test;
@@ -0,0 +1,76 @@
+//! Configuration which controls how the LLVM IR for a Bramble program is generated.
+
+/// Options, usually set by the user through the CLI, which change the code that is
+/// generated by either LLVM backend.
+#[derive(Clone, Debug, Default)]
+pub struct CodegenOptions {
+    /// If `true`, then every stack allocation (local variables, parameters, and
+    /// structure or array temporaries) will be zeroed as soon as it is allocated.
+    pub zero_init_locals: bool,
+
+    /// The set of sanitizers which will instrument the generated code.
+    pub sanitizers: Vec<Sanitizer>,
+
+    /// The target triple (e.g. `wasm32-unknown-unknown`) to generate code for.  If
+    /// `None`, then code is generated for the machine running the compiler.
+    pub target_triple: Option<String>,
+
+    /// The CPU to generate code for. If `None`, then code is generated for a generic
+    /// CPU of the host architecture; `native` selects the CPU of the host machine.
+    pub target_cpu: Option<String>,
+
+    /// Target features (e.g. `+avx2`) which are enabled or disabled on the target
+    /// machine in addition to those of the selected CPU.
+    pub target_features: Vec<String>,
+
+    /// If `true`, then a function whose code generation hits an internal compiler
+    /// error is skipped and code generation continues with the next function, so that
+    /// every function which triggers a bug is reported.
+    pub continue_on_ice: bool,
+
+    /// If `true`, then every index into an array or a slice is checked against the
+    /// length of the array or slice, and the program traps if it is out of bounds.
+    pub bounds_checks: bool,
+
+    /// If `true`, then DWARF debug information, which maps the generated code back to
+    /// the source code, is added to the module.
+    pub debug_info: bool,
+
+    /// If `true`, then LLVM's optimization passes are run over the module: small
+    /// functions are inlined, local variables are promoted from the stack into
+    /// registers, redundant instructions are combined or removed, loops are vectorized,
+    /// and the code generator optimizes the machine code it emits.
+    pub optimize: bool,
+
+    /// If `true`, then the `main` function installs handlers for the signals raised by
+    /// runtime errors (e.g. a segmentation fault or a division by zero), which describe
+    /// the error and write a stack trace before the program ends.
+    pub signal_handlers: bool,
+}
+
+impl CodegenOptions {
+    /// Returns `true` if the given [`Sanitizer`] has been enabled.
+    pub fn sanitize(&self, sanitizer: Sanitizer) -> bool {
+        self.sanitizers.contains(&sanitizer)
+    }
+}
+
+/// Runtime checks which can be added to the generated code to detect bugs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sanitizer {
+    /// Checks the divisor of every integer division and traps if it is zero. This is
+    /// the only undefined behavior which is checked: overflow and out of range shifts
+    /// are not.
+    Undefined,
+}
+
+impl std::str::FromStr for Sanitizer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "undefined" => Ok(Sanitizer::Undefined),
+            _ => Err(format!("Unrecognized sanitizer: {}", s)),
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Collects the optimization remarks which LLVM reports while it optimizes and compiles
+//! a module (e.g. that a call was inlined or that a loop could not be vectorized), so
+//! that they can be shown to the user along with the Bramble source code that they are
+//! about.
+//!
+//! Inkwell does not expose LLVM's diagnostic handler, so the handler is installed
+//! through `llvm-sys`.  LLVM only knows where a remark came from if the module has
+//! debug information.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_void, CStr},
+    marker::PhantomData,
+    os::raw::c_char,
+    sync::Once,
+};
+
+use inkwell::module::Module;
+use llvm_sys::{
+    core::{
+        LLVMContextSetDiagnosticHandler, LLVMDisposeMessage, LLVMGetDiagInfoDescription,
+        LLVMGetDiagInfoSeverity, LLVMGetModuleContext,
+    },
+    prelude::{LLVMContextRef, LLVMDiagnosticInfoRef},
+    support::LLVMParseCommandLineOptions,
+    LLVMDiagnosticSeverity,
+};
+
+use crate::{
+    compiler::{error::format_span, CompilerDisplay, CompilerDisplayError, SourceMap, Span},
+    StringTable,
+};
+
+use super::debuginfo::span_at;
+
+/// The LLVM options which enable the remarks of every pass: those for optimizations
+/// which were done, for optimizations which were missed, and for the analyses which
+/// explain them.  LLVM reads these like the arguments of a program, so the first is
+/// the program name.
+const REMARK_OPTIONS: [&[u8]; 4] = [
+    b"bramblec\0",
+    b"-pass-remarks=.*\0",
+    b"-pass-remarks-missed=.*\0",
+    b"-pass-remarks-analysis=.*\0",
+];
+
+/// LLVM's options can only be set once per process
+static ENABLE_REMARKS: Once = Once::new();
+
+/// An optimization remark which LLVM reported about the generated code.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Remark {
+    /// The source code which the remark is about, if LLVM knew where the code came from
+    span: Option<Span>,
+
+    /// LLVM's description of what it did, or did not do, with the code
+    message: String,
+}
+
+impl Remark {
+    /// Reads a remark from LLVM's description of it, which starts with the name of
+    /// the file and the line and column that the remark is about (e.g.
+    /// `main.br:3:5: bar inlined into foo`).  LLVM writes `<unknown>:0:0` if it does
+    /// not know where the code came from.
+    fn parse(sm: &SourceMap, description: &str) -> Remark {
+        let located = description
+            .split_once(": ")
+            .and_then(|(location, message)| {
+                let mut parts = location.rsplitn(3, ':');
+                let column = parts.next()?.parse().ok()?;
+                let line = parts.next()?.parse().ok()?;
+                let file = parts.next()?;
+                Some((span_at(sm, file, line, column), message))
+            });
+
+        match located {
+            Some((span, message)) => Remark {
+                span,
+                message: message.into(),
+            },
+            None => Remark {
+                span: None,
+                message: description.into(),
+            },
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl CompilerDisplay for Remark {
+    fn fmt(&self, sm: &SourceMap, _: &StringTable) -> Result<String, CompilerDisplayError> {
+        Ok(match self.span {
+            Some(span) => format!("{}: {}", format_span(sm, span), self.message),
+            None => self.message.clone(),
+        })
+    }
+}
+
+/// Records every remark which LLVM reports for the module that it was installed on,
+/// until it is finished or dropped.  Other diagnostics are written to `stderr`, as
+/// LLVM would have written them.
+pub struct RemarkCollector<'ctx> {
+    context: LLVMContextRef,
+
+    /// The description of each remark.  This is boxed so that its address, which LLVM
+    /// passes to the handler, does not change when the collector is moved.
+    remarks: Box<RefCell<Vec<String>>>,
+
+    _module: PhantomData<&'ctx Module<'ctx>>,
+}
+
+impl<'ctx> RemarkCollector<'ctx> {
+    /// Enables LLVM's optimization remarks and starts collecting those which are
+    /// reported for `module`.
+    pub(super) fn install(module: &Module<'ctx>) -> RemarkCollector<'ctx> {
+        ENABLE_REMARKS.call_once(|| {
+            let argv: Vec<*const c_char> = REMARK_OPTIONS
+                .iter()
+                .map(|opt| opt.as_ptr() as *const c_char)
+                .collect();
+            unsafe {
+                LLVMParseCommandLineOptions(
+                    argv.len() as i32,
+                    argv.as_ptr(),
+                    b"\0".as_ptr() as *const c_char,
+                )
+            }
+        });
+
+        let remarks = Box::new(RefCell::new(vec![]));
+        let context = unsafe { LLVMGetModuleContext(module.as_mut_ptr()) };
+        unsafe {
+            LLVMContextSetDiagnosticHandler(
+                context,
+                Some(handle_diagnostic),
+                &*remarks as *const RefCell<Vec<String>> as *mut c_void,
+            )
+        };
+
+        RemarkCollector {
+            context,
+            remarks,
+            _module: PhantomData,
+        }
+    }
+
+    /// Stops collecting and returns every remark which was reported, in the order that
+    /// LLVM reported them, with the source code that each is about.
+    pub fn finish(self, sm: &SourceMap) -> Vec<Remark> {
+        self.remarks
+            .borrow()
+            .iter()
+            .map(|description| Remark::parse(sm, description))
+            .collect()
+    }
+}
+
+impl<'ctx> Drop for RemarkCollector<'ctx> {
+    fn drop(&mut self) {
+        // Restore LLVM's own handler, so that it never calls back into freed memory
+        unsafe { LLVMContextSetDiagnosticHandler(self.context, None, std::ptr::null_mut()) }
+    }
+}
+
+extern "C" fn handle_diagnostic(info: LLVMDiagnosticInfoRef, remarks: *mut c_void) {
+    let remarks = unsafe { &*(remarks as *const RefCell<Vec<String>>) };
+    let description = unsafe {
+        let raw = LLVMGetDiagInfoDescription(info);
+        let description = CStr::from_ptr(raw).to_string_lossy().into_owned();
+        LLVMDisposeMessage(raw);
+        description
+    };
+
+    match unsafe { LLVMGetDiagInfoSeverity(info) } {
+        LLVMDiagnosticSeverity::LLVMDSRemark => remarks.borrow_mut().push(description),
+        LLVMDiagnosticSeverity::LLVMDSNote => eprintln!("note: {}", description),
+        LLVMDiagnosticSeverity::LLVMDSWarning => eprintln!("warning: {}", description),
+        LLVMDiagnosticSeverity::LLVMDSError => {
+            // LLVM's own handler ends the process on an error
+            eprintln!("error: {}", description);
+            std::process::exit(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::source::Offset;
+
+    use super::*;
+
+    #[test]
+    fn parse_located_remark() {
+        let mut sm = SourceMap::new();
+        sm.add_string("fn a() {}\n", "/src/first.br".into())
+            .unwrap();
+        sm.add_string("fn foo() {\n    bar();\n}\n", "/src/main.br".into())
+            .unwrap();
+
+        let remark = Remark::parse(&sm, "main.br:2:5: bar inlined into foo");
+        assert_eq!(remark.message(), "bar inlined into foo");
+
+        // The second file starts after the 10 bytes of the first, and `bar` is 15
+        // bytes into the second
+        let span = remark.span().unwrap();
+        assert_eq!(span.low(), Offset::new(25));
+        assert_eq!(span.high(), Offset::new(26));
+    }
+
+    #[test]
+    fn parse_unknown_location() {
+        let mut sm = SourceMap::new();
+        sm.add_string("fn foo() {}\n", "/src/main.br".into())
+            .unwrap();
+
+        let remark = Remark::parse(&sm, "<unknown>:0:0: loop not vectorized");
+        assert_eq!(remark.span(), None);
+        assert_eq!(remark.message(), "loop not vectorized");
+
+        let remark = Remark::parse(&sm, "other.br:1:1: foo not inlined");
+        assert_eq!(remark.span(), None);
+        assert_eq!(remark.message(), "foo not inlined");
+    }
+
+    #[test]
+    fn parse_without_location() {
+        let sm = SourceMap::new();
+        let remark = Remark::parse(&sm, "12 stack bytes in function");
+        assert_eq!(remark.span(), None);
+        assert_eq!(remark.message(), "12 stack bytes in function");
+    }
+}
@@ -0,0 +1,920 @@
+//! Support functions that the Bramble runtime depends upon but which the
+//! compiler, rather than the user, is responsible for providing.
+
+use std::convert::TryFrom;
+
+use inkwell::{
+    builder::Builder,
+    context::Context,
+    module::{Linkage, Module},
+    types::{BasicType, BasicTypeEnum, FunctionType, StructType},
+    values::{CallableValue, FunctionValue, GlobalValue, IntValue, PointerValue},
+    AddressSpace, IntPredicate,
+};
+
+use super::options::{CodegenOptions, Sanitizer};
+
+/// Symbol that Bramble code calls to allocate memory on the heap.
+pub const ALLOC_HOOK: &str = "bramble_alloc";
+
+/// Symbol that Bramble code calls to release memory allocated by [`ALLOC_HOOK`].
+pub const FREE_HOOK: &str = "bramble_free";
+
+/// Function which traps if its argument, the divisor of an integer division, is zero.
+const DIV_ZERO_CHECK: &str = "__bramble_check_div_zero";
+
+/// Function which traps if its first argument, an index, is not less than its second
+/// argument, the length of the array or slice being indexed.
+const BOUNDS_CHECK: &str = "__bramble_check_bounds";
+
+/// Function which traps unless its first two arguments, the start and end of a range,
+/// are in order and within its third argument, the length of the sequence being sliced.
+const RANGE_CHECK: &str = "__bramble_check_range";
+
+/// Function which concatenates two strings into a new `String`.
+const STRING_CONCAT: &str = "__bramble_string_concat";
+
+/// The global array which holds the coverage counters of a module.
+const COVERAGE_COUNTERS: &str = "__bramble_coverage_counters";
+
+/// Function which appends the coverage counters of a module to its counters file.
+const COVERAGE_DUMP: &str = "__bramble_coverage_dump";
+
+/// Function, run before `main`, which arranges for the coverage counters to be
+/// written when the program exits.
+const COVERAGE_INIT: &str = "__bramble_coverage_init";
+
+/// Function which reports a runtime error, described by the signal which it is given,
+/// and then ends the program.
+const SIGNAL_HANDLER: &str = "__bramble_signal_handler";
+
+/// Function which installs [`SIGNAL_HANDLER`] as the handler of every signal in
+/// [`HANDLED_SIGNALS`].
+const SIGNAL_INSTALL: &str = "__bramble_install_signal_handlers";
+
+/// The signals which are raised by runtime errors in Bramble code, each with the message
+/// which describes the error.  The numbers are the same on Linux and macOS.
+const HANDLED_SIGNALS: &[(u64, &str)] = &[
+    (
+        4, // SIGILL
+        "Runtime error: a runtime check failed (e.g. an index was out of bounds)\n",
+    ),
+    (
+        5, // SIGTRAP
+        "Runtime error: a runtime check failed (e.g. an index was out of bounds)\n",
+    ),
+    (
+        8, // SIGFPE
+        "Runtime error: an arithmetic error occurred (e.g. an integer division by zero)\n",
+    ),
+    (
+        11, // SIGSEGV
+        "Runtime error: segmentation fault, memory which the program does not own was accessed\n",
+    ),
+];
+
+/// The most stack frames which are written by [`SIGNAL_HANDLER`].
+const MAX_TRACE_FRAMES: u32 = 64;
+
+/// The file descriptor of standard error.
+const STDERR: u64 = 2;
+
+/// The C function used to find the length of a string literal.
+const STRLEN: &str = "strlen";
+
+/// The C functions which the default allocator hooks forward to.
+const DEFAULT_ALLOC: &str = "malloc";
+const DEFAULT_FREE: &str = "free";
+
+/// All heap allocation in Bramble goes through the `bramble_alloc` and
+/// `bramble_free` symbols rather than directly through `malloc` and `free`.
+/// This allows an embedder, or a program linked with its own runtime, to
+/// replace the allocator by defining those two symbols.
+///
+/// If the module declares either hook but nothing in the module defines it,
+/// then a default definition, which forwards to `malloc`/`free`, is added
+/// with weak linkage. At link time, any strong definition of the hook will
+/// take precedence over the default.
+pub(super) fn define_allocator_hooks<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+) {
+    for (hook, default) in &[(ALLOC_HOOK, DEFAULT_ALLOC), (FREE_HOOK, DEFAULT_FREE)] {
+        if let Some(hook_fn) = module.get_function(hook) {
+            if hook_fn.count_basic_blocks() == 0 {
+                define_forwarding_hook(context, module, builder, hook_fn, default)
+            }
+        }
+    }
+}
+
+/// Adds a body to `hook_fn` which passes all of its arguments to the function
+/// `target` and returns whatever `target` returns.
+fn define_forwarding_hook<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    hook_fn: FunctionValue<'ctx>,
+    target: &str,
+) {
+    let entry = context.append_basic_block(hook_fn, "entry");
+    builder.position_at_end(entry);
+
+    let target_fn = get_callable(module, builder, target, hook_fn.get_type());
+
+    let args: Vec<_> = hook_fn.get_param_iter().collect();
+    let result = builder
+        .build_call(target_fn, &args, "")
+        .try_as_basic_value()
+        .left();
+
+    match result {
+        Some(v) => builder.build_return(Some(&v)),
+        None => builder.build_return(None),
+    };
+
+    hook_fn.set_linkage(Linkage::WeakAny);
+}
+
+/// Returns a value which calls the function `name` with the signature `fn_ty`,
+/// declaring the function if the module does not already have it.
+///
+/// The user may have already declared the function with a different signature (e.g.
+/// `malloc` returning a pointer to a specific structure), in which case the existing
+/// declaration is cast to `fn_ty`.
+fn get_callable<'ctx>(
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    name: &str,
+    fn_ty: FunctionType<'ctx>,
+) -> CallableValue<'ctx> {
+    match module.get_function(name) {
+        Some(f) if f.get_type() == fn_ty => f.into(),
+        Some(f) => {
+            let ptr = builder
+                .build_bitcast(
+                    f.as_global_value().as_pointer_value(),
+                    fn_ty.ptr_type(AddressSpace::Generic),
+                    "",
+                )
+                .into_pointer_value();
+            CallableValue::try_from(ptr).expect("Expected a function pointer")
+        }
+        None => module.add_function(name, fn_ty, None).into(),
+    }
+}
+
+/// Returns the layout of the memory which a `String` points to: the length of the
+/// string followed by its characters and a terminating null byte, so that the
+/// characters can also be given to C functions which expect a null terminated string.
+pub(super) fn string_type<'ctx>(context: &'ctx Context) -> StructType<'ctx> {
+    context.struct_type(
+        &[
+            context.i64_type().into(),
+            context.i8_type().array_type(0).into(),
+        ],
+        false,
+    )
+}
+
+/// Returns a pointer to the characters of a string literal and its length.
+pub(super) fn build_string_literal_parts<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    s: PointerValue<'ctx>,
+) -> (PointerValue<'ctx>, IntValue<'ctx>) {
+    let i8_ptr_ty = context.i8_type().ptr_type(AddressSpace::Generic);
+    let data = builder.build_bitcast(s, i8_ptr_ty, "").into_pointer_value();
+
+    let strlen_ty = context.i64_type().fn_type(&[i8_ptr_ty.into()], false);
+    let strlen = get_callable(module, builder, STRLEN, strlen_ty);
+    let len = builder
+        .build_call(strlen, &[data.into()], "")
+        .try_as_basic_value()
+        .left()
+        .expect("strlen must return a value")
+        .into_int_value();
+
+    (data, len)
+}
+
+/// Returns a pointer to the characters of a `String` and its length.
+pub(super) fn build_string_parts<'ctx>(
+    context: &'ctx Context,
+    builder: &Builder<'ctx>,
+    s: PointerValue<'ctx>,
+) -> (PointerValue<'ctx>, IntValue<'ctx>) {
+    let len_ptr = builder.build_struct_gep(s, 0, "").unwrap();
+    let len = builder.build_load(len_ptr, "").into_int_value();
+
+    let chars_ptr = builder.build_struct_gep(s, 1, "").unwrap();
+    let data = builder
+        .build_bitcast(
+            chars_ptr,
+            context.i8_type().ptr_type(AddressSpace::Generic),
+            "",
+        )
+        .into_pointer_value();
+
+    (data, len)
+}
+
+/// Concatenates the strings `l` and `r`, each given as a pointer to its characters
+/// and its length, into a new `String`.  The memory for the new `String` is
+/// allocated with [`ALLOC_HOOK`].
+pub(super) fn build_string_concat<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    l: (PointerValue<'ctx>, IntValue<'ctx>),
+    r: (PointerValue<'ctx>, IntValue<'ctx>),
+) -> PointerValue<'ctx> {
+    let concat_fn = module.get_function(STRING_CONCAT).unwrap_or_else(|| {
+        let current_bb = builder.get_insert_block();
+        let concat_fn = define_string_concat(context, module, builder);
+        if let Some(bb) = current_bb {
+            builder.position_at_end(bb);
+        }
+        concat_fn
+    });
+
+    builder
+        .build_call(
+            concat_fn,
+            &[l.0.into(), l.1.into(), r.0.into(), r.1.into()],
+            "",
+        )
+        .try_as_basic_value()
+        .left()
+        .expect("String concatenation must return a value")
+        .into_pointer_value()
+}
+
+/// Defines a function which takes two strings, as pointers to their characters and
+/// their lengths, and returns a new `String` which holds both strings.
+fn define_string_concat<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+) -> FunctionValue<'ctx> {
+    let i8_ty = context.i8_type();
+    let i64_ty = context.i64_type();
+    let i8_ptr_ty = i8_ty.ptr_type(AddressSpace::Generic);
+    let string_ptr_ty = string_type(context).ptr_type(AddressSpace::Generic);
+
+    let fn_ty = string_ptr_ty.fn_type(
+        &[
+            i8_ptr_ty.into(),
+            i64_ty.into(),
+            i8_ptr_ty.into(),
+            i64_ty.into(),
+        ],
+        false,
+    );
+    let concat_fn = module.add_function(STRING_CONCAT, fn_ty, Some(Linkage::Private));
+
+    let entry = context.append_basic_block(concat_fn, "entry");
+    builder.position_at_end(entry);
+    let l = concat_fn.get_nth_param(0).unwrap().into_pointer_value();
+    let l_len = concat_fn.get_nth_param(1).unwrap().into_int_value();
+    let r = concat_fn.get_nth_param(2).unwrap().into_pointer_value();
+    let r_len = concat_fn.get_nth_param(3).unwrap().into_int_value();
+
+    // Allocate room for the length, the characters, and the null terminator
+    let len = builder.build_int_add(l_len, r_len, "len");
+    let header_sz = i64_ty.size_of();
+    let sz = builder.build_int_add(len, header_sz, "");
+    let sz = builder.build_int_add(sz, i64_ty.const_int(1, false), "sz");
+
+    let alloc_ty = i8_ptr_ty.fn_type(&[i64_ty.into()], false);
+    let alloc_fn = get_callable(module, builder, ALLOC_HOOK, alloc_ty);
+    let raw = builder
+        .build_call(alloc_fn, &[sz.into()], "")
+        .try_as_basic_value()
+        .left()
+        .expect("The allocator must return a pointer")
+        .into_pointer_value();
+    let s = builder
+        .build_bitcast(raw, string_ptr_ty, "s")
+        .into_pointer_value();
+
+    let len_ptr = builder.build_struct_gep(s, 0, "").unwrap();
+    builder.build_store(len_ptr, len);
+    let chars_ptr = builder.build_struct_gep(s, 1, "").unwrap();
+    let data = builder
+        .build_bitcast(chars_ptr, i8_ptr_ty, "")
+        .into_pointer_value();
+
+    builder.build_memcpy(data, 1, l, 1, l_len).unwrap();
+    let r_dest = unsafe { builder.build_gep(data, &[l_len], "") };
+    builder.build_memcpy(r_dest, 1, r, 1, r_len).unwrap();
+    let end = unsafe { builder.build_gep(data, &[len], "") };
+    builder.build_store(end, i8_ty.const_zero());
+
+    builder.build_return(Some(&s));
+
+    concat_fn
+}
+
+/// Returns the layout of a slice of elements of type `el_ty`: a pointer to the first
+/// element followed by the number of elements.
+pub(super) fn slice_type<'ctx>(
+    context: &'ctx Context,
+    el_ty: BasicTypeEnum<'ctx>,
+) -> StructType<'ctx> {
+    context.struct_type(
+        &[
+            el_ty.ptr_type(AddressSpace::Generic).into(),
+            context.i64_type().into(),
+        ],
+        false,
+    )
+}
+
+/// Returns a pointer to the first element of the slice `s` and its length.
+pub(super) fn build_slice_parts<'ctx>(
+    builder: &Builder<'ctx>,
+    s: PointerValue<'ctx>,
+) -> (PointerValue<'ctx>, IntValue<'ctx>) {
+    let data_ptr = builder.build_struct_gep(s, 0, "").unwrap();
+    let data = builder.build_load(data_ptr, "").into_pointer_value();
+    let len_ptr = builder.build_struct_gep(s, 1, "").unwrap();
+    let len = builder.build_load(len_ptr, "").into_int_value();
+    (data, len)
+}
+
+/// Returns the layout of a range with bounds of type `el_ty`: the start followed by the
+/// end.
+pub(super) fn range_type<'ctx>(
+    context: &'ctx Context,
+    el_ty: BasicTypeEnum<'ctx>,
+) -> StructType<'ctx> {
+    context.struct_type(&[el_ty, el_ty], false)
+}
+
+/// Returns the layout of an option of a `value_ty`: a `bool` tag, which is `true` if the
+/// option holds a value, followed by the value.
+pub(super) fn option_type<'ctx>(
+    context: &'ctx Context,
+    value_ty: BasicTypeEnum<'ctx>,
+) -> StructType<'ctx> {
+    context.struct_type(&[context.bool_type().into(), value_ty], false)
+}
+
+/// Returns the layout of a result of an `ok_ty` or an `err_ty`: a `bool` tag, which is
+/// `true` if the result holds a success value, followed by the success value and then the
+/// error value.
+pub(super) fn result_type<'ctx>(
+    context: &'ctx Context,
+    ok_ty: BasicTypeEnum<'ctx>,
+    err_ty: BasicTypeEnum<'ctx>,
+) -> StructType<'ctx> {
+    context.struct_type(&[context.bool_type().into(), ok_ty, err_ty], false)
+}
+
+/// Returns the start and the end of the range `r`.
+pub(super) fn build_range_parts<'ctx>(
+    builder: &Builder<'ctx>,
+    r: PointerValue<'ctx>,
+) -> (IntValue<'ctx>, IntValue<'ctx>) {
+    let start_ptr = builder.build_struct_gep(r, 0, "").unwrap();
+    let start = builder.build_load(start_ptr, "").into_int_value();
+    let end_ptr = builder.build_struct_gep(r, 1, "").unwrap();
+    let end = builder.build_load(end_ptr, "").into_int_value();
+    (start, end)
+}
+
+/// If the undefined behavior sanitizer is enabled, then this will add a call which
+/// checks that `divisor` is not zero before an integer division is performed.
+///
+/// The check is done by calling a function, rather than by branching within the
+/// current function, so that the control flow graph of the function being built is
+/// not changed.
+pub(super) fn build_div_zero_check<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    divisor: IntValue<'ctx>,
+    options: &CodegenOptions,
+) {
+    if !options.sanitize(Sanitizer::Undefined) {
+        return;
+    }
+
+    let i64_ty = context.i64_type();
+    let check_fn = module.get_function(DIV_ZERO_CHECK).unwrap_or_else(|| {
+        let current_bb = builder.get_insert_block();
+        let check_fn = define_div_zero_check(context, module, builder);
+        if let Some(bb) = current_bb {
+            builder.position_at_end(bb);
+        }
+        check_fn
+    });
+
+    let divisor = builder.build_int_z_extend_or_bit_cast(divisor, i64_ty, "");
+    builder.build_call(check_fn, &[divisor.into()], "");
+}
+
+/// Defines a function which takes an `i64` and traps if it is zero.
+fn define_div_zero_check<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+) -> FunctionValue<'ctx> {
+    let i64_ty = context.i64_type();
+    let fn_ty = context.void_type().fn_type(&[i64_ty.into()], false);
+    let check_fn = module.add_function(DIV_ZERO_CHECK, fn_ty, Some(Linkage::Private));
+
+    let entry = context.append_basic_block(check_fn, "entry");
+    let trap = context.append_basic_block(check_fn, "trap");
+    let ok = context.append_basic_block(check_fn, "ok");
+
+    builder.position_at_end(entry);
+    let divisor = check_fn.get_nth_param(0).unwrap().into_int_value();
+    let is_zero =
+        builder.build_int_compare(IntPredicate::EQ, divisor, i64_ty.const_zero(), "is_zero");
+    builder.build_conditional_branch(is_zero, trap, ok);
+
+    builder.position_at_end(trap);
+    let trap_fn = module.get_function("llvm.trap").unwrap_or_else(|| {
+        module.add_function("llvm.trap", context.void_type().fn_type(&[], false), None)
+    });
+    builder.build_call(trap_fn, &[], "");
+    builder.build_unreachable();
+
+    builder.position_at_end(ok);
+    builder.build_return(None);
+
+    check_fn
+}
+
+/// If bounds checks are enabled, then this will add a call which checks that `index`
+/// is less than `len` before an element of an array or slice is accessed.  `index` is
+/// sign extended if `is_signed` is `true`, so that a negative index is also caught.
+///
+/// Like [`build_div_zero_check`], the check is a function call so that the control
+/// flow graph of the function being built is not changed.
+pub(super) fn build_bounds_check<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    index: IntValue<'ctx>,
+    is_signed: bool,
+    len: IntValue<'ctx>,
+    options: &CodegenOptions,
+) {
+    if !options.bounds_checks {
+        return;
+    }
+
+    let i64_ty = context.i64_type();
+    let check_fn = module.get_function(BOUNDS_CHECK).unwrap_or_else(|| {
+        let current_bb = builder.get_insert_block();
+        let check_fn = define_bounds_check(context, module, builder);
+        if let Some(bb) = current_bb {
+            builder.position_at_end(bb);
+        }
+        check_fn
+    });
+
+    let index = if is_signed {
+        builder.build_int_s_extend_or_bit_cast(index, i64_ty, "")
+    } else {
+        builder.build_int_z_extend_or_bit_cast(index, i64_ty, "")
+    };
+    builder.build_call(check_fn, &[index.into(), len.into()], "");
+}
+
+/// Defines a function which takes an index and a length, both `i64`, and traps if the
+/// index, compared as an unsigned integer, is not less than the length.
+fn define_bounds_check<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+) -> FunctionValue<'ctx> {
+    let i64_ty = context.i64_type();
+    let fn_ty = context
+        .void_type()
+        .fn_type(&[i64_ty.into(), i64_ty.into()], false);
+    let check_fn = module.add_function(BOUNDS_CHECK, fn_ty, Some(Linkage::Private));
+
+    let entry = context.append_basic_block(check_fn, "entry");
+    let trap = context.append_basic_block(check_fn, "trap");
+    let ok = context.append_basic_block(check_fn, "ok");
+
+    builder.position_at_end(entry);
+    let index = check_fn.get_nth_param(0).unwrap().into_int_value();
+    let len = check_fn.get_nth_param(1).unwrap().into_int_value();
+    let out_of_bounds = builder.build_int_compare(IntPredicate::UGE, index, len, "out_of_bounds");
+    builder.build_conditional_branch(out_of_bounds, trap, ok);
+
+    builder.position_at_end(trap);
+    let trap_fn = module.get_function("llvm.trap").unwrap_or_else(|| {
+        module.add_function("llvm.trap", context.void_type().fn_type(&[], false), None)
+    });
+    builder.build_call(trap_fn, &[], "");
+    builder.build_unreachable();
+
+    builder.position_at_end(ok);
+    builder.build_return(None);
+
+    check_fn
+}
+
+/// If bounds checks are enabled, then this will add a call which checks that the range
+/// from `start` to `end` is within a sequence of length `len`, that is that
+/// `start <= end <= len`, before the part of an array, slice, or range which it covers is
+/// taken.  All three values must be `i64`s.
+///
+/// Like [`build_div_zero_check`], the check is a function call so that the control
+/// flow graph of the function being built is not changed.
+pub(super) fn build_range_check<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    start: IntValue<'ctx>,
+    end: IntValue<'ctx>,
+    len: IntValue<'ctx>,
+    options: &CodegenOptions,
+) {
+    if !options.bounds_checks {
+        return;
+    }
+
+    let check_fn = module.get_function(RANGE_CHECK).unwrap_or_else(|| {
+        let current_bb = builder.get_insert_block();
+        let check_fn = define_range_check(context, module, builder);
+        if let Some(bb) = current_bb {
+            builder.position_at_end(bb);
+        }
+        check_fn
+    });
+
+    builder.build_call(check_fn, &[start.into(), end.into(), len.into()], "");
+}
+
+/// Defines a function which takes the start and end of a range and a length, all `i64`,
+/// and traps if, compared as unsigned integers, the start is greater than the end or the
+/// end is greater than the length.
+fn define_range_check<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+) -> FunctionValue<'ctx> {
+    let i64_ty = context.i64_type();
+    let fn_ty = context
+        .void_type()
+        .fn_type(&[i64_ty.into(), i64_ty.into(), i64_ty.into()], false);
+    let check_fn = module.add_function(RANGE_CHECK, fn_ty, Some(Linkage::Private));
+
+    let entry = context.append_basic_block(check_fn, "entry");
+    let trap = context.append_basic_block(check_fn, "trap");
+    let ok = context.append_basic_block(check_fn, "ok");
+
+    builder.position_at_end(entry);
+    let start = check_fn.get_nth_param(0).unwrap().into_int_value();
+    let end = check_fn.get_nth_param(1).unwrap().into_int_value();
+    let len = check_fn.get_nth_param(2).unwrap().into_int_value();
+    let reversed = builder.build_int_compare(IntPredicate::UGT, start, end, "reversed");
+    let out_of_bounds = builder.build_int_compare(IntPredicate::UGT, end, len, "out_of_bounds");
+    let invalid = builder.build_or(reversed, out_of_bounds, "invalid");
+    builder.build_conditional_branch(invalid, trap, ok);
+
+    builder.position_at_end(trap);
+    let trap_fn = module.get_function("llvm.trap").unwrap_or_else(|| {
+        module.add_function("llvm.trap", context.void_type().fn_type(&[], false), None)
+    });
+    builder.build_call(trap_fn, &[], "");
+    builder.build_unreachable();
+
+    builder.position_at_end(ok);
+    builder.build_return(None);
+
+    check_fn
+}
+
+/// Adds a global array of `count` coverage counters to the module, all starting at
+/// zero, and returns it.
+///
+/// When the program exits the counters are appended, as native endian `u64`s, to the
+/// file at `path`.  Appending means that the counts from several runs of the program
+/// are all kept and can be summed.  Every module has its own counters, which are
+/// private to the module, so a program and the libraries it is linked with can each
+/// be instrumented.
+pub(super) fn add_coverage_counters<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    count: usize,
+    path: &str,
+) -> GlobalValue<'ctx> {
+    let counters_ty = context.i64_type().array_type(count as u32);
+    let counters = module.add_global(counters_ty, None, COVERAGE_COUNTERS);
+    counters.set_linkage(Linkage::Private);
+    counters.set_initializer(&counters_ty.const_zero());
+
+    let dump_fn = define_coverage_dump(context, module, builder, counters, count, path);
+    let init_fn = define_coverage_init(context, module, builder, dump_fn);
+    add_global_ctor(context, module, init_fn);
+
+    counters
+}
+
+/// Defines a function which appends the `count` counters in `counters` to the file
+/// at `path`.  If the file cannot be opened, then the counters are dropped, so that
+/// coverage never causes an instrumented program to fail.
+fn define_coverage_dump<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    counters: GlobalValue<'ctx>,
+    count: usize,
+    path: &str,
+) -> FunctionValue<'ctx> {
+    let i32_ty = context.i32_type();
+    let i64_ty = context.i64_type();
+    let i8_ptr_ty = context.i8_type().ptr_type(AddressSpace::Generic);
+
+    let fn_ty = context.void_type().fn_type(&[], false);
+    let dump_fn = module.add_function(COVERAGE_DUMP, fn_ty, Some(Linkage::Private));
+
+    let entry = context.append_basic_block(dump_fn, "entry");
+    let write = context.append_basic_block(dump_fn, "write");
+    let done = context.append_basic_block(dump_fn, "done");
+
+    builder.position_at_end(entry);
+    let path = builder.build_global_string_ptr(path, "coverage_path");
+    let mode = builder.build_global_string_ptr("ab", "coverage_mode");
+    let fopen_ty = i8_ptr_ty.fn_type(&[i8_ptr_ty.into(), i8_ptr_ty.into()], false);
+    let fopen_fn = get_callable(module, builder, "fopen", fopen_ty);
+    let file = builder
+        .build_call(
+            fopen_fn,
+            &[
+                path.as_pointer_value().into(),
+                mode.as_pointer_value().into(),
+            ],
+            "file",
+        )
+        .try_as_basic_value()
+        .left()
+        .expect("fopen must return a pointer")
+        .into_pointer_value();
+    let is_null = builder.build_is_null(file, "is_null");
+    builder.build_conditional_branch(is_null, done, write);
+
+    builder.position_at_end(write);
+    let data = builder.build_bitcast(counters.as_pointer_value(), i8_ptr_ty, "data");
+    let fwrite_ty = i64_ty.fn_type(
+        &[
+            i8_ptr_ty.into(),
+            i64_ty.into(),
+            i64_ty.into(),
+            i8_ptr_ty.into(),
+        ],
+        false,
+    );
+    let fwrite_fn = get_callable(module, builder, "fwrite", fwrite_ty);
+    builder.build_call(
+        fwrite_fn,
+        &[
+            data.into(),
+            i64_ty.size_of().into(),
+            i64_ty.const_int(count as u64, false).into(),
+            file.into(),
+        ],
+        "",
+    );
+    let fclose_ty = i32_ty.fn_type(&[i8_ptr_ty.into()], false);
+    let fclose_fn = get_callable(module, builder, "fclose", fclose_ty);
+    builder.build_call(fclose_fn, &[file.into()], "");
+    builder.build_unconditional_branch(done);
+
+    builder.position_at_end(done);
+    builder.build_return(None);
+
+    dump_fn
+}
+
+/// Defines a function which registers `dump_fn` to be called when the program exits.
+fn define_coverage_init<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    dump_fn: FunctionValue<'ctx>,
+) -> FunctionValue<'ctx> {
+    let fn_ty = context.void_type().fn_type(&[], false);
+    let init_fn = module.add_function(COVERAGE_INIT, fn_ty, Some(Linkage::Private));
+
+    let entry = context.append_basic_block(init_fn, "entry");
+    builder.position_at_end(entry);
+    let atexit_ty = context
+        .i32_type()
+        .fn_type(&[fn_ty.ptr_type(AddressSpace::Generic).into()], false);
+    let atexit_fn = get_callable(module, builder, "atexit", atexit_ty);
+    builder.build_call(
+        atexit_fn,
+        &[dump_fn.as_global_value().as_pointer_value().into()],
+        "",
+    );
+    builder.build_return(None);
+
+    init_fn
+}
+
+/// Adds `init_fn` to the functions which are run before `main`.
+fn add_global_ctor<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    init_fn: FunctionValue<'ctx>,
+) {
+    let i32_ty = context.i32_type();
+    let i8_ptr_ty = context.i8_type().ptr_type(AddressSpace::Generic);
+    let fn_ptr = init_fn.as_global_value().as_pointer_value();
+
+    let ctor_ty = context.struct_type(
+        &[i32_ty.into(), fn_ptr.get_type().into(), i8_ptr_ty.into()],
+        false,
+    );
+    let ctor = ctor_ty.const_named_struct(&[
+        i32_ty.const_int(65535, false).into(),
+        fn_ptr.into(),
+        i8_ptr_ty.const_null().into(),
+    ]);
+
+    let ctors = module.add_global(ctor_ty.array_type(1), None, "llvm.global_ctors");
+    ctors.set_linkage(Linkage::Appending);
+    ctors.set_initializer(&ctor_ty.const_array(&[ctor]));
+}
+
+/// If signal handlers are enabled, then this will add a call which installs a handler
+/// for the signals raised by runtime errors (segmentation faults, arithmetic errors, and
+/// failed runtime checks).  The handler writes a message which describes the error and a
+/// stack trace to standard error and then ends the program with the signal, so the exit
+/// status is the same as without the handler.
+///
+/// The stack trace names the function which faulted when the program's symbols are
+/// available, e.g. when it is linked with `gcc -rdynamic`; otherwise it lists addresses.
+/// A stack overflow cannot be reported, because the handler needs stack to run on.
+pub(super) fn build_install_signal_handlers<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    options: &CodegenOptions,
+) {
+    if !options.signal_handlers {
+        return;
+    }
+
+    let install_fn = module.get_function(SIGNAL_INSTALL).unwrap_or_else(|| {
+        let current_bb = builder.get_insert_block();
+        let handler_fn = define_signal_handler(context, module, builder);
+        let install_fn = define_signal_install(context, module, builder, handler_fn);
+        if let Some(bb) = current_bb {
+            builder.position_at_end(bb);
+        }
+        install_fn
+    });
+
+    builder.build_call(install_fn, &[], "");
+}
+
+/// Defines a function which takes a signal, writes the message for the signal and a
+/// stack trace to standard error, and then raises the signal again with its default
+/// action, which ends the program.
+fn define_signal_handler<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+) -> FunctionValue<'ctx> {
+    let i32_ty = context.i32_type();
+    let i64_ty = context.i64_type();
+    let i8_ptr_ty = context.i8_type().ptr_type(AddressSpace::Generic);
+
+    let fn_ty = context.void_type().fn_type(&[i32_ty.into()], false);
+    let handler_fn = module.add_function(SIGNAL_HANDLER, fn_ty, Some(Linkage::Private));
+    let sig = handler_fn.get_nth_param(0).unwrap().into_int_value();
+
+    let entry = context.append_basic_block(handler_fn, "entry");
+    let trace = context.append_basic_block(handler_fn, "trace");
+
+    // Only functions which are safe to call from a signal handler are used, so the
+    // messages are written with `write` rather than `printf`
+    let write_ty = i64_ty.fn_type(&[i32_ty.into(), i8_ptr_ty.into(), i64_ty.into()], false);
+    let stderr = i32_ty.const_int(STDERR, false);
+    let build_write = |msg: &str, name: &str| {
+        let write_fn = get_callable(module, builder, "write", write_ty);
+        let text = builder.build_global_string_ptr(msg, name);
+        let len = i64_ty.const_int(msg.len() as u64, false);
+        builder.build_call(
+            write_fn,
+            &[stderr.into(), text.as_pointer_value().into(), len.into()],
+            "",
+        );
+    };
+
+    // Write the message which describes the error
+    let mut cases = vec![];
+    for (signal, msg) in HANDLED_SIGNALS {
+        let bb = context.append_basic_block(handler_fn, "message");
+        builder.position_at_end(bb);
+        build_write(*msg, "signal_message");
+        builder.build_unconditional_branch(trace);
+        cases.push((i32_ty.const_int(*signal, false), bb));
+    }
+    builder.position_at_end(entry);
+    builder.build_switch(sig, trace, &cases);
+
+    // Write the stack trace
+    builder.position_at_end(trace);
+    build_write("Stack trace (most recent call first):\n", "trace_header");
+    let frames_ty = i8_ptr_ty.array_type(MAX_TRACE_FRAMES);
+    let frames = builder.build_alloca(frames_ty, "frames");
+    let frames = builder
+        .build_bitcast(frames, i8_ptr_ty.ptr_type(AddressSpace::Generic), "")
+        .into_pointer_value();
+    let backtrace_ty = i32_ty.fn_type(
+        &[
+            i8_ptr_ty.ptr_type(AddressSpace::Generic).into(),
+            i32_ty.into(),
+        ],
+        false,
+    );
+    let backtrace_fn = get_callable(module, builder, "backtrace", backtrace_ty);
+    let num_frames = builder
+        .build_call(
+            backtrace_fn,
+            &[
+                frames.into(),
+                i32_ty.const_int(MAX_TRACE_FRAMES as u64, false).into(),
+            ],
+            "num_frames",
+        )
+        .try_as_basic_value()
+        .left()
+        .expect("backtrace must return a value");
+    let symbols_ty = context.void_type().fn_type(
+        &[
+            i8_ptr_ty.ptr_type(AddressSpace::Generic).into(),
+            i32_ty.into(),
+            i32_ty.into(),
+        ],
+        false,
+    );
+    let symbols_fn = get_callable(module, builder, "backtrace_symbols_fd", symbols_ty);
+    builder.build_call(
+        symbols_fn,
+        &[frames.into(), num_frames.into(), stderr.into()],
+        "",
+    );
+
+    // Restore the default action and raise the signal again, so that the program ends
+    // the same way that it would have without the handler
+    let handler_ptr_ty = fn_ty.ptr_type(AddressSpace::Generic);
+    let signal_ty = handler_ptr_ty.fn_type(&[i32_ty.into(), handler_ptr_ty.into()], false);
+    let signal_fn = get_callable(module, builder, "signal", signal_ty);
+    builder.build_call(
+        signal_fn,
+        &[sig.into(), handler_ptr_ty.const_null().into()],
+        "",
+    );
+    let raise_ty = i32_ty.fn_type(&[i32_ty.into()], false);
+    let raise_fn = get_callable(module, builder, "raise", raise_ty);
+    builder.build_call(raise_fn, &[sig.into()], "");
+    builder.build_return(None);
+
+    handler_fn
+}
+
+/// Defines a function which installs `handler_fn` as the handler of every signal in
+/// [`HANDLED_SIGNALS`].
+fn define_signal_install<'ctx>(
+    context: &'ctx Context,
+    module: &Module<'ctx>,
+    builder: &Builder<'ctx>,
+    handler_fn: FunctionValue<'ctx>,
+) -> FunctionValue<'ctx> {
+    let i32_ty = context.i32_type();
+    let fn_ty = context.void_type().fn_type(&[], false);
+    let install_fn = module.add_function(SIGNAL_INSTALL, fn_ty, Some(Linkage::Private));
+
+    let entry = context.append_basic_block(install_fn, "entry");
+    builder.position_at_end(entry);
+
+    let handler_ptr = handler_fn.as_global_value().as_pointer_value();
+    let handler_ptr_ty = handler_ptr.get_type();
+    let signal_ty = handler_ptr_ty.fn_type(&[i32_ty.into(), handler_ptr_ty.into()], false);
+    let signal_fn = get_callable(module, builder, "signal", signal_ty);
+    for (signal, _) in HANDLED_SIGNALS {
+        builder.build_call(
+            signal_fn,
+            &[i32_ty.const_int(*signal, false).into(), handler_ptr.into()],
+            "",
+        );
+    }
+    builder.build_return(None);
+
+    install_fn
+}
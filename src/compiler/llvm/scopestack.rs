@@ -149,8 +149,28 @@ impl<'ctx> RegisterLookup<'ctx> {
 
         Ok(())
     }
+
+    /// Returns the number of scopes which are open, including the function scope.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Closes every scope above the first `depth` scopes, so that identifiers are
+    /// looked up as they were when the scope at `depth` was the innermost scope.  The
+    /// closed scopes are returned so that they can be reopened with `reopen`.
+    pub fn suspend(&mut self, depth: usize) -> SuspendedScopes<'ctx> {
+        SuspendedScopes(self.stack.split_off(depth.min(self.stack.len())))
+    }
+
+    /// Reopens the scopes which were closed by `suspend`.
+    pub fn reopen(&mut self, scopes: SuspendedScopes<'ctx>) {
+        self.stack.extend(scopes.0)
+    }
 }
 
+/// Scopes which have been closed by [`RegisterLookup::suspend`] and will be reopened.
+pub struct SuspendedScopes<'ctx>(Vec<IdToValueMap<'ctx>>);
+
 #[cfg(test)]
 mod tests {
     use std::assert_ne;
@@ -263,4 +283,33 @@ mod tests {
         let err = rl.close_fn().unwrap_err();
         assert_eq!("Cannot close fn scope: there is an open local scope", err);
     }
+
+    #[test]
+    fn suspend_scopes() {
+        let context = Context::create();
+
+        let mut rl = RegisterLookup::new();
+        rl.open_fn().unwrap();
+        let outer = context.i8_type().const_int(1, true);
+        rl.insert("test", outer.into()).unwrap();
+
+        rl.open_local().unwrap();
+        let inner = context.i8_type().const_int(2, true);
+        rl.insert("test", inner.into()).unwrap();
+        rl.insert("inner", inner.into()).unwrap();
+        assert_eq!(rl.depth(), 2);
+
+        // While the local scope is suspended, only the function scope is visible
+        let suspended = rl.suspend(1);
+        assert_eq!(rl.depth(), 1);
+        assert_eq!(outer, *rl.get("test").unwrap());
+        assert_eq!(None, rl.get("inner"));
+
+        rl.reopen(suspended);
+        assert_eq!(rl.depth(), 2);
+        assert_eq!(inner, *rl.get("test").unwrap());
+
+        rl.close_local().unwrap();
+        rl.close_fn().unwrap();
+    }
 }
@@ -0,0 +1,159 @@
+//! Selects and configures the machine which LLVM will generate code for.
+
+use inkwell::{
+    module::Module,
+    passes::PassManager,
+    targets::{CodeModel, InitializationConfig, RelocMode, Target, TargetMachine, TargetTriple},
+    OptimizationLevel,
+};
+
+use super::options::CodegenOptions;
+
+/// The CPU name which selects the CPU of the machine running the compiler.
+const NATIVE_CPU: &str = "native";
+
+/// The CPU which is used when the user does not select one.
+const DEFAULT_CPU: &str = "generic";
+
+/// Describes the target machine that code is generated for.
+pub struct TargetStats {
+    pub triple: String,
+    pub cpu: String,
+    pub features: String,
+}
+
+impl std::fmt::Display for TargetStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Target: {}\nCPU: {}\nFeatures: {}",
+            self.triple,
+            self.cpu,
+            if self.features.is_empty() {
+                "(none)"
+            } else {
+                &self.features
+            }
+        )
+    }
+}
+
+/// Returns the name and description of every target which is registered with LLVM.
+pub fn registered_targets() -> Vec<(String, String)> {
+    Target::initialize_all(&InitializationConfig::default());
+
+    let mut targets = vec![];
+    let mut next = Target::get_first();
+    while let Some(target) = next {
+        targets.push((
+            target.get_name().to_string_lossy().into_owned(),
+            target.get_description().to_string_lossy().into_owned(),
+        ));
+        next = target.get_next();
+    }
+    targets
+}
+
+/// Creates the LLVM target machine for the target triple given in `options`, or for the
+/// machine running the compiler if no triple is given, using the CPU and target features
+/// given in `options`.
+pub fn create_target_machine(
+    options: &CodegenOptions,
+) -> Result<(TargetMachine, TargetTriple), String> {
+    let triple = match &options.target_triple {
+        Some(triple) => TargetTriple::create(triple),
+        None => TargetMachine::get_default_triple(),
+    };
+
+    Target::initialize_all(&InitializationConfig::default());
+    let target = Target::from_triple(&triple).map_err(|e| e.to_string())?;
+
+    let (cpu, features) = cpu_and_features(options);
+    let machine = target
+        .create_target_machine(
+            &triple,
+            &cpu,
+            &features,
+            optimization_level(options),
+            RelocMode::Default,
+            CodeModel::Default,
+        )
+        .ok_or_else(|| {
+            format!(
+                "Could not create a target machine for {} with features \"{}\"",
+                cpu, features
+            )
+        })?;
+
+    Ok((machine, triple))
+}
+
+/// Runs LLVM's optimization passes over `module`, if `options` enables optimization.
+/// This must be done after every function has been added to the module and before the
+/// module is written out.
+pub fn optimize_module(module: &Module, options: &CodegenOptions) {
+    if !options.optimize {
+        return;
+    }
+
+    let passes = PassManager::create(());
+    // Inline small functions into their callers
+    passes.add_function_inlining_pass();
+    // Move local variables out of their stack slots and into registers
+    passes.add_promote_memory_to_register_pass();
+    // Peephole optimizations
+    passes.add_instruction_combining_pass();
+    passes.add_reassociate_pass();
+    // Remove redundant loads and computations and then the code that became dead
+    passes.add_gvn_pass();
+    passes.add_dead_store_elimination_pass();
+    passes.add_cfg_simplification_pass();
+    // Put loops into the form which the vectorizers expect and then vectorize them
+    passes.add_loop_rotate_pass();
+    passes.add_loop_vectorize_pass();
+    passes.add_slp_vectorize_pass();
+    passes.run_on(module);
+}
+
+/// The level at which the code generator optimizes the machine code that it emits.
+fn optimization_level(options: &CodegenOptions) -> OptimizationLevel {
+    if options.optimize {
+        OptimizationLevel::Default
+    } else {
+        OptimizationLevel::None
+    }
+}
+
+/// Describes the target machine which will be created for the given `options`.
+pub fn target_stats(options: &CodegenOptions) -> Result<TargetStats, String> {
+    let (machine, triple) = create_target_machine(options)?;
+    Ok(TargetStats {
+        triple: triple.as_str().to_string_lossy().into_owned(),
+        cpu: machine.get_cpu().to_string(),
+        features: machine.get_feature_string().to_string_lossy().into_owned(),
+    })
+}
+
+/// Returns the width, in bits, of a pointer on the target machine selected by
+/// `options`.  This is the width of `usize` and `isize`.
+pub fn pointer_width(options: &CodegenOptions) -> Result<u32, String> {
+    let (machine, _) = create_target_machine(options)?;
+    Ok(machine.get_target_data().get_pointer_byte_size(None) * 8)
+}
+
+/// Resolves the CPU name and the comma separated feature string which will be given to
+/// LLVM.  Selecting the `native` CPU also enables every feature of the host CPU.
+fn cpu_and_features(options: &CodegenOptions) -> (String, String) {
+    let mut features = vec![];
+    let cpu = match options.target_cpu.as_deref() {
+        None => DEFAULT_CPU.into(),
+        Some(NATIVE_CPU) => {
+            features.push(TargetMachine::get_host_cpu_features().to_string());
+            TargetMachine::get_host_cpu_name().to_string()
+        }
+        Some(cpu) => cpu.into(),
+    };
+
+    features.extend(options.target_features.iter().cloned());
+    (cpu, features.join(","))
+}
@@ -0,0 +1,439 @@
+//! The catalog of the messages which are shown to the user for each diagnostic.
+//!
+//! Every message is keyed by the code of the error that it describes.  A message may
+//! contain placeholders, `{0}`, `{1}`, ..., which are replaced with the arguments of
+//! the error when it is formatted.  Keeping every message in one place keeps their
+//! wording consistent, and a translation of the compiler only has to provide another
+//! [`Catalog`] with the same codes.
+
+/// A set of messages, keyed by error code.
+pub struct Catalog {
+    messages: &'static [(&'static str, &'static str)],
+}
+
+/// The catalog which is used to format diagnostics.
+pub const DEFAULT_CATALOG: Catalog = ENGLISH;
+
+/// Formats the message for the given code with the default catalog.
+pub fn message(code: &str, args: &[String]) -> String {
+    DEFAULT_CATALOG.format(code, args)
+}
+
+impl Catalog {
+    pub const fn new(messages: &'static [(&'static str, &'static str)]) -> Catalog {
+        Catalog { messages }
+    }
+
+    /// Returns the message template for the given code.
+    pub fn get(&self, code: &str) -> Option<&'static str> {
+        self.messages
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, msg)| *msg)
+    }
+
+    /// Returns the message for the given code with every placeholder replaced by the
+    /// argument at that position.  If the catalog does not have a message for the code,
+    /// then the code and its arguments are returned, so that the diagnostic is not lost.
+    pub fn format(&self, code: &str, args: &[String]) -> String {
+        match self.get(code) {
+            Some(template) => interpolate(template, args),
+            None if args.is_empty() => code.into(),
+            None => format!("{}: {}", code, args.join(", ")),
+        }
+    }
+
+    /// Returns every code which has a message in this catalog.
+    pub fn codes(&self) -> impl Iterator<Item = &'static str> {
+        self.messages.iter().map(|(code, _)| *code)
+    }
+}
+
+/// Replaces each `{n}` in `template` with `args[n]`.  A placeholder whose position does
+/// not have an argument is left as it is.
+fn interpolate(template: &str, args: &[String]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let arg = after.find('}').and_then(|end| {
+            after[..end]
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| args.get(idx))
+                .map(|arg| (arg, end))
+        });
+
+        match arg {
+            Some((arg, end)) => {
+                text.push_str(arg);
+                rest = &after[end + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = after;
+            }
+        }
+    }
+
+    text.push_str(rest);
+    text
+}
+
+/// The messages in English.
+///
+/// Codes starting with `L` are [`LexerError`](crate::compiler::lexer::LexerError)s, codes
+/// starting with `P` are [`ParserError`](crate::compiler::parser::ParserError)s and
+/// codes starting with `S` are [`SemanticError`](crate::compiler::semantics::error::SemanticError)s.
+/// Codes with a suffix (e.g. `S0047.param`) are parts of the message for that code.
+pub const ENGLISH: Catalog = Catalog::new(&[
+    ("L0001", "Unexpected EOF"),
+    ("L0002", "Lexer locked on EOF"),
+    ("L0002.char", "Lexer locked on {0}"),
+    ("L0003", "Invalid escape sequence \\{0}"),
+    ("L0004", "Expected an escape character after \\"),
+    (
+        "L0005",
+        "A c string literal cannot contain \\0, it is NUL-terminated",
+    ),
+    ("L0006", "Invalid number"),
+    ("L0007", "Invalid suffix after float literal."),
+    (
+        "L0008",
+        "Underscores in a number literal must be between two digits",
+    ),
+    ("L0009", "Expected the name of a label after '"),
+    ("L0010", "Invalid {0} literal {1}: {2}"),
+    ("L0011", "Invalid {0} literal {1}: {2}"),
+    ("L0012", "Invalid type suffix: {0}"),
+    ("L0013", "Error reading characters from source code"),
+    ("P0001", "No source code."),
+    ("P0002", "Parser cannot advance past {0}"),
+    ("P0003", "Identifier expected after mod keyword"),
+    ("P0004", "Module already contains {0}"),
+    ("P0005", "Expected name of a structure after impl keyword"),
+    ("P0006", "impl for {0} must be in the same module as the structure"),
+    ("P0007", "Structure {0} already contains {1}"),
+    ("P0008", "Path to an item or module expected after use keyword"),
+    (
+        "P0009",
+        "An extern declaration must have at least one parameter before a VarArgs (...) parameter",
+    ),
+    ("P0010", "Expected function declaration after extern keyword"),
+    ("P0011", "Expected identifier after struct keyword"),
+    ("P0012", "Expected identifier after fn keyword"),
+    (
+        "P0013",
+        "Varargs are not allowed in Bramble functions (only in externs)",
+    ),
+    ("P0014", "Type expected after ->"),
+    (
+        "P0015",
+        "Routines must end with a return statement, but found {0}",
+    ),
+    ("P0016", "Methods must take self as their first parameter"),
+    ("P0017", "Expected parameters after function call point"),
+    ("P0018", "Expected identifier after co keyword"),
+    ("P0019", "Expected integer literal for array size"),
+    ("P0020", "Expected type in array type declaration"),
+    (
+        "P0021",
+        "Expected size to be specified in array type declaration",
+    ),
+    ("P0022", "Expected type after : in variable declaration"),
+    ("P0023", "Raw Pointer expected underlying type"),
+    ("P0024", "Expected const or mut after *"),
+    ("P0025", "Expected {0}, but found {1}"),
+    (
+        "P0026",
+        "Expected identifier declaration (`<id> : <type>`) after let",
+    ),
+    ("P0027", "Expected type specification in let binding"),
+    ("P0028", "Expected expression after :="),
+    (
+        "P0029",
+        "Expected a parenthesized list of values after := to destructure",
+    ),
+    ("P0030", "Expected {0} values to destructure but found {1}"),
+    ("P0031", "Expected parameter list after identifier"),
+    ("P0032", "Expected identifer after init"),
+    ("P0033", "{0} is not a unary operator"),
+    ("P0034", "{0} is not a binary operator"),
+    ("P0035", "Expected conditional expression after if"),
+    (
+        "P0036",
+        "Expected expression block in true arm of if expression",
+    ),
+    ("P0037", "Expected expression block after else if"),
+    ("P0038", "Expected expression block after else"),
+    ("P0039", "Expected conditional after while keyword"),
+    ("P0040", "Expected expression block for while loop body"),
+    ("P0041", "Expected loop variable after for keyword"),
+    ("P0042", "Expected type of loop variable after :"),
+    ("P0043", "Expected a range after in"),
+    ("P0044", "Expected expression block for for loop body"),
+    ("P0045", "Expected identifier after ::"),
+    ("P0046", "Expected identifier after yield"),
+    ("P0047", "Expected an expression to be assigned to field {0}"),
+    ("P0048", "Expected expression after {0}"),
+    ("P0049", "Expected term after {0}"),
+    ("P0050", "Expected identifier after {0}"),
+    ("P0051", "Expected const or mut after @"),
+    ("P0052", "Expected member name after . operator."),
+    ("P0053", "Index operator must contain valid expression"),
+    ("P0054", "Can only cast to and from primitive types."),
+    ("P0055", "Expected type in range type declaration"),
+    ("P0056", "Expected type after opt"),
+    ("P0057", "Expected type after res"),
+    ("P0058", "Expected error type after , in result type declaration"),
+    (
+        "P0059",
+        "Unexpected token {0} at module level, expected mod, use, fn, co, struct, impl, or extern",
+    ),
+    ("P0060", "Expected field declaration after pub"),
+    ("P0061", "Expected the name of an attribute after #["),
+    ("P0062", "Expected a structure or an extern after attributes"),
+    ("P0063", "Unknown attribute {0}"),
+    ("P0064", "Cannot derive {0}, expected print or eq"),
+    (
+        "P0065",
+        "Cannot derive print because field {0} has a type which cannot be printed",
+    ),
+    ("P0066", "Expected the name of the macro after macro"),
+    ("P0067", "Expected at least one rule in the macro definition"),
+    ("P0068", "Expected $name:kind in the pattern of the macro rule"),
+    ("P0069", "Unknown fragment kind {0}, expected expr or ident"),
+    ("P0070", "Macro {0} is not defined"),
+    ("P0071", "No rule of macro {0} matches the arguments"),
+    ("P0072", "Expansion of macro {0} is not an expression"),
+    ("P0073", "Expansion of macro {0} exceeds the recursion limit of {1}"),
+    ("P0074", "Expected a string literal after = in the attribute"),
+    (
+        "P0075",
+        "link_name expects the name of a symbol, e.g. #[link_name = \"SDL_Init\"]",
+    ),
+    (
+        "P0076",
+        "static_assert expects a condition and a message, e.g. static_assert(cond, \"message\")",
+    ),
+    ("P0077", "Parsing was stopped because {0}"),
+    ("S0001", "{0} is not a variable"),
+    ("S0002", "{0} is not a routine"),
+    ("S0003", "{0} is not a coroutine"),
+    ("S0004", "{0} is defined multiple times"),
+    ("S0005", "{0} is defined in this project and is also imported"),
+    ("S0006", "use {0} does not refer to an item or module"),
+    ("S0007", "Could not find item with the given path: {0} ({1})"),
+    ("S0008", "Path is not valid"),
+    ("S0009", "Could not find definition for {0} in this scope"),
+    ("S0010", "Empty path"),
+    ("S0011", "Expected length > 0 for array, but found {0}"),
+    ("S0012", "Inconsistent types in array value"),
+    (
+        "S0013",
+        "Expected an array, slice, or range on LHS of [] but found {0}",
+    ),
+    ("S0014", "Expected an integer or a range for index but found {0}"),
+    ("S0015", "{0} already declared"),
+    ("S0016", "super cannot refer to the parent of the root module"),
+    ("S0017", "Bind expected {0} but got {1}"),
+    ("S0018", "{0} is not mutable"),
+    ("S0019", "{0} is of type {1} but is assigned {2}"),
+    ("S0020", "Yield return expected {0} but got {1}"),
+    ("S0021", "yield must be at end of function"),
+    (
+        "S0022",
+        "yret can only be used within a coroutine, but {0} is a function",
+    ),
+    ("S0023", "Return expected {0} but got {1}"),
+    ("S0024", "return invalid loc"),
+    ("S0025", "Member access invalid root type"),
+    ("S0026", "{0} does not have member {1}"),
+    ("S0027", "{0} does not have method {1}"),
+    (
+        "S0029",
+        "If expression has mismatching arms: expected {0} got {1}",
+    ),
+    (
+        "S0030",
+        "Expected boolean expression in if conditional, got: {0}",
+    ),
+    (
+        "S0031",
+        "The body of a while expression must resolve to the unit type, but got: {0}",
+    ),
+    (
+        "S0032",
+        "The condition of a while expression must resolve to the bool type, but got: {0}",
+    ),
+    ("S0033", "Yield expects co<_> but got {0}"),
+    (
+        "S0034",
+        "Incorrect number of parameters passed to routine: {0}. Expected {1} but got {2}",
+    ),
+    (
+        "S0035",
+        "Function {0} expects at least {1} parameters, but got {2}",
+    ),
+    ("S0036", "Struct expected {0} parameters but found {1}"),
+    ("S0037", "member {0} not found on {1}"),
+    ("S0038", "{0}.{1} expects {2} but got {3}"),
+    ("S0039", "{0} expected i32 or i64 but found {1}"),
+    ("S0040", "{0} expected bool but found {1}"),
+    ("S0041", "{0} expected a *mut or *const but found {1}"),
+    (
+        "S0042",
+        "{0} expected a string, String, array, slice, or range but found {1}",
+    ),
+    ("S0043", "{0} expected {1} but found {2} and {3}"),
+    (
+        "S0044",
+        "Comparison operators cannot be chained: the result of {0} is bool, which {1} cannot compare with {2}",
+    ),
+    ("S0045", "{0} expected identifier"),
+    ("S0046", "{0} expected an addressable operand"),
+    (
+        "S0047",
+        "One or more parameters have mismatching types for function {0}{1}: {2}",
+    ),
+    ("S0047.param", "parameter {0} expected {1} but got {2}"),
+    ("S0047.named-param", "parameter {0} ({1}) expected {2} but got {3}"),
+    ("S0048", "my_main must be a function of type () -> i64"),
+    (
+        "S0049",
+        "my_main must take no parameters. It must be of type () -> i64",
+    ),
+    ("S0050", "Not a valid structure definition"),
+    ("S0051", "Cannot make mutable pointer to immutable variable"),
+    ("S0052", "Expected {0} but {1} is a {2}"),
+    ("S0052.function", "function"),
+    ("S0052.coroutine", "coroutine"),
+    ("S0052.extern", "extern"),
+    ("S0052.method", "method"),
+    ("S0053", "init expects a coroutine but {0} is {1}"),
+    ("S0054", "Invalid type used in identifier declaration: {0}"),
+    (
+        "S0055",
+        "@ operator expects raw pointer on left side, but got {0}",
+    ),
+    (
+        "S0056",
+        "@ operator expects integer on right side, but got {0}",
+    ),
+    ("S0057", "Invalid type cast"),
+    (
+        "S0058",
+        "A const fn can only call other const fns, but {0} is not a const fn",
+    ),
+    ("S0059", "A const fn cannot use raw pointers, but found {0}"),
+    (
+        "S0060",
+        "Internal error: the type of this {0} was not resolved",
+    ),
+    ("S0061", "{0} expected an option but found {1}"),
+    (
+        "S0062",
+        "The type of this none, ok, or err cannot be inferred, it must be used where its option or result type is expected",
+    ),
+    ("S0063", "{0} expected a result but found {1}"),
+    (
+        "S0064",
+        "? cannot propagate {0} out of a routine which returns {1}",
+    ),
+    (
+        "S0065",
+        "{1} is a private field of {0}, it can only be used within the module which defines {0}",
+    ),
+    ("S0066", "fields_of expected a structure but found {0}"),
+    (
+        "S0067",
+        "Environment variable {0} is not set and was not defined with --define",
+    ),
+    (
+        "S0068",
+        "env expects the name of an environment variable as a string literal",
+    ),
+    ("S0069", "Static assertion failed: {0}"),
+    (
+        "S0070",
+        "The condition of a static assertion must be built from literals, cfg, operators, and calls to const fns",
+    ),
+    ("S0071", "static_assert expected bool but found {0}"),
+    (
+        "S0072",
+        "The condition of this static assertion overflows or divides by zero",
+    ),
+    ("S0073", "Type checking was stopped because {0}"),
+    (
+        "S0074",
+        "A deferred expression cannot return, because it runs while the scope is exited",
+    ),
+    ("S0075", "break can only be used within a loop"),
+    ("S0076", "continue can only be used within a loop"),
+    ("S0077", "There is no enclosing loop labeled {0}"),
+    (
+        "S0078",
+        "{0} expected an integer with a fixed width (e.g. u32 or i64) but found {1}",
+    ),
+    ("S0079", "{0} expected [u8; 1], [u8; 2], [u8; 4], or [u8; 8] but found {1}"),
+    (
+        "S0080",
+        "? cannot be used in a deferred expression, because it runs while the scope is exited",
+    ),
+    (
+        "S0081",
+        "Evaluating this constant took too many steps, or too many nested const fn calls",
+    ),
+    (
+        "M0001",
+        "Values of type {0} are not supported by --mir-beta yet",
+    ),
+    (
+        "M0002",
+        "The length of a {0} is not supported by --mir-beta yet",
+    ),
+    (
+        "M0003",
+        "A coroutine which holds another coroutine in its variables is not supported by --mir-beta yet",
+    ),
+]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolate() {
+        let args = vec!["a".to_string(), "b".to_string()];
+        for (template, expected) in vec![
+            ("no placeholders", "no placeholders"),
+            ("{0} and {1}", "a and b"),
+            ("{1}{0}{1}", "bab"),
+            ("{2} is missing", "{2} is missing"),
+            ("{x} is not a position", "{x} is not a position"),
+            ("unclosed {0", "unclosed {0"),
+            ("{0}.{1}", "a.b"),
+        ] {
+            assert_eq!(interpolate(template, &args), expected, "{}", template);
+        }
+    }
+
+    #[test]
+    fn test_unknown_code() {
+        let catalog = Catalog::new(&[("A1", "known {0}")]);
+        assert_eq!(catalog.format("A1", &["x".into()]), "known x");
+        assert_eq!(catalog.format("A2", &[]), "A2");
+        assert_eq!(catalog.format("A2", &["x".into(), "y".into()]), "A2: x, y");
+    }
+
+    #[test]
+    fn test_codes_are_unique() {
+        let mut codes: Vec<_> = ENGLISH.codes().collect();
+        let len = codes.len();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), len);
+    }
+}
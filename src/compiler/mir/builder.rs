@@ -19,6 +19,21 @@ pub struct MirProcedureBuilder {
     current_bb: Option<BasicBlockId>,
     /// All variables will be added to this scope.
     current_scope: ScopeId,
+    /// The loops which enclose the current [`BasicBlock`], from the outermost to the
+    /// innermost.
+    loops: Vec<LoopTargets>,
+}
+
+/// The [`BasicBlock`]s which a `break` or `continue` jumps to in order to leave a loop.
+pub struct LoopTargets {
+    /// The label of the loop, if it has one
+    pub label: Option<StringId>,
+    /// Evaluates the condition of the loop, this is where `continue` jumps to
+    pub cond_bb: BasicBlockId,
+    /// Follows the loop, this is where `break` jumps to
+    pub exit_bb: BasicBlockId,
+    /// The scope which the loop is in
+    pub scope: ScopeId,
 }
 
 impl MirProcedureBuilder {
@@ -29,6 +44,7 @@ impl MirProcedureBuilder {
             proc: Procedure::new(path, vec![], ret_ty, Span::zero()),
             current_bb: None,
             current_scope: ScopeId::root(),
+            loops: vec![],
         }
     }
 
@@ -37,6 +53,17 @@ impl MirProcedureBuilder {
         self.proc
     }
 
+    /// Returns the [`Procedure`] as it has been built so far.
+    pub fn proc(&self) -> &Procedure {
+        &self.proc
+    }
+
+    /// Replaces locations throughout the [`Procedure`] being built.  See
+    /// [`Procedure::map_lvalues`].
+    pub fn map_lvalues<F: Fn(&LValue) -> Option<LValue>>(&mut self, f: &F) {
+        self.proc.map_lvalues(f)
+    }
+
     /// Sets the span of input source code that this [`Procedure`] will represent.
     pub fn set_span(&mut self, span: Span) {
         self.proc.set_span(span)
@@ -53,6 +80,14 @@ impl MirProcedureBuilder {
         self.current_bb = Some(bb)
     }
 
+    /// Returns `true` if the current [`BasicBlock`] already has a [`Terminator`]. For
+    /// example, because control flow left the block through a `return` statement.
+    pub fn is_terminated(&self) -> bool {
+        self.current_bb
+            .map(|bb| self.proc.get_bb(bb).get_term().is_some())
+            .unwrap_or(false)
+    }
+
     /// Create a new scope that's the child of the current scope and make it
     /// the current scope.
     pub fn start_scope(&mut self) {
@@ -68,6 +103,48 @@ impl MirProcedureBuilder {
             .unwrap_or_else(ScopeId::root);
     }
 
+    /// Returns the scope which variables are currently added to and looked up in.
+    pub fn current_scope(&self) -> ScopeId {
+        self.current_scope
+    }
+
+    /// Make `scope`, which must be the current scope or one of its ancestors, the
+    /// current scope.  This is used to evaluate an expression as if it were written in
+    /// an enclosing scope, so that its identifiers are not shadowed by inner variables.
+    pub fn set_scope(&mut self, scope: ScopeId) {
+        self.current_scope = scope;
+    }
+
+    /// Enter the body of a loop, in the current scope, which a `continue` leaves by
+    /// jumping to `cond_bb` and a `break` leaves by jumping to `exit_bb`.
+    pub fn enter_loop(
+        &mut self,
+        label: Option<StringId>,
+        cond_bb: BasicBlockId,
+        exit_bb: BasicBlockId,
+    ) {
+        self.loops.push(LoopTargets {
+            label,
+            cond_bb,
+            exit_bb,
+            scope: self.current_scope,
+        })
+    }
+
+    /// Exit the body of the innermost loop.
+    pub fn exit_loop(&mut self) {
+        self.loops.pop();
+    }
+
+    /// Returns the innermost loop with the given label, or the innermost loop if there
+    /// is no label.
+    pub fn find_loop(&self, label: Option<StringId>) -> Option<&LoopTargets> {
+        self.loops
+            .iter()
+            .rev()
+            .find(|lp| label.is_none() || lp.label == label)
+    }
+
     /// Search the procedure's set of local variables for the variable with
     /// the matching name that's in the nearest scope.
     pub fn find_var(&self, name: StringId) -> Option<VarId> {
@@ -202,6 +279,23 @@ impl MirProcedureBuilder {
         LValue::Access(Box::new(base), Accessor::Field(field_id, field_mir.ty))
     }
 
+    /// Will construct an [`LValue`] whose location is the specified `field`, of type `ty`,
+    /// in an option or result.  `field` is one of [`FieldId::TAG`], [`FieldId::VALUE`], or
+    /// [`FieldId::ERR`].
+    pub fn tagged_field(&self, base: LValue, field: FieldId, ty: TypeId) -> LValue {
+        debug!("Tagged Field: {:?}.{}", base, field);
+
+        LValue::Access(Box::new(base), Accessor::Field(field, ty))
+    }
+
+    /// Will construct an [`LValue`] whose location is the specified `field`, of type `ty`,
+    /// in the frame of a coroutine.
+    pub fn frame_field(&self, base: LValue, field: FieldId, ty: TypeId) -> LValue {
+        debug!("Frame Field: {:?}.{}", base, field);
+
+        LValue::Access(Box::new(base), Accessor::Field(field, ty))
+    }
+
     pub fn array_at(&self, array: LValue, index: Operand) -> LValue {
         debug!("Array At: {:?}[{:?}]", array, index);
 
@@ -227,6 +321,12 @@ impl MirProcedureBuilder {
         RValue::UnOp(UnOp::FNegate, right)
     }
 
+    /// Add a byte swap to the current [`BasicBlock`].
+    pub fn byte_swap(&self, right: Operand) -> RValue {
+        debug!("ByteSwap: {:?}", right);
+        RValue::UnOp(UnOp::ByteSwap, right)
+    }
+
     /// Add an addition operation to the current [`BasicBlock`].
     pub fn add(&self, left: Operand, right: Operand) -> RValue {
         debug!("Add: {:?}, {:?}", left, right);
@@ -257,6 +357,30 @@ impl MirProcedureBuilder {
         RValue::BinOp(BinOp::UIDiv, left, right)
     }
 
+    /// Add a saturating signed integer addition operation to the current [`BasicBlock`].
+    pub fn sat_add(&self, left: Operand, right: Operand) -> RValue {
+        debug!("SatAdd: {:?}, {:?}", left, right);
+        RValue::BinOp(BinOp::SISatAdd, left, right)
+    }
+
+    /// Add a saturating unsigned integer addition operation to the current [`BasicBlock`].
+    pub fn ui_sat_add(&self, left: Operand, right: Operand) -> RValue {
+        debug!("UISatAdd: {:?}, {:?}", left, right);
+        RValue::BinOp(BinOp::UISatAdd, left, right)
+    }
+
+    /// Add a saturating signed integer subtraction operation to the current [`BasicBlock`].
+    pub fn sat_sub(&self, left: Operand, right: Operand) -> RValue {
+        debug!("SatSub: {:?}, {:?}", left, right);
+        RValue::BinOp(BinOp::SISatSub, left, right)
+    }
+
+    /// Add a saturating unsigned integer subtraction operation to the current [`BasicBlock`].
+    pub fn ui_sat_sub(&self, left: Operand, right: Operand) -> RValue {
+        debug!("UISatSub: {:?}, {:?}", left, right);
+        RValue::BinOp(BinOp::UISatSub, left, right)
+    }
+
     /// Add an addition operation to the current [`BasicBlock`].
     pub fn fadd(&self, left: Operand, right: Operand) -> RValue {
         debug!("FAdd: {:?}, {:?}", left, right);
@@ -293,6 +417,18 @@ impl MirProcedureBuilder {
         RValue::BinOp(BinOp::Or, left, right)
     }
 
+    /// Add a left shift operation to the current [`BasicBlock`].
+    pub fn shl(&self, left: Operand, right: Operand) -> RValue {
+        debug!("Shl: {:?}, {:?}", left, right);
+        RValue::BinOp(BinOp::Shl, left, right)
+    }
+
+    /// Add a logical right shift operation to the current [`BasicBlock`].
+    pub fn lshr(&self, left: Operand, right: Operand) -> RValue {
+        debug!("LShr: {:?}, {:?}", left, right);
+        RValue::BinOp(BinOp::LShr, left, right)
+    }
+
     /// Add an equality test operation to the current [`BasicBlock`].
     pub fn eq(&self, left: Operand, right: Operand) -> RValue {
         debug!("Eq: {:?}, {:?}", left, right);
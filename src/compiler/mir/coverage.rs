@@ -0,0 +1,98 @@
+//! Assigns a coverage counter to every [`BasicBlock`] of a MIR project.  When a
+//! program is compiled with `--coverage`, the code generator increments a block's
+//! counter every time the block is entered and the counters are written to a file
+//! when the program exits.  The [`CoverageMap`] is what relates each counter back to
+//! the source code that its block was generated from.
+
+use std::collections::HashMap;
+
+use crate::compiler::{ast::Path, Span};
+
+use super::{
+    ir::{BasicBlock, BasicBlockId},
+    DefId, MirProject,
+};
+
+/// The coverage counter of a single [`BasicBlock`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Counter {
+    /// The function which contains the basic block
+    path: Path,
+
+    /// The source code which the basic block was generated from
+    span: Span,
+}
+
+impl Counter {
+    /// The canonical path of the function which this counter is in.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The span of source code which this counter covers.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Maps every [`BasicBlock`] of every function defined in a [`MirProject`] to the
+/// index of its coverage counter.
+#[derive(Debug, Default)]
+pub struct CoverageMap {
+    counters: Vec<Counter>,
+    index: HashMap<(DefId, BasicBlockId), usize>,
+}
+
+impl CoverageMap {
+    /// Assigns a counter to every basic block of the given project.  Counters are
+    /// numbered in the order that the functions and their blocks are defined, so the
+    /// same MIR will always be given the same counters.
+    pub fn new(mir: &MirProject) -> CoverageMap {
+        let mut map = CoverageMap::default();
+        for (def, func) in mir.function_iter().filter(|(_, f)| !f.is_extern()) {
+            for (bb_id, bb) in func.bb_iter() {
+                map.index.insert((def, bb_id), map.counters.len());
+                map.counters.push(Counter {
+                    path: func.path().clone(),
+                    span: Self::bb_span(bb).unwrap_or_else(|| func.span()),
+                });
+            }
+        }
+        map
+    }
+
+    /// Returns the index of the counter for the given basic block.
+    pub fn counter(&self, func: DefId, bb: BasicBlockId) -> Option<usize> {
+        self.index.get(&(func, bb)).copied()
+    }
+
+    /// Returns an [`Iterator`] over every counter, in the order of their indices.
+    pub fn iter(&self) -> impl Iterator<Item = &Counter> {
+        self.counters.iter()
+    }
+
+    /// The number of counters.
+    pub fn len(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+
+    /// The span of a basic block covers all of its statements.  A block without
+    /// statements is covered by the span of its terminator.  Spans of code which the
+    /// compiler generated, which are zero, are ignored.
+    fn bb_span(bb: &BasicBlock) -> Option<Span> {
+        let stm_span = bb
+            .stm_iter()
+            .map(|s| s.span())
+            .filter(|s| *s != Span::zero())
+            .reduce(Span::cover);
+        stm_span.or_else(|| {
+            bb.get_term()
+                .map(|t| t.span())
+                .filter(|s| *s != Span::zero())
+        })
+    }
+}
@@ -1,6 +1,7 @@
 //! The IR abstractions used to represent any given Bramble program
 //! as a CFG.
 
+use serde::{Deserialize, Serialize};
 use std::{fmt::Display, slice::Iter};
 
 use crate::{
@@ -18,7 +19,7 @@ pub const ENTRY_BB: BasicBlockId = BasicBlockId(0);
 
 /// Procedure
 /// This type represents a single function from the input source code.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Procedure {
     /// The canonical path of the procedure
     path: Path,
@@ -32,6 +33,8 @@ pub struct Procedure {
     has_varargs: bool,
     /// This function declares an external function and should only be added as a declaration
     is_extern: bool,
+    /// The symbol which an extern is linked to, if it is not the label of its path
+    link_name: Option<StringId>,
     /// The set of all user declared variables from within this function
     vars: Vec<VarDecl>,
     /// The set of all temporary variables created by the MIR compiler
@@ -57,6 +60,7 @@ impl Procedure {
             ret_ty,
             args: vec![],
             is_extern: false,
+            link_name: None,
             has_varargs: false,
             vars: vec![],
             temps: vec![],
@@ -74,10 +78,12 @@ impl Procedure {
         p
     }
 
-    /// Creates a new MIR procedure. When created this will not have any
-    /// basic blocks or arguments.
+    /// Creates a new MIR procedure which declares an external function.  If `link_name`
+    /// is given, then the function is linked to that symbol rather than to the label of
+    /// its path.
     pub fn new_extern(
         path: &Path,
+        link_name: Option<StringId>,
         args: Vec<ArgDecl>,
         has_varargs: bool,
         ret_ty: TypeId,
@@ -89,6 +95,7 @@ impl Procedure {
             ret_ty,
             args,
             is_extern: true,
+            link_name,
             has_varargs,
             vars: vec![],
             temps: vec![],
@@ -227,6 +234,36 @@ impl Procedure {
         TempId::new(id)
     }
 
+    /// Replaces every [`LValue`] in this procedure for which `f` returns a new location,
+    /// including the locations nested within other [`LValues`](LValue), [`Operands`](Operand),
+    /// and [`Terminators`](Terminator).  An [`LValue`] which is replaced is not searched
+    /// for further replacements.
+    pub fn map_lvalues<F: Fn(&LValue) -> Option<LValue>>(&mut self, f: &F) {
+        for bb in &mut self.blocks {
+            for stm in &mut bb.statements {
+                let StatementKind::Assign(lv, rv) = &mut stm.kind;
+                lv.map(f);
+                rv.map(f);
+            }
+
+            if let Some(term) = &mut bb.terminator {
+                match &mut term.kind {
+                    TerminatorKind::Return | TerminatorKind::GoTo { .. } => (),
+                    TerminatorKind::CondGoTo { cond, .. } => cond.map(f),
+                    TerminatorKind::CallFn {
+                        func,
+                        args,
+                        reentry,
+                    } => {
+                        func.map(f);
+                        args.iter_mut().for_each(|a| a.map(f));
+                        reentry.0.map(f);
+                    }
+                }
+            }
+        }
+    }
+
     /// Returns true if the definition for this function is external
     pub fn is_extern(&self) -> bool {
         self.is_extern
@@ -237,6 +274,12 @@ impl Procedure {
         self.has_varargs
     }
 
+    /// Returns the symbol which this function is linked to, if it is an extern whose
+    /// symbol is not the label of its path
+    pub fn link_name(&self) -> Option<StringId> {
+        self.link_name
+    }
+
     /// Gets the return [type](Type) of this function.
     pub fn ret_ty(&self) -> TypeId {
         self.ret_ty
@@ -247,6 +290,11 @@ impl Procedure {
         &self.path
     }
 
+    /// Returns the span of source code which defines this procedure
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
     /// Returns the number of [`BasicBlocks`](BasicBlock) in the procedure
     pub fn len(&self) -> usize {
         self.blocks.len()
@@ -319,7 +367,7 @@ impl Display for Procedure {
 }
 
 /// Identifier for a specific basic block in a procedure
-#[derive(Debug, Hash, Eq, PartialEq, PartialOrd, Copy, Clone)]
+#[derive(Debug, Hash, Eq, PartialEq, PartialOrd, Copy, Clone, Serialize, Deserialize)]
 pub struct BasicBlockId(usize);
 
 impl BasicBlockId {
@@ -345,7 +393,7 @@ impl Display for BasicBlockId {
 }
 
 /// Identifier for a user declared variable
-#[derive(Debug, Hash, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Hash, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub struct VarId(usize);
 
 impl VarId {
@@ -365,7 +413,7 @@ impl Display for VarId {
 }
 
 /// Identifier for a temporary variable.
-#[derive(Debug, Hash, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Hash, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub struct TempId(usize);
 
 impl TempId {
@@ -385,7 +433,7 @@ impl Display for TempId {
 }
 
 /// Identifier for each scope that exists within the function
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub struct ScopeId(usize);
 
 impl ScopeId {
@@ -406,7 +454,7 @@ impl Display for ScopeId {
 
 /// An argument for a function.  These are always immutable and are always
 /// in the root scope: therefore, there is no scope or mutable property.
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub struct ArgDecl {
     /// Name of this variable
     name: StringId,
@@ -459,7 +507,7 @@ impl ArgId {
 }
 
 /// A variable declared by the user.
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub struct VarDecl {
     /// Name of this variable
     name: StringId,
@@ -503,7 +551,7 @@ impl VarDecl {
 
 /// A temporary variable created by the MIR compiler to store
 /// results.
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub struct TempDecl {
     /// The type of this variable
     ty: TypeId,
@@ -519,11 +567,15 @@ impl TempDecl {
     pub fn ty(&self) -> TypeId {
         self.ty
     }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
 }
 
 /// Basic Block
 /// A single basic block from a CFG
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BasicBlock {
     statements: Vec<Statement>,
 
@@ -608,7 +660,7 @@ struct StatementId(usize);
 
 /// Statement
 /// A single statement, from which basic blocks are constructed
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Statement {
     kind: StatementKind,
 
@@ -638,7 +690,7 @@ impl Display for Statement {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum StatementKind {
     /// This statement assigns the result of an [`RValue`] operation
     /// to the memory location represented by the [`LValue`].
@@ -655,7 +707,7 @@ impl Display for StatementKind {
 
 /// LValue
 /// A physical location in memory where a value can be stored
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum LValue {
     /// A static location in the program's memory space
     Static(DefId),
@@ -674,6 +726,24 @@ pub enum LValue {
     ReturnPointer,
 }
 
+impl LValue {
+    /// Replaces this location, or the locations nested within it, with the result of `f`.
+    /// See [`Procedure::map_lvalues`].
+    fn map<F: Fn(&LValue) -> Option<LValue>>(&mut self, f: &F) {
+        match f(self) {
+            Some(lv) => *self = lv,
+            None => {
+                if let LValue::Access(base, acc) = self {
+                    base.map(f);
+                    if let Accessor::Index(idx) = acc {
+                        idx.map(f);
+                    }
+                }
+            }
+        }
+    }
+}
+
 impl Display for LValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let text = match self {
@@ -692,7 +762,7 @@ impl Display for LValue {
 
 /// Describes the method used to access the data of an indirect data type
 /// such as a reference, array, or structure.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Accessor {
     Index(Box<Operand>),
     Field(FieldId, TypeId),
@@ -713,7 +783,7 @@ impl Display for Accessor {
 /// RValue
 /// An operation that results in a value which can be
 /// stored in some physical location in memory
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum RValue {
     /// Provides a way of reading a specific variable or using a constant in an assignment.
     Use(Operand),
@@ -731,6 +801,21 @@ pub enum RValue {
     AddressOf(LValue),
 }
 
+impl RValue {
+    /// Replaces the locations which this operation uses with the result of `f`.  See
+    /// [`Procedure::map_lvalues`].
+    fn map<F: Fn(&LValue) -> Option<LValue>>(&mut self, f: &F) {
+        match self {
+            RValue::Use(o) | RValue::UnOp(_, o) | RValue::Cast(o, ..) => o.map(f),
+            RValue::BinOp(_, l, r) => {
+                l.map(f);
+                r.map(f);
+            }
+            RValue::AddressOf(lv) => lv.map(f),
+        }
+    }
+}
+
 impl Display for RValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let text = match self {
@@ -746,7 +831,7 @@ impl Display for RValue {
 
 /// Operand
 /// Value that can be used as the parameters for the RValue operations
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Operand {
     Constant(Constant),
     LValue(LValue),
@@ -761,6 +846,14 @@ impl Operand {
             Self::Constant(_) => None,
         }
     }
+
+    /// If this is an [`Operand::LValue`] then replace its location with the result of `f`.
+    /// See [`Procedure::map_lvalues`].
+    fn map<F: Fn(&LValue) -> Option<LValue>>(&mut self, f: &F) {
+        if let Self::LValue(lv) = self {
+            lv.map(f)
+        }
+    }
 }
 
 impl Display for Operand {
@@ -773,7 +866,7 @@ impl Display for Operand {
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum Constant {
     Unit,
     I8(i8),
@@ -815,7 +908,7 @@ impl Display for Constant {
 /// Terminator
 /// Marks the final statement in a basic block and indicates where the
 /// program will go to next
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Terminator {
     kind: TerminatorKind,
 
@@ -844,7 +937,7 @@ impl Display for Terminator {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum TerminatorKind {
     /// Return from this function to the calling function.
     Return,
@@ -896,7 +989,7 @@ impl Display for TerminatorKind {
 }
 
 /// Binary operators
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum BinOp {
     /// '+' add two primitive numbers together
     Add,
@@ -908,6 +1001,14 @@ pub enum BinOp {
     SIDiv,
     /// '/' divide an unsigned integer primitive by another unsigned integer primitive
     UIDiv,
+    /// add two signed integers, clamping the result to the bounds of their type
+    SISatAdd,
+    /// add two unsigned integers, clamping the result to the bounds of their type
+    UISatAdd,
+    /// subtract one signed integer from another, clamping the result to the bounds of their type
+    SISatSub,
+    /// subtract one unsigned integer from another, clamping the result to the bounds of their type
+    UISatSub,
     /// '+' add two primitive numbers together
     FAdd,
     /// '-' subtract one primitive from another
@@ -952,6 +1053,10 @@ pub enum BinOp {
     And,
     /// '|' bitwise or operation on two primitives
     Or,
+    /// shift an integer left by a number of bits
+    Shl,
+    /// shift an integer right by a number of bits, filling with zeros
+    LShr,
     /// '@' compute an offset from a given raw pointer value
     RawPointerOffset,
 }
@@ -964,6 +1069,10 @@ impl Display for BinOp {
             BinOp::Mul => "Mul",
             BinOp::SIDiv => "Div",
             BinOp::UIDiv => "UIDiv",
+            BinOp::SISatAdd => "SatAdd",
+            BinOp::UISatAdd => "UISatAdd",
+            BinOp::SISatSub => "SatSub",
+            BinOp::UISatSub => "UISatSub",
             BinOp::Eq => "Eq",
             BinOp::Ne => "Neq",
             BinOp::SILe => "Le",
@@ -976,6 +1085,8 @@ impl Display for BinOp {
             BinOp::UIGt => "UIGt",
             BinOp::And => "BitwiseAnd",
             BinOp::Or => "BitwiseOr",
+            BinOp::Shl => "Shl",
+            BinOp::LShr => "LShr",
             BinOp::RawPointerOffset => "RawPointerOffset",
             BinOp::FAdd => "FAdd",
             BinOp::FSub => "FSub",
@@ -993,7 +1104,7 @@ impl Display for BinOp {
 }
 
 /// Unary operators
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum UnOp {
     /// '-' negate a primitive value
     Negate,
@@ -1001,6 +1112,8 @@ pub enum UnOp {
     FNegate,
     /// '!' bitwise not a primitive value
     Not,
+    /// reverse the order of the bytes of an integer
+    ByteSwap,
 }
 
 impl Display for UnOp {
@@ -1009,13 +1122,14 @@ impl Display for UnOp {
             UnOp::Negate => "-",
             UnOp::FNegate => "-",
             UnOp::Not => "!",
+            UnOp::ByteSwap => "bswap",
         };
         f.write_str(txt)
     }
 }
 
 /// Stores the topology of a function's scope tree.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct ScopeTree {
     scopes: Vec<Option<ScopeId>>,
 }
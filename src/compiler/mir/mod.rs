@@ -13,6 +13,7 @@
 */
 
 mod builder;
+mod coverage;
 mod ops;
 mod project;
 mod test;
@@ -22,6 +23,7 @@ mod typetable;
 pub mod ir;
 pub mod transform;
 
+pub use coverage::CoverageMap;
 pub use ops::{
     FunctionBuilder, ProgramBuilder, ProgramTraverser, TransformerError, TransformerInternalError,
 };
@@ -30,12 +30,15 @@ use crate::{
 /// Defines the interface used by the [`ProgramTraverser`](super::ProgramTraverser)
 /// to convert a MIR program into another IR form.
 pub trait ProgramBuilder<'p, L, V, F: FunctionBuilder<L, V>> {
-    /// Will attempt to Add the given function to the set of functions in the target
-    /// IR.
+    /// Will attempt to Add the given function, which is defined at `span`, to the set
+    /// of functions in the target IR.  If `link_name` is given, then the function is
+    /// named with that symbol rather than with a label derived from `canonical_path`.
     fn add_function(
         &mut self,
         func_id: DefId,
         canonical_path: &Path,
+        link_name: Option<StringId>,
+        span: Span,
         args: &[ArgDecl],
         is_variadic: bool,
         ret_ty: TypeId,
@@ -74,6 +77,13 @@ pub trait FunctionBuilder<L, V> {
     fn create_bb(&mut self, id: BasicBlockId, bb: &BasicBlock) -> Result<(), TransformerError>;
     fn set_bb(&mut self, bb: BasicBlockId) -> Result<(), TransformerError>;
 
+    /// Marks the code which is generated from now on as coming from the source code
+    /// at `span`.
+    fn set_location(&mut self, span: Span);
+
+    /// Adds one to the coverage counter with the given index.
+    fn increment_counter(&mut self, counter: usize) -> Result<(), TransformerError>;
+
     /// Allocate space for the given variable declaration
     fn alloc_arg(&mut self, id: ArgId, decl: &ArgDecl) -> Result<(), TransformerError>;
     fn alloc_var(&mut self, id: VarId, vd: &VarDecl) -> Result<(), TransformerError>;
@@ -182,6 +192,18 @@ pub trait FunctionBuilder<L, V> {
     /// Divide two unsigned integer values
     fn ui_div(&self, a: V, b: V) -> Result<V, TransformerError>;
 
+    /// Add two signed integer values, saturating at the bounds of their type
+    fn si_sat_add(&self, a: V, b: V) -> Result<V, TransformerError>;
+
+    /// Add two unsigned integer values, saturating at the bounds of their type
+    fn ui_sat_add(&self, a: V, b: V) -> Result<V, TransformerError>;
+
+    /// Subtract two signed integer values, saturating at the bounds of their type
+    fn si_sat_sub(&self, a: V, b: V) -> Result<V, TransformerError>;
+
+    /// Subtract two unsigned integer values, saturating at the bounds of their type
+    fn ui_sat_sub(&self, a: V, b: V) -> Result<V, TransformerError>;
+
     /// Add two values together
     fn f_add(&self, a: V, b: V) -> Result<V, TransformerError>;
 
@@ -257,6 +279,15 @@ pub trait FunctionBuilder<L, V> {
     /// Bitwise or two integer values
     fn i_or(&self, a: V, b: V) -> Result<V, TransformerError>;
 
+    /// Shift an integer value left by the number of bits in another
+    fn i_shl(&self, a: V, b: V) -> Result<V, TransformerError>;
+
+    /// Shift an integer value right by the number of bits in another, filling with zeros
+    fn i_lshr(&self, a: V, b: V) -> Result<V, TransformerError>;
+
+    /// Reverse the order of the bytes in an integer value
+    fn i_bswap(&self, a: V) -> Result<V, TransformerError>;
+
     /// Address of a given Location value.
     fn cast(
         &self,
@@ -7,8 +7,12 @@ use log::debug;
 
 use crate::{
     compiler::{
-        mir::{ir::*, MirProject, MirStructDef, MirTypeDef, TransformerError, TypeId},
-        SourceMap,
+        ice::{IceContext, InternalCompilerError},
+        mir::{
+            ir::*, CoverageMap, DefId, MirProject, MirStructDef, MirTypeDef, TransformerError,
+            TypeId,
+        },
+        CompilerError, SourceMap,
     },
     StringTable,
 };
@@ -23,6 +27,13 @@ pub struct ProgramTraverser<'a> {
     mir: &'a MirProject,
     source_map: &'a SourceMap,
     string_table: &'a StringTable,
+
+    /// If `true`, then a function which causes an internal compiler error is skipped
+    /// and the remaining functions are still transformed.
+    continue_on_ice: bool,
+
+    /// If set, then every basic block increments its coverage counter when it is entered.
+    coverage: Option<&'a CoverageMap>,
 }
 
 impl<'a> ProgramTraverser<'a> {
@@ -35,15 +46,32 @@ impl<'a> ProgramTraverser<'a> {
             mir,
             source_map,
             string_table,
+            continue_on_ice: false,
+            coverage: None,
         }
     }
 
+    /// Sets whether transforming continues with the next function after a function
+    /// causes an internal compiler error.
+    pub fn set_continue_on_ice(&mut self, continue_on_ice: bool) {
+        self.continue_on_ice = continue_on_ice
+    }
+
+    /// Instruments every basic block with an increment of the counter that the given
+    /// [`CoverageMap`] assigns to it.
+    pub fn set_coverage(&mut self, coverage: &'a CoverageMap) {
+        self.coverage = Some(coverage)
+    }
+
     /// This function takes an implementation of [`ProgramTransformer`] and uses it to
     /// conver source MIR value into the target IR form.
+    ///
+    /// If transforming a function causes an internal compiler error, then the error is
+    /// returned with the location of the statement that was being transformed.
     pub fn map<'p, L, V, F: FunctionBuilder<L, V>, P: ProgramBuilder<'p, L, V, F>>(
         &self,
         xfmr: &'p mut P,
-    ) {
+    ) -> Result<(), Vec<CompilerError<InternalCompilerError>>> {
         debug!("Applying given Transformer to MIR");
 
         // Declare every structure, but do not define the structures yet.
@@ -68,11 +96,21 @@ impl<'a> ProgramTraverser<'a> {
                 f.path().to_label(self.source_map, self.string_table),
                 f.has_varargs()
             );
-            xfmr.add_function(id, f.path(), f.get_args(), f.has_varargs(), f.ret_ty())
-                .unwrap();
+            xfmr.add_function(
+                id,
+                f.path(),
+                f.link_name(),
+                f.span(),
+                f.get_args(),
+                f.has_varargs(),
+                f.ret_ty(),
+            )
+            .unwrap();
         }
 
         // Iterate over every function in MIR
+        let ice_context = IceContext::new();
+        let mut internal_errors = vec![];
         for (id, f) in self.mir.function_iter().filter(|(_, f)| !f.is_extern()) {
             debug!(
                 "Transforming: {:?}",
@@ -84,8 +122,29 @@ impl<'a> ProgramTraverser<'a> {
             let mut fn_xfm = xfmr.get_function_transformer(id).unwrap();
 
             // Create function traverser and pass it the transformer
-            let mut traverser = FunctionTraverser::new(self.mir, f, &mut fn_xfm);
-            traverser.map();
+            let result = ice_context.catch(f.path(), f.span(), || {
+                let mut traverser = FunctionTraverser::new(
+                    self.mir,
+                    f,
+                    &mut fn_xfm,
+                    ice_context.clone(),
+                    self.coverage.map(|c| (c, id)),
+                );
+                traverser.map();
+            });
+
+            if let Err(ice) = result {
+                internal_errors.push(ice);
+                if !self.continue_on_ice {
+                    break;
+                }
+            }
+        }
+
+        if internal_errors.is_empty() {
+            Ok(())
+        } else {
+            Err(internal_errors)
         }
     }
 
@@ -108,9 +167,16 @@ impl<'a> ProgramTraverser<'a> {
                     MirTypeDef::Structure { .. } => (),
                     MirTypeDef::Array { .. }
                     | MirTypeDef::Base(..)
-                    | MirTypeDef::RawPointer { .. } => self.map_type(*target, target_ty, xfmr)?,
+                    | MirTypeDef::RawPointer { .. }
+                    | MirTypeDef::Option { .. }
+                    | MirTypeDef::Result { .. } => self.map_type(*target, target_ty, xfmr)?,
                 }
             }
+            MirTypeDef::Option { ty } => self.map_type(*ty, self.mir.get_type(*ty), xfmr)?,
+            MirTypeDef::Result { ok, err } => {
+                self.map_type(*ok, self.mir.get_type(*ok), xfmr)?;
+                self.map_type(*err, self.mir.get_type(*err), xfmr)?
+            }
             MirTypeDef::Structure { def, .. } => match def {
                 MirStructDef::Declared => panic!("Attempting to convert Undefined structure"),
                 MirStructDef::Defined(fields) => {
@@ -144,17 +210,30 @@ pub struct FunctionTraverser<'a, L, V, T: FunctionBuilder<L, V>> {
     xfmr: &'a mut T,
     mir: &'a MirProject,
     function: &'a Procedure,
+    ice_context: IceContext,
+
+    /// The coverage counters and the ID of the function being traversed, if
+    /// coverage instrumentation is enabled
+    coverage: Option<(&'a CoverageMap, DefId)>,
     _l: PhantomData<L>,
     _v: PhantomData<V>,
 }
 
 impl<'a, L, V, T: FunctionBuilder<L, V>> FunctionTraverser<'a, L, V, T> {
-    pub fn new(mir: &'a MirProject, function: &'a Procedure, xfmr: &'a mut T) -> Self {
+    pub fn new(
+        mir: &'a MirProject,
+        function: &'a Procedure,
+        xfmr: &'a mut T,
+        ice_context: IceContext,
+        coverage: Option<(&'a CoverageMap, DefId)>,
+    ) -> Self {
         debug!("New Function Traverser");
         Self {
             xfmr,
             mir,
             function,
+            ice_context,
+            coverage,
             _l: PhantomData,
             _v: PhantomData,
         }
@@ -216,6 +295,13 @@ impl<'a, L, V, T: FunctionBuilder<L, V>> FunctionTraverser<'a, L, V, T> {
     pub fn basic_block(&mut self, id: BasicBlockId, bb: &BasicBlock) {
         self.xfmr.set_bb(id).expect("Could not find BasicBlock");
 
+        if let Some((coverage, func)) = self.coverage {
+            let counter = coverage
+                .counter(func, id)
+                .expect("Every BasicBlock must have a coverage counter");
+            self.xfmr.increment_counter(counter).unwrap();
+        }
+
         // Iterate over the statements in the basic block
         bb.stm_iter().for_each(|s| self.statement(s));
 
@@ -223,6 +309,8 @@ impl<'a, L, V, T: FunctionBuilder<L, V>> FunctionTraverser<'a, L, V, T> {
         let term = bb
             .get_term()
             .expect("Terminator must be defined for a basic block");
+        let _node = self.ice_context.enter(term.span());
+        self.xfmr.set_location(term.span());
         match term.kind() {
             TerminatorKind::Return => self.xfmr.term_return(),
             TerminatorKind::GoTo { target } => self.xfmr.term_goto(*target).unwrap(),
@@ -260,6 +348,8 @@ impl<'a, L, V, T: FunctionBuilder<L, V>> FunctionTraverser<'a, L, V, T> {
     /// Call the [`Transformer`] on a statement
     fn statement(&mut self, stm: &Statement) {
         let span = stm.span();
+        let _node = self.ice_context.enter(span);
+        self.xfmr.set_location(span);
 
         match stm.kind() {
             StatementKind::Assign(lv, rv) => {
@@ -283,6 +373,10 @@ impl<'a, L, V, T: FunctionBuilder<L, V>> FunctionTraverser<'a, L, V, T> {
                     BinOp::Mul => self.xfmr.i_mul(lv, rv),
                     BinOp::SIDiv => self.xfmr.si_div(lv, rv),
                     BinOp::UIDiv => self.xfmr.ui_div(lv, rv),
+                    BinOp::SISatAdd => self.xfmr.si_sat_add(lv, rv),
+                    BinOp::UISatAdd => self.xfmr.ui_sat_add(lv, rv),
+                    BinOp::SISatSub => self.xfmr.si_sat_sub(lv, rv),
+                    BinOp::UISatSub => self.xfmr.ui_sat_sub(lv, rv),
                     BinOp::Eq => self.xfmr.i_eq(lv, rv),
                     BinOp::Ne => self.xfmr.i_neq(lv, rv),
                     BinOp::SILe => self.xfmr.si_lte(lv, rv),
@@ -295,6 +389,8 @@ impl<'a, L, V, T: FunctionBuilder<L, V>> FunctionTraverser<'a, L, V, T> {
                     BinOp::UIGt => self.xfmr.ui_gt(lv, rv),
                     BinOp::And => self.xfmr.i_and(lv, rv),
                     BinOp::Or => self.xfmr.i_or(lv, rv),
+                    BinOp::Shl => self.xfmr.i_shl(lv, rv),
+                    BinOp::LShr => self.xfmr.i_lshr(lv, rv),
                     BinOp::RawPointerOffset => self.xfmr.pointer_offset(lv, rv),
                     BinOp::FAdd => self.xfmr.f_add(lv, rv),
                     BinOp::FSub => self.xfmr.f_sub(lv, rv),
@@ -315,6 +411,7 @@ impl<'a, L, V, T: FunctionBuilder<L, V>> FunctionTraverser<'a, L, V, T> {
                     UnOp::Negate => self.xfmr.i_neg(v),
                     UnOp::FNegate => self.xfmr.f_neg(v),
                     UnOp::Not => self.xfmr.i_not(v),
+                    UnOp::ByteSwap => self.xfmr.i_bswap(v),
                 }
                 .unwrap()
             }
@@ -3,6 +3,7 @@ Represents an entire Bramble program, including imported libraries,
 in MIR form.
 */
 
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
 use crate::{
@@ -16,9 +17,12 @@ use crate::{
 
 use super::{
     ir::Procedure,
-    typetable::{MirTypeDef, TypeId, TypeTable, TypeTableError},
+    typetable::{Field, MirTypeDef, TypeId, TypeTable, TypeTableError},
 };
 
+/// The width of a pointer when the target machine has not been given
+const DEFAULT_POINTER_WIDTH: u64 = 64;
+
 /// Represents everything involved in compiling the current target compilation
 /// unit (executable, library, etc.).
 ///
@@ -35,6 +39,10 @@ pub struct MirProject {
 
     /// Table of all static defined values which can be referenced by code.
     static_defs: StaticDefinitions,
+
+    /// The width, in bits, of a pointer on the target machine.  This is also the
+    /// width of `usize` and `isize`.
+    pointer_width: u64,
 }
 
 impl MirProject {
@@ -42,9 +50,16 @@ impl MirProject {
         MirProject {
             types: TypeTable::new(),
             static_defs: StaticDefinitions::new(),
+            pointer_width: DEFAULT_POINTER_WIDTH,
         }
     }
 
+    /// Sets the width, in bits, of pointers, `usize`, and `isize` on the target
+    /// machine.
+    pub fn set_pointer_width(&mut self, bits: u64) {
+        self.pointer_width = bits
+    }
+
     /// Searches the [`TypeTable`] for the [`TypeId`] of the given
     /// [`Type`].
     pub fn find_type(&self, ty: &Type) -> Option<TypeId> {
@@ -69,6 +84,7 @@ impl MirProject {
                 | super::MirBaseType::I16
                 | super::MirBaseType::I32
                 | super::MirBaseType::I64
+                | super::MirBaseType::ISize
                 | super::MirBaseType::F64 => true,
                 super::MirBaseType::Bool
                 | super::MirBaseType::StringLiteral
@@ -77,11 +93,13 @@ impl MirProject {
                 | super::MirBaseType::U8
                 | super::MirBaseType::U16
                 | super::MirBaseType::U32
-                | super::MirBaseType::U64 => false,
+                | super::MirBaseType::U64
+                | super::MirBaseType::USize => false,
             },
             MirTypeDef::Array { .. } => false,
             MirTypeDef::RawPointer { .. } => false,
             MirTypeDef::Structure { .. } => false,
+            MirTypeDef::Option { .. } | MirTypeDef::Result { .. } => false,
         }
     }
 
@@ -97,12 +115,16 @@ impl MirProject {
                 super::MirBaseType::I64 | super::MirBaseType::F64 | super::MirBaseType::U64 => {
                     Some(64)
                 }
-                super::MirBaseType::Null | super::MirBaseType::StringLiteral => Some(64),
+                super::MirBaseType::Null
+                | super::MirBaseType::StringLiteral
+                | super::MirBaseType::USize
+                | super::MirBaseType::ISize => Some(self.pointer_width),
                 super::MirBaseType::Unit => None,
             },
             MirTypeDef::Array { .. } => None,
-            MirTypeDef::RawPointer { .. } => Some(64),
+            MirTypeDef::RawPointer { .. } => Some(self.pointer_width),
             MirTypeDef::Structure { .. } => None,
+            MirTypeDef::Option { .. } | MirTypeDef::Result { .. } => None,
         }
     }
 
@@ -114,6 +136,25 @@ impl MirProject {
         self.types.add_struct_def(sd)
     }
 
+    /// Declares the frame of the coroutines which yield `yield_ty`. See
+    /// [`TypeTable::add_coroutine_frame`].
+    pub fn add_coroutine_frame(
+        &mut self,
+        yield_ty: &Type,
+        path: &Path,
+    ) -> Result<TypeId, TypeTableError> {
+        self.types.add_coroutine_frame(yield_ty, path)
+    }
+
+    /// Defines the fields of a coroutine frame. See [`TypeTable::define_coroutine_frame`].
+    pub fn define_coroutine_frame(
+        &mut self,
+        id: TypeId,
+        fields: Vec<Field>,
+    ) -> Result<(), TypeTableError> {
+        self.types.define_coroutine_frame(id, fields)
+    }
+
     /// Adds a new Imported Structure definition to the [`MirProject`].
     pub fn add_import_struct_def(
         &mut self,
@@ -167,6 +208,12 @@ impl MirProject {
         self.static_defs.function_iter()
     }
 
+    /// Returns an [`Iterator`] over mutable references to all the functions defined
+    /// within this project.
+    pub fn function_iter_mut(&mut self) -> impl Iterator<Item = (DefId, &mut Procedure)> {
+        self.static_defs.function_iter_mut()
+    }
+
     /// Returns an [`Iterator`] over all the types defined within this project.
     pub fn type_iter(&self) -> impl Iterator<Item = (TypeId, &MirTypeDef)> {
         self.types.iter()
@@ -278,6 +325,18 @@ impl StaticDefinitions {
         })
     }
 
+    /// Return an iterator over mutable references to the functions that are defined
+    /// in a MIR Program.
+    fn function_iter_mut(&mut self) -> impl Iterator<Item = (DefId, &mut Procedure)> {
+        self.defs
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(id, i)| match i {
+                StaticItem::Function(f) => Some((DefId(id as u32), f)),
+                StaticItem::StringLiteral(_) => None,
+            })
+    }
+
     fn string_literal_iter(&self) -> impl Iterator<Item = (DefId, &StringId)> {
         self.defs.iter().enumerate().filter_map(|(id, i)| match i {
             StaticItem::Function(_) => None,
@@ -288,7 +347,7 @@ impl StaticDefinitions {
 
 /// Uniquely identifies an item that exists in the static memory of a program
 /// e.g., a function or static variable.
-#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone, Default)]
+#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone, Default, Serialize, Deserialize)]
 pub struct DefId(u32);
 
 impl DefId {
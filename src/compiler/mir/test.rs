@@ -7,7 +7,7 @@ pub mod tests {
             lexer::{tokens::Token, LexerError},
             mir::{ir::*, project::*, transform},
             parser::Parser,
-            semantics::semanticnode::SemanticContext,
+            semantics::typed::TypedModule,
             CompilerDisplay, CompilerError, Lexer, SourceMap,
         },
         resolve_types, StringTable,
@@ -15,7 +15,7 @@ pub mod tests {
 
     type LResult = std::result::Result<Vec<Token>, CompilerError<LexerError>>;
 
-    fn compile(input: &str, table: &mut StringTable) -> Module<SemanticContext> {
+    fn compile(input: &str, table: &StringTable) -> TypedModule {
         let mut sm = SourceMap::new();
         sm.add_string(input, "/test".into()).unwrap();
         let src = sm.get(0).unwrap().read().unwrap();
@@ -39,7 +39,7 @@ pub mod tests {
                 panic!("{}", err.fmt(&sm, table).unwrap());
             }
         };
-        match resolve_types(&ast, main_mod, main_fn, &logger) {
+        match resolve_types(&ast, main_mod, main_fn, &table, &logger) {
             Ok(module) => module,
             Err(err) => {
                 panic!("{}", err.fmt(&sm, table).unwrap());
@@ -65,8 +65,8 @@ pub mod tests {
             return 1 + 2 + 3 + x + y;
         }
         ";
-            let mut table = StringTable::new();
-            let module = compile(text, &mut table);
+            let table = StringTable::new();
+            let module = compile(text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
             println!("{}", project);
@@ -86,8 +86,8 @@ pub mod tests {
             return 1;
         }
         ";
-            let mut table = StringTable::new();
-            let module = compile(text, &mut table);
+            let table = StringTable::new();
+            let module = compile(text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
             println!("{}", project);
@@ -105,8 +105,8 @@ pub mod tests {
             return 2;
         }
         ";
-            let mut table = StringTable::new();
-            let module = compile(text, &mut table);
+            let table = StringTable::new();
+            let module = compile(text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
             println!("{}", project);
@@ -124,8 +124,8 @@ pub mod tests {
             return;
         }
         ";
-            let mut table = StringTable::new();
-            let module = compile(text, &mut table);
+            let table = StringTable::new();
+            let module = compile(text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
             println!("{}", project);
@@ -141,8 +141,8 @@ pub mod tests {
             return;
         }
         ";
-            let mut table = StringTable::new();
-            let module = compile(text, &mut table);
+            let table = StringTable::new();
+            let module = compile(text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
             println!("{}", project);
@@ -158,8 +158,8 @@ pub mod tests {
             return 1 + 2 + 3 + x;
         }
         ";
-            let mut table = StringTable::new();
-            let module = compile(text, &mut table);
+            let table = StringTable::new();
+            let module = compile(text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
             println!("{}", project);
@@ -177,8 +177,8 @@ pub mod tests {
             return 0;
         }
         ";
-            let mut table = StringTable::new();
-            let module = compile(text, &mut table);
+            let table = StringTable::new();
+            let module = compile(text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
             println!("{}", project);
@@ -192,8 +192,8 @@ pub mod tests {
             return x[1][0];
         }
         ";
-            let mut table = StringTable::new();
-            let module = compile(text, &mut table);
+            let table = StringTable::new();
+            let module = compile(text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
             println!("{}", project);
@@ -211,8 +211,8 @@ pub mod tests {
             b: i64,
         }
         ";
-            let mut table = StringTable::new();
-            let module = compile(text, &mut table);
+            let table = StringTable::new();
+            let module = compile(text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
             println!("{}", project);
@@ -234,8 +234,8 @@ pub mod tests {
             s: S,
         }
         ";
-            let mut table = StringTable::new();
-            let module = compile(text, &mut table);
+            let table = StringTable::new();
+            let module = compile(text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
             println!("{}", project);
@@ -251,8 +251,8 @@ pub mod tests {
             return ^p;
         }
         ";
-            let mut table = StringTable::new();
-            let module = compile(text, &mut table);
+            let table = StringTable::new();
+            let module = compile(text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
             println!("{}", project);
@@ -271,8 +271,31 @@ pub mod tests {
                     }
                 }
                 ";
-            let mut table = StringTable::new();
-            let module = compile(text, &mut table);
+            let table = StringTable::new();
+            let module = compile(text, &table);
+            let mut project = MirProject::new();
+            transform::transform(&module, &[], &mut project).unwrap();
+            println!("{}", project);
+        }
+
+        #[test]
+        fn print_mir_coroutine() {
+            let text = "
+                fn test() -> i64 {
+                    let mut c: co i64 := init count(2);
+                    let a: i64 := yield c;
+                    return a + yield c;
+                }
+
+                co count(n: i64) -> i64 {
+                    let mut i: i64 := n;
+                    yret i;
+                    mut i := i + 1;
+                    return i;
+                }
+                ";
+            let table = StringTable::new();
+            let module = compile(text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
             println!("{}", project);
@@ -287,8 +310,8 @@ pub mod tests {
             return x[0];
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
 
@@ -335,8 +358,8 @@ pub mod tests {
             return x[0];
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
 
@@ -369,8 +392,8 @@ pub mod tests {
             return x[0][1];
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
 
@@ -400,24 +423,24 @@ pub mod tests {
 
     #[test]
     fn pointer_offset() {
-        let mut table = StringTable::new();
-        let hello = table.insert("hello".into());
+        let table = StringTable::new();
+        let b = build::Builder::new(&table, || ());
         for (op, ptr_mut) in [
             (UnaryOperator::AddressConst, PointerMut::Const),
             (UnaryOperator::AddressMut, PointerMut::Mut),
         ] {
             for (literal_ty, v) in &[
-                (Type::I8, Expression::I8((), 1)),
-                (Type::I16, Expression::I16((), 1)),
-                (Type::I32, Expression::I32((), 1)),
-                (Type::I64, Expression::I64((), 1)),
-                (Type::U8, Expression::U8((), 1)),
-                (Type::U16, Expression::U16((), 1)),
-                (Type::U32, Expression::U32((), 1)),
-                (Type::U64, Expression::U64((), 1)),
-                (Type::F64, Expression::F64((), 5.0)),
-                (Type::StringLiteral, Expression::StringLiteral((), hello)),
-                (Type::Bool, Expression::Boolean((), true)),
+                (Type::I8, b.i8(1)),
+                (Type::I16, b.i16(1)),
+                (Type::I32, b.i32(1)),
+                (Type::I64, b.i64(1)),
+                (Type::U8, b.u8(1)),
+                (Type::U16, b.u16(1)),
+                (Type::U32, b.u32(1)),
+                (Type::U64, b.u64(1)),
+                (Type::F64, b.f64(5.0)),
+                (Type::StringLiteral, b.string("hello")),
+                (Type::Bool, b.bool(true)),
             ] {
                 let literal = to_code(v, &table);
                 let ptr_ty = Type::RawPointer(ptr_mut, Box::new(literal_ty.clone()));
@@ -431,7 +454,7 @@ pub mod tests {
                     }}
                     ",
                 );
-                let module = compile(&text, &mut table);
+                let module = compile(&text, &table);
                 let mut project = MirProject::new();
                 transform::transform(&module, &[], &mut project).unwrap();
 
@@ -457,24 +480,24 @@ pub mod tests {
 
     #[test]
     fn address_of() {
-        let mut table = StringTable::new();
-        let hello = table.insert("hello".into());
+        let table = StringTable::new();
+        let b = build::Builder::new(&table, || ());
         for (op, ptr_mut) in [
             (UnaryOperator::AddressConst, PointerMut::Const),
             (UnaryOperator::AddressMut, PointerMut::Mut),
         ] {
             for (literal_ty, v) in &[
-                (Type::I8, Expression::I8((), 1)),
-                (Type::I16, Expression::I16((), 1)),
-                (Type::I32, Expression::I32((), 1)),
-                (Type::I64, Expression::I64((), 1)),
-                (Type::U8, Expression::U8((), 1)),
-                (Type::U16, Expression::U16((), 1)),
-                (Type::U32, Expression::U32((), 1)),
-                (Type::U64, Expression::U64((), 1)),
-                (Type::F64, Expression::F64((), 5.0)),
-                (Type::StringLiteral, Expression::StringLiteral((), hello)),
-                (Type::Bool, Expression::Boolean((), true)),
+                (Type::I8, b.i8(1)),
+                (Type::I16, b.i16(1)),
+                (Type::I32, b.i32(1)),
+                (Type::I64, b.i64(1)),
+                (Type::U8, b.u8(1)),
+                (Type::U16, b.u16(1)),
+                (Type::U32, b.u32(1)),
+                (Type::U64, b.u64(1)),
+                (Type::F64, b.f64(5.0)),
+                (Type::StringLiteral, b.string("hello")),
+                (Type::Bool, b.bool(true)),
             ] {
                 let literal = to_code(v, &table);
                 let ptr_ty = Type::RawPointer(ptr_mut, Box::new(literal_ty.clone()));
@@ -487,7 +510,7 @@ pub mod tests {
                     }}
                     ",
                 );
-                let module = compile(&text, &mut table);
+                let module = compile(&text, &table);
                 let mut project = MirProject::new();
                 transform::transform(&module, &[], &mut project).unwrap();
 
@@ -508,33 +531,29 @@ pub mod tests {
 
     #[test]
     fn literals() {
-        let mut table = StringTable::new();
-        let hello = table.insert("hello".into());
+        let table = StringTable::new();
+        let b = build::Builder::new(&table, || ());
         for (ty, v, exp) in &[
-            (Type::I8, Expression::I8((), 1), Constant::I8(1)),
-            (Type::I16, Expression::I16((), 1), Constant::I16(1)),
-            (Type::I32, Expression::I32((), 1), Constant::I32(1)),
-            (Type::I64, Expression::I64((), 1), Constant::I64(1)),
-            (Type::U8, Expression::U8((), 1), Constant::U8(1)),
-            (Type::U16, Expression::U16((), 1), Constant::U16(1)),
-            (Type::U32, Expression::U32((), 1), Constant::U32(1)),
-            (Type::U64, Expression::U64((), 1), Constant::U64(1)),
-            (Type::F64, Expression::F64((), 5.0), Constant::F64(5.0)),
+            (Type::I8, b.i8(1), Constant::I8(1)),
+            (Type::I16, b.i16(1), Constant::I16(1)),
+            (Type::I32, b.i32(1), Constant::I32(1)),
+            (Type::I64, b.i64(1), Constant::I64(1)),
+            (Type::U8, b.u8(1), Constant::U8(1)),
+            (Type::U16, b.u16(1), Constant::U16(1)),
+            (Type::U32, b.u32(1), Constant::U32(1)),
+            (Type::U64, b.u64(1), Constant::U64(1)),
+            (Type::F64, b.f64(5.0), Constant::F64(5.0)),
             (
                 Type::StringLiteral,
-                Expression::StringLiteral((), hello),
+                b.string("hello"),
                 Constant::StringLiteral(DefId::new(1)),
             ),
             (
                 Type::RawPointer(PointerMut::Const, Box::new(Type::I16)),
-                Expression::Null(()),
+                b.null(),
                 Constant::Null,
             ),
-            (
-                Type::Bool,
-                Expression::Boolean((), true),
-                Constant::Bool(true),
-            ),
+            (Type::Bool, b.bool(true), Constant::Bool(true)),
         ] {
             let text = format!(
                 "
@@ -546,7 +565,7 @@ pub mod tests {
                 ty,
                 to_code(v, &table),
             );
-            let module = compile(&text, &mut table);
+            let module = compile(&text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
 
@@ -566,7 +585,8 @@ pub mod tests {
 
     #[test]
     fn numerical_binary_ops() {
-        let mut table = StringTable::new();
+        let table = StringTable::new();
+        let b = build::Builder::new(&table, || ());
         for (op, exp_op, ty_override) in [
             (BinaryOperator::Add, BinOp::Add, None),
             (BinaryOperator::Sub, BinOp::Sub, None),
@@ -580,15 +600,15 @@ pub mod tests {
             (BinaryOperator::GrEq, BinOp::SIGe, Some(Type::Bool)),
         ] {
             for (literal_ty, v, exp) in &[
-                (Type::I8, Expression::I8((), 1), Constant::I8(1)),
-                (Type::I16, Expression::I16((), 1), Constant::I16(1)),
-                (Type::I32, Expression::I32((), 1), Constant::I32(1)),
-                (Type::I64, Expression::I64((), 1), Constant::I64(1)),
-                (Type::U8, Expression::U8((), 1), Constant::U8(1)),
-                (Type::U16, Expression::U16((), 1), Constant::U16(1)),
-                (Type::U32, Expression::U32((), 1), Constant::U32(1)),
-                (Type::U64, Expression::U64((), 1), Constant::U64(1)),
-                (Type::F64, Expression::F64((), 5.0), Constant::F64(5.0)),
+                (Type::I8, b.i8(1), Constant::I8(1)),
+                (Type::I16, b.i16(1), Constant::I16(1)),
+                (Type::I32, b.i32(1), Constant::I32(1)),
+                (Type::I64, b.i64(1), Constant::I64(1)),
+                (Type::U8, b.u8(1), Constant::U8(1)),
+                (Type::U16, b.u16(1), Constant::U16(1)),
+                (Type::U32, b.u32(1), Constant::U32(1)),
+                (Type::U64, b.u64(1), Constant::U64(1)),
+                (Type::F64, b.f64(5.0), Constant::F64(5.0)),
             ] {
                 // If the test input is an unsigned integer then swap the expected operator to the
                 // unsigned form
@@ -641,7 +661,7 @@ pub mod tests {
                     }}
                     ",
                 );
-                let module = compile(&text, &mut table);
+                let module = compile(&text, &table);
                 let mut project = MirProject::new();
                 transform::transform(&module, &[], &mut project).unwrap();
 
@@ -668,40 +688,123 @@ pub mod tests {
     }
 
     #[test]
-    fn negate() {
-        let mut table = StringTable::new();
-        // this can only be used on signed types
-        for (literal_ty, v, exp_op, exp) in &[
-            (
-                Type::I8,
-                Expression::I8((), 1),
-                UnOp::Negate,
-                Constant::I8(1),
-            ),
+    fn overflow_arithmetic_builtins() {
+        let table = StringTable::new();
+        for (builtin, ty, literal, exp, exp_op) in [
+            ("wrapping_add", "i32", "1i32", Constant::I32(1), BinOp::Add),
+            ("wrapping_sub", "i32", "1i32", Constant::I32(1), BinOp::Sub),
+            ("wrapping_mul", "u8", "1u8", Constant::U8(1), BinOp::Mul),
             (
-                Type::I16,
-                Expression::I16((), 1),
-                UnOp::Negate,
+                "saturating_add",
+                "i16",
+                "1i16",
                 Constant::I16(1),
+                BinOp::SISatAdd,
             ),
             (
-                Type::I32,
-                Expression::I32((), 1),
-                UnOp::Negate,
-                Constant::I32(1),
+                "saturating_add",
+                "u16",
+                "1u16",
+                Constant::U16(1),
+                BinOp::UISatAdd,
             ),
             (
-                Type::I64,
-                Expression::I64((), 1),
-                UnOp::Negate,
+                "saturating_sub",
+                "i64",
+                "1",
                 Constant::I64(1),
+                BinOp::SISatSub,
             ),
             (
-                Type::F64,
-                Expression::F64((), 5.0),
-                UnOp::FNegate,
-                Constant::F64(5.0),
+                "saturating_sub",
+                "u64",
+                "1u64",
+                Constant::U64(1),
+                BinOp::UISatSub,
             ),
+        ] {
+            let text = format!(
+                "
+                fn test() {{
+                    let x: {ty} := {builtin}({literal}, {literal});
+                    return;
+                }}
+                ",
+            );
+            let module = compile(&text, &table);
+            let mut project = MirProject::new();
+            transform::transform(&module, &[], &mut project).unwrap();
+
+            let path: Path = to_path(&["main", "test"], &table);
+            let def_id = project.find_def(&path).unwrap();
+            let mir = project.get_def_fn(def_id).unwrap();
+
+            let bb = mir.get_bb(BasicBlockId::new(0));
+            let stm = bb.get_stm(0);
+            match stm.kind() {
+                StatementKind::Assign(_, r) => {
+                    assert_eq!(
+                        *r,
+                        RValue::BinOp(
+                            exp_op,
+                            Operand::Constant(exp.clone()),
+                            Operand::Constant(exp.clone())
+                        )
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn byte_conversion() {
+        let text = "
+        fn test() {
+            let x: u16 := 1u16;
+            let b: [u8; 2] := to_be_bytes(x);
+            return;
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "test"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+
+        // The bytes are swapped and then each byte is shifted into the lowest position
+        let bb = mir.get_bb(BasicBlockId::new(0));
+        match bb.get_stm(1).kind() {
+            StatementKind::Assign(_, r) => assert_eq!(
+                *r,
+                RValue::UnOp(UnOp::ByteSwap, Operand::LValue(LValue::Var(VarId::new(0))))
+            ),
+        }
+        for (idx, shift) in [(2, 0), (4, 8)] {
+            match bb.get_stm(idx).kind() {
+                StatementKind::Assign(_, r) => match r {
+                    RValue::BinOp(BinOp::LShr, _, Operand::Constant(c)) => {
+                        assert_eq!(*c, Constant::U16(shift))
+                    }
+                    r => panic!("Expected a right shift but found {:?}", r),
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn negate() {
+        let table = StringTable::new();
+        let b = build::Builder::new(&table, || ());
+        // this can only be used on signed types
+        for (literal_ty, v, exp_op, exp) in &[
+            (Type::I8, b.i8(1), UnOp::Negate, Constant::I8(1)),
+            (Type::I16, b.i16(1), UnOp::Negate, Constant::I16(1)),
+            (Type::I32, b.i32(1), UnOp::Negate, Constant::I32(1)),
+            (Type::I64, b.i64(1), UnOp::Negate, Constant::I64(1)),
+            (Type::F64, b.f64(5.0), UnOp::FNegate, Constant::F64(5.0)),
         ] {
             let literal = to_code(v, &table);
             let text = format!(
@@ -712,7 +815,7 @@ pub mod tests {
                     }}
                     ",
             );
-            let module = compile(&text, &mut table);
+            let module = compile(&text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
 
@@ -732,22 +835,15 @@ pub mod tests {
 
     #[test]
     fn boolean_binary_ops() {
-        let mut table = StringTable::new();
+        let table = StringTable::new();
+        let b = build::Builder::new(&table, || ());
         for (op, exp_op) in [
             (BinaryOperator::BAnd, BinOp::And),
             (BinaryOperator::BOr, BinOp::Or),
         ] {
             for (literal_ty, v, exp) in &[
-                (
-                    Type::Bool,
-                    Expression::Boolean((), true),
-                    Constant::Bool(true),
-                ),
-                (
-                    Type::Bool,
-                    Expression::Boolean((), false),
-                    Constant::Bool(false),
-                ),
+                (Type::Bool, b.bool(true), Constant::Bool(true)),
+                (Type::Bool, b.bool(false), Constant::Bool(false)),
             ] {
                 let literal = to_code(v, &table);
                 let text = format!(
@@ -758,7 +854,7 @@ pub mod tests {
                     }}
                     ",
                 );
-                let module = compile(&text, &mut table);
+                let module = compile(&text, &table);
                 let mut project = MirProject::new();
                 transform::transform(&module, &[], &mut project).unwrap();
 
@@ -786,7 +882,7 @@ pub mod tests {
 
     #[test]
     fn deref_rawpointer() {
-        let mut table = StringTable::new();
+        let table = StringTable::new();
         for literal_ty in &[
             Type::Bool,
             Type::I8,
@@ -808,7 +904,7 @@ pub mod tests {
                     }}
                     ",
             );
-            let module = compile(&text, &mut table);
+            let module = compile(&text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
 
@@ -847,12 +943,9 @@ pub mod tests {
 
     #[test]
     fn not() {
-        let mut table = StringTable::new();
-        for (literal_ty, v, exp) in &[(
-            Type::Bool,
-            Expression::Boolean((), true),
-            Constant::Bool(true),
-        )] {
+        let table = StringTable::new();
+        let b = build::Builder::new(&table, || ());
+        for (literal_ty, v, exp) in &[(Type::Bool, b.bool(true), Constant::Bool(true))] {
             let literal = to_code(v, &table);
             let text = format!(
                 "
@@ -862,7 +955,7 @@ pub mod tests {
                     }}
                     ",
             );
-            let module = compile(&text, &mut table);
+            let module = compile(&text, &table);
             let mut project = MirProject::new();
             transform::transform(&module, &[], &mut project).unwrap();
 
@@ -889,8 +982,8 @@ pub mod tests {
             return x;
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
 
@@ -919,6 +1012,75 @@ pub mod tests {
         assert_eq!(mutx.kind(), &expected_mutx);
     }
 
+    #[test]
+    fn mutate_array_element() {
+        let text = "
+        fn test() -> i64 {
+            let mut x: [i64; 2] := [1, 2];
+            mut x[1] := 5;
+            return x[1];
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "test"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+
+        let bb = mir.get_bb(BasicBlockId::new(0));
+
+        let mutx = bb.get_stm(bb.len() - 2); // mut x[1] := 5;
+        let expected_mutx = StatementKind::Assign(
+            LValue::Access(
+                Box::new(LValue::Var(VarId::new(0))),
+                Accessor::Index(Box::new(Operand::Constant(Constant::I64(1)))),
+            ),
+            RValue::Use(Operand::Constant(Constant::I64(5))),
+        );
+        assert_eq!(mutx.kind(), &expected_mutx);
+    }
+
+    #[test]
+    fn mutate_struct_field() {
+        let text = "
+        fn test() -> i64 {
+            let mut s: S := S{a: 1, b: 2};
+            mut s.b := 3;
+            return s.b;
+        }
+
+        struct S {
+            a: i64,
+            b: i64,
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "test"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+
+        let bb = mir.get_bb(BasicBlockId::new(0));
+
+        let muts = bb.get_stm(bb.len() - 2); // mut s.b := 3;
+        match muts.kind() {
+            StatementKind::Assign(
+                LValue::Access(base, Accessor::Field(fid, _)),
+                RValue::Use(Operand::Constant(Constant::I64(3))),
+            ) => {
+                assert_eq!(base.as_ref(), &LValue::Var(VarId::new(0)));
+                assert_eq!(u32::from(*fid), 1u32);
+            }
+            _ => panic!("Expected an assignment to the field b"),
+        }
+    }
+
     #[test]
     fn variable_scopes() {
         let text = "
@@ -933,8 +1095,8 @@ pub mod tests {
             return y;
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
 
@@ -989,8 +1151,8 @@ pub mod tests {
             return 0;
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
 
@@ -1029,6 +1191,106 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn if_expr_with_early_return() {
+        let text = "
+        fn test(b: bool) -> i64 {
+            if (b) {
+                return 1;
+            };
+            return 0;
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "test"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+
+        // Check that the entry BB goes to either the then arm or the merge BB
+        if let TerminatorKind::CondGoTo { cond: _, tru, fls } =
+            mir.get_bb(BasicBlockId::new(0)).get_term().unwrap().kind()
+        {
+            assert_eq!(*tru, BasicBlockId::new(1));
+            assert_eq!(*fls, BasicBlockId::new(2));
+        } else {
+            panic!("Expected a conditional go to")
+        }
+
+        // The then arm must return rather than go to the merge BB
+        assert_eq!(
+            mir.get_bb(BasicBlockId::new(1)).get_term().unwrap().kind(),
+            &TerminatorKind::Return
+        );
+
+        // The merge BB continues to the end of the function
+        assert_eq!(
+            mir.get_bb(BasicBlockId::new(2)).get_term().unwrap().kind(),
+            &TerminatorKind::Return
+        );
+    }
+
+    #[test]
+    fn if_expr_with_diverging_arm() {
+        let text = "
+        fn test(b: bool) -> i64 {
+            let x: i64 := if (b) {5} else {return 3;};
+            return x;
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "test"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+
+        // The then arm goes to the merge BB
+        assert_eq!(
+            mir.get_bb(BasicBlockId::new(1)).get_term().unwrap().kind(),
+            &TerminatorKind::GoTo {
+                target: BasicBlockId::new(3)
+            }
+        );
+
+        // The else arm diverges and returns from the function
+        assert_eq!(
+            mir.get_bb(BasicBlockId::new(2)).get_term().unwrap().kind(),
+            &TerminatorKind::Return
+        );
+    }
+
+    #[test]
+    fn while_expr_with_early_return() {
+        let text = "
+        fn test() -> i64 {
+            while (true) {
+                return 1;
+            };
+            return 0;
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "test"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+
+        // The body BB must return rather than go back to the cond BB
+        assert_eq!(
+            mir.get_bb(BasicBlockId::new(2)).get_term().unwrap().kind(),
+            &TerminatorKind::Return
+        );
+    }
+
     #[test]
     fn member_access() {
         let text = "
@@ -1041,8 +1303,8 @@ pub mod tests {
             b: i64,
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
 
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
@@ -1093,8 +1355,8 @@ pub mod tests {
             s: S,
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
 
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
@@ -1153,8 +1415,8 @@ pub mod tests {
             return x;
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
 
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
@@ -1185,8 +1447,8 @@ pub mod tests {
             return x;
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
 
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
@@ -1237,8 +1499,8 @@ pub mod tests {
             return;
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
 
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
@@ -1285,8 +1547,8 @@ pub mod tests {
             return test();
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
 
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
@@ -1330,8 +1592,8 @@ pub mod tests {
             return test2(1);
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
 
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
@@ -1343,7 +1605,7 @@ pub mod tests {
         assert_eq!(mir.path(), &path);
 
         // Get the Defid of the expected target
-        let path: Path = vec![Element::Id(table.find("test2").unwrap())].into();
+        let path: Path = to_path(&["main", "test2"], &table);
         let expected_target = project.find_def(&path).unwrap();
 
         // Check the BB terminator
@@ -1370,6 +1632,44 @@ pub mod tests {
         assert_eq!(ret_val.ty(), expected_ty);
     }
 
+    #[test]
+    fn call_extern_in_module() {
+        let text = "
+        mod ffi {
+            extern fn test2(x: i64) -> i64;
+        }
+
+        fn test() -> i64 {
+            return ffi::test2(1);
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "test"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+
+        // The extern is declared with the canonical path of the module it is in, but
+        // is linked to the name which it was declared with
+        let path: Path = to_path(&["main", "ffi", "test2"], &table);
+        let expected_target = project.find_def(&path).unwrap();
+        let target_def = project.get_def_fn(expected_target).unwrap();
+        assert_eq!(target_def.link_name(), table.find("test2"));
+
+        // Check the BB terminator
+        let term = mir.get_bb(BasicBlockId::new(0)).get_term().unwrap();
+        let func = match term.kind() {
+            TerminatorKind::CallFn { func, .. } => func,
+            _ => panic!(),
+        };
+
+        assert_eq!(*func, Operand::LValue(LValue::Static(expected_target)));
+    }
+
     #[test]
     fn call_variadic_extern() {
         let text = "
@@ -1379,8 +1679,8 @@ pub mod tests {
             return test2(1, 2, 3);
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
 
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
@@ -1392,7 +1692,7 @@ pub mod tests {
         assert_eq!(mir.path(), &path);
 
         // Get the Defid of the expected target
-        let path: Path = vec![Element::Id(table.find("test2").unwrap())].into();
+        let path: Path = to_path(&["main", "test2"], &table);
         let expected_target = project.find_def(&path).unwrap();
 
         // check that the extern is variadic
@@ -1432,8 +1732,8 @@ pub mod tests {
             return y;
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
 
@@ -1470,8 +1770,8 @@ pub mod tests {
             b: i64,
         }
         ";
-        let mut table = StringTable::new();
-        let module = compile(text, &mut table);
+        let table = StringTable::new();
+        let module = compile(text, &table);
 
         let mut project = MirProject::new();
         transform::transform(&module, &[], &mut project).unwrap();
@@ -1506,6 +1806,591 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn option_and_result_constructors() {
+        for (text, tag, value) in [
+            (
+                "fn test(x: i64) -> opt i64 { return some(x); }",
+                true,
+                Some(1u32),
+            ),
+            ("fn test(x: i64) -> opt i64 { return none; }", false, None),
+            (
+                "fn test(x: i64) -> res i64, bool { return ok(x); }",
+                true,
+                Some(1u32),
+            ),
+            (
+                "fn test(x: bool) -> res i64, bool { return err(x); }",
+                false,
+                Some(2u32),
+            ),
+        ] {
+            let table = StringTable::new();
+            let module = compile(text, &table);
+
+            let mut project = MirProject::new();
+            transform::transform(&module, &[], &mut project).unwrap();
+
+            let path: Path = to_path(&["main", "test"], &table);
+            let def_id = project.find_def(&path).unwrap();
+            let mir = project.get_def_fn(def_id).unwrap();
+            let bb = mir.get_bb(BasicBlockId::new(0));
+            let bool_ty = project.find_type(&Type::Bool).unwrap();
+
+            // The tag is stored first and then the value, if there is one
+            match bb.get_stm(0).kind() {
+                StatementKind::Assign(
+                    LValue::Access(base, Accessor::Field(fid, fty)),
+                    RValue::Use(Operand::Constant(Constant::Bool(b))),
+                ) => {
+                    assert!(matches!(**base, LValue::Temp(_)), "{}", text);
+                    assert_eq!(u32::from(*fid), 0u32, "{}", text);
+                    assert_eq!(*fty, bool_ty, "{}", text);
+                    assert_eq!(*b, tag, "{}", text);
+                }
+                _ => panic!("Expected the tag to be stored: {}", text),
+            }
+
+            match value {
+                Some(field) => match bb.get_stm(1).kind() {
+                    StatementKind::Assign(
+                        LValue::Access(_, Accessor::Field(fid, _)),
+                        RValue::Use(Operand::LValue(_)),
+                    ) => assert_eq!(u32::from(*fid), field, "{}", text),
+                    _ => panic!("Expected the value to be stored: {}", text),
+                },
+                None => assert!(
+                    !matches!(
+                        bb.get_stm(1).kind(),
+                        StatementKind::Assign(LValue::Access(..), _)
+                    ),
+                    "{}",
+                    text
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn option_and_result_if_let() {
+        for (text, field) in [
+            (
+                "fn test(x: opt i64) -> i64 { return if (let some(v) := x) {v} else {0}; }",
+                1u32,
+            ),
+            (
+                "fn test(x: res i64, bool) -> i64 { return if (let ok(v) := x) {v} else {0}; }",
+                1u32,
+            ),
+            (
+                "fn test(x: res i64, bool) -> bool { return if (let err(e) := x) {e} else {false}; }",
+                2u32,
+            ),
+        ] {
+            let table = StringTable::new();
+            let module = compile(text, &table);
+
+            let mut project = MirProject::new();
+            transform::transform(&module, &[], &mut project).unwrap();
+
+            let path: Path = to_path(&["main", "test"], &table);
+            let def_id = project.find_def(&path).unwrap();
+            let mir = project.get_def_fn(def_id).unwrap();
+
+            // The tag of the option or result is tested
+            let entry = mir.get_bb(BasicBlockId::new(0));
+            let tag = match entry.get_term().unwrap().kind() {
+                TerminatorKind::CondGoTo {
+                    cond: Operand::LValue(LValue::Access(_, Accessor::Field(fid, _))),
+                    ..
+                } => *fid,
+                TerminatorKind::CondGoTo {
+                    cond: Operand::LValue(LValue::Temp(_)),
+                    ..
+                } => match entry.get_stm(entry.len() - 1).kind() {
+                    StatementKind::Assign(
+                        _,
+                        RValue::UnOp(
+                            UnOp::Not,
+                            Operand::LValue(LValue::Access(_, Accessor::Field(fid, _))),
+                        ),
+                    ) => *fid,
+                    _ => panic!("Expected the tag to be negated: {}", text),
+                },
+                _ => panic!("Expected a conditional go to: {}", text),
+            };
+            assert_eq!(u32::from(tag), 0u32, "{}", text);
+
+            // And then the value which it holds is bound in the true arm
+            match mir.get_bb(BasicBlockId::new(1)).get_stm(0).kind() {
+                StatementKind::Assign(
+                    LValue::Var(_),
+                    RValue::Use(Operand::LValue(LValue::Access(_, Accessor::Field(fid, _)))),
+                ) => assert_eq!(u32::from(*fid), field, "{}", text),
+                _ => panic!("Expected the value to be bound: {}", text),
+            }
+        }
+    }
+
+    #[test]
+    fn try_option_returns_early_and_runs_defers() {
+        let text = "
+        fn test(x: opt i64) -> opt i64 {
+            defer test2();
+            let v: i64 := x?;
+            return some(v);
+        }
+
+        fn test2() {
+            return;
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "test"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+
+        // The tag of the option decides whether to continue or to return early
+        match mir.get_bb(BasicBlockId::new(0)).get_term().unwrap().kind() {
+            TerminatorKind::CondGoTo {
+                cond: Operand::LValue(LValue::Access(_, Accessor::Field(fid, _))),
+                tru,
+                fls,
+            } => {
+                assert_eq!(u32::from(*fid), 0u32);
+                assert_eq!(*tru, BasicBlockId::new(1));
+                assert_eq!(*fls, BasicBlockId::new(2));
+            }
+            _ => panic!("Expected a conditional go to"),
+        }
+
+        // Returning early stores none in the return value and then runs the deferred call
+        let fail = mir.get_bb(BasicBlockId::new(2));
+        match fail.get_stm(0).kind() {
+            StatementKind::Assign(
+                LValue::Access(base, Accessor::Field(fid, _)),
+                RValue::Use(Operand::Constant(Constant::Bool(false))),
+            ) => {
+                assert_eq!(**base, LValue::ReturnPointer);
+                assert_eq!(u32::from(*fid), 0u32);
+            }
+            _ => panic!("Expected none to be returned"),
+        }
+        assert!(matches!(
+            fail.get_term().unwrap().kind(),
+            TerminatorKind::CallFn { .. }
+        ));
+        assert_eq!(
+            mir.get_bb(BasicBlockId::new(3)).get_term().unwrap().kind(),
+            &TerminatorKind::Return
+        );
+
+        // Otherwise, the value of the option is bound to v
+        match mir.get_bb(BasicBlockId::new(1)).get_stm(0).kind() {
+            StatementKind::Assign(
+                LValue::Var(_),
+                RValue::Use(Operand::LValue(LValue::Access(_, Accessor::Field(fid, _)))),
+            ) => assert_eq!(u32::from(*fid), 1u32),
+            _ => panic!("Expected the value to be bound"),
+        }
+    }
+
+    #[test]
+    fn try_result_returns_err() {
+        let text = "
+        fn test(x: res i64, bool) -> res i64, bool {
+            let v: i64 := x?;
+            return ok(v);
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "test"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+        let bool_ty = project.find_type(&Type::Bool).unwrap();
+
+        // The err of x is copied into the return value
+        let fail = mir.get_bb(BasicBlockId::new(2));
+        match fail.get_stm(1).kind() {
+            StatementKind::Assign(
+                LValue::Access(base, Accessor::Field(fid, fty)),
+                RValue::Use(Operand::LValue(LValue::Access(_, Accessor::Field(src_fid, _)))),
+            ) => {
+                assert_eq!(**base, LValue::ReturnPointer);
+                assert_eq!(u32::from(*fid), 2u32);
+                assert_eq!(u32::from(*src_fid), 2u32);
+                assert_eq!(*fty, bool_ty);
+            }
+            _ => panic!("Expected the err to be returned"),
+        }
+        assert_eq!(fail.get_term().unwrap().kind(), &TerminatorKind::Return);
+    }
+
+    #[test]
+    fn struct_expression_source_order() {
+        let text = "
+        fn test() -> S {
+            let s: S := S{b: 2, a: 1};
+            return s;
+        }
+
+        struct S {
+            a: i64,
+            b: i64,
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "test"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+
+        let bb = mir.get_bb(BasicBlockId::new(0));
+
+        // The field initializers are evaluated in source order, not definition order
+        let stm = bb.get_stm(0);
+        match stm.kind() {
+            StatementKind::Assign(
+                LValue::Access(_, Accessor::Field(fid, _)),
+                RValue::Use(Operand::Constant(Constant::I64(2))),
+            ) => {
+                assert_eq!(u32::from(*fid), 1u32);
+            }
+            _ => panic!(),
+        }
+
+        let stm = bb.get_stm(1);
+        match stm.kind() {
+            StatementKind::Assign(
+                LValue::Access(_, Accessor::Field(fid, _)),
+                RValue::Use(Operand::Constant(Constant::I64(1))),
+            ) => {
+                assert_eq!(u32::from(*fid), 0u32);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn unsupported_string_type() {
+        let text = "
+        fn test() -> u64 {
+            let s: String := \"hello\" as String;
+            return len(s);
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        match transform::transform(&module, &[], &mut project) {
+            Err(transform::TransformError::Unsupported(err)) => {
+                assert_eq!(
+                    err.inner(),
+                    &transform::UnsupportedError::Type(Type::String)
+                );
+            }
+            _ => panic!("Expected the String type to be rejected"),
+        }
+    }
+
+    #[test]
+    fn unsupported_string_literal_length() {
+        let text = "
+        fn test() -> u64 {
+            return len(\"hello\");
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        match transform::transform(&module, &[], &mut project) {
+            Err(transform::TransformError::Unsupported(err)) => {
+                assert_eq!(
+                    err.inner(),
+                    &transform::UnsupportedError::Length(Type::StringLiteral)
+                );
+            }
+            _ => panic!("Expected the length of a string to be rejected"),
+        }
+    }
+
+    #[test]
+    fn unsupported_slice_type() {
+        let text = "
+        fn test(s: [i64]) -> i64 {
+            return s[0];
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        match transform::transform(&module, &[], &mut project) {
+            Err(transform::TransformError::Unsupported(err)) => {
+                assert_eq!(
+                    err.inner(),
+                    &transform::UnsupportedError::Type(Type::Slice(Box::new(Type::I64)))
+                );
+            }
+            _ => panic!("Expected the slice type to be rejected"),
+        }
+    }
+
+    #[test]
+    fn unsupported_range_type() {
+        let text = "
+        fn test() -> u64 {
+            let r: Range<i64> := 0..4;
+            return len(r);
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        match transform::transform(&module, &[], &mut project) {
+            Err(transform::TransformError::Unsupported(err)) => {
+                assert_eq!(
+                    err.inner(),
+                    &transform::UnsupportedError::Type(Type::Range(Box::new(Type::I64)))
+                );
+            }
+            _ => panic!("Expected the range type to be rejected"),
+        }
+    }
+
+    #[test]
+    fn coroutine_init_stores_args_in_frame() {
+        let text = "
+        fn test() -> i64 {
+            let c: co i64 := init count(2);
+            return 0;
+        }
+
+        co count(n: i64) -> i64 {
+            return n;
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "test"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+        let bb = mir.get_bb(BasicBlockId::new(0));
+
+        // The argument is stored in the field after ROUTINE, STATE, and DONE, and then
+        // the instance is set to run from the start of the coroutine
+        let expected = [
+            (3u32, Constant::I64(2)),
+            (0, Constant::U32(0)),
+            (1, Constant::U32(0)),
+            (2, Constant::Bool(false)),
+        ];
+        for (idx, (field, value)) in expected.iter().enumerate() {
+            match bb.get_stm(idx).kind() {
+                StatementKind::Assign(
+                    LValue::Access(base, Accessor::Field(fid, _)),
+                    RValue::Use(Operand::Constant(c)),
+                ) => {
+                    assert_eq!(**base, LValue::Temp(TempId::new(0)));
+                    assert_eq!(u32::from(*fid), *field);
+                    assert_eq!(c, value);
+                }
+                _ => panic!("Expected field {} of the frame to be set", field),
+            }
+        }
+    }
+
+    #[test]
+    fn coroutine_resumes_from_state() {
+        let text = "
+        fn test() -> i64 {
+            let mut c: co i64 := init count(2);
+            return yield c;
+        }
+
+        co count(n: i64) -> i64 {
+            yret n;
+            return n + 1;
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "count"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+        let frame = LValue::Access(Box::new(LValue::Var(VarId::new(0))), Accessor::Deref);
+
+        // The first block goes to the point which the STATE field says to resume from
+        match mir.get_bb(BasicBlockId::new(0)).get_stm(0).kind() {
+            StatementKind::Assign(_, RValue::BinOp(BinOp::Eq, Operand::LValue(lv), _)) => {
+                match lv {
+                    LValue::Access(base, Accessor::Field(fid, _)) => {
+                        assert_eq!(**base, frame);
+                        assert_eq!(u32::from(*fid), 1u32);
+                    }
+                    _ => panic!("Expected the STATE field to be read"),
+                }
+            }
+            _ => panic!("Expected the state to be compared"),
+        }
+
+        // yret returns the value of the parameter, which is stored in the frame, and then
+        // sets the point which the coroutine resumes from
+        let start = mir.get_bb(BasicBlockId::new(1));
+        match start.get_stm(0).kind() {
+            StatementKind::Assign(
+                LValue::ReturnPointer,
+                RValue::Use(Operand::LValue(LValue::Access(base, Accessor::Field(fid, _)))),
+            ) => {
+                assert_eq!(**base, frame);
+                assert_eq!(u32::from(*fid), 3u32);
+            }
+            _ => panic!("Expected the yielded value to be returned"),
+        }
+        match start.get_stm(1).kind() {
+            StatementKind::Assign(
+                LValue::Access(_, Accessor::Field(fid, _)),
+                RValue::Use(Operand::Constant(Constant::U32(1))),
+            ) => assert_eq!(u32::from(*fid), 1u32),
+            _ => panic!("Expected the state to be set"),
+        }
+        assert_eq!(start.get_term().unwrap().kind(), &TerminatorKind::Return);
+
+        // Calling yield resumes the coroutine with the address of the instance
+        let path: Path = to_path(&["main", "test"], &table);
+        let test_id = project.find_def(&path).unwrap();
+        let test = project.get_def_fn(test_id).unwrap();
+        match test.get_bb(BasicBlockId::new(1)).get_term().unwrap().kind() {
+            TerminatorKind::CallFn { func, args, .. } => {
+                assert_eq!(func, &Operand::LValue(LValue::Static(def_id)));
+                assert_eq!(args.len(), 1);
+            }
+            _ => panic!("Expected the coroutine to be resumed"),
+        }
+    }
+
+    #[test]
+    fn coroutine_return_sets_done() {
+        let text = "
+        co count(n: i64) -> i64 {
+            return n;
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "count"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+
+        // Returning resumes from the return statement, so that it is evaluated again, and
+        // marks the coroutine as done
+        let ret = mir.get_bb(BasicBlockId::new(2));
+        match ret.get_stm(1).kind() {
+            StatementKind::Assign(
+                LValue::Access(_, Accessor::Field(fid, _)),
+                RValue::Use(Operand::Constant(Constant::U32(1))),
+            ) => assert_eq!(u32::from(*fid), 1u32),
+            _ => panic!("Expected the state to be set"),
+        }
+        match ret.get_stm(2).kind() {
+            StatementKind::Assign(
+                LValue::Access(_, Accessor::Field(fid, _)),
+                RValue::Use(Operand::Constant(Constant::Bool(true))),
+            ) => assert_eq!(u32::from(*fid), 2u32),
+            _ => panic!("Expected the coroutine to be marked as done"),
+        }
+        match mir.get_bb(BasicBlockId::new(0)).get_term().unwrap().kind() {
+            TerminatorKind::CondGoTo { tru, .. } => assert_eq!(*tru, BasicBlockId::new(2)),
+            _ => panic!("Expected the state to be checked"),
+        }
+    }
+
+    #[test]
+    fn coroutine_done_reads_frame() {
+        let text = "
+        fn test() -> bool {
+            let c: co i64 := init count(2);
+            return c.done;
+        }
+
+        co count(n: i64) -> i64 {
+            return n;
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let path: Path = to_path(&["main", "test"], &table);
+        let def_id = project.find_def(&path).unwrap();
+        let mir = project.get_def_fn(def_id).unwrap();
+        let bb = mir.get_bb(BasicBlockId::new(0));
+
+        // `done` is the DONE field of the instance's frame
+        match bb.get_stm(bb.len() - 1).kind() {
+            StatementKind::Assign(
+                LValue::ReturnPointer,
+                RValue::Use(Operand::LValue(LValue::Access(base, Accessor::Field(fid, _)))),
+            ) => {
+                assert_eq!(**base, LValue::Var(VarId::new(0)));
+                assert_eq!(u32::from(*fid), 2u32);
+            }
+            _ => panic!("Expected the DONE field to be returned"),
+        }
+    }
+
+    #[test]
+    fn unsupported_nested_coroutine() {
+        let text = "
+        co outer(n: i64) -> i64 {
+            let c: co i64 := init outer(n);
+            return n;
+        }
+        ";
+        let table = StringTable::new();
+        let module = compile(text, &table);
+
+        let mut project = MirProject::new();
+        match transform::transform(&module, &[], &mut project) {
+            Err(transform::TransformError::Unsupported(err)) => {
+                assert_eq!(err.inner(), &transform::UnsupportedError::NestedCoroutine);
+            }
+            _ => panic!("Expected a coroutine within a coroutine to be rejected"),
+        }
+    }
+
     fn to_path(v: &[&str], table: &StringTable) -> Path {
         let mut path = vec![Element::CanonicalRoot];
 
@@ -0,0 +1,292 @@
+//! Lowers coroutines to state machines.
+//!
+//! Each coroutine is lowered to a resume procedure, which takes a pointer to the frame
+//! of a coroutine instance and runs the coroutine from where it was last suspended until
+//! it yields its next value.  The frame holds every variable of the coroutine, so that
+//! their values are kept while the coroutine is suspended, and the state, which tells
+//! the resume procedure where to continue from.
+//!
+//! A coroutine instance of type `co T` can be created by any coroutine which yields `T`,
+//! so all of the coroutines which yield `T` share one frame.  The frame begins with the
+//! [`ROUTINE`], [`STATE`], and [`DONE`] fields, which are followed by the variables of
+//! each coroutine in turn.  A coroutine instance is a value: copying it copies the frame,
+//! and the copy is resumed independently of the original.
+//!
+//! The [`DONE`] field is read by `c.done`.  It is set when the coroutine returns, and
+//! resuming a coroutine which is done evaluates its return statement again.
+
+use log::debug;
+
+use crate::compiler::{
+    ast::*,
+    mir::{
+        ir::{ArgDecl, Procedure},
+        project::MirProject,
+        typetable::{Field, FieldId, MirStructDef, MirTypeDef, TypeId},
+    },
+    semantics::semanticnode::SemanticContext,
+};
+
+use super::TransformError;
+
+/// The field of a frame which holds the number of the coroutine which created the
+/// instance, so that `yield` knows which resume procedure to call.
+pub(super) const ROUTINE: FieldId = FieldId::new(0);
+
+/// The field of a frame which holds the point that the coroutine resumes from.
+pub(super) const STATE: FieldId = FieldId::new(1);
+
+/// The field of a frame which is `true` once the coroutine has returned.
+pub(super) const DONE: FieldId = FieldId::new(2);
+
+/// The coroutines in a project and the frames which hold their variables.
+pub(super) struct Coroutines {
+    frames: Vec<Frame>,
+    routines: Vec<Routine>,
+}
+
+/// The frame of every coroutine which yields the same type.
+pub(super) struct Frame {
+    yield_ty: Type,
+
+    /// The structure which the frame is laid out as
+    ty: TypeId,
+
+    fields: Vec<Field>,
+
+    /// The paths of the coroutines which use this frame.  An instance created by the
+    /// coroutine at index `i` stores `i` in its [`ROUTINE`] field.
+    routines: Vec<Path>,
+}
+
+impl Frame {
+    /// Returns the structure which the frame is laid out as.
+    pub fn ty(&self) -> TypeId {
+        self.ty
+    }
+
+    /// Returns the paths of the coroutines which use this frame, in the order of the
+    /// number which each stores in the [`ROUTINE`] field.
+    pub fn routines(&self) -> &[Path] {
+        &self.routines
+    }
+
+    /// Returns the field which will hold the next variable that is added to the frame.
+    pub fn next_field(&self) -> u32 {
+        self.fields.len() as u32
+    }
+}
+
+/// A coroutine definition.
+pub(super) struct Routine {
+    path: Path,
+
+    /// The index of the frame which holds this coroutine's variables
+    frame: usize,
+
+    /// The number which is stored in the [`ROUTINE`] field of an instance of this coroutine
+    number: u32,
+
+    /// The fields which hold the parameters of the coroutine, in order, and their types
+    params: Vec<(FieldId, TypeId)>,
+}
+
+impl Routine {
+    /// Returns the number which is stored in the [`ROUTINE`] field of an instance of this
+    /// coroutine.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// Returns the fields of the frame which hold the parameters of the coroutine and
+    /// their types.
+    pub fn params(&self) -> &[(FieldId, TypeId)] {
+        &self.params
+    }
+}
+
+impl Coroutines {
+    /// Declares the frame of every type which is yielded by a coroutine in `module`, or
+    /// in any of its submodules, so that coroutine types can be added to the type table.
+    pub fn declare(
+        project: &mut MirProject,
+        module: &Module<SemanticContext>,
+    ) -> Result<Coroutines, TransformError> {
+        let mut coroutines = Coroutines {
+            frames: vec![],
+            routines: vec![],
+        };
+        coroutines.declare_frames(project, module)?;
+        Ok(coroutines)
+    }
+
+    fn declare_frames(
+        &mut self,
+        project: &mut MirProject,
+        module: &Module<SemanticContext>,
+    ) -> Result<(), TransformError> {
+        for co in get_coroutines(module) {
+            let yield_ty = co.context().ty();
+            let frame = match self.frames.iter().position(|f| f.yield_ty == *yield_ty) {
+                Some(frame) => frame,
+                None => {
+                    debug!("Declaring coroutine frame for {}", yield_ty);
+                    let ty =
+                        project.add_coroutine_frame(yield_ty, co.context().canonical_path())?;
+                    // The ROUTINE, STATE, and DONE fields
+                    let fields = [Type::U32, Type::U32, Type::Bool]
+                        .iter()
+                        .map(|ty| {
+                            let ty = project.find_type(ty).ok_or(TransformError::TypeNotFound)?;
+                            Ok(Field { name: co.name, ty })
+                        })
+                        .collect::<Result<_, TransformError>>()?;
+                    self.frames.push(Frame {
+                        yield_ty: yield_ty.clone(),
+                        ty,
+                        fields,
+                        routines: vec![],
+                    });
+                    self.frames.len() - 1
+                }
+            };
+
+            let path = co.context().canonical_path();
+            self.frames[frame].routines.push(path.clone());
+            self.routines.push(Routine {
+                path: path.clone(),
+                frame,
+                number: self.frames[frame].routines.len() as u32 - 1,
+                params: vec![],
+            });
+        }
+
+        for m in module.get_modules() {
+            self.declare_frames(project, m)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds the parameters of every coroutine in `module`, and its submodules, to their
+    /// frames and adds the declaration of each coroutine's resume procedure to the
+    /// project.  The types of the parameters must already be in the type table.
+    pub fn add_declarations(
+        &mut self,
+        project: &mut MirProject,
+        module: &Module<SemanticContext>,
+    ) -> Result<(), TransformError> {
+        for co in get_coroutines(module) {
+            let routine = self.find_routine(co.context().canonical_path());
+            let frame = self.routines[routine].frame;
+            for p in &co.params {
+                let ty = project
+                    .find_type(p.context().ty())
+                    .ok_or(TransformError::TypeNotFound)?;
+                let field = self.add_field(frame, Field { name: p.name, ty });
+                self.routines[routine].params.push((field, ty));
+            }
+
+            // The resume procedure is given a pointer to the frame of the instance
+            let frame_ty = Type::Coroutine(Box::new(co.context().ty().clone()));
+            let frame_ptr =
+                project.add_type(&Type::RawPointer(PointerMut::Mut, Box::new(frame_ty)))?;
+            let ret_ty = project
+                .find_type(co.context().ty())
+                .ok_or(TransformError::TypeNotFound)?;
+            let args = vec![ArgDecl::new(co.name, frame_ptr, None, co.context().span())];
+            let p = Procedure::new(
+                co.context().canonical_path(),
+                args,
+                ret_ty,
+                co.context().span(),
+            );
+            project.add_func(p)?;
+        }
+
+        for m in module.get_modules() {
+            self.add_declarations(project, m)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds the given variables to the end of the frame which yields `yield_ty`.
+    pub fn add_locals(&mut self, yield_ty: &Type, locals: Vec<Field>) {
+        let frame = self.find_frame(yield_ty);
+        for l in locals {
+            self.add_field(frame, l);
+        }
+    }
+
+    /// Defines every frame with the fields which have been added to it.
+    pub fn define_frames(&self, project: &mut MirProject) -> Result<(), TransformError> {
+        for f in &self.frames {
+            project.define_coroutine_frame(f.ty, f.fields.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Returns the frame of the coroutines which yield `yield_ty`.
+    pub fn frame(&self, yield_ty: &Type) -> &Frame {
+        &self.frames[self.find_frame(yield_ty)]
+    }
+
+    /// Returns the coroutine with the given canonical path.
+    pub fn routine(&self, path: &Path) -> &Routine {
+        &self.routines[self.find_routine(path)]
+    }
+
+    /// Returns `true` if a value of type `ty` holds the frame of a coroutine.
+    pub fn contains_frame(&self, project: &MirProject, ty: TypeId) -> bool {
+        if self.frames.iter().any(|f| f.ty == ty) {
+            return true;
+        }
+
+        match project.get_type(ty) {
+            MirTypeDef::Array { ty, .. } | MirTypeDef::Option { ty } => {
+                self.contains_frame(project, *ty)
+            }
+            MirTypeDef::Result { ok, err } => {
+                self.contains_frame(project, *ok) || self.contains_frame(project, *err)
+            }
+            MirTypeDef::Structure {
+                def: MirStructDef::Defined(fields),
+                ..
+            } => fields.iter().any(|f| self.contains_frame(project, f.ty)),
+            MirTypeDef::Base(_) | MirTypeDef::RawPointer { .. } | MirTypeDef::Structure { .. } => {
+                false
+            }
+        }
+    }
+
+    fn add_field(&mut self, frame: usize, field: Field) -> FieldId {
+        let fields = &mut self.frames[frame].fields;
+        fields.push(field);
+        FieldId::new(fields.len() as u32 - 1)
+    }
+
+    fn find_frame(&self, yield_ty: &Type) -> usize {
+        self.frames
+            .iter()
+            .position(|f| f.yield_ty == *yield_ty)
+            .unwrap_or_else(|| panic!("No coroutine yields {}", yield_ty))
+    }
+
+    fn find_routine(&self, path: &Path) -> usize {
+        self.routines
+            .iter()
+            .position(|r| r.path == *path)
+            .unwrap_or_else(|| panic!("Coroutine not found: {}", path))
+    }
+}
+
+/// Returns the coroutines which are defined in `module`, but not in its submodules.
+pub(super) fn get_coroutines(
+    module: &Module<SemanticContext>,
+) -> impl Iterator<Item = &RoutineDef<SemanticContext>> {
+    module
+        .get_coroutines()
+        .iter()
+        .filter_map(|c| c.to_routine())
+}
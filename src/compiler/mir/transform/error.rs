@@ -1,12 +1,23 @@
 //! Defines the error types which are used by the transformers.
 
-use crate::compiler::mir::{project::StaticDefinitionError, typetable::TypeTableError};
+use crate::{
+    compiler::{
+        ast::Type,
+        messages,
+        mir::{project::StaticDefinitionError, typetable::TypeTableError},
+        CompilerDisplay, CompilerDisplayError, CompilerError, SourceMap,
+    },
+    StringTable,
+};
 
 #[derive(Debug)]
 pub enum TransformError {
     TypeError(TypeTableError),
     StaticDefError(StaticDefinitionError),
     TypeNotFound,
+
+    /// The program uses a part of the language which cannot be lowered to MIR yet
+    Unsupported(CompilerError<UnsupportedError>),
 }
 
 impl From<TypeTableError> for TransformError {
@@ -20,3 +31,39 @@ impl From<StaticDefinitionError> for TransformError {
         TransformError::StaticDefError(sde)
     }
 }
+
+/// A part of the language which the MIR cannot represent yet.  A program which uses it
+/// must be compiled without `--mir-beta`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnsupportedError {
+    /// A value whose type is, or contains, a `String`, a slice, or a range
+    Type(Type),
+
+    /// The length of a sequence which is not an array
+    Length(Type),
+
+    /// A variable of a coroutine which holds a coroutine instance
+    NestedCoroutine,
+}
+
+impl UnsupportedError {
+    /// Returns the code which identifies this error in the message catalog.
+    pub fn code(&self) -> &'static str {
+        match self {
+            UnsupportedError::Type(_) => "M0001",
+            UnsupportedError::Length(_) => "M0002",
+            UnsupportedError::NestedCoroutine => "M0003",
+        }
+    }
+}
+
+impl CompilerDisplay for UnsupportedError {
+    fn fmt(&self, sm: &SourceMap, st: &StringTable) -> Result<String, CompilerDisplayError> {
+        let args = match self {
+            UnsupportedError::Type(ty) | UnsupportedError::Length(ty) => vec![ty.fmt(sm, st)?],
+            UnsupportedError::NestedCoroutine => vec![],
+        };
+
+        Ok(messages::message(self.code(), &args))
+    }
+}
@@ -11,31 +11,60 @@ use crate::{
         ast::{self, *},
         semantics::semanticnode::SemanticContext,
         source::Offset,
-        Span,
+        CompilerError, Span,
     },
     StringId,
 };
 
-use super::super::{builder::MirProcedureBuilder, ir::*, project::MirProject, typetable::*};
+use super::{
+    super::{builder::MirProcedureBuilder, ir::*, project::MirProject, typetable::*},
+    coroutine::{Coroutines, DONE, ROUTINE, STATE},
+    TransformError, UnsupportedError,
+};
 
 /// Transform a single function to the MIR form
 pub(super) struct FuncTransformer<'a> {
     project: &'a mut MirProject,
     mir: MirProcedureBuilder,
+
+    /// The expressions deferred within each scope that is open, from the body of the
+    /// function to the innermost block, with the scope that they were deferred in.
+    defers: Vec<(ScopeId, Vec<Expression<SemanticContext>>)>,
+
+    /// The first expression in the function which cannot be lowered to MIR yet.
+    unsupported: Option<CompilerError<UnsupportedError>>,
+
+    /// The coroutines in the project and the frames of their instances.
+    coroutines: &'a Coroutines,
+
+    /// If this is lowering a coroutine, then the [`BasicBlock`] which the coroutine
+    /// resumes from in each state.  The coroutine starts in state 0.
+    resume_points: Option<Vec<BasicBlockId>>,
 }
 
 impl<'a> FuncTransformer<'a> {
-    pub fn new(path: &Path, project: &'a mut MirProject) -> FuncTransformer<'a> {
+    pub fn new(
+        path: &Path,
+        project: &'a mut MirProject,
+        coroutines: &'a Coroutines,
+    ) -> FuncTransformer<'a> {
         let unit = project
             .find_type(&Type::Unit)
             .expect("Cannot find Unit type");
         FuncTransformer {
             project,
             mir: MirProcedureBuilder::new(path, unit),
+            defers: vec![],
+            unsupported: None,
+            coroutines,
+            resume_points: None,
         }
     }
 
-    pub fn transform(mut self, func: &RoutineDef<SemanticContext>) -> Procedure {
+    pub fn transform(
+        mut self,
+        func: &RoutineDef<SemanticContext>,
+    ) -> Result<Procedure, TransformError> {
         self.mir.set_span(func.context.span());
 
         // Set the function return type
@@ -54,26 +83,312 @@ impl<'a> FuncTransformer<'a> {
         self.mir.set_bb(bb);
 
         // Iterate over every statement and add it to the basic block
+        self.open_defer_scope();
         func.body.iter().for_each(|stm| self.statement(stm));
+        self.close_defer_scope();
 
         // Add the return from function as the terminator for the final basic block of the function
         self.mir.term_return(span_end(func.context.span()));
-        self.mir.complete()
+        match self.unsupported {
+            Some(err) => Err(TransformError::Unsupported(err)),
+            None => Ok(self.mir.complete()),
+        }
+    }
+
+    /// Lowers a coroutine to its resume procedure, which takes a pointer to the frame of a
+    /// coroutine instance and runs the coroutine until it yields its next value.  Returns
+    /// the procedure and the fields which must be added to the frame to hold the variables
+    /// of the coroutine.
+    pub fn transform_coroutine(
+        mut self,
+        co: &RoutineDef<SemanticContext>,
+    ) -> Result<(Procedure, Vec<Field>), TransformError> {
+        let span = co.context.span();
+        self.mir.set_span(span);
+
+        let yield_ty = co.context().ty();
+        let ret_ty = self.find_type(yield_ty);
+        self.mir.set_ret_ty(ret_ty);
+
+        let frame_ptr = self.frame_ptr_type(yield_ty);
+        self.mir.arg(co.name, frame_ptr, span);
+
+        // The entry block jumps to the point which the coroutine resumes from, and those
+        // points are only known once the body has been lowered
+        let entry = self.mir.new_bb();
+        let start = self.mir.new_bb();
+        self.mir.set_bb(start);
+        self.resume_points = Some(vec![start]);
+
+        // The parameters are stored in the frame by `init`, so they are variables rather
+        // than arguments of the resume procedure
+        co.params.iter().for_each(|p| {
+            let ty = self.find_type(p.context().ty());
+            self.mir.var(p.name, false, ty, p.context().span());
+        });
+
+        // The body of a coroutine always ends with a return statement, which terminates
+        // the last block
+        self.open_defer_scope();
+        co.body.iter().for_each(|stm| self.statement(stm));
+        self.defers.pop();
+
+        let locals = self.move_to_frame(co);
+        self.resume(entry, span_begin(span));
+        match self.unsupported {
+            Some(err) => Err(TransformError::Unsupported(err)),
+            None => Ok((self.mir.complete(), locals)),
+        }
+    }
+
+    /// Moves the parameters, variables, and temporaries of the coroutine `co` into its
+    /// frame, so that they keep their values while the coroutine is suspended.  Returns the
+    /// fields which must be added to the frame for the variables and temporaries; the
+    /// parameters already have fields.
+    fn move_to_frame(&mut self, co: &RoutineDef<SemanticContext>) -> Vec<Field> {
+        let params = self
+            .coroutines
+            .routine(co.context().canonical_path())
+            .params()
+            .to_vec();
+        let mut next_field = self.coroutines.frame(co.context().ty()).next_field();
+        let mut locals = vec![];
+
+        // The first variable is the pointer to the frame, which is left as it is
+        let proc = self.mir.proc();
+        let mut var_fields = vec![None];
+        for (var, param) in proc
+            .varid_iter()
+            .skip(1)
+            .zip(params.into_iter().map(Some).chain(std::iter::repeat(None)))
+        {
+            let decl = proc.get_var(var);
+            let (field, ty) = match param {
+                Some(param) => param,
+                None => {
+                    locals.push(Field {
+                        name: decl.name(),
+                        ty: decl.ty(),
+                    });
+                    next_field += 1;
+                    (FieldId::new(next_field - 1), decl.ty())
+                }
+            };
+            var_fields.push(Some((field, ty, decl.span())));
+        }
+
+        let mut temp_fields = vec![];
+        for temp in proc.tempid_iter() {
+            let decl = proc.get_temp(temp);
+            locals.push(Field {
+                name: co.name,
+                ty: decl.ty(),
+            });
+            next_field += 1;
+            temp_fields.push((FieldId::new(next_field - 1), decl.ty(), decl.span()));
+        }
+
+        // A frame cannot hold another frame, because a coroutine could then hold itself
+        let nested = var_fields
+            .iter()
+            .flatten()
+            .chain(temp_fields.iter())
+            .find(|(_, ty, _)| self.coroutines.contains_frame(self.project, *ty))
+            .map(|(_, _, span)| *span);
+        if let Some(span) = nested {
+            self.unsupported(span, UnsupportedError::NestedCoroutine);
+        }
+
+        let frame = self.frame();
+        let var_locs: Vec<_> = var_fields
+            .iter()
+            .map(|vf| vf.map(|(field, ty, _)| self.mir.frame_field(frame.clone(), field, ty)))
+            .collect();
+        let temp_locs: Vec<_> = temp_fields
+            .iter()
+            .map(|(field, ty, _)| self.mir.frame_field(frame.clone(), *field, *ty))
+            .collect();
+        self.mir.map_lvalues(&|lv| match lv {
+            LValue::Var(v) => var_locs[v.index()].clone(),
+            LValue::Temp(t) => Some(temp_locs[t.index()].clone()),
+            _ => None,
+        });
+
+        locals
+    }
+
+    /// Terminates `entry`, the first block of a resume procedure, by going to the block
+    /// which the coroutine resumes from in the state which is stored in its frame.
+    fn resume(&mut self, entry: BasicBlockId, span: Span) {
+        let resume_points = self
+            .resume_points
+            .take()
+            .expect("Only a coroutine has resume points");
+        let u32_ty = self.find_type(&Type::U32);
+        let bool_ty = self.find_type(&Type::Bool);
+        let state = Operand::LValue(self.mir.frame_field(self.frame(), STATE, u32_ty));
+
+        let mut bb = entry;
+        for (id, point) in resume_points.iter().enumerate().skip(1) {
+            self.mir.set_bb(bb);
+            let is_state = self.mir.eq(state.clone(), self.mir.const_u32(id as u32));
+            let is_state = self.mir.temp_store(is_state, bool_ty, span);
+            let next = self.mir.new_bb();
+            self.mir.term_cond_goto(is_state, *point, next, span);
+            bb = next;
+        }
+
+        self.mir.set_bb(bb);
+        self.mir.term_goto(resume_points[0], span);
+    }
+
+    /// Returns the location of the frame of a coroutine instance, whose address is the
+    /// only argument of a resume procedure.
+    fn frame(&self) -> LValue {
+        self.mir.deref_rawpointer(LValue::Var(VarId::new(0)))
+    }
+
+    /// Returns the type of a pointer to the frame of a coroutine which yields `yield_ty`.
+    fn frame_ptr_type(&self, yield_ty: &Type) -> TypeId {
+        let co_ty = Type::Coroutine(Box::new(yield_ty.clone()));
+        self.find_type(&Type::RawPointer(PointerMut::Mut, Box::new(co_ty)))
+    }
+
+    /// Records that an expression cannot be lowered to MIR yet.  Lowering continues, so
+    /// that the caller gets an error rather than a panic, but only the first such
+    /// expression is reported.
+    fn unsupported(&mut self, span: Span, err: UnsupportedError) {
+        if self.unsupported.is_none() {
+            self.unsupported = Some(CompilerError::new(span, err));
+        }
     }
 
     fn statement(&mut self, stm: &ast::Statement<SemanticContext>) {
         debug!("Transform statement");
+        self.skip_terminator();
+
         match stm {
             ast::Statement::Bind(bind) => self.bind(bind),
             ast::Statement::Expression(expr) => {
                 self.expression(expr);
             }
             ast::Statement::Mutate(mutate) => self.mutate(mutate),
-            ast::Statement::YieldReturn(_) => panic!("Coroutines are deprecated"),
+            ast::Statement::Discard(discard) => {
+                self.expression(discard.get_value());
+            }
+            ast::Statement::Defer(defer) => {
+                let (_, defers) = self.defers.last_mut().expect("Defer outside of a scope");
+                defers.push(defer.get_value().clone());
+            }
+            ast::Statement::YieldReturn(yr) => self.yield_return(yr),
+            ast::Statement::Break(b) => self.leave_loop(b.get_label(), false, b.context().span()),
+            ast::Statement::Continue(c) => self.leave_loop(c.get_label(), true, c.context().span()),
+            ast::Statement::Return(ret) if self.resume_points.is_some() => {
+                self.coroutine_return(ret)
+            }
             ast::Statement::Return(ret) => self.ret(ret),
         }
     }
 
+    /// Suspends a coroutine and gives the value of `yr` to the caller of `yield`.  The next
+    /// time that the coroutine is resumed, it continues from the following statement.
+    fn yield_return(&mut self, yr: &YieldReturn<SemanticContext>) {
+        if let Some(val) = yr.get_value() {
+            let v = self.expression(val);
+            if val.get_type() != Type::Never {
+                self.mir
+                    .store(LValue::ReturnPointer, RValue::Use(v), val.context().span());
+            }
+        }
+
+        if !self.mir.is_terminated() {
+            let resume_bb = self.mir.new_bb();
+            self.suspend(resume_bb, false, yr.context().span());
+            self.mir.set_bb(resume_bb);
+        }
+    }
+
+    /// Returns from a coroutine and marks it as done.  Resuming a coroutine which is done
+    /// evaluates its return statement again, so the return statement starts a new block
+    /// which the coroutine resumes from.
+    fn coroutine_return(&mut self, ret: &Return<SemanticContext>) {
+        let span = ret.context().span();
+        let ret_bb = self.mir.new_bb();
+        self.mir.term_goto(ret_bb, span_begin(span));
+        self.mir.set_bb(ret_bb);
+
+        if let Some(val) = ret.get_value() {
+            let v = self.expression(val);
+            if val.get_type() != Type::Never {
+                self.mir
+                    .store(LValue::ReturnPointer, RValue::Use(v), val.context().span());
+            }
+        }
+
+        if !self.mir.is_terminated() {
+            self.run_all_defers();
+            self.suspend(ret_bb, true, span);
+        }
+    }
+
+    /// Returns from a coroutine after storing, in its frame, that it resumes from
+    /// `resume_bb` and whether it is `done`.
+    fn suspend(&mut self, resume_bb: BasicBlockId, done: bool, span: Span) {
+        let resume_points = self
+            .resume_points
+            .as_mut()
+            .expect("yret outside of a coroutine");
+        resume_points.push(resume_bb);
+        let state = resume_points.len() as u32 - 1;
+
+        let u32_ty = self.find_type(&Type::U32);
+        let state_loc = self.mir.frame_field(self.frame(), STATE, u32_ty);
+        let state = self.mir.const_u32(state);
+        self.mir.store(state_loc, RValue::Use(state), span);
+
+        if done {
+            let bool_ty = self.find_type(&Type::Bool);
+            let done_loc = self.mir.frame_field(self.frame(), DONE, bool_ty);
+            let done = self.mir.const_bool(true);
+            self.mir.store(done_loc, RValue::Use(done), span);
+        }
+
+        self.mir.term_return(span);
+    }
+
+    /// Jumps out of the innermost loop with the given label, or the innermost loop if
+    /// there is no label, to either the condition of the loop (`continue`) or the block
+    /// which follows it (`break`).
+    fn leave_loop(&mut self, label: Option<StringId>, to_cond: bool, span: Span) {
+        let lp = self
+            .mir
+            .find_loop(label)
+            .expect("break or continue outside of a loop");
+        let target = if to_cond { lp.cond_bb } else { lp.exit_bb };
+
+        // Leaving the loop exits every scope within it, from the innermost out
+        let loop_scope = lp.scope;
+        let outside = self
+            .defers
+            .iter()
+            .rposition(|(scope, _)| *scope == loop_scope)
+            .map_or(0, |depth| depth + 1);
+        for depth in (outside..self.defers.len()).rev() {
+            self.run_defers(depth);
+        }
+        self.mir.term_goto(target, span);
+    }
+
+    /// Code which follows a return is unreachable, but it still needs a [`BasicBlock`] to
+    /// be placed in. So, if the current [`BasicBlock`] has been terminated, this starts a
+    /// new one.
+    fn skip_terminator(&mut self) {
+        if self.mir.is_terminated() {
+            let bb = self.mir.new_bb();
+            self.mir.set_bb(bb);
+        }
+    }
+
     fn bind(&mut self, bind: &Bind<SemanticContext>) {
         debug!("Binding statement");
         let var = bind.get_id();
@@ -83,8 +398,11 @@ impl<'a> FuncTransformer<'a> {
 
         let expr = self.expression(bind.get_rhs());
 
-        self.mir
-            .store(LValue::Var(vid), RValue::Use(expr), bind.context().span())
+        // An expression of type never diverges, so there is no value to store
+        if bind.get_rhs().get_type() != Type::Never {
+            self.mir
+                .store(LValue::Var(vid), RValue::Use(expr), bind.context().span())
+        }
     }
 
     fn mutate(&mut self, mutate: &Mutate<SemanticContext>) {
@@ -94,22 +412,64 @@ impl<'a> FuncTransformer<'a> {
             .into_lvalue()
             .expect("LHS of a mutate must be an addressable expression");
         let rhs = self.expression(mutate.get_rhs());
-        self.mir
-            .store(lhs, RValue::Use(rhs), mutate.context().span());
+        if mutate.get_rhs().get_type() != Type::Never {
+            self.mir
+                .store(lhs, RValue::Use(rhs), mutate.context().span());
+        }
     }
 
     fn ret(&mut self, ret: &Return<SemanticContext>) {
         match ret.get_value() {
             Some(val) => {
                 let v = self.expression(val);
-                self.mir
-                    .store(LValue::ReturnPointer, RValue::Use(v), val.context().span());
+                if val.get_type() != Type::Never {
+                    self.mir
+                        .store(LValue::ReturnPointer, RValue::Use(v), val.context().span());
+                }
             }
             None => (),
         };
+
+        if !self.mir.is_terminated() {
+            self.run_all_defers();
+        }
         self.mir.term_return(ret.context().span());
     }
 
+    /// Returning exits every open scope, so this evaluates the expressions deferred within
+    /// every scope, from the innermost out.
+    fn run_all_defers(&mut self) {
+        for depth in (0..self.defers.len()).rev() {
+            self.run_defers(depth);
+        }
+    }
+
+    /// Starts collecting the expressions which are deferred within a new scope.
+    fn open_defer_scope(&mut self) {
+        self.defers.push((self.mir.current_scope(), vec![]));
+    }
+
+    /// Exits the innermost scope by evaluating the expressions that were deferred within
+    /// it, unless the end of the scope cannot be reached.
+    fn close_defer_scope(&mut self) {
+        if !self.mir.is_terminated() {
+            self.run_defers(self.defers.len() - 1);
+        }
+        self.defers.pop();
+    }
+
+    /// Evaluates the expressions deferred within the scope at `depth`, the most recently
+    /// deferred first, in the scope which they were deferred in.
+    fn run_defers(&mut self, depth: usize) {
+        let (scope, defers) = self.defers[depth].clone();
+        let current = self.mir.current_scope();
+        self.mir.set_scope(scope);
+        for exp in defers.iter().rev() {
+            self.expression(exp);
+        }
+        self.mir.set_scope(current);
+    }
+
     /// This can return either an Operand or an RValue, if this is evaluating a constant or an identifier
     /// then this returns an operand.  If this is evaluating an operation then it returns an RValue.
     fn expression(&mut self, expr: &Expression<SemanticContext>) -> Operand {
@@ -125,6 +485,7 @@ impl<'a> FuncTransformer<'a> {
             Expression::U64(_, u) => self.mir.const_u64(*u),
             Expression::F64(_, f) => self.mir.const_f64(*f),
             Expression::Null(_) => self.mir.const_null(),
+            Expression::OptionNone(ctx) => self.tagged(ctx, false, None),
             Expression::Boolean(_, b) => self.mir.const_bool(*b),
             Expression::StringLiteral(_, sid) => {
                 // If it exists Get static definition of the string literal
@@ -160,36 +521,54 @@ impl<'a> FuncTransformer<'a> {
             }
             Expression::CustomType(_, _) => panic!("Cannot be an expression"),
             Expression::Path(_, _) => panic!("Cannot be an expression"),
+            Expression::FieldsOf(..) | Expression::NameOf(..) => {
+                panic!("Reflection builtins must be evaluated before MIR is generated")
+            }
+            Expression::MacroCall(..) => panic!("Macros must be expanded before MIR is generated"),
             Expression::IdentifierDeclare(_, _, _) => panic!("Cannot be an expression"),
-            Expression::RoutineCall(ctx, call, target, args) => {
-                self.fn_call(ctx, *call, target, args)
+            Expression::RoutineCall(ctx, RoutineCall::CoroutineInit, target, args) => {
+                self.coroutine_init(ctx, target, args)
             }
+            Expression::RoutineCall(ctx, _, target, args) => self.fn_call(ctx, target, args),
             Expression::StructExpression(ctx, ty, fields) => self.stuct_expr(ctx, ty, fields),
             Expression::If {
                 context,
                 cond,
                 if_arm,
                 else_arm,
-            } => self.if_expr(cond, if_arm, else_arm),
+            } => self.if_expr(context, cond, if_arm, else_arm),
             Expression::While {
                 context,
+                label,
                 cond,
                 body,
-            } => self.while_expr(cond, body, context.span()),
-            Expression::ExpressionBlock(_, block, expr) => {
+            } => self.while_expr(*label, cond, body, context.span()),
+            Expression::ExpressionBlock(ctx, block, expr) => {
                 self.mir.start_scope();
+                self.open_defer_scope();
                 for stm in block {
                     self.statement(stm);
                 }
-                let result = if let Some(expr) = expr {
+                let mut result = if let Some(expr) = expr {
+                    self.skip_terminator();
                     self.expression(expr)
                 } else {
                     Operand::Constant(Constant::Unit)
                 };
+
+                // Copy the value of the block, so that a deferred expression cannot change it
+                let deferred = !self.defers.last().unwrap().1.is_empty();
+                if deferred && !self.mir.is_terminated() {
+                    if let Operand::LValue(_) = result {
+                        let ty = self.find_type(ctx.ty());
+                        result = self.mir.temp_store(RValue::Use(result), ty, ctx.span());
+                    }
+                }
+                self.close_defer_scope();
                 self.mir.close_scope();
                 result
             }
-            Expression::Yield(_, _) => panic!("Coroutines are deprecated"),
+            Expression::Yield(ctx, co) => self.yield_expr(ctx, co),
         }
     }
 
@@ -224,7 +603,11 @@ impl<'a> FuncTransformer<'a> {
             .expect("Trying to access a field on a non-structure type")
             .clone();
 
-        // Evaluate each expression and store into the associated temp field
+        // Evaluate each expression and store into the associated temp field.  Field
+        // initializers are evaluated in the order they are written in the source code, not
+        // the order of the fields in the structure definition, and each value is stored
+        // before the next initializer is evaluated.  So, if an initializer has side effects
+        // then those side effects will be observed by every initializer after it.
         for (field, val) in expr {
             let result = self.expression(val);
             let field_loc = self.mir.member_access(LValue::Temp(temp), &def, *field);
@@ -257,17 +640,13 @@ impl<'a> FuncTransformer<'a> {
     fn fn_call(
         &mut self,
         ctx: &SemanticContext,
-        call: RoutineCall,
         target: &Path,
         args: &[Expression<SemanticContext>],
     ) -> Operand {
-        let fn_id = if call == RoutineCall::Extern {
-            let extern_path: Path = vec![Element::Id(target.item().unwrap())].into();
-            self.project.find_def(&extern_path)
-        } else {
-            self.project.find_def(target)
-        }
-        .unwrap_or_else(|| panic!("Target function not found: {}", target));
+        let fn_id = self
+            .project
+            .find_def(target)
+            .unwrap_or_else(|| panic!("Target function not found: {}", target));
 
         // Compute the value of each argument
         let args: Vec<_> = args.iter().map(|a| self.expression(a)).collect();
@@ -299,8 +678,118 @@ impl<'a> FuncTransformer<'a> {
         Operand::LValue(LValue::Temp(result))
     }
 
+    /// Creates an instance of the coroutine `target`, whose frame holds the arguments, that
+    /// runs from the start of the coroutine when it is first resumed.
+    fn coroutine_init(
+        &mut self,
+        ctx: &SemanticContext,
+        target: &Path,
+        args: &[Expression<SemanticContext>],
+    ) -> Operand {
+        let span = ctx.span();
+        let frame_ty = self.find_type(ctx.ty());
+        let frame = LValue::Temp(self.mir.temp(frame_ty, span));
+        let routine = self.coroutines.routine(target);
+        let (number, params) = (routine.number(), routine.params().to_vec());
+
+        // Compute the value of each argument and store it in the frame
+        for (arg, (field, ty)) in args.iter().zip(params) {
+            let value = self.expression(arg);
+            let loc = self.mir.frame_field(frame.clone(), field, ty);
+            self.mir
+                .store(loc, RValue::Use(value), arg.context().span());
+        }
+
+        let u32_ty = self.find_type(&Type::U32);
+        let bool_ty = self.find_type(&Type::Bool);
+        let header = [
+            (ROUTINE, u32_ty, self.mir.const_u32(number)),
+            (STATE, u32_ty, self.mir.const_u32(0)),
+            (DONE, bool_ty, self.mir.const_bool(false)),
+        ];
+        for (field, ty, value) in header {
+            let loc = self.mir.frame_field(frame.clone(), field, ty);
+            self.mir.store(loc, RValue::Use(value), span);
+        }
+
+        Operand::LValue(frame)
+    }
+
+    /// Resumes the coroutine instance `co` and evaluates to the value which it yields.
+    fn yield_expr(&mut self, ctx: &SemanticContext, co: &Expression<SemanticContext>) -> Operand {
+        let span = ctx.span();
+        let instance = self
+            .expression(co)
+            .into_lvalue()
+            .expect("A coroutine instance must resolve to a Location Expression");
+
+        // The resume procedure is given the address of the frame, so that it can update it
+        let frame_ptr = self.frame_ptr_type(ctx.ty());
+        let frame_ptr = self
+            .mir
+            .temp_store(RValue::AddressOf(instance.clone()), frame_ptr, span);
+        let result = self.mir.temp(self.find_type(ctx.ty()), span);
+
+        // The instance may have been created by any coroutine which yields the same type, so
+        // the resume procedure is chosen by the number in the instance's frame
+        let resume_fns: Vec<_> = self
+            .coroutines
+            .frame(ctx.ty())
+            .routines()
+            .iter()
+            .map(|path| {
+                self.project
+                    .find_def(path)
+                    .unwrap_or_else(|| panic!("Coroutine not found: {}", path))
+            })
+            .collect();
+        let call_bbs: Vec<_> = resume_fns.iter().map(|_| self.mir.new_bb()).collect();
+        let reentry_bb = self.mir.new_bb();
+
+        let u32_ty = self.find_type(&Type::U32);
+        let bool_ty = self.find_type(&Type::Bool);
+        let routine = Operand::LValue(self.mir.frame_field(instance, ROUTINE, u32_ty));
+        for (number, call_bb) in call_bbs.iter().enumerate() {
+            if number == call_bbs.len() - 1 {
+                self.mir.term_goto(*call_bb, span);
+            } else {
+                let is_routine = self
+                    .mir
+                    .eq(routine.clone(), self.mir.const_u32(number as u32));
+                let is_routine = self.mir.temp_store(is_routine, bool_ty, span);
+                let next_bb = self.mir.new_bb();
+                self.mir.term_cond_goto(is_routine, *call_bb, next_bb, span);
+                self.mir.set_bb(next_bb);
+            }
+        }
+
+        let args = vec![frame_ptr];
+        for (resume_fn, call_bb) in resume_fns.into_iter().zip(call_bbs) {
+            self.mir.set_bb(call_bb);
+            self.mir.term_call(
+                Operand::LValue(LValue::Static(resume_fn)),
+                &args,
+                (LValue::Temp(result), reentry_bb),
+                span,
+            );
+        }
+
+        self.mir.set_bb(reentry_bb);
+        Operand::LValue(LValue::Temp(result))
+    }
+
     /// Creates a member access operand which can be used in a statement or terminator
     fn member_access(&mut self, base: &Expression<SemanticContext>, field: StringId) -> Operand {
+        // The only member of a coroutine instance is `done`, which is a field of its frame
+        if let Type::Coroutine(_) = base.context().ty() {
+            let base_mir = self
+                .expression(base)
+                .into_lvalue()
+                .expect("A coroutine instance must resolve to a Location Expression");
+            let bool_ty = self.find_type(&Type::Bool);
+            return Operand::LValue(self.mir.frame_field(base_mir, DONE, bool_ty));
+        }
+
         // Get the Index of the Field and convert to a `FieldId`
         let ty = base.context().ty();
         let mir_ty = self
@@ -373,6 +862,7 @@ impl<'a> FuncTransformer<'a> {
 
     fn while_expr(
         &mut self,
+        label: Option<StringId>,
         cond: &Expression<SemanticContext>,
         body: &Expression<SemanticContext>,
         span: Span,
@@ -396,8 +886,14 @@ impl<'a> FuncTransformer<'a> {
 
         // Construct the while loop body BB
         self.mir.set_bb(body_bb);
+        self.mir.enter_loop(label, cond_bb, exit_bb);
         self.expression(body); // While loops always resolve to Unit value, so ignore the result of this expression
-        self.mir.term_goto(cond_bb, span_end(body.context().span()));
+        self.mir.exit_loop();
+
+        // If the body diverged (e.g. with a `return`) then it has already left the loop
+        if !self.mir.is_terminated() {
+            self.mir.term_goto(cond_bb, span_end(body.context().span()));
+        }
 
         // Set the exit_bb as the current BB to continue constructing the MIR after the while loop
         self.mir.set_bb(exit_bb);
@@ -407,6 +903,7 @@ impl<'a> FuncTransformer<'a> {
 
     fn if_expr(
         &mut self,
+        ctx: &SemanticContext,
         cond: &Expression<SemanticContext>,
         then_block: &Expression<SemanticContext>,
         else_block: &Option<Box<Expression<SemanticContext>>>,
@@ -430,41 +927,20 @@ impl<'a> FuncTransformer<'a> {
 
         // Only create a temp location if this If Expression can resolve to a
         // value
-        let result = if else_block.is_some() && then_block.get_type() != Type::Unit {
-            let then_ty = self.find_type(then_block.get_type());
-            Some(self.mir.temp(then_ty, then_block.context().span()))
+        let result = if ctx.ty() != Type::Unit && ctx.ty() != Type::Never {
+            let ty = self.find_type(ctx.ty());
+            Some(self.mir.temp(ty, ctx.span()))
         } else {
             None
         };
 
         self.mir.set_bb(then_bb);
-        let val = self.expression(then_block);
-        if let Some(t) = result {
-            self.mir.store(
-                LValue::Temp(t),
-                RValue::Use(val),
-                then_block.context().span(),
-            )
-        }
-
-        self.mir
-            .term_goto(merge_bb, span_end(then_block.context().span()));
+        self.if_arm(then_block, result, merge_bb);
 
         // If there is an else block, then construct it
         if let Some((else_block, else_bb)) = else_bb {
             self.mir.set_bb(else_bb);
-            let val = self.expression(else_block);
-
-            if let Some(t) = result {
-                self.mir.store(
-                    LValue::Temp(t),
-                    RValue::Use(val),
-                    else_block.context().span(),
-                )
-            }
-
-            self.mir
-                .term_goto(merge_bb, span_end(else_block.context().span()));
+            self.if_arm(else_block, result, merge_bb);
         }
 
         self.mir.set_bb(merge_bb);
@@ -474,13 +950,44 @@ impl<'a> FuncTransformer<'a> {
         }
     }
 
+    /// Evaluates one arm of an if expression, stores its value in `result` (if the if expression
+    /// has a value), and then goes to `merge_bb`.  If the arm diverges (e.g. because it contains a
+    /// `return` statement) then its [`BasicBlock`] has already been terminated and is left as is.
+    /// An arm of type never, which has not terminated its [`BasicBlock`], is unreachable and has no
+    /// value to store.
+    fn if_arm(
+        &mut self,
+        arm: &Expression<SemanticContext>,
+        result: Option<TempId>,
+        merge_bb: BasicBlockId,
+    ) {
+        let val = self.expression(arm);
+        if self.mir.is_terminated() {
+            return;
+        }
+
+        if let Some(t) = result {
+            if arm.get_type() != Type::Never {
+                self.mir
+                    .store(LValue::Temp(t), RValue::Use(val), arm.context().span())
+            }
+        }
+
+        self.mir.term_goto(merge_bb, span_end(arm.context().span()));
+    }
+
     fn unary_op(
         &mut self,
         ctx: &SemanticContext,
         op: UnaryOperator,
         right: &Expression<SemanticContext>,
     ) -> Operand {
-        let is_float = right.context().ty().is_float();
+        let right_ty = right.context().ty();
+        let is_float = right_ty.is_float();
+        let array_len = match right_ty {
+            Type::Array(_, len) => Some(*len),
+            _ => None,
+        };
         let right = self.expression(right);
         match op {
             UnaryOperator::Negate => {
@@ -515,6 +1022,195 @@ impl<'a> FuncTransformer<'a> {
                     panic!("Deref can only be applied to LValues")
                 }
             }
+            UnaryOperator::Length => match array_len {
+                // The length of an array is known at compile time
+                Some(len) => self.mir.const_u64(len as u64),
+                None => {
+                    self.unsupported(ctx.span(), UnsupportedError::Length(right_ty.clone()));
+                    self.mir.const_u64(0)
+                }
+            },
+            UnaryOperator::OptionSome | UnaryOperator::ResultOk => {
+                self.tagged(ctx, true, Some((FieldId::VALUE, right)))
+            }
+            UnaryOperator::ResultErr => self.tagged(ctx, false, Some((FieldId::ERR, right))),
+            UnaryOperator::OptionIsSome | UnaryOperator::ResultIsOk => {
+                self.unwrap(right, right_ty, FieldId::TAG)
+            }
+            UnaryOperator::OptionUnwrap | UnaryOperator::ResultUnwrapOk => {
+                self.unwrap(right, right_ty, FieldId::VALUE)
+            }
+            UnaryOperator::ResultUnwrapErr => self.unwrap(right, right_ty, FieldId::ERR),
+            UnaryOperator::Try => self.try_op(ctx, right, right_ty),
+            UnaryOperator::ToLeBytes | UnaryOperator::ToBeBytes => {
+                let big_endian = op == UnaryOperator::ToBeBytes;
+                self.to_bytes(ctx, right, right_ty, big_endian)
+            }
+            UnaryOperator::FromLeBytes | UnaryOperator::FromBeBytes => {
+                let len = array_len.expect("Expected a byte array");
+                let big_endian = op == UnaryOperator::FromBeBytes;
+                self.from_bytes(ctx, right, len, big_endian)
+            }
+        }
+    }
+
+    /// Stores a new option or result, of the type of `ctx`, in a temporary location.  `tag`
+    /// is `true` if it holds a value or is ok, and `value` is the field which holds the
+    /// value, ok, or err and what is stored in it, if there is one.
+    fn tagged(
+        &mut self,
+        ctx: &SemanticContext,
+        tag: bool,
+        value: Option<(FieldId, Operand)>,
+    ) -> Operand {
+        let span = ctx.span();
+        let ty = self.find_type(ctx.ty());
+        let temp = LValue::Temp(self.mir.temp(ty, span));
+
+        let tag_loc = self.tagged_field(temp.clone(), ctx.ty(), FieldId::TAG);
+        let tag = self.mir.const_bool(tag);
+        self.mir.store(tag_loc, RValue::Use(tag), span);
+
+        if let Some((field, value)) = value {
+            let value_loc = self.tagged_field(temp.clone(), ctx.ty(), field);
+            self.mir.store(value_loc, RValue::Use(value), span);
+        }
+
+        Operand::LValue(temp)
+    }
+
+    /// Reads the given field of `operand`, an option or result of type `ty`.
+    fn unwrap(&mut self, operand: Operand, ty: &Type, field: FieldId) -> Operand {
+        let base = operand
+            .into_lvalue()
+            .expect("Options and results must resolve to Location Expressions");
+        Operand::LValue(self.tagged_field(base, ty, field))
+    }
+
+    /// Returns the location of the given field of `base`, an option or result of type `ty`.
+    fn tagged_field(&self, base: LValue, ty: &Type, field: FieldId) -> LValue {
+        let field_ty = match ty {
+            _ if field == FieldId::TAG => &Type::Bool,
+            Type::Option(value) | Type::Result(value, _) if field == FieldId::VALUE => value,
+            Type::Result(_, err) if field == FieldId::ERR => err,
+            _ => panic!("Expected an option or result but found {}", ty),
+        };
+        let field_ty = self.find_type(field_ty);
+        self.mir.tagged_field(base, field, field_ty)
+    }
+
+    /// Evaluates `operand?`, where `operand` is an option or result of type `ty`.  If it
+    /// holds a value, or is ok, then that is the value of the expression.  Otherwise, the
+    /// routine returns `none`, or the err of `operand`, after evaluating every deferred
+    /// expression, like a `return` would.
+    fn try_op(&mut self, ctx: &SemanticContext, operand: Operand, ty: &Type) -> Operand {
+        let span = ctx.span();
+        let operand = operand
+            .into_lvalue()
+            .expect("Options and results must resolve to Location Expressions");
+        let ok_bb = self.mir.new_bb();
+        let fail_bb = self.mir.new_bb();
+
+        let tag = self.tagged_field(operand.clone(), ty, FieldId::TAG);
+        self.mir
+            .term_cond_goto(Operand::LValue(tag), ok_bb, fail_bb, span);
+
+        // The return type of the routine has the same tag and err as `ty`, so their
+        // fields can be found with `ty`
+        self.mir.set_bb(fail_bb);
+        let ret_tag = self.tagged_field(LValue::ReturnPointer, ty, FieldId::TAG);
+        let none = self.mir.const_bool(false);
+        self.mir.store(ret_tag, RValue::Use(none), span);
+        if let Type::Result(..) = ty {
+            let err = self.tagged_field(operand.clone(), ty, FieldId::ERR);
+            let ret_err = self.tagged_field(LValue::ReturnPointer, ty, FieldId::ERR);
+            self.mir
+                .store(ret_err, RValue::Use(Operand::LValue(err)), span);
+        }
+        self.run_all_defers();
+        self.mir.term_return(span);
+
+        self.mir.set_bb(ok_bb);
+        Operand::LValue(self.tagged_field(operand, ty, FieldId::VALUE))
+    }
+
+    /// Stores the bytes of the integer `value` in a temporary array, most significant
+    /// first if `big_endian` is `true` and least significant first otherwise.
+    fn to_bytes(
+        &mut self,
+        ctx: &SemanticContext,
+        value: Operand,
+        value_ty: &Type,
+        big_endian: bool,
+    ) -> Operand {
+        let span = ctx.span();
+        let int_ty = self.find_type(value_ty);
+        let u8_ty = self.find_type(&Type::U8);
+
+        let value = if big_endian {
+            let swapped = self.mir.byte_swap(value);
+            self.mir.temp_store(swapped, int_ty, span)
+        } else {
+            value
+        };
+
+        // Byte `idx` of the array is the value shifted right by `idx` bytes
+        let bytes_ty = self.find_type(ctx.ty());
+        let bytes = LValue::Temp(self.mir.temp(bytes_ty, span));
+        for idx in 0..value_ty.bit_width() / 8 {
+            let shift = self.const_int(value_ty, 8 * idx);
+            let shifted = self.mir.lshr(value.clone(), shift);
+            let shifted = self.mir.temp_store(shifted, int_ty, span);
+
+            let idx_mir = self.mir.const_i64(idx as i64);
+            let el_loc = self.mir.array_at(bytes.clone(), idx_mir);
+            let byte = self.mir.cast(shifted, int_ty, u8_ty);
+            self.mir.store(el_loc, byte, span);
+        }
+
+        Operand::LValue(bytes)
+    }
+
+    /// Combines the `len` bytes of the array `bytes` into an unsigned integer, reading
+    /// them as most significant first if `big_endian` is `true` and least significant
+    /// first otherwise.
+    fn from_bytes(
+        &mut self,
+        ctx: &SemanticContext,
+        bytes: Operand,
+        len: usize,
+        big_endian: bool,
+    ) -> Operand {
+        let span = ctx.span();
+        let int_ty = self.find_type(ctx.ty());
+        let u8_ty = self.find_type(&Type::U8);
+        let bytes = match bytes {
+            Operand::LValue(lv) => lv,
+            // Arrays are always stored in memory, so this is a bug in the compiler
+            _ => panic!("Byte arrays must resolve to Location Expressions"),
+        };
+
+        // Each byte is shifted left into its place and combined with the others
+        let mut value = self.const_int(ctx.ty(), 0);
+        for idx in 0..len {
+            let idx_mir = self.mir.const_i64(idx as i64);
+            let el = Operand::LValue(self.mir.array_at(bytes.clone(), idx_mir));
+            let byte = self.mir.cast(el, u8_ty, int_ty);
+            let byte = self.mir.temp_store(byte, int_ty, span);
+
+            let shift = self.const_int(ctx.ty(), 8 * idx as u8);
+            let shifted = self.mir.shl(byte, shift);
+            let shifted = self.mir.temp_store(shifted, int_ty, span);
+
+            let combined = self.mir.bitwise_or(value, shifted);
+            value = self.mir.temp_store(combined, int_ty, span);
+        }
+
+        if big_endian {
+            let swapped = self.mir.byte_swap(value);
+            self.mir.temp_store(swapped, int_ty, span)
+        } else {
+            value
         }
     }
 
@@ -567,6 +1263,41 @@ impl<'a> FuncTransformer<'a> {
                     self.mir.div(left, right)
                 }
             }
+            // Integer arithmetic in the MIR wraps on overflow, so the explicit wrapping
+            // operations lower to the ordinary arithmetic operations.
+            BinaryOperator::WrappingAdd => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                self.mir.add(left, right)
+            }
+            BinaryOperator::WrappingSub => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                self.mir.sub(left, right)
+            }
+            BinaryOperator::WrappingMul => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                self.mir.mul(left, right)
+            }
+            BinaryOperator::SaturatingAdd => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                if ctx.ty().is_unsigned_int() {
+                    self.mir.ui_sat_add(left, right)
+                } else {
+                    self.mir.sat_add(left, right)
+                }
+            }
+            BinaryOperator::SaturatingSub => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                if ctx.ty().is_unsigned_int() {
+                    self.mir.ui_sat_sub(left, right)
+                } else {
+                    self.mir.sat_sub(left, right)
+                }
+            }
             BinaryOperator::BAnd => {
                 let left = self.expression(left);
                 let right = self.expression(right);
@@ -644,6 +1375,25 @@ impl<'a> FuncTransformer<'a> {
                 let right = self.expression(right);
                 self.mir.offset(left, right)
             }
+            BinaryOperator::Range => {
+                self.unsupported(ctx.span(), UnsupportedError::Type(ctx.ty().clone()));
+                RValue::Use(self.mir.const_u64(0))
+            }
+        }
+    }
+
+    /// Returns the constant `i` as a value of the fixed width integer type `ty`.
+    fn const_int(&self, ty: &Type, i: u8) -> Operand {
+        match ty {
+            Type::U8 => self.mir.const_u8(i),
+            Type::U16 => self.mir.const_u16(i as u16),
+            Type::U32 => self.mir.const_u32(i as u32),
+            Type::U64 => self.mir.const_u64(i as u64),
+            Type::I8 => self.mir.const_i8(i as i8),
+            Type::I16 => self.mir.const_i16(i as i16),
+            Type::I32 => self.mir.const_i32(i as i32),
+            Type::I64 => self.mir.const_i64(i as i64),
+            _ => panic!("Expected a fixed width integer but found {}", ty),
         }
     }
 
@@ -1,8 +1,9 @@
 //! This handles transforming the Bramble AST into its MIR representation.
 
+mod coroutine;
 mod error;
 mod function;
 mod module;
 
-pub use error::TransformError;
+pub use error::{TransformError, UnsupportedError};
 pub use module::transform;
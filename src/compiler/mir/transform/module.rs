@@ -14,18 +14,23 @@ use crate::{
         ast::*,
         import::{Import, ImportRoutineDef},
         mir::ir::{ArgDecl, Procedure},
-        semantics::semanticnode::SemanticContext,
-        Span,
+        semantics::{semanticnode::SemanticContext, typed::TypedModule},
+        CompilerError, Span,
     },
     StringId,
 };
 
-use super::{super::project::MirProject, function::FuncTransformer, TransformError};
+use super::{
+    super::project::MirProject,
+    coroutine::{self, Coroutines},
+    function::FuncTransformer,
+    TransformError, UnsupportedError,
+};
 
-/// Transform a [`Module`] into its MIR representation and add all items to the
+/// Transform a [`TypedModule`] into its MIR representation and add all items to the
 /// given [`MirProject`].
 pub fn transform(
-    module: &Module<SemanticContext>,
+    module: &TypedModule,
     imports: &[Import],
     project: &mut MirProject,
 ) -> Result<(), TransformError> {
@@ -36,11 +41,19 @@ pub fn transform(
         add_import_declarations(project, im)?;
     }
 
+    // A coroutine type is laid out as the frame of the coroutines which yield it, so the
+    // frames are declared before any types are added
+    let mut coroutines = Coroutines::declare(project, module)?;
+
     // Add all the types in this module
     add_module_items(project, module)?;
+    coroutines.add_declarations(project, module)?;
 
     // Lower the AST to its MIR form
-    transform_fns(project, module)?;
+    transform_fns(project, &mut coroutines, module)?;
+
+    // Lowering the coroutines added their variables to the frames
+    coroutines.define_frames(project)?;
 
     Ok(())
 }
@@ -51,6 +64,9 @@ fn add_module_items(
 ) -> Result<(), TransformError> {
     debug!("Adding Module items to MIR");
 
+    // Types which the MIR cannot represent are rejected before any are added to the table
+    check_types_supported(module)?;
+
     // Add all the types in this module
     add_struct_defs_to_typetable(project, module)?;
     add_types_to_typetable(project, module)?;
@@ -69,13 +85,41 @@ fn add_types_to_typetable(
     project: &mut MirProject,
     module: &Module<SemanticContext>,
 ) -> Result<(), TransformError> {
+    // Expressions of type never do not produce a value and so have no type in MIR
     PostOrderIter::new(module)
+        .filter(|n| n.context().ty() != Type::Never)
         .map(|n| project.add_type(n.context().ty()))
         .collect::<Result<Vec<_>, _>>()?;
 
     Ok(())
 }
 
+/// Returns an error for the first node in the module whose type cannot be represented
+/// in the MIR yet.
+fn check_types_supported(module: &Module<SemanticContext>) -> Result<(), TransformError> {
+    match PostOrderIter::new(module).find(|n| !is_supported(n.context().ty())) {
+        Some(n) => Err(TransformError::Unsupported(CompilerError::new(
+            n.context().span(),
+            UnsupportedError::Type(n.context().ty().clone()),
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Returns `true` if the given type, and every type that it contains, can be
+/// represented in the MIR.
+fn is_supported(ty: &Type) -> bool {
+    match ty {
+        Type::String | Type::Slice(_) | Type::Range(_) => false,
+        Type::Array(el, _) => is_supported(el),
+        Type::RawPointer(_, target) => is_supported(target),
+        Type::Option(inner) => is_supported(inner),
+        Type::Result(ok, err) => is_supported(ok) && is_supported(err),
+        Type::Coroutine(inner) => is_supported(inner),
+        _ => true,
+    }
+}
+
 fn add_struct_defs_to_typetable(
     project: &mut MirProject,
     module: &Module<SemanticContext>,
@@ -128,6 +172,7 @@ fn add_extern_declarations(
 
         let p = Procedure::new_extern(
             e.context().canonical_path(),
+            Some(e.link_name()),
             args,
             e.has_varargs,
             ret_ty,
@@ -165,10 +210,10 @@ fn add_import_function(
         .params()
         .iter()
         .map(|p| {
-            //    iterate through each param and convert the type to a TypeId
-            let ty = project
-                .find_type(&p.1)
-                .ok_or(TransformError::TypeNotFound)?;
+            //    iterate through each param and convert the type to a TypeId.  An imported
+            //    routine may use an option or result which no local expression has, so
+            //    the type is added if it is not already in the table.
+            let ty = project.add_type(&p.1)?;
 
             //    Create a name for the parameter (names are not included in the manifeset :O )
             //    Generate an ArgDecl
@@ -177,12 +222,10 @@ fn add_import_function(
         .collect::<Result<Vec<_>, TransformError>>()?;
 
     // Conver the return type to a TypeId
-    let ret_ty = project
-        .find_type(&f.ty())
-        .ok_or(TransformError::TypeNotFound)?;
+    let ret_ty = project.add_type(&f.ty())?;
 
     // Create a Procedure
-    let p = Procedure::new_extern(f.path(), args, false, ret_ty, Span::zero());
+    let p = Procedure::new_extern(f.path(), None, args, false, ret_ty, Span::zero());
 
     // Add procedure to project
     project.add_func(p)?;
@@ -195,13 +238,17 @@ fn add_fn_declarations(
 ) -> Result<(), TransformError> {
     debug!("Adding function declarations");
 
-    let funcs = module.get_functions().iter().filter_map(|f| {
-        if let Item::Routine(r) = f {
-            Some(r)
-        } else {
-            None
-        }
-    });
+    let funcs = module
+        .get_functions()
+        .iter()
+        .filter_map(|f| {
+            if let Item::Routine(r) = f {
+                Some(r)
+            } else {
+                None
+            }
+        })
+        .chain(module.get_methods());
 
     for f in funcs {
         // convert args into MIR args
@@ -239,27 +286,39 @@ fn create_fn_declaration(f: &RoutineDef<SemanticContext>, project: &MirProject)
 
 fn transform_fns(
     project: &mut MirProject,
+    coroutines: &mut Coroutines,
     module: &Module<SemanticContext>,
 ) -> Result<(), TransformError> {
     debug!("Transforming functions to MIR");
 
-    let funcs = module.get_functions().iter().filter_map(|f| {
-        if let Item::Routine(r) = f {
-            Some(r)
-        } else {
-            None
-        }
-    });
+    let funcs = module
+        .get_functions()
+        .iter()
+        .filter_map(|f| {
+            if let Item::Routine(r) = f {
+                Some(r)
+            } else {
+                None
+            }
+        })
+        .chain(module.get_methods());
 
     for f in funcs {
-        let ft = FuncTransformer::new(f.context().canonical_path(), project);
-        let p = ft.transform(f);
+        let ft = FuncTransformer::new(f.context().canonical_path(), project, coroutines);
+        let p = ft.transform(f)?;
+        project.add_func(p)?;
+    }
+
+    for co in coroutine::get_coroutines(module) {
+        let ft = FuncTransformer::new(co.context().canonical_path(), project, coroutines);
+        let (p, locals) = ft.transform_coroutine(co)?;
         project.add_func(p)?;
+        coroutines.add_locals(co.context().ty(), locals);
     }
 
     // Repeat for all submodules
     for m in module.get_modules() {
-        transform_fns(project, m)?;
+        transform_fns(project, coroutines, m)?;
     }
 
     Ok(())
@@ -1,5 +1,6 @@
 //! The system for keeping track of and defining types in the Bramble MIR.
 
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
 use crate::{
@@ -13,6 +14,10 @@ use crate::{
 
 pub struct TypeTable {
     table: Vec<MirTypeDef>,
+
+    /// The frame of the coroutines which yield each type.  A coroutine instance of type
+    /// `co T` is laid out as the frame of the coroutines which yield `T`.
+    coroutines: Vec<(Type, TypeId)>,
 }
 
 impl TypeTable {
@@ -31,9 +36,14 @@ impl TypeTable {
             MirTypeDef::Base(MirBaseType::F64),
             MirTypeDef::Base(MirBaseType::Bool),
             MirTypeDef::Base(MirBaseType::StringLiteral),
+            MirTypeDef::Base(MirBaseType::USize),
+            MirTypeDef::Base(MirBaseType::ISize),
         ];
 
-        TypeTable { table }
+        TypeTable {
+            table,
+            coroutines: vec![],
+        }
     }
 
     /// Given a [`TypeId`] this returns the corresponding [`MirTypeDef`].
@@ -68,6 +78,13 @@ impl TypeTable {
                 mutable: *mutable,
                 target: self.add(target)?,
             },
+            Type::Option(inner) => MirTypeDef::Option {
+                ty: self.add(inner)?,
+            },
+            Type::Result(ok, err) => MirTypeDef::Result {
+                ok: self.add(ok)?,
+                err: self.add(err)?,
+            },
             Type::Custom(path) => {
                 if path.is_canonical() {
                     MirTypeDef::Structure {
@@ -78,6 +95,7 @@ impl TypeTable {
                     return Err(TypeTableError::ExpectedCanonicalPath);
                 }
             }
+            Type::Coroutine(_) => return Err(TypeTableError::CoroutineFrameMissing),
             _ => return Err(TypeTableError::BaseTypesMissing),
         };
 
@@ -125,6 +143,45 @@ impl TypeTable {
         }
     }
 
+    /// Declares the frame of the coroutines which yield `yield_ty`, which is a structure
+    /// with the given canonical path.  The frame is [`MirStructDef::Declared`] until
+    /// [`TypeTable::define_coroutine_frame`] is given its fields, because its fields are
+    /// the variables of every coroutine which uses it.
+    pub fn add_coroutine_frame(
+        &mut self,
+        yield_ty: &Type,
+        path: &Path,
+    ) -> Result<TypeId, TypeTableError> {
+        if !path.is_canonical() {
+            return Err(TypeTableError::ExpectedCanonicalPath);
+        }
+
+        self.table.push(MirTypeDef::Structure {
+            path: path.clone(),
+            def: MirStructDef::Declared,
+        });
+        let id = TypeId(self.table.len() as u32 - 1);
+        self.coroutines.push((yield_ty.clone(), id));
+        Ok(id)
+    }
+
+    /// Defines the fields of a coroutine frame which was declared with
+    /// [`TypeTable::add_coroutine_frame`].
+    pub fn define_coroutine_frame(
+        &mut self,
+        id: TypeId,
+        fields: Vec<Field>,
+    ) -> Result<(), TypeTableError> {
+        match self.get_mut(id) {
+            MirTypeDef::Structure { def, .. } if *def == MirStructDef::Declared => {
+                *def = MirStructDef::Defined(fields);
+                Ok(())
+            }
+            MirTypeDef::Structure { .. } => Err(TypeTableError::StrutureAlreadyDefined),
+            _ => Err(TypeTableError::ExpectedStructure),
+        }
+    }
+
     pub fn add_import_struct_def(
         &mut self,
         sd: &ImportStructDef,
@@ -201,6 +258,8 @@ impl TypeTable {
             | Type::I16
             | Type::I32
             | Type::I64
+            | Type::USize
+            | Type::ISize
             | Type::F64
             | Type::Bool
             | Type::StringLiteral => {
@@ -225,6 +284,13 @@ impl TypeTable {
                     target: target_id,
                 }
             }
+            Type::Option(inner) => MirTypeDef::Option {
+                ty: self.find(inner)?,
+            },
+            Type::Result(ok, err) => MirTypeDef::Result {
+                ok: self.find(ok)?,
+                err: self.find(err)?,
+            },
             Type::Custom(path) => {
                 // if ty is a structure, then search for an entry with a matching canonical path
                 MirTypeDef::Structure {
@@ -233,6 +299,14 @@ impl TypeTable {
                 }
             }
 
+            Type::Coroutine(yield_ty) => {
+                return self
+                    .coroutines
+                    .iter()
+                    .find(|(ty, _)| ty == yield_ty.as_ref())
+                    .map(|(_, id)| *id)
+            }
+
             // The presence of this wildcard points indicates that the Type enum has variants that no longer make sense
             // and should be refactored out.
             _ => panic!("Invalid Type: {:?}", ty),
@@ -268,6 +342,8 @@ impl TypeTable {
         match ty {
             MirTypeDef::Array { ty, .. } if *ty >= max_id => return false,
             MirTypeDef::RawPointer { target, .. } if *target >= max_id => return false,
+            MirTypeDef::Option { ty } if *ty >= max_id => return false,
+            MirTypeDef::Result { ok, err } if *ok >= max_id || *err >= max_id => return false,
             MirTypeDef::Structure { def, .. } if *def == MirStructDef::Declared => return false,
             MirTypeDef::Structure {
                 def: MirStructDef::Defined(fields),
@@ -323,6 +399,10 @@ pub enum MirBaseType {
     I16,
     I32,
     I64,
+    /// An unsigned integer as wide as a pointer on the target machine
+    USize,
+    /// A signed integer as wide as a pointer on the target machine
+    ISize,
     F64,
     Bool,
     StringLiteral,
@@ -341,6 +421,8 @@ impl MirBaseType {
             Type::I16 => Self::I16,
             Type::I32 => Self::I32,
             Type::I64 => Self::I64,
+            Type::USize => Self::USize,
+            Type::ISize => Self::ISize,
             Type::F64 => Self::F64,
             Type::Bool => Self::Bool,
             Type::StringLiteral => Self::StringLiteral,
@@ -364,6 +446,8 @@ impl Display for MirBaseType {
             MirBaseType::I16 => f.write_str("i16"),
             MirBaseType::I32 => f.write_str("i32"),
             MirBaseType::I64 => f.write_str("i64"),
+            MirBaseType::USize => f.write_str("usize"),
+            MirBaseType::ISize => f.write_str("isize"),
             MirBaseType::F64 => f.write_str("f64"),
             MirBaseType::Bool => f.write_str("bool"),
             MirBaseType::StringLiteral => f.write_str("string"),
@@ -375,9 +459,31 @@ impl Display for MirBaseType {
 #[derive(Debug, Clone)]
 pub enum MirTypeDef {
     Base(MirBaseType),
-    Array { ty: TypeId, sz: usize },
-    RawPointer { mutable: PointerMut, target: TypeId },
-    Structure { path: Path, def: MirStructDef },
+    Array {
+        ty: TypeId,
+        sz: usize,
+    },
+    RawPointer {
+        mutable: PointerMut,
+        target: TypeId,
+    },
+    Structure {
+        path: Path,
+        def: MirStructDef,
+    },
+
+    /// An option, which is laid out as a structure whose fields are [`FieldId::TAG`],
+    /// which is `true` if the option holds a value, and [`FieldId::VALUE`].
+    Option {
+        ty: TypeId,
+    },
+
+    /// A result, which is laid out as a structure whose fields are [`FieldId::TAG`],
+    /// which is `true` if the result is ok, [`FieldId::VALUE`], and [`FieldId::ERR`].
+    Result {
+        ok: TypeId,
+        err: TypeId,
+    },
 }
 
 impl MirTypeDef {
@@ -411,6 +517,17 @@ impl PartialEq for MirTypeDef {
                     target: r_target,
                 },
             ) => l_mutable == r_mutable && l_target == r_target,
+            (Self::Option { ty: l_ty }, Self::Option { ty: r_ty }) => l_ty == r_ty,
+            (
+                Self::Result {
+                    ok: l_ok,
+                    err: l_err,
+                },
+                Self::Result {
+                    ok: r_ok,
+                    err: r_err,
+                },
+            ) => l_ok == r_ok && l_err == r_err,
             (Self::Structure { path: l_path, .. }, Self::Structure { path: r_path, .. }) => {
                 l_path == r_path
             }
@@ -428,6 +545,8 @@ impl Display for MirTypeDef {
                 f.write_fmt(format_args!("*{} {}", mutable, target))
             }
             MirTypeDef::Structure { path, def } => f.write_fmt(format_args!("{}", path)),
+            MirTypeDef::Option { ty } => f.write_fmt(format_args!("opt {}", ty)),
+            MirTypeDef::Result { ok, err } => f.write_fmt(format_args!("res {}, {}", ok, err)),
         }
     }
 }
@@ -441,7 +560,7 @@ impl Display for MirTypeDef {
 /// including base types, be added to the [`TypeTable`], but because the implementation
 /// of [`TypeTable`] and [`TypeId`] is completely invisible to users, this
 /// was deemed acceptable.
-#[derive(Hash, Eq, Debug, PartialEq, PartialOrd, Clone, Copy)]
+#[derive(Hash, Eq, Debug, PartialEq, PartialOrd, Clone, Copy, Serialize, Deserialize)]
 pub struct TypeId(u32);
 
 impl Display for TypeId {
@@ -492,11 +611,20 @@ pub struct Field {
 /// Identifies a specific field within a [`MirTypeDef::Structure`]. To be useful, the [`FieldId`]
 /// must be coupled with a [`TypeId`] that refers to a [`MirTypeDef::Structure`] type in the
 /// [`TypeTable`]. This [`FieldId`] uniquely identifies a specific field in the structure referred to by [`TypeId`].
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub struct FieldId(u32);
 
 impl FieldId {
-    fn new(id: u32) -> FieldId {
+    /// The field of an option or result which is `true` if it holds a value or is ok
+    pub const TAG: FieldId = FieldId(0);
+
+    /// The field of an option which holds its value, or of a result which holds its ok
+    pub const VALUE: FieldId = FieldId(1);
+
+    /// The field of a result which holds its err
+    pub const ERR: FieldId = FieldId(2);
+
+    pub(super) const fn new(id: u32) -> FieldId {
         FieldId(id)
     }
 
@@ -524,6 +652,9 @@ pub enum TypeTableError {
 
     /// Attempting to add a definition to a type that is not a structure
     ExpectedStructure,
+
+    /// A coroutine type whose frame has not been added to the table
+    CoroutineFrameMissing,
 }
 
 #[cfg(test)]
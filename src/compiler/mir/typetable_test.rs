@@ -96,6 +96,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn add_option_type() {
+        let mut table = TypeTable::new();
+        let i64_id = table.find(&Type::I64).unwrap();
+
+        let opt = Type::Option(Box::new(Type::I64));
+        let opt_id = table.add(&opt).unwrap();
+        assert_eq!(table.get(opt_id), &MirTypeDef::Option { ty: i64_id });
+
+        // Adding a type which is already in the table returns the same entry
+        assert_eq!(table.add(&opt).unwrap(), opt_id);
+        assert_eq!(table.find(&opt), Some(opt_id));
+
+        // Options of different types are different types
+        let other = Type::Option(Box::new(Type::Bool));
+        assert_eq!(table.find(&other), None);
+    }
+
+    #[test]
+    fn add_result_type() {
+        let mut table = TypeTable::new();
+        let i64_id = table.find(&Type::I64).unwrap();
+        let bool_id = table.find(&Type::Bool).unwrap();
+
+        let res = Type::Result(Box::new(Type::I64), Box::new(Type::Bool));
+        let res_id = table.add(&res).unwrap();
+        assert_eq!(
+            table.get(res_id),
+            &MirTypeDef::Result {
+                ok: i64_id,
+                err: bool_id
+            }
+        );
+        assert_eq!(table.find(&res), Some(res_id));
+
+        // The ok and err types are not interchangeable
+        let swapped = Type::Result(Box::new(Type::Bool), Box::new(Type::I64));
+        assert_eq!(table.find(&swapped), None);
+    }
+
     #[test]
     fn define_struct_that_is_declared() {
         let mut table = TypeTable::new();
@@ -5,20 +5,30 @@ mod source;
 // Modules which should be accessible outside of  the [`compiler`] module
 pub mod ast;
 pub mod diagnostics;
+pub mod fuel;
+pub mod ice;
 pub mod import;
 pub mod lexer;
 pub mod llvm;
-mod mir;
+pub mod messages;
+pub(crate) mod mir;
 pub mod parser;
+pub mod passes;
+pub mod pipeline;
 pub mod semantics;
 pub mod stringtable;
+pub mod verify;
 
 // Expose certain compiler items outside of the module because they are key parts
 // of the interface between the compiler and modules which use the compiler.
-pub use error::CompilerError;
+pub(crate) use error::format_span;
+pub use error::{CompilerError, Note};
 pub use lexer::lexer::Lexer;
-pub use mir::{transform, MirProject, ProgramTraverser};
-pub use source::{Source, SourceCharIter, SourceError, SourceMap, SourceMapError, Span};
+pub use mir::{transform, CoverageMap, MirProject, ProgramTraverser};
+pub use source::{
+    Column, LineNumber, Offset, Source, SourceCharIter, SourceError, SourceMap, SourceMapError,
+    Span,
+};
 
 // Import items for use within the compiler submodule which are not needed outside
 use source::SourceChar;
@@ -1,32 +1,48 @@
-use crate::compiler::{ast::Context, lexer::tokens::Token, source::SourceIr, Span};
+use crate::compiler::{
+    ast::{Context, MapPreOrder, Module, Node},
+    lexer::tokens::Token,
+    source::SourceIr,
+    Span,
+};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ParserContext {
+    /// Identifies this node within its project.  This is `0` until the nodes of the
+    /// project are numbered by [`number_nodes`].
+    id: u32,
     span: Span,
 }
 
 impl ParserContext {
     pub fn new(span: Span) -> ParserContext {
-        ParserContext { span }
+        ParserContext { id: 0, span }
+    }
+
+    /// Returns a copy of this context with the given node ID.
+    pub fn with_id(self, id: u32) -> ParserContext {
+        ParserContext { id, ..self }
     }
 
     /// Joins this [`ParserContext`] with `a`, such that the span will cover
     /// both contexts.
     pub fn join(self, b: ParserContext) -> ParserContext {
         let span = Span::cover(self.span, b.span);
-        ParserContext::new(span)
+        ParserContext { span, ..self }
     }
 
     /// Extends the span of this [`ParserContext`] such that it also covers the
     /// span described by `b`.
     pub fn extend(self, b: Span) -> ParserContext {
-        ParserContext::new(Span::cover(self.span, b))
+        ParserContext {
+            span: Span::cover(self.span, b),
+            ..self
+        }
     }
 }
 
 impl Context for ParserContext {
     fn id(&self) -> u32 {
-        0
+        self.id
     }
 
     fn span(&self) -> Span {
@@ -36,6 +52,20 @@ impl Context for ParserContext {
 
 impl Token {
     pub fn to_ctx(&self) -> ParserContext {
-        ParserContext { span: self.span() }
+        ParserContext::new(self.span())
     }
 }
+
+/// Gives every node of a parsed project an ID which is unique within the project.
+/// Nodes are numbered, starting from `1`, in the order that [`MapPreOrder`] visits
+/// them, so parsing the same source code will always assign the same IDs.  These IDs
+/// are kept by every later phase of the compiler, which lets external tools refer to
+/// the same node across phases.
+pub fn number_nodes(m: &Module<ParserContext>) -> Module<ParserContext> {
+    let mut next_id = 0;
+    let mut mapper = MapPreOrder::new("number-nodes", |n: &dyn Node<ParserContext>| {
+        next_id += 1;
+        n.context().with_id(next_id)
+    });
+    mapper.apply(m)
+}
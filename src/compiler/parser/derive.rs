@@ -0,0 +1,402 @@
+//! Expands the `#[derive]` attributes of structures into routines.
+//!
+//! `#[derive(print)]` on a structure `S` generates `fn print_S(value: S)`, which writes
+//! every field of the value to stdout (e.g. `S { x: 1, flag: true }`), and
+//! `#[derive(eq)]` generates `fn eq_S(a: S, b: S) -> bool`, which compares every field
+//! of two values.  Type resolution replaces `==` and `!=` between two values of `S`
+//! with a call to `eq_S`.
+//!
+//! The routines are added to the module which defines the structure before types are
+//! resolved, so they are checked and compiled like any other routine and can be called
+//! by user code.  A field whose type is another structure is printed and compared by
+//! the routines derived for that structure, so it must derive the same routines.
+
+use crate::{
+    compiler::{
+        ast::{
+            build::Builder, BinaryOperator, Element, Expression, Item, Module, Path, RoutineCall,
+            RoutineDef, Statement, StructDef, Type, UnaryOperator,
+        },
+        source::SourceIr,
+        CompilerError, Span,
+    },
+    StringId, StringTable,
+};
+
+use super::{ParserContext, ParserError};
+
+type DeriveResult<T> = Result<T, CompilerError<ParserError>>;
+
+/// Generates the routines asked for by the `#[derive]` attribute of every structure in
+/// the given module, and its submodules, and adds them to the module.
+pub fn expand_derives(m: &mut Module<ParserContext>, st: &StringTable) -> DeriveResult<()> {
+    for child in m.get_modules_mut() {
+        expand_derives(child, st)?;
+    }
+
+    let mut routines = vec![];
+    let mut uses_printf = false;
+    for item in m.get_structs_mut() {
+        if let Item::Struct(sd) = item {
+            let (derived, prints) = derive_struct(sd, st)?;
+            routines.extend(derived);
+            uses_printf |= prints;
+        }
+    }
+
+    // The generated print routines write to stdout with `printf`, which is declared
+    // if the module does not already declare it
+    if uses_printf && m.get_item(st.insert("printf".into())).is_none() {
+        let span = m.span();
+        let b = Builder::new(st, || ParserContext::new(span));
+        let format = [("format", Type::StringLiteral)];
+        m.add_extern(b.extern_def("printf", &format, true, Type::Unit))?;
+    }
+
+    for r in routines {
+        m.add_function(r)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the routines asked for by the attributes of the given structure, and whether
+/// any of them call `printf`.  If `eq` is derived, then the structure is updated with
+/// the name of the routine which `==` calls.
+fn derive_struct(
+    sd: &mut StructDef<ParserContext>,
+    st: &StringTable,
+) -> DeriveResult<(Vec<RoutineDef<ParserContext>>, bool)> {
+    let derive = st.insert("derive".into());
+    let print = st.insert("print".into());
+    let eq = st.insert("eq".into());
+
+    let gen = Derive::new(st, sd.span());
+    let mut routines = vec![];
+    let mut prints = false;
+    for attr in sd.get_attributes().clone() {
+        if attr.get_name() != derive {
+            return Err(CompilerError::new(
+                attr.span(),
+                ParserError::UnknownAttribute(attr.get_name()),
+            ));
+        }
+
+        for arg in attr.get_args() {
+            if *arg == print {
+                routines.push(gen.print_routine(sd)?);
+                prints = true;
+            } else if *arg == eq {
+                let eq_fn = gen.eq_routine(sd);
+                sd.set_derived_eq(eq_fn.get_name());
+                routines.push(eq_fn);
+            } else {
+                return Err(CompilerError::new(
+                    attr.span(),
+                    ParserError::DeriveUnknown(*arg),
+                ));
+            }
+        }
+    }
+
+    Ok((routines, prints))
+}
+
+/// Builds the routines derived for a single structure.  Every node which is generated
+/// has the span of the structure.
+struct Derive<'a> {
+    b: Builder<'a, ParserContext>,
+    st: &'a StringTable,
+    span: Span,
+}
+
+impl<'a> Derive<'a> {
+    fn new(st: &'a StringTable, span: Span) -> Derive<'a> {
+        Derive {
+            b: Builder::new(st, move || ParserContext::new(span)),
+            st,
+            span,
+        }
+    }
+
+    fn name(&self, id: StringId) -> String {
+        self.st.get(id).unwrap()
+    }
+
+    /// The type of a value of the given structure, from within the module which defines
+    /// the structure
+    fn struct_ty(&self, sd: &StructDef<ParserContext>) -> Type {
+        Type::Custom(vec![Element::Id(sd.get_name())].into())
+    }
+
+    /// `fn print_S(value: S)`
+    fn print_routine(
+        &self,
+        sd: &StructDef<ParserContext>,
+    ) -> DeriveResult<RoutineDef<ParserContext>> {
+        let b = &self.b;
+        let name = self.name(sd.get_name());
+
+        let mut body = vec![self.printf(&format!("{} {{", name), vec![])];
+        for (idx, field) in sd.get_fields().iter().enumerate() {
+            let sep = if idx == 0 { " " } else { ", " };
+            body.push(self.printf(&format!("{}{}: ", sep, self.name(field.name)), vec![]));
+
+            let value = b.member(b.var("value"), &self.name(field.name));
+            let print = self.print_value(value, &field.ty, 0).ok_or_else(|| {
+                CompilerError::new(
+                    field.span(),
+                    ParserError::DerivePrintUnsupportedField(field.name),
+                )
+            })?;
+            body.extend(print);
+        }
+        let close = if sd.get_fields().is_empty() {
+            "}"
+        } else {
+            " }"
+        };
+        body.push(self.printf(close, vec![]));
+        body.push(b.ret(None));
+
+        Ok(b.fn_def(&format!("print_{}", name))
+            .param("value", self.struct_ty(sd))
+            .body(body)
+            .build())
+    }
+
+    /// Returns the statements which print `value`, or `None` if values of the given type
+    /// cannot be printed.  `depth` is the number of arrays which `value` is nested in.
+    fn print_value(
+        &self,
+        value: Expression<ParserContext>,
+        ty: &Type,
+        depth: usize,
+    ) -> Option<Vec<Statement<ParserContext>>> {
+        let b = &self.b;
+        let stm = match ty {
+            Type::I64 => self.printf("%ld", vec![value]),
+            Type::I8 | Type::I16 | Type::I32 | Type::ISize => {
+                self.printf("%ld", vec![b.cast(value, Type::I64)])
+            }
+            Type::U64 => self.printf("%lu", vec![value]),
+            Type::U8 | Type::U16 | Type::U32 | Type::USize => {
+                self.printf("%lu", vec![b.cast(value, Type::U64)])
+            }
+            Type::F64 => self.printf("%f", vec![value]),
+            Type::StringLiteral => self.printf("%s", vec![value]),
+            Type::RawPointer(..) => self.printf("%p", vec![value]),
+            Type::Bool => b.expr(b.if_(
+                value,
+                b.block(vec![self.printf("true", vec![])], None),
+                Some(b.block(vec![self.printf("false", vec![])], None)),
+            )),
+            Type::Array(el_ty, len) => {
+                // [a, b, ...]
+                let idx = format!("i{}", depth);
+                let mut body = vec![b.expr(b.if_(
+                    b.bin(BinaryOperator::Gr, b.var(&idx), b.i64(0)),
+                    b.block(vec![self.printf(", ", vec![])], None),
+                    None,
+                ))];
+                body.extend(self.print_value(b.index(value, b.var(&idx)), el_ty, depth + 1)?);
+                return Some(vec![
+                    self.printf("[", vec![]),
+                    self.for_each(&idx, *len, body),
+                    self.printf("]", vec![]),
+                ]);
+            }
+            Type::Custom(path) => b.expr(self.call_derived("print", path, vec![value])),
+            _ => return None,
+        };
+        Some(vec![stm])
+    }
+
+    /// `fn eq_S(a: S, b: S) -> bool`
+    fn eq_routine(&self, sd: &StructDef<ParserContext>) -> RoutineDef<ParserContext> {
+        let b = &self.b;
+
+        let mut body = vec![];
+        for field in sd.get_fields() {
+            let name = self.name(field.name);
+            let l = b.member(b.var("a"), &name);
+            let r = b.member(b.var("b"), &name);
+            body.extend(self.eq_value(l, r, &field.ty, 0));
+        }
+        body.push(b.ret(Some(b.bool(true))));
+
+        b.fn_def(&format!("eq_{}", self.name(sd.get_name())))
+            .param("a", self.struct_ty(sd))
+            .param("b", self.struct_ty(sd))
+            .ret_ty(Type::Bool)
+            .body(body)
+            .build()
+    }
+
+    /// Returns the statements which return `false` from the eq routine if `l` and `r` are
+    /// not equal.  `depth` is the number of arrays which the values are nested in.
+    fn eq_value(
+        &self,
+        l: Expression<ParserContext>,
+        r: Expression<ParserContext>,
+        ty: &Type,
+        depth: usize,
+    ) -> Vec<Statement<ParserContext>> {
+        let b = &self.b;
+        let not_eq = match ty {
+            Type::Array(el_ty, len) => {
+                let idx = format!("i{}", depth);
+                let l = b.index(l, b.var(&idx));
+                let r = b.index(r, b.var(&idx));
+                let body = self.eq_value(l, r, el_ty, depth + 1);
+                return vec![self.for_each(&idx, *len, body)];
+            }
+            Type::Custom(path) => b.unary(
+                UnaryOperator::Not,
+                self.call_derived("eq", path, vec![l, r]),
+            ),
+            _ => b.bin(BinaryOperator::NEq, l, r),
+        };
+        vec![b.expr(b.if_(
+            not_eq,
+            b.block(vec![b.ret(Some(b.bool(false)))], None),
+            None,
+        ))]
+    }
+
+    /// `{ let mut idx: i64 := 0; while (idx < len) { body; mut idx := idx + 1; }; }`
+    fn for_each(
+        &self,
+        idx: &str,
+        len: usize,
+        mut body: Vec<Statement<ParserContext>>,
+    ) -> Statement<ParserContext> {
+        let b = &self.b;
+        body.push(b.mutate(b.var(idx), b.bin(BinaryOperator::Add, b.var(idx), b.i64(1))));
+        let cond = b.bin(BinaryOperator::Ls, b.var(idx), b.i64(len as i64));
+        b.expr(b.block(
+            vec![
+                b.let_mut(idx, Type::I64, b.i64(0)),
+                b.expr(b.while_(cond, b.block(body, None))),
+            ],
+            None,
+        ))
+    }
+
+    /// Calls the routine which `derive` generated for the structure at `path`
+    fn call_derived(
+        &self,
+        derive: &str,
+        path: &Path,
+        args: Vec<Expression<ParserContext>>,
+    ) -> Expression<ParserContext> {
+        let mut routine = path.clone();
+        if let Some(Element::Id(name)) = routine.pop() {
+            let name = format!("{}_{}", derive, self.name(name));
+            routine.push(Element::Id(self.st.insert(name)));
+        }
+        Expression::RoutineCall(
+            ParserContext::new(self.span),
+            RoutineCall::Function,
+            Box::new(routine),
+            args,
+        )
+    }
+
+    /// `printf(format, args, ...);`
+    fn printf(
+        &self,
+        format: &str,
+        mut args: Vec<Expression<ParserContext>>,
+    ) -> Statement<ParserContext> {
+        let b = &self.b;
+        args.insert(0, b.string(format));
+        b.expr(b.call(&["printf"], args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compiler::{
+            ast::{Item, Module},
+            diagnostics::Logger,
+            lexer::tokens::Token,
+            parser::{Parser, ParserContext},
+            CompilerDisplay, Lexer, SourceMap,
+        },
+        StringTable,
+    };
+
+    use super::expand_derives;
+
+    fn parse(text: &str, sm: &mut SourceMap, table: &StringTable) -> Module<ParserContext> {
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let parser = Parser::new(&logger);
+        parser
+            .parse(table.insert("test".into()), &tokens)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn derive_print_and_eq() {
+        let table = StringTable::new();
+        let mut sm = SourceMap::new();
+        let mut m = parse(
+            "#[derive(print, eq)] struct S {x: i64, ys: [bool; 2]}",
+            &mut sm,
+            &table,
+        );
+        expand_derives(&mut m, &table).unwrap();
+
+        let print_s = table.find("print_S").unwrap();
+        let eq_s = table.find("eq_S").unwrap();
+        let printf = table.find("printf").unwrap();
+        assert!(matches!(m.get_item(print_s), Some(Item::Routine(_))));
+        assert!(matches!(m.get_item(eq_s), Some(Item::Routine(_))));
+        assert!(matches!(m.get_item(printf), Some(Item::Extern(_))));
+
+        match m.get_item(table.find("S").unwrap()) {
+            Some(Item::Struct(sd)) => assert_eq!(sd.get_derived_eq(), Some(eq_s)),
+            _ => panic!("Expected S to be a structure"),
+        }
+    }
+
+    #[test]
+    fn derive_fails() {
+        for (text, msg) in [
+            (
+                "#[inline] struct S {x: i64}",
+                "L1: Unknown attribute inline",
+            ),
+            (
+                "#[derive(hash)] struct S {x: i64}",
+                "L1: Cannot derive hash, expected print or eq",
+            ),
+            (
+                "#[derive(print)] struct S {x: opt i64}",
+                "L1: Cannot derive print because field x has a type which cannot be printed",
+            ),
+            (
+                "#[derive(print)] struct S {x: i64} fn print_S() {return;}",
+                "L1: Module already contains print_S\n    L1: note: previous fn definition here",
+            ),
+        ] {
+            let table = StringTable::new();
+            let mut sm = SourceMap::new();
+            let mut m = parse(text, &mut sm, &table);
+            let err = expand_derives(&mut m, &table).unwrap_err();
+            assert_eq!(err.fmt(&sm, &table).unwrap(), msg, "{}", text);
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use crate::{
     compiler::{
         ast::AstError,
+        fuel::FuelError,
         lexer::tokens::{Lex, Token},
-        CompilerDisplay, CompilerDisplayError, CompilerError, SourceMap,
+        messages, CompilerDisplay, CompilerDisplayError, CompilerError, SourceMap,
     },
     StringId, StringTable,
 };
@@ -16,6 +17,10 @@ pub enum ParserError {
     Locked(Option<Token>),
     ModExpectedName,
     ModAlreadyContains(StringId),
+    ImplExpectedIdentifier,
+    ImplStructNotFound(StringId),
+    StructAlreadyContains(StringId, StringId),
+    UseExpectedPath,
     ExternInvalidVarArgs,
     ExternExpectedFnDecl,
     StructExpectedIdentifier,
@@ -23,6 +28,7 @@ pub enum ParserError {
     FnVarArgsNotAllowed,
     FnExpectedTypeAfterArrow,
     FnExpectedReturn(Option<Token>),
+    MethodExpectedSelf,
     FnCallExpectedParams,
     CoExpectedIdentifierAfterCo,
     ArrayExpectedIntLiteral,
@@ -35,6 +41,8 @@ pub enum ParserError {
     ExpectedIdDeclAfterLet,
     ExpectedTypeInIdDecl,
     ExpectedExpressionOnRhs,
+    ExpectedValueListOnRhs,
+    DestructureWrongNumValues(usize, usize),
     ExpectedParams,
     ExpectedIdAfterInit,
     NotAUnaryOp(Lex),
@@ -45,6 +53,10 @@ pub enum ParserError {
     IfFalseArmMissingExpr,
     WhileExpectedConditional,
     WhileMissingBody,
+    ForExpectedIdentifier,
+    ForExpectedType,
+    ForExpectedRange,
+    ForMissingBody,
     PathExpectedIdentifier,
     YieldExpectedIdentifier,
     StructExpectedFieldExpr(StringId),
@@ -55,122 +67,158 @@ pub enum ParserError {
     MemberAccessExpectedField,
     IndexOpInvalidExpr,
     InvalidCastTarget,
+    RangeDeclExpectedType,
+    OptionDeclExpectedType,
+    ResultDeclExpectedType,
+    ResultDeclExpectedErrorType,
+    /// A token at module level which does not start an item (e.g. a stray `}`)
+    ModuleUnexpectedToken(Token),
+    StructExpectedFieldAfterPub,
+    AttributeExpectedName,
+    AttributeExpectedItem,
+    UnknownAttribute(StringId),
+    DeriveUnknown(StringId),
+    DerivePrintUnsupportedField(StringId),
+    MacroExpectedName,
+    MacroExpectedRule,
+    MacroExpectedFragment,
+    MacroUnknownFragment(StringId),
+    MacroNotDefined(StringId),
+    MacroNoMatchingRule(StringId),
+    MacroExpectedExpression(StringId),
+    MacroRecursionLimit(StringId, usize),
+    AttributeExpectedValue,
+    LinkNameExpectedValue,
+    StaticAssertExpectedArgs,
+    ResourceExhausted(FuelError),
+}
+
+impl ParserError {
+    /// Returns the code which identifies this error and its message in the
+    /// [message catalog](crate::compiler::messages).
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParserError::EmptyProject => "P0001",
+            ParserError::Locked(..) => "P0002",
+            ParserError::ModExpectedName => "P0003",
+            ParserError::ModAlreadyContains(..) => "P0004",
+            ParserError::ImplExpectedIdentifier => "P0005",
+            ParserError::ImplStructNotFound(..) => "P0006",
+            ParserError::StructAlreadyContains(..) => "P0007",
+            ParserError::UseExpectedPath => "P0008",
+            ParserError::ExternInvalidVarArgs => "P0009",
+            ParserError::ExternExpectedFnDecl => "P0010",
+            ParserError::StructExpectedIdentifier => "P0011",
+            ParserError::FnExpectedIdentifierAfterFn => "P0012",
+            ParserError::FnVarArgsNotAllowed => "P0013",
+            ParserError::FnExpectedTypeAfterArrow => "P0014",
+            ParserError::FnExpectedReturn(..) => "P0015",
+            ParserError::MethodExpectedSelf => "P0016",
+            ParserError::FnCallExpectedParams => "P0017",
+            ParserError::CoExpectedIdentifierAfterCo => "P0018",
+            ParserError::ArrayExpectedIntLiteral => "P0019",
+            ParserError::ArrayDeclExpectedType => "P0020",
+            ParserError::ArrayDeclExpectedSize => "P0021",
+            ParserError::IdDeclExpectedType => "P0022",
+            ParserError::RawPointerExpectedType => "P0023",
+            ParserError::RawPointerExpectedConstOrMut => "P0024",
+            ParserError::ExpectedButFound(..) => "P0025",
+            ParserError::ExpectedIdDeclAfterLet => "P0026",
+            ParserError::ExpectedTypeInIdDecl => "P0027",
+            ParserError::ExpectedExpressionOnRhs => "P0028",
+            ParserError::ExpectedValueListOnRhs => "P0029",
+            ParserError::DestructureWrongNumValues(..) => "P0030",
+            ParserError::ExpectedParams => "P0031",
+            ParserError::ExpectedIdAfterInit => "P0032",
+            ParserError::NotAUnaryOp(..) => "P0033",
+            ParserError::NotABinaryOp(..) => "P0034",
+            ParserError::IfExpectedConditional => "P0035",
+            ParserError::IfTrueArmMissingExpr => "P0036",
+            ParserError::IfElseExpectedIfExpr => "P0037",
+            ParserError::IfFalseArmMissingExpr => "P0038",
+            ParserError::WhileExpectedConditional => "P0039",
+            ParserError::WhileMissingBody => "P0040",
+            ParserError::ForExpectedIdentifier => "P0041",
+            ParserError::ForExpectedType => "P0042",
+            ParserError::ForExpectedRange => "P0043",
+            ParserError::ForMissingBody => "P0044",
+            ParserError::PathExpectedIdentifier => "P0045",
+            ParserError::YieldExpectedIdentifier => "P0046",
+            ParserError::StructExpectedFieldExpr(..) => "P0047",
+            ParserError::ExpectedExprAfter(..) => "P0048",
+            ParserError::ExpectedTermAfter(..) => "P0049",
+            ParserError::ExpectedIdentifierAfter(..) => "P0050",
+            ParserError::AddressOfExpectedConstOrMut => "P0051",
+            ParserError::MemberAccessExpectedField => "P0052",
+            ParserError::IndexOpInvalidExpr => "P0053",
+            ParserError::InvalidCastTarget => "P0054",
+            ParserError::RangeDeclExpectedType => "P0055",
+            ParserError::OptionDeclExpectedType => "P0056",
+            ParserError::ResultDeclExpectedType => "P0057",
+            ParserError::ResultDeclExpectedErrorType => "P0058",
+            ParserError::ModuleUnexpectedToken(..) => "P0059",
+            ParserError::StructExpectedFieldAfterPub => "P0060",
+            ParserError::AttributeExpectedName => "P0061",
+            ParserError::AttributeExpectedItem => "P0062",
+            ParserError::UnknownAttribute(..) => "P0063",
+            ParserError::DeriveUnknown(..) => "P0064",
+            ParserError::DerivePrintUnsupportedField(..) => "P0065",
+            ParserError::MacroExpectedName => "P0066",
+            ParserError::MacroExpectedRule => "P0067",
+            ParserError::MacroExpectedFragment => "P0068",
+            ParserError::MacroUnknownFragment(..) => "P0069",
+            ParserError::MacroNotDefined(..) => "P0070",
+            ParserError::MacroNoMatchingRule(..) => "P0071",
+            ParserError::MacroExpectedExpression(..) => "P0072",
+            ParserError::MacroRecursionLimit(..) => "P0073",
+            ParserError::AttributeExpectedValue => "P0074",
+            ParserError::LinkNameExpectedValue => "P0075",
+            ParserError::StaticAssertExpectedArgs => "P0076",
+            ParserError::ResourceExhausted(..) => "P0077",
+        }
+    }
 }
 
 impl CompilerDisplay for ParserError {
     /// Format a ParserError into a human readable message and replace any [`StringId`]s
     /// with their respective string values.
     fn fmt(&self, sm: &SourceMap, st: &crate::StringTable) -> Result<String, CompilerDisplayError> {
-        let msg = match self {
-            ParserError::Locked(token) => {
-                let ts = token_to_string(sm, st, token)?;
-                format!("Parser cannot advance past {}", ts)
-            }
-            ParserError::ModExpectedName => "Identifier expected after mod keyword".into(),
-            ParserError::ModAlreadyContains(sid) => {
-                format!("Module already contains {}", sid.fmt(sm, st)?)
-            }
-            ParserError::ExternInvalidVarArgs => "An extern declaration must have at least one \
-                    parameter before a VarArgs (...) parameter"
-                .into(),
-            ParserError::ExternExpectedFnDecl => {
-                "Expected function declaration after extern keyword".into()
-            }
-            ParserError::StructExpectedIdentifier => {
-                "Expected identifier after struct keyword".into()
-            }
-            ParserError::FnExpectedIdentifierAfterFn => {
-                "Expected identifier after fn keyword".into()
-            }
-            ParserError::FnVarArgsNotAllowed => {
-                "Varargs are not allowed in Bramble functions (only in externs)".into()
-            }
-            ParserError::FnExpectedTypeAfterArrow => "Type expected after ->".into(),
-            ParserError::FnExpectedReturn(token) => {
-                format!(
-                    "Routines must end with a return statement, but found {}",
-                    token_to_string(sm, st, token)?
-                )
-            }
-            ParserError::FnCallExpectedParams => {
-                "Expected parameters after function call point".into()
-            }
-            ParserError::CoExpectedIdentifierAfterCo => {
-                "Expected identifier after co keyword".into()
+        let args = match self {
+            ParserError::Locked(token) | ParserError::FnExpectedReturn(token) => {
+                vec![token_to_string(sm, st, token)?]
             }
-            ParserError::ArrayExpectedIntLiteral => {
-                "Expected integer literal for array size".into()
+            ParserError::ModuleUnexpectedToken(token) => vec![token.fmt(sm, st)?],
+            ParserError::ModAlreadyContains(sid)
+            | ParserError::ImplStructNotFound(sid)
+            | ParserError::StructExpectedFieldExpr(sid)
+            | ParserError::UnknownAttribute(sid)
+            | ParserError::DeriveUnknown(sid)
+            | ParserError::DerivePrintUnsupportedField(sid)
+            | ParserError::MacroUnknownFragment(sid)
+            | ParserError::MacroNotDefined(sid)
+            | ParserError::MacroNoMatchingRule(sid)
+            | ParserError::MacroExpectedExpression(sid) => vec![sid.fmt(sm, st)?],
+            ParserError::MacroRecursionLimit(sid, limit) => {
+                vec![sid.fmt(sm, st)?, limit.to_string()]
             }
-            ParserError::ArrayDeclExpectedType => "Expected type in array type declaration".into(),
-            ParserError::ArrayDeclExpectedSize => {
-                "Expected size to be specified in array type declaration".into()
+            ParserError::StructAlreadyContains(sd, sid) => vec![sd.fmt(sm, st)?, sid.fmt(sm, st)?],
+            ParserError::ExpectedButFound(expected, actual) => vec![
+                lex_set_to_string(sm, st, expected)?,
+                lex_to_string(sm, st, actual)?,
+            ],
+            ParserError::DestructureWrongNumValues(expected, actual) => {
+                vec![expected.to_string(), actual.to_string()]
             }
-            ParserError::IdDeclExpectedType => {
-                "Expected type after : in variable declaration".into()
+            ParserError::NotAUnaryOp(op) | ParserError::NotABinaryOp(op) => vec![op.to_string()],
+            ParserError::ResourceExhausted(err) => vec![err.to_string()],
+            ParserError::ExpectedExprAfter(lex)
+            | ParserError::ExpectedTermAfter(lex)
+            | ParserError::ExpectedIdentifierAfter(lex) => {
+                vec![lex_to_string(sm, st, &Some(*lex))?]
             }
-            ParserError::ExpectedButFound(expected, actual) => {
-                format!(
-                    "Expected {}, but found {}",
-                    lex_set_to_string(sm, st, expected)?,
-                    lex_to_string(sm, st, actual)?
-                )
-            }
-            ParserError::ExpectedIdDeclAfterLet => {
-                "Expected identifier declaration (`<id> : <type>`) after let".into()
-            }
-            ParserError::ExpectedTypeInIdDecl => {
-                "Expected type specification in let binding".into()
-            }
-            ParserError::ExpectedExpressionOnRhs => "Expected expression after :=".into(),
-            ParserError::ExpectedParams => "Expected parameter list after identifier".into(),
-            ParserError::ExpectedIdAfterInit => "Expected identifer after init".into(),
-            ParserError::NotAUnaryOp(op) => format!("{} is not a unary operator", op),
-            ParserError::NotABinaryOp(op) => format!("{} is not a binary operator", op),
-            ParserError::IfExpectedConditional => "Expected conditional expression after if".into(),
-            ParserError::IfTrueArmMissingExpr => {
-                "Expected expression block in true arm of if expression".into()
-            }
-            ParserError::IfElseExpectedIfExpr => "Expected expression block after else if".into(),
-            ParserError::IfFalseArmMissingExpr => "Expected expression block after else".into(),
-            ParserError::WhileExpectedConditional => {
-                "Expected conditional after while keyword".into()
-            }
-            ParserError::WhileMissingBody => "Expected expression block for while loop body".into(),
-            ParserError::PathExpectedIdentifier => "Expected identifier after ::".into(),
-            ParserError::YieldExpectedIdentifier => "Expected identifier after yield".into(),
-            ParserError::StructExpectedFieldExpr(sid) => format!(
-                "Expected an expression to be assigned to field {}",
-                sid.fmt(sm, st)?
-            ),
-            ParserError::ExpectedExprAfter(lex) => {
-                format!(
-                    "Expected expression after {}",
-                    lex_to_string(sm, st, &Some(*lex))?
-                )
-            }
-            ParserError::ExpectedTermAfter(lex) => {
-                format!(
-                    "Expected term after {}",
-                    lex_to_string(sm, st, &Some(*lex))?
-                )
-            }
-            ParserError::MemberAccessExpectedField => {
-                "Expected member name after . operator.".into()
-            }
-            ParserError::IndexOpInvalidExpr => {
-                "Index operator must contain valid expression".into()
-            }
-            ParserError::EmptyProject => "No source code.".into(),
-            ParserError::RawPointerExpectedType => "Raw Pointer expected underlying type".into(),
-            ParserError::RawPointerExpectedConstOrMut => "Expected const or mut after *".into(),
-            ParserError::ExpectedIdentifierAfter(lex) => format!(
-                "Expected identifier after {}",
-                lex_to_string(sm, st, &Some(*lex))?
-            ),
-            ParserError::AddressOfExpectedConstOrMut => "Expected const or mut after @".into(),
-            ParserError::InvalidCastTarget => "Can only cast to and from primitive types.".into(),
+            _ => vec![],
         };
-        Ok(msg)
+        Ok(messages::message(self.code(), &args))
     }
 }
 
@@ -209,11 +257,10 @@ fn lex_set_to_string(
 
 impl From<CompilerError<AstError>> for CompilerError<ParserError> {
     fn from(ce: CompilerError<AstError>) -> Self {
-        let (span, ae) = ce.take();
-        match ae {
-            AstError::ModuleAlreadyContains(sid) => {
-                CompilerError::new(span, ParserError::ModAlreadyContains(sid))
-            }
-        }
+        ce.map_inner(|ae| match ae {
+            AstError::ModuleAlreadyContains(sid) => ParserError::ModAlreadyContains(sid),
+            AstError::ImplStructNotFound(sid) => ParserError::ImplStructNotFound(sid),
+            AstError::StructAlreadyContains(sd, sid) => ParserError::StructAlreadyContains(sd, sid),
+        })
     }
 }
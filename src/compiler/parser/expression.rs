@@ -1,3 +1,7 @@
+use super::grammar::{
+    ARITHMETIC_BUILTINS, BYTE_BUILTINS, COMPARISON_OPS, CONSTRUCTOR_BUILTINS, PRODUCT_OPS, SUM_OPS,
+    TYPE_BUILTINS, UNARY_OPS,
+};
 use super::{Parser, ParserResult};
 
 use super::{tokenstream::TokenStream, ParserContext, ParserError};
@@ -179,6 +183,12 @@ impl Expression<ParserContext> {
                 left,
                 right,
             ))),
+            Lex::Range => Ok(Some(Expression::BinaryOp(
+                ctx,
+                BinaryOperator::Range,
+                left,
+                right,
+            ))),
             _ => {
                 err!(ctx.span(), ParserError::NotABinaryOp(*op))
             }
@@ -196,8 +206,8 @@ impl<'a> Parser<'a> {
                 let (event, result) = self.new_event(Span::zero()).and_then(|| {
                     // Read the statements composing the expression block
                     let mut stmts = vec![];
-                    while let Some(s) = self.statement(stream)? {
-                        stmts.push(s);
+                    while let Some(s) = self.statement_or_return(stream)? {
+                        stmts.extend(s);
                     }
 
                     // Check if the block ends in an expression rather than a statement (no semicolon post fix)
@@ -224,7 +234,16 @@ impl<'a> Parser<'a> {
         &self,
         stream: &mut TokenStream,
     ) -> ParserResult<Expression<ParserContext>> {
-        self.logical_or(stream)
+        self.burn(stream)?;
+        self.range(stream)
+    }
+
+    /// Parses a range, `<start>..<end>`, which has the lowest precedence of any operator.
+    pub(super) fn range(
+        &self,
+        stream: &mut TokenStream,
+    ) -> ParserResult<Expression<ParserContext>> {
+        self.binary_op(stream, &[Lex::Range], Self::logical_or)
     }
 
     pub(super) fn logical_or(
@@ -245,19 +264,15 @@ impl<'a> Parser<'a> {
         &self,
         stream: &mut TokenStream,
     ) -> ParserResult<Expression<ParserContext>> {
-        self.binary_op(
-            stream,
-            &[Lex::Eq, Lex::NEq, Lex::Ls, Lex::LsEq, Lex::Gr, Lex::GrEq],
-            Self::sum,
-        )
+        self.binary_op(stream, COMPARISON_OPS, Self::sum)
     }
 
     pub(super) fn sum(&self, stream: &mut TokenStream) -> ParserResult<Expression<ParserContext>> {
-        self.binary_op(stream, &[Lex::Add, Lex::Minus, Lex::At], Self::term)
+        self.binary_op(stream, SUM_OPS, Self::term)
     }
 
     pub(super) fn term(&self, stream: &mut TokenStream) -> ParserResult<Expression<ParserContext>> {
-        self.binary_op(stream, &[Lex::Mul, Lex::Div], Self::cast)
+        self.binary_op(stream, PRODUCT_OPS, Self::cast)
     }
 
     pub(super) fn binary_op(
@@ -371,7 +386,11 @@ impl<'a> Parser<'a> {
                             let cast_ctx = exp.context().join(ty_ctx);
 
                             // Create the cast node
-                            Ok(Some(Expression::TypeCast(cast_ctx, Box::new(exp), ty)))
+                            Ok(Some(Expression::TypeCast(
+                                cast_ctx,
+                                Box::new(exp),
+                                Box::new(ty),
+                            )))
                         }
                         None => Ok(Some(exp)),
                     }
@@ -394,7 +413,7 @@ impl<'a> Parser<'a> {
     ) -> ParserResult<Expression<ParserContext>> {
         match self.address_of(stream)? {
             Some(exp) => Ok(Some(exp)),
-            None => match stream.next_if_one_of(&[Lex::Minus, Lex::Not, Lex::Hat]) {
+            None => match stream.next_if_one_of(UNARY_OPS) {
                 Some(op) => {
                     let (event, result) = self.new_event(Span::zero()).and_then(|| {
                         self.negate(stream)
@@ -472,6 +491,12 @@ impl<'a> Parser<'a> {
                     .expect("Array Access Failed to Parse");
                 self.subdata_access_sequence(aa, stream)
             }
+            Some(tok) if tok.sym == Lex::Question => {
+                let ctx = factor.context().join(tok.to_ctx());
+                stream.next();
+                let tried = Expression::UnaryOp(ctx, UnaryOperator::Try, Box::new(factor));
+                self.subdata_access_sequence(tried, stream)
+            }
             _ => Ok(Some(factor)),
         }
     }
@@ -509,13 +534,31 @@ impl<'a> Parser<'a> {
                 .ok_or_else(|| {
                     CompilerError::new(token.span(), ParserError::MemberAccessExpectedField)
                 })
-                .map(|(member, member_span)| {
-                    self.record_terminal(member_span, Ok("Structure Field"));
-                    Some(Expression::MemberAccess(
-                        ma.context().extend(member_span),
-                        Box::new(ma),
-                        member,
-                    ))
+                .and_then(|(member, member_span)| {
+                    // A member which is followed by parameters is a call to a method, which
+                    // takes the value that it is called on as its first parameter
+                    match self.routine_call_params(stream)? {
+                        Some((params, params_ctx)) => {
+                            self.record_terminal(member_span, Ok("Method"));
+                            let ctx = ma.context().join(params_ctx);
+                            let mut args = vec![ma];
+                            args.extend(params);
+                            Ok(Some(Expression::RoutineCall(
+                                ctx,
+                                RoutineCall::Method,
+                                Box::new(vec![Element::Id(member)].into()),
+                                args,
+                            )))
+                        }
+                        None => {
+                            self.record_terminal(member_span, Ok("Structure Field"));
+                            Ok(Some(Expression::MemberAccess(
+                                ma.context().extend(member_span),
+                                Box::new(ma),
+                                member,
+                            )))
+                        }
+                    }
                 })
         } else {
             Ok(None)
@@ -532,17 +575,20 @@ impl<'a> Parser<'a> {
                 let (event, result) = self.new_event(Span::zero()).and_then(|| {
                     let ctx = lparen.to_ctx();
                     stream.next();
-                    let mut exp = self.expression(stream)?;
+                    let exp = self.expression(stream)?;
                     let rparen = stream.next_must_be(&Lex::RParen)?;
                     let ctx = ctx.join(rparen.to_ctx());
 
-                    // Extend the Span of exp to cover the left paren and the right
-                    exp.as_mut().map(|exp| {
-                        let ctx = exp.context().join(ctx);
-                        *exp.get_context_mut() = ctx;
-                    });
-
-                    Ok(exp)
+                    match exp {
+                        Some(mut exp) => {
+                            // Extend the Span of exp to cover the left paren and the right
+                            let ctx = exp.context().join(ctx);
+                            *exp.get_context_mut() = ctx;
+                            Ok(Some(exp))
+                        }
+                        // `()` is the unit literal, which is the same as an empty expression block
+                        None => Ok(Some(Expression::ExpressionBlock(ctx, vec![], None))),
+                    }
                 });
                 result.view(|v| {
                     let msg = v.map(|_| "Expression");
@@ -551,18 +597,26 @@ impl<'a> Parser<'a> {
             }
             _ => self
                 .if_expression(stream)
-                .por(|ts| self.size_of(ts), stream)
+                .por(|ts| self.type_builtin(ts), stream)
+                .por(|ts| self.len_builtin(ts), stream)
+                .por(|ts| self.constructor_builtin(ts), stream)
+                .por(|ts| self.arithmetic_builtin(ts), stream)
+                .por(|ts| self.byte_builtin(ts), stream)
                 .por(|ts| self.while_expression(ts), stream)
+                .por(|ts| self.for_expression(ts), stream)
                 .por(|ts| self.expression_block(ts), stream)
+                .por(|ts| self.macro_call(ts), stream)
+                .por(|ts| self.yield_expression(ts), stream)
                 .por(|ts| self.function_call_or_variable(ts), stream)
                 .por(|ts| self.constant(ts), stream)
                 .por(|ts| self.array_expression(ts), stream),
         }
     }
 
-    fn size_of(&self, stream: &mut TokenStream) -> ParserResult<Expression<ParserContext>> {
-        // Check of size_of keyword
-        match stream.next_if(&Lex::SizeOf) {
+    /// Parses the builtins which take a type rather than an expression (`size_of(T)`,
+    /// `fields_of(T)`, and `name_of(T)`).
+    fn type_builtin(&self, stream: &mut TokenStream) -> ParserResult<Expression<ParserContext>> {
+        match stream.next_if_one_of(TYPE_BUILTINS) {
             Some(op) => {
                 let (event, result) = self.new_event(Span::zero()).and_then(|| {
                     let ctx = op.to_ctx();
@@ -577,11 +631,173 @@ impl<'a> Parser<'a> {
                     // Must have )
                     let ctx = stream.next_must_be(&Lex::RParen)?.to_ctx().join(ctx);
 
-                    // Return size_of expression
-                    Ok(Some(Expression::SizeOf(ctx, Box::new(ty))))
+                    let ty = Box::new(ty);
+                    Ok(Some(match op.sym {
+                        Lex::SizeOf => Expression::SizeOf(ctx, ty),
+                        Lex::FieldsOf => Expression::FieldsOf(ctx, ty),
+                        Lex::NameOf => Expression::NameOf(ctx, ty),
+                        _ => panic!("Invalid type builtin: {}", op.sym),
+                    }))
+                });
+                result.view(|v| {
+                    let msg = v.map(|_| "Type Builtin");
+                    self.record(event.with_span(v.span()), msg)
+                })
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Parses the string length builtin (`len(s)`).  This is represented in the AST as a
+    /// unary operation.
+    fn len_builtin(&self, stream: &mut TokenStream) -> ParserResult<Expression<ParserContext>> {
+        match stream.next_if(&Lex::Len) {
+            Some(op) => {
+                let (event, result) = self.new_event(Span::zero()).and_then(|| {
+                    let ctx = op.to_ctx();
+
+                    // Must have (
+                    stream.next_must_be(&Lex::LParen)?;
+
+                    let operand = self.expression(stream)?.ok_or_else(|| {
+                        CompilerError::new(ctx.span(), ParserError::ExpectedExprAfter(op.sym))
+                    })?;
+
+                    // Must have )
+                    let ctx = stream.next_must_be(&Lex::RParen)?.to_ctx().join(ctx);
+
+                    Ok(Some(Expression::UnaryOp(
+                        ctx,
+                        UnaryOperator::Length,
+                        Box::new(operand),
+                    )))
+                });
+                result.view(|v| {
+                    let msg = v.map(|_| "len");
+                    self.record(event.with_span(v.span()), msg)
+                })
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Parses the option and result constructors (`some(x)`, `ok(x)`, and `err(e)`).  These
+    /// are represented in the AST as unary operations.
+    fn constructor_builtin(
+        &self,
+        stream: &mut TokenStream,
+    ) -> ParserResult<Expression<ParserContext>> {
+        match stream.next_if_one_of(CONSTRUCTOR_BUILTINS) {
+            Some(op) => {
+                let (event, result) = self.new_event(Span::zero()).and_then(|| {
+                    let ctx = op.to_ctx();
+                    let un_op = match op.sym {
+                        Lex::OptionSome => UnaryOperator::OptionSome,
+                        Lex::ResultOk => UnaryOperator::ResultOk,
+                        Lex::ResultErr => UnaryOperator::ResultErr,
+                        _ => panic!("Invalid constructor: {}", op.sym),
+                    };
+
+                    // Must have (
+                    stream.next_must_be(&Lex::LParen)?;
+
+                    let operand = self.expression(stream)?.ok_or_else(|| {
+                        CompilerError::new(ctx.span(), ParserError::ExpectedExprAfter(op.sym))
+                    })?;
+
+                    // Must have )
+                    let ctx = stream.next_must_be(&Lex::RParen)?.to_ctx().join(ctx);
+
+                    Ok(Some(Expression::UnaryOp(ctx, un_op, Box::new(operand))))
                 });
                 result.view(|v| {
-                    let msg = v.map(|_| "size_of");
+                    let msg = v.map(|_| "Constructor Builtin");
+                    self.record(event.with_span(v.span()), msg)
+                })
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Parses the explicit overflow arithmetic builtins (e.g. `wrapping_add(a, b)`).  These
+    /// are represented in the AST as binary operations.
+    fn arithmetic_builtin(
+        &self,
+        stream: &mut TokenStream,
+    ) -> ParserResult<Expression<ParserContext>> {
+        match stream.next_if_one_of(ARITHMETIC_BUILTINS) {
+            Some(op) => {
+                let (event, result) = self.new_event(Span::zero()).and_then(|| {
+                    let ctx = op.to_ctx();
+                    let bin_op = match op.sym {
+                        Lex::WrappingAdd => BinaryOperator::WrappingAdd,
+                        Lex::WrappingSub => BinaryOperator::WrappingSub,
+                        Lex::WrappingMul => BinaryOperator::WrappingMul,
+                        Lex::SaturatingAdd => BinaryOperator::SaturatingAdd,
+                        Lex::SaturatingSub => BinaryOperator::SaturatingSub,
+                        _ => panic!("Invalid arithmetic builtin: {}", op.sym),
+                    };
+
+                    // Must have (
+                    stream.next_must_be(&Lex::LParen)?;
+
+                    let left = self.expression(stream)?.ok_or_else(|| {
+                        CompilerError::new(ctx.span(), ParserError::ExpectedExprAfter(op.sym))
+                    })?;
+                    stream.next_must_be(&Lex::Comma)?;
+                    let right = self.expression(stream)?.ok_or_else(|| {
+                        CompilerError::new(ctx.span(), ParserError::ExpectedExprAfter(Lex::Comma))
+                    })?;
+                    stream.next_if(&Lex::Comma);
+
+                    // Must have )
+                    let ctx = stream.next_must_be(&Lex::RParen)?.to_ctx().join(ctx);
+
+                    Ok(Some(Expression::BinaryOp(
+                        ctx,
+                        bin_op,
+                        Box::new(left),
+                        Box::new(right),
+                    )))
+                });
+                result.view(|v| {
+                    let msg = v.map(|_| "Arithmetic Builtin");
+                    self.record(event.with_span(v.span()), msg)
+                })
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Parses the builtins which convert between integers and their bytes (e.g.
+    /// `to_le_bytes(x)`).  These are represented in the AST as unary operations.
+    fn byte_builtin(&self, stream: &mut TokenStream) -> ParserResult<Expression<ParserContext>> {
+        match stream.next_if_one_of(BYTE_BUILTINS) {
+            Some(op) => {
+                let (event, result) = self.new_event(Span::zero()).and_then(|| {
+                    let ctx = op.to_ctx();
+                    let un_op = match op.sym {
+                        Lex::ToLeBytes => UnaryOperator::ToLeBytes,
+                        Lex::ToBeBytes => UnaryOperator::ToBeBytes,
+                        Lex::FromLeBytes => UnaryOperator::FromLeBytes,
+                        Lex::FromBeBytes => UnaryOperator::FromBeBytes,
+                        _ => panic!("Invalid byte builtin: {}", op.sym),
+                    };
+
+                    // Must have (
+                    stream.next_must_be(&Lex::LParen)?;
+
+                    let operand = self.expression(stream)?.ok_or_else(|| {
+                        CompilerError::new(ctx.span(), ParserError::ExpectedExprAfter(op.sym))
+                    })?;
+
+                    // Must have )
+                    let ctx = stream.next_must_be(&Lex::RParen)?.to_ctx().join(ctx);
+
+                    Ok(Some(Expression::UnaryOp(ctx, un_op, Box::new(operand))))
+                });
+                result.view(|v| {
+                    let msg = v.map(|_| "Byte Builtin");
                     self.record(event.with_span(v.span()), msg)
                 })
             }
@@ -597,9 +813,18 @@ impl<'a> Parser<'a> {
             Some(if_tok) => {
                 let (event, result) = self.new_event(Span::zero()).and_then(|| {
                     stream.next_must_be(&Lex::LParen).and_then(|_| {
-                        let cond = self.expression(stream)?.ok_or_else(|| {
-                            CompilerError::new(if_tok.span(), ParserError::IfExpectedConditional)
-                        })?;
+                        let unwrap = self.if_let_unwrap(stream)?;
+                        let cond = match &unwrap {
+                            Some((pattern, var, var_ctx, _)) => {
+                                Self::if_unwrap_cond(*pattern, *var, *var_ctx)
+                            }
+                            None => self.expression(stream)?.ok_or_else(|| {
+                                CompilerError::new(
+                                    if_tok.span(),
+                                    ParserError::IfExpectedConditional,
+                                )
+                            })?,
+                        };
                         stream.next_must_be(&Lex::RParen)?;
 
                         let if_arm = self.expression_block(stream)?.ok_or_else(|| {
@@ -636,11 +861,17 @@ impl<'a> Parser<'a> {
                             |ea| if_tok.to_ctx().join(*ea.context()),
                         );
 
-                        Ok(Some(Expression::If {
+                        let exp = Expression::If {
                             context: ctx,
                             cond: Box::new(cond),
                             if_arm: Box::new(if_arm),
                             else_arm: else_arm.map(Box::new),
+                        };
+                        Ok(Some(match unwrap {
+                            Some((pattern, var, var_ctx, opt)) => {
+                                Self::if_unwrap(pattern, var, var_ctx, opt, exp)
+                            }
+                            None => exp,
                         }))
                     })
                 });
@@ -653,72 +884,453 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parses the `let some(<id>) := <exp>`, `let ok(<id>) := <exp>`, or
+    /// `let err(<id>) := <exp>` which can be the condition of an `if`.  Returns the
+    /// constructor which is matched, the name of the variable which the value is bound to,
+    /// and the option or result.
+    fn if_let_unwrap(
+        &self,
+        stream: &mut TokenStream,
+    ) -> ParserResult<(Lex, StringId, ParserContext, Expression<ParserContext>)> {
+        let tokens = CONSTRUCTOR_BUILTINS
+            .iter()
+            .find_map(|pattern| stream.next_ifn(vec![Lex::Let, *pattern]));
+        match tokens {
+            Some(tokens) => {
+                let pattern = tokens[1].sym;
+                stream.next_must_be(&Lex::LParen)?;
+                let (var, var_span) = stream.next_if_id().ok_or_else(|| {
+                    CompilerError::new(
+                        tokens[1].span(),
+                        ParserError::ExpectedIdentifierAfter(pattern),
+                    )
+                })?;
+                stream.next_must_be(&Lex::RParen)?;
+                let assign = stream.next_must_be(&Lex::Assign)?;
+                let opt = self.expression(stream)?.ok_or_else(|| {
+                    CompilerError::new(assign.span(), ParserError::ExpectedExpressionOnRhs)
+                })?;
+                Ok(Some((pattern, var, ParserContext::new(var_span), opt)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Builds the condition of `if (let <pattern>(var) := ...)`, which tests whether `var`
+    /// holds the case of the option or result that `pattern` names.
+    fn if_unwrap_cond(
+        pattern: Lex,
+        var: StringId,
+        var_ctx: ParserContext,
+    ) -> Expression<ParserContext> {
+        let var = Box::new(Expression::Identifier(var_ctx, var));
+        match pattern {
+            Lex::OptionSome => Expression::UnaryOp(var_ctx, UnaryOperator::OptionIsSome, var),
+            Lex::ResultOk => Expression::UnaryOp(var_ctx, UnaryOperator::ResultIsOk, var),
+            Lex::ResultErr => Expression::UnaryOp(
+                var_ctx,
+                UnaryOperator::Not,
+                Box::new(Expression::UnaryOp(var_ctx, UnaryOperator::ResultIsOk, var)),
+            ),
+            _ => panic!("Invalid if let pattern: {}", pattern),
+        }
+    }
+
+    /// Builds `if (let <pattern>(var) := opt) {...} else {...}` from `exp`, an `if` whose
+    /// condition tests whether `var` holds the case that `pattern` names.  The option or
+    /// result is first bound to `var`, so that it is evaluated once, and then, within the
+    /// true arm, `var` is bound to the value which it holds.  Because the value is only taken
+    /// out in the arm which runs when it is there, an option or result can never be used
+    /// without first checking which case it holds.
+    fn if_unwrap(
+        pattern: Lex,
+        var: StringId,
+        var_ctx: ParserContext,
+        opt: Expression<ParserContext>,
+        exp: Expression<ParserContext>,
+    ) -> Expression<ParserContext> {
+        let exp = match exp {
+            Expression::If {
+                context,
+                cond,
+                if_arm,
+                else_arm,
+            } => {
+                let if_arm = match *if_arm {
+                    Expression::ExpressionBlock(arm_ctx, mut body, final_exp) => {
+                        let unwrap = match pattern {
+                            Lex::OptionSome => UnaryOperator::OptionUnwrap,
+                            Lex::ResultOk => UnaryOperator::ResultUnwrapOk,
+                            Lex::ResultErr => UnaryOperator::ResultUnwrapErr,
+                            _ => panic!("Invalid if let pattern: {}", pattern),
+                        };
+                        let value = Expression::UnaryOp(
+                            var_ctx,
+                            unwrap,
+                            Box::new(Expression::Identifier(var_ctx, var)),
+                        );
+                        let bind = Bind::new(var_ctx, var, Type::Unknown, false, value);
+                        body.insert(0, Statement::Bind(Box::new(bind)));
+                        Expression::ExpressionBlock(arm_ctx, body, final_exp)
+                    }
+                    if_arm => if_arm,
+                };
+                Expression::If {
+                    context,
+                    cond,
+                    if_arm: Box::new(if_arm),
+                    else_arm,
+                }
+            }
+            exp => exp,
+        };
+
+        let ctx = *exp.context();
+        let init = Bind::new(var_ctx.join(*opt.context()), var, Type::Unknown, false, opt);
+        Expression::ExpressionBlock(
+            ctx,
+            vec![Statement::Bind(Box::new(init))],
+            Some(Box::new(exp)),
+        )
+    }
+
     pub(super) fn while_expression(
         &self,
         stream: &mut TokenStream,
     ) -> ParserResult<Expression<ParserContext>> {
-        let (event, result) =
-            self.new_event(Span::zero())
-                .and_then(|| match stream.next_if(&Lex::While) {
-                    Some(whl) => stream.next_must_be(&Lex::LParen).and_then(|_| {
-                        let cond = self.expression(stream)?.ok_or_else(|| {
-                            CompilerError::new(whl.span(), ParserError::WhileExpectedConditional)
-                        })?;
-                        stream.next_must_be(&Lex::RParen)?;
+        let (event, result) = self.new_event(Span::zero()).and_then(|| {
+            let label = Self::loop_label(stream, Lex::While);
+            match stream.next_if(&Lex::While) {
+                Some(whl) => stream.next_must_be(&Lex::LParen).and_then(|_| {
+                    let cond = self.expression(stream)?.ok_or_else(|| {
+                        CompilerError::new(whl.span(), ParserError::WhileExpectedConditional)
+                    })?;
+                    stream.next_must_be(&Lex::RParen)?;
 
-                        self.expression_block(stream)?
-                            .ok_or_else(|| {
-                                CompilerError::new(whl.span, ParserError::WhileMissingBody)
-                            })
-                            .map(|body| {
-                                Some(Expression::While {
-                                    context: whl.to_ctx().join(*body.context()),
-                                    cond: Box::new(cond),
-                                    body: Box::new(body),
-                                })
+                    let start = label.map_or(whl.to_ctx(), |(_, span)| ParserContext::new(span));
+                    self.expression_block(stream)?
+                        .ok_or_else(|| CompilerError::new(whl.span, ParserError::WhileMissingBody))
+                        .map(|body| {
+                            Some(Expression::While {
+                                context: start.join(*body.context()),
+                                label: label.map(|(label, _)| label),
+                                cond: Box::new(cond),
+                                body: Box::new(body),
                             })
-                    }),
-                    _ => Ok(None),
-                });
+                        })
+                }),
+                _ => Ok(None),
+            }
+        });
         result.view(|v| {
             let msg = v.map(|_| "While");
             self.record(event.with_span(v.span()), msg)
         })
     }
 
+    /// Parses the label of a loop, `'<label>:`, if it is followed by the keyword which
+    /// starts the loop.
+    fn loop_label(stream: &mut TokenStream, keyword: Lex) -> Option<(StringId, Span)> {
+        if stream.test_ifn(vec![Lex::Label(StringId::new()), Lex::Colon, keyword]) {
+            let label = stream.next_if_label();
+            stream.next();
+            label
+        } else {
+            None
+        }
+    }
+
+    /// Parses `['<label>:] for (<id>[: <type>] in <range>) <block>` and desugars it into a
+    /// `while` loop, with the same label.  If no type is given then the loop variable is an
+    /// `i64`.
+    ///
+    /// If the range is written as `<start>..<end>`, then the loop counts the loop variable
+    /// up from `start` until it reaches `end`:
+    ///
+    /// ```text
+    /// {
+    ///     let mut <id>: <type> := <start>;
+    ///     while (<id> < <end>) {
+    ///         defer { mut <id> := <id> + 1 as <type>; };
+    ///         <block>
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// Like the condition of a `while` loop, `end` is evaluated before every iteration.
+    /// The loop variable is advanced by a `defer`, so that it is also advanced when an
+    /// iteration is ended early by `continue`.
+    ///
+    /// Otherwise, the range is any sequence of `<type>`s, which is evaluated once.  A
+    /// sequence is a value which supports the iterator protocol: `is_empty(self) -> bool`,
+    /// `first(self) -> <type>`, the next element, and `rest(self) -> S`, the rest of the
+    /// sequence, which must have the same type as the sequence.  A structure is a
+    /// sequence if it has these methods.  Ranges and slices are sequences, whose steps
+    /// are `len(<seq>) == 0u64`, `<seq>[0u64]`, and `<seq>[1u64..len(<seq>)]`, and an
+    /// array is iterated through a slice of it.
+    ///
+    /// The sequence is stored in a variable with the same name as the loop variable,
+    /// which the loop variable shadows within the body, and the first element is removed
+    /// from it after every iteration.  The type of that variable is inferred:
+    ///
+    /// ```text
+    /// {
+    ///     let mut <id> := <range>;
+    ///     while (!<id>.is_empty()) {
+    ///         defer { mut <id> := <id>.rest(); };
+    ///         {
+    ///             let mut <id>: <type> := <id>.first();
+    ///             <block>
+    ///         };
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// A deferred expression is evaluated in the scope where it was deferred, so the
+    /// `defer` removes the first element from the sequence and not from the loop variable.
+    pub(super) fn for_expression(
+        &self,
+        stream: &mut TokenStream,
+    ) -> ParserResult<Expression<ParserContext>> {
+        let (event, result) = self.new_event(Span::zero()).and_then(|| {
+            let label = Self::loop_label(stream, Lex::For);
+            match stream.next_if(&Lex::For) {
+                Some(for_tok) => {
+                    stream.next_must_be(&Lex::LParen)?;
+                    let (var, var_span) = stream.next_if_id().ok_or_else(|| {
+                        CompilerError::new(for_tok.span(), ParserError::ForExpectedIdentifier)
+                    })?;
+                    let var_ctx = ParserContext::new(var_span);
+                    let ty = match stream.next_if(&Lex::Colon) {
+                        Some(colon) => {
+                            self.consume_type(stream)?
+                                .ok_or_else(|| {
+                                    CompilerError::new(colon.span(), ParserError::ForExpectedType)
+                                })?
+                                .0
+                        }
+                        None => Type::I64,
+                    };
+
+                    let in_tok = stream.next_must_be(&Lex::In)?;
+                    let range = self.expression(stream)?.ok_or_else(|| {
+                        CompilerError::new(in_tok.span(), ParserError::ForExpectedRange)
+                    })?;
+                    stream.next_must_be(&Lex::RParen)?;
+
+                    let (body_ctx, mut body, final_exp) = match self.expression_block(stream)? {
+                        Some(Expression::ExpressionBlock(ctx, body, final_exp)) => {
+                            (ctx, body, final_exp)
+                        }
+                        _ => return err!(for_tok.span(), ParserError::ForMissingBody),
+                    };
+
+                    // The value of the body is discarded, so its final expression
+                    // becomes a statement
+                    body.extend(final_exp.and_then(|e| Statement::from_ast(*e)));
+
+                    let (init, cond, body) = match range {
+                        Expression::BinaryOp(_, BinaryOperator::Range, start, end) => {
+                            Self::for_counter(var, var_ctx, ty, *start, *end, body)
+                        }
+                        seq => Self::for_sequence(var, var_ctx, ty, seq, body_ctx, body),
+                    };
+
+                    let start =
+                        label.map_or(for_tok.to_ctx(), |(_, span)| ParserContext::new(span));
+                    let ctx = start.join(body_ctx);
+                    let whl = Expression::While {
+                        context: ctx,
+                        label: label.map(|(label, _)| label),
+                        cond: Box::new(cond),
+                        body: Box::new(Expression::ExpressionBlock(body_ctx, body, None)),
+                    };
+                    Ok(Some(Expression::ExpressionBlock(
+                        ctx,
+                        vec![init, Statement::Expression(Box::new(whl))],
+                        None,
+                    )))
+                }
+                _ => Ok(None),
+            }
+        });
+        result.view(|v| {
+            let msg = v.map(|_| "For");
+            self.record(event.with_span(v.span()), msg)
+        })
+    }
+
+    /// Builds a `for` loop over `<start>..<end>`, which counts the loop variable up from
+    /// `start` until it reaches `end`.  Returns the declaration of the loop variable, the
+    /// condition of the loop, and the body of the loop.
+    fn for_counter(
+        var: StringId,
+        var_ctx: ParserContext,
+        ty: Type,
+        start: Expression<ParserContext>,
+        end: Expression<ParserContext>,
+        mut body: Vec<Statement<ParserContext>>,
+    ) -> (
+        Statement<ParserContext>,
+        Expression<ParserContext>,
+        Vec<Statement<ParserContext>>,
+    ) {
+        let var_exp = || Expression::Identifier(var_ctx, var);
+        let one = Expression::TypeCast(
+            var_ctx,
+            Box::new(Expression::I64(var_ctx, 1)),
+            Box::new(ty.clone()),
+        );
+        let next = Expression::BinaryOp(
+            var_ctx,
+            BinaryOperator::Add,
+            Box::new(var_exp()),
+            Box::new(one),
+        );
+        let step = Statement::Mutate(Box::new(Mutate::new(var_ctx, var_exp(), next)));
+        body.insert(0, Self::for_step(var_ctx, step));
+
+        let range_ctx = start.context().join(*end.context());
+        let cond = Expression::BinaryOp(
+            range_ctx,
+            BinaryOperator::Ls,
+            Box::new(var_exp()),
+            Box::new(end),
+        );
+
+        let init = Bind::new(var_ctx.join(*start.context()), var, ty, true, start);
+        (Statement::Bind(Box::new(init)), cond, body)
+    }
+
+    /// Builds a `for` loop over `seq`, a sequence of `ty`s, which removes the first element
+    /// of the sequence after every iteration until the sequence is empty.  Returns the
+    /// declaration of the sequence, the condition of the loop, and the body of the loop.
+    ///
+    /// The steps of the iterator protocol are written as [`RoutineCall::Iterator`]s, which
+    /// the type resolver turns into calls to the methods of a structure, or into builtin
+    /// operations if the sequence is not a structure.
+    fn for_sequence(
+        var: StringId,
+        var_ctx: ParserContext,
+        ty: Type,
+        seq: Expression<ParserContext>,
+        body_ctx: ParserContext,
+        mut body: Vec<Statement<ParserContext>>,
+    ) -> (
+        Statement<ParserContext>,
+        Expression<ParserContext>,
+        Vec<Statement<ParserContext>>,
+    ) {
+        let seq_ctx = *seq.context();
+        let var_exp = || Expression::Identifier(var_ctx, var);
+        let call = |step| {
+            Expression::RoutineCall(
+                seq_ctx,
+                RoutineCall::Iterator(step),
+                Box::new(Path::new()),
+                vec![var_exp()],
+            )
+        };
+
+        // The loop variable is the first element of the sequence
+        let first = call(IteratorStep::First);
+        body.insert(
+            0,
+            Statement::Bind(Box::new(Bind::new(var_ctx, var, ty.clone(), true, first))),
+        );
+        let body = Expression::ExpressionBlock(body_ctx, body, None);
+
+        // Then the first element is removed from the sequence
+        let rest = call(IteratorStep::Rest);
+        let step = Statement::Mutate(Box::new(Mutate::new(seq_ctx, var_exp(), rest)));
+
+        let cond = Expression::UnaryOp(
+            seq_ctx,
+            UnaryOperator::Not,
+            Box::new(call(IteratorStep::IsEmpty)),
+        );
+
+        // The type of the sequence is inferred by the type resolver
+        let init = Bind::new(var_ctx.join(seq_ctx), var, Type::Unknown, true, seq);
+        (
+            Statement::Bind(Box::new(init)),
+            cond,
+            vec![
+                Self::for_step(seq_ctx, step),
+                Statement::Expression(Box::new(body)),
+            ],
+        )
+    }
+
+    /// Defers the statement which advances a `for` loop to its next element, so that it
+    /// runs however an iteration of the loop ends.
+    fn for_step(ctx: ParserContext, step: Statement<ParserContext>) -> Statement<ParserContext> {
+        let block = Expression::ExpressionBlock(ctx, vec![step], None);
+        Statement::Defer(Box::new(Defer::new(ctx, block)))
+    }
+
+    /// Parses `yield <coroutine>`, which resumes the coroutine and evaluates to the next
+    /// value that it yields.  The coroutine may be any variable, member, or element, so
+    /// `yield c.x` resumes `c.x`; the value it yields is accessed with `(yield c).x`.
+    fn yield_expression(
+        &self,
+        stream: &mut TokenStream,
+    ) -> ParserResult<Expression<ParserContext>> {
+        match stream.next_if(&Lex::Yield) {
+            Some(yield_tok) => {
+                let (event, result) = self.new_event(Span::zero()).and_then(|| {
+                    let coroutine = self.subdata_access(stream)?.ok_or_else(|| {
+                        CompilerError::new(yield_tok.span(), ParserError::YieldExpectedIdentifier)
+                    })?;
+                    let ctx = yield_tok.to_ctx().join(*coroutine.context());
+                    Expression::new_yield(ctx, Box::new(coroutine))
+                });
+                result.view(|v| {
+                    let msg = v.map(|_| "Yield");
+                    self.record(event.with_span(v.span()), msg)
+                })
+            }
+            None => Ok(None),
+        }
+    }
+
     pub(super) fn function_call_or_variable(
         &self,
         stream: &mut TokenStream,
     ) -> ParserResult<Expression<ParserContext>> {
-        let (event, result) =
-            self.new_event(Span::zero())
-                .and_then(|| match self.path(stream)? {
-                    Some((path, call_ctx)) => match self.routine_call_params(stream)? {
-                        Some((params, params_ctx)) => Ok(Some(Expression::RoutineCall(
+        let (event, result) = self.new_event(Span::zero()).and_then(|| {
+            if let Some(receiver) = self.self_value(stream) {
+                return Ok(Some(receiver));
+            }
+
+            match self.path(stream)? {
+                Some((path, call_ctx)) => match self.routine_call_params(stream)? {
+                    Some((params, params_ctx)) => Ok(Some(Expression::RoutineCall(
+                        call_ctx.join(params_ctx),
+                        RoutineCall::Function,
+                        Box::new(path),
+                        params,
+                    ))),
+                    None => match self.struct_expression_params(stream)? {
+                        Some((params, params_ctx)) => Ok(Some(Expression::StructExpression(
                             call_ctx.join(params_ctx),
-                            RoutineCall::Function,
-                            path,
+                            Box::new(path),
                             params,
                         ))),
-                        None => match self.struct_expression_params(stream)? {
-                            Some((params, params_ctx)) => Ok(Some(Expression::StructExpression(
-                                call_ctx.join(params_ctx),
-                                path,
-                                params,
-                            ))),
-                            None => {
-                                if path.len() > 1 {
-                                    Ok(Some(Expression::Path(call_ctx, path)))
-                                } else if let Element::Id(sid) = path.last().unwrap() {
-                                    Ok(Some(Expression::Identifier(call_ctx, *sid)))
-                                } else {
-                                    err!(call_ctx.span(), ParserError::PathExpectedIdentifier)
-                                }
+                        None => {
+                            if path.len() > 1 {
+                                Ok(Some(Expression::Path(call_ctx, path)))
+                            } else if let Element::Id(sid) = path.last().unwrap() {
+                                Ok(Some(Expression::Identifier(call_ctx, *sid)))
+                            } else {
+                                err!(call_ctx.span(), ParserError::PathExpectedIdentifier)
                             }
-                        },
+                        }
                     },
-                    _ => Ok(None),
-                });
+                },
+                _ => Ok(None),
+            }
+        });
         result.view(|v| {
             let msg = v.map(|v| match &v {
                 Expression::Identifier(..) => "Identifier",
@@ -731,6 +1343,51 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parses a call to a macro, `name!(...)`.  The tokens between the parentheses are
+    /// kept as they are and are matched against the rules of the macro when it is
+    /// expanded.
+    fn macro_call(&self, stream: &mut TokenStream) -> ParserResult<Expression<ParserContext>> {
+        if !stream.test_ifn(vec![
+            Lex::Identifier(StringId::new()),
+            Lex::Not,
+            Lex::LParen,
+        ]) {
+            return Ok(None);
+        }
+
+        let (event, result) = self.new_event(Span::zero()).and_then(|| {
+            let (name, span) = stream.next_if_id().unwrap();
+            stream.next_must_be(&Lex::Not)?;
+            let lparen = stream.next_must_be(&Lex::LParen)?;
+            let (args, rparen) = self.delimited_tokens(stream, &lparen, &Lex::RParen)?;
+            let ctx = ParserContext::new(Span::cover(span, rparen.span()));
+            Ok(Some(Expression::MacroCall(ctx, name, args)))
+        });
+        result.view(|v| {
+            let msg = v.map(|_| "Macro Call");
+            self.record(event.with_span(v.span()), msg)
+        })
+    }
+
+    /// `self`, when it does not start a path, is the receiver of the method which is
+    /// being defined.
+    fn self_value(&self, stream: &mut TokenStream) -> Option<Expression<ParserContext>> {
+        if stream
+            .peek_at(1)
+            .map_or(false, |t| t.sym == Lex::PathSeparator)
+        {
+            return None;
+        }
+
+        match stream.next_if(&Lex::PathSelf(StringId::new())) {
+            Some(Token {
+                sym: Lex::PathSelf(self_id),
+                span,
+            }) => Some(Expression::Identifier(ParserContext::new(span), self_id)),
+            _ => None,
+        }
+    }
+
     pub(super) fn struct_expression_params(
         &self,
         stream: &mut TokenStream,
@@ -814,6 +1471,7 @@ impl<'a> Parser<'a> {
             .por(|ts| self.boolean_literal(ts), stream)
             .por(|ts| self.string_literal(ts), stream)
             .por(|ts| self.null_literal(ts), stream)
+            .por(|ts| self.none_literal(ts), stream)
     }
 
     pub(super) fn number(
@@ -905,6 +1563,24 @@ impl<'a> Parser<'a> {
         })
     }
 
+    pub(super) fn none_literal(
+        &self,
+        stream: &mut TokenStream,
+    ) -> ParserResult<Expression<ParserContext>> {
+        let (event, result) =
+            self.new_event(Span::zero())
+                .and_then(|| match stream.next_if(&Lex::OptionNone) {
+                    Some(Token { span, .. }) => {
+                        Ok(Some(Expression::OptionNone(ParserContext::new(span))))
+                    }
+                    _ => Ok(None),
+                });
+        result.view(|v| {
+            let msg = v.map(|_| "none");
+            self.record(event.with_span(v.span()), msg)
+        })
+    }
+
     pub(super) fn boolean_literal(
         &self,
         stream: &mut TokenStream,
@@ -936,7 +1612,20 @@ impl<'a> Parser<'a> {
                     sym: Lex::StringLiteral(s),
                     ..
                 }) => Ok(Some(Expression::StringLiteral(ParserContext::new(span), s))),
-                _ => Ok(None),
+                _ => match stream.next_if(&Lex::CStringLiteral(StringId::new())) {
+                    // A C string literal is a string literal cast to `*const u8`
+                    Some(Token {
+                        span,
+                        sym: Lex::CStringLiteral(s),
+                        ..
+                    }) => {
+                        let ctx = ParserContext::new(span);
+                        let lit = Expression::StringLiteral(ctx, s);
+                        let ty = Type::RawPointer(PointerMut::Const, Box::new(Type::U8));
+                        Ok(Some(Expression::TypeCast(ctx, Box::new(lit), Box::new(ty))))
+                    }
+                    _ => Ok(None),
+                },
             }
         });
         result.view(|v| {
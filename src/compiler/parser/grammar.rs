@@ -0,0 +1,927 @@
+//! The grammar of Bramble, written as a table of rules.
+//!
+//! The parser is a hand written recursive descent parser, so this table is not used
+//! to drive it.  Instead, the sets of tokens which the parser chooses between (the
+//! operators at each level of precedence and the builtins) are defined here and shared
+//! with the parser, and most rules are named after the parser function that implements
+//! them.  The table is rendered as EBNF by `bramblec --emit grammar`, which is the
+//! reference for the syntax of the language.
+//!
+//! The rendered grammar uses quoted strings for tokens which are always written the
+//! same way, upper case names for tokens which carry a value (e.g. `IDENTIFIER`), `[ ]`
+//! for something which is optional, `{ }` for something which repeats zero or more
+//! times, and `|` to separate alternatives.
+
+use crate::{
+    compiler::lexer::tokens::{Lex, Primitive},
+    StringId,
+};
+
+/// The comparison operators
+pub(super) const COMPARISON_OPS: &[Lex] =
+    &[Lex::Eq, Lex::NEq, Lex::Ls, Lex::LsEq, Lex::Gr, Lex::GrEq];
+
+/// The operators which have the same precedence as addition
+pub(super) const SUM_OPS: &[Lex] = &[Lex::Add, Lex::Minus, Lex::At];
+
+/// The operators which have the same precedence as multiplication
+pub(super) const PRODUCT_OPS: &[Lex] = &[Lex::Mul, Lex::Div];
+
+/// The prefix operators: arithmetic negation, boolean negation, and dereference
+pub(super) const UNARY_OPS: &[Lex] = &[Lex::Minus, Lex::Not, Lex::Hat];
+
+/// The builtins which take a type rather than an expression
+pub(super) const TYPE_BUILTINS: &[Lex] = &[Lex::SizeOf, Lex::FieldsOf, Lex::NameOf];
+
+/// The option and result constructors, which are also the patterns of `if (let ...)`
+pub(super) const CONSTRUCTOR_BUILTINS: &[Lex] = &[Lex::OptionSome, Lex::ResultOk, Lex::ResultErr];
+
+/// The explicit overflow arithmetic builtins
+pub(super) const ARITHMETIC_BUILTINS: &[Lex] = &[
+    Lex::WrappingAdd,
+    Lex::WrappingSub,
+    Lex::WrappingMul,
+    Lex::SaturatingAdd,
+    Lex::SaturatingSub,
+];
+
+/// The builtins which convert between integers and their bytes
+pub(super) const BYTE_BUILTINS: &[Lex] = &[
+    Lex::ToLeBytes,
+    Lex::ToBeBytes,
+    Lex::FromLeBytes,
+    Lex::FromBeBytes,
+];
+
+const PRIMITIVES: &[Lex] = &[
+    Lex::Primitive(Primitive::U8),
+    Lex::Primitive(Primitive::U16),
+    Lex::Primitive(Primitive::U32),
+    Lex::Primitive(Primitive::U64),
+    Lex::Primitive(Primitive::I8),
+    Lex::Primitive(Primitive::I16),
+    Lex::Primitive(Primitive::I32),
+    Lex::Primitive(Primitive::I64),
+    Lex::Primitive(Primitive::USize),
+    Lex::Primitive(Primitive::ISize),
+    Lex::Primitive(Primitive::F64),
+    Lex::Primitive(Primitive::Bool),
+    Lex::Primitive(Primitive::StringLiteral),
+    Lex::Primitive(Primitive::String),
+];
+
+const SELF: Lex = Lex::PathSelf(StringId::new());
+
+/// The tokens which carry a value, and a description of how they are written
+const TERMINALS: &[(&str, &str)] = &[
+    (
+        "IDENTIFIER",
+        "a letter or _ followed by letters, digits, and _, e.g. count_2",
+    ),
+    (
+        "INTEGER",
+        "digits with an optional type suffix, e.g. 5 or 5u8",
+    ),
+    (
+        "FLOAT",
+        "digits with a fractional part and an optional f64 suffix, e.g. 2.5",
+    ),
+    ("BOOLEAN", "true or false"),
+    ("LABEL", "' followed by an identifier, e.g. 'outer"),
+    (
+        "STRING_LITERAL",
+        "text between double quotes, e.g. \"hello\"",
+    ),
+    (
+        "C_STRING_LITERAL",
+        "a string literal prefixed with c, e.g. c\"hello\"",
+    ),
+    (
+        "TOKEN",
+        "any token; parentheses and braces within a macro must be balanced",
+    ),
+];
+
+/// A part of the body of a grammar rule
+enum Sym {
+    /// A token which is always written the same way, e.g. `fn` or `:=`
+    Token(Lex),
+    /// Any one of the given tokens
+    OneOf(&'static [Lex]),
+    /// A token which carries a value, one of [`TERMINALS`]
+    Terminal(&'static str),
+    /// Another rule of the grammar
+    Rule(&'static str),
+    Seq(&'static [Sym]),
+    Choice(&'static [Sym]),
+    Opt(&'static Sym),
+    Many(&'static Sym),
+    /// Zero or more of the given symbol separated by commas, with an optional trailing
+    /// comma
+    List(&'static Sym),
+}
+
+use Sym::*;
+
+/// A named rule of the grammar
+struct Production {
+    name: &'static str,
+    body: Sym,
+}
+
+const fn rule(name: &'static str, body: Sym) -> Production {
+    Production { name, body }
+}
+
+/// Every rule of the grammar.  The first rule is the start of the grammar, which is a
+/// file, and most rules are named after the parser function that implements them.
+const GRAMMAR: &[Production] = &[
+    // Items
+    rule("module", Many(&Rule("item"))),
+    rule(
+        "item",
+        Choice(&[
+            Seq(&[
+                Rule("attribute"),
+                Many(&Rule("attribute")),
+                Choice(&[Rule("struct_def"), Rule("extern_def")]),
+            ]),
+            Rule("use_decl"),
+            Rule("module_def"),
+            Rule("function_def"),
+            Rule("coroutine_def"),
+            Rule("struct_def"),
+            Rule("impl_def"),
+            Rule("extern_def"),
+            Rule("macro_def"),
+            Rule("static_assert"),
+        ]),
+    ),
+    rule(
+        "attribute",
+        Seq(&[
+            Token(Lex::Hash),
+            Token(Lex::LBracket),
+            Terminal("IDENTIFIER"),
+            Opt(&Seq(&[
+                Token(Lex::LParen),
+                List(&Terminal("IDENTIFIER")),
+                Token(Lex::RParen),
+            ])),
+            Opt(&Seq(&[Token(Lex::Equals), Terminal("STRING_LITERAL")])),
+            Token(Lex::RBracket),
+        ]),
+    ),
+    rule(
+        "use_decl",
+        Seq(&[Token(Lex::Use), Rule("path"), Token(Lex::Semicolon)]),
+    ),
+    rule(
+        "module_def",
+        Seq(&[
+            Token(Lex::ModuleDef),
+            Terminal("IDENTIFIER"),
+            Token(Lex::LBrace),
+            Many(&Rule("item")),
+            Token(Lex::RBrace),
+        ]),
+    ),
+    rule(
+        "function_def",
+        Seq(&[
+            Opt(&Token(Lex::Const)),
+            Token(Lex::FunctionDef),
+            Terminal("IDENTIFIER"),
+            Token(Lex::LParen),
+            Rule("parameter_list"),
+            Token(Lex::RParen),
+            Opt(&Seq(&[Token(Lex::LArrow), Rule("type")])),
+            Rule("fn_body"),
+        ]),
+    ),
+    rule(
+        "coroutine_def",
+        Seq(&[
+            Token(Lex::CoroutineDef),
+            Terminal("IDENTIFIER"),
+            Token(Lex::LParen),
+            Rule("parameter_list"),
+            Token(Lex::RParen),
+            Opt(&Seq(&[Token(Lex::LArrow), Rule("type")])),
+            Rule("fn_body"),
+        ]),
+    ),
+    rule(
+        "method_def",
+        Seq(&[
+            Opt(&Token(Lex::Const)),
+            Token(Lex::FunctionDef),
+            Terminal("IDENTIFIER"),
+            Token(Lex::LParen),
+            Token(SELF),
+            Opt(&Seq(&[Token(Lex::Comma), Rule("parameter_list")])),
+            Token(Lex::RParen),
+            Opt(&Seq(&[Token(Lex::LArrow), Rule("type")])),
+            Rule("fn_body"),
+        ]),
+    ),
+    rule(
+        "fn_body",
+        Seq(&[
+            Token(Lex::LBrace),
+            Many(&Choice(&[Rule("statement"), Rule("yield_return_stmt")])),
+            Rule("return_stmt"),
+            Token(Lex::RBrace),
+        ]),
+    ),
+    rule("parameter_list", List(&Rule("id_declaration"))),
+    rule(
+        "struct_def",
+        Seq(&[
+            Token(Lex::Struct),
+            Terminal("IDENTIFIER"),
+            Token(Lex::LBrace),
+            List(&Seq(&[Opt(&Token(Lex::Pub)), Rule("id_declaration")])),
+            Token(Lex::RBrace),
+        ]),
+    ),
+    rule(
+        "impl_def",
+        Seq(&[
+            Token(Lex::Impl),
+            Terminal("IDENTIFIER"),
+            Token(Lex::LBrace),
+            Many(&Rule("method_def")),
+            Token(Lex::RBrace),
+        ]),
+    ),
+    rule(
+        "extern_def",
+        Seq(&[
+            Token(Lex::Extern),
+            Token(Lex::FunctionDef),
+            Terminal("IDENTIFIER"),
+            Token(Lex::LParen),
+            Rule("parameter_list"),
+            Opt(&Token(Lex::VarArgs)),
+            Token(Lex::RParen),
+            Opt(&Seq(&[Token(Lex::LArrow), Rule("type")])),
+            Token(Lex::Semicolon),
+        ]),
+    ),
+    rule(
+        "macro_def",
+        Seq(&[
+            Token(Lex::Macro),
+            Terminal("IDENTIFIER"),
+            Token(Lex::LBrace),
+            Rule("macro_rule"),
+            Many(&Rule("macro_rule")),
+            Token(Lex::RBrace),
+        ]),
+    ),
+    rule(
+        "macro_rule",
+        Seq(&[
+            Token(Lex::LParen),
+            Many(&Rule("macro_pattern")),
+            Token(Lex::RParen),
+            Token(Lex::FatArrow),
+            Token(Lex::LBrace),
+            Many(&Terminal("TOKEN")),
+            Token(Lex::RBrace),
+            Opt(&Token(Lex::Semicolon)),
+        ]),
+    ),
+    rule(
+        "macro_pattern",
+        Choice(&[
+            Seq(&[
+                Token(Lex::Dollar),
+                Terminal("IDENTIFIER"),
+                Token(Lex::Colon),
+                Terminal("IDENTIFIER"),
+            ]),
+            Terminal("TOKEN"),
+        ]),
+    ),
+    rule(
+        "static_assert",
+        Seq(&[
+            Token(Lex::StaticAssert),
+            Token(Lex::LParen),
+            Rule("expression"),
+            Token(Lex::Comma),
+            Terminal("STRING_LITERAL"),
+            Token(Lex::RParen),
+            Token(Lex::Semicolon),
+        ]),
+    ),
+    // Types and paths
+    rule(
+        "id_declaration",
+        Seq(&[Terminal("IDENTIFIER"), Token(Lex::Colon), Rule("type")]),
+    ),
+    rule(
+        "type",
+        Seq(&[
+            Opt(&Token(Lex::CoroutineDef)),
+            Choice(&[
+                OneOf(PRIMITIVES),
+                Rule("range_type"),
+                Rule("option_type"),
+                Rule("result_type"),
+                Rule("path"),
+                Rule("array_type"),
+                Rule("raw_pointer_type"),
+            ]),
+        ]),
+    ),
+    rule(
+        "range_type",
+        Seq(&[
+            Token(Lex::RangeType),
+            Token(Lex::Ls),
+            Rule("type"),
+            Token(Lex::Gr),
+        ]),
+    ),
+    rule("option_type", Seq(&[Token(Lex::OptionType), Rule("type")])),
+    rule(
+        "result_type",
+        Seq(&[
+            Token(Lex::ResultType),
+            Rule("type"),
+            Token(Lex::Comma),
+            Rule("type"),
+        ]),
+    ),
+    rule(
+        "array_type",
+        Seq(&[
+            Token(Lex::LBracket),
+            Rule("type"),
+            Opt(&Seq(&[Token(Lex::Semicolon), Terminal("INTEGER")])),
+            Token(Lex::RBracket),
+        ]),
+    ),
+    rule(
+        "raw_pointer_type",
+        Seq(&[
+            Token(Lex::Mul),
+            OneOf(&[Lex::Const, Lex::Mut]),
+            Rule("type"),
+        ]),
+    ),
+    rule(
+        "path",
+        Choice(&[
+            Seq(&[
+                Choice(&[
+                    Token(Lex::PathProjectRoot),
+                    Token(Lex::PathFileRoot),
+                    Token(SELF),
+                    Token(Lex::PathSuper),
+                    Terminal("IDENTIFIER"),
+                ]),
+                Many(&Rule("path_segment")),
+            ]),
+            Seq(&[Rule("path_segment"), Many(&Rule("path_segment"))]),
+        ]),
+    ),
+    rule(
+        "path_segment",
+        Seq(&[
+            Token(Lex::PathSeparator),
+            Choice(&[Terminal("IDENTIFIER"), Token(Lex::PathSuper)]),
+        ]),
+    ),
+    // Statements
+    rule(
+        "statement",
+        Seq(&[
+            Choice(&[
+                Rule("let_bind"),
+                Rule("mutate"),
+                Rule("discard"),
+                Rule("defer"),
+                Rule("break_or_continue"),
+                Seq(&[
+                    Rule("expression"),
+                    Opt(&Seq(&[Token(Lex::Equals), Rule("expression")])),
+                ]),
+            ]),
+            Token(Lex::Semicolon),
+        ]),
+    ),
+    rule(
+        "let_bind",
+        Seq(&[
+            Token(Lex::Let),
+            Opt(&Token(Lex::Mut)),
+            Choice(&[
+                Seq(&[
+                    Rule("let_single"),
+                    Many(&Seq(&[Token(Lex::Comma), Rule("let_single")])),
+                ]),
+                Seq(&[
+                    Token(Lex::LParen),
+                    Rule("parameter_list"),
+                    Token(Lex::RParen),
+                    Token(Lex::Assign),
+                    Rule("routine_call_params"),
+                ]),
+            ]),
+        ]),
+    ),
+    rule(
+        "let_single",
+        Seq(&[
+            Rule("id_declaration"),
+            Token(Lex::Assign),
+            Choice(&[Rule("co_init"), Rule("expression")]),
+        ]),
+    ),
+    rule(
+        "co_init",
+        Seq(&[Token(Lex::Init), Rule("path"), Rule("routine_call_params")]),
+    ),
+    rule(
+        "mutate",
+        Seq(&[
+            Token(Lex::Mut),
+            Rule("expression"),
+            Token(Lex::Assign),
+            Rule("expression"),
+        ]),
+    ),
+    rule(
+        "discard",
+        Seq(&[
+            Token(Lex::Underscore),
+            Token(Lex::Assign),
+            Rule("expression"),
+        ]),
+    ),
+    rule("defer", Seq(&[Token(Lex::Defer), Rule("expression")])),
+    rule(
+        "break_or_continue",
+        Seq(&[OneOf(&[Lex::Break, Lex::Continue]), Opt(&Terminal("LABEL"))]),
+    ),
+    rule(
+        "return_stmt",
+        Seq(&[
+            Token(Lex::Return),
+            Opt(&Rule("expression")),
+            Token(Lex::Semicolon),
+        ]),
+    ),
+    rule(
+        "yield_return_stmt",
+        Seq(&[
+            Token(Lex::YieldReturn),
+            Opt(&Rule("expression")),
+            Token(Lex::Semicolon),
+        ]),
+    ),
+    // Expressions, from the lowest precedence to the highest
+    rule("expression", Rule("range")),
+    rule(
+        "range",
+        Seq(&[
+            Rule("logical_or"),
+            Opt(&Seq(&[Token(Lex::Range), Rule("range")])),
+        ]),
+    ),
+    rule(
+        "logical_or",
+        Seq(&[
+            Rule("logical_and"),
+            Opt(&Seq(&[Token(Lex::BOr), Rule("logical_or")])),
+        ]),
+    ),
+    rule(
+        "logical_and",
+        Seq(&[
+            Rule("comparison"),
+            Opt(&Seq(&[Token(Lex::BAnd), Rule("logical_and")])),
+        ]),
+    ),
+    rule(
+        "comparison",
+        Seq(&[
+            Rule("sum"),
+            Opt(&Seq(&[OneOf(COMPARISON_OPS), Rule("comparison")])),
+        ]),
+    ),
+    rule(
+        "sum",
+        Seq(&[Rule("term"), Opt(&Seq(&[OneOf(SUM_OPS), Rule("sum")]))]),
+    ),
+    rule(
+        "term",
+        Seq(&[Rule("cast"), Opt(&Seq(&[OneOf(PRODUCT_OPS), Rule("term")]))]),
+    ),
+    rule(
+        "cast",
+        Seq(&[Rule("negate"), Opt(&Seq(&[Token(Lex::As), Rule("type")]))]),
+    ),
+    rule(
+        "negate",
+        Choice(&[
+            Rule("address_of"),
+            Seq(&[OneOf(UNARY_OPS), Rule("negate")]),
+            Rule("subdata_access"),
+        ]),
+    ),
+    rule(
+        "address_of",
+        Seq(&[
+            Token(Lex::At),
+            OneOf(&[Lex::Const, Lex::Mut]),
+            Rule("subdata_access"),
+        ]),
+    ),
+    rule(
+        "subdata_access",
+        Seq(&[
+            Rule("factor"),
+            Many(&Choice(&[
+                Seq(&[
+                    Token(Lex::MemberAccess),
+                    Terminal("IDENTIFIER"),
+                    Opt(&Rule("routine_call_params")),
+                ]),
+                Seq(&[
+                    Token(Lex::LBracket),
+                    Rule("expression"),
+                    Token(Lex::RBracket),
+                ]),
+                Token(Lex::Question),
+            ])),
+        ]),
+    ),
+    rule(
+        "factor",
+        Choice(&[
+            Seq(&[
+                Token(Lex::LParen),
+                Opt(&Rule("expression")),
+                Token(Lex::RParen),
+            ]),
+            Rule("if_expression"),
+            Rule("type_builtin"),
+            Rule("len_builtin"),
+            Rule("constructor_builtin"),
+            Rule("arithmetic_builtin"),
+            Rule("byte_builtin"),
+            Rule("while_expression"),
+            Rule("for_expression"),
+            Rule("expression_block"),
+            Rule("macro_call"),
+            Rule("yield_expression"),
+            Rule("function_call_or_variable"),
+            Rule("constant"),
+            Rule("array_expression"),
+        ]),
+    ),
+    rule(
+        "type_builtin",
+        Seq(&[
+            OneOf(TYPE_BUILTINS),
+            Token(Lex::LParen),
+            Rule("type"),
+            Token(Lex::RParen),
+        ]),
+    ),
+    rule(
+        "len_builtin",
+        Seq(&[
+            Token(Lex::Len),
+            Token(Lex::LParen),
+            Rule("expression"),
+            Token(Lex::RParen),
+        ]),
+    ),
+    rule(
+        "constructor_builtin",
+        Seq(&[
+            OneOf(CONSTRUCTOR_BUILTINS),
+            Token(Lex::LParen),
+            Rule("expression"),
+            Token(Lex::RParen),
+        ]),
+    ),
+    rule(
+        "arithmetic_builtin",
+        Seq(&[
+            OneOf(ARITHMETIC_BUILTINS),
+            Token(Lex::LParen),
+            Rule("expression"),
+            Token(Lex::Comma),
+            Rule("expression"),
+            Opt(&Token(Lex::Comma)),
+            Token(Lex::RParen),
+        ]),
+    ),
+    rule(
+        "byte_builtin",
+        Seq(&[
+            OneOf(BYTE_BUILTINS),
+            Token(Lex::LParen),
+            Rule("expression"),
+            Token(Lex::RParen),
+        ]),
+    ),
+    rule(
+        "yield_expression",
+        Seq(&[Token(Lex::Yield), Rule("subdata_access")]),
+    ),
+    rule(
+        "if_expression",
+        Seq(&[
+            Token(Lex::If),
+            Token(Lex::LParen),
+            Choice(&[Rule("if_let_unwrap"), Rule("expression")]),
+            Token(Lex::RParen),
+            Rule("expression_block"),
+            Opt(&Seq(&[
+                Token(Lex::Else),
+                Choice(&[Rule("if_expression"), Rule("expression_block")]),
+            ])),
+        ]),
+    ),
+    rule(
+        "if_let_unwrap",
+        Seq(&[
+            Token(Lex::Let),
+            OneOf(CONSTRUCTOR_BUILTINS),
+            Token(Lex::LParen),
+            Terminal("IDENTIFIER"),
+            Token(Lex::RParen),
+            Token(Lex::Assign),
+            Rule("expression"),
+        ]),
+    ),
+    rule(
+        "while_expression",
+        Seq(&[
+            Opt(&Rule("loop_label")),
+            Token(Lex::While),
+            Token(Lex::LParen),
+            Rule("expression"),
+            Token(Lex::RParen),
+            Rule("expression_block"),
+        ]),
+    ),
+    rule(
+        "for_expression",
+        Seq(&[
+            Opt(&Rule("loop_label")),
+            Token(Lex::For),
+            Token(Lex::LParen),
+            Terminal("IDENTIFIER"),
+            Opt(&Seq(&[Token(Lex::Colon), Rule("type")])),
+            Token(Lex::In),
+            Rule("expression"),
+            Token(Lex::RParen),
+            Rule("expression_block"),
+        ]),
+    ),
+    rule("loop_label", Seq(&[Terminal("LABEL"), Token(Lex::Colon)])),
+    rule(
+        "expression_block",
+        Seq(&[
+            Token(Lex::LBrace),
+            Many(&Choice(&[Rule("statement"), Rule("return_stmt")])),
+            Opt(&Rule("expression")),
+            Token(Lex::RBrace),
+        ]),
+    ),
+    rule(
+        "macro_call",
+        Seq(&[
+            Terminal("IDENTIFIER"),
+            Token(Lex::Not),
+            Token(Lex::LParen),
+            Many(&Terminal("TOKEN")),
+            Token(Lex::RParen),
+        ]),
+    ),
+    rule(
+        "function_call_or_variable",
+        Choice(&[
+            Token(SELF),
+            Seq(&[
+                Rule("path"),
+                Opt(&Choice(&[
+                    Rule("routine_call_params"),
+                    Rule("struct_expression_params"),
+                ])),
+            ]),
+        ]),
+    ),
+    rule(
+        "routine_call_params",
+        Seq(&[
+            Token(Lex::LParen),
+            List(&Rule("expression")),
+            Token(Lex::RParen),
+        ]),
+    ),
+    rule(
+        "struct_expression_params",
+        Seq(&[
+            Token(Lex::LBrace),
+            List(&Seq(&[
+                Terminal("IDENTIFIER"),
+                Token(Lex::Colon),
+                Rule("expression"),
+            ])),
+            Token(Lex::RBrace),
+        ]),
+    ),
+    rule(
+        "array_expression",
+        Seq(&[
+            Token(Lex::LBracket),
+            List(&Rule("expression")),
+            Token(Lex::RBracket),
+        ]),
+    ),
+    rule(
+        "constant",
+        Choice(&[
+            Terminal("INTEGER"),
+            Terminal("FLOAT"),
+            Terminal("BOOLEAN"),
+            Terminal("STRING_LITERAL"),
+            Terminal("C_STRING_LITERAL"),
+            Token(Lex::Null),
+            Token(Lex::OptionNone),
+        ]),
+    ),
+];
+
+/// Renders the grammar of Bramble as EBNF.
+pub fn grammar() -> String {
+    let mut text = String::from("(* The grammar of Bramble *)\n\n");
+
+    for (name, description) in TERMINALS {
+        text.push_str(&format!("(* {}: {} *)\n", name, description));
+    }
+    text.push('\n');
+
+    for r in GRAMMAR {
+        let body = match &r.body {
+            // Each alternative of a rule is written on its own line
+            Choice(alts) => alts
+                .iter()
+                .map(|alt| render(alt, false))
+                .collect::<Vec<_>>()
+                .join("\n    | "),
+            body => render(body, false),
+        };
+        text.push_str(&format!("{} ::= {} ;\n", r.name, body));
+    }
+
+    text
+}
+
+/// Renders a symbol.  If `nested` is true then the symbol is part of a sequence and a
+/// choice must be put in parentheses.
+fn render(sym: &Sym, nested: bool) -> String {
+    let join = |syms: &[Sym], sep: &str| {
+        syms.iter()
+            .map(|s| render(s, true))
+            .collect::<Vec<_>>()
+            .join(sep)
+    };
+
+    match sym {
+        Token(lex) => format!("\"{}\"", lex),
+        OneOf(lexs) => {
+            let alts = lexs
+                .iter()
+                .map(|l| format!("\"{}\"", l))
+                .collect::<Vec<_>>()
+                .join(" | ");
+            if nested {
+                format!("( {} )", alts)
+            } else {
+                alts
+            }
+        }
+        Terminal(name) | Rule(name) => name.to_string(),
+        Seq(syms) => join(syms, " "),
+        Choice(alts) => {
+            let alts = alts
+                .iter()
+                .map(|s| render(s, false))
+                .collect::<Vec<_>>()
+                .join(" | ");
+            if nested {
+                format!("( {} )", alts)
+            } else {
+                alts
+            }
+        }
+        Opt(sym) => format!("[ {} ]", render(sym, false)),
+        Many(sym) => format!("{{ {} }}", render(sym, false)),
+        List(sym) => {
+            let item = render(sym, false);
+            format!("{{ {} \",\" }} [ {} ]", item, item)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::lexer::lexer::KEYWORDS;
+
+    /// Calls `f` with every rule which is referred to by `sym`
+    fn references(sym: &Sym, f: &mut dyn FnMut(&'static str)) {
+        match sym {
+            Rule(name) => f(*name),
+            Seq(syms) | Choice(syms) => {
+                for s in syms.iter() {
+                    references(s, f)
+                }
+            }
+            Opt(sym) | Many(sym) | List(sym) => references(sym, f),
+            Token(_) | OneOf(_) | Terminal(_) => (),
+        }
+    }
+
+    #[test]
+    fn every_rule_is_defined_once_and_used() {
+        let names: Vec<_> = GRAMMAR.iter().map(|r| r.name).collect();
+        for (idx, name) in names.iter().enumerate() {
+            assert!(
+                !names[idx + 1..].contains(name),
+                "{} is defined twice",
+                name
+            );
+        }
+
+        let mut used = vec![GRAMMAR[0].name];
+        for r in GRAMMAR {
+            references(&r.body, &mut |name| {
+                assert!(
+                    names.contains(&name),
+                    "{} refers to undefined rule {}",
+                    r.name,
+                    name
+                );
+                used.push(name);
+            });
+        }
+
+        for name in names {
+            assert!(used.contains(&name), "{} is never used", name);
+        }
+    }
+
+    #[test]
+    fn every_terminal_is_described() {
+        fn check(sym: &Sym) {
+            match sym {
+                Terminal(name) => assert!(
+                    TERMINALS.iter().any(|(t, _)| t == name),
+                    "{} is not described",
+                    name
+                ),
+                Seq(syms) | Choice(syms) => syms.iter().for_each(check),
+                Opt(sym) | Many(sym) | List(sym) => check(sym),
+                Token(_) | OneOf(_) | Rule(_) => (),
+            }
+        }
+
+        for r in GRAMMAR {
+            check(&r.body);
+        }
+    }
+
+    #[test]
+    fn every_keyword_is_in_the_grammar() {
+        let text = grammar();
+
+        for kw in KEYWORDS.iter() {
+            assert!(text.contains(&format!("\"{}\"", kw)), "{} is missing", kw);
+        }
+    }
+
+    #[test]
+    fn render_rules() {
+        let text = grammar();
+        for line in [
+            "sum ::= term [ ( \"+\" | \"-\" | \"@\" ) sum ] ;",
+            "routine_call_params ::= \"(\" { expression \",\" } [ expression ] \")\" ;",
+            "negate ::= address_of\n    | ( \"-\" | \"!\" | \"^\" ) negate\n    | subdata_access ;",
+            "option_type ::= \"opt\" type ;",
+        ] {
+            assert!(text.contains(line), "Expected:\n{}\nin:\n{}", line, text);
+        }
+    }
+}
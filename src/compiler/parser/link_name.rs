@@ -0,0 +1,143 @@
+//! Applies the `#[link_name]` attributes of externs.
+//!
+//! `#[link_name = "SDL_Init"] extern fn start(flags: u32) -> i32;` declares an extern
+//! which is called as `start` from Bramble, but which is linked to the C symbol
+//! `SDL_Init`.  Without the attribute an extern is linked to the symbol with the same
+//! name as the extern.
+
+use crate::{
+    compiler::{
+        ast::{Extern, Item, Module},
+        source::SourceIr,
+        CompilerError,
+    },
+    StringTable,
+};
+
+use super::{ParserContext, ParserError};
+
+type LinkNameResult<T> = Result<T, CompilerError<ParserError>>;
+
+/// Sets the link name of every extern in the given module, and its submodules, which
+/// has a `#[link_name]` attribute.
+pub fn apply_link_names(m: &mut Module<ParserContext>, st: &StringTable) -> LinkNameResult<()> {
+    for child in m.get_modules_mut() {
+        apply_link_names(child, st)?;
+    }
+
+    for item in m.get_externs_mut() {
+        if let Item::Extern(ex) = item {
+            apply_link_name(ex, st)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_link_name(ex: &mut Extern<ParserContext>, st: &StringTable) -> LinkNameResult<()> {
+    let link_name = st.insert("link_name".into());
+
+    for attr in ex.get_attributes().clone() {
+        if attr.get_name() != link_name {
+            return Err(CompilerError::new(
+                attr.span(),
+                ParserError::UnknownAttribute(attr.get_name()),
+            ));
+        }
+
+        match attr.get_value() {
+            Some(symbol) if attr.get_args().is_empty() => ex.set_link_name(symbol),
+            _ => {
+                return Err(CompilerError::new(
+                    attr.span(),
+                    ParserError::LinkNameExpectedValue,
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compiler::{
+            ast::{Item, Module},
+            diagnostics::Logger,
+            lexer::tokens::Token,
+            parser::{Parser, ParserContext},
+            CompilerDisplay, Lexer, SourceMap,
+        },
+        StringTable,
+    };
+
+    use super::apply_link_names;
+
+    fn parse(text: &str, sm: &mut SourceMap, table: &StringTable) -> Module<ParserContext> {
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let parser = Parser::new(&logger);
+        parser
+            .parse(table.insert("test".into()), &tokens)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn link_name() {
+        let table = StringTable::new();
+        let mut sm = SourceMap::new();
+        let mut m = parse(
+            "#[link_name = \"SDL_Init\"] extern fn start(flags: u32) -> i32;\n\
+            extern fn puts(s: string) -> i32;",
+            &mut sm,
+            &table,
+        );
+        apply_link_names(&mut m, &table).unwrap();
+
+        let start = table.find("start").unwrap();
+        let sdl_init = table.find("SDL_Init").unwrap();
+        let puts = table.find("puts").unwrap();
+        match m.get_item(start) {
+            Some(Item::Extern(ex)) => assert_eq!(ex.link_name(), sdl_init),
+            _ => panic!("Expected start to be an extern"),
+        }
+        match m.get_item(puts) {
+            Some(Item::Extern(ex)) => assert_eq!(ex.link_name(), puts),
+            _ => panic!("Expected puts to be an extern"),
+        }
+    }
+
+    #[test]
+    fn link_name_fails() {
+        for (text, msg) in [
+            (
+                "#[inline] extern fn start(flags: u32) -> i32;",
+                "L1: Unknown attribute inline",
+            ),
+            (
+                "#[link_name] extern fn start(flags: u32) -> i32;",
+                "L1: link_name expects the name of a symbol, e.g. #[link_name = \"SDL_Init\"]",
+            ),
+            (
+                "#[link_name(SDL_Init)] extern fn start(flags: u32) -> i32;",
+                "L1: link_name expects the name of a symbol, e.g. #[link_name = \"SDL_Init\"]",
+            ),
+        ] {
+            let table = StringTable::new();
+            let mut sm = SourceMap::new();
+            let mut m = parse(text, &mut sm, &table);
+            let err = apply_link_names(&mut m, &table).unwrap_err();
+            assert_eq!(err.fmt(&sm, &table).unwrap(), msg, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,579 @@
+//! Expands calls to macros into the expressions that they produce.
+//!
+//! A macro is defined with a list of rules, each of which has a pattern and a body:
+//!
+//! ```text
+//! macro max {
+//!     ($a:expr, $b:expr) => { let a: i64 := $a; let b: i64 := $b; if (a > b) {a} else {b} };
+//! }
+//! ```
+//!
+//! A call, `max!(x, y + 1)`, is replaced with the body of the first rule whose pattern
+//! matches the tokens between the parentheses, with each `$name` in the body replaced
+//! by the tokens that the fragment `name` matched.  An `expr` fragment is wrapped in
+//! parentheses so that it keeps its meaning wherever it is used in the body.  The body
+//! becomes a block, which is parsed and then expanded again, so a macro can call other
+//! macros and itself, up to [`RECURSION_LIMIT`] expansions deep.
+//!
+//! Expansion is hygienic: a variable which is bound by the body of a rule is renamed
+//! for each expansion, so it cannot capture or shadow a variable used by the arguments.
+//! Every token from the body of a rule is given the span of the call, so errors in an
+//! expansion are reported where the macro was called.
+//!
+//! Macros are visible in the module which defines them and all of its submodules, and
+//! they are expanded before derives are generated and before types are resolved.
+
+use std::collections::HashMap;
+
+use crate::{
+    compiler::{
+        ast::{
+            Context, Expression, Item, MacroDef, MacroPattern, MacroRule, Module, Node, Statement,
+        },
+        diagnostics::Logger,
+        fuel::Fuel,
+        lexer::tokens::{Lex, Token},
+        source::SourceIr,
+        CompilerError, Span,
+    },
+    StringId, StringTable,
+};
+
+use super::{Parser, ParserContext, ParserError};
+
+type MacroResult<T> = Result<T, CompilerError<ParserError>>;
+
+/// The maximum depth of macro calls which expand into other macro calls
+pub const RECURSION_LIMIT: usize = 64;
+
+/// Replaces every macro call in the given module, and its submodules, with the
+/// expression which the call expands into.
+pub fn expand_macros<'a>(
+    m: &mut Module<ParserContext>,
+    st: &'a StringTable,
+    logger: &'a Logger<'a>,
+    fuel: &'a Fuel,
+) -> MacroResult<()> {
+    let mut expander = Expander {
+        parser: Parser::new(logger).with_fuel(fuel),
+        st,
+        scope: vec![],
+        expr: st.insert("expr".into()),
+        ident: st.insert("ident".into()),
+        expansions: 0,
+    };
+    expander.module(m)
+}
+
+struct Expander<'a> {
+    parser: Parser<'a>,
+    st: &'a StringTable,
+
+    /// The macros defined by the module being expanded and each of its ancestors
+    scope: Vec<Vec<MacroDef>>,
+
+    /// The names of the fragment kinds
+    expr: StringId,
+    ident: StringId,
+
+    /// The number of expansions so far, which makes the names of hygienic variables
+    /// unique
+    expansions: usize,
+}
+
+impl<'a> Expander<'a> {
+    fn module(&mut self, m: &mut Module<ParserContext>) -> MacroResult<()> {
+        for mac in m.get_macros() {
+            self.check_fragments(mac)?;
+        }
+
+        self.scope.push(m.get_macros().to_vec());
+
+        for child in m.get_modules_mut() {
+            self.module(child)?;
+        }
+
+        self.items(m.get_functions_mut())?;
+        self.items(m.get_coroutines_mut())?;
+        for routine in m.get_methods_mut() {
+            for stm in routine.get_body_mut() {
+                self.statement(stm, 0)?;
+            }
+        }
+
+        self.scope.pop();
+        Ok(())
+    }
+
+    /// Checks that every fragment in the patterns of the given macro has a known kind
+    fn check_fragments(&self, mac: &MacroDef) -> MacroResult<()> {
+        for rule in mac.get_rules() {
+            for p in rule.get_pattern() {
+                match p {
+                    MacroPattern::Fragment(_, kind)
+                        if *kind != self.expr && *kind != self.ident =>
+                    {
+                        return Err(CompilerError::new(
+                            mac.span(),
+                            ParserError::MacroUnknownFragment(*kind),
+                        ))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn items(&mut self, items: &mut [Item<ParserContext>]) -> MacroResult<()> {
+        for item in items {
+            if let Item::Routine(routine) = item {
+                for stm in routine.get_body_mut() {
+                    self.statement(stm, 0)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, stm: &mut Statement<ParserContext>, depth: usize) -> MacroResult<()> {
+        match stm {
+            Statement::Bind(bind) => self.expression(bind.get_rhs_mut(), depth),
+            Statement::Mutate(mutate) => self.expression(mutate.get_rhs_mut(), depth),
+            Statement::Discard(discard) => self.expression(discard.get_value_mut(), depth),
+            Statement::Defer(defer) => self.expression(defer.get_value_mut(), depth),
+            Statement::Break(_) | Statement::Continue(_) => Ok(()),
+            Statement::Expression(exp) => self.expression(exp, depth),
+            Statement::YieldReturn(yr) => match yr.get_value_mut() {
+                Some(value) => self.expression(value, depth),
+                None => Ok(()),
+            },
+            Statement::Return(ret) => match ret.get_value_mut() {
+                Some(value) => self.expression(value, depth),
+                None => Ok(()),
+            },
+        }
+    }
+
+    fn expression(&mut self, exp: &mut Expression<ParserContext>, depth: usize) -> MacroResult<()> {
+        match exp {
+            Expression::MacroCall(ctx, name, args) => {
+                let expanded = self.expand(*ctx, *name, args, depth)?;
+                *exp = expanded;
+                Ok(())
+            }
+            Expression::ArrayExpression(_, elements, _) => {
+                for el in elements {
+                    self.expression(el, depth)?
+                }
+                Ok(())
+            }
+            Expression::ArrayAt { array, index, .. } => {
+                self.expression(array, depth)?;
+                self.expression(index, depth)
+            }
+            Expression::MemberAccess(_, src, _)
+            | Expression::TypeCast(_, src, _)
+            | Expression::UnaryOp(_, _, src)
+            | Expression::Yield(_, src) => self.expression(src, depth),
+            Expression::RoutineCall(_, _, _, args) => {
+                for arg in args {
+                    self.expression(arg, depth)?
+                }
+                Ok(())
+            }
+            Expression::StructExpression(_, _, fields) => {
+                for (_, value) in fields {
+                    self.expression(value, depth)?
+                }
+                Ok(())
+            }
+            Expression::If {
+                cond,
+                if_arm,
+                else_arm,
+                ..
+            } => {
+                self.expression(cond, depth)?;
+                self.expression(if_arm, depth)?;
+                match else_arm {
+                    Some(else_arm) => self.expression(else_arm, depth),
+                    None => Ok(()),
+                }
+            }
+            Expression::While { cond, body, .. } => {
+                self.expression(cond, depth)?;
+                self.expression(body, depth)
+            }
+            Expression::ExpressionBlock(_, body, final_exp) => {
+                for stm in body {
+                    self.statement(stm, depth)?
+                }
+                match final_exp {
+                    Some(final_exp) => self.expression(final_exp, depth),
+                    None => Ok(()),
+                }
+            }
+            Expression::BinaryOp(_, _, l, r) => {
+                self.expression(l, depth)?;
+                self.expression(r, depth)
+            }
+            Expression::Null(_)
+            | Expression::OptionNone(_)
+            | Expression::U8(..)
+            | Expression::U16(..)
+            | Expression::U32(..)
+            | Expression::U64(..)
+            | Expression::I8(..)
+            | Expression::I16(..)
+            | Expression::I32(..)
+            | Expression::I64(..)
+            | Expression::F64(..)
+            | Expression::Boolean(..)
+            | Expression::StringLiteral(..)
+            | Expression::SizeOf(..)
+            | Expression::FieldsOf(..)
+            | Expression::NameOf(..)
+            | Expression::CustomType(..)
+            | Expression::Identifier(..)
+            | Expression::Path(..)
+            | Expression::IdentifierDeclare(..) => Ok(()),
+        }
+    }
+
+    /// Expands a single call to a macro and then expands every macro call in the result
+    fn expand(
+        &mut self,
+        ctx: ParserContext,
+        name: StringId,
+        args: &[Token],
+        depth: usize,
+    ) -> MacroResult<Expression<ParserContext>> {
+        if depth >= RECURSION_LIMIT {
+            return err!(
+                ctx.span(),
+                ParserError::MacroRecursionLimit(name, RECURSION_LIMIT)
+            );
+        }
+
+        let mac = self
+            .lookup(name)
+            .ok_or_else(|| CompilerError::new(ctx.span(), ParserError::MacroNotDefined(name)))?;
+
+        let (rule, captures) = mac
+            .get_rules()
+            .iter()
+            .find_map(|rule| self.match_rule(rule, args).map(|c| (rule, c)))
+            .ok_or_else(|| {
+                CompilerError::new(ctx.span(), ParserError::MacroNoMatchingRule(name))
+            })?;
+
+        let tokens = self.substitute(rule, &captures, ctx.span());
+        let mut exp = self.parser.parse_expression(&tokens)?.ok_or_else(|| {
+            CompilerError::new(ctx.span(), ParserError::MacroExpectedExpression(name))
+        })?;
+        *exp.get_context_mut() = ctx;
+
+        self.expression(&mut exp, depth + 1)?;
+        Ok(exp)
+    }
+
+    /// Finds the macro with the given name in the closest module which defines it
+    fn lookup(&self, name: StringId) -> Option<MacroDef> {
+        self.scope
+            .iter()
+            .rev()
+            .flatten()
+            .find(|m| m.get_name() == name)
+            .cloned()
+    }
+
+    /// If the given arguments match the pattern of the rule, then this returns the
+    /// tokens which each fragment matched.
+    fn match_rule(
+        &self,
+        rule: &MacroRule,
+        args: &[Token],
+    ) -> Option<HashMap<StringId, Vec<Token>>> {
+        let pattern = rule.get_pattern();
+        let mut captures = HashMap::new();
+        let mut idx = 0;
+
+        for (pidx, p) in pattern.iter().enumerate() {
+            match p {
+                MacroPattern::Token(lex) => match args.get(idx) {
+                    Some(token) if token.sym == *lex => idx += 1,
+                    _ => return None,
+                },
+                MacroPattern::Fragment(name, kind) if *kind == self.ident => match args.get(idx) {
+                    Some(
+                        token @ Token {
+                            sym: Lex::Identifier(_),
+                            ..
+                        },
+                    ) => {
+                        captures.insert(*name, vec![*token]);
+                        idx += 1;
+                    }
+                    _ => return None,
+                },
+                MacroPattern::Fragment(name, _) => {
+                    // An expression is every token up to the next token of the pattern,
+                    // or a comma or semicolon, which is not nested inside of brackets
+                    let end = pattern.get(pidx + 1).and_then(|p| match p {
+                        MacroPattern::Token(lex) => Some(*lex),
+                        MacroPattern::Fragment(..) => None,
+                    });
+                    let start = idx;
+                    let mut nesting = 0;
+                    while let Some(token) = args.get(idx) {
+                        match token.sym {
+                            sym if nesting == 0
+                                && (Some(sym) == end
+                                    || sym == Lex::Comma
+                                    || sym == Lex::Semicolon) =>
+                            {
+                                break
+                            }
+                            Lex::LParen | Lex::LBrace | Lex::LBracket => nesting += 1,
+                            Lex::RParen | Lex::RBrace | Lex::RBracket => nesting -= 1,
+                            _ => (),
+                        }
+                        idx += 1;
+                    }
+
+                    if idx == start {
+                        return None;
+                    }
+
+                    let span = Span::cover(args[start].span(), args[idx - 1].span());
+                    let mut tokens = vec![Token::new(Lex::LParen, span)];
+                    tokens.extend_from_slice(&args[start..idx]);
+                    tokens.push(Token::new(Lex::RParen, span));
+                    captures.insert(*name, tokens);
+                }
+            }
+        }
+
+        if idx == args.len() {
+            Some(captures)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the tokens of the block which the rule expands into.  Each `$name` is
+    /// replaced by the tokens that it captured and every variable which is bound by the
+    /// body is renamed.
+    fn substitute(
+        &mut self,
+        rule: &MacroRule,
+        captures: &HashMap<StringId, Vec<Token>>,
+        span: Span,
+    ) -> Vec<Token> {
+        self.expansions += 1;
+        let body = rule.get_body();
+        let renames = self.hygienic_names(body);
+
+        let mut tokens = vec![Token::new(Lex::LBrace, span)];
+        let mut idx = 0;
+        while idx < body.len() {
+            let prev = idx.checked_sub(1).map(|p| body[p].sym);
+            match (body[idx].sym, body.get(idx + 1).map(|t| t.sym)) {
+                (Lex::Dollar, Some(Lex::Identifier(name))) if captures.contains_key(&name) => {
+                    tokens.extend_from_slice(&captures[&name]);
+                    idx += 2;
+                    continue;
+                }
+                // Fields and path elements are not variables, so they are not renamed
+                (Lex::Identifier(id), _)
+                    if renames.contains_key(&id)
+                        && prev != Some(Lex::MemberAccess)
+                        && prev != Some(Lex::PathSeparator) =>
+                {
+                    tokens.push(Token::new(Lex::Identifier(renames[&id]), span))
+                }
+                (sym, _) => tokens.push(Token::new(sym, span)),
+            }
+            idx += 1;
+        }
+        tokens.push(Token::new(Lex::RBrace, span));
+        tokens
+    }
+
+    /// Gives every variable bound by `let` in the body of a rule a name which cannot
+    /// be written in source code, so that it is unique to this expansion.
+    fn hygienic_names(&self, body: &[Token]) -> HashMap<StringId, StringId> {
+        let mut renames = HashMap::new();
+        for (idx, token) in body.iter().enumerate() {
+            if token.sym != Lex::Let {
+                continue;
+            }
+
+            let var = match body.get(idx + 1).map(|t| t.sym) {
+                Some(Lex::Mut) => body.get(idx + 2).map(|t| t.sym),
+                sym => sym,
+            };
+
+            if let Some(Lex::Identifier(id)) = var {
+                let name = self.st.get(id).unwrap_or_default();
+                let hygienic = self.st.insert(format!("{}#{}", name, self.expansions));
+                renames.insert(id, hygienic);
+            }
+        }
+        renames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compiler::{
+            ast::{BinaryOperator, Expression, Item, Statement},
+            diagnostics::Logger,
+            fuel::Fuel,
+            lexer::tokens::Token,
+            parser::{Parser, ParserError},
+            Lexer, SourceMap,
+        },
+        StringTable,
+    };
+
+    use super::expand_macros;
+
+    fn expand(text: &str) -> Result<Expression<super::ParserContext>, ParserError> {
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let table = StringTable::new();
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let parser = Parser::new(&logger);
+        let mut module = parser
+            .parse(table.insert("test".into()), &tokens)
+            .unwrap()
+            .unwrap();
+        expand_macros(&mut module, &table, &logger, &Fuel::unlimited()).map_err(|e| e.take().1)?;
+
+        let test = table.insert("test".into());
+        match module.get_item(test) {
+            Some(Item::Routine(r)) => match r.get_body()[0].clone() {
+                Statement::Return(ret) => Ok(ret.get_value().clone().unwrap()),
+                _ => panic!("Expected a return"),
+            },
+            _ => panic!("Expected a routine"),
+        }
+    }
+
+    #[test]
+    fn expand_expression() {
+        let exp = expand(
+            "macro square { ($x:expr) => { $x * $x }; }
+            fn test() -> i64 { return square!(2 + 3); }",
+        )
+        .unwrap();
+
+        // { (2 + 3) * (2 + 3) }
+        match exp {
+            Expression::ExpressionBlock(_, body, Some(fe)) => {
+                assert!(body.is_empty());
+                match *fe {
+                    Expression::BinaryOp(_, BinaryOperator::Mul, l, r) => {
+                        assert!(matches!(
+                            *l,
+                            Expression::BinaryOp(_, BinaryOperator::Add, ..)
+                        ));
+                        assert!(matches!(
+                            *r,
+                            Expression::BinaryOp(_, BinaryOperator::Add, ..)
+                        ));
+                    }
+                    _ => panic!("Expected a multiplication"),
+                }
+            }
+            _ => panic!("Expected a block"),
+        }
+    }
+
+    #[test]
+    fn expand_rules_in_order() {
+        let exp = expand(
+            "macro pick { (first $x:expr) => { 1 }; ($x:expr) => { 2 }; }
+            fn test() -> i64 { return pick!(first 5); }",
+        )
+        .unwrap();
+
+        match exp {
+            Expression::ExpressionBlock(_, _, Some(fe)) => {
+                assert!(matches!(*fe, Expression::I64(_, 1)))
+            }
+            _ => panic!("Expected a block"),
+        }
+    }
+
+    #[test]
+    fn expand_hygiene() {
+        let exp = expand(
+            "macro double { ($x:expr) => { let y: i64 := $x; y + y }; }
+            fn test() -> i64 { return double!(y); }",
+        )
+        .unwrap();
+
+        // { let y#1: i64 := (y); y#1 + y#1 }
+        match exp {
+            Expression::ExpressionBlock(_, body, Some(fe)) => {
+                let bound = match &body[0] {
+                    Statement::Bind(bind) => match bind.get_rhs() {
+                        Expression::Identifier(_, y) => {
+                            assert_ne!(bind.get_id(), *y);
+                            bind.get_id()
+                        }
+                        _ => panic!("Expected the argument"),
+                    },
+                    _ => panic!("Expected a bind"),
+                };
+                match *fe {
+                    Expression::BinaryOp(_, BinaryOperator::Add, l, _) => {
+                        assert!(matches!(*l, Expression::Identifier(_, id) if id == bound))
+                    }
+                    _ => panic!("Expected an addition"),
+                }
+            }
+            _ => panic!("Expected a block"),
+        }
+    }
+
+    #[test]
+    fn expand_fails() {
+        for (text, expected) in [
+            ("fn test() -> i64 { return nope!(1); }", "MacroNotDefined"),
+            (
+                "macro m { (a) => { 1 }; } fn test() -> i64 { return m!(b); }",
+                "MacroNoMatchingRule",
+            ),
+            (
+                "macro m { ($x:stmt) => { 1 }; } fn test() -> i64 { return m!(b); }",
+                "MacroUnknownFragment",
+            ),
+            (
+                "macro m { ($x:expr) => { m!($x) }; } fn test() -> i64 { return m!(1); }",
+                "MacroRecursionLimit",
+            ),
+        ] {
+            let err = expand(text).unwrap_err();
+            assert!(
+                format!("{:?}", err).starts_with(expected),
+                "{}: {:?}",
+                text,
+                err
+            );
+        }
+    }
+}
@@ -1,5 +1,9 @@
 mod context;
+mod derive;
 mod error;
+mod grammar;
+mod link_name;
+mod macros;
 mod statement;
 mod tests;
 mod tokenstream;
@@ -7,11 +11,16 @@ mod tokenstream;
 pub mod expression;
 pub mod parser;
 
-pub use context::ParserContext;
+pub use context::{number_nodes, ParserContext};
+pub use derive::expand_derives;
 pub use error::ParserError;
+pub use grammar::grammar;
+pub use link_name::apply_link_names;
+pub use macros::expand_macros;
 
 use super::{
     diagnostics::{EventStack, Logger},
+    fuel::Fuel,
     lexer::tokens::Token,
     CompilerError,
 };
@@ -30,6 +39,9 @@ fn ctx_over_tokens(tokens: &[Token]) -> Option<ParserContext> {
 pub struct Parser<'a> {
     logger: &'a Logger<'a>,
     event_stack: EventStack,
+
+    /// Limits how much work the parser will do, if it is set
+    fuel: Option<&'a Fuel>,
 }
 
 impl<'a> Parser<'a> {
@@ -37,6 +49,14 @@ impl<'a> Parser<'a> {
         Parser {
             logger,
             event_stack: EventStack::new(),
+            fuel: None,
         }
     }
+
+    /// Limits the parser to the given fuel, which it burns for every expression and
+    /// statement that it tries to parse.
+    pub fn with_fuel(mut self, fuel: &'a Fuel) -> Parser<'a> {
+        self.fuel = Some(fuel);
+        self
+    }
 }
@@ -23,6 +23,18 @@ impl<'a> Parser<'a> {
         Event::new("parser", span, self.event_stack.clone())
     }
 
+    /// Burns a unit of fuel, if the parser is limited to some fuel.  Returns an error at
+    /// the next token if the fuel has run out.
+    pub(super) fn burn(&self, stream: &TokenStream) -> Result<(), CompilerError<ParserError>> {
+        match self.fuel.map(|fuel| fuel.burn(1)) {
+            Some(Err(err)) => {
+                let span = stream.peek().map_or(Span::zero(), |t| t.span());
+                err!(span, ParserError::ResourceExhausted(err))
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Support function which records parser events to the tracing system
     pub(super) fn record<V: Writable>(
         &self,
@@ -66,19 +78,17 @@ impl<'a> Parser<'a> {
                         CompilerError::new(Span::zero(), ParserError::EmptyProject)
                     })?;
 
-                    while stream.peek().is_some() {
-                        let start_index = stream.index();
-                        self.parse_items_into(&mut stream, &mut module)?;
+                    self.parse_items_into(&mut stream, &mut module)?;
 
-                        if stream.index() == start_index {
-                            return err!(
-                                stream.peek().unwrap().span(),
-                                ParserError::Locked(stream.peek().map(|t| t.clone()))
-                            );
-                        }
+                    // Items are parsed until a token which does not start an item is
+                    // found, and the file must end there
+                    match stream.peek() {
+                        Some(token) => err!(
+                            token.span(),
+                            ParserError::ModuleUnexpectedToken(token.clone())
+                        ),
+                        None => Ok(Some(module)),
                     }
-
-                    Ok(Some(module))
                 })
         });
         result.view(|v| {
@@ -87,6 +97,20 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parses the given tokens as a single expression.  Returns `None` if the tokens are
+    /// not exactly one expression.
+    pub fn parse_expression(&self, tokens: &Vec<Token>) -> ParserResult<Expression<ParserContext>> {
+        let mut stream = match TokenStream::new(tokens, self.logger) {
+            Some(stream) => stream,
+            None => return Ok(None),
+        };
+
+        match self.expression(&mut stream)? {
+            Some(exp) if stream.peek().is_none() => Ok(Some(exp)),
+            _ => Ok(None),
+        }
+    }
+
     fn module(&self, stream: &mut TokenStream) -> ParserResult<Module<ParserContext>> {
         let (event, result) =
             self.new_event(Span::zero())
@@ -98,6 +122,17 @@ impl<'a> Parser<'a> {
 
                             self.parse_items_into(stream, &mut module)?;
 
+                            // The module must end at the first token which does not start an item
+                            match stream.peek() {
+                                Some(token) if token.sym != Lex::RBrace => {
+                                    return err!(
+                                        token.span(),
+                                        ParserError::ModuleUnexpectedToken(token.clone())
+                                    )
+                                }
+                                _ => (),
+                            }
+
                             let ctx = stream
                                 .next_must_be(&Lex::RBrace)?
                                 .to_ctx()
@@ -120,14 +155,34 @@ impl<'a> Parser<'a> {
         stream: &mut TokenStream,
         module: &mut Module<ParserContext>,
     ) -> ParserResult<()> {
-        if let Some((submods, items)) = self.parse_items(stream)? {
+        if let Some((submods, items, impls, uses, macros, asserts)) = self.parse_items(stream)? {
             for sm in submods {
                 module.add_module(sm);
             }
 
+            for sa in asserts {
+                module.add_static_assert(sa);
+            }
+
+            for m in macros {
+                module.add_macro(m)?;
+            }
+
+            for u in uses {
+                module.add_use(u);
+            }
+
             for item in items {
                 module.add_item(item)?;
             }
+
+            // Methods are added after every item so that an impl block can come before
+            // the structure which it is for
+            for (struct_name, methods) in impls {
+                for m in methods {
+                    module.add_method(struct_name, m)?;
+                }
+            }
         }
 
         Ok(Some(()))
@@ -136,44 +191,249 @@ impl<'a> Parser<'a> {
     fn parse_items(
         &self,
         stream: &mut TokenStream,
-    ) -> ParserResult<(Vec<Module<ParserContext>>, Vec<Item<ParserContext>>)> {
+    ) -> ParserResult<(
+        Vec<Module<ParserContext>>,
+        Vec<Item<ParserContext>>,
+        Vec<(StringId, Vec<RoutineDef<ParserContext>>)>,
+        Vec<Use>,
+        Vec<MacroDef>,
+        Vec<StaticAssert<ParserContext>>,
+    )> {
         let mut modules = vec![];
         let mut items = vec![];
+        let mut impls = vec![];
+        let mut uses = vec![];
+        let mut macros = vec![];
+        let mut asserts = vec![];
         while stream.peek().is_some() {
             let start_index = stream.index();
+
+            // Attributes annotate the item which follows them
+            let attributes = self.attributes(stream)?;
+            if let Some(first) = attributes.first() {
+                if let Some(mut s) = self.struct_def(stream)? {
+                    *s.get_attributes_mut() = attributes;
+                    items.push(Item::Struct(s));
+                    continue;
+                }
+
+                match self.extern_def(stream)? {
+                    Some(mut e) => {
+                        *e.get_attributes_mut() = attributes;
+                        items.push(Item::Extern(e));
+                        continue;
+                    }
+                    None => return err!(first.span(), ParserError::AttributeExpectedItem),
+                }
+            }
+
+            if let Some(u) = self.use_decl(stream)? {
+                uses.push(u);
+            }
+
             if let Some(m) = self.module(stream)? {
                 modules.push(m);
             }
 
-            if let Some(f) = self.function_def(stream)? {
+            if let Some(f) = self.function_def(stream, None)? {
                 items.push(Item::Routine(f));
             }
 
+            if let Some(c) = self.coroutine_def(stream)? {
+                items.push(Item::Routine(c));
+            }
+
             if let Some(s) = self.struct_def(stream)? {
                 items.push(Item::Struct(s));
             }
 
+            if let Some((_, struct_name, methods)) = self.impl_def(stream)? {
+                impls.push((struct_name, methods));
+            }
+
             if let Some(e) = self.extern_def(stream)? {
                 items.push(Item::Extern(e));
             }
 
+            if let Some(m) = self.macro_def(stream)? {
+                macros.push(m);
+            }
+
+            if let Some(sa) = self.static_assert(stream)? {
+                asserts.push(sa);
+            }
+
             if stream.index() == start_index {
                 break;
             }
         }
 
-        if modules.is_empty() && items.is_empty() {
+        if modules.is_empty()
+            && items.is_empty()
+            && impls.is_empty()
+            && uses.is_empty()
+            && macros.is_empty()
+            && asserts.is_empty()
+        {
             Ok(None)
         } else {
-            Ok(Some((modules, items)))
+            Ok(Some((modules, items, impls, uses, macros, asserts)))
         }
     }
 
+    /// Parses a static assertion: `static_assert(cond, "message");`
+    fn static_assert(&self, stream: &mut TokenStream) -> ParserResult<StaticAssert<ParserContext>> {
+        let (event, result) =
+            self.new_event(Span::zero())
+                .and_then(|| match stream.next_if(&Lex::StaticAssert) {
+                    Some(sa_tok) => {
+                        stream.next_must_be(&Lex::LParen)?;
+                        let cond = self.expression(stream)?;
+                        let msg = match stream.next_if(&Lex::Comma) {
+                            Some(_) => self.string_literal(stream)?,
+                            None => None,
+                        };
+                        match (cond, msg) {
+                            (Some(cond), Some(Expression::StringLiteral(_, msg))) => {
+                                stream.next_must_be(&Lex::RParen)?;
+                                let end = stream.next_must_be(&Lex::Semicolon)?;
+                                let span = Span::cover(sa_tok.span(), end.span());
+                                Ok(Some(StaticAssert::new(span, cond, msg)))
+                            }
+                            _ => err!(sa_tok.span(), ParserError::StaticAssertExpectedArgs),
+                        }
+                    }
+                    None => Ok(None),
+                });
+        result.view(|v| {
+            let msg = v.map(|_| "Static Assert");
+            self.record(event.with_span(v.span()), msg)
+        })
+    }
+
+    /// Parses a macro definition, which is a list of rules that each match a pattern
+    /// of tokens: `macro name { ($x:expr) => { $x * $x }; }`.
+    fn macro_def(&self, stream: &mut TokenStream) -> ParserResult<MacroDef> {
+        let (event, result) =
+            self.new_event(Span::zero())
+                .and_then(|| match stream.next_if(&Lex::Macro) {
+                    Some(macro_tok) => match stream.next_if_id() {
+                        Some((name, _)) => {
+                            stream.next_must_be(&Lex::LBrace)?;
+                            let mut rules = vec![];
+                            while let Some(lparen) = stream.next_if(&Lex::LParen) {
+                                let (pattern, _) =
+                                    self.delimited_tokens(stream, &lparen, &Lex::RParen)?;
+                                stream.next_must_be(&Lex::FatArrow)?;
+                                let lbrace = stream.next_must_be(&Lex::LBrace)?;
+                                let (body, _) =
+                                    self.delimited_tokens(stream, &lbrace, &Lex::RBrace)?;
+                                stream.next_if(&Lex::Semicolon);
+                                rules.push(MacroRule::new(self.macro_pattern(&pattern)?, body));
+                            }
+
+                            if rules.is_empty() {
+                                return err!(macro_tok.span(), ParserError::MacroExpectedRule);
+                            }
+
+                            let end = stream.next_must_be(&Lex::RBrace)?;
+                            let span = Span::cover(macro_tok.span(), end.span());
+                            Ok(Some(MacroDef::new(span, name, rules)))
+                        }
+                        None => err!(macro_tok.span(), ParserError::MacroExpectedName),
+                    },
+                    None => Ok(None),
+                });
+        result.view(|v| {
+            let msg = v.map(|_| "Macro Definition");
+            self.record(event.with_span(v.span()), msg)
+        })
+    }
+
+    /// Converts the tokens of a macro rule's pattern into the elements which are matched
+    /// against the arguments of a call.
+    fn macro_pattern(
+        &self,
+        tokens: &[Token],
+    ) -> Result<Vec<MacroPattern>, CompilerError<ParserError>> {
+        let mut pattern = vec![];
+        let mut idx = 0;
+        while idx < tokens.len() {
+            if tokens[idx].sym != Lex::Dollar {
+                pattern.push(MacroPattern::Token(tokens[idx].sym));
+                idx += 1;
+                continue;
+            }
+
+            match tokens
+                .get(idx + 1..idx + 4)
+                .map(|f| (f[0].sym, f[1].sym, f[2].sym))
+            {
+                Some((Lex::Identifier(name), Lex::Colon, Lex::Identifier(kind))) => {
+                    pattern.push(MacroPattern::Fragment(name, kind));
+                    idx += 4;
+                }
+                _ => return err!(tokens[idx].span(), ParserError::MacroExpectedFragment),
+            }
+        }
+        Ok(pattern)
+    }
+
+    /// Returns every token up to the token which closes the given opening token, and
+    /// the closing token.  The closing token is consumed but is not in the returned
+    /// tokens.
+    pub(super) fn delimited_tokens(
+        &self,
+        stream: &mut TokenStream,
+        open: &Token,
+        close: &Lex,
+    ) -> Result<(Vec<Token>, Token), CompilerError<ParserError>> {
+        let mut tokens = vec![];
+        let mut depth = 0;
+        while let Some(token) = stream.peek() {
+            if token.sym == open.sym {
+                depth += 1;
+            } else if token.sym == *close {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+            }
+            tokens.push(*token);
+            stream.next();
+        }
+
+        let close = stream.next_must_be(close)?;
+        Ok((tokens, close))
+    }
+
+    fn use_decl(&self, stream: &mut TokenStream) -> ParserResult<Use> {
+        let (event, result) =
+            self.new_event(Span::zero())
+                .and_then(|| match stream.next_if(&Lex::Use) {
+                    Some(use_tok) => match self.path(stream)? {
+                        Some((path, _)) if path.item().is_some() => {
+                            let ctx = stream
+                                .next_must_be(&Lex::Semicolon)?
+                                .to_ctx()
+                                .join(use_tok.to_ctx());
+                            Ok(Some(Use::new(ctx.span(), path)))
+                        }
+                        _ => err!(use_tok.span(), ParserError::UseExpectedPath),
+                    },
+                    None => Ok(None),
+                });
+        result.view(|v| {
+            let msg = v.map(|_| "Use Declaration");
+            self.record(event.with_span(v.span()), msg)
+        })
+    }
+
     fn extern_def(&self, stream: &mut TokenStream) -> ParserResult<Extern<ParserContext>> {
         let (event, result) =
             self.new_event(Span::zero())
                 .and_then(|| match stream.next_if(&Lex::Extern) {
-                    Some(extern_tok) => match self.function_decl(stream, true)? {
+                    Some(extern_tok) => match self.function_decl(stream, true, None)? {
                         Some((fn_ctx, fn_name, params, has_varargs, fn_type)) => {
                             if has_varargs && params.is_empty() {
                                 err!(fn_ctx.span(), ParserError::ExternInvalidVarArgs)
@@ -208,12 +468,14 @@ impl<'a> Parser<'a> {
                     Some(st_def) => match stream.next_if_id() {
                         Some((id, _)) => {
                             stream.next_must_be(&Lex::LBrace)?;
-                            let fields = self.parameter_list(stream)?;
+                            let (fields, public_fields) = self.field_list(stream)?;
                             let ctx = stream
                                 .next_must_be(&Lex::RBrace)?
                                 .to_ctx()
                                 .join(st_def.to_ctx());
-                            Ok(Some(StructDef::new(id, ctx, fields)))
+                            let mut sd = StructDef::new(id, ctx, fields);
+                            *sd.get_public_fields_mut() = public_fields;
+                            Ok(Some(sd))
                         }
                         None => {
                             err!(st_def.span(), ParserError::StructExpectedIdentifier)
@@ -227,10 +489,63 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn function_def(&self, stream: &mut TokenStream) -> ParserResult<RoutineDef<ParserContext>> {
+    /// Parses an `impl` block, which defines methods for the structure that it names.
+    /// Each method must take `self` as its first parameter.
+    fn impl_def(
+        &self,
+        stream: &mut TokenStream,
+    ) -> ParserResult<(ParserContext, StringId, Vec<RoutineDef<ParserContext>>)> {
+        let (event, result) =
+            self.new_event(Span::zero())
+                .and_then(|| match stream.next_if(&Lex::Impl) {
+                    Some(impl_tok) => match stream.next_if_id() {
+                        Some((id, _)) => {
+                            stream.next_must_be(&Lex::LBrace)?;
+                            let mut methods = vec![];
+                            while let Some(m) = self.function_def(stream, Some(id))? {
+                                methods.push(m);
+                            }
+                            let ctx = stream
+                                .next_must_be(&Lex::RBrace)?
+                                .to_ctx()
+                                .join(impl_tok.to_ctx());
+                            Ok(Some((ctx, id, methods)))
+                        }
+                        None => err!(impl_tok.span(), ParserError::ImplExpectedIdentifier),
+                    },
+                    None => Ok(None),
+                });
+        result.view(|v| {
+            let msg = v.map(|_| "Impl Block");
+            let span = match v {
+                Ok(v) => v.0.span(),
+                Err(err) => err.span(),
+            };
+            self.record(event.with_span(span), msg)
+        })
+    }
+
+    /// Parses a function definition, which is a `const fn` if it starts with `const`.  If
+    /// `receiver` is the name of a structure, then the function is a method of that
+    /// structure and its first parameter must be `self`.
+    fn function_def(
+        &self,
+        stream: &mut TokenStream,
+        receiver: Option<StringId>,
+    ) -> ParserResult<RoutineDef<ParserContext>> {
         let (event, result) = self.new_event(Span::zero()).and_then(|| {
-            match self.function_decl(stream, false)? {
+            let const_tok = if stream.test_ifn(vec![Lex::Const, Lex::FunctionDef]) {
+                stream.next()
+            } else {
+                None
+            };
+
+            match self.function_decl(stream, false, receiver)? {
                 Some((ctx, name, params, is_variadic, ret_ty)) => {
+                    let ctx = match &const_tok {
+                        Some(tok) => ctx.join(tok.to_ctx()),
+                        None => ctx,
+                    };
                     if is_variadic {
                         err!(ctx.span(), ParserError::FnVarArgsNotAllowed)
                     } else {
@@ -257,6 +572,7 @@ impl<'a> Parser<'a> {
                 Ok(Some(RoutineDef {
                     context: ctx,
                     def: RoutineDefType::Function,
+                    is_const: const_tok.is_some(),
                     name: fn_name,
                     params,
                     ret_ty: fn_type,
@@ -270,10 +586,62 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parses a coroutine definition: `co name(params) -> T { ... }`.  Like a function, the
+    /// body must end with a return statement; the value of each `yret` in the body is
+    /// given to the caller of `yield`.
+    fn coroutine_def(&self, stream: &mut TokenStream) -> ParserResult<RoutineDef<ParserContext>> {
+        let (event, result) = self.new_event(Span::zero()).and_then(|| {
+            let co_ctx = match stream.next_if(&Lex::CoroutineDef) {
+                Some(co) => co.to_ctx(),
+                None => return Ok(None),
+            };
+
+            let (co_name, co_name_span) = stream.next_if_id().ok_or_else(|| {
+                CompilerError::new(co_ctx.span(), ParserError::CoExpectedIdentifierAfterCo)
+            })?;
+            let co_ctx = co_ctx.extend(co_name_span);
+
+            let (params, _, params_ctx) = self.fn_def_params(stream, false, None)?;
+            let co_ctx = params_ctx.join(co_ctx);
+
+            let (co_type, co_type_ctx) = if stream.next_if(&Lex::LArrow).is_some() {
+                self.consume_type(stream)?.ok_or_else(|| {
+                    CompilerError::new(co_ctx.span(), ParserError::FnExpectedTypeAfterArrow)
+                })?
+            } else {
+                (Type::Unit, co_ctx)
+            };
+            let co_ctx = co_type_ctx.join(co_ctx);
+
+            stream.next_must_be(&Lex::LBrace)?;
+            let mut stmts = self.co_block(stream)?;
+
+            match self.return_stmt(stream)? {
+                Some(ret) => stmts.push(Statement::Return(Box::new(ret))),
+                None => {
+                    return err!(
+                        co_ctx.span(),
+                        ParserError::FnExpectedReturn(stream.peek().map(|t| t.clone()))
+                    );
+                }
+            }
+            let ctx = stream.next_must_be(&Lex::RBrace)?.to_ctx().join(co_ctx);
+
+            Ok(Some(RoutineDef::new_coroutine(
+                co_name, ctx, params, co_type, stmts,
+            )))
+        });
+        result.view(|v| {
+            let msg = v.map(|_| "Coroutine Definition");
+            self.record(event.with_span(v.span()), msg)
+        })
+    }
+
     fn function_decl(
         &self,
         stream: &mut TokenStream,
         allow_var_args: bool,
+        receiver: Option<StringId>,
     ) -> ParserResult<(
         ParserContext,
         StringId,
@@ -296,7 +664,7 @@ impl<'a> Parser<'a> {
                     fn_ctx = fn_ctx.extend(fn_def_span);
 
                     let (params, has_varargs, params_ctx) =
-                        self.fn_def_params(stream, allow_var_args)?;
+                        self.fn_def_params(stream, allow_var_args, receiver)?;
                     let fn_ctx = params_ctx.join(fn_ctx);
 
                     let (fn_type, fn_type_ctx) = if stream.next_if(&Lex::LArrow).is_some() {
@@ -326,8 +694,10 @@ impl<'a> Parser<'a> {
         stream: &mut TokenStream,
     ) -> Result<Vec<Statement<ParserContext>>, CompilerError<ParserError>> {
         let mut stmts = vec![];
-        while let Some(s) = self.statement(stream)? {
-            stmts.push(s);
+        // A yield return is not valid in a function, but it is accepted here so that semantic
+        // analysis can report a clearer error than a missing return statement.
+        while let Some(s) = self.statement_or_yield_return(stream)? {
+            stmts.extend(s);
         }
         Ok(stmts)
     }
@@ -338,7 +708,7 @@ impl<'a> Parser<'a> {
     ) -> Result<Vec<Statement<ParserContext>>, CompilerError<ParserError>> {
         let mut stmts = vec![];
         while let Some(s) = self.statement_or_yield_return(stream)? {
-            stmts.push(s);
+            stmts.extend(s);
         }
         Ok(stmts)
     }
@@ -347,12 +717,36 @@ impl<'a> Parser<'a> {
         &self,
         stream: &mut TokenStream,
         allow_var_args: bool,
+        receiver: Option<StringId>,
     ) -> Result<
         (Vec<Parameter<ParserContext>>, HasVarArgs, ParserContext),
         CompilerError<ParserError>,
     > {
         let ctx = stream.next_must_be(&Lex::LParen)?.to_ctx();
-        let params = self.parameter_list(stream)?;
+
+        // The receiver of a method is the instance of the structure which it is called on
+        let (mut params, more_params) = match receiver {
+            Some(struct_name) => match stream.next_if(&Lex::PathSelf(StringId::new())) {
+                Some(Token {
+                    sym: Lex::PathSelf(self_id),
+                    span,
+                }) => {
+                    // Any further parameters must be separated from the receiver by a comma
+                    let more_params = stream.next_if(&Lex::Comma).is_some();
+                    let receiver = Parameter {
+                        context: ParserContext::new(span),
+                        name: self_id,
+                        ty: Type::Custom(vec![Element::Id(struct_name)].into()),
+                    };
+                    (vec![receiver], more_params)
+                }
+                _ => return err!(ctx.span(), ParserError::MethodExpectedSelf),
+            },
+            None => (vec![], true),
+        };
+        if more_params {
+            params.append(&mut self.parameter_list(stream)?);
+        }
 
         let has_varargs = if allow_var_args {
             stream.next_if(&Lex::VarArgs).is_some()
@@ -384,6 +778,96 @@ impl<'a> Parser<'a> {
         Ok(params)
     }
 
+    /// Parses the attributes written before an item (e.g. `#[derive(print, eq)]`).  Each
+    /// attribute is a name which can be followed by a parenthesized list of identifiers
+    /// or by `=` and a string literal (e.g. `#[link_name = "SDL_Init"]`).
+    fn attributes(
+        &self,
+        stream: &mut TokenStream,
+    ) -> Result<Vec<Attribute>, CompilerError<ParserError>> {
+        let mut attributes = vec![];
+
+        while let Some(hash) = stream.next_if(&Lex::Hash) {
+            stream.next_must_be(&Lex::LBracket)?;
+            let name = match stream.next_if_id() {
+                Some((name, _)) => name,
+                None => return err!(hash.span(), ParserError::AttributeExpectedName),
+            };
+
+            let mut args = vec![];
+            if stream.next_if(&Lex::LParen).is_some() {
+                while let Some((arg, _)) = stream.next_if_id() {
+                    args.push(arg);
+                    if stream.next_if(&Lex::Comma).is_none() {
+                        break;
+                    }
+                }
+                stream.next_must_be(&Lex::RParen)?;
+            }
+
+            let value = match stream.next_if(&Lex::Equals) {
+                Some(eq) => match stream.next_if(&Lex::StringLiteral(StringId::new())) {
+                    Some(Token {
+                        sym: Lex::StringLiteral(value),
+                        ..
+                    }) => Some(value),
+                    _ => return err!(eq.span(), ParserError::AttributeExpectedValue),
+                },
+                None => None,
+            };
+
+            let end = stream.next_must_be(&Lex::RBracket)?;
+            let attr = Attribute::new(Span::cover(hash.span(), end.span()), name, args);
+            attributes.push(match value {
+                Some(value) => attr.with_value(value),
+                None => attr,
+            });
+        }
+
+        Ok(attributes)
+    }
+
+    /// Parses the fields of a structure, each of which can be declared `pub`.  Returns
+    /// the fields and the names of the fields which are `pub`.
+    fn field_list(
+        &self,
+        stream: &mut TokenStream,
+    ) -> Result<(Vec<Parameter<ParserContext>>, Vec<StringId>), CompilerError<ParserError>> {
+        let mut fields = vec![];
+        let mut public_fields = vec![];
+
+        loop {
+            let pub_tok = stream.next_if(&Lex::Pub);
+            match (self.id_declaration(stream)?, pub_tok) {
+                (Some(Expression::IdentifierDeclare(ctx, name, ty)), pub_tok) => {
+                    let context = match pub_tok {
+                        Some(pub_tok) => {
+                            public_fields.push(name);
+                            ctx.join(pub_tok.to_ctx())
+                        }
+                        None => ctx,
+                    };
+                    fields.push(Parameter {
+                        context,
+                        name,
+                        ty: *ty,
+                    });
+                }
+                (Some(_), _) => panic!("CRITICAL: IdDeclaration not returned by id_declaration"),
+                (None, Some(pub_tok)) => {
+                    return err!(pub_tok.span(), ParserError::StructExpectedFieldAfterPub)
+                }
+                (None, None) => break,
+            }
+
+            if stream.next_if(&Lex::Comma).is_none() {
+                break;
+            }
+        }
+
+        Ok((fields, public_fields))
+    }
+
     pub(super) fn id_declaration_list(
         &self,
         stream: &mut TokenStream,
@@ -393,8 +877,11 @@ impl<'a> Parser<'a> {
         while let Some(id_decl) = self.id_declaration(stream)? {
             match id_decl {
                 Expression::IdentifierDeclare(ctx, id, ty) => {
-                    decls.push((id, ty, ctx));
-                    stream.next_if(&Lex::Comma);
+                    decls.push((id, *ty, ctx));
+                    match stream.next_if(&Lex::Comma) {
+                        Some(_) => {}
+                        None => break,
+                    };
                 }
                 _ => panic!("CRITICAL: IdDeclaration not returned by id_declaration"),
             }
@@ -436,7 +923,10 @@ impl<'a> Parser<'a> {
         let (event, result) = self.new_event(Span::zero()).and_then(|| {
             let mut path = vec![];
 
-            let mut ctx = stream.peek().map(|t| t.to_ctx()).unwrap();
+            let mut ctx = match stream.peek() {
+                Some(token) => token.to_ctx(),
+                None => return Ok(None),
+            };
             // The path "::a" is equivalent to "root::a"; it is a short way of starting an absolute path
             if stream.test_if(&Lex::PathSeparator) {
                 path.push(Element::FileRoot);
@@ -444,7 +934,7 @@ impl<'a> Parser<'a> {
                 path.push(Element::CanonicalRoot);
             } else if stream.next_if(&Lex::PathFileRoot).is_some() {
                 path.push(Element::FileRoot);
-            } else if stream.next_if(&Lex::PathSelf).is_some() {
+            } else if stream.next_if(&Lex::PathSelf(StringId::new())).is_some() {
                 path.push(Element::Selph);
             } else if stream.next_if(&Lex::PathSuper).is_some() {
                 path.push(Element::Super);
@@ -529,20 +1019,32 @@ impl<'a> Parser<'a> {
                         Primitive::I16 => Some(Type::I16),
                         Primitive::I32 => Some(Type::I32),
                         Primitive::I64 => Some(Type::I64),
+                        Primitive::USize => Some(Type::USize),
+                        Primitive::ISize => Some(Type::ISize),
                         Primitive::F64 => Some(Type::F64),
                         Primitive::Bool => Some(Type::Bool),
                         Primitive::StringLiteral => Some(Type::StringLiteral),
+                        Primitive::String => Some(Type::String),
                     };
                     let ctx = ParserContext::new(span);
                     ty.map(|ty| (ty, ctx))
                 }
-                _ => match self.path(stream)? {
-                    Some((path, path_ctx)) => Some((Type::Custom(path), path_ctx)),
-                    _ => match self.array_type(stream)? {
+                _ => match self.range_type(stream)? {
+                    Some((ty, ctx)) => Some((ty, ctx)),
+                    None => match self.option_type(stream)? {
                         Some((ty, ctx)) => Some((ty, ctx)),
-                        None => match self.raw_pointer_type(stream)? {
+                        None => match self.result_type(stream)? {
                             Some((ty, ctx)) => Some((ty, ctx)),
-                            None => None,
+                            None => match self.path(stream)? {
+                                Some((path, path_ctx)) => Some((Type::Custom(path), path_ctx)),
+                                _ => match self.array_type(stream)? {
+                                    Some((ty, ctx)) => Some((ty, ctx)),
+                                    None => match self.raw_pointer_type(stream)? {
+                                        Some((ty, ctx)) => Some((ty, ctx)),
+                                        None => None,
+                                    },
+                                },
+                            },
                         },
                     },
                 },
@@ -560,6 +1062,10 @@ impl<'a> Parser<'a> {
             let msg = v.map(|v| match v.0 {
                 Type::Custom(_) => "Custom Type",
                 Type::Array(..) => "Array Type",
+                Type::Slice(..) => "Slice Type",
+                Type::Range(..) => "Range Type",
+                Type::Option(..) => "Option Type",
+                Type::Result(..) => "Result Type",
                 Type::RawPointer(..) => "Raw Pointer Type",
                 _ => "Primitive Type",
             });
@@ -631,6 +1137,12 @@ impl<'a> Parser<'a> {
                                 CompilerError::new(ctx.span(), ParserError::ArrayDeclExpectedType)
                             })
                             .and_then(|(element_ty, _)| {
+                                // A slice only has an element type: `[T]`
+                                if let Some(rbracket) = stream.next_if(&Lex::RBracket) {
+                                    let ctx = rbracket.to_ctx().join(ctx);
+                                    return Ok(Some((Type::Slice(Box::new(element_ty)), ctx)));
+                                }
+
                                 stream.next_must_be(&Lex::Semicolon)?;
 
                                 let len = self.expression(stream)?.ok_or_else(|| {
@@ -663,7 +1175,98 @@ impl<'a> Parser<'a> {
                     None => Ok(None),
                 });
         result.view(|v| {
-            let msg = v.map(|_| "Array Type");
+            let msg = v.map(|(ty, _)| match ty {
+                Type::Slice(..) => "Slice Type",
+                _ => "Array Type",
+            });
+            let span = match v {
+                Ok(ok) => ok.1.span(),
+                Err(err) => err.span(),
+            };
+            self.record(event.with_span(span), msg)
+        })
+    }
+
+    /// Parses the type of a range, `Range<<type>>`.
+    fn range_type(&self, stream: &mut TokenStream) -> ParserResult<(Type, ParserContext)> {
+        let (event, result) =
+            self.new_event(Span::zero())
+                .and_then(|| match stream.next_if(&Lex::RangeType) {
+                    Some(range) => {
+                        let ctx = range.to_ctx();
+                        stream.next_must_be(&Lex::Ls)?;
+                        let (element_ty, _) = self.consume_type(stream)?.ok_or_else(|| {
+                            CompilerError::new(ctx.span(), ParserError::RangeDeclExpectedType)
+                        })?;
+                        let ctx = stream.next_must_be(&Lex::Gr)?.to_ctx().join(ctx);
+                        Ok(Some((Type::Range(Box::new(element_ty)), ctx)))
+                    }
+                    None => Ok(None),
+                });
+        result.view(|v| {
+            let msg = v.map(|_| "Range Type");
+            let span = match v {
+                Ok(ok) => ok.1.span(),
+                Err(err) => err.span(),
+            };
+            self.record(event.with_span(span), msg)
+        })
+    }
+
+    /// Parses the type of an option, `opt <type>`.
+    fn option_type(&self, stream: &mut TokenStream) -> ParserResult<(Type, ParserContext)> {
+        let (event, result) =
+            self.new_event(Span::zero())
+                .and_then(|| match stream.next_if(&Lex::OptionType) {
+                    Some(opt) => {
+                        let ctx = opt.to_ctx();
+                        let (inner_ty, inner_ctx) =
+                            self.consume_type(stream)?.ok_or_else(|| {
+                                CompilerError::new(ctx.span(), ParserError::OptionDeclExpectedType)
+                            })?;
+                        Ok(Some((
+                            Type::Option(Box::new(inner_ty)),
+                            ctx.join(inner_ctx),
+                        )))
+                    }
+                    None => Ok(None),
+                });
+        result.view(|v| {
+            let msg = v.map(|_| "Option Type");
+            let span = match v {
+                Ok(ok) => ok.1.span(),
+                Err(err) => err.span(),
+            };
+            self.record(event.with_span(span), msg)
+        })
+    }
+
+    /// Parses the type of a result, `res <type>, <error type>`.
+    fn result_type(&self, stream: &mut TokenStream) -> ParserResult<(Type, ParserContext)> {
+        let (event, result) =
+            self.new_event(Span::zero())
+                .and_then(|| match stream.next_if(&Lex::ResultType) {
+                    Some(res) => {
+                        let ctx = res.to_ctx();
+                        let (ok_ty, _) = self.consume_type(stream)?.ok_or_else(|| {
+                            CompilerError::new(ctx.span(), ParserError::ResultDeclExpectedType)
+                        })?;
+                        let comma = stream.next_must_be(&Lex::Comma)?;
+                        let (err_ty, err_ctx) = self.consume_type(stream)?.ok_or_else(|| {
+                            CompilerError::new(
+                                comma.span(),
+                                ParserError::ResultDeclExpectedErrorType,
+                            )
+                        })?;
+                        Ok(Some((
+                            Type::Result(Box::new(ok_ty), Box::new(err_ty)),
+                            ctx.join(err_ctx),
+                        )))
+                    }
+                    None => Ok(None),
+                });
+        result.view(|v| {
+            let msg = v.map(|_| "Result Type");
             let span = match v {
                 Ok(ok) => ok.1.span(),
                 Err(err) => err.span(),
@@ -686,7 +1289,7 @@ impl<'a> Parser<'a> {
                     self.consume_type(stream).and_then(|result| {
                         Ok(result.and_then(|(ty, ty_ctx)| {
                             let ctx = ctx.join(ty_ctx);
-                            Some(Expression::IdentifierDeclare(ctx, id, ty))
+                            Some(Expression::IdentifierDeclare(ctx, id, Box::new(ty)))
                         }))
                     })
                 }
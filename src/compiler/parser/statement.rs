@@ -1,6 +1,10 @@
 use super::{Parser, ParserResult};
 use crate::compiler::{
-    ast::*, diagnostics::View2, lexer::tokens::Lex, source::SourceIr, CompilerError, Span,
+    ast::*,
+    diagnostics::View2,
+    lexer::tokens::{Lex, Token},
+    source::SourceIr,
+    CompilerError, Span,
 };
 
 use super::{tokenstream::TokenStream, ParserContext, ParserError};
@@ -9,11 +13,11 @@ impl<'a> Parser<'a> {
     pub(super) fn statement_or_yield_return(
         &self,
         stream: &mut TokenStream,
-    ) -> ParserResult<Statement<ParserContext>> {
+    ) -> ParserResult<Vec<Statement<ParserContext>>> {
         let stm = match self.statement(stream)? {
             Some(n) => Some(n),
             None => match self.yield_return_stmt(stream)? {
-                Some(yr) => Some(yr),
+                Some(yr) => Some(vec![yr]),
                 None => None,
             },
         };
@@ -21,29 +25,82 @@ impl<'a> Parser<'a> {
         Ok(stm)
     }
 
+    /// Parses a statement which is within an expression block.  Unlike the top level of a
+    /// routine, where the return must be the final statement, a return can appear anywhere
+    /// within an expression block.
+    pub(super) fn statement_or_return(
+        &self,
+        stream: &mut TokenStream,
+    ) -> ParserResult<Vec<Statement<ParserContext>>> {
+        let stm = match self.statement(stream)? {
+            Some(n) => Some(n),
+            None => self
+                .return_stmt(stream)?
+                .map(|ret| vec![Statement::Return(Box::new(ret))]),
+        };
+
+        Ok(stm)
+    }
+
+    /// Parses a single statement.  A `let` which declares several variables is desugared
+    /// into one bind statement for each variable, so this may return more than one
+    /// statement.
     pub(super) fn statement(
         &self,
         stream: &mut TokenStream,
-    ) -> ParserResult<Statement<ParserContext>> {
+    ) -> ParserResult<Vec<Statement<ParserContext>>> {
+        self.burn(stream)?;
         let (event, result) = self.new_event(Span::zero()).and_then(|| {
             let start_index = stream.index();
-            let must_have_semicolon = stream.test_if_one_of(&vec![Lex::Let, Lex::Mut]);
+            let mut must_have_semicolon = stream.test_if_one_of(&vec![
+                Lex::Let,
+                Lex::Mut,
+                Lex::Underscore,
+                Lex::Defer,
+                Lex::Break,
+                Lex::Continue,
+            ]);
             let stm = match self.let_bind(stream)? {
-                Some(bind) => Some(Statement::Bind(Box::new(bind))),
+                Some(binds) => Some(
+                    binds
+                        .into_iter()
+                        .map(|bind| Statement::Bind(Box::new(bind)))
+                        .collect(),
+                ),
                 None => match self.mutate(stream)? {
-                    Some(mutate) => Some(Statement::Mutate(Box::new(mutate))),
-                    None => self
-                        .expression(stream)?
-                        .map(|s| Statement::from_ast(s))
-                        .flatten(),
+                    Some(mutate) => Some(vec![Statement::Mutate(Box::new(mutate))]),
+                    None => match self.discard(stream)? {
+                        Some(discard) => Some(vec![Statement::Discard(Box::new(discard))]),
+                        None => match self.defer(stream)? {
+                            Some(defer) => Some(vec![Statement::Defer(Box::new(defer))]),
+                            None => match self.break_or_continue(stream)? {
+                                Some(jump) => Some(vec![jump]),
+                                None => match self.expression(stream)? {
+                                    // An expression followed by `=` is the target of an
+                                    // assignment
+                                    Some(lhs) => match stream.next_if(&Lex::Equals) {
+                                        Some(equals) => {
+                                            must_have_semicolon = true;
+                                            let assign = self.assignment(stream, lhs, &equals)?;
+                                            Some(vec![Statement::Mutate(Box::new(assign))])
+                                        }
+                                        None => Statement::from_ast(lhs).map(|s| vec![s]),
+                                    },
+                                    None => None,
+                                },
+                            },
+                        },
+                    },
                 },
             };
 
             match stm {
                 Some(mut stm) => match stream.next_if(&Lex::Semicolon) {
                     Some(semicolon) => {
-                        let ctx = stm.context().join(semicolon.to_ctx());
-                        *stm.get_context_mut() = ctx;
+                        // The semicolon belongs to the last statement
+                        let last = stm.last_mut().unwrap();
+                        let ctx = last.context().join(semicolon.to_ctx());
+                        *last.get_context_mut() = ctx;
                         Ok(Some(stm))
                     }
                     _ => {
@@ -68,54 +125,54 @@ impl<'a> Parser<'a> {
             }
         });
         result.view(|v| {
-            let msg = v.map(|v| match &v {
+            let msg = v.map(|v| match &v[0] {
                 Statement::Bind(..) => "Statement Bind",
                 Statement::Expression(..) => "Statement Expression",
                 Statement::Mutate(..) => "Statement Mutate",
                 Statement::Return(..) => "Statement Return",
                 Statement::YieldReturn(..) => "Statement Yield Return",
+                Statement::Discard(..) => "Statement Discard",
+                Statement::Defer(..) => "Statement Defer",
+                Statement::Break(..) => "Statement Break",
+                Statement::Continue(..) => "Statement Continue",
             });
             self.record(event.with_span(v.span()), msg)
         })
     }
 
-    fn let_bind(&self, stream: &mut TokenStream) -> ParserResult<Bind<ParserContext>> {
+    /// Parses a `let` statement, which may declare more than one variable:
+    /// - `let x: i64 := 1;` declares a single variable.
+    /// - `let x: i64 := 1, y: i64 := 2;` declares each variable in the list.
+    /// - `let (x: i64, y: i64) := (1, 2);` destructures a list of values, the first value
+    /// is bound to the first variable and so on.
+    ///
+    /// If `mut` follows `let` then every variable declared by the statement is mutable.
+    /// Each declaration becomes its own [`Bind`] in source order, so the values are
+    /// evaluated left to right, each variable is bound before the next value is
+    /// evaluated and a value can refer to the variables declared before it in the same
+    /// statement.
+    fn let_bind(&self, stream: &mut TokenStream) -> ParserResult<Vec<Bind<ParserContext>>> {
         let (event, result) =
             self.new_event(Span::zero())
                 .and_then(|| match stream.next_if(&Lex::Let) {
                     Some(let_tok) => {
                         let is_mutable = stream.next_if(&Lex::Mut).is_some();
-                        self.id_declaration(stream)?
-                            .ok_or_else(|| {
-                                CompilerError::new(
-                                    let_tok.span(),
-                                    ParserError::ExpectedIdDeclAfterLet,
-                                )
-                            })
-                            .and_then(|id_decl| {
-                                stream.next_must_be(&Lex::Assign)?;
-
-                                let exp = match self.co_init(stream)? {
-                                    Some(co_init) => co_init,
-                                    None => self.expression(stream)?.ok_or_else(|| {
-                                        CompilerError::new(
-                                            let_tok.span(),
-                                            ParserError::ExpectedExpressionOnRhs,
-                                        )
-                                    })?,
-                                };
-
-                                match id_decl {
-                                    Expression::IdentifierDeclare(_, id, ty) => {
-                                        let ctx = exp.context().join(let_tok.to_ctx());
-                                        Ok(Some(Bind::new(ctx, id, ty.clone(), is_mutable, exp)))
-                                    }
-                                    _ => Err(CompilerError::new(
-                                        let_tok.span(),
-                                        ParserError::ExpectedTypeInIdDecl,
-                                    )),
-                                }
-                            })
+                        let mut binds = if stream.test_if(&Lex::LParen) {
+                            self.let_destructure(stream, &let_tok, is_mutable)?
+                        } else {
+                            let mut binds = vec![self.let_single(stream, &let_tok, is_mutable)?];
+                            while stream.next_if(&Lex::Comma).is_some() {
+                                binds.push(self.let_single(stream, &let_tok, is_mutable)?);
+                            }
+                            binds
+                        };
+
+                        // The first bind also covers the `let` keyword
+                        let first = &mut binds[0];
+                        let ctx = first.context().join(let_tok.to_ctx());
+                        *first.get_context_mut() = ctx;
+
+                        Ok(Some(binds))
                     }
                     None => Ok(None),
                 });
@@ -125,6 +182,78 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parses a single `<id>: <type> := <exp>` declaration from a `let` statement.
+    fn let_single(
+        &self,
+        stream: &mut TokenStream,
+        let_tok: &Token,
+        is_mutable: bool,
+    ) -> Result<Bind<ParserContext>, CompilerError<ParserError>> {
+        let id_decl = self.id_declaration(stream)?.ok_or_else(|| {
+            CompilerError::new(let_tok.span(), ParserError::ExpectedIdDeclAfterLet)
+        })?;
+
+        stream.next_must_be(&Lex::Assign)?;
+
+        let exp = match self.co_init(stream)? {
+            Some(co_init) => co_init,
+            None => self.expression(stream)?.ok_or_else(|| {
+                CompilerError::new(let_tok.span(), ParserError::ExpectedExpressionOnRhs)
+            })?,
+        };
+
+        match id_decl {
+            Expression::IdentifierDeclare(decl_ctx, id, ty) => {
+                let ctx = exp.context().join(decl_ctx);
+                Ok(Bind::new(ctx, id, *ty, is_mutable, exp))
+            }
+            _ => Err(CompilerError::new(
+                let_tok.span(),
+                ParserError::ExpectedTypeInIdDecl,
+            )),
+        }
+    }
+
+    /// Parses `(<id>: <type>, ...) := (<exp>, ...)` from a `let` statement and pairs each
+    /// declaration with the value in the same position.
+    fn let_destructure(
+        &self,
+        stream: &mut TokenStream,
+        let_tok: &Token,
+        is_mutable: bool,
+    ) -> Result<Vec<Bind<ParserContext>>, CompilerError<ParserError>> {
+        let lparen = stream.next_must_be(&Lex::LParen)?;
+        let decls = self.id_declaration_list(stream)?;
+        let rparen = stream.next_must_be(&Lex::RParen)?;
+        let decls_ctx = lparen.to_ctx().join(rparen.to_ctx());
+
+        if decls.is_empty() {
+            return err!(let_tok.span(), ParserError::ExpectedIdDeclAfterLet);
+        }
+
+        stream.next_must_be(&Lex::Assign)?;
+
+        let (values, values_ctx) = self.routine_call_params(stream)?.ok_or_else(|| {
+            CompilerError::new(let_tok.span(), ParserError::ExpectedValueListOnRhs)
+        })?;
+
+        if decls.len() != values.len() {
+            return err!(
+                decls_ctx.join(values_ctx).span(),
+                ParserError::DestructureWrongNumValues(decls.len(), values.len())
+            );
+        }
+
+        Ok(decls
+            .into_iter()
+            .zip(values)
+            .map(|((id, ty, decl_ctx), exp)| {
+                let ctx = exp.context().join(decl_ctx);
+                Bind::new(ctx, id, ty, is_mutable, exp)
+            })
+            .collect())
+    }
+
     fn mutate(&self, stream: &mut TokenStream) -> ParserResult<Mutate<ParserContext>> {
         let (event, result) = self.new_event(Span::zero()).and_then(|| {
             match stream.next_ifn(vec![Lex::Mut]) {
@@ -161,6 +290,99 @@ impl<'a> Parser<'a> {
         })
     }
 
+    /// Parses the value of `<exp> = <value>`, given the expression which is assigned to.
+    /// This is the same statement as `mut <exp> := <value>`.
+    fn assignment(
+        &self,
+        stream: &mut TokenStream,
+        lhs: Expression<ParserContext>,
+        equals: &Token,
+    ) -> Result<Mutate<ParserContext>, CompilerError<ParserError>> {
+        let rhs = self.expression(stream)?.ok_or_else(|| {
+            CompilerError::new(equals.span(), ParserError::ExpectedExprAfter(Lex::Equals))
+        })?;
+        let ctx = lhs.context().join(*rhs.context());
+        Ok(Mutate::new(ctx, lhs, rhs))
+    }
+
+    fn discard(&self, stream: &mut TokenStream) -> ParserResult<Discard<ParserContext>> {
+        let (event, result) = self.new_event(Span::zero()).and_then(|| {
+            match stream.next_if(&Lex::Underscore) {
+                None => Ok(None),
+                Some(token) => {
+                    // Check for the := operator
+                    stream.next_must_be(&Lex::Assign)?;
+
+                    self.expression(stream)?
+                        .ok_or_else(|| {
+                            CompilerError::new(token.span(), ParserError::ExpectedExpressionOnRhs)
+                        })
+                        .map(|exp| {
+                            let ctx = token.to_ctx().join(*exp.context());
+                            Some(Discard::new(ctx, exp))
+                        })
+                }
+            }
+        });
+        result.view(|v| {
+            let msg = v.map(|_| "Discard");
+            self.record(event.with_span(v.span()), msg)
+        })
+    }
+
+    /// Parses `defer exp`, which evaluates `exp` when the enclosing scope is exited.
+    fn defer(&self, stream: &mut TokenStream) -> ParserResult<Defer<ParserContext>> {
+        let (event, result) =
+            self.new_event(Span::zero())
+                .and_then(|| match stream.next_if(&Lex::Defer) {
+                    None => Ok(None),
+                    Some(token) => self
+                        .expression(stream)?
+                        .ok_or_else(|| {
+                            CompilerError::new(
+                                token.span(),
+                                ParserError::ExpectedExprAfter(Lex::Defer),
+                            )
+                        })
+                        .map(|exp| {
+                            let ctx = token.to_ctx().join(*exp.context());
+                            Some(Defer::new(ctx, exp))
+                        }),
+                });
+        result.view(|v| {
+            let msg = v.map(|_| "Defer");
+            self.record(event.with_span(v.span()), msg)
+        })
+    }
+
+    /// Parses `break ['label]` or `continue ['label]`.  Which loop the statement targets
+    /// is resolved by the type resolver.
+    fn break_or_continue(
+        &self,
+        stream: &mut TokenStream,
+    ) -> ParserResult<Statement<ParserContext>> {
+        let (event, result) = self.new_event(Span::zero()).and_then(|| {
+            Ok(match stream.next_if_one_of(&[Lex::Break, Lex::Continue]) {
+                Some(token) => {
+                    let label = stream.next_if_label();
+                    let ctx = label.map_or(token.to_ctx(), |(_, span)| {
+                        token.to_ctx().join(ParserContext::new(span))
+                    });
+                    let label = label.map(|(label, _)| label);
+                    Some(match token.sym {
+                        Lex::Break => Statement::Break(Box::new(Break::new(ctx, label))),
+                        _ => Statement::Continue(Box::new(Continue::new(ctx, label))),
+                    })
+                }
+                None => None,
+            })
+        });
+        result.view(|v| {
+            let msg = v.map(|_| "Break or Continue");
+            self.record(event.with_span(v.span()), msg)
+        })
+    }
+
     fn co_init(&self, stream: &mut TokenStream) -> ParserResult<Expression<ParserContext>> {
         let (event, result) =
             self.new_event(Span::zero())
@@ -175,7 +397,7 @@ impl<'a> Parser<'a> {
                                 Ok(Some(Expression::RoutineCall(
                                     init_tok.to_ctx().join(params_ctx),
                                     RoutineCall::CoroutineInit,
-                                    path,
+                                    Box::new(path),
                                     params,
                                 )))
                             }),
@@ -6,12 +6,13 @@ pub mod tests {
         compiler::{
             ast::*,
             diagnostics::Logger,
+            fuel::Fuel,
             lexer::{
                 tokens::{Lex, Token},
                 LexerError,
             },
             parser::{tokenstream::TokenStream, Parser, ParserContext, ParserError},
-            source::Offset,
+            source::{Offset, SourceIr},
             CompilerDisplay, CompilerError, Lexer, SourceMap, Span,
         },
         StringTable,
@@ -223,6 +224,44 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn parse_range_expression() {
+        let text = "1 + 2..3 * 4";
+        let mut table = StringTable::new();
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+        let parser = Parser::new(&logger);
+        if let Some(Expression::BinaryOp(ctx, BinaryOperator::Range, left, right)) =
+            parser.expression(&mut stream).unwrap()
+        {
+            assert_eq!(ctx, new_ctx(0, 12));
+            match *left {
+                Expression::BinaryOp(ctx, BinaryOperator::Add, ..) => {
+                    assert_eq!(ctx, new_ctx(0, 5))
+                }
+                _ => panic!("Expected Add syntax"),
+            }
+            match *right {
+                Expression::BinaryOp(ctx, BinaryOperator::Mul, ..) => {
+                    assert_eq!(ctx, new_ctx(7, 12))
+                }
+                _ => panic!("Expected Mul syntax"),
+            }
+        } else {
+            panic!("No nodes returned by parser")
+        }
+    }
+
     #[test]
     fn parse_boolean_expression() {
         let text = "true || false";
@@ -290,7 +329,7 @@ pub mod tests {
                 //assert_eq!(op, expected);
                 assert_eq!(ctx, new_ctx(l, h));
                 assert_eq!(*left, Expression::I64(new_ctx(0, 1), 2));
-                assert_eq!(result_ty, ty);
+                assert_eq!(*result_ty, ty);
             } else {
                 panic!("No nodes returned by parser for {}", text)
             }
@@ -462,7 +501,9 @@ pub mod tests {
             .unwrap();
         let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        let stm = parser.statement(&mut stream).unwrap().unwrap();
+        let mut stms = parser.statement(&mut stream).unwrap().unwrap();
+        assert_eq!(stms.len(), 1);
+        let stm = stms.remove(0);
         assert_eq!(*stm.context(), new_ctx(0, 15));
         match stm {
             Statement::Bind(b) => {
@@ -494,7 +535,9 @@ pub mod tests {
             .unwrap();
         let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        let stm = parser.statement(&mut stream).unwrap().unwrap();
+        let mut stms = parser.statement(&mut stream).unwrap().unwrap();
+        assert_eq!(stms.len(), 1);
+        let stm = stms.remove(0);
         assert_eq!(*stm.context(), new_ctx(0, 19));
         match stm {
             Statement::Bind(b) => {
@@ -507,6 +550,285 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn parse_multiple_binds() {
+        let text = "let x: i64 := 1, y: bool := true;";
+        let mut table = StringTable::new();
+        let x = table.insert("x".into());
+        let y = table.insert("y".into());
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+        let parser = Parser::new(&logger);
+        let stms = parser.statement(&mut stream).unwrap().unwrap();
+        assert_eq!(stms.len(), 2);
+        match (&stms[0], &stms[1]) {
+            (Statement::Bind(bx), Statement::Bind(by)) => {
+                assert_eq!(*bx.context(), new_ctx(0, 15));
+                assert_eq!(bx.get_id(), x);
+                assert_eq!(bx.get_type(), Type::I64);
+                assert_eq!(bx.is_mutable(), false);
+                assert_eq!(*bx.get_rhs(), Expression::I64(new_ctx(14, 15), 1));
+
+                assert_eq!(*by.context(), new_ctx(17, 33));
+                assert_eq!(by.get_id(), y);
+                assert_eq!(by.get_type(), Type::Bool);
+                assert_eq!(by.is_mutable(), false);
+                assert_eq!(*by.get_rhs(), Expression::Boolean(new_ctx(28, 32), true));
+            }
+            _ => panic!("Not binding statements"),
+        }
+    }
+
+    #[test]
+    fn parse_destructure_bind() {
+        let text = "let mut (x: i64, y: bool) := (1, true);";
+        let mut table = StringTable::new();
+        let x = table.insert("x".into());
+        let y = table.insert("y".into());
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+        let parser = Parser::new(&logger);
+        let stms = parser.statement(&mut stream).unwrap().unwrap();
+        assert_eq!(stms.len(), 2);
+        match (&stms[0], &stms[1]) {
+            (Statement::Bind(bx), Statement::Bind(by)) => {
+                assert_eq!(*bx.context(), new_ctx(0, 31));
+                assert_eq!(bx.get_id(), x);
+                assert_eq!(bx.get_type(), Type::I64);
+                assert_eq!(bx.is_mutable(), true);
+                assert_eq!(*bx.get_rhs(), Expression::I64(new_ctx(30, 31), 1));
+
+                assert_eq!(*by.context(), new_ctx(17, 39));
+                assert_eq!(by.get_id(), y);
+                assert_eq!(by.get_type(), Type::Bool);
+                assert_eq!(by.is_mutable(), true);
+                assert_eq!(*by.get_rhs(), Expression::Boolean(new_ctx(33, 37), true));
+            }
+            _ => panic!("Not binding statements"),
+        }
+    }
+
+    #[test]
+    fn destructure_bind_fails() {
+        for (text, span, expected) in vec![
+            (
+                "let (x: i64, y: bool) := (1);",
+                (4, 28),
+                ParserError::DestructureWrongNumValues(2, 1),
+            ),
+            (
+                "let (x: i64) := 5;",
+                (0, 3),
+                ParserError::ExpectedValueListOnRhs,
+            ),
+            ("let () := ();", (0, 3), ParserError::ExpectedIdDeclAfterLet),
+        ] {
+            let mut table = StringTable::new();
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+
+            let err = parser.statement(&mut stream).unwrap_err();
+            assert_eq!(
+                err,
+                CompilerError::new(
+                    Span::new(Offset::new(span.0), Offset::new(span.1)),
+                    expected,
+                ),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn arithmetic_builtins_are_not_identifiers() {
+        for name in [
+            "wrapping_add",
+            "wrapping_sub",
+            "wrapping_mul",
+            "saturating_add",
+            "saturating_sub",
+        ] {
+            let text = format!("let {}: i64 := 1;", name);
+            let mut table = StringTable::new();
+
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+
+            let err = parser.statement(&mut stream).unwrap_err();
+            assert_eq!(
+                err,
+                CompilerError::new(
+                    Span::new(Offset::new(0), Offset::new(3)),
+                    ParserError::ExpectedIdDeclAfterLet,
+                ),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn parse_discard() {
+        let text = "_ := 5;";
+        let mut table = StringTable::new();
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+        let parser = Parser::new(&logger);
+        let mut stms = parser.statement(&mut stream).unwrap().unwrap();
+        assert_eq!(stms.len(), 1);
+        let stm = stms.remove(0);
+        assert_eq!(*stm.context(), new_ctx(0, 7));
+        match stm {
+            Statement::Discard(d) => {
+                assert_eq!(*d.get_value(), Expression::I64(new_ctx(5, 6), 5));
+            }
+            _ => panic!("Not a discard statement"),
+        }
+    }
+
+    #[test]
+    fn parse_defer() {
+        let text = "defer 5;";
+        let mut table = StringTable::new();
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+        let parser = Parser::new(&logger);
+        let mut stms = parser.statement(&mut stream).unwrap().unwrap();
+        assert_eq!(stms.len(), 1);
+        let stm = stms.remove(0);
+        assert_eq!(*stm.context(), new_ctx(0, 8));
+        match stm {
+            Statement::Defer(d) => {
+                assert_eq!(*d.get_value(), Expression::I64(new_ctx(6, 7), 5));
+            }
+            _ => panic!("Not a defer statement"),
+        }
+    }
+
+    #[test]
+    fn parse_break_and_continue() {
+        for (text, ctx, is_break, has_label) in [
+            ("break;", new_ctx(0, 6), true, false),
+            ("break 'outer;", new_ctx(0, 13), true, true),
+            ("continue;", new_ctx(0, 9), false, false),
+            ("continue 'outer;", new_ctx(0, 16), false, true),
+        ] {
+            let mut table = StringTable::new();
+            let outer = table.insert("'outer".into());
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+            let mut stms = parser.statement(&mut stream).unwrap().unwrap();
+            assert_eq!(stms.len(), 1);
+            let stm = stms.remove(0);
+            assert_eq!(*stm.context(), ctx, "{}", text);
+            let label = match stm {
+                Statement::Break(b) if is_break => b.get_label(),
+                Statement::Continue(c) if !is_break => c.get_label(),
+                stm => panic!("Unexpected statement for {}: {:?}", text, stm),
+            };
+            assert_eq!(label, if has_label { Some(outer) } else { None });
+        }
+    }
+
+    #[test]
+    fn parse_unit_literal() {
+        let text = "()";
+        let mut table = StringTable::new();
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+        let parser = Parser::new(&logger);
+        let exp = parser.expression(&mut stream).unwrap().unwrap();
+        assert_eq!(
+            exp,
+            Expression::ExpressionBlock(new_ctx(0, 2), vec![], None)
+        );
+    }
+
     #[test]
     fn parse_types() {
         for (text, expected_ty) in vec![
@@ -523,10 +845,47 @@ pub mod tests {
             ("let x:i64 := 5;", Type::I64),
             ("let x: bool := true;", Type::Bool),
             ("let x: string := \"hello\";", Type::StringLiteral),
+            ("let x: String := \"hello\" as String;", Type::String),
             (
                 "let x: [i32;5] := [1, 2, 3, 4, 5];",
                 Type::Array(Box::new(Type::I32), 5),
             ),
+            (
+                "let x: [i32] := [1, 2, 3, 4, 5];",
+                Type::Slice(Box::new(Type::I32)),
+            ),
+            (
+                "let x: [[i32; 2]] := [[1, 2]];",
+                Type::Slice(Box::new(Type::Array(Box::new(Type::I32), 2))),
+            ),
+            (
+                "let x: Range<i64> := 0..5;",
+                Type::Range(Box::new(Type::I64)),
+            ),
+            (
+                "let x: Range<u8> := 0u8..5u8;",
+                Type::Range(Box::new(Type::U8)),
+            ),
+            ("let x: opt i64 := none;", Type::Option(Box::new(Type::I64))),
+            (
+                "let x: opt [i32; 2] := some([1, 2]);",
+                Type::Option(Box::new(Type::Array(Box::new(Type::I32), 2))),
+            ),
+            (
+                "let x: opt opt bool := some(none);",
+                Type::Option(Box::new(Type::Option(Box::new(Type::Bool)))),
+            ),
+            (
+                "let x: res i64, bool := ok(5);",
+                Type::Result(Box::new(Type::I64), Box::new(Type::Bool)),
+            ),
+            (
+                "let x: res opt i64, [u8; 2] := err([1u8, 2u8]);",
+                Type::Result(
+                    Box::new(Type::Option(Box::new(Type::I64))),
+                    Box::new(Type::Array(Box::new(Type::U8), 2)),
+                ),
+            ),
             (
                 "let x: *const i32 := 0;",
                 Type::RawPointer(PointerMut::Const, Box::new(Type::I32)),
@@ -555,7 +914,9 @@ pub mod tests {
             let mut stream = TokenStream::new(&tokens, &logger).unwrap();
             let parser = Parser::new(&logger);
 
-            let stm = parser.statement(&mut stream).unwrap().unwrap();
+            let mut stms = parser.statement(&mut stream).unwrap().unwrap();
+            assert_eq!(stms.len(), 1);
+            let stm = stms.remove(0);
 
             assert_eq!(*stm.context(), new_ctx(0, text.len() as u32));
 
@@ -636,7 +997,9 @@ pub mod tests {
             let mut stream = TokenStream::new(&tokens, &logger).unwrap();
             let parser = Parser::new(&logger);
 
-            let stm = parser.statement(&mut stream).unwrap().unwrap();
+            let mut stms = parser.statement(&mut stream).unwrap().unwrap();
+            assert_eq!(stms.len(), 1);
+            let stm = stms.remove(0);
 
             assert_eq!(*stm.context(), new_ctx(0, text.len() as u32));
 
@@ -683,6 +1046,43 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn range_type_annotation_fails() {
+        for (text, msg) in vec![
+            ("let x: Range := 0..1;", "L1: Expected <, but found :="),
+            (
+                "let x: Range<> := 0..1;",
+                "L1: Expected type in range type declaration",
+            ),
+            ("let x: Range<i64 := 0..1;", "L1: Expected >, but found :="),
+            ("let x: opt := none;", "L1: Expected type after opt"),
+            ("let x: res := ok(1);", "L1: Expected type after res"),
+            (
+                "let x: res i64, := ok(1);",
+                "L1: Expected error type after , in result type declaration",
+            ),
+        ] {
+            let mut table = StringTable::new();
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+
+            let err = parser.statement(&mut stream).unwrap_err();
+            assert_eq!(err.fmt(&sm, &table).unwrap(), msg, "{}", text);
+        }
+    }
+
     #[test]
     fn address_of_fails() {
         for (text, span) in vec![
@@ -787,11 +1187,245 @@ pub mod tests {
     }
 
     #[test]
-    fn parse_mutation() {
-        let text = "mut x := 5;";
-        let mut table = StringTable::new();
-        let x_sid = table.insert("x".into());
-        let x = Expression::Identifier(new_ctx(4, 5), x_sid);
+    fn parse_reflection_builtins() {
+        for (text, is_fields_of) in [("fields_of(MyStruct)", true), ("name_of(MyStruct)", false)] {
+            let mut table = StringTable::new();
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+
+            let ms_id = table.insert("MyStruct".into());
+            let mut path = Path::new();
+            path.push(Element::Id(ms_id));
+            let expected_ty = Box::new(Type::Custom(path));
+            let expected_ctx = new_ctx(0, text.len() as u32);
+
+            match parser.expression(&mut stream).unwrap() {
+                Some(Expression::FieldsOf(ctx, ty)) if is_fields_of => {
+                    assert_eq!(ty, expected_ty);
+                    assert_eq!(ctx, expected_ctx);
+                }
+                Some(Expression::NameOf(ctx, ty)) if !is_fields_of => {
+                    assert_eq!(ty, expected_ty);
+                    assert_eq!(ctx, expected_ctx);
+                }
+                e => panic!("Unexpected result for {}: {:?}", text, e),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_len() {
+        let text = "len(s)";
+
+        let mut table = StringTable::new();
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+        let parser = Parser::new(&logger);
+        if let Some(Expression::UnaryOp(ctx, op, operand)) = parser.expression(&mut stream).unwrap()
+        {
+            let s = table.insert("s".into());
+            assert_eq!(op, UnaryOperator::Length);
+            assert_eq!(ctx, new_ctx(0, 6));
+            assert_eq!(*operand, Expression::Identifier(new_ctx(4, 5), s));
+        } else {
+            panic!("No nodes returned by parser")
+        }
+    }
+
+    #[test]
+    fn parse_arithmetic_builtins() {
+        for (text, expected_op) in vec![
+            ("wrapping_add(1, 2)", BinaryOperator::WrappingAdd),
+            ("wrapping_sub(1, 2)", BinaryOperator::WrappingSub),
+            ("wrapping_mul(1, 2)", BinaryOperator::WrappingMul),
+            ("saturating_add(1, 2)", BinaryOperator::SaturatingAdd),
+            ("saturating_sub(1, 2)", BinaryOperator::SaturatingSub),
+        ] {
+            let mut table = StringTable::new();
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+            if let Some(Expression::BinaryOp(ctx, op, l, r)) =
+                parser.expression(&mut stream).unwrap()
+            {
+                let len = text.len() as u32;
+                assert_eq!(op, expected_op);
+                assert_eq!(ctx, new_ctx(0, len));
+                assert_eq!(*l, Expression::I64(new_ctx(len - 5, len - 4), 1));
+                assert_eq!(*r, Expression::I64(new_ctx(len - 2, len - 1), 2));
+            } else {
+                panic!("No nodes returned by parser")
+            }
+        }
+    }
+
+    #[test]
+    fn parse_byte_builtins() {
+        for (text, expected_op) in vec![
+            ("to_le_bytes(x)", UnaryOperator::ToLeBytes),
+            ("to_be_bytes(x)", UnaryOperator::ToBeBytes),
+            ("from_le_bytes(x)", UnaryOperator::FromLeBytes),
+            ("from_be_bytes(x)", UnaryOperator::FromBeBytes),
+        ] {
+            let mut table = StringTable::new();
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+            if let Some(Expression::UnaryOp(ctx, op, operand)) =
+                parser.expression(&mut stream).unwrap()
+            {
+                let x = table.insert("x".into());
+                let len = text.len() as u32;
+                assert_eq!(op, expected_op);
+                assert_eq!(ctx, new_ctx(0, len));
+                assert_eq!(
+                    *operand,
+                    Expression::Identifier(new_ctx(len - 2, len - 1), x)
+                );
+            } else {
+                panic!("No nodes returned by parser")
+            }
+        }
+    }
+
+    #[test]
+    fn parse_yield() {
+        let mut table = StringTable::new();
+        let c = table.insert("c".into());
+        let x = table.insert("x".into());
+        for (text, expected) in [
+            (
+                "yield c",
+                Expression::Yield(
+                    new_ctx(0, 7),
+                    Box::new(Expression::Identifier(new_ctx(6, 7), c)),
+                ),
+            ),
+            (
+                "yield (c)",
+                Expression::Yield(
+                    new_ctx(0, 9),
+                    Box::new(Expression::Identifier(new_ctx(6, 9), c)),
+                ),
+            ),
+            (
+                "yield c.x",
+                Expression::Yield(
+                    new_ctx(0, 9),
+                    Box::new(Expression::MemberAccess(
+                        new_ctx(6, 9),
+                        Box::new(Expression::Identifier(new_ctx(6, 7), c)),
+                        x,
+                    )),
+                ),
+            ),
+            (
+                "(yield c).x",
+                Expression::MemberAccess(
+                    new_ctx(0, 11),
+                    Box::new(Expression::Yield(
+                        new_ctx(0, 9),
+                        Box::new(Expression::Identifier(new_ctx(7, 8), c)),
+                    )),
+                    x,
+                ),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+            let exp = parser.expression(&mut stream).unwrap().unwrap();
+            assert_eq!(exp, expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn yield_expects_coroutine() {
+        for text in ["yield", "yield ;"] {
+            let mut table = StringTable::new();
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+            let err = parser.expression(&mut stream).unwrap_err();
+            assert_eq!(
+                err,
+                CompilerError::new(
+                    Span::new(Offset::new(0), Offset::new(5)),
+                    ParserError::YieldExpectedIdentifier
+                ),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn parse_mutation() {
+        let text = "mut x := 5;";
+        let mut table = StringTable::new();
+        let x_sid = table.insert("x".into());
+        let x = Expression::Identifier(new_ctx(4, 5), x_sid);
 
         let mut sm = SourceMap::new();
         sm.add_string(text, "/test".into()).unwrap();
@@ -806,7 +1440,9 @@ pub mod tests {
             .unwrap();
         let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        let stm = parser.statement(&mut stream).unwrap().unwrap();
+        let mut stms = parser.statement(&mut stream).unwrap().unwrap();
+        assert_eq!(stms.len(), 1);
+        let stm = stms.remove(0);
         assert_eq!(*stm.context(), new_ctx(0, text.len() as u32));
         match stm {
             Statement::Mutate(m) => {
@@ -818,11 +1454,11 @@ pub mod tests {
     }
 
     #[test]
-    fn parse_module_empty() {
-        let text = "mod test_mod {}";
+    fn parse_assignment() {
+        let text = "x = 5;";
         let mut table = StringTable::new();
-        let test = table.insert("test".into());
-        let test_mod = table.insert("test_mod".into());
+        let x_sid = table.insert("x".into());
+        let x = Expression::Identifier(new_ctx(0, 1), x_sid);
 
         let mut sm = SourceMap::new();
         sm.add_string(text, "/test".into()).unwrap();
@@ -835,27 +1471,88 @@ pub mod tests {
             .into_iter()
             .collect::<LResult>()
             .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        if let Some(m) = parser
-            .parse(test, &tokens)
-            .unwrap()
-            .unwrap()
-            .get_module(test_mod)
-        {
-            assert_eq!(*m.context(), new_ctx(0, 15));
-            assert_eq!(m.get_name(), test_mod);
-        } else {
-            panic!("No nodes returned by parser")
+        let mut stms = parser.statement(&mut stream).unwrap().unwrap();
+        assert_eq!(stms.len(), 1);
+        let stm = stms.remove(0);
+        assert_eq!(*stm.context(), new_ctx(0, text.len() as u32));
+        match stm {
+            Statement::Mutate(m) => {
+                assert_eq!(m.get_lhs(), &x);
+                assert_eq!(*m.get_rhs(), Expression::I64(new_ctx(4, 5), 5));
+            }
+            _ => panic!("Not a mutate statement"),
         }
     }
 
     #[test]
-    fn parse_module_with_function() {
-        let text = "mod test_fn_mod { fn test(x:i64) {return;} }";
+    fn parse_assignment_or_equality() {
+        for (text, is_assignment) in vec![
+            ("x = y;", true),
+            ("x == y;", false),
+            ("a.b = 1;", true),
+            ("a[0] = a[1] == 2;", true),
+            ("^p = 3;", true),
+        ] {
+            let mut table = StringTable::new();
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+            let stms = parser.statement(&mut stream).unwrap().unwrap();
+            assert_eq!(
+                matches!(stms[0], Statement::Mutate(..)),
+                is_assignment,
+                "{}",
+                text
+            );
+            assert!(stream.peek().is_none(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_assignment_fails() {
+        for (text, msg) in vec![
+            ("x = ;", "L1: Expected expression after ="),
+            ("x = 5", "L1: Expected ;, but found EOF"),
+        ] {
+            let mut table = StringTable::new();
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+            let err = parser.statement(&mut stream).unwrap_err();
+            assert_eq!(err.fmt(&sm, &table).unwrap(), msg, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_module_empty() {
+        let text = "mod test_mod {}";
         let mut table = StringTable::new();
         let test = table.insert("test".into());
-        let test_fn_mod = table.insert("test_fn_mod".into());
-        let x = table.insert("x".into());
+        let test_mod = table.insert("test_mod".into());
 
         let mut sm = SourceMap::new();
         sm.add_string(text, "/test".into()).unwrap();
@@ -869,10 +1566,192 @@ pub mod tests {
             .collect::<LResult>()
             .unwrap();
         let parser = Parser::new(&logger);
+        if let Some(m) = parser
+            .parse(test, &tokens)
+            .unwrap()
+            .unwrap()
+            .get_module(test_mod)
+        {
+            assert_eq!(*m.context(), new_ctx(0, 15));
+            assert_eq!(m.get_name(), test_mod);
+        } else {
+            panic!("No nodes returned by parser")
+        }
+    }
 
-        if let Some(m) = parser.parse(test, &tokens).unwrap() {
-            assert_eq!(*m.context(), new_ctx(0, 44));
-            assert_eq!(m.get_name(), test);
+    #[test]
+    fn parse_module_unexpected_token() {
+        for (text, msg) in vec![
+            (
+                "fn f() {return;} }",
+                "L1: Unexpected token } at module level, expected mod, use, fn, co, struct, impl, or extern",
+            ),
+            (
+                "fn f() {return;}\nlet x: i64 := 5;",
+                "L2: Unexpected token let at module level, expected mod, use, fn, co, struct, impl, or extern",
+            ),
+            (
+                "mod m {\n    fn f() {return;};\n}",
+                "L2: Unexpected token ; at module level, expected mod, use, fn, co, struct, impl, or extern",
+            ),
+        ] {
+            let mut table = StringTable::new();
+            let test = table.insert("test".into());
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let parser = Parser::new(&logger);
+            let err = parser.parse(test, &tokens).unwrap_err();
+            assert_eq!(err.fmt(&sm, &table).unwrap(), msg, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_use() {
+        let text = "use root::std::io;\nmod m {\n    use super::io::write;\n}";
+        let mut table = StringTable::new();
+        let test = table.insert("test".into());
+        let std = table.insert("std".into());
+        let io = table.insert("io".into());
+        let write = table.insert("write".into());
+        let m = table.insert("m".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let parser = Parser::new(&logger);
+        let module = parser.parse(test, &tokens).unwrap().unwrap();
+
+        assert_eq!(
+            module.get_uses(),
+            &vec![Use::new(
+                new_span(0, 18),
+                vec![Element::FileRoot, Element::Id(std), Element::Id(io)].into()
+            )]
+        );
+        assert_eq!(module.get_uses()[0].get_name(), io);
+
+        let inner = module.get_module(m).unwrap();
+        assert_eq!(
+            inner.get_uses(),
+            &vec![Use::new(
+                new_span(31, 52),
+                vec![Element::Super, Element::Id(io), Element::Id(write)].into()
+            )]
+        );
+    }
+
+    #[test]
+    fn parse_use_fails() {
+        for (text, expected) in vec![
+            (
+                "use;",
+                "L1: Path to an item or module expected after use keyword",
+            ),
+            (
+                "use super;",
+                "L1: Path to an item or module expected after use keyword",
+            ),
+        ] {
+            let mut table = StringTable::new();
+            let test = table.insert("test".into());
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let parser = Parser::new(&logger);
+
+            let err = parser.parse(test, &tokens).unwrap_err();
+            assert_eq!(err.fmt(&sm, &table).unwrap(), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_items_with_same_name_fails() {
+        for (text, expected) in vec![
+            (
+                "fn dupe() {return;}\nstruct dupe {}",
+                "L2: Module already contains dupe\n    L1: note: previous fn definition here",
+            ),
+            (
+                "struct dupe {}\nextern fn dupe();",
+                "L2: Module already contains dupe\n    L1: note: previous struct definition here",
+            ),
+            (
+                "mod m {\n    extern fn dupe();\n    fn dupe() {return;}\n}",
+                "L3: Module already contains dupe\n    L2: note: previous extern definition here",
+            ),
+        ] {
+            let mut table = StringTable::new();
+            let test = table.insert("test".into());
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let parser = Parser::new(&logger);
+
+            let err = parser.parse(test, &tokens).unwrap_err();
+            assert_eq!(err.fmt(&sm, &table).unwrap(), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_module_with_function() {
+        let text = "mod test_fn_mod { fn test(x:i64) {return;} }";
+        let mut table = StringTable::new();
+        let test = table.insert("test".into());
+        let test_fn_mod = table.insert("test_fn_mod".into());
+        let x = table.insert("x".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let parser = Parser::new(&logger);
+
+        if let Some(m) = parser.parse(test, &tokens).unwrap() {
+            assert_eq!(*m.context(), new_ctx(0, 44));
+            assert_eq!(m.get_name(), test);
 
             assert_eq!(m.get_modules().len(), 1);
             assert_eq!(m.get_functions().len(), 0);
@@ -892,38 +1771,578 @@ pub mod tests {
                 def: RoutineDefType::Function,
                 name,
                 params,
-                ret_ty: ty,
-                body,
-                ..
-            }) = &m.get_functions()[0]
-            {
-                assert_eq!(*context, new_ctx(18, 42));
-                assert_eq!(*name, test);
-                assert_eq!(
-                    params,
-                    &vec![Parameter::new(new_ctx(26, 31), x, &Type::I64)]
-                );
-                assert_eq!(ty, &Type::Unit);
-                assert_eq!(body.len(), 1);
-                match &body[0] {
-                    Statement::Return(r) => assert_eq!(*r.get_value(), None),
-                    _ => panic!("Wrong body, expected unit return"),
-                }
-            } else {
-                panic!("Expected function definition")
-            }
+                ret_ty: ty,
+                body,
+                ..
+            }) = &m.get_functions()[0]
+            {
+                assert_eq!(*context, new_ctx(18, 42));
+                assert_eq!(*name, test);
+                assert_eq!(
+                    params,
+                    &vec![Parameter::new(new_ctx(26, 31), x, &Type::I64)]
+                );
+                assert_eq!(ty, &Type::Unit);
+                assert_eq!(body.len(), 1);
+                match &body[0] {
+                    Statement::Return(r) => assert_eq!(*r.get_value(), None),
+                    _ => panic!("Wrong body, expected unit return"),
+                }
+            } else {
+                panic!("Expected function definition")
+            }
+        } else {
+            panic!("No nodes returned by parser")
+        }
+    }
+
+    #[test]
+    fn parse_module_with_struct() {
+        let text = "mod test_struct_mod { struct my_struct{x: i64} }";
+        let mut table = StringTable::new();
+        let test_struct_mod = table.insert("test_struct_mod".into());
+        let test = table.insert("test".into());
+        let my_struct = table.insert("my_struct".into());
+        let x = table.insert("x".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let parser = Parser::new(&logger);
+        if let Some(m) = parser
+            .parse(test, &tokens)
+            .unwrap()
+            .unwrap()
+            .get_module(test_struct_mod)
+        {
+            assert_eq!(*m.context(), new_ctx(0, 48));
+            assert_eq!(m.get_name(), test_struct_mod);
+
+            assert_eq!(m.get_modules().len(), 0);
+            assert_eq!(m.get_functions().len(), 0);
+            assert_eq!(m.get_coroutines().len(), 0);
+            assert_eq!(m.get_structs().len(), 1);
+
+            if let Some(Item::Struct(sd)) = m.get_item(my_struct) {
+                assert_eq!(*sd.context(), new_ctx(22, 46));
+                assert_eq!(sd.get_name(), my_struct);
+                assert_eq!(
+                    sd.get_fields(),
+                    &vec![Parameter::new(new_ctx(39, 45), x, &Type::I64)]
+                );
+            }
+        } else {
+            panic!("No nodes returned by parser")
+        }
+    }
+
+    #[test]
+    fn parse_extern_with_attribute() {
+        let text = "#[link_name = \"SDL_Init\"] extern fn start(flags: u32) -> i32;";
+        let mut table = StringTable::new();
+        let test = table.insert("test".into());
+        let start = table.insert("start".into());
+        let link_name = table.insert("link_name".into());
+        let sdl_init = table.insert("SDL_Init".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let parser = Parser::new(&logger);
+        let m = parser.parse(test, &tokens).unwrap().unwrap();
+        match m.get_item(start) {
+            Some(Item::Extern(e)) => {
+                assert_eq!(*e.context(), new_ctx(26, 61));
+                assert_eq!(
+                    e.get_attributes(),
+                    &vec![Attribute::new(new_span(0, 25), link_name, vec![]).with_value(sdl_init)]
+                );
+            }
+            _ => panic!("Expected start to be an extern"),
+        }
+    }
+
+    #[test]
+    fn parse_module_with_extern() {
+        let text = "mod test_extern_mod { extern fn my_fn(x: i64) -> i32; }";
+        let mut table = StringTable::new();
+        let test_extern_mod = table.insert("test_extern_mod".into());
+        let test = table.insert("test".into());
+        let my_fn = table.insert("my_fn".into());
+        let x = table.insert("x".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let parser = Parser::new(&logger);
+        if let Some(m) = parser
+            .parse(test, &tokens)
+            .unwrap()
+            .unwrap()
+            .get_module(test_extern_mod)
+        {
+            assert_eq!(*m.context(), new_ctx(0, 55));
+            assert_eq!(m.get_name(), test_extern_mod);
+
+            assert_eq!(m.get_modules().len(), 0);
+            assert_eq!(m.get_functions().len(), 0);
+            assert_eq!(m.get_coroutines().len(), 0);
+            assert_eq!(m.get_structs().len(), 0);
+            assert_eq!(m.get_externs().len(), 1);
+
+            if let Some(Item::Extern(e)) = m.get_item(my_fn) {
+                assert_eq!(*e.context(), new_ctx(22, 53));
+                assert_eq!(e.get_name(), my_fn);
+                assert_eq!(
+                    e.get_params(),
+                    &vec![Parameter::new(new_ctx(38, 44), x, &Type::I64)]
+                );
+                assert_eq!(e.get_return_type(), Type::I32);
+            }
+        } else {
+            panic!("No nodes returned by parser")
+        }
+    }
+
+    #[test]
+    fn parse_module_with_extern_with_varargs() {
+        let text = "mod test_extern_mod { extern fn my_fn(x: i64, ...) -> i32; }";
+        let mut table = StringTable::new();
+        let test_extern_mod = table.insert("test_extern_mod".into());
+        let test = table.insert("test".into());
+        let my_fn = table.insert("my_fn".into());
+        let x = table.insert("x".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let parser = Parser::new(&logger);
+        if let Some(m) = parser
+            .parse(test, &tokens)
+            .unwrap()
+            .unwrap()
+            .get_module(test_extern_mod)
+        {
+            assert_eq!(*m.context(), new_ctx(0, 60));
+            assert_eq!(m.get_name(), test_extern_mod);
+
+            assert_eq!(m.get_modules().len(), 0);
+            assert_eq!(m.get_functions().len(), 0);
+            assert_eq!(m.get_coroutines().len(), 0);
+            assert_eq!(m.get_structs().len(), 0);
+            assert_eq!(m.get_externs().len(), 1);
+
+            if let Some(Item::Extern(e)) = m.get_item(my_fn) {
+                assert_eq!(*e.context(), new_ctx(22, 58));
+                assert_eq!(e.get_name(), my_fn);
+                assert_eq!(
+                    e.get_params(),
+                    &vec![Parameter::new(new_ctx(38, 44), x, &Type::I64)]
+                );
+                assert_eq!(e.get_return_type(), Type::I32);
+                assert_eq!(e.has_varargs, true);
+            }
+        } else {
+            panic!("No nodes returned by parser")
+        }
+    }
+
+    #[test]
+    fn parse_unit_function_def() {
+        let text = "fn test(x:i64) {return;}";
+        let mut table = StringTable::new();
+        let x = table.insert("x".into());
+        let test = table.insert("test".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let parser = Parser::new(&logger);
+        if let Some(Item::Routine(RoutineDef {
+            context: l,
+            def: RoutineDefType::Function,
+            name,
+            params,
+            ret_ty: ty,
+            body,
+            ..
+        })) = parser.parse(test, &tokens).unwrap().unwrap().get_item(test)
+        {
+            assert_eq!(*l, new_ctx(0, 24));
+            assert_eq!(*name, test);
+            assert_eq!(*params, vec![Parameter::new(new_ctx(8, 13), x, &Type::I64)]);
+            assert_eq!(ty, Type::Unit);
+            assert_eq!(body.len(), 1);
+            match &body[0] {
+                Statement::Return(r) => assert_eq!(*r.get_value(), None),
+                _ => panic!("Wrong body, expected unit return"),
+            }
+        } else {
+            panic!("No nodes returned by parser")
+        }
+    }
+
+    #[test]
+    fn parse_function_def() {
+        let text = "fn test(x:i64) -> bool {return true;}";
+        let mut table = StringTable::new();
+        let x = table.insert("x".into());
+        let test = table.insert("test".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let parser = Parser::new(&logger);
+        if let Some(Item::Routine(RoutineDef {
+            context: l,
+            def: RoutineDefType::Function,
+            name,
+            params,
+            ret_ty: ty,
+            body,
+            ..
+        })) = parser.parse(test, &tokens).unwrap().unwrap().get_item(test)
+        {
+            assert_eq!(*l, new_ctx(0, 37));
+            assert_eq!(*name, test);
+            assert_eq!(*params, vec![Parameter::new(new_ctx(8, 13), x, &Type::I64)]);
+            assert_eq!(ty, Type::Bool);
+            assert_eq!(body.len(), 1);
+            match &body[0] {
+                Statement::Return(r) => {
+                    assert_eq!(
+                        *r.get_value(),
+                        Some(Expression::Boolean(new_ctx(31, 35), true))
+                    );
+                }
+                _ => panic!("No body"),
+            }
+        } else {
+            panic!("No nodes returned by parser")
+        }
+    }
+
+    #[test]
+    fn parse_const_function_def() {
+        for (text, is_const) in vec![
+            ("const fn test() -> i64 {return 1;}", true),
+            ("fn test() -> i64 {return 1;}", false),
+        ] {
+            let mut table = StringTable::new();
+            let test = table.insert("test".into());
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let parser = Parser::new(&logger);
+            let module = parser.parse(test, &tokens).unwrap().unwrap();
+            if let Some(Item::Routine(rd)) = module.get_item(test) {
+                assert_eq!(rd.is_const(), is_const);
+                assert_eq!(*rd.context(), new_ctx(0, text.len() as u32));
+            } else {
+                panic!("No nodes returned by parser")
+            }
+        }
+    }
+
+    #[test]
+    fn parse_missing_fn_token() {
+        // This tests that the parser will terminate if it reaches a point
+        // where it cannot advance while parsing a set of items.
+        let text = "
+        struct Test {
+        }
+
+        get_data() {
+            return;
+        }
+        ";
+        let mut table = StringTable::new();
+        let test = table.insert("test".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let parser = Parser::new(&logger);
+        parser.parse(test, &tokens).expect_err("This should fail");
+    }
+
+    #[test]
+    fn parse_trailing_comma_in_definitions() {
+        for (text, expected_len) in vec![
+            ("fn test(a: i64, b: i64,) {return;}", 2),
+            ("fn test(a: i64, b: i64) {return;}", 2),
+            ("extern fn test(a: i64,);", 1),
+            ("extern fn test(a: i64, ...);", 1),
+            ("struct test {a: i64, b: bool,}", 2),
+            ("struct test {a: i64, b: bool}", 2),
+            ("struct test {}\nimpl test { fn f(self,) {return;} }", 1),
+            (
+                "struct test {}\nimpl test { fn f(self, a: i64,) {return;} }",
+                2,
+            ),
+        ] {
+            let mut table = StringTable::new();
+            let test = table.insert("test".into());
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let parser = Parser::new(&logger);
+            let module = parser.parse(test, &tokens).unwrap().unwrap();
+            let len = match module.get_methods().next() {
+                Some(method) => method.get_params().len(),
+                None => match module.get_item(test) {
+                    Some(Item::Routine(rd)) => rd.get_params().len(),
+                    Some(Item::Struct(sd)) => sd.get_fields().len(),
+                    Some(Item::Extern(ex)) => ex.get_params().len(),
+                    None => panic!("No item returned by parser: {}", text),
+                },
+            };
+            assert_eq!(len, expected_len, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_trailing_comma_in_expressions() {
+        for (text, expected_len) in vec![
+            ("test(x, y,)", 2),
+            ("test(x,)", 1),
+            ("test {x: 1, y: 2,}", 2),
+            ("test {x: 1,}", 1),
+            ("[1, 2, 3,]", 3),
+            ("[1,]", 1),
+            ("wrapping_add(x, y,)", 2),
+        ] {
+            let mut table = StringTable::new();
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+            let len = match parser.expression(&mut stream).unwrap() {
+                Some(Expression::RoutineCall(_, _, _, params)) => params.len(),
+                Some(Expression::StructExpression(_, _, fields)) => fields.len(),
+                Some(Expression::ArrayExpression(_, elements, _)) => elements.len(),
+                Some(Expression::BinaryOp(..)) => 2,
+                exp => panic!("Unexpected expression for {}: {:?}", text, exp),
+            };
+            assert_eq!(len, expected_len, "{}", text);
+            assert!(stream.peek().is_none(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_trailing_comma_in_let_destructure() {
+        let text = "let (a: i64, b: i64,) := (1, 2,);";
+        let mut table = StringTable::new();
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+        let parser = Parser::new(&logger);
+        let stms = parser.statement(&mut stream).unwrap().unwrap();
+        assert_eq!(stms.len(), 2);
+    }
+
+    #[test]
+    fn parse_missing_comma_between_params_fails() {
+        for text in vec![
+            "fn test(a: i64 b: i64) {return;}",
+            "extern fn test(a: i64 b: i64);",
+            "struct test {a: i64 b: i64}",
+            "struct test {}\nimpl test { fn f(self a: i64) {return;} }",
+        ] {
+            let mut table = StringTable::new();
+            let test = table.insert("test".into());
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let parser = Parser::new(&logger);
+            assert!(parser.parse(test, &tokens).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_routine_call() {
+        let text = "test(x, y)";
+        let mut table = StringTable::new();
+        let x = table.insert("x".into());
+        let y = table.insert("y".into());
+        let test = table.insert("test".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let mut iter = TokenStream::new(&tokens, &logger).unwrap();
+        let parser = Parser::new(&logger);
+        if let Some(Expression::RoutineCall(l, RoutineCall::Function, name, params)) =
+            parser.expression(&mut iter).unwrap()
+        {
+            assert_eq!(l, new_ctx(0, 10));
+            assert_eq!(*name, vec![Element::Id(test)].into());
+            assert_eq!(
+                params,
+                vec![
+                    Expression::Identifier(new_ctx(5, 6), x),
+                    Expression::Identifier(new_ctx(8, 9), y)
+                ]
+            );
+        } else {
+            panic!("No nodes returned by parser")
+        }
+    }
+
+    #[test]
+    fn parse_routine_by_path_call() {
+        let text = "self::test(x, y)";
+        let mut table = StringTable::new();
+        let x = table.insert("x".into());
+        let y = table.insert("y".into());
+        let test = table.insert("test".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        println!("{:?}", table);
+        let mut iter = TokenStream::new(&tokens, &logger).unwrap();
+        let parser = Parser::new(&logger);
+        if let Some(Expression::RoutineCall(l, RoutineCall::Function, name, params)) =
+            parser.expression(&mut iter).unwrap()
+        {
+            assert_eq!(l, new_ctx(0, 16));
+            assert_eq!(*name, vec![Element::Selph, Element::Id(test)].into());
+            assert_eq!(
+                params,
+                vec![
+                    Expression::Identifier(new_ctx(11, 12), x),
+                    Expression::Identifier(new_ctx(14, 15), y)
+                ]
+            );
         } else {
             panic!("No nodes returned by parser")
         }
     }
 
     #[test]
-    fn parse_module_with_struct() {
-        let text = "mod test_struct_mod { struct my_struct{x: i64} }";
+    fn parse_if_expression() {
+        let text = "if (x) {5} else {7}";
         let mut table = StringTable::new();
-        let test_struct_mod = table.insert("test_struct_mod".into());
-        let test = table.insert("test".into());
-        let my_struct = table.insert("my_struct".into());
         let x = table.insert("x".into());
 
         let mut sm = SourceMap::new();
@@ -937,42 +2356,45 @@ pub mod tests {
             .into_iter()
             .collect::<LResult>()
             .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        if let Some(m) = parser
-            .parse(test, &tokens)
-            .unwrap()
-            .unwrap()
-            .get_module(test_struct_mod)
+        let exp = parser.expression(&mut stream).unwrap();
+        if let Some(Expression::If {
+            context: l,
+            cond,
+            if_arm,
+            else_arm,
+        }) = exp
         {
-            assert_eq!(*m.context(), new_ctx(0, 48));
-            assert_eq!(m.get_name(), test_struct_mod);
-
-            assert_eq!(m.get_modules().len(), 0);
-            assert_eq!(m.get_functions().len(), 0);
-            assert_eq!(m.get_coroutines().len(), 0);
-            assert_eq!(m.get_structs().len(), 1);
+            assert_eq!(l, new_ctx(0, 19));
+            assert_eq!(*cond, Expression::Identifier(new_ctx(4, 5), x));
+            if let Expression::ExpressionBlock(_l, _body, Some(final_exp)) = *if_arm {
+                assert_eq!(*final_exp, Expression::I64(new_ctx(8, 9), 5));
+            } else {
+                panic!("Expected Expression block");
+            }
 
-            if let Some(Item::Struct(sd)) = m.get_item(my_struct) {
-                assert_eq!(*sd.context(), new_ctx(22, 46));
-                assert_eq!(sd.get_name(), my_struct);
-                assert_eq!(
-                    sd.get_fields(),
-                    &vec![Parameter::new(new_ctx(39, 45), x, &Type::I64)]
-                );
+            if let Some(exp) = else_arm {
+                if let Expression::ExpressionBlock(_l, _body, Some(final_exp)) = *exp {
+                    assert_eq!(*final_exp, Expression::I64(new_ctx(17, 18), 7));
+                } else {
+                    panic!("Expected Expression block");
+                }
+            } else {
+                panic!("Expected Expression block");
             }
         } else {
-            panic!("No nodes returned by parser")
+            panic!("No nodes returned by parser, got: {:?}", exp)
         }
     }
 
     #[test]
-    fn parse_module_with_extern() {
-        let text = "mod test_extern_mod { extern fn my_fn(x: i64) -> i32; }";
+    fn parse_if_else_if_expression() {
+        let text = "if (x) {5} else if (y && z) {7} else {8}";
         let mut table = StringTable::new();
-        let test_extern_mod = table.insert("test_extern_mod".into());
-        let test = table.insert("test".into());
-        let my_fn = table.insert("my_fn".into());
         let x = table.insert("x".into());
+        let y = table.insert("y".into());
+        let z = table.insert("z".into());
 
         let mut sm = SourceMap::new();
         sm.add_string(text, "/test".into()).unwrap();
@@ -985,43 +2407,73 @@ pub mod tests {
             .into_iter()
             .collect::<LResult>()
             .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        if let Some(m) = parser
-            .parse(test, &tokens)
-            .unwrap()
-            .unwrap()
-            .get_module(test_extern_mod)
+        let exp = parser.expression(&mut stream).unwrap();
+        if let Some(Expression::If {
+            context,
+            cond,
+            if_arm,
+            else_arm,
+        }) = exp
         {
-            assert_eq!(*m.context(), new_ctx(0, 55));
-            assert_eq!(m.get_name(), test_extern_mod);
+            assert_eq!(context, new_ctx(0, 40));
+            assert_eq!(*cond, Expression::Identifier(new_ctx(4, 5), x));
+            if let Expression::ExpressionBlock(_l, _body, Some(final_exp)) = *if_arm {
+                assert_eq!(*final_exp, Expression::I64(new_ctx(8, 9), 5));
+            } else {
+                panic!("Expected Expression block");
+            }
 
-            assert_eq!(m.get_modules().len(), 0);
-            assert_eq!(m.get_functions().len(), 0);
-            assert_eq!(m.get_coroutines().len(), 0);
-            assert_eq!(m.get_structs().len(), 0);
-            assert_eq!(m.get_externs().len(), 1);
+            if let Some(exp) = else_arm {
+                if let Expression::If {
+                    cond,
+                    if_arm,
+                    else_arm,
+                    context,
+                } = *exp
+                {
+                    assert_eq!(context, new_ctx(16, 40));
+                    assert_eq!(
+                        *cond,
+                        Expression::BinaryOp(
+                            new_ctx(20, 26),
+                            BinaryOperator::BAnd,
+                            Box::new(Expression::Identifier(new_ctx(20, 21), y)),
+                            Box::new(Expression::Identifier(new_ctx(25, 26), z))
+                        )
+                    );
+                    if let Expression::ExpressionBlock(_l, _body, Some(final_exp)) = *if_arm {
+                        assert_eq!(*final_exp, Expression::I64(new_ctx(29, 30), 7));
+                    } else {
+                        panic!("Expected Expression block");
+                    }
 
-            if let Some(Item::Extern(e)) = m.get_item(my_fn) {
-                assert_eq!(*e.context(), new_ctx(22, 53));
-                assert_eq!(e.get_name(), my_fn);
-                assert_eq!(
-                    e.get_params(),
-                    &vec![Parameter::new(new_ctx(38, 44), x, &Type::I64)]
-                );
-                assert_eq!(e.get_return_type(), Type::I32);
+                    if let Some(exp) = else_arm {
+                        if let Expression::ExpressionBlock(_l, _body, Some(final_exp)) = *exp {
+                            assert_eq!(*final_exp, Expression::I64(new_ctx(38, 39), 8));
+                        } else {
+                            panic!("Expected Expression block");
+                        }
+                    } else {
+                        panic!("Expected expression block");
+                    }
+                } else {
+                    panic!("Expected if statement in else arm");
+                }
+            } else {
+                panic!("Expected if statement in else arm");
             }
         } else {
-            panic!("No nodes returned by parser")
+            panic!("No nodes returned by parser, got: {:?}", exp)
         }
     }
 
     #[test]
-    fn parse_module_with_extern_with_varargs() {
-        let text = "mod test_extern_mod { extern fn my_fn(x: i64, ...) -> i32; }";
+    fn parse_while_expression() {
+        let text = "while (x) {5;}";
+
         let mut table = StringTable::new();
-        let test_extern_mod = table.insert("test_extern_mod".into());
-        let test = table.insert("test".into());
-        let my_fn = table.insert("my_fn".into());
         let x = table.insert("x".into());
 
         let mut sm = SourceMap::new();
@@ -1035,43 +2487,81 @@ pub mod tests {
             .into_iter()
             .collect::<LResult>()
             .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        if let Some(m) = parser
-            .parse(test, &tokens)
-            .unwrap()
-            .unwrap()
-            .get_module(test_extern_mod)
+        let exp = parser.expression(&mut stream).unwrap();
+        if let Some(Expression::While {
+            context: l,
+            label,
+            cond,
+            body,
+        }) = exp
         {
-            assert_eq!(*m.context(), new_ctx(0, 60));
-            assert_eq!(m.get_name(), test_extern_mod);
-
-            assert_eq!(m.get_modules().len(), 0);
-            assert_eq!(m.get_functions().len(), 0);
-            assert_eq!(m.get_coroutines().len(), 0);
-            assert_eq!(m.get_structs().len(), 0);
-            assert_eq!(m.get_externs().len(), 1);
-
-            if let Some(Item::Extern(e)) = m.get_item(my_fn) {
-                assert_eq!(*e.context(), new_ctx(22, 58));
-                assert_eq!(e.get_name(), my_fn);
+            assert_eq!(l, new_ctx(0, 14));
+            assert_eq!(label, None);
+            assert_eq!(*cond, Expression::Identifier(new_ctx(7, 8), x));
+            if let Expression::ExpressionBlock(_ctx, body, None) = *body {
                 assert_eq!(
-                    e.get_params(),
-                    &vec![Parameter::new(new_ctx(38, 44), x, &Type::I64)]
+                    body[0],
+                    Statement::Expression(Box::new(Expression::I64(new_ctx(11, 13), 5)))
                 );
-                assert_eq!(e.get_return_type(), Type::I32);
-                assert_eq!(e.has_varargs, true);
+            } else {
+                panic!("Expected Expression block, got {:?}", *body);
             }
         } else {
-            panic!("No nodes returned by parser")
+            panic!("No nodes returned by parser, got: {:?}", exp)
         }
     }
 
     #[test]
-    fn parse_unit_function_def() {
-        let text = "fn test(x:i64) {return;}";
+    fn parse_labeled_loops() {
+        for (text, ctx) in [
+            ("'outer: while (x) {5;}", new_ctx(0, 22)),
+            ("'outer: for (i in 0..x) {5;}", new_ctx(0, 28)),
+        ] {
+            let mut table = StringTable::new();
+            let outer = table.insert("'outer".into());
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+            let exp = parser.expression(&mut stream).unwrap().unwrap();
+
+            // A for loop is desugared into a block which declares the loop variable
+            let whl = match exp {
+                Expression::ExpressionBlock(_, mut body, None) => match body.pop() {
+                    Some(Statement::Expression(whl)) => *whl,
+                    stm => panic!("Expected a while loop, got {:?}", stm),
+                },
+                whl => whl,
+            };
+            match whl {
+                Expression::While { context, label, .. } => {
+                    assert_eq!(context, ctx, "{}", text);
+                    assert_eq!(label, Some(outer), "{}", text);
+                }
+                exp => panic!("Expected a while loop, got {:?}", exp),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_if_let_some_expression() {
+        let text = "if (let some(x) := o) {x} else {0}";
+
         let mut table = StringTable::new();
         let x = table.insert("x".into());
-        let test = table.insert("test".into());
+        let o = table.insert("o".into());
 
         let mut sm = SourceMap::new();
         sm.add_string(text, "/test".into()).unwrap();
@@ -1084,37 +2574,55 @@ pub mod tests {
             .into_iter()
             .collect::<LResult>()
             .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        if let Some(Item::Routine(RoutineDef {
-            context: l,
-            def: RoutineDefType::Function,
-            name,
-            params,
-            ret_ty: ty,
-            body,
-            ..
-        })) = parser.parse(test, &tokens).unwrap().unwrap().get_item(test)
-        {
-            assert_eq!(*l, new_ctx(0, 24));
-            assert_eq!(*name, test);
-            assert_eq!(*params, vec![Parameter::new(new_ctx(8, 13), x, &Type::I64)]);
-            assert_eq!(ty, Type::Unit);
-            assert_eq!(body.len(), 1);
-            match &body[0] {
-                Statement::Return(r) => assert_eq!(*r.get_value(), None),
-                _ => panic!("Wrong body, expected unit return"),
-            }
-        } else {
-            panic!("No nodes returned by parser")
-        }
+        let exp = parser.expression(&mut stream).unwrap().unwrap();
+
+        let var = || Box::new(Expression::Identifier(new_ctx(13, 14), x));
+        let expected = Expression::ExpressionBlock(
+            new_ctx(0, 34),
+            vec![Statement::Bind(Box::new(Bind::new(
+                new_ctx(13, 20),
+                x,
+                Type::Unknown,
+                false,
+                Expression::Identifier(new_ctx(19, 20), o),
+            )))],
+            Some(Box::new(Expression::If {
+                context: new_ctx(0, 34),
+                cond: Box::new(Expression::UnaryOp(
+                    new_ctx(13, 14),
+                    UnaryOperator::OptionIsSome,
+                    var(),
+                )),
+                if_arm: Box::new(Expression::ExpressionBlock(
+                    new_ctx(22, 25),
+                    vec![Statement::Bind(Box::new(Bind::new(
+                        new_ctx(13, 14),
+                        x,
+                        Type::Unknown,
+                        false,
+                        Expression::UnaryOp(new_ctx(13, 14), UnaryOperator::OptionUnwrap, var()),
+                    )))],
+                    Some(Box::new(Expression::Identifier(new_ctx(23, 24), x))),
+                )),
+                else_arm: Some(Box::new(Expression::ExpressionBlock(
+                    new_ctx(31, 34),
+                    vec![],
+                    Some(Box::new(Expression::I64(new_ctx(32, 33), 0))),
+                ))),
+            })),
+        );
+        assert_eq!(exp, expected);
     }
 
     #[test]
-    fn parse_function_def() {
-        let text = "fn test(x:i64) -> bool {return true;}";
+    fn parse_if_let_err_expression() {
+        let text = "if (let err(e) := r) {e} else {0}";
+
         let mut table = StringTable::new();
-        let x = table.insert("x".into());
-        let test = table.insert("test".into());
+        let e = table.insert("e".into());
+        let r = table.insert("r".into());
 
         let mut sm = SourceMap::new();
         sm.add_string(text, "/test".into()).unwrap();
@@ -1127,50 +2635,60 @@ pub mod tests {
             .into_iter()
             .collect::<LResult>()
             .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        if let Some(Item::Routine(RoutineDef {
-            context: l,
-            def: RoutineDefType::Function,
-            name,
-            params,
-            ret_ty: ty,
-            body,
-            ..
-        })) = parser.parse(test, &tokens).unwrap().unwrap().get_item(test)
-        {
-            assert_eq!(*l, new_ctx(0, 37));
-            assert_eq!(*name, test);
-            assert_eq!(*params, vec![Parameter::new(new_ctx(8, 13), x, &Type::I64)]);
-            assert_eq!(ty, Type::Bool);
-            assert_eq!(body.len(), 1);
-            match &body[0] {
-                Statement::Return(r) => {
-                    assert_eq!(
-                        *r.get_value(),
-                        Some(Expression::Boolean(new_ctx(31, 35), true))
-                    );
-                }
-                _ => panic!("No body"),
-            }
-        } else {
-            panic!("No nodes returned by parser")
-        }
+        let exp = parser.expression(&mut stream).unwrap().unwrap();
+
+        let var = || Box::new(Expression::Identifier(new_ctx(12, 13), e));
+        let expected = Expression::ExpressionBlock(
+            new_ctx(0, 33),
+            vec![Statement::Bind(Box::new(Bind::new(
+                new_ctx(12, 19),
+                e,
+                Type::Unknown,
+                false,
+                Expression::Identifier(new_ctx(18, 19), r),
+            )))],
+            Some(Box::new(Expression::If {
+                context: new_ctx(0, 33),
+                cond: Box::new(Expression::UnaryOp(
+                    new_ctx(12, 13),
+                    UnaryOperator::Not,
+                    Box::new(Expression::UnaryOp(
+                        new_ctx(12, 13),
+                        UnaryOperator::ResultIsOk,
+                        var(),
+                    )),
+                )),
+                if_arm: Box::new(Expression::ExpressionBlock(
+                    new_ctx(21, 24),
+                    vec![Statement::Bind(Box::new(Bind::new(
+                        new_ctx(12, 13),
+                        e,
+                        Type::Unknown,
+                        false,
+                        Expression::UnaryOp(new_ctx(12, 13), UnaryOperator::ResultUnwrapErr, var()),
+                    )))],
+                    Some(Box::new(Expression::Identifier(new_ctx(22, 23), e))),
+                )),
+                else_arm: Some(Box::new(Expression::ExpressionBlock(
+                    new_ctx(30, 33),
+                    vec![],
+                    Some(Box::new(Expression::I64(new_ctx(31, 32), 0))),
+                ))),
+            })),
+        );
+        assert_eq!(exp, expected);
     }
 
     #[test]
-    fn parse_missing_fn_token() {
-        // This tests that the parser will terminate if it reaches a point
-        // where it cannot advance while parsing a set of items.
-        let text = "
-        struct Test {
-        }
+    fn parse_try_expression() {
+        let text = "f(x)?.a?";
 
-        get_data() {
-            return;
-        }
-        ";
         let mut table = StringTable::new();
-        let test = table.insert("test".into());
+        let f = table.insert("f".into());
+        let x = table.insert("x".into());
+        let a = table.insert("a".into());
 
         let mut sm = SourceMap::new();
         sm.add_string(text, "/test".into()).unwrap();
@@ -1183,17 +2701,39 @@ pub mod tests {
             .into_iter()
             .collect::<LResult>()
             .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        parser.parse(test, &tokens).expect_err("This should fail");
+        let exp = parser.expression(&mut stream).unwrap().unwrap();
+
+        let call = Expression::RoutineCall(
+            new_ctx(0, 4),
+            RoutineCall::Function,
+            Box::new(vec![Element::Id(f)].into()),
+            vec![Expression::Identifier(new_ctx(2, 3), x)],
+        );
+        let expected = Expression::UnaryOp(
+            new_ctx(0, 8),
+            UnaryOperator::Try,
+            Box::new(Expression::MemberAccess(
+                new_ctx(0, 7),
+                Box::new(Expression::UnaryOp(
+                    new_ctx(0, 5),
+                    UnaryOperator::Try,
+                    Box::new(call),
+                )),
+                a,
+            )),
+        );
+        assert_eq!(exp, expected);
     }
 
     #[test]
-    fn parse_routine_call() {
-        let text = "test(x, y)";
+    fn parse_for_expression() {
+        let text = "for (i in 0..n) {5;}";
+
         let mut table = StringTable::new();
-        let x = table.insert("x".into());
-        let y = table.insert("y".into());
-        let test = table.insert("test".into());
+        let i = table.insert("i".into());
+        let n = table.insert("n".into());
 
         let mut sm = SourceMap::new();
         sm.add_string(text, "/test".into()).unwrap();
@@ -1206,32 +2746,72 @@ pub mod tests {
             .into_iter()
             .collect::<LResult>()
             .unwrap();
-        let mut iter = TokenStream::new(&tokens, &logger).unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        if let Some(Expression::RoutineCall(l, RoutineCall::Function, name, params)) =
-            parser.expression(&mut iter).unwrap()
-        {
-            assert_eq!(l, new_ctx(0, 10));
-            assert_eq!(name, vec![Element::Id(test)].into());
-            assert_eq!(
-                params,
-                vec![
-                    Expression::Identifier(new_ctx(5, 6), x),
-                    Expression::Identifier(new_ctx(8, 9), y)
-                ]
-            );
-        } else {
-            panic!("No nodes returned by parser")
-        }
+        let exp = parser.expression(&mut stream).unwrap().unwrap();
+
+        let var = || Box::new(Expression::Identifier(new_ctx(5, 6), i));
+        let expected = Expression::ExpressionBlock(
+            new_ctx(0, 20),
+            vec![
+                Statement::Bind(Box::new(Bind::new(
+                    new_ctx(5, 11),
+                    i,
+                    Type::I64,
+                    true,
+                    Expression::I64(new_ctx(10, 11), 0),
+                ))),
+                Statement::Expression(Box::new(Expression::While {
+                    context: new_ctx(0, 20),
+                    label: None,
+                    cond: Box::new(Expression::BinaryOp(
+                        new_ctx(10, 14),
+                        BinaryOperator::Ls,
+                        var(),
+                        Box::new(Expression::Identifier(new_ctx(13, 14), n)),
+                    )),
+                    body: Box::new(Expression::ExpressionBlock(
+                        new_ctx(16, 20),
+                        vec![
+                            Statement::Defer(Box::new(Defer::new(
+                                new_ctx(5, 6),
+                                Expression::ExpressionBlock(
+                                    new_ctx(5, 6),
+                                    vec![Statement::Mutate(Box::new(Mutate::new(
+                                        new_ctx(5, 6),
+                                        *var(),
+                                        Expression::BinaryOp(
+                                            new_ctx(5, 6),
+                                            BinaryOperator::Add,
+                                            var(),
+                                            Box::new(Expression::TypeCast(
+                                                new_ctx(5, 6),
+                                                Box::new(Expression::I64(new_ctx(5, 6), 1)),
+                                                Box::new(Type::I64),
+                                            )),
+                                        ),
+                                    )))],
+                                    None,
+                                ),
+                            ))),
+                            Statement::Expression(Box::new(Expression::I64(new_ctx(17, 19), 5))),
+                        ],
+                        None,
+                    )),
+                })),
+            ],
+            None,
+        );
+        assert_eq!(exp, expected);
     }
 
     #[test]
-    fn parse_routine_by_path_call() {
-        let text = "self::test(x, y)";
+    fn parse_for_range_value_expression() {
+        let text = "for (i in r) {5;}";
+
         let mut table = StringTable::new();
-        let x = table.insert("x".into());
-        let y = table.insert("y".into());
-        let test = table.insert("test".into());
+        let i = table.insert("i".into());
+        let r = table.insert("r".into());
 
         let mut sm = SourceMap::new();
         sm.add_string(text, "/test".into()).unwrap();
@@ -1244,31 +2824,255 @@ pub mod tests {
             .into_iter()
             .collect::<LResult>()
             .unwrap();
-        println!("{:?}", table);
-        let mut iter = TokenStream::new(&tokens, &logger).unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        if let Some(Expression::RoutineCall(l, RoutineCall::Function, name, params)) =
-            parser.expression(&mut iter).unwrap()
-        {
-            assert_eq!(l, new_ctx(0, 16));
-            assert_eq!(name, vec![Element::Selph, Element::Id(test)].into());
-            assert_eq!(
-                params,
-                vec![
-                    Expression::Identifier(new_ctx(11, 12), x),
-                    Expression::Identifier(new_ctx(14, 15), y)
-                ]
-            );
-        } else {
-            panic!("No nodes returned by parser")
+        let exp = parser.expression(&mut stream).unwrap().unwrap();
+
+        let var = || Expression::Identifier(new_ctx(5, 6), i);
+        let call = |step| {
+            Expression::RoutineCall(
+                new_ctx(10, 11),
+                RoutineCall::Iterator(step),
+                Box::new(Path::new()),
+                vec![var()],
+            )
+        };
+        let expected = Expression::ExpressionBlock(
+            new_ctx(0, 17),
+            vec![
+                Statement::Bind(Box::new(Bind::new(
+                    new_ctx(5, 11),
+                    i,
+                    Type::Unknown,
+                    true,
+                    Expression::Identifier(new_ctx(10, 11), r),
+                ))),
+                Statement::Expression(Box::new(Expression::While {
+                    context: new_ctx(0, 17),
+                    label: None,
+                    cond: Box::new(Expression::UnaryOp(
+                        new_ctx(10, 11),
+                        UnaryOperator::Not,
+                        Box::new(call(IteratorStep::IsEmpty)),
+                    )),
+                    body: Box::new(Expression::ExpressionBlock(
+                        new_ctx(13, 17),
+                        vec![
+                            Statement::Defer(Box::new(Defer::new(
+                                new_ctx(10, 11),
+                                Expression::ExpressionBlock(
+                                    new_ctx(10, 11),
+                                    vec![Statement::Mutate(Box::new(Mutate::new(
+                                        new_ctx(10, 11),
+                                        var(),
+                                        call(IteratorStep::Rest),
+                                    )))],
+                                    None,
+                                ),
+                            ))),
+                            Statement::Expression(Box::new(Expression::ExpressionBlock(
+                                new_ctx(13, 17),
+                                vec![
+                                    Statement::Bind(Box::new(Bind::new(
+                                        new_ctx(5, 6),
+                                        i,
+                                        Type::I64,
+                                        true,
+                                        call(IteratorStep::First),
+                                    ))),
+                                    Statement::Expression(Box::new(Expression::I64(
+                                        new_ctx(14, 16),
+                                        5,
+                                    ))),
+                                ],
+                                None,
+                            ))),
+                        ],
+                        None,
+                    )),
+                })),
+            ],
+            None,
+        );
+        assert_eq!(exp, expected);
+    }
+
+    #[test]
+    fn parse_for_expression_with_type() {
+        for (text, expected) in vec![
+            ("for (i: u8 in 0u8..10u8) {}", Ok(Type::U8)),
+            (
+                "for (i: in 0..10) {}",
+                Err("L1: Expected type of loop variable after :"),
+            ),
+            (
+                "for (in 0..10) {}",
+                Err("L1: Expected loop variable after for keyword"),
+            ),
+            ("for (i in ) {}", Err("L1: Expected a range after in")),
+            (
+                "for (i in 0..10)",
+                Err("L1: Expected expression block for for loop body"),
+            ),
+        ] {
+            let mut table = StringTable::new();
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+            let parser = Parser::new(&logger);
+            let result = parser.expression(&mut stream);
+            match expected {
+                Ok(ty) => match result.unwrap().unwrap() {
+                    Expression::ExpressionBlock(_, body, _) => match &body[0] {
+                        Statement::Bind(b) => assert_eq!(*b.get_type(), ty),
+                        stm => panic!("Expected a bind, got {:?}", stm),
+                    },
+                    exp => panic!("Expected an expression block, got {:?}", exp),
+                },
+                Err(msg) => {
+                    assert_eq!(
+                        result.unwrap_err().fmt(&sm, &table).unwrap(),
+                        msg,
+                        "{}",
+                        text
+                    )
+                }
+            }
         }
     }
 
     #[test]
-    fn parse_if_expression() {
-        let text = "if (x) {5} else {7}";
+    fn parse_struct_def() {
         let mut table = StringTable::new();
+        let test = table.insert("test".into());
+        let my_struct = table.insert("MyStruct".into());
         let x = table.insert("x".into());
+        let y = table.insert("y".into());
+        let derive = table.insert("derive".into());
+        let print = table.insert("print".into());
+        let eq = table.insert("eq".into());
+
+        for (text, expected) in vec![
+            (
+                "struct MyStruct {}",
+                StructDef::new(my_struct, new_ctx(0, 18), vec![]),
+            ),
+            (
+                "struct MyStruct {x: i64}",
+                StructDef::new(
+                    my_struct,
+                    new_ctx(0, 24),
+                    vec![Parameter::new(new_ctx(17, 23), x, &Type::I64)],
+                ),
+            ),
+            (
+                "struct MyStruct {x: i64, y: bool}",
+                StructDef::new(
+                    my_struct,
+                    new_ctx(0, 33),
+                    vec![
+                        Parameter::new(new_ctx(17, 23), x, &Type::I64),
+                        Parameter::new(new_ctx(25, 32), y, &Type::Bool),
+                    ],
+                ),
+            ),
+            ("struct MyStruct {x: i64, pub y: bool}", {
+                let mut sd = StructDef::new(
+                    my_struct,
+                    new_ctx(0, 37),
+                    vec![
+                        Parameter::new(new_ctx(17, 23), x, &Type::I64),
+                        Parameter::new(new_ctx(25, 36), y, &Type::Bool),
+                    ],
+                );
+                *sd.get_public_fields_mut() = vec![y];
+                sd
+            }),
+            ("#[derive(print, eq)] struct MyStruct {}", {
+                let mut sd = StructDef::new(my_struct, new_ctx(21, 39), vec![]);
+                *sd.get_attributes_mut() =
+                    vec![Attribute::new(new_span(0, 20), derive, vec![print, eq])];
+                sd
+            }),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let parser = Parser::new(&logger);
+            if let Some(m) = parser.parse(test, &tokens).unwrap() {
+                assert_eq!(m.get_structs()[0], Item::Struct(expected), "{:?}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_struct_def_fails() {
+        for (text, msg) in vec![
+            (
+                "struct MyStruct {x: i64, pub}",
+                "L1: Expected field declaration after pub",
+            ),
+            (
+                "struct MyStruct {pub pub x: i64}",
+                "L1: Expected field declaration after pub",
+            ),
+            (
+                "#[derive(print)] fn test() {return;}",
+                "L1: Expected a structure or an extern after attributes",
+            ),
+            (
+                "#[] struct MyStruct {}",
+                "L1: Expected the name of an attribute after #[",
+            ),
+            (
+                "#[link_name = SDL_Init] extern fn start();",
+                "L1: Expected a string literal after = in the attribute",
+            ),
+        ] {
+            let mut table = StringTable::new();
+            let test = table.insert("test".into());
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let parser = Parser::new(&logger);
+            let err = parser.parse(test, &tokens).unwrap_err();
+            assert_eq!(err.fmt(&sm, &table).unwrap(), msg, "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_static_assert() {
+        let text = "static_assert(1 < 2, \"msg\");";
+        let mut table = StringTable::new();
+        let test = table.insert("test".into());
+        let msg = table.insert("msg".into());
 
         let mut sm = SourceMap::new();
         sm.add_string(text, "/test".into()).unwrap();
@@ -1281,45 +3085,60 @@ pub mod tests {
             .into_iter()
             .collect::<LResult>()
             .unwrap();
-        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        let exp = parser.expression(&mut stream).unwrap();
-        if let Some(Expression::If {
-            context: l,
-            cond,
-            if_arm,
-            else_arm,
-        }) = exp
-        {
-            assert_eq!(l, new_ctx(0, 19));
-            assert_eq!(*cond, Expression::Identifier(new_ctx(4, 5), x));
-            if let Expression::ExpressionBlock(_l, _body, Some(final_exp)) = *if_arm {
-                assert_eq!(*final_exp, Expression::I64(new_ctx(8, 9), 5));
-            } else {
-                panic!("Expected Expression block");
-            }
+        let module = parser.parse(test, &tokens).unwrap().unwrap();
+
+        let asserts = module.get_static_asserts();
+        assert_eq!(asserts.len(), 1);
+        assert_eq!(asserts[0].span(), new_span(0, 28));
+        assert_eq!(asserts[0].get_message(), msg);
+        assert!(matches!(
+            asserts[0].get_cond(),
+            Expression::BinaryOp(_, BinaryOperator::Ls, _, _)
+        ));
+    }
 
-            if let Some(exp) = else_arm {
-                if let Expression::ExpressionBlock(_l, _body, Some(final_exp)) = *exp {
-                    assert_eq!(*final_exp, Expression::I64(new_ctx(17, 18), 7));
-                } else {
-                    panic!("Expected Expression block");
-                }
-            } else {
-                panic!("Expected Expression block");
-            }
-        } else {
-            panic!("No nodes returned by parser, got: {:?}", exp)
+    #[test]
+    fn parse_static_assert_fails() {
+        for text in [
+            "static_assert(true);",
+            "static_assert(true, msg);",
+            "static_assert(, \"msg\");",
+        ] {
+            let mut table = StringTable::new();
+            let test = table.insert("test".into());
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let parser = Parser::new(&logger);
+            let err = parser.parse(test, &tokens).unwrap_err();
+            assert_eq!(
+                err.fmt(&sm, &table).unwrap(),
+                "L1: static_assert expects a condition and a message, e.g. static_assert(cond, \"message\")",
+                "{}",
+                text
+            );
         }
     }
 
     #[test]
-    fn parse_if_else_if_expression() {
-        let text = "if (x) {5} else if (y && z) {7} else {8}";
+    fn parse_macro() {
+        let text =
+            "macro sq { ($x:expr) => { $x * $x }; }\nfn test() -> i64 { return sq!(1 + 2); }";
         let mut table = StringTable::new();
+        let test = table.insert("test".into());
+        let sq = table.insert("sq".into());
         let x = table.insert("x".into());
-        let y = table.insert("y".into());
-        let z = table.insert("z".into());
+        let expr = table.insert("expr".into());
 
         let mut sm = SourceMap::new();
         sm.add_string(text, "/test".into()).unwrap();
@@ -1332,74 +3151,96 @@ pub mod tests {
             .into_iter()
             .collect::<LResult>()
             .unwrap();
-        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        let exp = parser.expression(&mut stream).unwrap();
-        if let Some(Expression::If {
-            context,
-            cond,
-            if_arm,
-            else_arm,
-        }) = exp
-        {
-            assert_eq!(context, new_ctx(0, 40));
-            assert_eq!(*cond, Expression::Identifier(new_ctx(4, 5), x));
-            if let Expression::ExpressionBlock(_l, _body, Some(final_exp)) = *if_arm {
-                assert_eq!(*final_exp, Expression::I64(new_ctx(8, 9), 5));
-            } else {
-                panic!("Expected Expression block");
-            }
-
-            if let Some(exp) = else_arm {
-                if let Expression::If {
-                    cond,
-                    if_arm,
-                    else_arm,
-                    context,
-                } = *exp
-                {
-                    assert_eq!(context, new_ctx(16, 40));
-                    assert_eq!(
-                        *cond,
-                        Expression::BinaryOp(
-                            new_ctx(20, 26),
-                            BinaryOperator::BAnd,
-                            Box::new(Expression::Identifier(new_ctx(20, 21), y)),
-                            Box::new(Expression::Identifier(new_ctx(25, 26), z))
-                        )
-                    );
-                    if let Expression::ExpressionBlock(_l, _body, Some(final_exp)) = *if_arm {
-                        assert_eq!(*final_exp, Expression::I64(new_ctx(29, 30), 7));
-                    } else {
-                        panic!("Expected Expression block");
-                    }
+        let m = parser.parse(test, &tokens).unwrap().unwrap();
 
-                    if let Some(exp) = else_arm {
-                        if let Expression::ExpressionBlock(_l, _body, Some(final_exp)) = *exp {
-                            assert_eq!(*final_exp, Expression::I64(new_ctx(38, 39), 8));
-                        } else {
-                            panic!("Expected Expression block");
-                        }
-                    } else {
-                        panic!("Expected expression block");
-                    }
-                } else {
-                    panic!("Expected if statement in else arm");
-                }
-            } else {
-                panic!("Expected if statement in else arm");
-            }
-        } else {
-            panic!("No nodes returned by parser, got: {:?}", exp)
+        let expected = MacroDef::new(
+            new_span(0, 38),
+            sq,
+            vec![MacroRule::new(
+                vec![MacroPattern::Fragment(x, expr)],
+                vec![
+                    Token::new(Lex::Dollar, new_span(26, 27)),
+                    Token::new(Lex::Identifier(x), new_span(27, 28)),
+                    Token::new(Lex::Mul, new_span(29, 30)),
+                    Token::new(Lex::Dollar, new_span(31, 32)),
+                    Token::new(Lex::Identifier(x), new_span(32, 33)),
+                ],
+            )],
+        );
+        assert_eq!(m.get_macro(sq), Some(&expected));
+
+        let body = match m.get_item(test) {
+            Some(Item::Routine(r)) => r.get_body(),
+            _ => panic!("Expected a routine"),
+        };
+        match &body[0] {
+            Statement::Return(ret) => assert_eq!(
+                *ret.get_value(),
+                Some(Expression::MacroCall(
+                    new_ctx(65, 75),
+                    sq,
+                    vec![
+                        Token::new(Lex::I64(1), new_span(69, 70)),
+                        Token::new(Lex::Add, new_span(71, 72)),
+                        Token::new(Lex::I64(2), new_span(73, 74)),
+                    ]
+                ))
+            ),
+            _ => panic!("Expected a return"),
         }
     }
 
     #[test]
-    fn parse_while_expression() {
-        let text = "while (x) {5;}";
+    fn parse_macro_fails() {
+        for (text, msg) in vec![
+            (
+                "macro { () => { 1 }; }",
+                "L1: Expected the name of the macro after macro",
+            ),
+            (
+                "macro m { }",
+                "L1: Expected at least one rule in the macro definition",
+            ),
+            (
+                "macro m { ($x) => { 1 }; }",
+                "L1: Expected $name:kind in the pattern of the macro rule",
+            ),
+            (
+                "macro m { ($x:expr) { 1 }; }",
+                "L1: Expected =>, but found {",
+            ),
+        ] {
+            let mut table = StringTable::new();
+            let test = table.insert("test".into());
+
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+            let parser = Parser::new(&logger);
+            let err = parser.parse(test, &tokens).unwrap_err();
+            assert_eq!(err.fmt(&sm, &table).unwrap(), msg, "{}", text);
+        }
+    }
 
+    #[test]
+    fn parse_impl_block() {
+        let text = "impl P { fn get(self, y: i64) -> i64 {return self.x;} }\nstruct P {x: i64}";
         let mut table = StringTable::new();
+        let test = table.insert("test".into());
+        let p = table.insert("P".into());
+        let get = table.insert("get".into());
+        let selph = table.insert("self".into());
         let x = table.insert("x".into());
+        let y = table.insert("y".into());
 
         let mut sm = SourceMap::new();
         sm.add_string(text, "/test".into()).unwrap();
@@ -1412,63 +3253,63 @@ pub mod tests {
             .into_iter()
             .collect::<LResult>()
             .unwrap();
-        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
         let parser = Parser::new(&logger);
-        let exp = parser.expression(&mut stream).unwrap();
-        if let Some(Expression::While {
-            context: l,
-            cond,
-            body,
-        }) = exp
-        {
-            assert_eq!(l, new_ctx(0, 14));
-            assert_eq!(*cond, Expression::Identifier(new_ctx(7, 8), x));
-            if let Expression::ExpressionBlock(_ctx, body, None) = *body {
-                assert_eq!(
-                    body[0],
-                    Statement::Expression(Box::new(Expression::I64(new_ctx(11, 13), 5)))
-                );
-            } else {
-                panic!("Expected Expression block, got {:?}", *body);
+        let m = parser.parse(test, &tokens).unwrap().unwrap();
+
+        if let Item::Struct(sd) = &m.get_structs()[0] {
+            assert_eq!(sd.get_methods().len(), 1);
+            let method = &sd.get_methods()[0];
+            assert_eq!(method.get_name(), get);
+            assert_eq!(
+                method
+                    .get_params()
+                    .iter()
+                    .map(|p| (p.name, p.ty.clone()))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (selph, Type::Custom(vec![Element::Id(p)].into())),
+                    (y, Type::I64)
+                ]
+            );
+            assert_eq!(method.get_return_type(), &Type::I64);
+            match &method.get_body()[0] {
+                Statement::Return(r) => match r.get_value() {
+                    Some(Expression::MemberAccess(_, receiver, field)) => {
+                        assert_eq!(**receiver, Expression::Identifier(new_ctx(45, 49), selph));
+                        assert_eq!(*field, x);
+                    }
+                    v => panic!("Expected member access but got {:?}", v),
+                },
+                s => panic!("Expected return but got {:?}", s),
             }
         } else {
-            panic!("No nodes returned by parser, got: {:?}", exp)
+            panic!("Expected a structure")
         }
     }
 
     #[test]
-    fn parse_struct_def() {
-        let mut table = StringTable::new();
-        let test = table.insert("test".into());
-        let my_struct = table.insert("MyStruct".into());
-        let x = table.insert("x".into());
-        let y = table.insert("y".into());
-
+    fn parse_impl_block_fails() {
         for (text, expected) in vec![
             (
-                "struct MyStruct {}",
-                StructDef::new(my_struct, new_ctx(0, 18), vec![]),
+                "impl P { fn f(self) {return;} }",
+                "L1: impl for P must be in the same module as the structure",
             ),
             (
-                "struct MyStruct {x: i64}",
-                StructDef::new(
-                    my_struct,
-                    new_ctx(0, 24),
-                    vec![Parameter::new(new_ctx(17, 23), x, &Type::I64)],
-                ),
+                "struct P {}\nimpl P { fn f() {return;} }",
+                "L2: Methods must take self as their first parameter",
             ),
             (
-                "struct MyStruct {x: i64, y: bool}",
-                StructDef::new(
-                    my_struct,
-                    new_ctx(0, 33),
-                    vec![
-                        Parameter::new(new_ctx(17, 23), x, &Type::I64),
-                        Parameter::new(new_ctx(25, 32), y, &Type::Bool),
-                    ],
-                ),
+                "struct P {f: i64}\nimpl P { fn f(self) {return;} }",
+                "L2: Structure P already contains f\n    L1: note: previous field definition here",
+            ),
+            (
+                "struct P {}\nimpl P { fn f(self) {return;} }\nimpl P { fn f(self) {return;} }",
+                "L3: Structure P already contains f\n    L2: note: previous method definition here",
             ),
         ] {
+            let mut table = StringTable::new();
+            let test = table.insert("test".into());
+
             let mut sm = SourceMap::new();
             sm.add_string(text, "/test".into()).unwrap();
             let src = sm.get(0).unwrap().read().unwrap();
@@ -1481,9 +3322,9 @@ pub mod tests {
                 .collect::<LResult>()
                 .unwrap();
             let parser = Parser::new(&logger);
-            if let Some(m) = parser.parse(test, &tokens).unwrap() {
-                assert_eq!(m.get_structs()[0], Item::Struct(expected), "{:?}", text);
-            }
+
+            let err = parser.parse(test, &tokens).unwrap_err();
+            assert_eq!(err.fmt(&sm, &table).unwrap(), expected, "{}", text);
         }
     }
 
@@ -1501,7 +3342,7 @@ pub mod tests {
                 "MyStruct{}",
                 Expression::StructExpression(
                     new_ctx(0, 10),
-                    vec![Element::Id(my_struct)].into(),
+                    Box::new(vec![Element::Id(my_struct)].into()),
                     vec![],
                 ),
             ),
@@ -1509,7 +3350,7 @@ pub mod tests {
                 "MyStruct{x: 5}",
                 Expression::StructExpression(
                     new_ctx(0, 14),
-                    vec![Element::Id(my_struct)].into(),
+                    Box::new(vec![Element::Id(my_struct)].into()),
                     vec![(x, Expression::I64(new_ctx(12, 13), 5))],
                 ),
             ),
@@ -1517,7 +3358,7 @@ pub mod tests {
                 "MyStruct{x: 5, y: false}",
                 Expression::StructExpression(
                     new_ctx(0, 24),
-                    vec![Element::Id(my_struct)].into(),
+                    Box::new(vec![Element::Id(my_struct)].into()),
                     vec![
                         (x, Expression::I64(new_ctx(12, 13), 5)),
                         (y, Expression::Boolean(new_ctx(18, 23), false)),
@@ -1528,14 +3369,14 @@ pub mod tests {
                 "MyStruct{x: 5, y: MyStruct2{z:3}}",
                 Expression::StructExpression(
                     new_ctx(0, 33),
-                    vec![Element::Id(my_struct)].into(),
+                    Box::new(vec![Element::Id(my_struct)].into()),
                     vec![
                         (x, Expression::I64(new_ctx(12, 13), 5)),
                         (
                             y,
                             Expression::StructExpression(
                                 new_ctx(18, 32),
-                                vec![Element::Id(my_struct2)].into(),
+                                Box::new(vec![Element::Id(my_struct2)].into()),
                                 vec![(z, Expression::I64(new_ctx(30, 31), 3))],
                             ),
                         ),
@@ -1606,6 +3447,33 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn parse_c_string_literal() {
+        let text = "c\"test\"";
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+
+        let mut table = StringTable::new();
+        let src = sm.get(0).unwrap().read().unwrap();
+        let logger = Logger::new();
+        let tokens = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+        let parser = Parser::new(&logger);
+
+        let test = table.insert("test".into());
+        let expected = Expression::TypeCast(
+            new_ctx(0, 7),
+            Box::new(Expression::StringLiteral(new_ctx(0, 7), test)),
+            Box::new(Type::RawPointer(PointerMut::Const, Box::new(Type::U8))),
+        );
+        assert_eq!(parser.expression(&mut stream), Ok(Some(expected)));
+    }
+
     #[test]
     fn parse_null() {
         for (text, expected) in vec![("null", Expression::Null(new_ctx(0, 4)))] {
@@ -2009,6 +3877,49 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn parse_method_call() {
+        let text = "thing.area(2).x";
+        let mut table = StringTable::new();
+        let thing_id = table.insert("thing".into());
+        let area_id = table.insert("area".into());
+        let x_id = table.insert("x".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+        let parser = Parser::new(&logger);
+        match parser.subdata_access(&mut stream) {
+            Ok(Some(Expression::MemberAccess(_, call, field))) => {
+                assert_eq!(field, x_id);
+                assert_eq!(
+                    *call,
+                    Expression::RoutineCall(
+                        new_ctx(0, 13),
+                        RoutineCall::Method,
+                        Box::new(vec![Element::Id(area_id)].into()),
+                        vec![
+                            Expression::Identifier(new_ctx(0, 5), thing_id),
+                            Expression::I64(new_ctx(11, 12), 2),
+                        ]
+                    )
+                );
+            }
+            Ok(Some(n)) => panic!("{} resulted in {:?}", text, n),
+            Ok(None) => panic!("No node returned for {}", text),
+            Err(msg) => panic!("{} caused {:?}", text, msg),
+        }
+    }
+
     #[test]
     fn parse_expression_block_oneline() {
         let text = "{5}";
@@ -2039,6 +3950,41 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn parse_expression_block_with_return() {
+        let text = "{return 5;}";
+        let mut table = StringTable::new();
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let mut stream = TokenStream::new(&tokens, &logger).unwrap();
+
+        let parser = Parser::new(&logger);
+        if let Some(Expression::ExpressionBlock(ctx, body, None)) =
+            parser.expression_block(&mut stream).unwrap()
+        {
+            assert_eq!(ctx, new_ctx(0, 11));
+            assert_eq!(body.len(), 1);
+            match &body[0] {
+                Statement::Return(r) => {
+                    assert_eq!(*r.get_value(), Some(Expression::I64(new_ctx(8, 9), 5)));
+                }
+                _ => panic!("Not a return statement"),
+            }
+        } else {
+            panic!("No nodes returned by parser")
+        }
+    }
+
     #[test]
     fn parse_expression_block_bad() {
         for (text, msg) in [
@@ -2135,7 +4081,7 @@ pub mod tests {
                     if let Expression::RoutineCall(_, RoutineCall::Function, fn_name, params) =
                         &**exp
                     {
-                        assert_eq!(*fn_name, vec![Element::Id(f)].into());
+                        assert_eq!(**fn_name, vec![Element::Id(f)].into());
                         assert_eq!(params[0], Expression::Identifier(new_ctx(19, 20), x));
                     } else {
                         panic!("No body");
@@ -2154,4 +4100,38 @@ pub mod tests {
             panic!("No nodes returned by parser")
         }
     }
+
+    #[test]
+    fn parse_out_of_fuel() {
+        let text = "fn test() {\n    let x: i64 := 1 + 2;\n    return;\n}";
+        let mut table = StringTable::new();
+        let test = table.insert("test".into());
+
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+
+        // Plenty of fuel to parse the routine
+        let fuel = Fuel::new(Some(100), None);
+        let parser = Parser::new(&logger).with_fuel(&fuel);
+        assert!(parser.parse(test, &tokens).unwrap().is_some());
+        assert!(fuel.burned() > 1);
+
+        // Enough fuel for the let statement, but not the expression that it binds
+        let fuel = Fuel::new(Some(1), None);
+        let parser = Parser::new(&logger).with_fuel(&fuel);
+        let err = parser.parse(test, &tokens).unwrap_err();
+        assert_eq!(
+            err.fmt(&sm, &table).unwrap(),
+            "L2: Parsing was stopped because it used all 1 units of fuel"
+        );
+    }
 }
@@ -61,6 +61,19 @@ impl<'a> TokenStream<'a> {
         }
     }
 
+    /// Consumes the next token if it is the label of a loop and returns the label.
+    pub fn next_if_label(&mut self) -> Option<(StringId, Span)> {
+        match self.next_if(&Lex::Label(StringId::new())) {
+            Some(Token {
+                span,
+                sym: Lex::Label(label),
+                ..
+            }) => Some((label, span)),
+            Some(_) => None,
+            None => None,
+        }
+    }
+
     pub fn next_must_be(&mut self, test: &Lex) -> Result<Token, CompilerError<ParserError>> {
         match self.peek() {
             Some(t) => {
@@ -0,0 +1,459 @@
+//! Registration of custom passes which are run between the standard phases of the
+//! compiler.  This lets a program which uses the compiler as a library add its own
+//! lints, instrumentation, or code transforms without changing the compiler itself.
+//!
+//! # Pass Ordering
+//! A [`Pipeline`](super::pipeline::Pipeline) runs custom passes at three fixed points:
+//!
+//! 1. [`AstPass`]es are run on the parsed AST after dead branches have been pruned
+//! and before type resolution.  Any change which they make to the AST is type checked
+//! along with the rest of the program.
+//! 2. [`SemanticPass`]es are run on the type resolved AST before its reflection
+//! builtins are evaluated and before it is verified, linted, or lowered.  A semantic
+//! pass must keep every node's type and every path canonical: the pipeline fails if a
+//! node is left without a type, and `--verify-ir` checks that every path is canonical.
+//! 3. [`MirPass`]es are run on the MIR after it has been generated and before it is
+//! verified and lowered to LLVM.
+//!
+//! At each point, passes are run in the order in which they were registered.  A pass
+//! reports problems through the [`PassDiagnostics`] that it is given.  If a pass
+//! reports an error, then the passes registered after it at the same point are not
+//! run and the compiler stops after reporting the errors.  Warnings never stop
+//! compilation.
+
+use crate::{
+    compiler::{
+        ast::Module, parser::ParserContext, semantics::semanticnode::SemanticContext,
+        CompilerDisplay, CompilerDisplayError, CompilerError, MirProject, SourceMap, Span,
+    },
+    StringTable,
+};
+
+/// A message reported by a custom pass.  Custom passes are not part of the compiler's
+/// message catalog, so the message is stored as text along with the name of the pass
+/// which reported it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PassMessage {
+    pass: String,
+    message: String,
+}
+
+impl PassMessage {
+    /// The name of the pass which reported this message.
+    pub fn pass(&self) -> &str {
+        &self.pass
+    }
+
+    /// The text of this message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl CompilerDisplay for PassMessage {
+    fn fmt(&self, _: &SourceMap, _: &StringTable) -> Result<String, CompilerDisplayError> {
+        Ok(format!("{}: {}", self.pass, self.message))
+    }
+}
+
+/// Collects the warnings and errors which are reported by the passes run at a single
+/// point in the pipeline.
+#[derive(Debug, Default)]
+pub struct PassDiagnostics {
+    /// The name of the pass which is currently running
+    pass: String,
+    warnings: Vec<CompilerError<PassMessage>>,
+    errors: Vec<CompilerError<PassMessage>>,
+}
+
+impl PassDiagnostics {
+    /// Reports a warning about the given span of source code.
+    pub fn warn(&mut self, span: Span, message: &str) {
+        let msg = self.message(message);
+        self.warnings.push(CompilerError::new(span, msg))
+    }
+
+    /// Reports an error about the given span of source code.  Compilation will stop
+    /// once the pass which reported the error finishes.
+    pub fn error(&mut self, span: Span, message: &str) {
+        let msg = self.message(message);
+        self.errors.push(CompilerError::new(span, msg))
+    }
+
+    /// The warnings which have been reported.
+    pub fn warnings(&self) -> &[CompilerError<PassMessage>] {
+        &self.warnings
+    }
+
+    /// The errors which have been reported.
+    pub fn errors(&self) -> &[CompilerError<PassMessage>] {
+        &self.errors
+    }
+
+    /// Returns true if any pass reported an error.
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// Splits these diagnostics into their warnings and their errors.
+    pub fn into_parts(
+        self,
+    ) -> (
+        Vec<CompilerError<PassMessage>>,
+        Vec<CompilerError<PassMessage>>,
+    ) {
+        (self.warnings, self.errors)
+    }
+
+    fn message(&self, message: &str) -> PassMessage {
+        PassMessage {
+            pass: self.pass.clone(),
+            message: message.into(),
+        }
+    }
+}
+
+/// A pass which is run on the AST immediately before type resolution.
+pub trait AstPass {
+    /// The name of the pass, which is added to every message it reports.
+    fn name(&self) -> &str;
+
+    fn run(
+        &mut self,
+        module: &mut Module<ParserContext>,
+        st: &StringTable,
+        diags: &mut PassDiagnostics,
+    );
+}
+
+/// A pass which is run on the AST immediately after type resolution.
+pub trait SemanticPass {
+    /// The name of the pass, which is added to every message it reports.
+    fn name(&self) -> &str;
+
+    fn run(
+        &mut self,
+        module: &mut Module<SemanticContext>,
+        st: &StringTable,
+        diags: &mut PassDiagnostics,
+    );
+}
+
+/// A pass which is run on the MIR immediately after it is generated.
+pub trait MirPass {
+    /// The name of the pass, which is added to every message it reports.
+    fn name(&self) -> &str;
+
+    fn run(&mut self, project: &mut MirProject, st: &StringTable, diags: &mut PassDiagnostics);
+}
+
+/// The set of custom passes which will be run during compilation.  See the
+/// [module documentation](self) for when each kind of pass is run.
+#[derive(Default)]
+pub struct PassRegistry {
+    ast: Vec<Box<dyn AstPass>>,
+    semantic: Vec<Box<dyn SemanticPass>>,
+    mir: Vec<Box<dyn MirPass>>,
+}
+
+impl PassRegistry {
+    pub fn new() -> PassRegistry {
+        PassRegistry::default()
+    }
+
+    /// Adds a pass which will be run after any AST passes that have already been
+    /// registered.
+    pub fn register_ast_pass<P: AstPass + 'static>(&mut self, pass: P) {
+        self.ast.push(Box::new(pass))
+    }
+
+    /// Adds a pass which will be run after any semantic passes that have already been
+    /// registered.
+    pub fn register_semantic_pass<P: SemanticPass + 'static>(&mut self, pass: P) {
+        self.semantic.push(Box::new(pass))
+    }
+
+    /// Adds a pass which will be run after any MIR passes that have already been
+    /// registered.
+    pub fn register_mir_pass<P: MirPass + 'static>(&mut self, pass: P) {
+        self.mir.push(Box::new(pass))
+    }
+
+    /// Runs every registered [`AstPass`], in order, on the given module.
+    pub fn run_ast_passes(
+        &mut self,
+        module: &mut Module<ParserContext>,
+        st: &StringTable,
+    ) -> PassDiagnostics {
+        let mut diags = PassDiagnostics::default();
+        for pass in &mut self.ast {
+            diags.pass = pass.name().into();
+            pass.run(module, st, &mut diags);
+            if diags.has_errors() {
+                break;
+            }
+        }
+        diags
+    }
+
+    /// Runs every registered [`SemanticPass`], in order, on the given module.
+    pub fn run_semantic_passes(
+        &mut self,
+        module: &mut Module<SemanticContext>,
+        st: &StringTable,
+    ) -> PassDiagnostics {
+        let mut diags = PassDiagnostics::default();
+        for pass in &mut self.semantic {
+            diags.pass = pass.name().into();
+            pass.run(module, st, &mut diags);
+            if diags.has_errors() {
+                break;
+            }
+        }
+        diags
+    }
+
+    /// Runs every registered [`MirPass`], in order, on the given project.
+    pub fn run_mir_passes(
+        &mut self,
+        project: &mut MirProject,
+        st: &StringTable,
+    ) -> PassDiagnostics {
+        let mut diags = PassDiagnostics::default();
+        for pass in &mut self.mir {
+            diags.pass = pass.name().into();
+            pass.run(project, st, &mut diags);
+            if diags.has_errors() {
+                break;
+            }
+        }
+        diags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{
+        compiler::{
+            ast::{Context, Item, Module, Node, MAIN_MODULE},
+            diagnostics::Logger,
+            lexer::tokens::Token,
+            mir::{transform, MirProject},
+            parser::{Parser, ParserContext},
+            semantics::{
+                semanticnode::SemanticContext, type_resolver::resolve_types, typed::TypedModule,
+            },
+            CompilerError, Lexer, SourceMap, Span,
+        },
+        StringTable,
+    };
+
+    use super::{AstPass, MirPass, PassDiagnostics, PassMessage, PassRegistry, SemanticPass};
+
+    fn parse(text: &str, table: &StringTable) -> Module<ParserContext> {
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let main = table.insert("main".into());
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let parser = Parser::new(&logger);
+        parser.parse(main, &tokens).unwrap().unwrap()
+    }
+
+    fn resolve(module: &Module<ParserContext>, table: &StringTable) -> TypedModule {
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+        resolve_types(module, main_mod, main_fn, &table, &Logger::new()).unwrap()
+    }
+
+    const PROGRAM: &str = "
+fn my_main() -> i64 {
+    return 0;
+}
+
+fn broken() -> i64 {
+    return true;
+}
+";
+
+    /// Records its name into a shared log every time it is run.
+    struct Record(&'static str, Rc<RefCell<Vec<&'static str>>>);
+
+    impl AstPass for Record {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn run(&mut self, _: &mut Module<ParserContext>, _: &StringTable, _: &mut PassDiagnostics) {
+            self.1.borrow_mut().push(self.0)
+        }
+    }
+
+    impl SemanticPass for Record {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn run(
+            &mut self,
+            _: &mut Module<SemanticContext>,
+            _: &StringTable,
+            _: &mut PassDiagnostics,
+        ) {
+            self.1.borrow_mut().push(self.0)
+        }
+    }
+
+    impl MirPass for Record {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn run(&mut self, _: &mut MirProject, _: &StringTable, _: &mut PassDiagnostics) {
+            self.1.borrow_mut().push(self.0)
+        }
+    }
+
+    /// Removes every function named `broken` from the module.
+    struct RemoveBroken;
+
+    impl AstPass for RemoveBroken {
+        fn name(&self) -> &str {
+            "remove-broken"
+        }
+
+        fn run(
+            &mut self,
+            module: &mut Module<ParserContext>,
+            st: &StringTable,
+            _: &mut PassDiagnostics,
+        ) {
+            let broken = st.insert("broken".into());
+            module
+                .get_functions_mut()
+                .retain(|f| f.get_name() != broken)
+        }
+    }
+
+    /// Reports a warning for every function and an error for the function named
+    /// `broken`.
+    struct ReportFunctions;
+
+    impl AstPass for ReportFunctions {
+        fn name(&self) -> &str {
+            "report"
+        }
+
+        fn run(
+            &mut self,
+            module: &mut Module<ParserContext>,
+            st: &StringTable,
+            diags: &mut PassDiagnostics,
+        ) {
+            let broken = st.insert("broken".into());
+            for f in module.get_functions() {
+                let span = match f {
+                    Item::Routine(r) => r.context().span(),
+                    _ => panic!("Expected a function"),
+                };
+                if f.get_name() == broken {
+                    diags.error(span, "broken")
+                } else {
+                    diags.warn(span, "function")
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn passes_run_in_registration_order() {
+        let table = StringTable::new();
+        let log = Rc::new(RefCell::new(vec![]));
+
+        let mut passes = PassRegistry::new();
+        passes.register_ast_pass(Record("ast1", log.clone()));
+        passes.register_semantic_pass(Record("sem1", log.clone()));
+        passes.register_mir_pass(Record("mir1", log.clone()));
+        passes.register_ast_pass(RemoveBroken);
+        passes.register_ast_pass(Record("ast2", log.clone()));
+        passes.register_semantic_pass(Record("sem2", log.clone()));
+        passes.register_mir_pass(Record("mir2", log.clone()));
+
+        let mut module = parse(PROGRAM, &table);
+        assert!(!passes.run_ast_passes(&mut module, &table).has_errors());
+
+        let mut module = resolve(&module, &table).into_inner();
+        assert!(!passes.run_semantic_passes(&mut module, &table).has_errors());
+        let module = TypedModule::check(module).unwrap();
+
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+        assert!(!passes.run_mir_passes(&mut project, &table).has_errors());
+
+        assert_eq!(
+            *log.borrow(),
+            vec!["ast1", "ast2", "sem1", "sem2", "mir1", "mir2"]
+        );
+    }
+
+    #[test]
+    fn ast_pass_changes_are_type_checked() {
+        let table = StringTable::new();
+        let module = parse(PROGRAM, &table);
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+        assert!(resolve_types(&module, main_mod, main_fn, &table, &Logger::new()).is_err());
+
+        let mut passes = PassRegistry::new();
+        passes.register_ast_pass(RemoveBroken);
+        let mut module = parse(PROGRAM, &table);
+        passes.run_ast_passes(&mut module, &table);
+        assert!(resolve_types(&module, main_mod, main_fn, &table, &Logger::new()).is_ok());
+    }
+
+    #[test]
+    fn error_stops_later_passes() {
+        let table = StringTable::new();
+        let log = Rc::new(RefCell::new(vec![]));
+
+        let mut passes = PassRegistry::new();
+        passes.register_ast_pass(Record("before", log.clone()));
+        passes.register_ast_pass(ReportFunctions);
+        passes.register_ast_pass(Record("after", log.clone()));
+
+        let mut module = parse(PROGRAM, &table);
+        let spans: Vec<Span> = module
+            .get_functions()
+            .iter()
+            .map(|f| match f {
+                Item::Routine(r) => r.context().span(),
+                _ => panic!("Expected a function"),
+            })
+            .collect();
+
+        let diags = passes.run_ast_passes(&mut module, &table);
+        let msg = |message: &str| PassMessage {
+            pass: "report".into(),
+            message: message.into(),
+        };
+        assert_eq!(
+            diags.warnings(),
+            &[CompilerError::new(spans[0], msg("function"))]
+        );
+        assert_eq!(
+            diags.errors(),
+            &[CompilerError::new(spans[1], msg("broken"))]
+        );
+        assert_eq!(*log.borrow(), vec!["before"]);
+    }
+}
@@ -0,0 +1,166 @@
+//! The phases of the compiler which take a parsed project to its MIR, with the custom
+//! passes of a [`PassRegistry`] run between them as described in the
+//! [pass ordering](super::passes#pass-ordering).  `bramblec` compiles every program
+//! through a [`Pipeline`], so a program which uses the compiler as a library and
+//! registers its own passes gets exactly the same compilation as the command line.
+
+use crate::{
+    compiler::{
+        ast::{Module, MAIN_MODULE},
+        diagnostics::Logger,
+        fuel::Fuel,
+        import::Import,
+        parser::ParserContext,
+        passes::{PassDiagnostics, PassMessage, PassRegistry},
+        semantics::{
+            reflect::eval_reflection, type_resolver::resolve_types_with_imports,
+            typed::TypedModule, SemanticError,
+        },
+        transform::{self, TransformError},
+        CompilerDisplayError, CompilerError, MirProject, SourceMap,
+    },
+    StringId, StringTable,
+};
+
+/// The reason that a [`Pipeline`] stopped.
+#[derive(Debug)]
+pub enum CompileError {
+    /// A custom pass reported errors
+    Pass(Vec<CompilerError<PassMessage>>),
+
+    /// The program is not well typed
+    Semantic(CompilerError<SemanticError>),
+
+    /// A reflection builtin could not be evaluated
+    Reflection(CompilerDisplayError),
+
+    /// The typed AST could not be lowered to MIR, which is a bug in the compiler
+    Transform(TransformError),
+}
+
+/// Runs the phases of the compiler, and the custom passes between them, on a parsed
+/// project.
+pub struct Pipeline<'a> {
+    passes: &'a mut PassRegistry,
+    sm: &'a SourceMap,
+    st: &'a StringTable,
+    logger: &'a Logger<'a>,
+    imports: &'a [Import],
+    fuel: Option<&'a Fuel>,
+
+    /// The warnings reported by custom passes which have not yet been taken
+    warnings: Vec<CompilerError<PassMessage>>,
+}
+
+impl<'a> Pipeline<'a> {
+    pub fn new(
+        passes: &'a mut PassRegistry,
+        sm: &'a SourceMap,
+        st: &'a StringTable,
+        logger: &'a Logger<'a>,
+    ) -> Pipeline<'a> {
+        Pipeline {
+            passes,
+            sm,
+            st,
+            logger,
+            imports: &[],
+            fuel: None,
+            warnings: vec![],
+        }
+    }
+
+    /// Makes the items of the given imported projects available to the program.
+    pub fn with_imports(mut self, imports: &'a [Import]) -> Pipeline<'a> {
+        self.imports = imports;
+        self
+    }
+
+    /// Limits the work the pipeline may do to the given fuel.
+    pub fn with_fuel(mut self, fuel: &'a Fuel) -> Pipeline<'a> {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    /// Runs the [`AstPass`](super::passes::AstPass)es on `root`, resolves its types,
+    /// runs the [`SemanticPass`](super::passes::SemanticPass)es, and evaluates its
+    /// reflection builtins.  `main_fn` is the name of the program's main function
+    /// within the main module.
+    pub fn analyze(
+        &mut self,
+        mut root: Module<ParserContext>,
+        main_fn: StringId,
+    ) -> Result<TypedModule, CompileError> {
+        let diags = self.passes.run_ast_passes(&mut root, self.st);
+        self.check_passes(diags)?;
+
+        let unlimited = Fuel::unlimited();
+        let main_mod = self.st.insert(MAIN_MODULE.into());
+        let mut module = resolve_types_with_imports(
+            &root,
+            main_mod,
+            main_fn,
+            self.imports,
+            self.st,
+            self.logger,
+            self.fuel.unwrap_or(&unlimited),
+        )
+        .map_err(CompileError::Semantic)?
+        .into_inner();
+
+        let diags = self.passes.run_semantic_passes(&mut module, self.st);
+        self.check_passes(diags)?;
+
+        eval_reflection(&mut module, self.sm, self.st).map_err(CompileError::Reflection)?;
+
+        // The passes may have changed the module, so it is checked again
+        TypedModule::check(module).map_err(CompileError::Semantic)
+    }
+
+    /// Lowers `module` to MIR, for a target whose pointers are `pointer_width` bits
+    /// wide, and runs the [`MirPass`](super::passes::MirPass)es on it.
+    pub fn lower(
+        &mut self,
+        module: &TypedModule,
+        pointer_width: u64,
+    ) -> Result<MirProject, CompileError> {
+        let mut project = MirProject::new();
+        project.set_pointer_width(pointer_width);
+        transform::transform(module, self.imports, &mut project)
+            .map_err(CompileError::Transform)?;
+
+        let diags = self.passes.run_mir_passes(&mut project, self.st);
+        self.check_passes(diags)?;
+
+        Ok(project)
+    }
+
+    /// Runs every phase on `root`, and returns its typed AST and MIR.
+    pub fn compile(
+        &mut self,
+        root: Module<ParserContext>,
+        main_fn: StringId,
+        pointer_width: u64,
+    ) -> Result<(TypedModule, MirProject), CompileError> {
+        let module = self.analyze(root, main_fn)?;
+        let project = self.lower(&module, pointer_width)?;
+        Ok((module, project))
+    }
+
+    /// Returns the warnings reported by custom passes since this was last called.
+    /// Warnings never stop the pipeline, so they are returned whether or not it
+    /// succeeded.
+    pub fn take_warnings(&mut self) -> Vec<CompilerError<PassMessage>> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn check_passes(&mut self, diags: PassDiagnostics) -> Result<(), CompileError> {
+        let (warnings, errors) = diags.into_parts();
+        self.warnings.extend(warnings);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(CompileError::Pass(errors))
+        }
+    }
+}
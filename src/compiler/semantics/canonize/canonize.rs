@@ -1,6 +1,6 @@
 use crate::compiler::{
     diagnostics::ViewErr,
-    diagnostics::{Event, Logger},
+    diagnostics::{Event, Logger, Writable, Writer},
     import::Import,
     semantics::error::SemanticError,
     source::SourceIr,
@@ -74,12 +74,14 @@ fn default_canonize_context_path<T: Canonizable + ?Sized>(
         // Set SemanticAnnotation::canonical_path to CanonicalPath
         // Addresses RoutineDefs and StructDefs (for LLVM IR)
         Some(name) => {
+            let path = vec![Element::Id(name)].into();
             let cpath = stack
-                .to_canonical(&vec![Element::Id(name)].into())
+                .to_canonical(&path)
                 .map_err(|e| CompilerError::new(node.span(), e))
                 .view_err(|e| record_item_path_event(node.span(), Err(e), logger))?;
 
-            record_item_path_event(node.span(), Ok(&cpath), logger);
+            let trace = CanonizedPath::new(&path, &cpath, CanonizeReason::ItemDefinition);
+            record_item_path_event(node.span(), Ok(trace), logger);
 
             node.get_context_mut().set_canonical_path(cpath);
         }
@@ -96,23 +98,20 @@ impl Canonizable for Expression<SemanticContext> {
     ) -> CanonizeResult<()> {
         let span = self.span();
         match self {
-            Expression::SizeOf(_, ref mut ty) => {
-                let canon_type = stack
-                    .canonize_type(ty.as_ref())
-                    .map_err(|e| CompilerError::new(span, e))
-                    .view_err(|e| record_type_ref_event(span, Err(e), logger))?;
-                canon_type
-                    .get_path()
-                    .map(|p| record_type_ref_event(span, Ok(p), logger));
-                *ty = Box::new(canon_type);
+            Expression::SizeOf(_, ref mut ty)
+            | Expression::FieldsOf(_, ref mut ty)
+            | Expression::NameOf(_, ref mut ty) => {
+                *ty = Box::new(canonize_type_ref(ty, span, stack, logger)?);
                 Ok(())
             }
             Expression::Path(_, ref mut path) => {
                 if !path.is_canonical() {
                     stack
-                        .to_canonical(path)
+                        .canonize_reference(path)
                         .and_then(|canonical_path| {
-                            record_type_ref_event(span, Ok(&canonical_path), logger);
+                            let reason = CanonizeReason::PathExpression;
+                            let trace = CanonizedPath::new(path, &canonical_path, reason);
+                            record_type_ref_event(span, Ok(trace), logger);
 
                             *path = canonical_path;
 
@@ -123,14 +122,21 @@ impl Canonizable for Expression<SemanticContext> {
                     Ok(())
                 }
             }
+            // The method which is called depends upon the type of the receiver, so its
+            // path is resolved by the type resolver
+            Expression::RoutineCall(_, RoutineCall::Method | RoutineCall::Iterator(_), ..) => {
+                Ok(())
+            }
             Expression::RoutineCall(_, _, ref mut path, _) => {
                 if !path.is_canonical() {
                     stack
-                        .to_canonical(path)
+                        .canonize_reference(path)
                         .and_then(|canonical_path| {
-                            record_type_ref_event(span, Ok(&canonical_path), logger);
+                            let reason = CanonizeReason::RoutineCall;
+                            let trace = CanonizedPath::new(path, &canonical_path, reason);
+                            record_type_ref_event(span, Ok(trace), logger);
 
-                            *path = canonical_path;
+                            **path = canonical_path;
                             Ok(())
                         })
                         .map_err(|e| CompilerError::new(self.span(), e))
@@ -141,11 +147,13 @@ impl Canonizable for Expression<SemanticContext> {
             Expression::StructExpression(_, ref mut path, _) => {
                 if !path.is_canonical() {
                     stack
-                        .to_canonical(path)
+                        .canonize_reference(path)
                         .and_then(|canonical_path| {
-                            record_type_ref_event(span, Ok(&canonical_path), logger);
+                            let reason = CanonizeReason::StructExpression;
+                            let trace = CanonizedPath::new(path, &canonical_path, reason);
+                            record_type_ref_event(span, Ok(trace), logger);
 
-                            *path = canonical_path;
+                            **path = canonical_path;
                             Ok(())
                         })
                         .map_err(|e| CompilerError::new(self.span(), e))
@@ -167,15 +175,7 @@ impl Canonizable for Bind<SemanticContext> {
         stack: &SymbolTableScopeStack,
         logger: &Logger,
     ) -> CanonizeResult<()> {
-        let canon_type = stack
-            .canonize_type(self.get_type())
-            .map_err(|e| CompilerError::new(self.span(), e))
-            .view_err(|e| record_type_ref_event(self.span(), Err(e), logger))?;
-
-        canon_type
-            .get_path()
-            .map(|p| record_type_ref_event(self.span(), Ok(p), logger));
-
+        let canon_type = canonize_type_ref(self.get_type(), self.span(), stack, logger)?;
         self.set_type(canon_type);
         Ok(())
     }
@@ -183,6 +183,14 @@ impl Canonizable for Bind<SemanticContext> {
 
 impl Canonizable for Mutate<SemanticContext> {}
 
+impl Canonizable for Discard<SemanticContext> {}
+
+impl Canonizable for Defer<SemanticContext> {}
+
+impl Canonizable for Break<SemanticContext> {}
+
+impl Canonizable for Continue<SemanticContext> {}
+
 impl Canonizable for Module<SemanticContext> {
     fn canonize_context_path(
         &mut self,
@@ -216,58 +224,47 @@ impl Canonizable for Module<SemanticContext> {
 impl Canonizable for StructDef<SemanticContext> {}
 
 impl Canonizable for RoutineDef<SemanticContext> {
-    fn canonize_type_refs(
+    fn canonize_context_path(
         &mut self,
         stack: &SymbolTableScopeStack,
         logger: &Logger,
     ) -> CanonizeResult<()> {
-        let ctype = stack
-            .canonize_type(&self.ret_ty)
-            .map_err(|e| CompilerError::new(self.span(), e))
-            .view_err(|e| record_type_ref_event(self.span(), Err(e), logger))?;
-
-        ctype
-            .get_path()
-            .map(|p| record_type_ref_event(self.span(), Ok(p), logger));
-
-        self.ret_ty = ctype;
-        Ok(())
+        // A method is an item within the structure that it is defined for
+        match stack.get_current_struct() {
+            Some(struct_name) => {
+                let path = vec![Element::Id(struct_name), Element::Id(self.name)].into();
+                let cpath = stack
+                    .to_canonical(&path)
+                    .map_err(|e| CompilerError::new(self.span(), e))
+                    .view_err(|e| record_item_path_event(self.span(), Err(e), logger))?;
+
+                let trace = CanonizedPath::new(&path, &cpath, CanonizeReason::MethodDefinition);
+                record_item_path_event(self.span(), Ok(trace), logger);
+
+                self.get_context_mut().set_canonical_path(cpath);
+                Ok(())
+            }
+            None => default_canonize_context_path(self, stack, logger),
+        }
     }
-}
 
-impl Canonizable for Extern<SemanticContext> {
-    fn canonize_context_path(
+    fn canonize_type_refs(
         &mut self,
-        _: &SymbolTableScopeStack,
+        stack: &SymbolTableScopeStack,
         logger: &Logger,
     ) -> CanonizeResult<()> {
-        let name = match self.name() {
-            Some(name) => name,
-            None => panic!("Externs must have a name"),
-        };
-        let cpath: Path = vec![Element::Id(name)].into();
-
-        record_item_path_event(self.span(), Ok(&cpath), logger);
-
-        self.get_context_mut().set_canonical_path(cpath);
+        self.ret_ty = canonize_type_ref(&self.ret_ty, self.span(), stack, logger)?;
         Ok(())
     }
+}
 
+impl Canonizable for Extern<SemanticContext> {
     fn canonize_type_refs(
         &mut self,
         stack: &SymbolTableScopeStack,
         logger: &Logger,
     ) -> CanonizeResult<()> {
-        let ctype = stack
-            .canonize_type(&self.ty)
-            .map_err(|e| CompilerError::new(self.span(), e))
-            .view_err(|e| record_type_ref_event(self.span(), Err(e), logger))?;
-
-        ctype
-            .get_path()
-            .map(|p| record_type_ref_event(self.span(), Ok(p), logger));
-
-        self.ty = ctype;
+        self.ty = canonize_type_ref(&self.ty, self.span(), stack, logger)?;
         Ok(())
     }
 }
@@ -278,16 +275,7 @@ impl Canonizable for Parameter<SemanticContext> {
         stack: &SymbolTableScopeStack,
         logger: &Logger,
     ) -> CanonizeResult<()> {
-        let ctype = stack
-            .canonize_type(&self.ty)
-            .map_err(|e| CompilerError::new(self.span(), e))
-            .view_err(|e| record_type_ref_event(self.span(), Err(e), logger))?;
-
-        ctype
-            .get_path()
-            .map(|p| record_type_ref_event(self.span(), Ok(p), logger));
-
-        self.ty = ctype;
+        self.ty = canonize_type_ref(&self.ty, self.span(), stack, logger)?;
         Ok(())
     }
 
@@ -304,9 +292,93 @@ impl Canonizable for YieldReturn<SemanticContext> {}
 
 impl Canonizable for Return<SemanticContext> {}
 
+/// Canonizes a type which is referred to by the node at `span` and records the path
+/// that it was rewritten to.
+fn canonize_type_ref(
+    ty: &Type,
+    span: Span,
+    stack: &SymbolTableScopeStack,
+    logger: &Logger,
+) -> CanonizeResult<Type> {
+    let ctype = stack
+        .canonize_type(ty)
+        .map_err(|e| CompilerError::new(span, e))
+        .view_err(|e| record_type_ref_event(span, Err(e), logger))?;
+
+    if let (Some(path), Some(cpath)) = (ty.get_path(), ctype.get_path()) {
+        let trace = CanonizedPath::new(path, cpath, CanonizeReason::TypeReference);
+        record_type_ref_event(span, Ok(trace), logger);
+    }
+
+    Ok(ctype)
+}
+
+/// Why canonization rewrote a path
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CanonizeReason {
+    /// The name of an item which is defined in a module
+    ItemDefinition,
+
+    /// The name of a method, which is an item within its structure
+    MethodDefinition,
+
+    /// The type of a variable, parameter, field, or return value
+    TypeReference,
+
+    /// The routine which is called
+    RoutineCall,
+
+    /// The structure which a struct expression constructs
+    StructExpression,
+
+    /// A path which is used as a value
+    PathExpression,
+}
+
+impl Writable for CanonizeReason {
+    fn write(&self, w: &dyn Writer) {
+        w.write_str(match self {
+            CanonizeReason::ItemDefinition => "item definition",
+            CanonizeReason::MethodDefinition => "method definition",
+            CanonizeReason::TypeReference => "type reference",
+            CanonizeReason::RoutineCall => "routine call",
+            CanonizeReason::StructExpression => "struct expression",
+            CanonizeReason::PathExpression => "path expression",
+        })
+    }
+}
+
+/// The trace of a single path which was canonized: the path as it was written, the
+/// canonical path that it was rewritten to, and why it was rewritten.
+struct CanonizedPath<'a> {
+    original: &'a Path,
+    canonical: &'a Path,
+    reason: CanonizeReason,
+}
+
+impl<'a> CanonizedPath<'a> {
+    fn new(original: &'a Path, canonical: &'a Path, reason: CanonizeReason) -> Self {
+        CanonizedPath {
+            original,
+            canonical,
+            reason,
+        }
+    }
+}
+
+impl<'a> Writable for CanonizedPath<'a> {
+    fn write(&self, w: &dyn Writer) {
+        // The canonical path is the value of the event and the rest are written as
+        // fields which follow it
+        w.write(&self.canonical);
+        w.write_field("original", &self.original);
+        w.write_field("reason", &self.reason);
+    }
+}
+
 fn record_item_path_event(
     span: Span,
-    path: Result<&Path, &CompilerError<SemanticError>>,
+    path: Result<CanonizedPath, &CompilerError<SemanticError>>,
     logger: &Logger,
 ) {
     logger.write(Event::<_, SemanticError>::new_without_parent(
@@ -318,7 +390,7 @@ fn record_item_path_event(
 
 fn record_type_ref_event(
     span: Span,
-    path: Result<&Path, &CompilerError<SemanticError>>,
+    path: Result<CanonizedPath, &CompilerError<SemanticError>>,
     logger: &Logger,
 ) {
     logger.write(Event::<_, SemanticError>::new_without_parent(
@@ -94,8 +94,13 @@ impl<'a> ForEachPreOrderMut {
     where
         F: FnMut(&SymbolTableScopeStack, &mut dyn Canonizable) -> CanonizeResult<()> + Copy,
     {
+        self.symbols.enter_scope(sd.context().sym().clone());
         let r = self.transform(sd, f);
         self.for_parameters(&mut sd.get_fields_mut(), f)?;
+        for m in sd.get_methods_mut().iter_mut() {
+            self.for_routinedef(m, f)?;
+        }
+        self.symbols.leave_scope();
         r
     }
 
@@ -167,6 +172,18 @@ impl<'a> ForEachPreOrderMut {
             Statement::Expression(e) => {
                 self.for_expression(e, f)?;
             }
+            Statement::Discard(d) => {
+                self.for_discard(d, f)?;
+            }
+            Statement::Defer(d) => {
+                self.for_defer(d, f)?;
+            }
+            Statement::Break(b) => {
+                self.transform(b.as_mut(), f)?;
+            }
+            Statement::Continue(c) => {
+                self.transform(c.as_mut(), f)?;
+            }
         };
         Ok(())
     }
@@ -189,6 +206,24 @@ impl<'a> ForEachPreOrderMut {
         r
     }
 
+    fn for_discard<F>(&mut self, discard: &mut Discard<SemanticContext>, f: F) -> CanonizeResult<()>
+    where
+        F: FnMut(&SymbolTableScopeStack, &mut dyn Canonizable) -> CanonizeResult<()> + Copy,
+    {
+        let r = self.transform(discard, f);
+        self.for_expression(discard.get_value_mut(), f)?;
+        r
+    }
+
+    fn for_defer<F>(&mut self, defer: &mut Defer<SemanticContext>, f: F) -> CanonizeResult<()>
+    where
+        F: FnMut(&SymbolTableScopeStack, &mut dyn Canonizable) -> CanonizeResult<()> + Copy,
+    {
+        let r = self.transform(defer, f);
+        self.for_expression(defer.get_value_mut(), f)?;
+        r
+    }
+
     fn for_yieldreturn<F>(
         &mut self,
         yr: &mut YieldReturn<SemanticContext>,
@@ -232,6 +267,7 @@ impl<'a> ForEachPreOrderMut {
         match exp {
             ExpressionBlock(..) => self.for_expression_block(exp, f),
             Null(..) => self.transform(exp, f),
+            OptionNone(..) => self.transform(exp, f),
             U8(..) => self.transform(exp, f),
             U16(..) => self.transform(exp, f),
             U32(..) => self.transform(exp, f),
@@ -255,6 +291,8 @@ impl<'a> ForEachPreOrderMut {
                 self.transform(exp, f)
             }
             SizeOf(..) => self.transform(exp, f),
+            FieldsOf(..) => self.transform(exp, f),
+            NameOf(..) => self.transform(exp, f),
             CustomType(..) => self.transform(exp, f),
             Identifier(..) => self.transform(exp, f),
             Path(..) => self.transform(exp, f),
@@ -268,6 +306,7 @@ impl<'a> ForEachPreOrderMut {
             RoutineCall(..) => self.for_routine_call(exp, f),
             StructExpression(..) => self.for_struct_expression(exp, f),
             TypeCast(_, exp, _) => self.for_expression(exp, f),
+            MacroCall(..) => self.transform(exp, f),
         }
     }
 
@@ -0,0 +1,287 @@
+//! Measures the size of every routine in a semantically valid AST.  Code generators
+//! which emit Bramble can produce routines far larger than a person would write, and
+//! LLVM's compile time and memory use grow quickly with the size of a function.  The
+//! measurements are compared against configurable limits and any routine which
+//! exceeds a limit is reported as a [`Lint`], so that a code generator can detect
+//! that it is producing something the backend will struggle with.
+
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::{
+    compiler::{
+        ast::{Arena, ArenaExpression, ArenaStatement, Context, Module, Node, Path, RoutineDef},
+        CompilerDisplay, CompilerDisplayError, CompilerError, SourceMap, Span,
+    },
+    StringTable,
+};
+
+use super::{lint::Lint, semanticnode::SemanticContext};
+
+/// A property of a routine which is measured and can be limited.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// The number of AST nodes in the routine
+    Nodes,
+
+    /// The deepest nesting of expressions in the routine
+    Depth,
+
+    /// The number of basic blocks that the routine is lowered into
+    BasicBlocks,
+
+    /// The number of parameters and local variables declared in the routine
+    Locals,
+}
+
+impl Metric {
+    const ALL: [Metric; 4] = [
+        Metric::Nodes,
+        Metric::Depth,
+        Metric::BasicBlocks,
+        Metric::Locals,
+    ];
+}
+
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Metric::Nodes => f.write_str("AST nodes"),
+            Metric::Depth => f.write_str("levels of nested expressions"),
+            Metric::BasicBlocks => f.write_str("basic blocks"),
+            Metric::Locals => f.write_str("local variables"),
+        }
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nodes" => Ok(Metric::Nodes),
+            "depth" => Ok(Metric::Depth),
+            "blocks" => Ok(Metric::BasicBlocks),
+            "locals" => Ok(Metric::Locals),
+            _ => Err(format!(
+                "Unknown complexity metric {}: expected nodes, depth, blocks, or locals",
+                s
+            )),
+        }
+    }
+}
+
+/// The largest value of each [`Metric`] which a routine can have before it is
+/// reported.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComplexityLimits {
+    pub nodes: usize,
+    pub depth: usize,
+    pub basic_blocks: usize,
+    pub locals: usize,
+}
+
+impl Default for ComplexityLimits {
+    fn default() -> Self {
+        ComplexityLimits {
+            nodes: 20_000,
+            depth: 256,
+            basic_blocks: 2_000,
+            locals: 1_000,
+        }
+    }
+}
+
+impl ComplexityLimits {
+    /// Sets the limit of the given metric
+    pub fn set(&mut self, metric: Metric, limit: usize) {
+        match metric {
+            Metric::Nodes => self.nodes = limit,
+            Metric::Depth => self.depth = limit,
+            Metric::BasicBlocks => self.basic_blocks = limit,
+            Metric::Locals => self.locals = limit,
+        }
+    }
+
+    fn get(&self, metric: Metric) -> usize {
+        match metric {
+            Metric::Nodes => self.nodes,
+            Metric::Depth => self.depth,
+            Metric::BasicBlocks => self.basic_blocks,
+            Metric::Locals => self.locals,
+        }
+    }
+}
+
+/// The measurements of a single routine.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Complexity {
+    pub nodes: usize,
+    pub depth: usize,
+    pub basic_blocks: usize,
+    pub locals: usize,
+}
+
+impl Complexity {
+    fn get(&self, metric: Metric) -> usize {
+        match metric {
+            Metric::Nodes => self.nodes,
+            Metric::Depth => self.depth,
+            Metric::BasicBlocks => self.basic_blocks,
+            Metric::Locals => self.locals,
+        }
+    }
+
+    /// Returns every metric whose value is greater than its limit
+    fn exceeded(&self, limits: &ComplexityLimits) -> Vec<Metric> {
+        Metric::ALL
+            .iter()
+            .copied()
+            .filter(|m| self.get(*m) > limits.get(*m))
+            .collect()
+    }
+}
+
+/// Measures every routine in the given module and its submodules.  Returns the
+/// canonical path and span of each routine with its measurements.
+pub fn measure(m: &Module<SemanticContext>) -> Vec<(Path, Span, Complexity)> {
+    let mut routines = vec![];
+    measure_module(m, &mut routines);
+    routines
+}
+
+/// Reports every routine in the given module which exceeds any of the given limits.
+pub fn check_complexity(
+    m: &Module<SemanticContext>,
+    limits: &ComplexityLimits,
+) -> Vec<CompilerError<Lint>> {
+    measure(m)
+        .into_iter()
+        .flat_map(|(path, span, complexity)| {
+            complexity.exceeded(limits).into_iter().map(move |metric| {
+                CompilerError::new(
+                    span,
+                    Lint::TooComplex(
+                        path.clone(),
+                        metric,
+                        complexity.get(metric),
+                        limits.get(metric),
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+fn measure_module(m: &Module<SemanticContext>, routines: &mut Vec<(Path, Span, Complexity)>) {
+    for sm in m.get_modules() {
+        measure_module(sm, routines)
+    }
+
+    let items = m.get_functions().iter().chain(m.get_coroutines());
+    for r in items.filter_map(|i| i.to_routine()).chain(m.get_methods()) {
+        routines.push((
+            r.context().canonical_path().clone(),
+            r.context().span(),
+            measure_routine(r),
+        ))
+    }
+}
+
+/// Measures a routine by copying its body into an [`Arena`], where each metric can be
+/// found with a single loop over its nodes rather than by recursing through the tree.
+fn measure_routine(r: &RoutineDef<SemanticContext>) -> Complexity {
+    let mut arena = Arena::new();
+    for stm in r.get_body() {
+        arena.insert_statement(stm);
+    }
+
+    // Children are added to the arena before their parents, so the depth of every child
+    // is known by the time its parent is reached
+    let mut depths = vec![0; arena.len()];
+    for (id, _) in arena.iter() {
+        depths[id.index()] = 1 + arena
+            .children(id)
+            .iter()
+            .map(|c| depths[c.index()])
+            .max()
+            .unwrap_or(0);
+    }
+
+    let binds = arena
+        .iter_statements()
+        .filter(|(_, stm)| matches!(stm, ArenaStatement::Bind { .. }))
+        .count();
+
+    Complexity {
+        nodes: r.iter_preorder().count(),
+        depth: depths.into_iter().max().unwrap_or(0),
+        // Every routine starts with an entry block
+        basic_blocks: 1 + arena.iter().map(|(_, e)| basic_blocks(e)).sum::<usize>(),
+        locals: r.get_params().len() + binds,
+    }
+}
+
+/// The number of basic blocks which the given expression adds to its routine, not
+/// counting those of its children.  Basic blocks are counted the same way that the MIR
+/// transformer creates them: one block for each call to return into, two for an `if`
+/// (three with an `else`), and three for a `while`.
+fn basic_blocks(exp: &ArenaExpression<SemanticContext>) -> usize {
+    match exp {
+        ArenaExpression::RoutineCall(..) => 1,
+        ArenaExpression::If { else_arm, .. } => 2 + else_arm.iter().count(),
+        ArenaExpression::While { .. } => 3,
+        _ => 0,
+    }
+}
+
+/// The measurements of every routine in a project, which is written as JSON by
+/// `--emit complexity` so that tools which generate Bramble can check the size of
+/// what they generated.
+#[derive(Serialize, Debug)]
+pub struct ComplexityReport {
+    routines: Vec<ComplexityEntry>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct ComplexityEntry {
+    path: String,
+    low: u32,
+    high: u32,
+    #[serde(flatten)]
+    complexity: Complexity,
+
+    /// The metrics which exceed their limits
+    exceeds: Vec<Metric>,
+}
+
+impl ComplexityReport {
+    pub fn new(
+        m: &Module<SemanticContext>,
+        limits: &ComplexityLimits,
+        sm: &SourceMap,
+        st: &StringTable,
+    ) -> Result<Self, CompilerDisplayError> {
+        let routines = measure(m)
+            .into_iter()
+            .map(|(path, span, complexity)| {
+                Ok(ComplexityEntry {
+                    path: path.fmt(sm, st)?,
+                    low: span.low().as_u32(),
+                    high: span.high().as_u32(),
+                    complexity,
+                    exceeds: complexity.exceeded(limits),
+                })
+            })
+            .collect::<Result<_, CompilerDisplayError>>()?;
+
+        Ok(ComplexityReport { routines })
+    }
+
+    /// Writes the report to the given file as JSON
+    pub fn write(&self, file: &mut std::fs::File) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(file, self)
+    }
+}
@@ -0,0 +1,564 @@
+//! Evaluates expressions when the program is compiled, for the places where the
+//! language requires a compile time constant (e.g. the condition of a static assertion).
+//!
+//! A constant is built from boolean and integer literals, `cfg(NAME)`, the operators
+//! which apply to them, and calls to `const fn`s.  The body of a `const fn` is
+//! interpreted: it may bind and mutate local variables, and use `if`, `while`, `break`,
+//! `continue`, and `return`.  Operands follow the same rules as when the program is
+//! run: both sides of a binary operator must have the same type, and arithmetic which
+//! overflows the type of its operands is an error, unless it is `wrapping_*` or
+//! `saturating_*` arithmetic.
+//!
+//! Evaluation happens before types are resolved, so the evaluator checks the types of
+//! the values that it computes itself.  A `const fn` which loops forever, or recurses
+//! too deeply, is stopped after a fixed number of steps or calls.
+
+use std::cell::Cell;
+
+use crate::{
+    compiler::{
+        ast::{
+            BinaryOperator, Context, Element, Expression, Item, Module, Node, Path, RoutineCall,
+            RoutineDef, RoutineDefType, Statement, Type, UnaryOperator,
+        },
+        CompilerError, Span,
+    },
+    StringId,
+};
+
+use super::{dead_branch::Defines, error::SemanticError};
+
+pub(super) type EvalResult<T> = Result<T, CompilerError<SemanticError>>;
+
+/// The number of expressions and statements which may be evaluated for one constant
+const MAX_STEPS: u64 = 1_000_000;
+
+/// The number of `const fn` calls which may be active at once
+const MAX_CALL_DEPTH: usize = 64;
+
+/// A value which is known when the program is compiled
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Const {
+    Bool(bool),
+    /// An integer and its type
+    Int(i128, Type),
+}
+
+impl Const {
+    pub(super) fn ty(&self) -> Type {
+        match self {
+            Const::Bool(_) => Type::Bool,
+            Const::Int(_, ty) => ty.clone(),
+        }
+    }
+}
+
+/// Why evaluation stopped before it reached the end of an expression
+enum Exit {
+    Error(CompilerError<SemanticError>),
+    Return(Option<Const>),
+    Break(Option<StringId>),
+    Continue(Option<StringId>),
+}
+
+impl From<CompilerError<SemanticError>> for Exit {
+    fn from(e: CompilerError<SemanticError>) -> Self {
+        Exit::Error(e)
+    }
+}
+
+/// The result of evaluating an expression: its value, or `None` if it has the unit type
+type Flow = Result<Option<Const>, Exit>;
+
+/// Evaluates constant expressions in a module tree, calling the `const fn`s which are
+/// defined in it.
+pub(super) struct ConstEval<'a, M> {
+    root: &'a Module<M>,
+    defines: &'a Defines<'a>,
+
+    /// The steps which may still be taken while evaluating the current constant
+    steps: Cell<u64>,
+}
+
+/// The state of one call to a `const fn`
+struct Frame<'a, M> {
+    /// The modules from the root to the module which contains the routine, which paths
+    /// in the routine are relative to
+    scope: Vec<&'a Module<M>>,
+
+    /// The local variables which are in scope, innermost last
+    locals: Vec<(StringId, Const)>,
+}
+
+impl<'a, M: Context> ConstEval<'a, M> {
+    pub(super) fn new(root: &'a Module<M>, defines: &'a Defines<'a>) -> ConstEval<'a, M> {
+        ConstEval {
+            root,
+            defines,
+            steps: Cell::new(MAX_STEPS),
+        }
+    }
+
+    /// Evaluates `exp`, which is written in the last module of `scope`.  `scope` is the
+    /// list of modules from the root to that module.
+    pub(super) fn eval(
+        &self,
+        scope: &[&'a Module<M>],
+        exp: &'a Expression<M>,
+    ) -> EvalResult<Const> {
+        self.steps.set(MAX_STEPS);
+        let mut frame = Frame {
+            scope: scope.to_vec(),
+            locals: vec![],
+        };
+        match self.expression(&mut frame, exp, 0) {
+            Ok(Some(v)) => Ok(v),
+            Ok(None) => Err(not_constant(exp.context().span())),
+            Err(Exit::Error(e)) => Err(e),
+            Err(Exit::Return(_)) => Err(CompilerError::new(
+                exp.context().span(),
+                SemanticError::ReturnInvalidLocation,
+            )),
+            Err(Exit::Break(_)) => Err(CompilerError::new(
+                exp.context().span(),
+                SemanticError::BreakOutsideLoop,
+            )),
+            Err(Exit::Continue(_)) => Err(CompilerError::new(
+                exp.context().span(),
+                SemanticError::ContinueOutsideLoop,
+            )),
+        }
+    }
+
+    fn step(&self, span: Span) -> EvalResult<()> {
+        match self.steps.get().checked_sub(1) {
+            Some(left) => {
+                self.steps.set(left);
+                Ok(())
+            }
+            None => Err(CompilerError::new(span, SemanticError::ConstEvalLimit)),
+        }
+    }
+
+    fn expression(&self, frame: &mut Frame<'a, M>, exp: &'a Expression<M>, depth: usize) -> Flow {
+        let span = exp.context().span();
+        self.step(span)?;
+        let v = match exp {
+            Expression::Boolean(_, b) => Const::Bool(*b),
+            Expression::U8(_, v) => Const::Int(*v as i128, Type::U8),
+            Expression::U16(_, v) => Const::Int(*v as i128, Type::U16),
+            Expression::U32(_, v) => Const::Int(*v as i128, Type::U32),
+            Expression::U64(_, v) => Const::Int(*v as i128, Type::U64),
+            Expression::I8(_, v) => Const::Int(*v as i128, Type::I8),
+            Expression::I16(_, v) => Const::Int(*v as i128, Type::I16),
+            Expression::I32(_, v) => Const::Int(*v as i128, Type::I32),
+            Expression::I64(_, v) => Const::Int(*v as i128, Type::I64),
+            // Only the local variables of a const fn have known values
+            Expression::Identifier(_, id) => match frame.locals.iter().rev().find(|(n, _)| n == id)
+            {
+                Some((_, v)) => v.clone(),
+                None => return Err(not_constant(span).into()),
+            },
+            Expression::UnaryOp(_, op, v) => {
+                let v = self.value(frame, v, depth)?;
+                match (op, v) {
+                    (UnaryOperator::Not, Const::Bool(b)) => Const::Bool(!b),
+                    (UnaryOperator::Not, v) => {
+                        return Err(CompilerError::new(
+                            span,
+                            SemanticError::ExpectedBool(*op, v.ty()),
+                        )
+                        .into())
+                    }
+                    (UnaryOperator::Negate, Const::Int(i, ty)) if ty.is_signed_int() => {
+                        checked(-i, ty, span)?
+                    }
+                    (UnaryOperator::Negate, v) => {
+                        return Err(CompilerError::new(
+                            span,
+                            SemanticError::ExpectedSignedInteger(*op, v.ty()),
+                        )
+                        .into())
+                    }
+                    _ => return Err(not_constant(span).into()),
+                }
+            }
+            Expression::BinaryOp(_, op, l, r) => {
+                let l = self.value(frame, l, depth)?;
+
+                // Like when the program is run, the right side is only evaluated if it
+                // can change the result, so that a recursive const fn can stop
+                match (op, &l) {
+                    (BinaryOperator::BAnd, Const::Bool(false)) => return Ok(Some(l)),
+                    (BinaryOperator::BOr, Const::Bool(true)) => return Ok(Some(l)),
+                    _ => (),
+                }
+
+                let r = self.value(frame, r, depth)?;
+                binary_op(*op, l, r, span)?
+            }
+            Expression::ExpressionBlock(_, stmts, final_exp) => {
+                let scope_start = frame.locals.len();
+                let result = self.block(frame, stmts, final_exp.as_deref(), depth);
+                frame.locals.truncate(scope_start);
+                return result;
+            }
+            Expression::If {
+                cond,
+                if_arm,
+                else_arm,
+                ..
+            } => {
+                return if self.condition(frame, cond, depth)? {
+                    self.expression(frame, if_arm, depth)
+                } else {
+                    match else_arm {
+                        Some(else_arm) => self.expression(frame, else_arm, depth),
+                        None => Ok(None),
+                    }
+                }
+            }
+            Expression::While {
+                label, cond, body, ..
+            } => {
+                while self.condition(frame, cond, depth)? {
+                    match self.expression(frame, body, depth) {
+                        Ok(_) => (),
+                        Err(Exit::Break(l)) if l.is_none() || l == *label => break,
+                        Err(Exit::Continue(l)) if l.is_none() || l == *label => continue,
+                        Err(e) => return Err(e),
+                    }
+                }
+                return Ok(None);
+            }
+            Expression::RoutineCall(_, RoutineCall::Function, path, args) => {
+                match self.defines.check(exp) {
+                    Some(defined) => Const::Bool(defined),
+                    None => return self.call(frame, path, args, span, depth),
+                }
+            }
+            _ => return Err(not_constant(span).into()),
+        };
+        Ok(Some(v))
+    }
+
+    /// Evaluates an expression which must have a value
+    fn value(
+        &self,
+        frame: &mut Frame<'a, M>,
+        exp: &'a Expression<M>,
+        depth: usize,
+    ) -> Result<Const, Exit> {
+        self.expression(frame, exp, depth)?
+            .ok_or_else(|| not_constant(exp.context().span()).into())
+    }
+
+    /// Evaluates the condition of an `if` or `while`, which must be a `bool`
+    fn condition(
+        &self,
+        frame: &mut Frame<'a, M>,
+        cond: &'a Expression<M>,
+        depth: usize,
+    ) -> Result<bool, Exit> {
+        match self.value(frame, cond, depth)? {
+            Const::Bool(b) => Ok(b),
+            v => Err(CompilerError::new(
+                cond.context().span(),
+                SemanticError::CondExpectedBool(v.ty()),
+            )
+            .into()),
+        }
+    }
+
+    fn block(
+        &self,
+        frame: &mut Frame<'a, M>,
+        stmts: &'a [Statement<M>],
+        final_exp: Option<&'a Expression<M>>,
+        depth: usize,
+    ) -> Flow {
+        for stm in stmts {
+            self.statement(frame, stm, depth)?;
+        }
+        match final_exp {
+            Some(exp) => self.expression(frame, exp, depth),
+            None => Ok(None),
+        }
+    }
+
+    fn statement(
+        &self,
+        frame: &mut Frame<'a, M>,
+        stm: &'a Statement<M>,
+        depth: usize,
+    ) -> Result<(), Exit> {
+        let span = stm.context().span();
+        self.step(span)?;
+        match stm {
+            Statement::Bind(b) => {
+                let v = self.value(frame, b.get_rhs(), depth)?;
+                if v.ty() != *b.get_type() {
+                    return Err(CompilerError::new(
+                        span,
+                        SemanticError::BindExpected(b.get_type().clone(), v.ty()),
+                    )
+                    .into());
+                }
+                frame.locals.push((b.get_id(), v));
+            }
+            Statement::Mutate(m) => {
+                let id = match m.get_lhs() {
+                    Expression::Identifier(_, id) => *id,
+                    _ => return Err(not_constant(span).into()),
+                };
+                let v = self.value(frame, m.get_rhs(), depth)?;
+                match frame.locals.iter_mut().rev().find(|(n, _)| *n == id) {
+                    Some((_, local)) if local.ty() == v.ty() => *local = v,
+                    Some((_, local)) => {
+                        return Err(CompilerError::new(
+                            span,
+                            SemanticError::BindExpected(local.ty(), v.ty()),
+                        )
+                        .into())
+                    }
+                    None => {
+                        return Err(CompilerError::new(span, SemanticError::NotDefined(id)).into())
+                    }
+                }
+            }
+            Statement::Expression(exp) => {
+                self.expression(frame, exp, depth)?;
+            }
+            Statement::Return(r) => {
+                let v = match r.get_value() {
+                    Some(v) => Some(self.value(frame, v, depth)?),
+                    None => None,
+                };
+                return Err(Exit::Return(v));
+            }
+            Statement::Break(b) => return Err(Exit::Break(b.get_label())),
+            Statement::Continue(c) => return Err(Exit::Continue(c.get_label())),
+            Statement::YieldReturn(_) | Statement::Discard(_) | Statement::Defer(_) => {
+                return Err(not_constant(span).into())
+            }
+        }
+        Ok(())
+    }
+
+    /// Calls the `const fn` at `path` with the values of `args`
+    fn call(
+        &self,
+        frame: &mut Frame<'a, M>,
+        path: &Path,
+        args: &'a [Expression<M>],
+        span: Span,
+        depth: usize,
+    ) -> Flow {
+        let (scope, routine) = match self.find_routine(&frame.scope, path) {
+            Some((scope, routine)) if routine.is_const() => (scope, routine),
+            Some(_) => {
+                return Err(CompilerError::new(
+                    span,
+                    SemanticError::ConstFnCallsNonConst(path.clone()),
+                )
+                .into())
+            }
+            None => return Err(not_constant(span).into()),
+        };
+        if depth >= MAX_CALL_DEPTH {
+            return Err(CompilerError::new(span, SemanticError::ConstEvalLimit).into());
+        }
+
+        let params = routine.get_params();
+        if params.len() != args.len() {
+            return Err(CompilerError::new(
+                span,
+                SemanticError::RoutineCallWrongNumParams(path.clone(), params.len(), args.len()),
+            )
+            .into());
+        }
+
+        let mut callee = Frame {
+            scope,
+            locals: vec![],
+        };
+        for (param, arg) in params.iter().zip(args) {
+            let v = self.value(frame, arg, depth)?;
+            if v.ty() != param.ty {
+                return Err(CompilerError::new(
+                    arg.context().span(),
+                    SemanticError::BindExpected(param.ty.clone(), v.ty()),
+                )
+                .into());
+            }
+            callee.locals.push((param.name, v));
+        }
+
+        let result = match self.block(&mut callee, routine.get_body(), None, depth + 1) {
+            Ok(_) => None,
+            Err(Exit::Return(v)) => v,
+            Err(Exit::Error(e)) => return Err(Exit::Error(e)),
+            Err(Exit::Break(_)) => {
+                return Err(CompilerError::new(span, SemanticError::BreakOutsideLoop).into())
+            }
+            Err(Exit::Continue(_)) => {
+                return Err(CompilerError::new(span, SemanticError::ContinueOutsideLoop).into())
+            }
+        };
+
+        let ret_ty = routine.get_return_type();
+        match &result {
+            Some(v) if v.ty() != *ret_ty => Err(CompilerError::new(
+                span,
+                SemanticError::ReturnExpected(ret_ty.clone(), v.ty()),
+            )
+            .into()),
+            None if *ret_ty != Type::Unit => Err(CompilerError::new(
+                span,
+                SemanticError::ReturnExpected(ret_ty.clone(), Type::Unit),
+            )
+            .into()),
+            _ => Ok(result),
+        }
+    }
+
+    /// Follows `path` from the last module of `scope` to a function.  Returns the
+    /// modules from the root to the module which contains the function, and the
+    /// function.
+    fn find_routine(
+        &self,
+        scope: &[&'a Module<M>],
+        path: &Path,
+    ) -> Option<(Vec<&'a Module<M>>, &'a RoutineDef<M>)> {
+        let (name, steps) = match path.iter().as_slice().split_last()? {
+            (Element::Id(name), steps) => (*name, steps),
+            _ => return None,
+        };
+
+        let mut scope = scope.to_vec();
+        for step in steps {
+            match step {
+                Element::CanonicalRoot | Element::FileRoot => scope = vec![self.root],
+                Element::Selph => (),
+                Element::Super => {
+                    if scope.len() <= 1 {
+                        return None;
+                    }
+                    scope.pop();
+                }
+                Element::Id(id) => {
+                    let child = scope.last()?.get_module(*id)?;
+                    scope.push(child)
+                }
+            }
+        }
+
+        match scope.last()?.get_item(name)? {
+            Item::Routine(r) if *r.get_def() == RoutineDefType::Function => Some((scope, r)),
+            _ => None,
+        }
+    }
+}
+
+fn not_constant(span: Span) -> CompilerError<SemanticError> {
+    CompilerError::new(span, SemanticError::StaticAssertNotConstant)
+}
+
+fn binary_op(op: BinaryOperator, l: Const, r: Const, span: Span) -> EvalResult<Const> {
+    use BinaryOperator::*;
+
+    match (&l, &r) {
+        (Const::Bool(a), Const::Bool(b)) => match op {
+            BAnd => return Ok(Const::Bool(*a && *b)),
+            BOr => return Ok(Const::Bool(*a || *b)),
+            Eq => return Ok(Const::Bool(a == b)),
+            NEq => return Ok(Const::Bool(a != b)),
+            _ => (),
+        },
+        (Const::Int(a, lty), Const::Int(b, rty)) if lty == rty => match op {
+            Eq => return Ok(Const::Bool(a == b)),
+            NEq => return Ok(Const::Bool(a != b)),
+            Ls => return Ok(Const::Bool(a < b)),
+            LsEq => return Ok(Const::Bool(a <= b)),
+            Gr => return Ok(Const::Bool(a > b)),
+            GrEq => return Ok(Const::Bool(a >= b)),
+            Add | Sub | Mul | Div | WrappingAdd | WrappingSub | WrappingMul | SaturatingAdd
+            | SaturatingSub => return arithmetic(op, *a, *b, lty.clone(), span),
+            _ => (),
+        },
+        _ => (),
+    }
+
+    match op {
+        Range | RawPointerOffset => Err(CompilerError::new(
+            span,
+            SemanticError::StaticAssertNotConstant,
+        )),
+        BAnd | BOr => Err(CompilerError::new(
+            span,
+            SemanticError::OpExpected(op, Type::Bool, l.ty(), r.ty()),
+        )),
+        _ => Err(CompilerError::new(
+            span,
+            SemanticError::OpExpected(op, l.ty(), l.ty(), r.ty()),
+        )),
+    }
+}
+
+/// Applies an arithmetic operator to two integers of type `ty`.
+fn arithmetic(op: BinaryOperator, a: i128, b: i128, ty: Type, span: Span) -> EvalResult<Const> {
+    use BinaryOperator::*;
+
+    let overflow = || CompilerError::new(span, SemanticError::StaticAssertOverflow);
+    let v = match op {
+        Add | SaturatingAdd => a.checked_add(b),
+        Sub | SaturatingSub => a.checked_sub(b),
+        Mul => a.checked_mul(b),
+        Div => a.checked_div(b),
+        // Wrapping in 128 bits leaves the low bits, which are all that are kept, intact
+        WrappingAdd => Some(a.wrapping_add(b)),
+        WrappingSub => Some(a.wrapping_sub(b)),
+        WrappingMul => Some(a.wrapping_mul(b)),
+        _ => None,
+    }
+    .ok_or_else(overflow)?;
+
+    let (min, max) = range(&ty);
+    match op {
+        WrappingAdd | WrappingSub | WrappingMul => {
+            let bits = ty.bit_width() as u32;
+            let low = v & ((1i128 << bits) - 1);
+            let v = if low > max {
+                low - (1i128 << bits)
+            } else {
+                low
+            };
+            Ok(Const::Int(v, ty))
+        }
+        SaturatingAdd | SaturatingSub => Ok(Const::Int(v.clamp(min, max), ty)),
+        _ => checked(v, ty, span),
+    }
+}
+
+/// Returns `v` as a value of type `ty`, or an error if it does not fit in `ty`.
+fn checked(v: i128, ty: Type, span: Span) -> EvalResult<Const> {
+    let (min, max) = range(&ty);
+    if (min..=max).contains(&v) {
+        Ok(Const::Int(v, ty))
+    } else {
+        Err(CompilerError::new(
+            span,
+            SemanticError::StaticAssertOverflow,
+        ))
+    }
+}
+
+/// Returns the smallest and largest values of the integer type `ty`.
+fn range(ty: &Type) -> (i128, i128) {
+    let bits = ty.bit_width() as u32;
+    if ty.is_signed_int() {
+        (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+    } else {
+        (0, (1i128 << bits) - 1)
+    }
+}
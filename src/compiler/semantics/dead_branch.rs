@@ -0,0 +1,364 @@
+//! Removes the arms of `if` expressions which can never be taken because their
+//! condition is a compile time constant.
+//!
+//! A condition is a compile time constant if it uses `cfg(NAME)`, which is `true` if
+//! `NAME` was defined when the compiler was run (e.g. with `--define NAME`) and
+//! `false` otherwise, and is otherwise built only from boolean literals and the
+//! logical operators.  This runs before paths are canonized and before type
+//! resolution, so the code in a dead arm is never checked.  Like `if cfg!(...)` in
+//! Rust, this allows a single source file to refer to items (e.g. platform specific
+//! externs) which only exist when the arm that uses them is live.
+//!
+//! Conditions which only use boolean literals, such as `if (true) {...}`, are not
+//! pruned and both arms are still checked.
+//!
+//! This pass also replaces `env("NAME")` with a string literal whose value is the value
+//! given to `NAME` with `--define NAME=VALUE` or, if it was not defined, the value of
+//! the environment variable `NAME` when the compiler was run.  This lets a program
+//! stamp itself with a version or build information without generating code.  Only
+//! the `env` calls in live arms are expanded, so a dead arm can use a variable which
+//! is not set.
+//!
+//! Because it evaluates `cfg(NAME)`, this pass also checks the module level static
+//! assertions (see [`static_assert`](super::static_assert)).
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    compiler::{
+        ast::{
+            BinaryOperator, Context, Element, Expression, Item, Module, Node, RoutineCall,
+            Statement, UnaryOperator,
+        },
+        CompilerError,
+    },
+    StringId, StringTable,
+};
+
+use super::{error::SemanticError, static_assert::check_static_asserts};
+
+type PruneResult = Result<(), CompilerError<SemanticError>>;
+
+/// The name of the builtin which checks whether a name has been defined.
+const CFG: &str = "cfg";
+
+/// The name of the builtin which reads the value of a definition or environment
+/// variable.
+const ENV: &str = "env";
+
+/// The set of names which have been defined for this compilation and which can be
+/// checked with `cfg(NAME)`, and the values given to any of them with `NAME=VALUE`.
+pub struct Defines<'a> {
+    table: &'a StringTable,
+    cfg: StringId,
+    env: StringId,
+    names: HashSet<StringId>,
+    values: HashMap<String, String>,
+}
+
+impl<'a> Defines<'a> {
+    pub fn new(table: &'a StringTable, names: &[&str]) -> Defines<'a> {
+        let mut defines = Defines {
+            table,
+            cfg: table.insert(CFG.into()),
+            env: table.insert(ENV.into()),
+            names: HashSet::new(),
+            values: HashMap::new(),
+        };
+
+        for define in names.iter().copied() {
+            let name = match define.split_once('=') {
+                Some((name, value)) => {
+                    defines.values.insert(name.into(), value.into());
+                    name
+                }
+                None => define,
+            };
+            defines.names.insert(table.insert(name.into()));
+        }
+        defines
+    }
+
+    /// If `exp` is `cfg(NAME)`, then return whether `NAME` is defined.
+    pub(super) fn check(&self, exp: &Expression<impl Context>) -> Option<bool> {
+        match exp {
+            Expression::RoutineCall(_, RoutineCall::Function, path, args)
+                if path.len() == 1 && path.first() == Some(&Element::Id(self.cfg)) =>
+            {
+                match args.as_slice() {
+                    [Expression::Identifier(_, name)] => Some(self.names.contains(name)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// If `exp` is `env("NAME")`, then return the string literal which it is replaced
+    /// with.
+    fn env<M: Context + Clone>(
+        &self,
+        exp: &Expression<M>,
+    ) -> Result<Option<Expression<M>>, CompilerError<SemanticError>> {
+        match exp {
+            Expression::RoutineCall(ctx, RoutineCall::Function, path, args)
+                if path.len() == 1 && path.first() == Some(&Element::Id(self.env)) =>
+            {
+                let name = match args.as_slice() {
+                    [Expression::StringLiteral(_, name)] => *name,
+                    _ => {
+                        return Err(CompilerError::new(
+                            ctx.span(),
+                            SemanticError::EnvExpectedName,
+                        ))
+                    }
+                };
+
+                let key = self.table.get(name).unwrap_or_default();
+                let value = match self.values.get(&key) {
+                    Some(value) => value.clone(),
+                    None => std::env::var(&key).map_err(|_| {
+                        CompilerError::new(ctx.span(), SemanticError::EnvNotDefined(name))
+                    })?,
+                };
+
+                let literal = self.table.insert(escape(&value));
+                Ok(Some(Expression::StringLiteral(ctx.clone(), literal)))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Writes the characters of `s` which cannot appear in a string literal as escape
+/// sequences, so that the literal has the same value as `s`.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\0' => escaped.push_str("\\0"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Replaces every `if` expression in the given module, and its submodules, whose
+/// condition is a compile time constant with the arm which will be taken.  If the
+/// condition is `false` and there is no `else` arm, then the `if` is replaced with an
+/// empty block.  Every other use of `cfg(NAME)` is replaced with a boolean literal and
+/// every use of `env("NAME")` is replaced with a string literal.
+///
+/// The static assertions of each module are then evaluated, and an error is returned
+/// for the first one which does not hold.  `m` is the root of the module tree, which
+/// canonical paths in the assertions start from.
+pub fn prune_dead_branches<M: Context + Clone>(
+    m: &mut Module<M>,
+    defines: &Defines,
+) -> PruneResult {
+    prune_module(m, defines)?;
+    check_static_asserts(m, defines)
+}
+
+fn prune_module<M: Context + Clone>(m: &mut Module<M>, defines: &Defines) -> PruneResult {
+    for child in m.get_modules_mut() {
+        prune_module(child, defines)?
+    }
+
+    prune_items(m.get_functions_mut(), defines)?;
+    prune_items(m.get_coroutines_mut(), defines)?;
+    for routine in m.get_methods_mut() {
+        for stm in routine.get_body_mut() {
+            prune_statement(stm, defines)?
+        }
+    }
+    Ok(())
+}
+
+fn prune_items<M: Context + Clone>(items: &mut [Item<M>], defines: &Defines) -> PruneResult {
+    for item in items {
+        if let Item::Routine(routine) = item {
+            for stm in routine.get_body_mut() {
+                prune_statement(stm, defines)?
+            }
+        }
+    }
+    Ok(())
+}
+
+fn prune_statement<M: Context + Clone>(stm: &mut Statement<M>, defines: &Defines) -> PruneResult {
+    match stm {
+        Statement::Bind(bind) => prune_expression(bind.get_rhs_mut(), defines),
+        Statement::Mutate(mutate) => prune_expression(mutate.get_rhs_mut(), defines),
+        Statement::Discard(discard) => prune_expression(discard.get_value_mut(), defines),
+        Statement::Defer(defer) => prune_expression(defer.get_value_mut(), defines),
+        Statement::Break(_) | Statement::Continue(_) => Ok(()),
+        Statement::Expression(exp) => prune_expression(exp, defines),
+        Statement::YieldReturn(yr) => match yr.get_value_mut() {
+            Some(value) => prune_expression(value, defines),
+            None => Ok(()),
+        },
+        Statement::Return(ret) => match ret.get_value_mut() {
+            Some(value) => prune_expression(value, defines),
+            None => Ok(()),
+        },
+    }
+}
+
+fn prune_expression<M: Context + Clone>(exp: &mut Expression<M>, defines: &Defines) -> PruneResult {
+    // Replace this expression first so that the live arm is pruned by the traversal
+    // below.
+    if let Some(live) = take_live_arm(exp, defines) {
+        *exp = live;
+    }
+
+    if let Some(defined) = defines.check(exp) {
+        *exp = Expression::Boolean(exp.context().clone(), defined);
+        return Ok(());
+    }
+
+    if let Some(value) = defines.env(exp)? {
+        *exp = value;
+        return Ok(());
+    }
+
+    match exp {
+        Expression::ArrayExpression(_, elements, _) => {
+            for el in elements {
+                prune_expression(el, defines)?
+            }
+            Ok(())
+        }
+        Expression::ArrayAt { array, index, .. } => {
+            prune_expression(array, defines)?;
+            prune_expression(index, defines)
+        }
+        Expression::MemberAccess(_, src, _)
+        | Expression::TypeCast(_, src, _)
+        | Expression::UnaryOp(_, _, src)
+        | Expression::Yield(_, src) => prune_expression(src, defines),
+        Expression::RoutineCall(_, _, _, args) => {
+            for arg in args {
+                prune_expression(arg, defines)?
+            }
+            Ok(())
+        }
+        Expression::StructExpression(_, _, fields) => {
+            for (_, value) in fields {
+                prune_expression(value, defines)?
+            }
+            Ok(())
+        }
+        Expression::If {
+            cond,
+            if_arm,
+            else_arm,
+            ..
+        } => {
+            prune_expression(cond, defines)?;
+            prune_expression(if_arm, defines)?;
+            match else_arm {
+                Some(else_arm) => prune_expression(else_arm, defines),
+                None => Ok(()),
+            }
+        }
+        Expression::While { cond, body, .. } => {
+            prune_expression(cond, defines)?;
+            prune_expression(body, defines)
+        }
+        Expression::ExpressionBlock(_, body, final_exp) => {
+            for stm in body {
+                prune_statement(stm, defines)?
+            }
+            match final_exp {
+                Some(final_exp) => prune_expression(final_exp, defines),
+                None => Ok(()),
+            }
+        }
+        Expression::BinaryOp(_, _, l, r) => {
+            prune_expression(l, defines)?;
+            prune_expression(r, defines)
+        }
+        Expression::Null(_)
+        | Expression::OptionNone(_)
+        | Expression::U8(..)
+        | Expression::U16(..)
+        | Expression::U32(..)
+        | Expression::U64(..)
+        | Expression::I8(..)
+        | Expression::I16(..)
+        | Expression::I32(..)
+        | Expression::I64(..)
+        | Expression::F64(..)
+        | Expression::Boolean(..)
+        | Expression::StringLiteral(..)
+        | Expression::SizeOf(..)
+        | Expression::FieldsOf(..)
+        | Expression::NameOf(..)
+        | Expression::MacroCall(..)
+        | Expression::CustomType(..)
+        | Expression::Identifier(..)
+        | Expression::Path(..)
+        | Expression::IdentifierDeclare(..) => Ok(()),
+    }
+}
+
+/// If `exp` is an `if` expression with a compile time constant condition, then return
+/// the arm which will be taken.  An `if` with no `else` arm is only replaced when its
+/// condition is `false`, otherwise the `if` is kept so that its arm is still checked to
+/// be unit.
+fn take_live_arm<M: Context + Clone>(
+    exp: &Expression<M>,
+    defines: &Defines,
+) -> Option<Expression<M>> {
+    match exp {
+        Expression::If {
+            context,
+            cond,
+            if_arm,
+            else_arm,
+        } if uses_cfg(cond, defines) => match (const_condition(cond, defines)?, else_arm) {
+            (true, Some(_)) => Some((**if_arm).clone()),
+            (true, None) => None,
+            (false, Some(else_arm)) => Some((**else_arm).clone()),
+            (false, None) => Some(Expression::ExpressionBlock(context.clone(), vec![], None)),
+        },
+        _ => None,
+    }
+}
+
+/// Evaluates a condition which is built only from `cfg(NAME)`, boolean literals, and
+/// the logical operators.  Returns `None` if the value of the condition is not known
+/// until the program is run.
+fn const_condition(cond: &Expression<impl Context>, defines: &Defines) -> Option<bool> {
+    match cond {
+        Expression::Boolean(_, b) => Some(*b),
+        Expression::UnaryOp(_, UnaryOperator::Not, v) => const_condition(v, defines).map(|v| !v),
+        Expression::BinaryOp(_, op, l, r) => {
+            let l = const_condition(l, defines)?;
+            let r = const_condition(r, defines)?;
+            match op {
+                BinaryOperator::BAnd => Some(l && r),
+                BinaryOperator::BOr => Some(l || r),
+                BinaryOperator::Eq => Some(l == r),
+                BinaryOperator::NEq => Some(l != r),
+                _ => None,
+            }
+        }
+        _ => defines.check(cond),
+    }
+}
+
+/// Returns true if `cond` contains a `cfg(NAME)` check.
+fn uses_cfg(cond: &Expression<impl Context>, defines: &Defines) -> bool {
+    match cond {
+        Expression::UnaryOp(_, _, v) => uses_cfg(v, defines),
+        Expression::BinaryOp(_, _, l, r) => uses_cfg(l, defines) || uses_cfg(r, defines),
+        _ => defines.check(cond).is_some(),
+    }
+}
@@ -1,7 +1,11 @@
 use crate::{
     compiler::{
-        ast::{BinaryOperator, Path, PathCanonizationError, RoutineCall, Type, UnaryOperator},
-        CompilerDisplay, CompilerDisplayError, SourceError, SourceMap, Span,
+        ast::{
+            BinaryOperator, HasVarArgs, NodeType, Path, PathCanonizationError, RoutineCall, Type,
+            UnaryOperator,
+        },
+        fuel::FuelError,
+        messages, CompilerDisplay, CompilerDisplayError, SourceError, SourceMap, Span,
     },
     StringId,
 };
@@ -13,6 +17,8 @@ pub enum SemanticError {
     NotRoutine(StringId),
     NotCoroutine(StringId),
     MultipleDefs(Path),
+    ImportConflict(Path),
+    UseNotFound(Path),
     PathNotFound(Path, Path),
     PathNotValid,
     NotDefined(StringId),
@@ -28,10 +34,12 @@ pub enum SemanticError {
     BindMismatch(Span, Type, Type),
     YieldExpected(Type, Type),
     YieldInvalidLocation,
+    YieldReturnInFunction(StringId),
     ReturnExpected(Type, Type),
     ReturnInvalidLocation,
     MemberAccessInvalidRootType(Type),
     MemberAccessMemberNotFound(Path, StringId),
+    MethodNotFound(Path, StringId),
     IfExprMismatchArms(Type, Type),
     CondExpectedBool(Type),
     WhileInvalidType(Type),
@@ -45,227 +53,400 @@ pub enum SemanticError {
     ExpectedSignedInteger(UnaryOperator, Type),
     ExpectedBool(UnaryOperator, Type),
     ExpectedRawPointer(UnaryOperator, Type),
+    ExpectedSequence(UnaryOperator, Type),
+    ExpectedOption(UnaryOperator, Type),
+    ExpectedResult(UnaryOperator, Type),
+    ExpectedFixedWidthInteger(UnaryOperator, Type),
+    ExpectedByteArray(UnaryOperator, Type),
     OpExpected(BinaryOperator, Type, Type, Type),
+    ChainedComparison(BinaryOperator, BinaryOperator, Type),
     ExpectedIdentifier(UnaryOperator),
     ExpectedAddressable(UnaryOperator),
-    RoutineParamTypeMismatch(Path, Vec<(u32, Type, Type)>),
+    RoutineParamTypeMismatch(Path, RoutineSignature, Vec<(u32, Type, Type)>),
     MainFnInvalidType,
     MainFnInvalidParams,
     InvalidStructure,
     MutablePointerToImmutable,
     RoutineCallInvalidTarget(RoutineCall, Path, Type),
+    InitOfNonCoroutine(Path, Type),
     InvalidIdentifierType(Type),
     OffsetOperatorRequiresPointer(Type),
     OffsetOperatorRequiresInteger(Type),
     InvalidTypeCast,
+    ConstFnCallsNonConst(Path),
+    ConstFnUsesRawPointer(Type),
+    UnresolvedType(NodeType),
+    PartialTypeNotInferred,
+    TryMismatch(Type, Type),
+    PrivateField(Path, StringId),
+    FieldsOfNonStruct(Type),
+    EnvNotDefined(StringId),
+    EnvExpectedName,
+    StaticAssertFailed(StringId),
+    StaticAssertNotConstant,
+    StaticAssertExpectedBool(Type),
+    StaticAssertOverflow,
+    ConstEvalLimit,
+    ResourceExhausted(FuelError),
+    ReturnInDefer,
+    TryInDefer,
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+    LoopLabelNotFound(StringId),
+}
+
+impl SemanticError {
+    /// Converts every canonical path in this error to the path which the user would
+    /// write to refer to the same item from within `module`, so that the error
+    /// message shows paths the way they appear in the source code.
+    pub(super) fn to_source_paths(self, module: &Path) -> SemanticError {
+        use SemanticError::*;
+
+        let p = |path: Path| path.to_source_path(module);
+        let t = |ty: Type| ty.to_source_paths(module);
+        match self {
+            MultipleDefs(path) => MultipleDefs(p(path)),
+            PathNotFound(path, canon) => PathNotFound(p(path), canon),
+            ArrayIndexingInvalidType(ty) => ArrayIndexingInvalidType(t(ty)),
+            ArrayIndexingInvalidIndexType(ty) => ArrayIndexingInvalidIndexType(t(ty)),
+            BindExpected(l, r) => BindExpected(t(l), t(r)),
+            BindMismatch(span, l, r) => BindMismatch(span, t(l), t(r)),
+            YieldExpected(l, r) => YieldExpected(t(l), t(r)),
+            ReturnExpected(l, r) => ReturnExpected(t(l), t(r)),
+            MemberAccessInvalidRootType(ty) => MemberAccessInvalidRootType(t(ty)),
+            MemberAccessMemberNotFound(path, m) => MemberAccessMemberNotFound(p(path), m),
+            MethodNotFound(path, m) => MethodNotFound(p(path), m),
+            PrivateField(path, f) => PrivateField(p(path), f),
+            IfExprMismatchArms(l, r) => IfExprMismatchArms(t(l), t(r)),
+            TryMismatch(l, r) => TryMismatch(t(l), t(r)),
+            CondExpectedBool(ty) => CondExpectedBool(t(ty)),
+            WhileInvalidType(ty) => WhileInvalidType(t(ty)),
+            WhileCondInvalidType(ty) => WhileCondInvalidType(t(ty)),
+            YieldInvalidType(ty) => YieldInvalidType(t(ty)),
+            RoutineCallWrongNumParams(path, e, a) => RoutineCallWrongNumParams(p(path), e, a),
+            FunctionParamsNotEnough(path, e, a) => FunctionParamsNotEnough(p(path), e, a),
+            StructExprMemberNotFound(path, m) => StructExprMemberNotFound(p(path), m),
+            StructExprFieldTypeMismatch(path, f, e, a) => {
+                StructExprFieldTypeMismatch(p(path), f, t(e), t(a))
+            }
+            ExpectedSignedInteger(op, ty) => ExpectedSignedInteger(op, t(ty)),
+            ExpectedBool(op, ty) => ExpectedBool(op, t(ty)),
+            ExpectedRawPointer(op, ty) => ExpectedRawPointer(op, t(ty)),
+            ExpectedSequence(op, ty) => ExpectedSequence(op, t(ty)),
+            ExpectedOption(op, ty) => ExpectedOption(op, t(ty)),
+            ExpectedResult(op, ty) => ExpectedResult(op, t(ty)),
+            ExpectedFixedWidthInteger(op, ty) => ExpectedFixedWidthInteger(op, t(ty)),
+            ExpectedByteArray(op, ty) => ExpectedByteArray(op, t(ty)),
+            OpExpected(op, e, l, r) => OpExpected(op, t(e), t(l), t(r)),
+            ChainedComparison(inner, outer, r) => ChainedComparison(inner, outer, t(r)),
+            RoutineParamTypeMismatch(path, sig, mismatches) => RoutineParamTypeMismatch(
+                p(path),
+                RoutineSignature {
+                    params: sig
+                        .params
+                        .into_iter()
+                        .map(|(name, ty)| (name, t(ty)))
+                        .collect(),
+                    has_varargs: sig.has_varargs,
+                    ret_ty: t(sig.ret_ty),
+                },
+                mismatches
+                    .into_iter()
+                    .map(|(idx, e, a)| (idx, t(e), t(a)))
+                    .collect(),
+            ),
+            RoutineCallInvalidTarget(call, path, ty) => {
+                RoutineCallInvalidTarget(call, p(path), t(ty))
+            }
+            InitOfNonCoroutine(path, ty) => InitOfNonCoroutine(p(path), t(ty)),
+            InvalidIdentifierType(ty) => InvalidIdentifierType(t(ty)),
+            OffsetOperatorRequiresPointer(ty) => OffsetOperatorRequiresPointer(t(ty)),
+            OffsetOperatorRequiresInteger(ty) => OffsetOperatorRequiresInteger(t(ty)),
+            ConstFnCallsNonConst(path) => ConstFnCallsNonConst(p(path)),
+            ConstFnUsesRawPointer(ty) => ConstFnUsesRawPointer(t(ty)),
+            FieldsOfNonStruct(ty) => FieldsOfNonStruct(t(ty)),
+            StaticAssertExpectedBool(ty) => StaticAssertExpectedBool(t(ty)),
+            e => e,
+        }
+    }
+}
+
+impl SemanticError {
+    /// Returns the code which identifies this error and its message in the
+    /// [message catalog](crate::compiler::messages).
+    pub fn code(&self) -> &'static str {
+        match self {
+            SemanticError::NotVariable(..) => "S0001",
+            SemanticError::NotRoutine(..) => "S0002",
+            SemanticError::NotCoroutine(..) => "S0003",
+            SemanticError::MultipleDefs(..) => "S0004",
+            SemanticError::ImportConflict(..) => "S0005",
+            SemanticError::UseNotFound(..) => "S0006",
+            SemanticError::PathNotFound(..) => "S0007",
+            SemanticError::PathNotValid => "S0008",
+            SemanticError::NotDefined(..) => "S0009",
+            SemanticError::EmptyPath => "S0010",
+            SemanticError::ArrayInvalidSize(..) => "S0011",
+            SemanticError::ArrayInconsistentElementTypes => "S0012",
+            SemanticError::ArrayIndexingInvalidType(..) => "S0013",
+            SemanticError::ArrayIndexingInvalidIndexType(..) => "S0014",
+            SemanticError::AlreadyDeclared(..) => "S0015",
+            SemanticError::PathTooSuper => "S0016",
+            SemanticError::BindExpected(..) => "S0017",
+            SemanticError::ExpressionNotMutable(..) => "S0018",
+            SemanticError::BindMismatch(..) => "S0019",
+            SemanticError::YieldExpected(..) => "S0020",
+            SemanticError::YieldInvalidLocation => "S0021",
+            SemanticError::YieldReturnInFunction(..) => "S0022",
+            SemanticError::ReturnExpected(..) => "S0023",
+            SemanticError::ReturnInvalidLocation => "S0024",
+            SemanticError::MemberAccessInvalidRootType(..) => "S0025",
+            SemanticError::MemberAccessMemberNotFound(..) => "S0026",
+            SemanticError::MethodNotFound(..) => "S0027",
+            SemanticError::IfExprMismatchArms(..) => "S0029",
+            SemanticError::CondExpectedBool(..) => "S0030",
+            SemanticError::WhileInvalidType(..) => "S0031",
+            SemanticError::WhileCondInvalidType(..) => "S0032",
+            SemanticError::YieldInvalidType(..) => "S0033",
+            SemanticError::RoutineCallWrongNumParams(..) => "S0034",
+            SemanticError::FunctionParamsNotEnough(..) => "S0035",
+            SemanticError::StructExprWrongNumParams(..) => "S0036",
+            SemanticError::StructExprMemberNotFound(..) => "S0037",
+            SemanticError::StructExprFieldTypeMismatch(..) => "S0038",
+            SemanticError::ExpectedSignedInteger(..) => "S0039",
+            SemanticError::ExpectedBool(..) => "S0040",
+            SemanticError::ExpectedRawPointer(..) => "S0041",
+            SemanticError::ExpectedSequence(..) => "S0042",
+            SemanticError::OpExpected(..) => "S0043",
+            SemanticError::ChainedComparison(..) => "S0044",
+            SemanticError::ExpectedIdentifier(..) => "S0045",
+            SemanticError::ExpectedAddressable(..) => "S0046",
+            SemanticError::RoutineParamTypeMismatch(..) => "S0047",
+            SemanticError::MainFnInvalidType => "S0048",
+            SemanticError::MainFnInvalidParams => "S0049",
+            SemanticError::InvalidStructure => "S0050",
+            SemanticError::MutablePointerToImmutable => "S0051",
+            SemanticError::RoutineCallInvalidTarget(..) => "S0052",
+            SemanticError::InitOfNonCoroutine(..) => "S0053",
+            SemanticError::InvalidIdentifierType(..) => "S0054",
+            SemanticError::OffsetOperatorRequiresPointer(..) => "S0055",
+            SemanticError::OffsetOperatorRequiresInteger(..) => "S0056",
+            SemanticError::InvalidTypeCast => "S0057",
+            SemanticError::ConstFnCallsNonConst(..) => "S0058",
+            SemanticError::ConstFnUsesRawPointer(..) => "S0059",
+            SemanticError::UnresolvedType(..) => "S0060",
+            SemanticError::ExpectedOption(..) => "S0061",
+            SemanticError::PartialTypeNotInferred => "S0062",
+            SemanticError::ExpectedResult(..) => "S0063",
+            SemanticError::TryMismatch(..) => "S0064",
+            SemanticError::PrivateField(..) => "S0065",
+            SemanticError::FieldsOfNonStruct(..) => "S0066",
+            SemanticError::EnvNotDefined(..) => "S0067",
+            SemanticError::EnvExpectedName => "S0068",
+            SemanticError::StaticAssertFailed(..) => "S0069",
+            SemanticError::StaticAssertNotConstant => "S0070",
+            SemanticError::StaticAssertExpectedBool(..) => "S0071",
+            SemanticError::StaticAssertOverflow => "S0072",
+            SemanticError::ResourceExhausted(..) => "S0073",
+            SemanticError::ReturnInDefer => "S0074",
+            SemanticError::BreakOutsideLoop => "S0075",
+            SemanticError::ContinueOutsideLoop => "S0076",
+            SemanticError::LoopLabelNotFound(..) => "S0077",
+            SemanticError::ExpectedFixedWidthInteger(..) => "S0078",
+            SemanticError::ExpectedByteArray(..) => "S0079",
+            SemanticError::TryInDefer => "S0080",
+            SemanticError::ConstEvalLimit => "S0081",
+        }
+    }
 }
 
 impl CompilerDisplay for SemanticError {
     /// Turn a SemanticError into a human readable message.  This will convert all StringIds
     /// to their associated string value.
     fn fmt(&self, sm: &SourceMap, st: &crate::StringTable) -> Result<String, CompilerDisplayError> {
-        match self {
-            SemanticError::NotVariable(sid) => {
-                Ok(format!("{} is not a variable", sid.fmt(sm, st)?))
-            }
-            SemanticError::NotRoutine(sid) => Ok(format!("{} is not a routine", sid.fmt(sm, st)?)),
-            SemanticError::NotCoroutine(sid) => {
-                Ok(format!("{} is not a coroutine", sid.fmt(sm, st)?))
-            }
-            SemanticError::MultipleDefs(path) => {
-                Ok(format!("{} is defined multiple times", path.fmt(sm, st)?))
-            }
-            SemanticError::PathNotFound(path, canonical_form) => Ok(format!(
-                "Could not find item with the given path: {} ({})",
-                path.fmt(sm, st)?,
-                canonical_form.fmt(sm, st)?
-            )),
-            SemanticError::PathNotValid => Ok("Path is not valid".into()),
-            SemanticError::NotDefined(sid) => Ok(format!(
-                "Could not find definition for {} in this scope",
-                sid.fmt(sm, st)?
-            )),
-            SemanticError::EmptyPath => Ok("Empty path".into()),
-            SemanticError::ArrayInvalidSize(sz) => {
-                Ok(format!("Expected length > 0 for array, but found {}", sz))
-            }
-            SemanticError::ArrayInconsistentElementTypes => {
-                Ok("Inconsistent types in array value".into())
-            }
-            SemanticError::ArrayIndexingInvalidType(ty) => Ok(format!(
-                "Expected array type on LHS of [] but found {}",
-                ty.fmt(sm, st)?
-            )),
-            SemanticError::ArrayIndexingInvalidIndexType(ty) => Ok(format!(
-                "Expected integral type for index but found {}",
-                ty.fmt(sm, st)?
-            )),
-            SemanticError::AlreadyDeclared(sid) => {
-                Ok(format!("{} already declared", sid.fmt(sm, st)?))
+        use SemanticError::*;
+
+        let args = match self {
+            NotVariable(sid)
+            | NotRoutine(sid)
+            | NotCoroutine(sid)
+            | NotDefined(sid)
+            | AlreadyDeclared(sid)
+            | YieldReturnInFunction(sid)
+            | EnvNotDefined(sid)
+            | StaticAssertFailed(sid)
+            | LoopLabelNotFound(sid) => vec![sid.fmt(sm, st)?],
+            MultipleDefs(path)
+            | ImportConflict(path)
+            | UseNotFound(path)
+            | ConstFnCallsNonConst(path) => vec![path.fmt(sm, st)?],
+            PathNotFound(path, canonical_form) => {
+                vec![path.fmt(sm, st)?, canonical_form.fmt(sm, st)?]
             }
-            SemanticError::PathTooSuper => Ok("Path too super".into()),
-            SemanticError::BindExpected(expected, actual) => Ok(format!(
-                "Bind expected {} but got {}",
-                expected.fmt(sm, st)?,
-                actual.fmt(sm, st)?
-            )),
-            SemanticError::ExpressionNotMutable(span) => {
-                Ok(format!("{} is not mutable", sm.text_in_span(*span)?))
+            ArrayInvalidSize(sz) => vec![sz.to_string()],
+            ArrayIndexingInvalidType(ty)
+            | ArrayIndexingInvalidIndexType(ty)
+            | CondExpectedBool(ty)
+            | WhileInvalidType(ty)
+            | WhileCondInvalidType(ty)
+            | YieldInvalidType(ty)
+            | InvalidIdentifierType(ty)
+            | OffsetOperatorRequiresPointer(ty)
+            | OffsetOperatorRequiresInteger(ty)
+            | ConstFnUsesRawPointer(ty)
+            | FieldsOfNonStruct(ty)
+            | StaticAssertExpectedBool(ty) => vec![ty.fmt(sm, st)?],
+            BindExpected(expected, actual)
+            | YieldExpected(expected, actual)
+            | ReturnExpected(expected, actual)
+            | IfExprMismatchArms(expected, actual)
+            | TryMismatch(expected, actual) => {
+                vec![expected.fmt(sm, st)?, actual.fmt(sm, st)?]
             }
-            SemanticError::BindMismatch(span, expected, actual) => Ok(format!(
-                "{} is of type {} but is assigned {}",
+            ExpressionNotMutable(span) => vec![sm.text_in_span(*span)?],
+            BindMismatch(span, expected, actual) => vec![
                 sm.text_in_span(*span)?,
                 expected.fmt(sm, st)?,
-                actual.fmt(sm, st)?
-            )),
-            SemanticError::YieldExpected(expected, actual) => Ok(format!(
-                "Yield return expected {} but got {}",
-                expected.fmt(sm, st)?,
-                actual.fmt(sm, st)?
-            )),
-            SemanticError::YieldInvalidLocation => Ok("yield must be at end of function".into()),
-            SemanticError::ReturnExpected(expected, actual) => Ok(format!(
-                "Return expected {} but got {}",
-                expected.fmt(sm, st)?,
-                actual.fmt(sm, st)?
-            )),
-            SemanticError::ReturnInvalidLocation => Ok("return invalid loc".into()),
-            SemanticError::MemberAccessInvalidRootType(_) => {
-                Ok("Member access invalid root type".into())
+                actual.fmt(sm, st)?,
+            ],
+            MemberAccessMemberNotFound(path, sid)
+            | MethodNotFound(path, sid)
+            | PrivateField(path, sid) => {
+                vec![path.fmt(sm, st)?, sid.fmt(sm, st)?]
             }
-            SemanticError::MemberAccessMemberNotFound(path, member) => Ok(format!(
-                "{} does not have member {}",
-                path.fmt(sm, st)?,
-                member.fmt(sm, st)?
-            )),
-            SemanticError::IfExprMismatchArms(t, f) => Ok(format!(
-                "If expression has mismatching arms: expected {} got {}",
-                t.fmt(sm, st)?,
-                f.fmt(sm, st)?
-            )),
-            SemanticError::CondExpectedBool(actual) => Ok(format!(
-                "Expected boolean expression in if conditional, got: {}",
-                actual.fmt(sm, st)?
-            )),
-            SemanticError::WhileInvalidType(actual) => Ok(format!(
-                "The body of a while expression must resolve to the unit type, but got: {}",
-                actual.fmt(sm, st)?
-            )),
-            SemanticError::WhileCondInvalidType(actual) => Ok(format!(
-                "The condition of a while expression must resolve to the bool type, but got: {}",
-                actual.fmt(sm, st)?
-            )),
-            SemanticError::YieldInvalidType(ty) => {
-                Ok(format!("Yield expects co<_> but got {}", ty.fmt(sm, st)?))
+            RoutineCallWrongNumParams(path, expected, actual)
+            | FunctionParamsNotEnough(path, expected, actual) => {
+                vec![path.fmt(sm, st)?, expected.to_string(), actual.to_string()]
             }
-            SemanticError::RoutineCallWrongNumParams(path, expected, actual) => Ok(format!(
-                "Incorrect number of parameters passed to routine: {}. Expected {} but got {}",
-                path.fmt(sm, st)?,
-                expected,
-                actual,
-            )),
-            SemanticError::FunctionParamsNotEnough(path, expected, actual) => Ok(format!(
-                "Function {} expects at least {} parameters, but got {}",
-                path.fmt(sm, st)?,
-                expected,
-                actual,
-            )),
-            SemanticError::StructExprWrongNumParams(expected, actual) => Ok(format!(
-                "Struct expected {} parameters but found {}",
-                expected, actual
-            )),
-            SemanticError::StructExprMemberNotFound(path, sid) => Ok(format!(
-                "member {} not found on {}",
-                sid.fmt(sm, st)?,
-                path.fmt(sm, st)?
-            )),
-            SemanticError::StructExprFieldTypeMismatch(path, fname, expected, actual) => {
-                Ok(format!(
-                    "{}.{} expects {} but got {}",
-                    path.fmt(sm, st)?,
-                    fname.fmt(sm, st)?,
-                    expected.fmt(sm, st)?,
-                    actual.fmt(sm, st)?
-                ))
+            StructExprWrongNumParams(expected, actual) => {
+                vec![expected.to_string(), actual.to_string()]
             }
-            SemanticError::ExpectedSignedInteger(op, ty) => Ok(format!(
-                "{} expected i32 or i64 but found {}",
-                op,
-                ty.fmt(sm, st)?
-            )),
-            SemanticError::ExpectedBool(op, ty) => Ok(format!(
-                "{} expected bool but found {}",
-                op,
-                ty.fmt(sm, st)?
-            )),
-            SemanticError::ExpectedRawPointer(op, ty) => Ok(format!(
-                "{} expected a *mut or *const but found {}",
-                op,
-                ty.fmt(sm, st)?
-            )),
-            SemanticError::OpExpected(op, expected, l, r) => Ok(format!(
-                "{} expected {} but found {} and {}",
-                op,
+            StructExprMemberNotFound(path, sid) => vec![sid.fmt(sm, st)?, path.fmt(sm, st)?],
+            StructExprFieldTypeMismatch(path, fname, expected, actual) => vec![
+                path.fmt(sm, st)?,
+                fname.fmt(sm, st)?,
+                expected.fmt(sm, st)?,
+                actual.fmt(sm, st)?,
+            ],
+            ExpectedSignedInteger(op, ty)
+            | ExpectedBool(op, ty)
+            | ExpectedRawPointer(op, ty)
+            | ExpectedSequence(op, ty)
+            | ExpectedOption(op, ty)
+            | ExpectedResult(op, ty)
+            | ExpectedFixedWidthInteger(op, ty)
+            | ExpectedByteArray(op, ty) => vec![op.to_string(), ty.fmt(sm, st)?],
+            OpExpected(op, expected, l, r) => vec![
+                op.to_string(),
                 expected.fmt(sm, st)?,
                 l.fmt(sm, st)?,
-                r.fmt(sm, st)?
-            )),
-            SemanticError::RoutineParamTypeMismatch(path, mismatches) => Ok(format!(
-                "One or more parameters have mismatching types for function {}: {}",
+                r.fmt(sm, st)?,
+            ],
+            ChainedComparison(inner, outer, r) => {
+                vec![inner.to_string(), outer.to_string(), r.fmt(sm, st)?]
+            }
+            ExpectedIdentifier(op) | ExpectedAddressable(op) => vec![op.to_string()],
+            RoutineParamTypeMismatch(path, sig, mismatches) => vec![
                 path.fmt(sm, st)?,
+                sig.fmt(sm, st)?,
                 mismatches
                     .iter()
                     .map(|(param_id, expected, actual)| {
-                        Ok(format!(
-                            "parameter {} expected {} but got {}",
-                            param_id,
-                            expected.fmt(sm, st)?,
-                            actual.fmt(sm, st)?
-                        ))
+                        let (code, mut args) = match sig.param_name(*param_id) {
+                            Some(name) => (
+                                "S0047.named-param",
+                                vec![param_id.to_string(), st.get(name)?.to_string()],
+                            ),
+                            None => ("S0047.param", vec![param_id.to_string()]),
+                        };
+                        args.push(expected.fmt(sm, st)?);
+                        args.push(actual.fmt(sm, st)?);
+                        Ok(messages::message(code, &args))
                     })
                     .collect::<Result<Vec<_>, CompilerDisplayError>>()?
-                    .join(", ")
-            )),
-            SemanticError::MainFnInvalidType => {
-                Ok("my_main must be a function of type () -> i64".into())
-            }
-            SemanticError::MainFnInvalidParams => {
-                Ok("my_main must take no parameters. It must be of type () -> i64".into())
-            }
-            SemanticError::InvalidStructure => Ok("Not a valid structure definition".into()),
-            SemanticError::RoutineCallInvalidTarget(call, path, ty) => {
+                    .join(", "),
+            ],
+            RoutineCallInvalidTarget(call, path, ty) => {
                 let call = match call {
-                    crate::compiler::ast::RoutineCall::Function => "function",
-                    crate::compiler::ast::RoutineCall::CoroutineInit => "coroutine",
-                    crate::compiler::ast::RoutineCall::Extern => "extern",
+                    RoutineCall::Function => "S0052.function",
+                    RoutineCall::CoroutineInit => "S0052.coroutine",
+                    RoutineCall::Extern => "S0052.extern",
+                    RoutineCall::Method | RoutineCall::Iterator(_) => "S0052.method",
                 };
-                Ok(format!(
-                    "Expected {} but {} is a {}",
-                    call,
+                vec![
+                    messages::message(call, &[]),
                     path.fmt(sm, st)?,
-                    ty.fmt(sm, st)?
-                ))
-            }
-            SemanticError::InvalidIdentifierType(ty) => Ok(format!(
-                "Invalid type used in identifier declaration: {}",
-                ty.fmt(sm, st)?
-            )),
-            SemanticError::MutablePointerToImmutable => {
-                Ok("Cannot make mutable pointer to immutable variable".into())
-            }
-            SemanticError::ExpectedIdentifier(op) => Ok(format!("{} expected identifier", op)),
-            SemanticError::ExpectedAddressable(op) => {
-                Ok(format!("{} expected an addressable operand", op))
+                    ty.fmt(sm, st)?,
+                ]
             }
-            SemanticError::OffsetOperatorRequiresPointer(ty) => Ok(format!(
-                "@ operator expects raw pointer on left side, but got {}",
-                ty.fmt(sm, st)?
-            )),
-            SemanticError::OffsetOperatorRequiresInteger(ty) => Ok(format!(
-                "@ operator expects integer on right side, but got {}",
-                ty.fmt(sm, st)?
+            InitOfNonCoroutine(path, ty) => vec![path.fmt(sm, st)?, ty.fmt(sm, st)?],
+            UnresolvedType(node) => vec![node.to_string()],
+            ResourceExhausted(err) => vec![err.to_string()],
+            PathNotValid
+            | PartialTypeNotInferred
+            | EmptyPath
+            | ArrayInconsistentElementTypes
+            | PathTooSuper
+            | YieldInvalidLocation
+            | ReturnInvalidLocation
+            | MemberAccessInvalidRootType(_)
+            | MainFnInvalidType
+            | MainFnInvalidParams
+            | InvalidStructure
+            | MutablePointerToImmutable
+            | InvalidTypeCast
+            | EnvExpectedName
+            | StaticAssertNotConstant
+            | StaticAssertOverflow
+            | ConstEvalLimit
+            | ReturnInDefer
+            | TryInDefer
+            | BreakOutsideLoop
+            | ContinueOutsideLoop => vec![],
+        };
+        Ok(messages::message(self.code(), &args))
+    }
+}
+
+/// The signature of a routine, including the names of its parameters, which is
+/// included in errors about calls to that routine.
+#[derive(Debug, PartialEq)]
+pub struct RoutineSignature {
+    pub params: Vec<(Option<StringId>, Type)>,
+    pub has_varargs: HasVarArgs,
+    pub ret_ty: Type,
+}
+
+impl RoutineSignature {
+    /// Returns the name of the parameter at the given 1-based position, if known.
+    fn param_name(&self, idx: u32) -> Option<StringId> {
+        self.params
+            .get((idx as usize).checked_sub(1)?)
+            .and_then(|(name, _)| *name)
+    }
+}
+
+impl CompilerDisplay for RoutineSignature {
+    fn fmt(&self, sm: &SourceMap, st: &crate::StringTable) -> Result<String, CompilerDisplayError> {
+        let mut params = self
+            .params
+            .iter()
+            .map(|(name, ty)| match name {
+                Some(name) => Ok(format!("{}: {}", st.get(*name)?, ty.fmt(sm, st)?)),
+                None => ty.fmt(sm, st),
+            })
+            .collect::<Result<Vec<_>, CompilerDisplayError>>()?;
+        if self.has_varargs {
+            params.push("...".into());
+        }
+
+        match self.ret_ty {
+            Type::Unit => Ok(format!("({})", params.join(", "))),
+            _ => Ok(format!(
+                "({}) -> {}",
+                params.join(", "),
+                self.ret_ty.fmt(sm, st)?
             )),
-            SemanticError::InvalidTypeCast => Ok("Invalid type cast".into()),
         }
     }
 }
@@ -273,7 +454,7 @@ impl CompilerDisplay for SemanticError {
 impl From<PathCanonizationError> for SemanticError {
     fn from(pe: PathCanonizationError) -> Self {
         match pe {
-            PathCanonizationError::SubceedingRoot => Self::PathTooSuper, // TODO: maybe reevaluate why I get this from the AST in Semantic?
+            PathCanonizationError::SubceedingRoot => Self::PathTooSuper,
         }
     }
 }
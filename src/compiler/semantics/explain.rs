@@ -0,0 +1,462 @@
+//! Explains how the type resolver derived the type of an expression.  This is used by
+//! `--explain-types` so that the user can point at an expression and see why it has
+//! the type that it has: how each literal was typed, which implicit coercions were
+//! applied, and which operator or routine each operation was resolved to.
+
+use crate::{
+    compiler::{
+        ast::{
+            BinaryOperator, Expression, Item, Module, RoutineDef, Statement, Type, UnaryOperator,
+        },
+        source::SourceIr,
+        CompilerDisplay, CompilerDisplayError, SourceMap, Span,
+    },
+    StringId, StringTable,
+};
+
+use super::semanticnode::SemanticContext;
+
+/// The number of spaces that each level of the derivation is indented by.
+const INDENT: usize = 2;
+
+/// The number of characters of source code which are shown for an expression.  Longer
+/// expressions are shortened to their start and their end.
+const MAX_SOURCE_WIDTH: usize = 40;
+
+/// Returns the derivation of the type of the smallest expression in `m` which covers
+/// `span`, or `None` if no expression covers it.
+///
+/// Each line of the derivation is an expression, its type, and the rule by which it was
+/// given that type.  The expressions which the type was derived from are indented
+/// beneath it.
+pub fn explain_types(
+    m: &Module<SemanticContext>,
+    span: Span,
+    sm: &SourceMap,
+    st: &StringTable,
+) -> Result<Option<String>, CompilerDisplayError> {
+    let mut finder = ExpressionFinder { span, found: None };
+    finder.module(m);
+    explain_found(finder.found, sm, st)
+}
+
+/// Returns the derivation of the type of the smallest expression in the item `item`
+/// which covers `span`, like [`explain_types`].  This lets an expression be explained
+/// when only the item which contains it has been resolved, with
+/// [`SemanticQueries`](super::query::SemanticQueries), and so even when other items in
+/// the project do not type check.
+pub fn explain_item_types(
+    item: &Item<SemanticContext>,
+    span: Span,
+    sm: &SourceMap,
+    st: &StringTable,
+) -> Result<Option<String>, CompilerDisplayError> {
+    let mut finder = ExpressionFinder { span, found: None };
+    finder.item(item);
+    explain_found(finder.found, sm, st)
+}
+
+fn explain_found(
+    found: Option<&Expression<SemanticContext>>,
+    sm: &SourceMap,
+    st: &StringTable,
+) -> Result<Option<String>, CompilerDisplayError> {
+    match found {
+        Some(exp) => {
+            let mut explainer = TypeExplainer {
+                sm,
+                st,
+                depth: 0,
+                text: String::new(),
+            };
+            explainer.expression(exp)?;
+            Ok(Some(explainer.text))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Returns true if `outer` covers every character in `inner`.
+fn covers(outer: Span, inner: Span) -> bool {
+    outer.low() <= inner.low() && inner.high() <= outer.high()
+}
+
+fn width(span: Span) -> u32 {
+    span.high().as_u32() - span.low().as_u32()
+}
+
+/// Finds the smallest expression which covers a span.  If several expressions have the
+/// same span (e.g. an implicit coercion and the expression which it coerces), then the
+/// outermost is found, so that nothing which was done to the expression is hidden.
+struct ExpressionFinder<'a> {
+    span: Span,
+    found: Option<&'a Expression<SemanticContext>>,
+}
+
+impl<'a> ExpressionFinder<'a> {
+    fn module(&mut self, m: &'a Module<SemanticContext>) {
+        for sm in m.get_modules() {
+            self.module(sm)
+        }
+
+        for item in m.get_functions().iter().chain(m.get_coroutines()) {
+            if let Item::Routine(r) = item {
+                self.routine(r)
+            }
+        }
+
+        for r in m.get_methods() {
+            self.routine(r)
+        }
+    }
+
+    fn item(&mut self, item: &'a Item<SemanticContext>) {
+        match item {
+            Item::Routine(r) => self.routine(r),
+            Item::Struct(sd) => {
+                for r in sd.get_methods() {
+                    self.routine(r)
+                }
+            }
+            Item::Extern(_) => (),
+        }
+    }
+
+    fn routine(&mut self, r: &'a RoutineDef<SemanticContext>) {
+        for stm in r.get_body() {
+            self.statement(stm)
+        }
+    }
+
+    fn statement(&mut self, stm: &'a Statement<SemanticContext>) {
+        match stm {
+            Statement::Bind(b) => self.expression(b.get_rhs()),
+            Statement::Mutate(m) => {
+                self.expression(m.get_lhs());
+                self.expression(m.get_rhs())
+            }
+            Statement::YieldReturn(yr) => {
+                if let Some(v) = yr.get_value() {
+                    self.expression(v)
+                }
+            }
+            Statement::Return(r) => {
+                if let Some(v) = r.get_value() {
+                    self.expression(v)
+                }
+            }
+            Statement::Expression(e) => self.expression(e),
+            Statement::Discard(d) => self.expression(d.get_value()),
+            Statement::Defer(d) => self.expression(d.get_value()),
+            Statement::Break(_) | Statement::Continue(_) => (),
+        }
+    }
+
+    fn expression(&mut self, exp: &'a Expression<SemanticContext>) {
+        let span = exp.span();
+        if covers(span, self.span) && self.found.map_or(true, |f| width(span) < width(f.span())) {
+            self.found = Some(exp)
+        }
+
+        match exp {
+            Expression::ExpressionBlock(_, body, final_exp) => {
+                for stm in body {
+                    self.statement(stm)
+                }
+                if let Some(fe) = final_exp {
+                    self.expression(fe)
+                }
+            }
+            Expression::StructExpression(_, _, fields) => {
+                for (_, fe) in fields {
+                    self.expression(fe)
+                }
+            }
+            Expression::ArrayExpression(_, elements, _)
+            | Expression::RoutineCall(_, _, _, elements) => {
+                for e in elements {
+                    self.expression(e)
+                }
+            }
+            Expression::ArrayAt { array, index, .. } => {
+                self.expression(array);
+                self.expression(index)
+            }
+            Expression::If {
+                cond,
+                if_arm,
+                else_arm,
+                ..
+            } => {
+                self.expression(cond);
+                self.expression(if_arm);
+                if let Some(ea) = else_arm {
+                    self.expression(ea)
+                }
+            }
+            Expression::While { cond, body, .. } => {
+                self.expression(cond);
+                self.expression(body)
+            }
+            Expression::BinaryOp(_, _, l, r) => {
+                self.expression(l);
+                self.expression(r)
+            }
+            Expression::MemberAccess(_, e, _)
+            | Expression::TypeCast(_, e, _)
+            | Expression::UnaryOp(_, _, e)
+            | Expression::Yield(_, e) => self.expression(e),
+            _ => (),
+        }
+    }
+}
+
+/// Renders the derivation of the type of an expression as a tree.
+struct TypeExplainer<'a> {
+    sm: &'a SourceMap,
+    st: &'a StringTable,
+
+    /// The depth of the expression currently being explained
+    depth: usize,
+    text: String,
+}
+
+impl<'a> TypeExplainer<'a> {
+    /// Adds the line for `exp`, which says that it was given its type by `rule`, at the
+    /// current depth, and then explains each expression in `from` one level deeper.
+    fn derive(
+        &mut self,
+        exp: &Expression<SemanticContext>,
+        rule: &str,
+        from: &[&Expression<SemanticContext>],
+    ) -> Result<(), CompilerDisplayError> {
+        let line = format!(
+            "{:indent$}`{}`: {}, {}\n",
+            "",
+            self.source(exp.span())?,
+            self.ty(exp.get_type())?,
+            rule,
+            indent = self.depth * INDENT
+        );
+        self.text.push_str(&line);
+
+        self.depth += 1;
+        let result = from.iter().try_for_each(|e| self.expression(e));
+        self.depth -= 1;
+        result
+    }
+
+    /// Returns the source code that `span` covers, with each block of whitespace
+    /// compressed to a single space.
+    fn source(&self, span: Span) -> Result<String, CompilerDisplayError> {
+        let src = self.sm.text_in_span(span)?;
+        let src: Vec<char> = src
+            .split_ascii_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .collect();
+
+        if src.len() <= MAX_SOURCE_WIDTH {
+            Ok(src.iter().collect())
+        } else {
+            let start: String = src[..MAX_SOURCE_WIDTH / 2].iter().collect();
+            let end: String = src[src.len() - MAX_SOURCE_WIDTH / 2..].iter().collect();
+            Ok(format!("{}...{}", start, end))
+        }
+    }
+
+    fn ty(&self, ty: &Type) -> Result<String, CompilerDisplayError> {
+        ty.fmt(self.sm, self.st)
+    }
+
+    fn name(&self, id: StringId) -> Result<String, CompilerDisplayError> {
+        Ok(self.st.get(id)?)
+    }
+
+    /// Explains a numeric literal, whose type is either given by its suffix or is the
+    /// default for its kind of literal.
+    fn number(
+        &mut self,
+        exp: &Expression<SemanticContext>,
+        default: &Type,
+    ) -> Result<(), CompilerDisplayError> {
+        let ty = self.ty(exp.get_type())?;
+        let rule = if self.sm.text_in_span(exp.span())?.ends_with(&ty) {
+            format!("literal with the suffix {}", ty)
+        } else if exp.get_type() == default {
+            format!("literal without a suffix, which is {} by default", ty)
+        } else {
+            "literal".into()
+        };
+        self.derive(exp, &rule, &[])
+    }
+
+    fn expression(
+        &mut self,
+        exp: &Expression<SemanticContext>,
+    ) -> Result<(), CompilerDisplayError> {
+        match exp {
+            Expression::U8(..)
+            | Expression::U16(..)
+            | Expression::U32(..)
+            | Expression::U64(..)
+            | Expression::I8(..)
+            | Expression::I16(..)
+            | Expression::I32(..)
+            | Expression::I64(..) => self.number(exp, &Type::I64),
+            Expression::F64(..) => self.number(exp, &Type::F64),
+            Expression::Boolean(..) => self.derive(exp, "boolean literal", &[]),
+            Expression::StringLiteral(..) => self.derive(exp, "string literal", &[]),
+            Expression::Null(_) => self.derive(exp, "null literal", &[]),
+            Expression::OptionNone(_) => self.derive(
+                exp,
+                "none, which takes the option type that is expected where it is used",
+                &[],
+            ),
+            Expression::ArrayExpression(_, elements, len) => {
+                let rule = format!("array of {} elements with the type of its elements", len);
+                self.derive(exp, &rule, &elements.iter().collect::<Vec<_>>())
+            }
+            Expression::ArrayAt { array, index, .. } => {
+                let rule = if index.get_type().is_integral() {
+                    format!("element of {}", self.ty(array.get_type())?)
+                } else {
+                    format!(
+                        "part of {}, selected by a range",
+                        self.ty(array.get_type())?
+                    )
+                };
+                self.derive(exp, &rule, &[array, index])
+            }
+            Expression::SizeOf(_, ty) => {
+                let rule = format!("size of {}", self.ty(ty)?);
+                self.derive(exp, &rule, &[])
+            }
+            Expression::FieldsOf(_, ty) => {
+                let rule = format!("fields of {}", self.ty(ty)?);
+                self.derive(exp, &rule, &[])
+            }
+            Expression::NameOf(_, ty) => {
+                let rule = format!("name of {}", self.ty(ty)?);
+                self.derive(exp, &rule, &[])
+            }
+            Expression::CustomType(..) => self.derive(exp, "type", &[]),
+            Expression::Identifier(_, id) => {
+                let rule = format!(
+                    "variable {}, which was declared with this type",
+                    self.name(*id)?
+                );
+                self.derive(exp, &rule, &[])
+            }
+            Expression::Path(_, path) => {
+                let rule = format!("item {}", path.fmt(self.sm, self.st)?);
+                self.derive(exp, &rule, &[])
+            }
+            Expression::IdentifierDeclare(_, id, _) => {
+                let rule = format!("declaration of {}", self.name(*id)?);
+                self.derive(exp, &rule, &[])
+            }
+            Expression::MemberAccess(_, src, member) => {
+                let rule = format!(
+                    "field {} of {}",
+                    self.name(*member)?,
+                    self.ty(src.get_type())?
+                );
+                self.derive(exp, &rule, &[src])
+            }
+            Expression::RoutineCall(_, call, path, args) => {
+                let rule = format!(
+                    "{} {}, which returns this type",
+                    call,
+                    path.fmt(self.sm, self.st)?
+                );
+                self.derive(exp, &rule, &args.iter().collect::<Vec<_>>())
+            }
+            Expression::StructExpression(_, path, fields) => {
+                let rule = format!("structure {}", path.fmt(self.sm, self.st)?);
+                let fields: Vec<_> = fields.iter().map(|(_, fe)| fe).collect();
+                self.derive(exp, &rule, &fields)
+            }
+            Expression::If {
+                if_arm, else_arm, ..
+            } => {
+                match else_arm {
+                    Some(else_arm) => {
+                        let rule = match (if_arm.get_type(), else_arm.get_type()) {
+                        (Type::Never, Type::Never) => "if, whose arms never finish",
+                        (Type::Never, _) => "if, with the type of the else arm, because the if arm never finishes",
+                        (_, Type::Never) => "if, with the type of the if arm, because the else arm never finishes",
+                        _ => "if, with the type of both of its arms",
+                    };
+                        self.derive(exp, rule, &[if_arm, else_arm])
+                    }
+                    None => self.derive(exp, "if without an else arm", &[if_arm]),
+                }
+            }
+            Expression::While { .. } => self.derive(exp, "while loop", &[]),
+            Expression::ExpressionBlock(_, body, final_exp) => match final_exp {
+                Some(fe) => self.derive(exp, "block, with the type of its final expression", &[fe]),
+                None if body.iter().any(|s| matches!(s, Statement::Return(_))) => {
+                    self.derive(exp, "block which returns from the routine", &[])
+                }
+                None if body
+                    .iter()
+                    .any(|s| matches!(s, Statement::Break(_) | Statement::Continue(_))) =>
+                {
+                    self.derive(exp, "block which leaves its loop", &[])
+                }
+                None => self.derive(exp, "block without a final expression", &[]),
+            },
+            Expression::BinaryOp(_, op, l, r) => {
+                let rule = match op {
+                    BinaryOperator::Range => "range".to_string(),
+                    op => format!(
+                        "{} applied to {} and {}",
+                        op,
+                        self.ty(l.get_type())?,
+                        self.ty(r.get_type())?
+                    ),
+                };
+                self.derive(exp, &rule, &[l, r])
+            }
+            Expression::TypeCast(_, value, ty) => {
+                // A cast which was written by the user covers `as <type>`, so a cast which
+                // has the same span as its operand was added by the type resolver
+                let rule = if value.span() == exp.span() {
+                    format!(
+                        "implicit coercion of {} to {}",
+                        self.ty(value.get_type())?,
+                        self.ty(ty)?
+                    )
+                } else {
+                    format!("cast of {} to {}", self.ty(value.get_type())?, self.ty(ty)?)
+                };
+                self.derive(exp, &rule, &[value])
+            }
+            Expression::UnaryOp(_, op, value) => {
+                let rule = match op {
+                    UnaryOperator::ResultOk => {
+                        "ok, which takes the error type that is expected where it is used".into()
+                    }
+                    UnaryOperator::ResultErr => {
+                        "err, which takes the value type that is expected where it is used".into()
+                    }
+                    UnaryOperator::Try => format!(
+                        "? applied to {}, which returns from the routine if there is no value",
+                        self.ty(value.get_type())?
+                    ),
+                    op => format!("{} applied to {}", op, self.ty(value.get_type())?),
+                };
+                self.derive(exp, &rule, &[value])
+            }
+            Expression::Yield(_, value) => {
+                let rule = format!("yield of {}", self.ty(value.get_type())?);
+                self.derive(exp, &rule, &[value])
+            }
+            Expression::MacroCall(..) => {
+                panic!("Macros must be expanded before types are resolved")
+            }
+        }
+    }
+}
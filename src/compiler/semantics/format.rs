@@ -0,0 +1,420 @@
+//! Compile time checking of calls to the C formatting routines (`printf`, `scanf`,
+//! and their relatives).  These routines are declared as varargs externs, so the
+//! type resolver cannot check the arguments which follow the format string.  When
+//! the format string is a string literal, the compiler parses it and checks that
+//! the number and types of the arguments match the conversion specifiers before
+//! the call is lowered to the varargs extern.
+
+use crate::{
+    compiler::{
+        ast::{
+            Expression, Item, Module, Node, PointerMut, RoutineCall, RoutineDef, Statement, Type,
+        },
+        source::SourceIr,
+        CompilerDisplay, CompilerDisplayError, CompilerError, SourceMap,
+    },
+    StringTable,
+};
+
+use super::semanticnode::SemanticContext;
+
+/// Errors found by checking the arguments of a call to a formatting routine
+/// against its format string.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FormatError {
+    /// The format string contains a conversion specifier which is malformed or
+    /// which cannot be checked.
+    InvalidSpecifier(String),
+
+    /// The number of arguments given does not match the number of arguments
+    /// required by the format string.
+    WrongNumberOfArgs(usize, usize),
+
+    /// An argument does not have the type required by its conversion specifier.
+    ArgTypeMismatch(String, &'static str, Type),
+}
+
+impl CompilerDisplay for FormatError {
+    fn fmt(&self, sm: &SourceMap, st: &StringTable) -> Result<String, CompilerDisplayError> {
+        match self {
+            FormatError::InvalidSpecifier(spec) => Ok(format!(
+                "Invalid conversion specifier {} in format string",
+                spec
+            )),
+            FormatError::WrongNumberOfArgs(expected, found) => Ok(format!(
+                "Format string expects {} arguments but {} were given",
+                expected, found
+            )),
+            FormatError::ArgTypeMismatch(spec, expected, found) => Ok(format!(
+                "{} expects {} but got {}",
+                spec,
+                expected,
+                found.fmt(sm, st)?
+            )),
+        }
+    }
+}
+
+/// Whether a formatting routine writes the values of its arguments or reads values
+/// into its arguments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Direction {
+    Write,
+    Read,
+}
+
+/// The formatting routines which are checked, the index of their format string
+/// parameter, and whether they write or read their arguments.
+const FORMAT_ROUTINES: &[(&str, usize, Direction)] = &[
+    ("printf", 0, Direction::Write),
+    ("fprintf", 1, Direction::Write),
+    ("sprintf", 1, Direction::Write),
+    ("snprintf", 2, Direction::Write),
+    ("scanf", 0, Direction::Read),
+    ("fscanf", 1, Direction::Read),
+    ("sscanf", 1, Direction::Read),
+];
+
+/// The kind of value which a single conversion specifier consumes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Arg {
+    /// An integer with the given bit width, signedness is not checked.
+    Int(u8),
+    /// A single character.
+    Char,
+    Float,
+    Str,
+    Pointer,
+}
+
+impl Arg {
+    /// Returns true if a value of type `ty` can be passed for this argument.
+    fn accepts(self, ty: &Type) -> bool {
+        match self {
+            Arg::Int(bits) => ty.is_integral() && ty.bit_width() == bits,
+            Arg::Char => ty.is_integral() && (ty.bit_width() == 8 || ty.bit_width() == 32),
+            Arg::Float => *ty == Type::F64,
+            Arg::Str => match ty {
+                Type::StringLiteral => true,
+                Type::RawPointer(_, target) => {
+                    matches!(target.as_ref(), Type::U8 | Type::I8)
+                }
+                _ => false,
+            },
+            Arg::Pointer => ty.is_raw_pointer(),
+        }
+    }
+
+    /// Returns true if a value can be read into an argument of type `ty`.
+    fn accepts_target(self, ty: &Type) -> bool {
+        match (self, ty) {
+            (Arg::Str, Type::RawPointer(PointerMut::Mut, target)) => {
+                matches!(target.as_ref(), Type::U8 | Type::I8)
+            }
+            (Arg::Char, Type::RawPointer(PointerMut::Mut, target)) => {
+                matches!(target.as_ref(), Type::U8 | Type::I8)
+            }
+            (_, Type::RawPointer(PointerMut::Mut, target)) => self.accepts(target),
+            _ => false,
+        }
+    }
+
+    /// A description of the type expected for this argument, used in error messages.
+    fn describe(self, dir: Direction) -> &'static str {
+        match (dir, self) {
+            (Direction::Write, Arg::Int(8)) => "i8 or u8",
+            (Direction::Write, Arg::Int(16)) => "i16 or u16",
+            (Direction::Write, Arg::Int(32)) => "i32 or u32",
+            (Direction::Write, Arg::Int(_)) => "i64 or u64",
+            (Direction::Write, Arg::Char) => "u8, i8, u32 or i32",
+            (Direction::Write, Arg::Float) => "f64",
+            (Direction::Write, Arg::Str) => "string or *const u8",
+            (Direction::Write, Arg::Pointer) => "a raw pointer",
+            (Direction::Read, Arg::Int(8)) => "*mut i8 or *mut u8",
+            (Direction::Read, Arg::Int(16)) => "*mut i16 or *mut u16",
+            (Direction::Read, Arg::Int(32)) => "*mut i32 or *mut u32",
+            (Direction::Read, Arg::Int(_)) => "*mut i64 or *mut u64",
+            (Direction::Read, Arg::Char) | (Direction::Read, Arg::Str) => "*mut u8",
+            (Direction::Read, Arg::Float) => "*mut f64",
+            (Direction::Read, Arg::Pointer) => "a *mut raw pointer",
+        }
+    }
+}
+
+/// Parses a C format string and returns the argument consumed by each conversion
+/// specifier, in order, along with the text of the specifier.  Width and precision
+/// given by `*` consume an `i32` argument.
+fn parse_format(fmt: &str, dir: Direction) -> Result<Vec<(String, Arg)>, FormatError> {
+    let mut args = vec![];
+    let mut chars = fmt.char_indices();
+
+    while let Some((start, c)) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+
+        let mut spec_args = vec![];
+        let mut suppress = false;
+        let mut next = chars.next();
+
+        // Flags (printf) or assignment suppression (scanf)
+        while let Some((_, c)) = next {
+            match (dir, c) {
+                (Direction::Write, '-' | '+' | ' ' | '#' | '0') => (),
+                (Direction::Read, '*') => suppress = true,
+                _ => break,
+            }
+            next = chars.next();
+        }
+
+        // Width
+        if let (Direction::Write, Some((_, '*'))) = (dir, next) {
+            spec_args.push(Arg::Int(32));
+            next = chars.next();
+        }
+        while let Some((_, '0'..='9')) = next {
+            next = chars.next();
+        }
+
+        // Precision
+        if let (Direction::Write, Some((_, '.'))) = (dir, next) {
+            next = chars.next();
+            if let Some((_, '*')) = next {
+                spec_args.push(Arg::Int(32));
+                next = chars.next();
+            }
+            while let Some((_, '0'..='9')) = next {
+                next = chars.next();
+            }
+        }
+
+        // Length modifier
+        let mut length = String::new();
+        while let Some((_, c @ ('h' | 'l' | 'j' | 'z' | 't' | 'L'))) = next {
+            length.push(c);
+            next = chars.next();
+        }
+
+        let (end, conv) = match next {
+            Some((idx, c)) => (idx + c.len_utf8(), c),
+            None => return Err(FormatError::InvalidSpecifier(fmt[start..].into())),
+        };
+        let spec = &fmt[start..end];
+
+        let arg = match (conv, length.as_str()) {
+            ('%', "") if spec == "%%" => continue,
+            ('d' | 'i' | 'u' | 'x' | 'X' | 'o', _) => match length.as_str() {
+                "hh" => Arg::Int(8),
+                "h" => Arg::Int(16),
+                "" => Arg::Int(32),
+                "l" | "ll" | "j" | "z" | "t" => Arg::Int(64),
+                _ => return Err(FormatError::InvalidSpecifier(spec.into())),
+            },
+            ('c', "") => Arg::Char,
+            ('s', "") => Arg::Str,
+            ('p', "") => Arg::Pointer,
+            // printf promotes floats to doubles, while scanf requires `l` to read a double
+            ('f' | 'F' | 'e' | 'E' | 'g' | 'G' | 'a' | 'A', "") if dir == Direction::Write => {
+                Arg::Float
+            }
+            ('f' | 'F' | 'e' | 'E' | 'g' | 'G' | 'a' | 'A', "l") => Arg::Float,
+            _ => return Err(FormatError::InvalidSpecifier(spec.into())),
+        };
+
+        if !suppress {
+            spec_args.push(arg);
+        }
+        args.extend(spec_args.into_iter().map(|a| (spec.to_string(), a)));
+    }
+
+    Ok(args)
+}
+
+/// Checks every call to a formatting routine in the given module whose format string
+/// is a string literal and returns the errors which were found.
+pub fn check_formats(
+    m: &Module<SemanticContext>,
+    st: &StringTable,
+) -> Vec<CompilerError<FormatError>> {
+    let mut checker = FormatChecker::new(st);
+    checker.module(m);
+    checker.errors
+}
+
+struct FormatChecker<'a> {
+    st: &'a StringTable,
+    errors: Vec<CompilerError<FormatError>>,
+}
+
+impl<'a> FormatChecker<'a> {
+    fn new(st: &'a StringTable) -> FormatChecker<'a> {
+        FormatChecker { st, errors: vec![] }
+    }
+
+    fn module(&mut self, m: &Module<SemanticContext>) {
+        for sm in m.get_modules() {
+            self.module(sm)
+        }
+
+        for item in m.get_functions().iter().chain(m.get_coroutines()) {
+            if let Item::Routine(r) = item {
+                self.routine(r)
+            }
+        }
+
+        for r in m.get_methods() {
+            self.routine(r)
+        }
+    }
+
+    fn routine(&mut self, r: &RoutineDef<SemanticContext>) {
+        for stm in r.get_body() {
+            self.statement(stm)
+        }
+    }
+
+    fn statement(&mut self, stm: &Statement<SemanticContext>) {
+        match stm {
+            Statement::Bind(b) => self.expression(b.get_rhs()),
+            Statement::Mutate(m) => {
+                self.expression(m.get_lhs());
+                self.expression(m.get_rhs())
+            }
+            Statement::YieldReturn(yr) => {
+                if let Some(v) = yr.get_value() {
+                    self.expression(v)
+                }
+            }
+            Statement::Return(r) => {
+                if let Some(v) = r.get_value() {
+                    self.expression(v)
+                }
+            }
+            Statement::Expression(e) => self.expression(e),
+            Statement::Discard(d) => self.expression(d.get_value()),
+            Statement::Defer(d) => self.expression(d.get_value()),
+            Statement::Break(_) | Statement::Continue(_) => (),
+        }
+    }
+
+    fn expression(&mut self, exp: &Expression<SemanticContext>) {
+        match exp {
+            Expression::ExpressionBlock(_, body, final_exp) => {
+                for stm in body {
+                    self.statement(stm)
+                }
+                if let Some(fe) = final_exp {
+                    self.expression(fe)
+                }
+            }
+            Expression::StructExpression(_, _, fields) => {
+                for (_, fe) in fields {
+                    self.expression(fe)
+                }
+            }
+            Expression::ArrayExpression(_, elements, _) => {
+                for e in elements {
+                    self.expression(e)
+                }
+            }
+            Expression::RoutineCall(_, call, path, args) => {
+                if *call == RoutineCall::Extern {
+                    self.check_call(exp, path.item(), args);
+                }
+                for a in args {
+                    self.expression(a)
+                }
+            }
+            Expression::ArrayAt { array, index, .. } => {
+                self.expression(array);
+                self.expression(index)
+            }
+            Expression::If {
+                cond,
+                if_arm,
+                else_arm,
+                ..
+            } => {
+                self.expression(cond);
+                self.expression(if_arm);
+                if let Some(ea) = else_arm {
+                    self.expression(ea)
+                }
+            }
+            Expression::While { cond, body, .. } => {
+                self.expression(cond);
+                self.expression(body)
+            }
+            Expression::BinaryOp(_, _, l, r) => {
+                self.expression(l);
+                self.expression(r)
+            }
+            Expression::MemberAccess(_, e, _)
+            | Expression::TypeCast(_, e, _)
+            | Expression::UnaryOp(_, _, e)
+            | Expression::Yield(_, e) => self.expression(e),
+            _ => (),
+        }
+    }
+
+    /// If `routine` is a formatting routine and its format string is a literal, then
+    /// checks the remaining arguments against the format string.
+    fn check_call(
+        &mut self,
+        call: &Expression<SemanticContext>,
+        routine: Option<crate::StringId>,
+        args: &[Expression<SemanticContext>],
+    ) {
+        let name = match routine.and_then(|r| self.st.get(r).ok()) {
+            Some(name) => name,
+            None => return,
+        };
+
+        let (fmt_idx, dir) = match FORMAT_ROUTINES.iter().find(|(n, ..)| *n == name) {
+            Some((_, idx, dir)) => (*idx, *dir),
+            None => return,
+        };
+
+        let fmt_arg = match args.get(fmt_idx) {
+            Some(Expression::StringLiteral(_, s)) => s,
+            _ => return,
+        };
+        let fmt = match self.st.get(*fmt_arg) {
+            Ok(fmt) => fmt,
+            Err(_) => return,
+        };
+
+        let specs = match parse_format(&fmt, dir) {
+            Ok(specs) => specs,
+            Err(err) => {
+                self.errors
+                    .push(CompilerError::new(args[fmt_idx].span(), err));
+                return;
+            }
+        };
+
+        let values = &args[fmt_idx + 1..];
+        if specs.len() != values.len() {
+            self.errors.push(CompilerError::new(
+                call.span(),
+                FormatError::WrongNumberOfArgs(specs.len(), values.len()),
+            ));
+            return;
+        }
+
+        for ((spec, arg), value) in specs.into_iter().zip(values) {
+            let ty = value.context().ty();
+            let accepted = match dir {
+                Direction::Write => arg.accepts(ty),
+                Direction::Read => arg.accepts_target(ty),
+            };
+
+            if !accepted {
+                self.errors.push(CompilerError::new(
+                    value.span(),
+                    FormatError::ArgTypeMismatch(spec, arg.describe(dir), ty.clone()),
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,336 @@
+//! Lints check for code which is valid but which follows patterns that commonly
+//! hide bugs. Unlike [`SemanticError`](super::error::SemanticError)s, a lint does
+//! not stop compilation: lints are reported to the user as warnings.
+
+use crate::{
+    compiler::{
+        ast::{BinaryOperator, Expression, Item, Module, Node, Path, RoutineDef, Statement, Type},
+        lexer::tokens::{Lex, Token},
+        source::SourceIr,
+        CompilerDisplay, CompilerDisplayError, CompilerError, SourceMap, Span,
+    },
+    StringTable,
+};
+
+use super::{complexity::Metric, semanticnode::SemanticContext};
+
+/// Warnings which are found by linting a semantically valid AST.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Lint {
+    /// An operand of a comparison was cast from a signed integer type to an
+    /// unsigned integer type, or vice versa.  Such a cast changes how the sign bit
+    /// is interpreted, so negative values will compare as very large values (and
+    /// large values as negative ones).
+    SignChangingCastInComparison(BinaryOperator, Type, Type),
+
+    /// An expression statement produced a value which was silently thrown away.  If
+    /// this is intended, then the value should be explicitly discarded with `_ := exp;`.
+    IgnoredValue(Type),
+
+    /// An `||` expression has an operand which is an `&&` expression that is not
+    /// wrapped in parentheses.  `&&` has a higher precedence than `||`, but relying on
+    /// that makes it easy to misread the condition.  Stores the span of the `||`
+    /// expression and the spans of its `&&` operands.
+    MixedLogicalOperators(Span, Vec<Span>),
+
+    /// A routine exceeds one of the limits on its size.  Stores the path of the
+    /// routine, the metric which was exceeded, the routine's value for that metric,
+    /// and the limit.
+    TooComplex(Path, Metric, usize, usize),
+
+    /// The last item of a list is followed by a comma.  Trailing commas are valid in
+    /// every list, so this is only reported when a project asks for it as a style rule.
+    TrailingComma,
+
+    /// A value is assigned with the old `mut <exp> := <value>` syntax rather than with
+    /// `<exp> = <value>`.  Both are valid, this is reported to help migrate code to the
+    /// new syntax when a project asks for it.
+    MutAssignment,
+}
+
+impl CompilerDisplay for Lint {
+    fn fmt(&self, sm: &SourceMap, st: &StringTable) -> Result<String, CompilerDisplayError> {
+        match self {
+            Lint::SignChangingCastInComparison(op, from, to) => Ok(format!(
+                "{} compares a value cast from {} to {}, which changes how its sign is interpreted",
+                op,
+                from.fmt(sm, st)?,
+                to.fmt(sm, st)?
+            )),
+            Lint::IgnoredValue(ty) => Ok(format!(
+                "Value of type {} is ignored, use `_ := ...;` to discard it explicitly",
+                ty.fmt(sm, st)?
+            )),
+            Lint::MixedLogicalOperators(exp, ands) => Ok(format!(
+                "&& and || are mixed without parentheses, add parentheses to make the order explicit: {}",
+                parenthesize(sm, *exp, ands)?
+            )),
+            Lint::TooComplex(path, metric, value, limit) => Ok(format!(
+                "{} has {} {}, which exceeds the limit of {}",
+                path.fmt(sm, st)?,
+                value,
+                metric,
+                limit
+            )),
+            Lint::TrailingComma => Ok("Trailing comma at the end of a list".into()),
+            Lint::MutAssignment => {
+                Ok("`mut <exp> := <value>` is the old assignment syntax, use `<exp> = <value>`".into())
+            }
+        }
+    }
+}
+
+/// Returns the source code of `exp` with each of the spans in `inner` wrapped in
+/// parentheses.
+fn parenthesize(sm: &SourceMap, exp: Span, inner: &[Span]) -> Result<String, CompilerDisplayError> {
+    let mut text = sm.text_in_span(exp)?;
+
+    // Insert from the end so that the offsets of earlier spans are not moved
+    let mut inner = inner.to_vec();
+    inner.sort_by_key(|s| std::cmp::Reverse(s.low()));
+    for span in inner {
+        let low = (span.low().as_u32() - exp.low().as_u32()) as usize;
+        let high = (span.high().as_u32() - exp.low().as_u32()) as usize;
+        text.insert(high, ')');
+        text.insert(low, '(');
+    }
+
+    Ok(text)
+}
+
+/// Runs every lint over the given module and returns the warnings which were found.
+pub fn lint(m: &Module<SemanticContext>) -> Vec<CompilerError<Lint>> {
+    let mut linter = Linter::new();
+    linter.module(m);
+    linter.lints
+}
+
+/// Finds every comma which is directly followed by the closing delimiter of a list.
+/// This works on tokens rather than the AST because the AST does not record where
+/// the separators of a list were.
+pub fn lint_trailing_commas(tokens: &[Token]) -> Vec<CompilerError<Lint>> {
+    tokens
+        .windows(2)
+        .filter(|pair| {
+            pair[0].sym == Lex::Comma
+                && matches!(pair[1].sym, Lex::RParen | Lex::RBrace | Lex::RBracket)
+        })
+        .map(|pair| CompilerError::new(pair[0].span, Lint::TrailingComma))
+        .collect()
+}
+
+/// Finds every assignment which uses the `mut <exp> := <value>` syntax.  Every other
+/// use of `mut` follows `let` or is part of a mutable pointer (`*mut` or `@mut`).
+pub fn lint_mut_assignments(tokens: &[Token]) -> Vec<CompilerError<Lint>> {
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(idx, token)| {
+            token.sym == Lex::Mut
+                && !(*idx > 0 && matches!(tokens[idx - 1].sym, Lex::Let | Lex::Mul | Lex::At))
+        })
+        .map(|(_, token)| CompilerError::new(token.span, Lint::MutAssignment))
+        .collect()
+}
+
+struct Linter {
+    lints: Vec<CompilerError<Lint>>,
+}
+
+impl Linter {
+    fn new() -> Linter {
+        Linter { lints: vec![] }
+    }
+
+    fn module(&mut self, m: &Module<SemanticContext>) {
+        for sm in m.get_modules() {
+            self.module(sm)
+        }
+
+        for item in m.get_functions().iter().chain(m.get_coroutines()) {
+            if let Item::Routine(r) = item {
+                self.routine(r)
+            }
+        }
+
+        for r in m.get_methods() {
+            self.routine(r)
+        }
+    }
+
+    fn routine(&mut self, r: &RoutineDef<SemanticContext>) {
+        for stm in r.get_body() {
+            self.statement(stm)
+        }
+    }
+
+    fn statement(&mut self, stm: &Statement<SemanticContext>) {
+        match stm {
+            Statement::Bind(b) => self.expression(b.get_rhs()),
+            Statement::Mutate(m) => {
+                self.expression(m.get_lhs());
+                self.expression(m.get_rhs())
+            }
+            Statement::YieldReturn(yr) => {
+                if let Some(v) = yr.get_value() {
+                    self.expression(v)
+                }
+            }
+            Statement::Return(r) => {
+                if let Some(v) = r.get_value() {
+                    self.expression(v)
+                }
+            }
+            Statement::Expression(e) => {
+                self.check_ignored_value(e);
+                self.expression(e)
+            }
+            Statement::Discard(d) => self.expression(d.get_value()),
+            Statement::Defer(d) => self.expression(d.get_value()),
+            Statement::Break(_) | Statement::Continue(_) => (),
+        }
+    }
+
+    fn expression(&mut self, exp: &Expression<SemanticContext>) {
+        self.check_expression(exp);
+
+        match exp {
+            Expression::ExpressionBlock(_, body, final_exp) => {
+                for stm in body {
+                    self.statement(stm)
+                }
+                if let Some(fe) = final_exp {
+                    self.expression(fe)
+                }
+            }
+            Expression::StructExpression(_, _, fields) => {
+                for (_, fe) in fields {
+                    self.expression(fe)
+                }
+            }
+            Expression::ArrayExpression(_, elements, _) => {
+                for e in elements {
+                    self.expression(e)
+                }
+            }
+            Expression::RoutineCall(_, _, _, args) => {
+                for a in args {
+                    self.expression(a)
+                }
+            }
+            Expression::ArrayAt { array, index, .. } => {
+                self.expression(array);
+                self.expression(index)
+            }
+            Expression::If {
+                cond,
+                if_arm,
+                else_arm,
+                ..
+            } => {
+                self.expression(cond);
+                self.expression(if_arm);
+                if let Some(ea) = else_arm {
+                    self.expression(ea)
+                }
+            }
+            Expression::While { cond, body, .. } => {
+                self.expression(cond);
+                self.expression(body)
+            }
+            Expression::BinaryOp(_, _, l, r) => {
+                self.expression(l);
+                self.expression(r)
+            }
+            Expression::MemberAccess(_, e, _)
+            | Expression::TypeCast(_, e, _)
+            | Expression::UnaryOp(_, _, e)
+            | Expression::Yield(_, e) => self.expression(e),
+            _ => (),
+        }
+    }
+
+    /// Applies each of the expression lints to the given expression.
+    fn check_expression(&mut self, exp: &Expression<SemanticContext>) {
+        if let Expression::BinaryOp(_, op, l, r) = exp {
+            self.check_sign_changing_comparison(*op, l);
+            self.check_sign_changing_comparison(*op, r);
+
+            if *op == BinaryOperator::BOr {
+                self.check_mixed_logical_operators(exp, l, r);
+            }
+        }
+    }
+
+    /// Checks whether either operand of an `||` expression is an `&&` expression which
+    /// is not wrapped in parentheses.
+    fn check_mixed_logical_operators(
+        &mut self,
+        exp: &Expression<SemanticContext>,
+        l: &Expression<SemanticContext>,
+        r: &Expression<SemanticContext>,
+    ) {
+        let ands: Vec<_> = [l, r]
+            .iter()
+            .filter(|operand| is_unparenthesized_and(operand))
+            .map(|operand| operand.span())
+            .collect();
+
+        if !ands.is_empty() {
+            self.lints.push(CompilerError::new(
+                exp.span(),
+                Lint::MixedLogicalOperators(exp.span(), ands),
+            ))
+        }
+    }
+
+    /// Checks whether an expression statement produces a value which is not used.
+    fn check_ignored_value(&mut self, exp: &Expression<SemanticContext>) {
+        let ty = exp.context().ty();
+        if *ty != Type::Unit && *ty != Type::Never {
+            self.lints.push(CompilerError::new(
+                exp.span(),
+                Lint::IgnoredValue(ty.clone()),
+            ))
+        }
+    }
+
+    /// Checks whether an operand of a comparison is a cast which changes the signedness
+    /// of an integer.
+    fn check_sign_changing_comparison(
+        &mut self,
+        op: BinaryOperator,
+        operand: &Expression<SemanticContext>,
+    ) {
+        use BinaryOperator::*;
+
+        if !matches!(op, Eq | NEq | Ls | LsEq | Gr | GrEq) {
+            return;
+        }
+
+        if let Expression::TypeCast(_, value, target) = operand {
+            let from = value.context().ty();
+            let changes_sign = (from.is_signed_int() && target.is_unsigned_int())
+                || (from.is_unsigned_int() && target.is_signed_int());
+
+            if changes_sign {
+                self.lints.push(CompilerError::new(
+                    operand.span(),
+                    Lint::SignChangingCastInComparison(op, from.clone(), (**target).clone()),
+                ))
+            }
+        }
+    }
+}
+
+/// Returns true if `exp` is an `&&` expression which is not wrapped in parentheses.
+/// The parser extends the span of a parenthesized expression to cover the parentheses,
+/// so an `&&` without parentheses has exactly the span of its two operands.
+fn is_unparenthesized_and(exp: &Expression<SemanticContext>) -> bool {
+    match exp {
+        Expression::BinaryOp(_, BinaryOperator::BAnd, l, r) => {
+            exp.span() == Span::cover(l.span(), r.span())
+        }
+        _ => false,
+    }
+}
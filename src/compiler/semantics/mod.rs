@@ -17,15 +17,26 @@ use super::{
  * with SemanticAnnotations (including the type of each node and the symbol tables).
  */
 mod canonize;
+mod const_eval;
 mod error;
 mod stack;
+mod static_assert;
 mod tests;
 
+pub mod complexity;
+pub mod dead_branch;
+pub mod explain;
+pub mod format;
+pub mod lint;
+pub mod query;
+pub mod reflect;
 pub mod semanticnode;
 pub mod symbol_table;
 pub mod type_resolver;
+pub mod typed;
 
-use error::SemanticError;
+use error::RoutineSignature;
+pub use error::SemanticError;
 
 /// Captures the Failure state of any Semantic Analysis operation.
 /// Which will, if it fails, result in a [`SemanticError`] wrapped
@@ -88,9 +99,12 @@ impl Writable for Type {
             Type::I16 => w.write_text("i16"),
             Type::I32 => w.write_text("i32"),
             Type::I64 => w.write_text("i64"),
+            Type::USize => w.write_text("usize"),
+            Type::ISize => w.write_text("isize"),
             Type::F64 => w.write_text("f64"),
             Type::Bool => w.write_text("bool"),
             Type::StringLiteral => w.write_text("string"),
+            Type::String => w.write_text("String"),
             Type::RawPointer(is_mut, ty) => {
                 if *is_mut == PointerMut::Mut {
                     w.write_text("*mut ")
@@ -104,7 +118,28 @@ impl Writable for Type {
                 w.write(ty.as_ref());
                 w.write_text(&format!("; {}]", sz));
             }
+            Type::Slice(ty) => {
+                w.write_text("[");
+                w.write(ty.as_ref());
+                w.write_text("]");
+            }
+            Type::Range(ty) => {
+                w.write_text("Range<");
+                w.write(ty.as_ref());
+                w.write_text(">");
+            }
+            Type::Option(ty) => {
+                w.write_text("opt ");
+                w.write(ty.as_ref());
+            }
+            Type::Result(ty, err_ty) => {
+                w.write_text("res ");
+                w.write(ty.as_ref());
+                w.write_text(", ");
+                w.write(err_ty.as_ref());
+            }
             Type::Unit => w.write_text("Unit"),
+            Type::Never => w.write_text("Never"),
             Type::Custom(p) => w.write_path(p),
             Type::StructDef(_) => w.write_text("Struct Def"),
             Type::FunctionDef(_, _) => w.write_text("Function Def"),
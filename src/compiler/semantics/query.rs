@@ -0,0 +1,230 @@
+//! Memoized queries over the semantic information of a project.
+//!
+//! [`resolve_types`](super::type_resolver::resolve_types) checks an entire module
+//! tree every time it is called.  [`SemanticQueries`] instead resolves each item
+//! only when it is asked for and caches the result, keyed by the canonical path of
+//! the item.  When a new version of the source is given to
+//! [`SemanticQueries::update`], only the cached items which are affected by the
+//! edit are invalidated, so that a long running process (such as an editor
+//! integration) can re-check just the items which changed.
+
+use std::collections::HashMap;
+
+use crate::compiler::{
+    ast::{Element, Item, Module, Node, Path, Type},
+    diagnostics::Logger,
+    import::Import,
+    parser::ParserContext,
+    source::SourceIr,
+    Span,
+};
+use crate::{StringId, StringTable};
+
+use super::{
+    semanticnode::SemanticContext,
+    symbol_table::SymbolTable,
+    type_resolver::{prepare_module, TypeResolver},
+    SemanticResult,
+};
+
+/// The parts of a symbol which other items can depend upon.  The span of the symbol
+/// is left out, so that moving a definition does not change its signature.
+type Signature = (Path, StringId, Type, bool, bool);
+
+/// Caches the results of semantic queries on a module tree.
+pub struct SemanticQueries<'a> {
+    main_mod: StringId,
+    main_fn: StringId,
+    imports: &'a [Import],
+    st: &'a StringTable,
+    logger: &'a Logger<'a>,
+
+    /// The current version of the parsed input. This is used to determine which
+    /// items have changed when the input is updated.
+    parsed: Module<ParserContext>,
+
+    /// The current input with paths canonized and the item definitions of every
+    /// module added to its symbol table.
+    root: Module<SemanticContext>,
+
+    /// The memoized result of [`SemanticQueries::type_of`] for each item.
+    items: HashMap<Path, SemanticResult<Item<SemanticContext>>>,
+}
+
+impl<'a> SemanticQueries<'a> {
+    pub fn new(
+        ast: &Module<ParserContext>,
+        main_mod: StringId,
+        main_fn: StringId,
+        imports: &'a [Import],
+        st: &'a StringTable,
+        logger: &'a Logger<'a>,
+    ) -> SemanticResult<SemanticQueries<'a>> {
+        let root = prepare_module(ast, imports, logger)?;
+
+        Ok(SemanticQueries {
+            main_mod,
+            main_fn,
+            imports,
+            st,
+            logger,
+            parsed: ast.clone(),
+            root,
+            items: HashMap::new(),
+        })
+    }
+
+    /// Replaces the input with a new version of the parsed module and invalidates
+    /// every cached item which may be affected by the change. Returns the paths of
+    /// the items which were invalidated.
+    ///
+    /// If the signature of any item has changed then every cached item is invalidated,
+    /// because any item could depend upon it.  Otherwise, only the items whose own
+    /// definitions have changed are invalidated.
+    pub fn update(&mut self, ast: &Module<ParserContext>) -> SemanticResult<Vec<Path>> {
+        let root = prepare_module(ast, self.imports, self.logger)?;
+
+        let invalidated: Vec<Path> = if signatures(&root) != signatures(&self.root) {
+            self.items.keys().cloned().collect()
+        } else {
+            self.items
+                .keys()
+                .filter(|path| find_item(&self.parsed, path) != find_item(ast, path))
+                .cloned()
+                .collect()
+        };
+
+        for path in &invalidated {
+            self.items.remove(path);
+        }
+
+        self.parsed = ast.clone();
+        self.root = root;
+
+        Ok(invalidated)
+    }
+
+    /// Returns the symbol table of the module with the given canonical path.
+    pub fn symbol_table_of(&self, module: &Path) -> Option<&SymbolTable> {
+        self.root.go_to_module(module).map(|m| m.context().sym())
+    }
+
+    /// Returns the type resolved version of the item with the given canonical path.
+    /// The item is only resolved if it has not been resolved since it was last
+    /// invalidated.
+    pub fn type_of(&mut self, item: &Path) -> Option<&SemanticResult<Item<SemanticContext>>> {
+        if !self.items.contains_key(item) {
+            let mut resolver = TypeResolver::new(
+                &self.root,
+                self.imports,
+                self.main_mod,
+                self.main_fn,
+                self.st,
+                self.logger,
+            );
+            let result = resolver.resolve_item(item)?;
+            self.items.insert(item.clone(), result);
+        }
+
+        self.items.get(item)
+    }
+
+    /// Returns true if the result for the given item is currently cached.
+    pub fn is_cached(&self, item: &Path) -> bool {
+        self.items.contains_key(item)
+    }
+
+    /// Returns the canonical path of the item whose definition covers `span`.  The
+    /// methods of a structure belong to the structure.
+    pub fn item_at(&self, span: Span) -> Option<Path> {
+        item_at(&self.root, span)
+    }
+
+    /// Returns the canonical path of every item in the input.
+    pub fn item_paths(&self) -> Vec<Path> {
+        let mut paths = vec![];
+        item_paths(&self.root, &mut paths);
+        paths
+    }
+}
+
+fn item_paths(m: &Module<SemanticContext>, paths: &mut Vec<Path>) {
+    for sm in m.get_modules() {
+        item_paths(sm, paths)
+    }
+
+    for item in m
+        .get_functions()
+        .iter()
+        .chain(m.get_coroutines())
+        .chain(m.get_structs())
+        .chain(m.get_externs())
+    {
+        paths.push(item.context().canonical_path().clone())
+    }
+}
+
+fn item_at(m: &Module<SemanticContext>, span: Span) -> Option<Path> {
+    let covers = |outer: Span| outer.low() <= span.low() && span.high() <= outer.high();
+
+    m.get_modules()
+        .iter()
+        .find_map(|sm| item_at(sm, span))
+        .or_else(|| {
+            m.get_functions()
+                .iter()
+                .chain(m.get_coroutines())
+                .chain(m.get_externs())
+                .find(|item| covers(item.span()))
+                .or_else(|| {
+                    m.get_structs().iter().find(|item| match item {
+                        Item::Struct(sd) => {
+                            covers(item.span()) || sd.get_methods().iter().any(|r| covers(r.span()))
+                        }
+                        _ => false,
+                    })
+                })
+                .map(|item| item.context().canonical_path().clone())
+        })
+}
+
+/// Finds the item with the given canonical path in a parsed module.
+fn find_item<'m>(m: &'m Module<ParserContext>, path: &Path) -> Option<&'m Item<ParserContext>> {
+    let mut current = m;
+    if path.first() != Some(&Element::Id(m.get_name())) {
+        return None;
+    }
+
+    for el in path.parent().iter().skip(1) {
+        match el {
+            Element::Id(id) => current = current.get_module(*id)?,
+            _ => return None,
+        }
+    }
+
+    current.get_item(path.item()?)
+}
+
+/// Collects the signature of every symbol defined in every module of the tree.
+fn signatures(m: &Module<SemanticContext>) -> Vec<Signature> {
+    let mut sigs = vec![];
+    collect_signatures(m, &mut sigs);
+    sigs
+}
+
+fn collect_signatures(m: &Module<SemanticContext>, sigs: &mut Vec<Signature>) {
+    let path = m.context().canonical_path();
+    for s in m.context().sym().table() {
+        sigs.push((
+            path.clone(),
+            s.name,
+            s.ty.clone(),
+            s.is_mutable,
+            s.is_extern,
+        ))
+    }
+
+    for sm in m.get_modules() {
+        collect_signatures(sm, sigs)
+    }
+}
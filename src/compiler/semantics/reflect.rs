@@ -0,0 +1,172 @@
+//! Evaluates the reflection builtins at compile time.
+//!
+//! `fields_of(T)` is replaced with an array which has a `[name, type]` pair of strings
+//! for each field of the structure `T`, in the order that the fields are defined, and
+//! `name_of(T)` is replaced with a string which is the name of the type `T`.  Types
+//! are written with their canonical paths (e.g. `$main::inner::S`).  This lets helpers
+//! such as serializers and debug printers be written in Bramble without a macro
+//! system.
+//!
+//! Type resolution checks each builtin and replaces `fields_of(T)` with an array
+//! whose field types are each still a `name_of`, because types can only be written as
+//! strings once the [`StringTable`] is available.  This pass replaces every remaining
+//! `name_of` and must run before code is generated.
+
+use crate::{
+    compiler::{
+        ast::{Expression, Item, Module, Statement},
+        CompilerDisplay, CompilerDisplayError, SourceMap,
+    },
+    StringTable,
+};
+
+use super::semanticnode::SemanticContext;
+
+/// Replaces every use of `name_of(T)` in the given module, and its submodules, with
+/// the string that it evaluates to.
+pub fn eval_reflection(
+    m: &mut Module<SemanticContext>,
+    sm: &SourceMap,
+    st: &StringTable,
+) -> Result<(), CompilerDisplayError> {
+    let eval = Reflection { sm, st };
+
+    for child in m.get_modules_mut() {
+        eval_reflection(child, sm, st)?;
+    }
+
+    eval.items(m.get_functions_mut())?;
+    eval.items(m.get_coroutines_mut())?;
+    for routine in m.get_methods_mut() {
+        for stm in routine.get_body_mut() {
+            eval.statement(stm)?;
+        }
+    }
+    Ok(())
+}
+
+struct Reflection<'a> {
+    sm: &'a SourceMap,
+    st: &'a StringTable,
+}
+
+impl<'a> Reflection<'a> {
+    fn items(&self, items: &mut [Item<SemanticContext>]) -> Result<(), CompilerDisplayError> {
+        for item in items {
+            if let Item::Routine(routine) = item {
+                for stm in routine.get_body_mut() {
+                    self.statement(stm)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn statement(&self, stm: &mut Statement<SemanticContext>) -> Result<(), CompilerDisplayError> {
+        match stm {
+            Statement::Bind(bind) => self.expression(bind.get_rhs_mut()),
+            Statement::Mutate(mutate) => self.expression(mutate.get_rhs_mut()),
+            Statement::Discard(discard) => self.expression(discard.get_value_mut()),
+            Statement::Defer(defer) => self.expression(defer.get_value_mut()),
+            Statement::Break(_) | Statement::Continue(_) => Ok(()),
+            Statement::Expression(exp) => self.expression(exp),
+            Statement::YieldReturn(yr) => match yr.get_value_mut() {
+                Some(value) => self.expression(value),
+                None => Ok(()),
+            },
+            Statement::Return(ret) => match ret.get_value_mut() {
+                Some(value) => self.expression(value),
+                None => Ok(()),
+            },
+        }
+    }
+
+    fn expression(
+        &self,
+        exp: &mut Expression<SemanticContext>,
+    ) -> Result<(), CompilerDisplayError> {
+        match exp {
+            Expression::NameOf(ctx, ty) => {
+                let name = self.st.insert(ty.fmt(self.sm, self.st)?);
+                *exp = Expression::StringLiteral(ctx.clone(), name);
+                Ok(())
+            }
+            Expression::ArrayExpression(_, elements, _) => {
+                for el in elements {
+                    self.expression(el)?
+                }
+                Ok(())
+            }
+            Expression::ArrayAt { array, index, .. } => {
+                self.expression(array)?;
+                self.expression(index)
+            }
+            Expression::MemberAccess(_, src, _)
+            | Expression::TypeCast(_, src, _)
+            | Expression::UnaryOp(_, _, src)
+            | Expression::Yield(_, src) => self.expression(src),
+            Expression::RoutineCall(_, _, _, args) => {
+                for arg in args {
+                    self.expression(arg)?
+                }
+                Ok(())
+            }
+            Expression::StructExpression(_, _, fields) => {
+                for (_, value) in fields {
+                    self.expression(value)?
+                }
+                Ok(())
+            }
+            Expression::If {
+                cond,
+                if_arm,
+                else_arm,
+                ..
+            } => {
+                self.expression(cond)?;
+                self.expression(if_arm)?;
+                match else_arm {
+                    Some(else_arm) => self.expression(else_arm),
+                    None => Ok(()),
+                }
+            }
+            Expression::While { cond, body, .. } => {
+                self.expression(cond)?;
+                self.expression(body)
+            }
+            Expression::ExpressionBlock(_, body, final_exp) => {
+                for stm in body {
+                    self.statement(stm)?
+                }
+                match final_exp {
+                    Some(final_exp) => self.expression(final_exp),
+                    None => Ok(()),
+                }
+            }
+            Expression::BinaryOp(_, _, l, r) => {
+                self.expression(l)?;
+                self.expression(r)
+            }
+            Expression::Null(_)
+            | Expression::OptionNone(_)
+            | Expression::U8(..)
+            | Expression::U16(..)
+            | Expression::U32(..)
+            | Expression::U64(..)
+            | Expression::I8(..)
+            | Expression::I16(..)
+            | Expression::I32(..)
+            | Expression::I64(..)
+            | Expression::F64(..)
+            | Expression::Boolean(..)
+            | Expression::StringLiteral(..)
+            | Expression::SizeOf(..)
+            | Expression::FieldsOf(..)
+            | Expression::MacroCall(..)
+            | Expression::CustomType(..)
+            | Expression::Identifier(..)
+            | Expression::Path(..)
+            | Expression::IdentifierDeclare(..) => Ok(()),
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use crate::compiler::{ast::*, parser::ParserContext, Span};
 use crate::StringId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 use super::{error::SemanticError, symbol_table::SymbolTable};
 
@@ -8,7 +10,7 @@ use super::{error::SemanticError, symbol_table::SymbolTable};
 /// not a value at all. This property is essential for determing
 /// if an expression resolves to a place which can be mutated or
 /// referenced via addresses
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Addressability {
     None,
     Value,
@@ -19,7 +21,7 @@ pub enum Addressability {
 /// Contextual data that is derived during the semantic analysis process
 /// This includes the resolved [`Type`] of a node or expression from the user
 /// input and the scoped symbol tables
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SemanticContext {
     /// Unique id of a node in the Semantic AST
     id: u32,
@@ -89,6 +91,17 @@ impl SemanticContext {
         }
     }
 
+    pub fn new_struct(id: u32, ctx: ParserContext, name: StringId) -> SemanticContext {
+        SemanticContext {
+            id,
+            span: ctx.span(),
+            ty: Type::Unknown,
+            addressability: Addressability::None,
+            sym: SymbolTable::new_struct(name),
+            canonical_path: Path::new(),
+        }
+    }
+
     pub fn new_module(id: u32, ctx: ParserContext, name: StringId) -> SemanticContext {
         SemanticContext {
             id,
@@ -108,6 +121,21 @@ impl SemanticContext {
         sm
     }
 
+    /// Creates a copy of this instance of [`SemanticContext`] with the given node ID.
+    pub fn with_id(&self, id: u32) -> SemanticContext {
+        let mut sm = self.clone();
+        sm.id = id;
+        sm
+    }
+
+    /// Creates a copy of this instance of [`SemanticContext`] for a node which
+    /// resolves to a value, with no location in memory, of the type `ty`
+    pub fn with_value_type(&self, ty: Type) -> SemanticContext {
+        let mut sm = self.with_type(ty);
+        sm.addressability = Addressability::Value;
+        sm
+    }
+
     /// Updates this context to mark the node as Addressable.  If `is_mut` is
     /// `true` then this node will also be marked as mutable (which is a subset
     /// of Addressable).
@@ -181,55 +209,58 @@ impl SemanticContext {
     }
 }
 
-pub struct SemanticAst {
-    next_id: u32,
-}
+/// Converts a parsed AST into a semantic AST.  Each semantic node keeps the ID of
+/// the parser node that it was created from, so that a node can be referred to by
+/// the same ID before and after semantic analysis.
+pub struct SemanticAst {}
 
 impl SemanticAst {
     pub fn new() -> SemanticAst {
-        SemanticAst { next_id: 0 }
+        SemanticAst {}
     }
 
     pub fn from_module(&mut self, m: &Module<ParserContext>) -> Module<SemanticContext> {
         let f = |n: &dyn Node<ParserContext>| match n.node_type() {
             NodeType::Module => {
                 let name = n.name().expect("Modules must have a name");
-                self.module_semantic_context_from(*n.context(), name)
+                SemanticContext::new_module(n.context().id(), *n.context(), name)
             }
             NodeType::RoutineDef(_) => {
                 let name = n.name().expect("RoutineDefs must have a name");
-                self.routine_semantic_context_from(*n.context(), name)
+                SemanticContext::new_routine(n.context().id(), *n.context(), name, Type::Unknown)
+            }
+            NodeType::StructDef => {
+                let name = n.name().expect("StructDefs must have a name");
+                SemanticContext::new_struct(n.context().id(), *n.context(), name)
             }
-            _ => self.semantic_context_from(*n.context()),
+            _ => SemanticContext::new_local(n.context().id(), *n.context(), Type::Unknown),
         };
 
         let mut mapper = MapPreOrder::new("parser-to-semantic", f);
         mapper.apply(m)
     }
+}
 
-    fn semantic_context_from(&mut self, ctx: ParserContext) -> SemanticContext {
-        let sm_data = SemanticContext::new_local(self.next_id, ctx, Type::Unknown);
-        self.next_id += 1;
-        sm_data
-    }
-
-    fn routine_semantic_context_from(
-        &mut self,
-        ctx: ParserContext,
-        name: StringId,
-    ) -> SemanticContext {
-        let sm_data = SemanticContext::new_routine(self.next_id, ctx, name, Type::Unknown);
-        self.next_id += 1;
-        sm_data
-    }
-
-    fn module_semantic_context_from(
-        &mut self,
-        ctx: ParserContext,
-        name: StringId,
-    ) -> SemanticContext {
-        let sm_data = SemanticContext::new_module(self.next_id, ctx, name);
-        self.next_id += 1;
-        sm_data
-    }
+/// Gives a fresh ID to every node which shares its ID with a node visited before it.
+/// Lowering, such as of the iterator protocol in `for` loops, creates new nodes by
+/// copying the context, or entire subtrees, of the node that it replaces.  The first
+/// node with an ID keeps it, so every node from the source code has the same ID that it
+/// was given by the parser, and the copies are numbered, in the order that
+/// [`MapPreOrder`] visits them, after the largest ID in the module.
+pub fn number_lowered_nodes(m: &Module<SemanticContext>) -> Module<SemanticContext> {
+    let mut next_id = m
+        .iter_preorder()
+        .map(|n| n.context().id())
+        .max()
+        .unwrap_or(0);
+    let mut seen = HashSet::new();
+    let mut mapper = MapPreOrder::new("number-lowered-nodes", |n: &dyn Node<SemanticContext>| {
+        if seen.insert(n.context().id()) {
+            n.context().clone()
+        } else {
+            next_id += 1;
+            n.context().with_id(next_id)
+        }
+    });
+    mapper.apply(m)
 }
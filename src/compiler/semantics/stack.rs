@@ -1,9 +1,7 @@
-use std::collections::HashMap;
-
 use log::*;
 
 use crate::compiler::{
-    ast::{Element, Module, Node, Path, Type},
+    ast::{Element, Item, Module, Node, Path, PathMap, PathTable, StructDef, Type},
     import::{Import, ImportStructDef},
     Span,
 };
@@ -21,7 +19,21 @@ pub struct SymbolTableScopeStack {
 
     stack: Vec<SymbolTable>,
     head: Option<SymbolTable>,
-    imported_symbols: HashMap<String, Symbol>, // TODO: change this to a SymbolTable?
+
+    /// Every item imported from another artifact, keyed by the canonical path of the item
+    imported: PathMap<ImportedSymbol>,
+    paths: PathTable,
+}
+
+/// An item which is imported from another artifact.  Imported items are not part of
+/// the module tree being compiled, so the symbol is stored along with anything else
+/// which would otherwise be read from the item's definition.
+#[derive(Clone, Debug, PartialEq)]
+struct ImportedSymbol {
+    symbol: Symbol,
+
+    /// The names of the parameters, if the item is a routine
+    param_names: Option<Vec<StringId>>,
 }
 
 impl<'a> std::fmt::Display for SymbolTableScopeStack {
@@ -45,7 +57,8 @@ impl<'a> SymbolTableScopeStack {
             stack: vec![],
             head: None,
             root,
-            imported_symbols: HashMap::new(),
+            imported: PathMap::new(),
+            paths: PathTable::new(),
         };
 
         ss.add_imports(imports);
@@ -73,11 +86,7 @@ impl<'a> SymbolTableScopeStack {
                 debug!("Import function {}", imp_routine.path());
                 self.import_function(
                     imp_routine.path().clone(),
-                    imp_routine
-                        .params()
-                        .iter()
-                        .map(|(_, ty)| ty.clone())
-                        .collect(),
+                    imp_routine.params(),
                     imp_routine.ty().clone(),
                 );
             }
@@ -86,53 +95,60 @@ impl<'a> SymbolTableScopeStack {
 
     /// Add a function from another module to this symbol table
     /// So that calls to external functions can be type checked.
-    pub fn import_function(
+    fn import_function(
         &mut self,
         canonical_name: Path,
-        params: Vec<Type>,
+        params: &[(StringId, Type)],
         return_ty: Type,
     ) -> Option<Symbol> {
-        match canonical_name.item() {
-            Some(item) => self.imported_symbols.insert(
-                canonical_name.to_string(),
-                Symbol {
-                    name: item.into(),
-                    ty: Type::FunctionDef(params, Box::new(return_ty)),
-                    is_mutable: false,
-                    is_extern: false,
-                    span: None,
-                },
-            ),
-            None => None,
-        }
+        let item = canonical_name.item()?;
+        let ty = Type::FunctionDef(
+            params.iter().map(|(_, ty)| ty.clone()).collect(),
+            Box::new(return_ty),
+        );
+        let param_names = Some(params.iter().map(|(name, _)| *name).collect());
+        self.add_import(&canonical_name, item, ty, param_names)
     }
 
-    /// Add a function from another module to this symbol table
-    /// So that calls to external functions can be type checked.
-    pub fn import_structdef(&mut self, sd: &ImportStructDef) -> Option<Symbol> {
-        let canon_path = sd.path().clone();
-        match canon_path.item() {
-            Some(item) => self.imported_symbols.insert(
-                canon_path.to_string(),
-                Symbol {
-                    name: item.into(),
-                    ty: Type::StructDef(
-                        sd.fields()
-                            .iter()
-                            .map(|(f_name, f_ty)| (f_name.clone(), f_ty.clone()))
-                            .collect(),
-                    ),
-                    is_mutable: false,
-                    is_extern: false,
-                    span: None,
+    /// Add a structure from another module to this symbol table
+    /// So that uses of external structures can be type checked.
+    fn import_structdef(&mut self, sd: &ImportStructDef) -> Option<Symbol> {
+        let canon_path = sd.path();
+        let item = canon_path.item()?;
+        let ty = Type::StructDef(sd.fields().to_vec());
+        self.add_import(canon_path, item, ty, None)
+    }
+
+    fn add_import(
+        &mut self,
+        canon_path: &Path,
+        name: StringId,
+        ty: Type,
+        param_names: Option<Vec<StringId>>,
+    ) -> Option<Symbol> {
+        let symbol = Symbol {
+            name,
+            ty,
+            is_mutable: false,
+            is_extern: false,
+            span: None,
+        };
+        let id = self.paths.insert(canon_path);
+        self.imported
+            .insert(
+                id,
+                ImportedSymbol {
+                    symbol,
+                    param_names,
                 },
-            ),
-            None => None,
-        }
+            )
+            .map(|prev| prev.symbol)
     }
 
-    fn get_imported_symbol(&self, canonical_name: &Path) -> Option<&Symbol> {
-        self.imported_symbols.get(&canonical_name.to_string())
+    fn get_import(&self, canonical_name: &Path) -> Option<&ImportedSymbol> {
+        self.paths
+            .find(canonical_name)
+            .and_then(|id| self.imported.get(id))
     }
 
     pub fn enter_scope(&mut self, sym: SymbolTable) {
@@ -181,6 +197,20 @@ impl<'a> SymbolTableScopeStack {
         None
     }
 
+    /// Returns the name of the structure whose methods are currently being visited, if
+    /// the current node is within an `impl` block.
+    pub fn get_current_struct(&self) -> Option<StringId> {
+        let head = self.head.as_ref().expect("There is no head scope");
+        for scope in std::iter::once(head).chain(self.stack.iter().rev()) {
+            match scope.scope_type() {
+                ScopeType::Struct(name) => return Some(*name),
+                ScopeType::Module(_) => return None,
+                ScopeType::Local | ScopeType::Routine(_) => (),
+            }
+        }
+        None
+    }
+
     /// Searches SymbolStack, starting at the top of the stack and moving down,
     /// for a symbol that matches `name`.
     ///
@@ -211,6 +241,12 @@ impl<'a> SymbolTableScopeStack {
         }
     }
 
+    /// Looks up a symbol in the current symbol table (the SymbolTable that is at the
+    /// top of the stack) without searching any of the enclosing scopes.
+    pub fn lookup_local(&self, id: StringId) -> Option<&Symbol> {
+        self.head.as_ref().and_then(|h| h.get(id))
+    }
+
     /// Add a new symbol to the current symbol table (the SymbolTable that is at the
     /// top of the stack).
     pub fn add(
@@ -243,17 +279,25 @@ impl<'a> SymbolTableScopeStack {
             | Type::I16
             | Type::I32
             | Type::I64
+            | Type::USize
+            | Type::ISize
             | Type::F64
             | Type::Bool
             | Type::StringLiteral
+            | Type::String
             | Type::RawPointer(..)
             | Type::Array(_, _)
+            | Type::Slice(_)
+            | Type::Range(_)
+            | Type::Option(_)
+            | Type::Result(..)
             | Type::Unit => Ok(symbol),
             Type::Null
             | Type::FunctionDef(..)
             | Type::CoroutineDef(..)
             | Type::ExternDecl(..)
             | Type::StructDef { .. }
+            | Type::Never
             | Type::Unknown => return Err(SemanticError::NotVariable(id)),
         }
     }
@@ -307,7 +351,7 @@ impl<'a> SymbolTableScopeStack {
             let project_symbol = self.get_item(&canon_path);
 
             // look in any imported symbols
-            let imported_symbol = self.get_imported_symbol(&canon_path);
+            let imported_symbol = self.get_import(&canon_path).map(|i| &i.symbol);
 
             // Make sure that there is no ambiguity about what is being referenced
             match (project_symbol, imported_symbol) {
@@ -340,6 +384,32 @@ impl<'a> SymbolTableScopeStack {
             .item()
             .expect("Expected a canonical path with at least one step in it");
 
+        match self.get_parent_module(canon_path) {
+            Some(module) => module.context().sym().get(item),
+            None => self
+                .get_parent_struct(canon_path)?
+                .context()
+                .sym()
+                .get(item),
+        }
+    }
+
+    /// Follows a canonical path from the root module to the structure which contains
+    /// the method that the path refers to.
+    fn get_parent_struct(&self, canon_path: &Path) -> Option<&StructDef<SemanticContext>> {
+        let struct_path = canon_path.parent();
+        match self
+            .get_parent_module(&struct_path)?
+            .get_item(struct_path.item()?)?
+        {
+            Item::Struct(sd) => Some(sd),
+            _ => None,
+        }
+    }
+
+    /// Follows a canonical path from the root module to the module which contains
+    /// the item that the path refers to.
+    fn get_parent_module(&self, canon_path: &Path) -> Option<&Module<SemanticContext>> {
         if !canon_path.is_canonical() {
             panic!("Given path is not canonical: {}", canon_path);
         }
@@ -359,10 +429,31 @@ impl<'a> SymbolTableScopeStack {
                 }
             }
 
-            (*current).context().sym().get(item)
+            current.as_ref()
         }
     }
 
+    /// Returns the names of the parameters of the routine with the given canonical path.
+    /// Returns `None` if the path does not refer to a routine.
+    pub fn lookup_param_names(&self, canon_path: &Path) -> Option<Vec<StringId>> {
+        if let Some(import) = self.get_import(canon_path) {
+            return import.param_names.clone();
+        }
+
+        let params = match self.get_parent_module(canon_path) {
+            Some(module) => match module.get_item(canon_path.item()?)? {
+                Item::Routine(rd) => rd.get_params(),
+                Item::Extern(ex) => ex.get_params(),
+                Item::Struct(_) => return None,
+            },
+            None => self
+                .get_parent_struct(canon_path)?
+                .get_method(canon_path.item()?)?
+                .get_params(),
+        };
+        Some(params.iter().map(|p| p.name).collect())
+    }
+
     /**
     Given a type reference that appears in the current node, will convert that type reference
     to a canonical path from a relative path.  If the type reference is already an absolute
@@ -373,7 +464,7 @@ impl<'a> SymbolTableScopeStack {
      */
     pub fn canonize_type(&self, ty: &Type) -> Result<Type, SemanticError> {
         match ty {
-            Type::Custom(path) => self.to_canonical(path).map(|p| Type::Custom(p)),
+            Type::Custom(path) => self.canonize_reference(path).map(|p| Type::Custom(p)),
             Type::Coroutine(ty) => Ok(Type::Coroutine(Box::new(self.canonize_type(&ty)?))),
             Type::CoroutineDef(params, ret_ty) => {
                 let cparams = params
@@ -417,6 +508,13 @@ impl<'a> SymbolTableScopeStack {
                     Ok(Type::Array(Box::new(self.canonize_type(el_ty)?), *len))
                 }
             }
+            Type::Slice(el_ty) => Ok(Type::Slice(Box::new(self.canonize_type(el_ty)?))),
+            Type::Range(el_ty) => Ok(Type::Range(Box::new(self.canonize_type(el_ty)?))),
+            Type::Option(el_ty) => Ok(Type::Option(Box::new(self.canonize_type(el_ty)?))),
+            Type::Result(ty, err_ty) => Ok(Type::Result(
+                Box::new(self.canonize_type(ty)?),
+                Box::new(self.canonize_type(err_ty)?),
+            )),
             Type::Null
             | Type::U8
             | Type::U16
@@ -426,10 +524,14 @@ impl<'a> SymbolTableScopeStack {
             | Type::I16
             | Type::I32
             | Type::I64
+            | Type::USize
+            | Type::ISize
             | Type::F64
             | Type::Bool
             | Type::StringLiteral
+            | Type::String
             | Type::Unit
+            | Type::Never
             | Type::Unknown => Ok(ty.clone()),
         }
     }
@@ -441,6 +543,43 @@ impl<'a> SymbolTableScopeStack {
         path.to_canonical(&current_path).map_err(|e| e.into())
     }
 
+    /// Converts a path which refers to an item or module (e.g. in a type or a routine
+    /// call) into a canonical path.  If the first element of the path is a name which
+    /// was declared by a `use` in the current module, then that name is replaced with
+    /// the path given in the `use` before the path is canonized.
+    pub fn canonize_reference(&self, path: &Path) -> Result<Path, SemanticError> {
+        match self.expand_use(path) {
+            Some(expanded) => self.to_canonical(&expanded),
+            None => self.to_canonical(path),
+        }
+    }
+
+    /// If `path` begins with a name declared by a `use` in the current module, then
+    /// return the path with that name replaced by the path which it refers to.
+    fn expand_use(&self, path: &Path) -> Option<Path> {
+        if path.is_canonical() {
+            return None;
+        }
+
+        let name = match path.first()? {
+            Element::Id(name) => *name,
+            _ => return None,
+        };
+
+        // A `use` only applies within the module which declares it
+        let module = self
+            .head
+            .iter()
+            .chain(self.stack.iter().rev())
+            .find(|scope| matches!(scope.scope_type(), ScopeType::Module(_)))?;
+
+        let mut expanded = module.get_use(name)?.clone();
+        for step in path.iter().skip(1) {
+            expanded.push(*step);
+        }
+        Some(expanded)
+    }
+
     /// Starting from the bottom of the stack this builds a path
     /// of all the modules that we are current in, in effect
     /// the current path within the AST.
@@ -450,14 +589,14 @@ impl<'a> SymbolTableScopeStack {
         for node in self.stack.iter() {
             match node.scope_type() {
                 ScopeType::Module(name) => steps.push(Element::Id(*name)),
-                ScopeType::Local | ScopeType::Routine(_) => (),
+                ScopeType::Local | ScopeType::Routine(_) | ScopeType::Struct(_) => (),
             }
         }
 
         match &self.head {
             Some(h) => match h.scope_type() {
                 ScopeType::Module(name) => steps.push(Element::Id(*name)),
-                ScopeType::Local | ScopeType::Routine(_) => (),
+                ScopeType::Local | ScopeType::Routine(_) | ScopeType::Struct(_) => (),
             },
             None => panic!("Expected a head"),
         }
@@ -0,0 +1,62 @@
+//! Evaluates static assertions, `static_assert(cond, "message");`, which are written at
+//! the module level and fail compilation with their message if their condition is
+//! `false`.
+//!
+//! The condition is evaluated when the program is compiled, so it must be a constant,
+//! as described in [`const_eval`](super::const_eval).
+
+use crate::compiler::{
+    ast::{Context, Module, Node},
+    source::SourceIr,
+    CompilerError,
+};
+
+use super::{
+    const_eval::{Const, ConstEval, EvalResult},
+    dead_branch::Defines,
+    error::SemanticError,
+};
+
+/// Evaluates every static assertion in the given module and its submodules, and
+/// returns an error for the first one which does not hold.  The conditions may call
+/// any `const fn` in the module tree.
+pub(super) fn check_static_asserts<M: Context>(
+    root: &Module<M>,
+    defines: &Defines,
+) -> EvalResult<()> {
+    let eval = ConstEval::new(root, defines);
+    check_module(&eval, &mut vec![root])
+}
+
+/// Checks the static assertions of the last module of `scope`, which is the list of
+/// modules from the root to that module, after those of its submodules.
+fn check_module<'a, M: Context>(
+    eval: &ConstEval<'a, M>,
+    scope: &mut Vec<&'a Module<M>>,
+) -> EvalResult<()> {
+    let m = scope[scope.len() - 1];
+    for child in m.get_modules() {
+        scope.push(child);
+        check_module(eval, scope)?;
+        scope.pop();
+    }
+
+    for sa in m.get_static_asserts() {
+        match eval.eval(scope, sa.get_cond())? {
+            Const::Bool(true) => (),
+            Const::Bool(false) => {
+                return Err(CompilerError::new(
+                    sa.span(),
+                    SemanticError::StaticAssertFailed(sa.get_message()),
+                ))
+            }
+            value => {
+                return Err(CompilerError::new(
+                    sa.get_cond().context().span(),
+                    SemanticError::StaticAssertExpectedBool(value.ty()),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,11 +1,14 @@
 use log::debug;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    compiler::{ast::*, semantics::semanticnode::SemanticContext, source::SourceIr, Span},
+    compiler::{
+        ast::*, semantics::semanticnode::SemanticContext, source::SourceIr, CompilerError, Span,
+    },
     StringId,
 };
 
-use super::SemanticError;
+use super::{SemanticError, SemanticResult};
 
 /**
  `SymbolTable` is an AST node context that contains information about symbols that
@@ -30,10 +33,14 @@ use super::SemanticError;
  know about are the ones in the `SymbolTable`s of the nodes that comprise the path from
  the root of the AST to the given node.
 */
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SymbolTable {
     ty: ScopeType,
     sym: Vec<Symbol>,
+
+    /// The names declared by `use` declarations in this scope and the paths which
+    /// they refer to
+    uses: Vec<(StringId, Path)>,
 }
 
 impl SymbolTable {
@@ -41,6 +48,7 @@ impl SymbolTable {
         SymbolTable {
             ty: ScopeType::Local,
             sym: vec![],
+            uses: vec![],
         }
     }
 
@@ -48,6 +56,15 @@ impl SymbolTable {
         SymbolTable {
             ty: ScopeType::Routine(name),
             sym: vec![],
+            uses: vec![],
+        }
+    }
+
+    pub fn new_struct(name: StringId) -> Self {
+        SymbolTable {
+            ty: ScopeType::Struct(name),
+            sym: vec![],
+            uses: vec![],
         }
     }
 
@@ -55,6 +72,7 @@ impl SymbolTable {
         SymbolTable {
             ty: ScopeType::Module(name),
             sym: vec![],
+            uses: vec![],
         }
     }
 
@@ -99,6 +117,48 @@ impl SymbolTable {
         Ok(())
     }
 
+    /**
+     * Adds the name declared by every `use` declaration in a module to the symbol table
+     * of the module, so that paths which begin with that name can be expanded when they
+     * are canonized.  A name declared by `use` cannot be the same as the name of an
+     * item or submodule in the same module.
+     *
+     * This function is recursively applied to child modules.
+     */
+    pub fn add_uses_to_table(module: &mut Module<SemanticContext>) -> SemanticResult<()> {
+        let mut sym = module.context().sym().clone();
+        let uses = module.get_uses();
+        for (idx, u) in uses.iter().enumerate() {
+            let name = u.get_name();
+            let existing = module
+                .get_item(name)
+                .map(|i| i.span())
+                .or_else(|| module.get_module(name).map(|m| m.span()))
+                .or_else(|| {
+                    uses[..idx]
+                        .iter()
+                        .find(|prev| prev.get_name() == name)
+                        .map(|prev| prev.span())
+                });
+
+            if let Some(existing) = existing {
+                return Err(
+                    CompilerError::new(u.span(), SemanticError::AlreadyDeclared(name))
+                        .with_note(existing, "previous declaration here"),
+                );
+            }
+
+            sym.add_use(name, u.get_path().clone());
+        }
+        *module.get_context_mut() = module.context().with_sym(sym);
+
+        for m in module.get_modules_mut().iter_mut() {
+            SymbolTable::add_uses_to_table(m)?;
+        }
+
+        Ok(())
+    }
+
     fn for_item(
         item: &mut Item<SemanticContext>,
         sym: &mut SemanticContext,
@@ -110,10 +170,18 @@ impl SymbolTable {
         }
     }
 
+    /// Adds the structure to the symbol table of its module and adds its methods to the
+    /// symbol table of the structure itself.
     fn add_structdef(
         structdef: &mut StructDef<SemanticContext>,
         sym: &mut SemanticContext,
     ) -> Result<(), SemanticError> {
+        let mut context = structdef.context().clone();
+        for m in structdef.get_methods_mut().iter_mut() {
+            SymbolTable::add_routine_parameters(m, &mut context)?;
+        }
+        *structdef.get_context_mut() = context;
+
         sym.add_symbol(
             structdef.get_name(),
             Type::StructDef(
@@ -190,6 +258,18 @@ impl SymbolTable {
         self.sym.iter().find(|s| s.name == name)
     }
 
+    /// Returns the path which `name` was declared to refer to by a `use` declaration
+    pub fn get_use(&self, name: StringId) -> Option<&Path> {
+        self.uses
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, path)| path)
+    }
+
+    fn add_use(&mut self, name: StringId, path: Path) {
+        self.uses.push((name, path))
+    }
+
     pub fn get_path(&self, name: &Path) -> Option<&Symbol> {
         if name.len() == 1 {
             self.sym.iter().find(|s| Element::Id(s.name) == name[0])
@@ -236,7 +316,7 @@ impl std::fmt::Display for SymbolTable {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Symbol {
     pub name: StringId,
     pub ty: Type,
@@ -254,17 +334,20 @@ impl std::fmt::Display for Symbol {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub(super) enum ScopeType {
     Local,
     Routine(StringId),
+
+    /// The methods of a structure
+    Struct(StringId),
     Module(StringId),
 }
 
 impl ScopeType {
     pub fn is_boundary(&self) -> bool {
         match self {
-            Self::Routine(..) | Self::Local => false,
+            Self::Routine(..) | Self::Struct(..) | Self::Local => false,
             Self::Module(..) => true,
         }
     }
@@ -272,7 +355,7 @@ impl ScopeType {
     pub fn get_name(&self) -> Option<StringId> {
         match self {
             Self::Local => None,
-            Self::Routine(name) | Self::Module(name) => Some(*name),
+            Self::Routine(name) | Self::Struct(name) | Self::Module(name) => Some(*name),
         }
     }
 }
@@ -282,6 +365,7 @@ impl std::fmt::Display for ScopeType {
         match self {
             ScopeType::Local => f.write_str("Local"),
             ScopeType::Routine(name) => f.write_fmt(format_args!("Routine({})", name)),
+            ScopeType::Struct(name) => f.write_fmt(format_args!("Struct({})", name)),
             ScopeType::Module(name) => f.write_fmt(format_args!("Module({})", name)),
         }
     }
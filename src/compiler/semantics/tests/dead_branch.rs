@@ -0,0 +1,374 @@
+#[cfg(test)]
+mod dead_branch_tests {
+    use crate::{
+        compiler::{
+            ast::*,
+            diagnostics::Logger,
+            lexer::tokens::Token,
+            lexer::LexerError,
+            parser::Parser,
+            semantics::{
+                dead_branch::{prune_dead_branches, Defines},
+                type_resolver::resolve_types,
+            },
+            CompilerDisplay, CompilerError, Lexer, SourceMap,
+        },
+        StringTable,
+    };
+
+    type LResult = std::result::Result<Vec<Token>, CompilerError<LexerError>>;
+
+    #[test]
+    pub fn test_cfg_conditions() {
+        for (text, expected) in vec![
+            (
+                "fn test() -> i64 {
+                    return if (cfg(linux)) {linux_only()} else {machos_only()};
+                }
+                extern fn linux_only() -> i64;",
+                Ok(()),
+            ),
+            (
+                "fn test() -> i64 {
+                    return if (cfg(machos)) {machos_only()} else {linux_only()};
+                }
+                extern fn linux_only() -> i64;",
+                Ok(()),
+            ),
+            (
+                "fn test() -> i64 {
+                    return if (!cfg(machos) && (cfg(linux) || false)) {linux_only()} else {true};
+                }
+                extern fn linux_only() -> i64;",
+                Ok(()),
+            ),
+            (
+                "fn test() {
+                    if (cfg(machos)) {machos_only();};
+                    return;
+                }",
+                Ok(()),
+            ),
+            (
+                "fn test() -> bool {
+                    let x: bool := cfg(linux) && !cfg(machos);
+                    return x;
+                }",
+                Ok(()),
+            ),
+            (
+                "fn test(b: bool) -> i64 {
+                    return if (cfg(linux) && b) {linux_only()} else {machos_only()};
+                }
+                extern fn linux_only() -> i64;",
+                Err(
+                    "L2: Could not find item with the given path: machos_only ($main::machos_only)",
+                ),
+            ),
+            (
+                "fn test() -> i64 {
+                    return if (true) {1} else {machos_only()};
+                }",
+                Err(
+                    "L2: Could not find item with the given path: machos_only ($main::machos_only)",
+                ),
+            ),
+            (
+                "fn test() -> i64 {
+                    return if (cfg(linux)) {true} else {1};
+                }",
+                Err("L2: Return expected i64 but got bool"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let mut ast = parser.parse(main, &tokens).unwrap().unwrap();
+            prune_dead_branches(&mut ast, &Defines::new(&table, &["linux"])).unwrap();
+
+            let result = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(()) => assert!(result.is_ok(), "{:?}", result.err()),
+                Err(msg) => {
+                    assert_eq!(
+                        result.err().unwrap().fmt(&sm, &table).unwrap(),
+                        msg,
+                        "{}",
+                        text
+                    )
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_env() {
+        for (text, expected) in vec![
+            (
+                "fn test() -> string {
+                    return env(\"VERSION\");
+                }",
+                Ok("1.2.3"),
+            ),
+            (
+                "fn test() -> string {
+                    return env(\"QUOTED\");
+                }",
+                Ok("say \\\"hi\\\""),
+            ),
+            (
+                "fn test() -> string {
+                    return if (cfg(machos)) {env(\"BRAMBLE_UNDEFINED\")} else {env(\"VERSION\")};
+                }",
+                Ok("1.2.3"),
+            ),
+            (
+                "fn test() -> string {
+                    return env(\"BRAMBLE_UNDEFINED\");
+                }",
+                Err("L2: Environment variable BRAMBLE_UNDEFINED is not set and was not defined with --define"),
+            ),
+            (
+                "fn test() -> string {
+                    return env(VERSION);
+                }",
+                Err("L2: env expects the name of an environment variable as a string literal"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let test = table.insert("test".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let mut ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let defines = Defines::new(&table, &["linux", "VERSION=1.2.3", "QUOTED=say \"hi\""]);
+            let result = prune_dead_branches(&mut ast, &defines).map(|()| {
+                match ast.get_item(test) {
+                    Some(Item::Routine(r)) => match &r.get_body()[0] {
+                        Statement::Return(ret) => match ret.get_value() {
+                            Some(Expression::StringLiteral(_, s)) => table.get(*s).unwrap(),
+                            // The live arm of an `if`
+                            Some(Expression::ExpressionBlock(_, _, Some(fe))) => match &**fe {
+                                Expression::StringLiteral(_, s) => table.get(*s).unwrap(),
+                                _ => panic!("Expected a string literal"),
+                            },
+                            _ => panic!("Expected a string literal"),
+                        },
+                        _ => panic!("Expected a return"),
+                    },
+                    _ => panic!("Expected a routine"),
+                }
+            });
+
+            match expected {
+                Ok(value) => assert_eq!(result.unwrap(), value, "{}", text),
+                Err(msg) => assert_eq!(
+                    result.err().unwrap().fmt(&sm, &table).unwrap(),
+                    msg,
+                    "{}",
+                    text
+                ),
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_static_asserts() {
+        for (text, expected) in vec![
+            ("static_assert(1 + 1 == 2, \"math\");", Ok(())),
+            ("static_assert(-7 / 2 == -3 && 2 * 3 >= 6, \"math\");", Ok(())),
+            ("static_assert(cfg(linux) && !cfg(machos), \"linux\");", Ok(())),
+            ("static_assert(wrapping_add(250u8, 10u8) == 4u8, \"wraps\");", Ok(())),
+            ("static_assert(saturating_add(250u8, 10u8) == 255u8, \"saturates\");", Ok(())),
+            (
+                "static_assert(cfg(machos), \"requires machos\");",
+                Err("L1: Static assertion failed: requires machos"),
+            ),
+            (
+                "mod inner { static_assert(1 > 2, \"inner\"); }",
+                Err("L1: Static assertion failed: inner"),
+            ),
+            (
+                "static_assert(200u8 * 2u8 > 0u8, \"overflow\");",
+                Err("L1: The condition of this static assertion overflows or divides by zero"),
+            ),
+            (
+                "static_assert(1 / 0 == 0, \"divide\");",
+                Err("L1: The condition of this static assertion overflows or divides by zero"),
+            ),
+            (
+                "static_assert(1i8 + 1i64 == 2i64, \"mismatch\");",
+                Err("L1: + expected i8 but found i8 and i64"),
+            ),
+            (
+                "static_assert(5, \"not bool\");",
+                Err("L1: static_assert expected bool but found i64"),
+            ),
+            (
+                "static_assert(x == 1, \"variable\");",
+                Err("L1: The condition of a static assertion must be built from literals, cfg, operators, and calls to const fns"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let mut ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let result = prune_dead_branches(&mut ast, &Defines::new(&table, &["linux"]));
+
+            match expected {
+                Ok(()) => assert!(result.is_ok(), "{}: {:?}", text, result),
+                Err(msg) => assert_eq!(
+                    result.err().unwrap().fmt(&sm, &table).unwrap(),
+                    msg,
+                    "{}",
+                    text
+                ),
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_static_asserts_call_const_fns() {
+        for (text, expected) in vec![
+            (
+                "const fn square(x: i64) -> i64 { return x * x; }
+                static_assert(square(3) == 9, \"square\");",
+                Ok(()),
+            ),
+            (
+                "const fn fact(n: u32) -> u32 {
+                    let mut acc: u32 := 1u32;
+                    let mut i: u32 := n;
+                    while (i > 0u32) {
+                        mut acc := acc * i;
+                        mut i := i - 1u32;
+                    };
+                    return acc;
+                }
+                static_assert(fact(5u32) == 120u32, \"fact\");",
+                Ok(()),
+            ),
+            (
+                "const fn fib(n: i64) -> i64 {
+                    return if (n < 2) {n} else {fib(n - 1) + fib(n - 2)};
+                }
+                static_assert(fib(10) == 55, \"fib\");",
+                Ok(()),
+            ),
+            (
+                "const fn is_even(n: i64) -> bool { return n == 0 || !is_even(n - 1); }
+                static_assert(is_even(4) && !is_even(3), \"even\");",
+                Ok(()),
+            ),
+            (
+                "mod math { const fn two() -> i64 { return 2; } }
+                mod inner { static_assert(super::math::two() == 2, \"path\"); }",
+                Ok(()),
+            ),
+            (
+                "const fn answer() -> i64 { return if (cfg(linux)) {42} else {0}; }
+                static_assert(answer() == 42, \"cfg\");",
+                Ok(()),
+            ),
+            (
+                "const fn square(x: i64) -> i64 { return x * x; }
+                static_assert(square(3) == 10, \"square\");",
+                Err("L2: Static assertion failed: square"),
+            ),
+            (
+                "fn two() -> i64 { return 2; }
+                static_assert(two() == 2, \"not const\");",
+                Err("L2: A const fn can only call other const fns, but two is not a const fn"),
+            ),
+            (
+                "const fn big(x: u8) -> u8 { return x * 2u8; }
+                static_assert(big(200u8) > 0u8, \"overflow\");",
+                Err("L1: The condition of this static assertion overflows or divides by zero"),
+            ),
+            (
+                "const fn forever() -> bool { while (true) {}; return true; }
+                static_assert(forever(), \"forever\");",
+                Err("L1: Evaluating this constant took too many steps, or too many nested const fn calls"),
+            ),
+            (
+                "const fn down(n: i64) -> i64 { return down(n + 1); }
+                static_assert(down(0) == 0, \"deep\");",
+                Err("L1: Evaluating this constant took too many steps, or too many nested const fn calls"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let mut ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let result = prune_dead_branches(&mut ast, &Defines::new(&table, &["linux"]));
+
+            match expected {
+                Ok(()) => assert!(
+                    result.is_ok(),
+                    "{}: {:?}",
+                    text,
+                    result.map_err(|e| e.fmt(&sm, &table))
+                ),
+                Err(msg) => assert_eq!(
+                    result.err().unwrap().fmt(&sm, &table).unwrap(),
+                    msg,
+                    "{}",
+                    text
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,175 @@
+#[cfg(test)]
+mod explain_tests {
+    use crate::{
+        compiler::{
+            ast::*,
+            diagnostics::Logger,
+            lexer::tokens::Token,
+            lexer::LexerError,
+            parser::Parser,
+            semantics::{
+                explain::{explain_item_types, explain_types},
+                query::SemanticQueries,
+                type_resolver::resolve_types,
+            },
+            Column, CompilerError, Lexer, LineNumber, SourceMap, Span,
+        },
+        StringTable,
+    };
+
+    type LResult = std::result::Result<Vec<Token>, CompilerError<LexerError>>;
+
+    #[test]
+    pub fn test_explain_types() {
+        let text = "fn sum(s: [i64]) -> i64 { return 0; }
+fn test() -> i64 {
+    let a: [i64; 2] := [1, 2];
+    let b: u8 := 5u8;
+    let x: i64 := sum(a) + 3;
+    return x;
+}";
+        for ((line, start, end), expected) in vec![
+            (
+                (5, 19, 28),
+                vec![
+                    "`sum(a) + 3`: i64, + applied to i64 and i64",
+                    "  `sum(a)`: i64, call ",
+                    "    `a`: [i64], implicit coercion of [i64; 2] to [i64]",
+                    "      `a`: [i64; 2], variable a, which was declared with this type",
+                    "  `3`: i64, literal without a suffix, which is i64 by default",
+                ],
+            ),
+            // The smallest expression which covers the location is explained
+            (
+                (5, 28, 28),
+                vec!["`3`: i64, literal without a suffix, which is i64 by default"],
+            ),
+            ((4, 18, 20), vec!["`5u8`: u8, literal with the suffix u8"]),
+            (
+                (3, 24, 29),
+                vec![
+                    "`[1, 2]`: [i64; 2], array of 2 elements with the type of its elements",
+                    "  `1`: i64, literal without a suffix, which is i64 by default",
+                    "  `2`: i64, literal without a suffix, which is i64 by default",
+                ],
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
+
+            let offset = |col| {
+                sm.offset_at(None, LineNumber::new(line), Column::new(col))
+                    .unwrap()
+            };
+            let mut high = offset(end);
+            high += 1;
+            let span = Span::new(offset(start), high);
+
+            let explanation = explain_types(&module, span, &sm, &table).unwrap().unwrap();
+            let lines: Vec<_> = explanation.lines().collect();
+            assert_eq!(lines.len(), expected.len(), "{}", explanation);
+            for (line, expected) in lines.iter().zip(&expected) {
+                // The canonical path of a routine depends on the module, so only the
+                // start of each line is checked
+                assert!(line.starts_with(expected), "{}", explanation);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_explain_types_outside_routine() {
+        let text = "struct S { a: i64 }";
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let mut table = StringTable::new();
+        let main = table.insert("main".into());
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+
+        let parser = Parser::new(&logger);
+        let ast = parser.parse(main, &tokens).unwrap().unwrap();
+        let module = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
+
+        let span = sm.span().unwrap();
+        assert_eq!(explain_types(&module, span, &sm, &table).unwrap(), None);
+    }
+
+    #[test]
+    pub fn test_explain_item_types() {
+        let text = "fn bad() -> i64 { return true; }
+fn test() -> i64 {
+    let x: i64 := 2 + 3;
+    return x;
+}";
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let mut table = StringTable::new();
+        let main = table.insert("main".into());
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+
+        let parser = Parser::new(&logger);
+        let ast = parser.parse(main, &tokens).unwrap().unwrap();
+        assert!(resolve_types(&ast, main_mod, main_fn, &table, &logger).is_err());
+
+        // `bad` does not type check, but only the item with the expression is resolved
+        let mut queries =
+            SemanticQueries::new(&ast, main_mod, main_fn, &[], &table, &logger).unwrap();
+        let offset = |col| {
+            sm.offset_at(None, LineNumber::new(3), Column::new(col))
+                .unwrap()
+        };
+        let mut high = offset(23);
+        high += 1;
+        let span = Span::new(offset(19), high);
+
+        let path = queries.item_at(span).unwrap();
+        let item = queries.type_of(&path).unwrap().as_ref().unwrap();
+        let explanation = explain_item_types(item, span, &sm, &table)
+            .unwrap()
+            .unwrap();
+        assert!(
+            explanation.starts_with("`2 + 3`: i64, + applied to i64 and i64"),
+            "{}",
+            explanation
+        );
+    }
+}
@@ -0,0 +1,142 @@
+#[cfg(test)]
+mod format_tests {
+    use crate::{
+        compiler::{
+            ast::*,
+            diagnostics::Logger,
+            lexer::tokens::Token,
+            lexer::LexerError,
+            parser::Parser,
+            semantics::{format::check_formats, type_resolver::resolve_types},
+            CompilerDisplay, CompilerError, Lexer, SourceMap,
+        },
+        StringTable,
+    };
+
+    type LResult = std::result::Result<Vec<Token>, CompilerError<LexerError>>;
+
+    #[test]
+    pub fn test_format_strings() {
+        for (text, expected) in vec![
+            (
+                "fn test(a: i64, b: i32, c: u8, f: f64, s: string) {
+                    printf(\"%ld %d %hhu %f %s %% %5.2f\\n\", a, b, c, f, s, f);
+                    return;
+                }",
+                vec![],
+            ),
+            (
+                "fn test(a: i64) {
+                    printf(\"%d\\n\", a);
+                    return;
+                }",
+                vec!["L2: %d expects i32 or u32 but got i64"],
+            ),
+            (
+                "fn test(a: i64) {
+                    printf(\"%ld %ld\\n\", a);
+                    return;
+                }",
+                vec!["L2: Format string expects 2 arguments but 1 were given"],
+            ),
+            (
+                "fn test(a: i64) {
+                    printf(\"%ld\\n\", a, a);
+                    return;
+                }",
+                vec!["L2: Format string expects 1 arguments but 2 were given"],
+            ),
+            (
+                "fn test(w: i32, f: f64, p: *const i64) {
+                    printf(\"%*.*f %p\", w, w, f, p);
+                    return;
+                }",
+                vec![],
+            ),
+            (
+                "fn test(f: f64) {
+                    printf(\"%*f\", f, f);
+                    return;
+                }",
+                vec!["L2: %*f expects i32 or u32 but got f64"],
+            ),
+            (
+                "fn test(a: i64) {
+                    printf(\"%ld %q\", a);
+                    return;
+                }",
+                vec!["L2: Invalid conversion specifier %q in format string"],
+            ),
+            (
+                "fn test(a: i64) {
+                    printf(\"%ld %\", a);
+                    return;
+                }",
+                vec!["L2: Invalid conversion specifier % in format string"],
+            ),
+            (
+                "fn test(s: string) {
+                    printf(s);
+                    return;
+                }",
+                vec![],
+            ),
+            (
+                "fn test() {
+                    let mut i: i64 := 0;
+                    let mut f: f64 := 0.0;
+                    scanf(\"%ld %lf %*d\", @mut i, @mut f);
+                    return;
+                }",
+                vec![],
+            ),
+            (
+                "fn test() {
+                    let mut i: i64 := 0;
+                    scanf(\"%ld\", i);
+                    return;
+                }",
+                vec!["L3: %ld expects *mut i64 or *mut u64 but got i64"],
+            ),
+            (
+                "fn test() {
+                    let mut f: f64 := 0.0;
+                    scanf(\"%f\", @mut f);
+                    return;
+                }",
+                vec!["L3: Invalid conversion specifier %f in format string"],
+            ),
+        ] {
+            let text = format!(
+                "extern fn printf(fmt: string, ...); extern fn scanf(fmt: string, ...); {}",
+                text
+            );
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
+
+            let errors: Vec<_> = check_formats(&module, &table)
+                .iter()
+                .map(|e| e.fmt(&sm, &table).unwrap())
+                .collect();
+            assert_eq!(errors, expected, "{}", text);
+        }
+    }
+}
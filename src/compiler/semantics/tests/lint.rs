@@ -0,0 +1,442 @@
+#[cfg(test)]
+mod lint_tests {
+    use crate::{
+        compiler::{
+            ast::*,
+            diagnostics::Logger,
+            lexer::tokens::Token,
+            lexer::LexerError,
+            parser::Parser,
+            semantics::{
+                complexity::{check_complexity, measure, Complexity, ComplexityLimits},
+                lint::{lint, lint_mut_assignments, lint_trailing_commas},
+                type_resolver::resolve_types,
+            },
+            CompilerDisplay, CompilerError, Lexer, SourceMap,
+        },
+        StringTable,
+    };
+
+    type LResult = std::result::Result<Vec<Token>, CompilerError<LexerError>>;
+
+    #[test]
+    pub fn test_sign_changing_cast_in_comparison() {
+        for (text, expected) in vec![
+            (
+                "fn test(a: i64, b: u64) -> bool {
+                    return a as u64 < b;
+                }",
+                vec!["L2: < compares a value cast from i64 to u64, which changes how its sign is interpreted"],
+            ),
+            (
+                "fn test(a: i64, b: u64) -> bool {
+                    return a == b as i64;
+                }",
+                vec!["L2: == compares a value cast from u64 to i64, which changes how its sign is interpreted"],
+            ),
+            (
+                "fn test(a: i8, b: u8) -> bool {
+                    let x: bool := if (a as u8 >= b) {true} else {false};
+                    return x;
+                }",
+                vec!["L2: >= compares a value cast from i8 to u8, which changes how its sign is interpreted"],
+            ),
+            (
+                "fn test(a: i32, b: u8) -> bool {
+                    return a as i64 > b as i64;
+                }",
+                vec!["L2: > compares a value cast from u8 to i64, which changes how its sign is interpreted"],
+            ),
+            (
+                "fn test(a: i32, b: i64) -> bool {
+                    return a as i64 > b;
+                }",
+                vec![],
+            ),
+            (
+                "fn test(a: i64) -> u64 {
+                    return a as u64 + 1u64;
+                }",
+                vec![],
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
+
+            let lints: Vec<_> = lint(&module)
+                .iter()
+                .map(|l| l.fmt(&sm, &table).unwrap())
+                .collect();
+            assert_eq!(lints, expected, "{}", text);
+        }
+    }
+
+    #[test]
+    pub fn test_ignored_value() {
+        for (text, expected) in vec![
+            (
+                "fn test() -> i64 {
+                    five();
+                    return 0;
+                }
+                fn five() -> i64 {return 5;}",
+                vec!["L2: Value of type i64 is ignored, use `_ := ...;` to discard it explicitly"],
+            ),
+            (
+                "fn test(b: bool) -> i64 {
+                    if (b) {1} else {2};
+                    return 0;
+                }",
+                vec!["L2: Value of type i64 is ignored, use `_ := ...;` to discard it explicitly"],
+            ),
+            (
+                "fn test() -> i64 {
+                    _ := five();
+                    return 0;
+                }
+                fn five() -> i64 {return 5;}",
+                vec![],
+            ),
+            (
+                "fn test() -> i64 {
+                    nothing();
+                    ();
+                    return 0;
+                }
+                fn nothing() {return;}",
+                vec![],
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
+
+            let lints: Vec<_> = lint(&module)
+                .iter()
+                .map(|l| l.fmt(&sm, &table).unwrap())
+                .collect();
+            assert_eq!(lints, expected, "{}", text);
+        }
+    }
+
+    #[test]
+    pub fn test_mixed_logical_operators() {
+        for (text, expected) in vec![
+            (
+                "fn test(a: bool, b: bool, c: bool) -> bool {
+                    return a || b && c;
+                }",
+                vec!["L2: && and || are mixed without parentheses, add parentheses to make the order explicit: a || (b && c)"],
+            ),
+            (
+                "fn test(a: bool, b: bool, c: bool) -> bool {
+                    return a && b || c;
+                }",
+                vec!["L2: && and || are mixed without parentheses, add parentheses to make the order explicit: (a && b) || c"],
+            ),
+            (
+                "fn test(a: bool, b: bool, c: bool, d: bool) -> bool {
+                    return a && b || c && d;
+                }",
+                vec!["L2: && and || are mixed without parentheses, add parentheses to make the order explicit: (a && b) || (c && d)"],
+            ),
+            (
+                "fn test(a: bool, b: bool, c: bool) -> bool {
+                    return a || (b && c);
+                }",
+                vec![],
+            ),
+            (
+                "fn test(a: bool, b: bool, c: bool) -> bool {
+                    return (a && b) || c;
+                }",
+                vec![],
+            ),
+            (
+                "fn test(a: bool, b: bool, c: bool) -> bool {
+                    return a && (b || c);
+                }",
+                vec![],
+            ),
+            (
+                "fn test(a: bool, b: bool, c: bool) -> bool {
+                    return a || b || c;
+                }",
+                vec![],
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
+
+            let lints: Vec<_> = lint(&module)
+                .iter()
+                .map(|l| l.fmt(&sm, &table).unwrap())
+                .collect();
+            assert_eq!(lints, expected, "{}", text);
+        }
+    }
+
+    #[test]
+    pub fn test_too_complex() {
+        let limits = ComplexityLimits {
+            nodes: 100,
+            depth: 3,
+            basic_blocks: 3,
+            locals: 2,
+        };
+
+        for (text, expected) in vec![
+            (
+                "fn test(a: i64) -> i64 {
+                    return a + 1;
+                }",
+                vec![],
+            ),
+            (
+                "fn test(a: i64) -> i64 {
+                    let b: i64 := a;
+                    let c: i64 := b;
+                    return c;
+                }",
+                vec!["L1-5: $main::test has 3 local variables, which exceeds the limit of 2"],
+            ),
+            (
+                "fn test(a: i64) -> i64 {
+                    return ((a + 1) * 2) - (a / 2);
+                }",
+                vec!["L1-3: $main::test has 4 levels of nested expressions, which exceeds the limit of 3"],
+            ),
+            (
+                "fn test(a: bool) -> i64 {
+                    if (a) {f();};
+                    return 0;
+                }
+                fn f() {
+                    return;
+                }",
+                vec!["L1-4: $main::test has 4 basic blocks, which exceeds the limit of 3"],
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
+
+            let lints: Vec<_> = check_complexity(&module, &limits)
+                .iter()
+                .map(|l| l.fmt(&sm, &table).unwrap())
+                .collect();
+            assert_eq!(lints, expected, "{}", text);
+        }
+    }
+
+    #[test]
+    pub fn test_measure_complexity() {
+        let text = "fn test(a: i64) -> i64 {
+            let mut i: i64 := 0;
+            while (i < a) {
+                mut i := i + 1;
+            };
+            return if (i > 10) {10} else {i};
+        }";
+
+        let mut sm = SourceMap::new();
+        sm.add_string(&text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let mut table = StringTable::new();
+        let main = table.insert("main".into());
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+
+        let parser = Parser::new(&logger);
+        let ast = parser.parse(main, &tokens).unwrap().unwrap();
+        let module = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
+
+        let routines = measure(&module);
+        assert_eq!(routines.len(), 1);
+        assert_eq!(
+            routines[0].2,
+            Complexity {
+                nodes: 22,
+                depth: 4,
+                basic_blocks: 7,
+                locals: 2,
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_trailing_comma() {
+        for (text, expected) in vec![
+            (
+                "fn test(a: i64, b: i64,) -> i64 {
+                    return add(a, b,);
+                }",
+                vec![
+                    "L1: Trailing comma at the end of a list",
+                    "L2: Trailing comma at the end of a list",
+                ],
+            ),
+            (
+                "struct S {
+                    a: i64,
+                }
+                fn test() -> [i64; 2] {
+                    let s: S := S{a: 1,};
+                    return [s.a, 2,];
+                }",
+                vec![
+                    "L2: Trailing comma at the end of a list",
+                    "L5: Trailing comma at the end of a list",
+                    "L6: Trailing comma at the end of a list",
+                ],
+            ),
+            (
+                "extern fn printf(fmt: *const i8, ...);
+                fn test(a: i64, b: i64) -> [i64; 2] {
+                    return [a, b];
+                }",
+                vec![],
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let lints: Vec<_> = lint_trailing_commas(&tokens)
+                .iter()
+                .map(|l| l.fmt(&sm, &table).unwrap())
+                .collect();
+            assert_eq!(lints, expected, "{}", text);
+        }
+    }
+
+    #[test]
+    pub fn test_mut_assignment() {
+        for (text, expected) in vec![
+            (
+                "fn test() -> i64 {
+                    let mut x: i64 := 0;
+                    mut x := 1;
+                    return x;
+                }",
+                vec!["L3: `mut <exp> := <value>` is the old assignment syntax, use `<exp> = <value>`"],
+            ),
+            (
+                "fn test(p: *mut i64) -> i64 {
+                    let mut x: i64 := 0;
+                    let q: *mut i64 := @mut x;
+                    x = 1;
+                    return x;
+                }",
+                vec![],
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let lints: Vec<_> = lint_mut_assignments(&tokens)
+                .iter()
+                .map(|l| l.fmt(&sm, &table).unwrap())
+                .collect();
+            assert_eq!(lints, expected, "{}", text);
+        }
+    }
+}
@@ -1,2 +1,7 @@
+mod dead_branch;
+mod explain;
+mod format;
+mod lint;
+mod query;
 mod stack;
 mod type_resolver;
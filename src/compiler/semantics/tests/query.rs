@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod query_tests {
+    use crate::{
+        compiler::{
+            ast::*,
+            diagnostics::Logger,
+            lexer::tokens::Token,
+            lexer::LexerError,
+            parser::{Parser, ParserContext},
+            semantics::query::SemanticQueries,
+            CompilerDisplay, CompilerError, Lexer, SourceMap,
+        },
+        StringTable,
+    };
+
+    type LResult = std::result::Result<Vec<Token>, CompilerError<LexerError>>;
+
+    fn parse(text: &str, table: &StringTable, logger: &Logger) -> Module<ParserContext> {
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let main = table.insert(MAIN_MODULE.into());
+        let tokens: Vec<Token> = Lexer::new(src, table, logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+
+        let parser = Parser::new(logger);
+        parser.parse(main, &tokens).unwrap().unwrap()
+    }
+
+    fn item_path(table: &StringTable, name: &str) -> Path {
+        vec![
+            Element::CanonicalRoot,
+            Element::Id(table.insert(MAIN_MODULE.into())),
+            Element::Id(table.insert(name.into())),
+        ]
+        .into()
+    }
+
+    const ORIGINAL: &str = "
+        fn a() -> i64 { return b(); }
+        fn b() -> i64 { return 1; }
+        fn c() -> i64 { return 1; }";
+
+    #[test]
+    fn test_type_of_is_memoized() {
+        let table = StringTable::new();
+        let logger = Logger::new();
+        let ast = parse(ORIGINAL, &table, &logger);
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+
+        let mut queries =
+            SemanticQueries::new(&ast, main_mod, main_fn, &[], &table, &logger).unwrap();
+        let a = item_path(&table, "a");
+        let b = item_path(&table, "b");
+
+        assert_eq!(
+            queries.item_paths(),
+            vec![a.clone(), b.clone(), item_path(&table, "c")]
+        );
+        assert!(!queries.is_cached(&a));
+
+        let item = queries.type_of(&a).unwrap().as_ref().unwrap();
+        assert_eq!(item.to_routine().unwrap().context().ty(), &Type::I64);
+        assert!(queries.is_cached(&a));
+        assert!(!queries.is_cached(&b));
+
+        assert!(queries.type_of(&item_path(&table, "d")).is_none());
+    }
+
+    #[test]
+    fn test_symbol_table_of() {
+        let table = StringTable::new();
+        let logger = Logger::new();
+        let ast = parse(ORIGINAL, &table, &logger);
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+
+        let queries = SemanticQueries::new(&ast, main_mod, main_fn, &[], &table, &logger).unwrap();
+        let module: Path = vec![Element::CanonicalRoot, Element::Id(main_mod)].into();
+        let sym = queries.symbol_table_of(&module).unwrap();
+        assert!(sym.get(table.insert("a".into())).is_some());
+        assert!(sym.get(table.insert("b".into())).is_some());
+    }
+
+    #[test]
+    fn test_update_invalidates_changed_items() {
+        let table = StringTable::new();
+        let logger = Logger::new();
+        let ast = parse(ORIGINAL, &table, &logger);
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+
+        let mut queries =
+            SemanticQueries::new(&ast, main_mod, main_fn, &[], &table, &logger).unwrap();
+        let paths = queries.item_paths();
+        for p in &paths {
+            assert!(queries.type_of(p).unwrap().is_ok());
+        }
+
+        // Changing the body of `b` only invalidates `b`
+        let edited = parse(
+            "
+        fn a() -> i64 { return b(); }
+        fn b() -> i64 { return 2; }
+        fn c() -> i64 { return 1; }",
+            &table,
+            &logger,
+        );
+        let invalidated = queries.update(&edited).unwrap();
+        assert_eq!(invalidated, vec![item_path(&table, "b")]);
+        assert!(queries.is_cached(&item_path(&table, "a")));
+        assert!(queries.is_cached(&item_path(&table, "c")));
+
+        // Changing the signature of `b` invalidates every item
+        for p in &paths {
+            queries.type_of(p);
+        }
+        let edited = parse(
+            "
+        fn a() -> i64 { return b(); }
+        fn b() -> bool { return true; }
+        fn c() -> i64 { return 1; }",
+            &table,
+            &logger,
+        );
+        let invalidated = queries.update(&edited).unwrap();
+        assert_eq!(invalidated.len(), 3);
+        assert!(paths.iter().all(|p| !queries.is_cached(p)));
+
+        let sm = SourceMap::new();
+        let err = queries
+            .type_of(&item_path(&table, "a"))
+            .unwrap()
+            .as_ref()
+            .unwrap_err()
+            .fmt(&sm, &table)
+            .unwrap();
+        assert!(err.contains("bool"), "{}", err);
+    }
+}
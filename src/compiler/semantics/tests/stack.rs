@@ -3,7 +3,7 @@ mod stack_tests {
     use super::super::super::*;
     use crate::{
         compiler::{
-            ast::{Element, Module, Type},
+            ast::{build, Element, Module, Type},
             parser::ParserContext,
             semantics::{semanticnode::SemanticContext, symbol_table::SymbolTable},
             Span,
@@ -16,12 +16,21 @@ mod stack_tests {
         ParserContext::new(Span::zero())
     }
 
+    /// An empty root module named `test`
+    fn root(table: &StringTable) -> Module<SemanticContext> {
+        build::Builder::new(table, || {
+            SemanticContext::new_module(1, new_ctx(), table.insert("test".into()))
+        })
+        .module("test")
+        .build()
+    }
+
     #[test]
     fn test_empty_stack_to_path() {
         let table = StringTable::new();
         let test = table.insert("test".into());
 
-        let m = Module::new(test, SemanticContext::new_module(1, new_ctx(), test));
+        let m = root(&table);
         let mut stack = SymbolTableScopeStack::new(&m, &vec![]);
         let sym = SymbolTable::new_module(test);
         stack.enter_scope(sym);
@@ -38,7 +47,7 @@ mod stack_tests {
         let test = table.insert("test".into());
         let inner = table.insert("inner".into());
 
-        let m = Module::new(test, SemanticContext::new_module(1, new_ctx(), test));
+        let m = root(&table);
         let mut stack = SymbolTableScopeStack::new(&m, &vec![]);
         let sym = SymbolTable::new_module(test);
         stack.enter_scope(sym);
@@ -56,7 +65,7 @@ mod stack_tests {
         let test = table.insert("test".into());
         let inner = table.insert("inner".into());
 
-        let m = Module::new(test, SemanticContext::new_module(1, new_ctx(), test));
+        let m = root(&table);
         let mut stack = SymbolTableScopeStack::new(&m, &vec![]);
         let sym = SymbolTable::new_module(test);
         stack.enter_scope(sym);
@@ -82,7 +91,7 @@ mod stack_tests {
         let test_mod = table.insert("test_mod".into());
         let inner = table.insert("inner".into());
 
-        let m = Module::new(test, SemanticContext::new_module(1, new_ctx(), test));
+        let m = root(&table);
         let mut stack = SymbolTableScopeStack::new(&m, &vec![]);
         let sym = SymbolTable::new_module(test);
         stack.enter_scope(sym);
@@ -108,7 +117,7 @@ mod stack_tests {
         let test_mod = table.insert("test_mod".into());
         let inner = table.insert("inner".into());
 
-        let m = Module::new(test, SemanticContext::new_module(1, new_ctx(), test));
+        let m = root(&table);
         let mut stack = SymbolTableScopeStack::new(&m, &vec![]);
         let sym = SymbolTable::new_module(test);
         stack.enter_scope(sym);
@@ -127,7 +136,7 @@ mod stack_tests {
         let test = table.insert("test".into());
         let test_mod = table.insert("test_mod".into());
 
-        let m = Module::new(test, SemanticContext::new_module(1, new_ctx(), test));
+        let m = root(&table);
         let mut stack = SymbolTableScopeStack::new(&m, &vec![]);
         let sym = SymbolTable::new_module(test);
         stack.enter_scope(sym);
@@ -144,7 +153,7 @@ mod stack_tests {
         let test = table.insert("test".into());
         let inner = table.insert("inner".into());
 
-        let m = Module::new(test, SemanticContext::new_module(1, new_ctx(), test));
+        let m = root(&table);
         let mut stack = SymbolTableScopeStack::new(&m, &vec![]);
         let sym = SymbolTable::new_module(test);
         stack.enter_scope(sym);
@@ -171,7 +180,7 @@ mod stack_tests {
         let first = table.insert("first".into());
         let second = table.insert("second".into());
 
-        let m = Module::new(test, SemanticContext::new_module(1, new_ctx(), test));
+        let m = root(&table);
         let mut stack = SymbolTableScopeStack::new(&m, &vec![]);
         let sym = SymbolTable::new_module(test);
         stack.enter_scope(sym);
@@ -206,7 +215,7 @@ mod stack_tests {
         let first = table.insert("first".into());
         let x = table.insert("x".into());
 
-        let m = Module::new(test, SemanticContext::new_module(1, new_ctx(), test));
+        let m = root(&table);
         let mut stack = SymbolTableScopeStack::new(&m, &vec![]);
         let sym = SymbolTable::new_module(test);
         stack.enter_scope(sym);
@@ -232,7 +241,7 @@ mod stack_tests {
         let first = table.insert("first".into());
         let x = table.insert("x".into());
 
-        let m = Module::new(test, SemanticContext::new_module(1, new_ctx(), test));
+        let m = root(&table);
         let mut stack = SymbolTableScopeStack::new(&m, &vec![]);
         let sym = SymbolTable::new_module(test);
         stack.enter_scope(sym);
@@ -262,7 +271,7 @@ mod stack_tests {
         let x = table.insert("x".into());
         let my_func = table.insert("my_func".into());
 
-        let m = Module::new(test, SemanticContext::new_module(1, new_ctx(), test));
+        let m = root(&table);
         let mut stack = SymbolTableScopeStack::new(&m, &vec![]);
         let sym = SymbolTable::new_module(test);
         stack.enter_scope(sym);
@@ -302,7 +311,7 @@ mod stack_tests {
         let third = table.insert("third".into());
         let x = table.insert("x".into());
 
-        let m = Module::new(test, SemanticContext::new_module(1, new_ctx(), test));
+        let m = root(&table);
         let mut stack = SymbolTableScopeStack::new(&m, &vec![]);
         let sym = SymbolTable::new_module(test);
         stack.enter_scope(sym);
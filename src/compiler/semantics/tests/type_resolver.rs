@@ -4,9 +4,10 @@ mod type_resolver_tests {
         compiler::{
             ast::*,
             diagnostics::Logger,
+            fuel::Fuel,
             lexer::tokens::Token,
             lexer::LexerError,
-            parser::{Parser, ParserContext},
+            parser::{number_nodes, Parser, ParserContext},
             semantics::semanticnode::SemanticContext,
             CompilerDisplay, CompilerError, Lexer, SourceMap, Span,
         },
@@ -160,7 +161,7 @@ mod type_resolver_tests {
                 .parse(test, &tokens)
                 .expect(&format!("{}", text))
                 .unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
@@ -233,7 +234,7 @@ mod type_resolver_tests {
                         return;
                     }
                 }",
-                Err("L4: Could not find item with the given path: $test::my_mod::my_mod::test ($test::my_mod::my_mod::test)"),
+                Err("L4: Could not find item with the given path: my_mod::test ($test::my_mod::my_mod::test)"),
             ),
         ] {
             println!("Test: {}", ln);
@@ -258,6 +259,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
             let result = resolve_types(
                 &ast,
                 main_mod, main_fn,
+                &table,
                 &logger,
             );
             match expected {
@@ -267,6 +269,111 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
         }
     }
 
+    #[test]
+    pub fn test_path_with_super() {
+        for (ln, text, expected) in vec![
+            (
+                line!(),
+                "fn test() -> i64 {
+                    return 0;
+                }
+                mod my_mod {
+                    fn main() {
+                        let i: i64 := super::test();
+                        let j: i64 := self::super::test();
+                        let k: i64 := root::my_mod::super::test();
+                        let l: i64 := project::test::my_mod::super::test();
+                        return;
+                    }
+                }",
+                Ok(()),
+            ),
+            (
+                line!(),
+                "fn test() -> i64 {
+                    return 0;
+                }
+                mod my_mod {
+                    fn main() {
+                        let i: i64 := inner::super::super::test();
+                        return;
+                    }
+                    mod inner {
+                        fn main() {
+                            let i: i64 := super::super::test();
+                            let j: i64 := super::inner::super::super::test();
+                            return;
+                        }
+                    }
+                }",
+                Ok(()),
+            ),
+            (
+                line!(),
+                "fn test() -> i64 {
+                    let i: i64 := super::test();
+                    return i;
+                }",
+                Err("L2: super cannot refer to the parent of the root module"),
+            ),
+            (
+                line!(),
+                "fn test() -> i64 {
+                    let i: i64 := root::super::test();
+                    return i;
+                }",
+                Err("L2: super cannot refer to the parent of the root module"),
+            ),
+            (
+                line!(),
+                "fn test() -> i64 {
+                    let i: i64 := project::test::super::test();
+                    return i;
+                }",
+                Err("L2: super cannot refer to the parent of the root module"),
+            ),
+            (
+                line!(),
+                "fn test() -> i64 {
+                    return 0;
+                }
+                mod my_mod {
+                    fn main() {
+                        let i: i64 := super::super::test();
+                        return;
+                    }
+                }",
+                Err("L6: super cannot refer to the parent of the root module"),
+            ),
+        ] {
+            println!("Test: {}", ln);
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let test = table.insert("test".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(test, &tokens).unwrap().unwrap();
+            let result = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(_) => assert!(result.is_ok(), "{:?} got {:?}", expected, result),
+                Err(msg) => assert_eq!(result.err().unwrap().fmt(&sm, &table).unwrap(), msg),
+            }
+        }
+    }
+
     #[test]
     pub fn test_path_to_function_in_different_module() {
         for (text,) in vec![
@@ -323,11 +430,114 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(test, &tokens).unwrap().unwrap();
-            let result = resolve_types(&ast, main_mod, main_fn, &logger);
+            let result = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             assert!(result.is_ok());
         }
     }
 
+    #[test]
+    pub fn test_use_declarations() {
+        let lib = "mod std {
+                    mod io {
+                        fn write(x: i64) -> i64 { return x; }
+                        struct Point { pub x: i64 }
+                    }
+                }
+                ";
+        for (ln, text, expected) in vec![
+            (
+                line!(),
+                "mod main_mod {
+                    use root::std::io::write;
+                    fn main() {
+                        let j: i64 := write(1);
+                        return;
+                    }
+                }",
+                Ok(()),
+            ),
+            (
+                line!(),
+                "mod main_mod {
+                    use super::std::io;
+                    fn main() {
+                        let j: i64 := io::write(1);
+                        let p: io::Point := io::Point{x: j};
+                        return;
+                    }
+                }",
+                Ok(()),
+            ),
+            (
+                line!(),
+                "mod main_mod {
+                    use root::std::io::Point;
+                    fn get(p: Point) -> Point {
+                        return Point{x: p.x};
+                    }
+                }",
+                Ok(()),
+            ),
+            (
+                line!(),
+                "mod main_mod {
+                    use root::std::io::write;
+                    mod inner {
+                        fn main() {
+                            let j: i64 := write(1);
+                            return;
+                        }
+                    }
+                }",
+                Err("L11: Could not find item with the given path: write ($test::main_mod::inner::write)"),
+            ),
+            (
+                line!(),
+                "mod main_mod {
+                    use root::std::io::read;
+                }",
+                Err("L8: use root::std::io::read does not refer to an item or module"),
+            ),
+            (
+                line!(),
+                "mod main_mod {
+                    use root::std::io::write;
+                    fn write() {
+                        return;
+                    }
+                }",
+                Err("L8: write already declared\n    L9-11: note: previous declaration here"),
+            ),
+        ] {
+            println!("Test: {}", ln);
+            let text = format!("{}{}", lib, text);
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let test = table.insert("test".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(test, &tokens).unwrap().unwrap();
+            let result = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(_) => assert!(result.is_ok(), "{:?} got {:?}", expected, result),
+                Err(msg) => assert_eq!(result.err().unwrap().fmt(&sm, &table).unwrap(), msg),
+            }
+        }
+    }
+
     #[test]
     pub fn test_path_to_struct() {
         for (text, expected) in vec![
@@ -352,7 +562,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                         return;
                     }
                 }",
-                Err("L4: Could not find item with the given path: $test::my_mod::my_mod::test ($test::my_mod::my_mod::test)"),
+                Err("L4: Could not find item with the given path: my_mod::test ($test::my_mod::my_mod::test)"),
             ),
         ] {
             let mut sm = SourceMap::new();
@@ -377,6 +587,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 &ast,
                 main_mod,
                 main_fn,
+                &table,
                 &logger,
             );
             match expected {
@@ -437,14 +648,14 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(test, &tokens).unwrap().unwrap();
-            let result = resolve_types(&ast, main_mod, main_fn, &logger).unwrap();
+            let result = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
             if let Item::Routine(RoutineDef { body, .. }) = &result.get_functions()[0] {
                 if let Statement::Bind(b) = &body[0] {
                     if let Expression::StructExpression(_, struct_name, ..) = b.get_rhs() {
                         let expected: Path =
                             vec![Element::CanonicalRoot, Element::Id(test), Element::Id(test)]
                                 .into();
-                        assert_eq!(struct_name, &expected)
+                        assert_eq!(**struct_name, expected)
                     } else {
                         panic!("Not a struct expression")
                     }
@@ -501,7 +712,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match (expected, module) {
                 (Ok(expected_ty), Ok(actual)) => {
                     let fn_main = actual.get_functions()[0].to_routine().unwrap();
@@ -567,7 +778,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let result = resolve_types(&ast, main_mod, main_fn, &logger).unwrap();
+            let result = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
             if let Item::Routine(RoutineDef { params, .. }) = &result.get_functions()[0] {
                 if let Parameter {
                     ty: Type::Custom(ty_path),
@@ -615,7 +826,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let result = resolve_types(&ast, main_mod, main_fn, &logger).unwrap();
+            let result = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
             if let Item::Struct(s) = &result.get_structs()[1] {
                 let fields = s.get_fields();
                 if let Type::Custom(ty_path) = &fields[0].ty {
@@ -814,7 +1025,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     assert!(module.is_ok(), "Test Case at L:{}", line);
@@ -1058,7 +1269,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
@@ -1100,7 +1311,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                     let k: i64 := \"hello\" + 5;
                     return k + 3;
                 }",
-                Err("L2: + expected i64 but found string and i64"),
+                Err("L2: + expected String but found string and i64"),
             ),
             (
                 "fn main() -> bool {
@@ -1129,7 +1340,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
@@ -1211,7 +1422,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
@@ -1244,100 +1455,42 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
     }
 
     #[test]
-    pub fn test_null_usage() {
+    pub fn test_arithmetic_builtins() {
         for (text, expected) in vec![
             (
-                "fn main() -> i64 {
-                    let p: *const i64 := null;
-                    return 0;
-                }",
-                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
-            ),
-            (
-                "fn main() -> i64 {
-                    let p: *mut i64 := null;
-                    return 0;
-                }",
-                Ok(Type::RawPointer(PointerMut::Mut, Box::new(Type::I64))),
-            ),
-            (
-                "fn main() -> i64 {
-                    let mut p: *const i64 := null;
-                    mut p := null;
-                    return 0;
-                }",
-                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
-            ),
-            (
-                "fn main() -> i64 {
-                    let mut p: *mut i64 := null;
-                    mut p := null;
-                    return 0;
-                }",
-                Ok(Type::RawPointer(PointerMut::Mut, Box::new(Type::I64))),
-            ),
-            (
-                "fn main() -> bool {
-                    let mut p: *mut i64 := null;
-                    return p == null;
-                }",
-                Ok(Type::RawPointer(PointerMut::Mut, Box::new(Type::I64))),
-            ),
-            (
-                "fn main() -> bool {
-                    let p: *const i64 := null;
-                    return p != null;
-                }",
-                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
-            ),
-            (
-                "fn main() -> bool {
-                    let p: *const i64 := null;
-                    return null <= p;
+                "fn main() -> u8 {
+                    let k: u8 := wrapping_add(250u8, 10u8);
+                    return saturating_sub(k, 5u8);
                 }",
-                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
+                Ok(Type::U8),
             ),
             (
-                "fn main() -> bool {
-                    let p: *const i64 := null;
-                    return null < p;
+                "fn main() -> i32 {
+                    let k: i32 := saturating_add(1i32, 5i32);
+                    return wrapping_mul(k, wrapping_sub(k, 2i32));
                 }",
-                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
+                Ok(Type::I32),
             ),
             (
-                "fn main() -> bool {
-                    let p: *const i64 := null;
-                    return null >= p;
+                "fn main() -> u8 {
+                    let k: u8 := wrapping_add(250u8, 10i8);
+                    return k;
                 }",
-                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
+                Err("L2: wrapping_add expected u8 but found u8 and i8"),
             ),
             (
-                "fn main() -> bool {
-                    let p: *const i64 := null;
-                    return null > p;
+                "fn main() -> f64 {
+                    let k: f64 := saturating_add(1.0, 2.0);
+                    return k;
                 }",
-                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
+                Err("L2: saturating_add expected i64 but found f64 and f64"),
             ),
             (
                 "fn main() -> bool {
-                    let k: *const i64 := null;
-                    return null == 0;
-                }",
-                Err("L3: == expected null but found null and i64"),
-            ),
-            (
-                "fn main() -> i64 {
-                    let k: i64 := ^null;
-                    return 0;
-                }",
-                Err("L2: ^ expected a *mut or *const but found null"),
-            ),
-            (
-                "fn main() -> i64 {
-                    mut ^null := 0;
-                    return 0;
+                    let k: bool := wrapping_sub(true, false);
+                    return k;
                 }",
-                Err("L2: ^ expected a *mut or *const but found null"),
+                Err("L2: wrapping_sub expected i64 but found bool and bool"),
             ),
         ] {
             let mut sm = SourceMap::new();
@@ -1359,105 +1512,67 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
                     let fn_main = module.get_functions()[0].to_routine().unwrap();
 
-                    // validate that the RHS of the bind is the correct type
-                    let bind_stm = &fn_main.get_body()[0];
-                    if let Statement::Bind(b) = bind_stm {
-                        assert_eq!(b.get_type(), expected_ty);
-                    } else {
-                        panic!("Expected a bind statement");
-                    }
+                    // Validate that the return statement is the correct type
+                    let ret_stm = &fn_main.get_body()[1];
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
                 }
                 Err(msg) => {
-                    assert_eq!(
-                        module.unwrap_err().fmt(&sm, &table).unwrap(),
-                        msg,
-                        "{}",
-                        text
-                    );
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
                 }
             }
         }
     }
 
     #[test]
-    pub fn test_pointer_offset_op() {
+    pub fn test_byte_builtins() {
         for (text, expected) in vec![
             (
-                "fn main() -> i64 {
-                    let k: i64 := 1;
-                    let p: *const i64 := @const k;
-                    let pp: *const i64 := p@2;
-                    return 0;
-                }",
-                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
-            ),
-            (
-                "fn main() -> i64 {
-                    let mut k: i64 := 1;
-                    let p: *mut i64 := @mut k;
-                    let pp: *mut i64 := p@2;
-                    return 0;
-                }",
-                Ok(Type::RawPointer(PointerMut::Mut, Box::new(Type::I64))),
-            ),
-            (
-                "fn main() -> i64 {
-                    let mut k: i64 := 1;
-                    let p: *mut i64 := @mut k;
-                    let pp: *mut i64 := p@k;
-                    return 0;
+                "fn main() -> u32 {
+                    let k: [u8; 4] := to_le_bytes(5i32);
+                    return from_le_bytes(to_be_bytes(from_le_bytes(k)));
                 }",
-                Ok(Type::RawPointer(PointerMut::Mut, Box::new(Type::I64))),
+                Ok(Type::U32),
             ),
             (
-                "fn main() -> i64 {
-                    let mut k: i64 := 1;
-                    let p: *mut i64 := @mut k;
-                    let pp: *mut i64 := p@(-2 - 3 * 4);
-                    return 0;
+                "fn main() -> u16 {
+                    let k: [u8; 2] := [1u8, 2u8];
+                    return from_be_bytes(k);
                 }",
-                Ok(Type::RawPointer(PointerMut::Mut, Box::new(Type::I64))),
+                Ok(Type::U16),
             ),
             (
-                "fn main() -> i64 {
-                    let mut k: i64 := 1;
-                    let p: *mut i64 := @mut k;
-                    let pp: *const i64 := (p@(-2 - 3 * 4))@k;
-                    return 0;
+                "fn main() -> u64 {
+                    let k: usize := 1 as usize;
+                    return from_le_bytes(to_le_bytes(k));
                 }",
-                Err("L4: Bind expected *const i64 but got *mut i64"),
+                Err("L3: to_le_bytes expected an integer with a fixed width (e.g. u32 or i64) but found usize"),
             ),
             (
-                "fn main() -> i64 {
-                    let k: i64 := 1;
-                    let p: *const i64 := @const k;
-                    let pp: *mut i64 := p@2;
-                    return 0;
+                "fn main() -> u64 {
+                    let k: bool := true;
+                    return from_be_bytes(to_be_bytes(k));
                 }",
-                Err("L4: Bind expected *mut i64 but got *const i64"),
+                Err("L3: to_be_bytes expected an integer with a fixed width (e.g. u32 or i64) but found bool"),
             ),
             (
-                "fn main() -> i64 {
-                    let mut k: i64 := 1;
-                    let p: *mut i64 := @mut k;
-                    let pp: *mut i64 := p@2.0;
-                    return 0;
+                "fn main() -> u32 {
+                    let k: [u8; 3] := [1u8, 2u8, 3u8];
+                    return from_le_bytes(k);
                 }",
-                Err("L4: @ operator expects integer on right side, but got f64"),
+                Err("L3: from_le_bytes expected [u8; 1], [u8; 2], [u8; 4], or [u8; 8] but found [u8; 3]"),
             ),
             (
-                "fn main() -> bool {
-                    let k: i64 := 1;
-                    let p: *const i64 := k@2;
-                    return k + 3;
+                "fn main() -> u16 {
+                    let k: [i8; 2] := [1i8, 2i8];
+                    return from_be_bytes(k);
                 }",
-                Err("L3: @ operator expects raw pointer on left side, but got i64"),
+                Err("L3: from_be_bytes expected [u8; 1], [u8; 2], [u8; 4], or [u8; 8] but found [i8; 2]"),
             ),
         ] {
             let mut sm = SourceMap::new();
@@ -1479,62 +1594,59 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
                     let fn_main = module.get_functions()[0].to_routine().unwrap();
 
-                    // validate that the RHS of the bind is the correct type
-                    let bind_stm = &fn_main.get_body()[2];
-                    if let Statement::Bind(b) = bind_stm {
-                        assert_eq!(b.get_rhs().get_type(), expected_ty);
-                    } else {
-                        panic!("Expected a bind statement");
-                    }
+                    // Validate that the return statement is the correct type
+                    let ret_stm = &fn_main.get_body()[1];
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
                 }
                 Err(msg) => {
-                    assert_eq!(
-                        module.unwrap_err().fmt(&sm, &table).unwrap(),
-                        msg,
-                        "{}",
-                        text
-                    );
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
                 }
             }
         }
     }
 
     #[test]
-    pub fn test_boolean_and_op() {
+    pub fn test_return_in_expression_block() {
         for (text, expected) in vec![
             (
-                "fn main() -> bool {
-                    let k: bool := true && false;
-                    return k && true;
+                "fn test(b: bool) -> i64 {
+                    if (b) {
+                        return 1;
+                    };
+                    return 0;
                 }",
-                Ok(Type::Bool),
+                Ok(()),
             ),
             (
-                "fn main() -> bool {
-                    let k: bool := true && 1;
-                    return k && true;
+                "fn test(b: bool) -> i64 {
+                    while (b) {
+                        if (b) {return 1;} else {return 2;};
+                    };
+                    return 0;
                 }",
-                Err("L2: && expected bool but found bool and i64"),
+                Ok(()),
             ),
             (
-                "fn main() -> bool {
-                    let k: bool := \"hello\" && false;
-                    return k && true;
+                "fn test(b: bool) -> i64 {
+                    if (b) {
+                        return true;
+                    };
+                    return 0;
                 }",
-                Err("L2: && expected bool but found string and bool"),
+                Err("L3: Return expected i64 but got bool"),
             ),
             (
-                "fn main() -> bool {
-                    let k: i64 := 5;
-                    return k && true;
+                "fn test(b: bool) {
+                    let x: i64 := {return; 5};
+                    return;
                 }",
-                Err("L3: && expected bool but found i64 and bool"),
+                Ok(()),
             ),
         ] {
             let mut sm = SourceMap::new();
@@ -1556,68 +1668,51 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let result = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
-                Ok(expected_ty) => {
-                    let module = module.unwrap();
-                    let fn_main = module.get_functions()[0].to_routine().unwrap();
-
-                    let bind_stm = &fn_main.get_body()[0];
-                    assert_eq!(bind_stm.context().ty(), Type::Bool);
-
-                    // validate that the RHS of the bind is the correct type
-                    if let Statement::Bind(b) = bind_stm {
-                        assert_eq!(b.get_rhs().get_type(), expected_ty);
-                    } else {
-                        panic!("Expected a bind statement");
-                    }
-
-                    // Validate that the return statement is the correct type
-                    let ret_stm = &fn_main.get_body()[1];
-                    assert_eq!(ret_stm.context().ty(), expected_ty);
-                    if let Statement::Return(r) = ret_stm {
-                        assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
-                    } else {
-                        panic!("Expected a return statement")
-                    }
-                }
-                Err(msg) => {
-                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
-                }
+                Ok(_) => assert!(result.is_ok(), "{} -> {:?}", text, result),
+                Err(msg) => assert_eq!(result.unwrap_err().fmt(&sm, &table).unwrap(), msg),
             }
         }
     }
 
     #[test]
-    pub fn test_boolean_or_op() {
+    pub fn test_never_type() {
         for (text, expected) in vec![
             (
-                "fn main() -> bool {
-                    let k: bool := true || false;
-                    return k || true;
+                "fn test(b: bool) -> i64 {
+                    let x: i64 := if (b) {5} else {return 1;};
+                    return x;
                 }",
-                Ok(Type::Bool),
+                Ok(Type::I64),
             ),
             (
-                "fn main() -> bool {
-                    let k: bool := true || 1;
-                    return k || true;
+                "fn test(b: bool) -> i64 {
+                    let x: i64 := if (b) {return 1;} else {5};
+                    return x;
                 }",
-                Err("L2: || expected bool but found bool and i64"),
+                Ok(Type::I64),
             ),
             (
-                "fn main() -> bool {
-                    let k: bool := \"hello\" || false;
-                    return k || true;
+                "fn test(b: bool) -> i64 {
+                    let x: i64 := if (b) {return 1;} else {return 2;};
+                    return x;
                 }",
-                Err("L2: || expected bool but found string and bool"),
+                Ok(Type::Never),
             ),
             (
-                "fn main() -> bool {
-                    let k: i64 := 5;
-                    return k || true;
+                "fn test(b: bool) -> i64 {
+                    let x: i64 := if (b) {return 1;};
+                    return x;
                 }",
-                Err("L3: || expected bool but found i64 and bool"),
+                Err("L2: Bind expected i64 but got unit"),
+            ),
+            (
+                "fn test(b: bool) -> i64 {
+                    let x: i64 := if (b) {true} else {return 1;};
+                    return x;
+                }",
+                Err("L2: Bind expected i64 but got bool"),
             ),
         ] {
             let mut sm = SourceMap::new();
@@ -1639,144 +1734,1567 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let result = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
-                    let module = module.unwrap();
+                    let module = result.unwrap();
                     let fn_main = module.get_functions()[0].to_routine().unwrap();
-
-                    let bind_stm = &fn_main.get_body()[0];
-                    assert_eq!(bind_stm.context().ty(), Type::Bool);
-
-                    // validate that the RHS of the bind is the correct type
-                    if let Statement::Bind(b) = bind_stm {
-                        assert_eq!(b.get_rhs().get_type(), expected_ty);
-                    } else {
-                        panic!("Expected a bind statement");
-                    }
-
-                    // Validate that the return statement is the correct type
-                    let ret_stm = &fn_main.get_body()[1];
-                    assert_eq!(ret_stm.context().ty(), expected_ty);
-                    if let Statement::Return(r) = ret_stm {
-                        assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
+                    if let Statement::Bind(b) = &fn_main.get_body()[0] {
+                        assert_eq!(b.get_rhs().get_type(), expected_ty, "{}", text);
                     } else {
-                        panic!("Expected a return statement")
+                        panic!("Expected a bind statement")
                     }
                 }
-                Err(msg) => {
-                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
-                }
+                Err(msg) => assert_eq!(result.unwrap_err().fmt(&sm, &table).unwrap(), msg),
             }
         }
     }
 
     #[test]
-    pub fn test_comparison_op() {
-        for op in vec!["<", ">", "<=", ">=", "==", "!="] {
-            for (text, expected) in vec![
-                (
-                    String::from(&format!(
-                        "fn main() -> bool {{
-                            let k: bool := 1 {} 5;
-                            return k;
-                        }}",
-                        op
-                    )),
-                    Ok(Type::Bool),
-                ),
-                (
-                    String::from(&format!(
-                        "fn main() -> bool {{
-                            let k: bool := 1 {} true;
-                            return k;
-                        }}",
-                        op
-                    )),
-                    Err(format!("L2: {} expected i64 but found i64 and bool", op)),
-                ),
-                (
-                    String::from(&format!(
-                        "fn main() -> bool {{
-                            let k: bool := false {} 5;
-                            return k;
-                        }}",
-                        op
-                    )),
-                    Err(format!("L2: {} expected bool but found bool and i64", op)),
-                ),
-            ] {
-                let mut sm = SourceMap::new();
-                sm.add_string(&text, "/test".into()).unwrap();
-                let src = sm.get(0).unwrap().read().unwrap();
-
-                let mut table = StringTable::new();
-                let main = table.insert("main".into());
-                let main_mod = table.insert(MAIN_MODULE.into());
-                let main_fn = table.insert("my_main".into());
+    pub fn test_redeclaration_notes() {
+        for (text, expected) in vec![
+            (
+                "fn test() -> i64 {
+                    let x: i64 := 5;
+                    let x: i64 := 10;
+                    return x;
+                }",
+                "L3: x already declared\n    L2: note: previous declaration here",
+            ),
+            (
+                "fn test(
+                    a: i64,
+                    a: bool,
+                ) -> i64 {
+                    return 0;
+                }",
+                "L3: a already declared\n    L2: note: previous declaration here",
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
 
-                let logger = Logger::new();
-                let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
-                    .unwrap()
-                    .tokenize()
-                    .into_iter()
-                    .collect::<LResult>()
-                    .unwrap();
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
 
-                let parser = Parser::new(&logger);
-                let ast = parser.parse(main, &tokens).unwrap().unwrap();
-                let module = resolve_types(&ast, main_mod, main_fn, &logger);
-                match expected {
-                    Ok(expected_ty) => {
-                        let module = module.unwrap();
-                        let fn_main = module.get_functions()[0].to_routine().unwrap();
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let result = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            let err = result.unwrap_err();
+            assert_eq!(err.fmt(&sm, &table).unwrap(), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    pub fn test_null_usage() {
+        for (text, expected) in vec![
+            (
+                "fn main() -> i64 {
+                    let p: *const i64 := null;
+                    return 0;
+                }",
+                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> i64 {
+                    let p: *mut i64 := null;
+                    return 0;
+                }",
+                Ok(Type::RawPointer(PointerMut::Mut, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> i64 {
+                    let mut p: *const i64 := null;
+                    mut p := null;
+                    return 0;
+                }",
+                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> i64 {
+                    let mut p: *mut i64 := null;
+                    mut p := null;
+                    return 0;
+                }",
+                Ok(Type::RawPointer(PointerMut::Mut, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> bool {
+                    let mut p: *mut i64 := null;
+                    return p == null;
+                }",
+                Ok(Type::RawPointer(PointerMut::Mut, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> bool {
+                    let p: *const i64 := null;
+                    return p != null;
+                }",
+                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> bool {
+                    let p: *const i64 := null;
+                    return null <= p;
+                }",
+                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> bool {
+                    let p: *const i64 := null;
+                    return null < p;
+                }",
+                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> bool {
+                    let p: *const i64 := null;
+                    return null >= p;
+                }",
+                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> bool {
+                    let p: *const i64 := null;
+                    return null > p;
+                }",
+                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> bool {
+                    let k: *const i64 := null;
+                    return null == 0;
+                }",
+                Err("L3: == expected null but found null and i64"),
+            ),
+            (
+                "fn main() -> i64 {
+                    let k: i64 := ^null;
+                    return 0;
+                }",
+                Err("L2: ^ expected a *mut or *const but found null"),
+            ),
+            (
+                "fn main() -> i64 {
+                    mut ^null := 0;
+                    return 0;
+                }",
+                Err("L2: ^ expected a *mut or *const but found null"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    // validate that the RHS of the bind is the correct type
+                    let bind_stm = &fn_main.get_body()[0];
+                    if let Statement::Bind(b) = bind_stm {
+                        assert_eq!(b.get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a bind statement");
+                    }
+                }
+                Err(msg) => {
+                    assert_eq!(
+                        module.unwrap_err().fmt(&sm, &table).unwrap(),
+                        msg,
+                        "{}",
+                        text
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_pointer_offset_op() {
+        for (text, expected) in vec![
+            (
+                "fn main() -> i64 {
+                    let k: i64 := 1;
+                    let p: *const i64 := @const k;
+                    let pp: *const i64 := p@2;
+                    return 0;
+                }",
+                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> i64 {
+                    let mut k: i64 := 1;
+                    let p: *mut i64 := @mut k;
+                    let pp: *mut i64 := p@2;
+                    return 0;
+                }",
+                Ok(Type::RawPointer(PointerMut::Mut, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> i64 {
+                    let mut k: i64 := 1;
+                    let p: *mut i64 := @mut k;
+                    let pp: *mut i64 := p@k;
+                    return 0;
+                }",
+                Ok(Type::RawPointer(PointerMut::Mut, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> i64 {
+                    let mut k: i64 := 1;
+                    let p: *mut i64 := @mut k;
+                    let pp: *mut i64 := p@(-2 - 3 * 4);
+                    return 0;
+                }",
+                Ok(Type::RawPointer(PointerMut::Mut, Box::new(Type::I64))),
+            ),
+            (
+                "fn main() -> i64 {
+                    let mut k: i64 := 1;
+                    let p: *mut i64 := @mut k;
+                    let pp: *const i64 := (p@(-2 - 3 * 4))@k;
+                    return 0;
+                }",
+                Err("L4: Bind expected *const i64 but got *mut i64"),
+            ),
+            (
+                "fn main() -> i64 {
+                    let k: i64 := 1;
+                    let p: *const i64 := @const k;
+                    let pp: *mut i64 := p@2;
+                    return 0;
+                }",
+                Err("L4: Bind expected *mut i64 but got *const i64"),
+            ),
+            (
+                "fn main() -> i64 {
+                    let mut k: i64 := 1;
+                    let p: *mut i64 := @mut k;
+                    let pp: *mut i64 := p@2.0;
+                    return 0;
+                }",
+                Err("L4: @ operator expects integer on right side, but got f64"),
+            ),
+            (
+                "fn main() -> bool {
+                    let k: i64 := 1;
+                    let p: *const i64 := k@2;
+                    return k + 3;
+                }",
+                Err("L3: @ operator expects raw pointer on left side, but got i64"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    // validate that the RHS of the bind is the correct type
+                    let bind_stm = &fn_main.get_body()[2];
+                    if let Statement::Bind(b) = bind_stm {
+                        assert_eq!(b.get_rhs().get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a bind statement");
+                    }
+                }
+                Err(msg) => {
+                    assert_eq!(
+                        module.unwrap_err().fmt(&sm, &table).unwrap(),
+                        msg,
+                        "{}",
+                        text
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_boolean_and_op() {
+        for (text, expected) in vec![
+            (
+                "fn main() -> bool {
+                    let k: bool := true && false;
+                    return k && true;
+                }",
+                Ok(Type::Bool),
+            ),
+            (
+                "fn main() -> bool {
+                    let k: bool := true && 1;
+                    return k && true;
+                }",
+                Err("L2: && expected bool but found bool and i64"),
+            ),
+            (
+                "fn main() -> bool {
+                    let k: bool := \"hello\" && false;
+                    return k && true;
+                }",
+                Err("L2: && expected bool but found string and bool"),
+            ),
+            (
+                "fn main() -> bool {
+                    let k: i64 := 5;
+                    return k && true;
+                }",
+                Err("L3: && expected bool but found i64 and bool"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    let bind_stm = &fn_main.get_body()[0];
+                    assert_eq!(bind_stm.context().ty(), Type::Bool);
+
+                    // validate that the RHS of the bind is the correct type
+                    if let Statement::Bind(b) = bind_stm {
+                        assert_eq!(b.get_rhs().get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a bind statement");
+                    }
+
+                    // Validate that the return statement is the correct type
+                    let ret_stm = &fn_main.get_body()[1];
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
+                    if let Statement::Return(r) = ret_stm {
+                        assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a return statement")
+                    }
+                }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_boolean_or_op() {
+        for (text, expected) in vec![
+            (
+                "fn main() -> bool {
+                    let k: bool := true || false;
+                    return k || true;
+                }",
+                Ok(Type::Bool),
+            ),
+            (
+                "fn main() -> bool {
+                    let k: bool := true || 1;
+                    return k || true;
+                }",
+                Err("L2: || expected bool but found bool and i64"),
+            ),
+            (
+                "fn main() -> bool {
+                    let k: bool := \"hello\" || false;
+                    return k || true;
+                }",
+                Err("L2: || expected bool but found string and bool"),
+            ),
+            (
+                "fn main() -> bool {
+                    let k: i64 := 5;
+                    return k || true;
+                }",
+                Err("L3: || expected bool but found i64 and bool"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    let bind_stm = &fn_main.get_body()[0];
+                    assert_eq!(bind_stm.context().ty(), Type::Bool);
+
+                    // validate that the RHS of the bind is the correct type
+                    if let Statement::Bind(b) = bind_stm {
+                        assert_eq!(b.get_rhs().get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a bind statement");
+                    }
+
+                    // Validate that the return statement is the correct type
+                    let ret_stm = &fn_main.get_body()[1];
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
+                    if let Statement::Return(r) = ret_stm {
+                        assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a return statement")
+                    }
+                }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_comparison_op() {
+        for op in vec!["<", ">", "<=", ">=", "==", "!="] {
+            for (text, expected) in vec![
+                (
+                    String::from(&format!(
+                        "fn main() -> bool {{
+                            let k: bool := 1 {} 5;
+                            return k;
+                        }}",
+                        op
+                    )),
+                    Ok(Type::Bool),
+                ),
+                (
+                    String::from(&format!(
+                        "fn main() -> bool {{
+                            let k: bool := 1 {} true;
+                            return k;
+                        }}",
+                        op
+                    )),
+                    Err(format!("L2: {} expected i64 but found i64 and bool", op)),
+                ),
+                (
+                    String::from(&format!(
+                        "fn main() -> bool {{
+                            let k: bool := false {} 5;
+                            return k;
+                        }}",
+                        op
+                    )),
+                    Err(format!("L2: {} expected bool but found bool and i64", op)),
+                ),
+                (
+                    String::from(&format!(
+                        "fn main() -> bool {{
+                            let k: bool := 1 {} 2 {} 3;
+                            return k;
+                        }}",
+                        op, op
+                    )),
+                    Err(format!(
+                        "L2: Comparison operators cannot be chained: the result of {} is bool, which {} cannot compare with i64\n    hint: use && to check both comparisons: a {} b && b {} c",
+                        op, op, op, op
+                    )),
+                ),
+                (
+                    String::from(&format!(
+                        "fn main() -> bool {{
+                            let k: bool := (1 {} 2) == true;
+                            return k;
+                        }}",
+                        op
+                    )),
+                    Ok(Type::Bool),
+                ),
+            ] {
+                let mut sm = SourceMap::new();
+                sm.add_string(&text, "/test".into()).unwrap();
+                let src = sm.get(0).unwrap().read().unwrap();
+
+                let mut table = StringTable::new();
+                let main = table.insert("main".into());
+                let main_mod = table.insert(MAIN_MODULE.into());
+                let main_fn = table.insert("my_main".into());
+
+                let logger = Logger::new();
+                let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                    .unwrap()
+                    .tokenize()
+                    .into_iter()
+                    .collect::<LResult>()
+                    .unwrap();
+
+                let parser = Parser::new(&logger);
+                let ast = parser.parse(main, &tokens).unwrap().unwrap();
+                let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+                match expected {
+                    Ok(expected_ty) => {
+                        let module = module.unwrap();
+                        let fn_main = module.get_functions()[0].to_routine().unwrap();
 
                         let bind_stm = &fn_main.get_body()[0];
                         assert_eq!(bind_stm.get_type(), Type::Bool);
 
-                        // validate that the RHS of the bind is the correct type
-                        if let Statement::Bind(b) = bind_stm {
-                            assert_eq!(b.get_rhs().get_type(), expected_ty);
-                        } else {
-                            panic!("Expected a bind statement");
-                        }
+                        // validate that the RHS of the bind is the correct type
+                        if let Statement::Bind(b) = bind_stm {
+                            assert_eq!(b.get_rhs().get_type(), expected_ty);
+                        } else {
+                            panic!("Expected a bind statement");
+                        }
+
+                        // Validate that the return statement is the correct type
+                        let ret_stm = &fn_main.get_body()[1];
+                        assert_eq!(ret_stm.get_type(), expected_ty);
+                        if let Statement::Return(r) = ret_stm {
+                            assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
+                        } else {
+                            panic!("Expected a return statement")
+                        }
+                    }
+                    Err(msg) => {
+                        assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_array_size_types() {
+        for ty in vec!["u64", "u32", "u16", "u8", "i64", "i32", "i16", "i8"] {
+            let text = format!(
+                "fn main() -> i64 {{
+                    let a: [i64; 2{}] := [1, 2,];
+                    return 0;
+                }}",
+                ty
+            );
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            let module = module.unwrap();
+            let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+            let bind_stm = &fn_main.get_body()[0];
+            assert_eq!(bind_stm.context().ty(), Type::Array(Box::new(Type::I64), 2));
+        }
+    }
+
+    #[test]
+    pub fn test_array_element_types() {
+        for (text, expected) in vec![
+            (
+                "fn main() -> i64 {
+                    let a: [i64; 5] := [1, 2, 3, 4, 5];
+                    let k: i64 := a[0];
+                    return k * 3;
+                }",
+                Ok(Type::I64),
+            ),
+            (
+                "fn main() -> i64 {
+                    let a: [[i64; 1]; 1] := [[1]];
+                    let k: i64 := a[0][0];
+                    return k * 3;
+                }",
+                Ok(Type::I64),
+            ),
+            (
+                "fn main() -> i8 {
+                    let a: [i8; 5] := [1i8, 2i8, 3i8, 4i8, 5i8];
+                    let k: i8 := a[0];
+                    return k * 3i8;
+                }",
+                Ok(Type::I8),
+            ),
+            (
+                "fn main() -> u8 {
+                    let a: [u8; 5] := [1u8, 2u8, 3u8, 4u8, 5u8];
+                    let k: u8 := a[0];
+                    return k * 3u8;
+                }",
+                Ok(Type::U8),
+            ),
+            (
+                "fn main() -> u16 {
+                    let a: [u16; 5] := [1u16, 2u16, 3u16, 4u16, 5u16];
+                    let k: u16 := a[0];
+                    return k * 3u16;
+                }",
+                Ok(Type::U16),
+            ),
+            (
+                "fn main() -> *const u16 {
+                    let a: [*const u16; 5] := [null, null, null, null, null];
+                    let k: *const u16 := a[0];
+                    return k;
+                }",
+                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::U16))),
+            ),
+            (
+                "fn main() -> i16 {
+                    let a: [i16; 2] := [1, 2,];
+                    let k: i16 := a[0];
+                    return k * 3i16;
+                }",
+                Err("L2: Bind expected [i16; 2] but got [i64; 2]"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    let bind_stm = &fn_main.get_body()[1];
+                    assert_eq!(bind_stm.context().ty(), expected_ty);
+
+                    // validate that the RHS of the bind is the correct type
+                    if let Statement::Bind(b) = bind_stm {
+                        assert_eq!(b.get_rhs().get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a bind statement");
+                    }
+
+                    // Validate that the return statement is the correct type
+                    let ret_stm = &fn_main.get_body()[2];
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
+                    if let Statement::Return(r) = ret_stm {
+                        assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a return statement")
+                    }
+                }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_type_cast() {
+        for (text, expected) in vec![
+            (
+                "fn main() -> i64 {
+                    let k: i32 := (1 + 5) as i32;
+                    return k as i64;
+                }",
+                Ok(vec![Type::I32, Type::I64]),
+            ),
+            (
+                "fn main() -> i64 {
+                    let k: *const i64 := 0 as *const i64;
+                    return ^k;
+                }",
+                Ok(vec![
+                    Type::RawPointer(PointerMut::Const, Box::new(Type::I64)),
+                    Type::I64,
+                ]),
+            ),
+            // Cast from *mut to *const -> Ok
+            (
+                "fn main() -> i64 {
+                    let i: *mut i64 := null;
+                    let k: *const i64 := i as *const i64;
+                    return ^k;
+                }",
+                Ok(vec![
+                    Type::RawPointer(PointerMut::Mut, Box::new(Type::I64)),
+                    Type::RawPointer(PointerMut::Const, Box::new(Type::I64)),
+                    Type::I64,
+                ]),
+            ),
+            // Cast from iX to *const -> Ok
+            (
+                "fn main() -> i64 {
+                    let k: *const i64 := 0i8 as *const i64;
+                    return ^k;
+                }",
+                Ok(vec![
+                    Type::RawPointer(PointerMut::Const, Box::new(Type::I64)),
+                    Type::I64,
+                ]),
+            ),
+            // C string literal is a string cast to *const u8 -> Ok
+            (
+                "fn main() -> u8 {
+                    let k: *const u8 := c\"hello\";
+                    return ^k;
+                }",
+                Ok(vec![
+                    Type::RawPointer(PointerMut::Const, Box::new(Type::U8)),
+                    Type::U8,
+                ]),
+            ),
+            // Cast from string to *const i64 -> Err
+            (
+                "fn main() -> i64 {
+                    let k: *const i64 := \"hello\" as *const i64;
+                    return ^k;
+                }",
+                Err("L2: Invalid type cast"),
+            ),
+            // Cast from fX to iY -> Ok
+            (
+                "fn main() -> i64 {
+                    let k: f64 := 0.2;
+                    return k as i64;
+                }",
+                Ok(vec![Type::F64, Type::I64]),
+            ),
+            // Cast from iY to fX -> Ok
+            (
+                "fn main() -> f64 {
+                    let k: i64 := 2;
+                    return k as f64;
+                }",
+                Ok(vec![Type::I64, Type::F64]),
+            ),
+            // Cast from bool to iX -> Ok
+            (
+                "fn main() -> i64 {
+                    let k: bool := true;
+                    return k as i64;
+                }",
+                Ok(vec![Type::Bool, Type::I64]),
+            ),
+            // Cast from uX to bool -> Ok
+            (
+                "fn main() -> bool {
+                    let k: u8 := 2u8;
+                    return k as bool;
+                }",
+                Ok(vec![Type::U8, Type::Bool]),
+            ),
+            // Cast from fX to bool -> Err
+            (
+                "fn main() -> bool {
+                    let k: f64 := 1.0;
+                    return k as bool;
+                }",
+                Err("L3: Invalid type cast"),
+            ),
+            // Implicit conversion from bool to iX -> Err
+            (
+                "fn main() -> i64 {
+                    let k: i64 := true;
+                    return k;
+                }",
+                Err("L2: Bind expected i64 but got bool"),
+            ),
+            // Implicit conversion from iX to bool -> Err
+            (
+                "fn main() -> bool {
+                    let k: i64 := 1;
+                    return k;
+                }",
+                Err("L3: Return expected bool but got i64"),
+            ),
+            // Cast from iX to *mut -> Err
+            (
+                "fn main() -> i64 {
+                    let k: *mut i64 := 5 as *mut i64;
+                    return ^k;
+                }
+                struct MyStruct{}
+                ",
+                Err("L2: Invalid type cast"),
+            ),
+            // Cast from *const to *mut -> Err
+            (
+                "fn main() -> i64 {
+                    let i: *const i64 := null;
+                    let k: *mut i64 := 5 as *mut i64;
+                    return ^k;
+                }
+                struct MyStruct{}
+                ",
+                Err("L3: Invalid type cast"),
+            ),
+            // Cast from fX to *m -> Err
+            (
+                "fn main() -> i64 {
+                    let k: *mut i64 := 5.0 as *mut f64;
+                    return ^k;
+                }
+                struct MyStruct{}
+                ",
+                Err("L2: Invalid type cast"),
+            ),
+            // Cast from *m to fX -> Err
+            (
+                "fn main() -> f64 {
+                    let k: *mut f64 := null;
+                    return k as f64;
+                }
+                struct MyStruct{}
+                ",
+                Err("L3: Invalid type cast"),
+            ),
+            (
+                "fn main() -> bool {
+                    let k: MyStruct := 5 as MyStruct;
+                    return k + 3;
+                }
+                struct MyStruct{}
+                ",
+                Err("L2: Invalid type cast"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    // Validate the resolved type of each statement matches the expected resolved type
+                    fn_main
+                        .get_body()
+                        .iter()
+                        .enumerate()
+                        .for_each(|(idx, stm)| {
+                            assert_eq!(stm.context().ty(), expected_ty[idx], "Test Data: {}", text);
+                        });
+                }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_size_of() {
+        for (line, text, expected) in vec![
+            (
+                line!(),
+                "fn main() -> u64 {
+                    return size_of(i64);
+                }",
+                Ok(Type::U64),
+            ),
+            (
+                line!(),
+                "fn main() -> u64 {
+                    return size_of([i32; 13]);
+                }",
+                Ok(Type::U64),
+            ),
+            (
+                line!(),
+                "fn main() -> u64 {
+                    return size_of(FakeType);
+                }",
+                Err("L2: Could not find item with the given path: FakeType ($main::FakeType)"),
+            ),
+            (
+                line!(),
+                "fn main() -> i64 {
+                    return size_of(f64);
+                }",
+                Err("L2: Return expected i64 but got u64"),
+            ),
+        ] {
+            println!("Test L{}", line);
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    // Validate that the return statement is the correct type
+                    let ret_stm = &fn_main.get_body()[0];
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
+                    if let Statement::Return(r) = ret_stm {
+                        assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a return statement")
+                    }
+                }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_reflection_builtins() {
+        let pairs_ty = |n| Type::Array(Box::new(Type::Array(Box::new(Type::StringLiteral), 2)), n);
+        for (line, text, expected) in vec![
+            (
+                line!(),
+                "fn main() -> [[string; 2]; 2] {
+                    return fields_of(S);
+                }
+                struct S { a: i64, b: bool }",
+                Ok(pairs_ty(2)),
+            ),
+            (
+                line!(),
+                "fn main() -> string {
+                    return name_of(S);
+                }
+                struct S { a: i64, b: bool }",
+                Ok(Type::StringLiteral),
+            ),
+            (
+                line!(),
+                "fn main() -> string {
+                    return name_of([i64; 4]);
+                }",
+                Ok(Type::StringLiteral),
+            ),
+            (
+                line!(),
+                "fn main() -> [[string; 2]; 1] {
+                    return fields_of(i64);
+                }",
+                Err("L2: fields_of expected a structure but found i64"),
+            ),
+            (
+                line!(),
+                "fn main() -> [[string; 2]; 1] {
+                    return fields_of(E);
+                }
+                struct E {}",
+                Err("L2: Expected length > 0 for array, but found 0"),
+            ),
+            (
+                line!(),
+                "fn main() -> string {
+                    return name_of(FakeType);
+                }",
+                Err("L2: Could not find item with the given path: FakeType ($main::FakeType)"),
+            ),
+        ] {
+            println!("Test L{}", line);
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    // Validate that the return statement is the correct type
+                    let ret_stm = &fn_main.get_body()[0];
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
+                    if let Statement::Return(r) = ret_stm {
+                        assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a return statement")
+                    }
+                }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_strings() {
+        for (line, text, expected) in vec![
+            (
+                line!(),
+                "fn main() -> String {
+                    return \"hello, \" + \"world\";
+                }",
+                Ok(Type::String),
+            ),
+            (
+                line!(),
+                "fn main() -> String {
+                    let s: String := \"hello\" as String;
+                    return s + \", \" + s;
+                }",
+                Ok(Type::String),
+            ),
+            (
+                line!(),
+                "fn main() -> string {
+                    let s: String := \"hello\" + \"world\";
+                    return s as string;
+                }",
+                Ok(Type::StringLiteral),
+            ),
+            (
+                line!(),
+                "fn main() -> u64 {
+                    return len(\"hello\" + \"world\");
+                }",
+                Ok(Type::U64),
+            ),
+            (
+                line!(),
+                "fn main() -> u64 {
+                    return len(\"hello\");
+                }",
+                Ok(Type::U64),
+            ),
+            (
+                line!(),
+                "fn main() -> string {
+                    return \"hello\" + \"world\";
+                }",
+                Err("L2: Return expected string but got String"),
+            ),
+            (
+                line!(),
+                "fn main() -> u64 {
+                    return len(5);
+                }",
+                Err("L2: len expected a string, String, array, slice, or range but found i64"),
+            ),
+            (
+                line!(),
+                "fn main() -> String {
+                    return \"hello\" - \"world\";
+                }",
+                Err("L2: - expected i64 but found string and string"),
+            ),
+        ] {
+            println!("Test L{}", line);
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    // Validate that the return statement is the correct type
+                    let ret_stm = fn_main.get_body().last().unwrap();
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
+                }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_array_at_index() {
+        for (line, text, expected) in vec![
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let a: [i64; 5] := [1, 2, 3, 4, 5];
+                    let k: i64 := a[0];
+                    return k * 3;
+                }",
+                Ok(Type::I64),
+            ),
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let a: [i64; 5] := [[1, 2, 3, 4, 5]][0];
+                    let k: i64 := a[0];
+                    return k * 3;
+                }",
+                Ok(Type::I64),
+            ),
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let a: [[i64; 1]; 1] := [[1]];
+                    let k: i64 := a[0][0];
+                    return k * 3;
+                }",
+                Ok(Type::I64),
+            ),
+            (
+                line!(),
+                "fn main() -> i8 {
+                    let a: [i8; 5] := [1i8, 2i8, 3i8, 4i8, 5i8];
+                    let k: i8 := a[0];
+                    return k * 3i8;
+                }",
+                Ok(Type::I8),
+            ),
+            (
+                line!(),
+                "fn main() -> u8 {
+                    let a: [u8; 5] := [1u8, 2u8, 3u8, 4u8, 5u8];
+                    let k: u8 := a[0i8];
+                    return k * 3u8;
+                }",
+                Ok(Type::U8),
+            ),
+            (
+                line!(),
+                "fn main() -> bool {
+                    let a: [i64; 5] := [1, 2, 3, 4, 5];
+                    let k: i64 := a[false];
+                    return k * 3;
+                }",
+                Err("L3: Expected an integer or a range for index but found bool"),
+            ),
+            (
+                line!(),
+                "fn main() -> bool {
+                    let a: i64 := 1;
+                    let k: i64 := a[0];
+                    return k * 3;
+                }",
+                Err("L3: Expected an array, slice, or range on LHS of [] but found i64"),
+            ),
+        ] {
+            println!("Test L{}", line);
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
 
-                        // Validate that the return statement is the correct type
-                        let ret_stm = &fn_main.get_body()[1];
-                        assert_eq!(ret_stm.get_type(), expected_ty);
-                        if let Statement::Return(r) = ret_stm {
-                            assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
-                        } else {
-                            panic!("Expected a return statement")
-                        }
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    let bind_stm = &fn_main.get_body()[1];
+                    assert_eq!(bind_stm.context().ty(), expected_ty);
+
+                    // validate that the RHS of the bind is the correct type
+                    if let Statement::Bind(b) = bind_stm {
+                        assert_eq!(b.get_rhs().get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a bind statement");
                     }
-                    Err(msg) => {
-                        assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+
+                    // Validate that the return statement is the correct type
+                    let ret_stm = &fn_main.get_body()[2];
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
+                    if let Statement::Return(r) = ret_stm {
+                        assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a return statement")
                     }
                 }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
             }
         }
     }
 
     #[test]
-    pub fn test_array_size_types() {
-        for ty in vec!["u64", "u32", "u16", "u8", "i64", "i32", "i16", "i8"] {
-            let text = format!(
-                "fn main() -> i64 {{
-                    let a: [i64; 2{}] := [1, 2,];
-                    return 0;
-                }}",
-                ty
-            );
+    pub fn test_slices() {
+        for (line, text, expected) in vec![
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let a: [i64; 3] := [1, 2, 3];
+                    return sum(a);
+                }
+                fn sum(s: [i64]) -> i64 {
+                    return s[0] + s[len(s) - 1u64];
+                }",
+                Ok(Type::I64),
+            ),
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let a: [i64; 3] := [1, 2, 3];
+                    let s: [i64] := a;
+                    return s[1];
+                }",
+                Ok(Type::I64),
+            ),
+            (
+                line!(),
+                "fn main() -> u64 {
+                    let a: [i64; 3] := [1, 2, 3];
+                    let s: [i64] := a as [i64];
+                    return len(s) + len(a);
+                }",
+                Ok(Type::U64),
+            ),
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let a: [i32; 3] := [1i32, 2i32, 3i32];
+                    let s: [i64] := a;
+                    return s[1];
+                }",
+                Err("L3: Bind expected [i64] but got [i32; 3]"),
+            ),
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let mut a: [i64; 3] := [1, 2, 3];
+                    let mut s: [i64] := a;
+                    mut s[0] := 5;
+                    return s[0];
+                }",
+                Err("L4: s[0] is not mutable"),
+            ),
+            (
+                line!(),
+                "fn main() -> [i64] {
+                    let a: [i64; 3] := [1, 2, 3];
+                    return a;
+                }",
+                Err("L3: Return expected [i64] but got [i64; 3]"),
+            ),
+        ] {
+            println!("Test L{}", line);
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
             let mut table = StringTable::new();
             let main = table.insert("main".into());
             let main_mod = table.insert(MAIN_MODULE.into());
             let main_fn = table.insert("my_main".into());
 
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    // Validate that the return statement is the correct type
+                    let ret_stm = fn_main.get_body().last().unwrap();
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
+                }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_ranges() {
+        for (line, text, expected) in vec![
+            (
+                line!(),
+                "fn main() -> Range<i64> {
+                    return 0..10;
+                }",
+                Ok(Type::Range(Box::new(Type::I64))),
+            ),
+            (
+                line!(),
+                "fn main() -> Range<u8> {
+                    let r: Range<u8> := 1u8..5u8;
+                    return r;
+                }",
+                Ok(Type::Range(Box::new(Type::U8))),
+            ),
+            (
+                line!(),
+                "fn main() -> u64 {
+                    let r: Range<i64> := 0..10;
+                    return len(r);
+                }",
+                Ok(Type::U64),
+            ),
+            (
+                line!(),
+                "fn main() -> i32 {
+                    let r: Range<i32> := 5i32..10i32;
+                    return r[2];
+                }",
+                Ok(Type::I32),
+            ),
+            (
+                line!(),
+                "fn main() -> [i64] {
+                    let a: [i64; 5] := [1, 2, 3, 4, 5];
+                    return a[1..3];
+                }",
+                Ok(Type::Slice(Box::new(Type::I64))),
+            ),
+            (
+                line!(),
+                "fn main() -> Range<i64> {
+                    let r: Range<i64> := 0..10;
+                    return r[1u64..2u64];
+                }",
+                Ok(Type::Range(Box::new(Type::I64))),
+            ),
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let mut sum: i64 := 0;
+                    let r: Range<i64> := 0..10;
+                    for (i in r) {
+                        mut sum := sum + i;
+                    };
+                    return sum;
+                }",
+                Ok(Type::I64),
+            ),
+            (
+                line!(),
+                "fn main() -> u8 {
+                    let mut sum: u8 := 0u8;
+                    for (i: u8 in first(3u8)) {
+                        mut sum := sum + i;
+                    };
+                    return sum;
+                }
+                fn first(n: u8) -> Range<u8> {
+                    return 0u8..n;
+                }",
+                Ok(Type::U8),
+            ),
+            (
+                line!(),
+                "fn main() -> Range<i64> {
+                    return 0..10u8;
+                }",
+                Err("L2: .. expected i64 but found i64 and u8"),
+            ),
+            (
+                line!(),
+                "fn main() -> Range<bool> {
+                    return true..false;
+                }",
+                Err("L2: .. expected i64 but found bool and bool"),
+            ),
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let r: Range<u8> := 0u8..10u8;
+                    for (i in r) {};
+                    return 0;
+                }",
+                Err("L3: Bind expected i64 but got u8"),
+            ),
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let r: Range<i64> := 0..10;
+                    mut r[0] := 5;
+                    return 0;
+                }",
+                Err("L3: r[0] is not mutable"),
+            ),
+        ] {
+            println!("Test L{}", line);
             let mut sm = SourceMap::new();
             sm.add_string(&text, "/test".into()).unwrap();
             let src = sm.get(0).unwrap().read().unwrap();
 
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
             let logger = Logger::new();
             let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
                 .unwrap()
@@ -1787,81 +3305,157 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
-            let module = module.unwrap();
-            let fn_main = module.get_functions()[0].to_routine().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
 
-            let bind_stm = &fn_main.get_body()[0];
-            assert_eq!(bind_stm.context().ty(), Type::Array(Box::new(Type::I64), 2));
+                    // Validate that the return statement is the correct type
+                    let ret_stm = fn_main.get_body().last().unwrap();
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
+                }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
         }
     }
 
     #[test]
-    pub fn test_array_element_types() {
-        for (text, expected) in vec![
+    pub fn test_for_sequences() {
+        for (line, text, expected) in vec![
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let mut sum: i64 := 0;
+                    let a: [i64; 3] := [1, 2, 3];
+                    for (x in a) {
+                        mut sum := sum + x;
+                    };
+                    return sum;
+                }",
+                Ok(Type::I64),
+            ),
             (
+                line!(),
                 "fn main() -> i64 {
-                    let a: [i64; 5] := [1, 2, 3, 4, 5];
-                    let k: i64 := a[0];
-                    return k * 3;
+                    let a: [i64; 3] := [1, 2, 3];
+                    return sum(a);
+                }
+                fn sum(s: [i64]) -> i64 {
+                    let mut total: i64 := 0;
+                    for (x in s) {
+                        mut total := total + x;
+                    };
+                    return total;
                 }",
                 Ok(Type::I64),
             ),
             (
+                line!(),
                 "fn main() -> i64 {
-                    let a: [[i64; 1]; 1] := [[1]];
-                    let k: i64 := a[0][0];
-                    return k * 3;
+                    let mut n: i64 := 0;
+                    let a: [[i64; 2]; 2] := [[1, 2], [3, 4]];
+                    for (row: [i64; 2] in a) {
+                        for (x in row) {
+                            mut n := n + x;
+                        };
+                    };
+                    return n;
                 }",
                 Ok(Type::I64),
             ),
             (
-                "fn main() -> i8 {
-                    let a: [i8; 5] := [1i8, 2i8, 3i8, 4i8, 5i8];
-                    let k: i8 := a[0];
-                    return k * 3i8;
+                line!(),
+                "fn main() -> i64 {
+                    let a: [u8; 3] := [1u8, 2u8, 3u8];
+                    for (x in a) {};
+                    return 0;
                 }",
-                Ok(Type::I8),
+                Err("L3: Bind expected i64 but got u8"),
             ),
             (
-                "fn main() -> u8 {
-                    let a: [u8; 5] := [1u8, 2u8, 3u8, 4u8, 5u8];
-                    let k: u8 := a[0];
-                    return k * 3u8;
+                line!(),
+                "fn main() -> i64 {
+                    for (x in 5) {};
+                    return 0;
                 }",
-                Ok(Type::U8),
+                Err("L2: len expected a string, String, array, slice, or range but found i64"),
             ),
             (
-                "fn main() -> u16 {
-                    let a: [u16; 5] := [1u16, 2u16, 3u16, 4u16, 5u16];
-                    let k: u16 := a[0];
-                    return k * 3u16;
+                line!(),
+                "fn main() -> i64 {
+                    let mut sum: i64 := 0;
+                    for (x in Count{n: 3}) {
+                        mut sum := sum + x;
+                    };
+                    return sum;
+                }
+                struct Count {
+                    n: i64,
+                }
+                impl Count {
+                    fn is_empty(self) -> bool {
+                        return self.n == 0;
+                    }
+                    fn first(self) -> i64 {
+                        return self.n;
+                    }
+                    fn rest(self) -> Count {
+                        return Count{n: self.n - 1};
+                    }
                 }",
-                Ok(Type::U16),
+                Ok(Type::I64),
             ),
             (
-                "fn main() -> *const u16 {
-                    let a: [*const u16; 5] := [null, null, null, null, null];
-                    let k: *const u16 := a[0];
-                    return k;
+                line!(),
+                "fn main() -> i64 {
+                    for (x in Count{n: 3}) {};
+                    return 0;
+                }
+                struct Count {
+                    n: i64,
+                }
+                impl Count {
+                    fn is_empty(self) -> bool {
+                        return self.n == 0;
+                    }
+                    fn first(self) -> i64 {
+                        return self.n;
+                    }
                 }",
-                Ok(Type::RawPointer(PointerMut::Const, Box::new(Type::U16))),
+                Err("L2: Count does not have method rest"),
             ),
             (
-                "fn main() -> i16 {
-                    let a: [i16; 2] := [1, 2,];
-                    let k: i16 := a[0];
-                    return k * 3i16;
+                line!(),
+                "fn main() -> i64 {
+                    for (x: bool in Count{n: 3}) {};
+                    return 0;
+                }
+                struct Count {
+                    n: i64,
+                }
+                impl Count {
+                    fn is_empty(self) -> bool {
+                        return self.n == 0;
+                    }
+                    fn first(self) -> i64 {
+                        return self.n;
+                    }
+                    fn rest(self) -> Count {
+                        return Count{n: self.n - 1};
+                    }
                 }",
-                Err("L2: Bind expected [i16; 2] but got [i64; 2]"),
+                Err("L2: Bind expected bool but got i64"),
             ),
         ] {
+            println!("Test L{}", line);
             let mut sm = SourceMap::new();
             sm.add_string(&text, "/test".into()).unwrap();
             let src = sm.get(0).unwrap().read().unwrap();
 
             let mut table = StringTable::new();
-
             let main = table.insert("main".into());
             let main_mod = table.insert(MAIN_MODULE.into());
             let main_fn = table.insert("my_main".into());
@@ -1876,30 +3470,15 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
                     let fn_main = module.get_functions()[0].to_routine().unwrap();
 
-                    let bind_stm = &fn_main.get_body()[1];
-                    assert_eq!(bind_stm.context().ty(), expected_ty);
-
-                    // validate that the RHS of the bind is the correct type
-                    if let Statement::Bind(b) = bind_stm {
-                        assert_eq!(b.get_rhs().get_type(), expected_ty);
-                    } else {
-                        panic!("Expected a bind statement");
-                    }
-
                     // Validate that the return statement is the correct type
-                    let ret_stm = &fn_main.get_body()[2];
+                    let ret_stm = fn_main.get_body().last().unwrap();
                     assert_eq!(ret_stm.context().ty(), expected_ty);
-                    if let Statement::Return(r) = ret_stm {
-                        assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
-                    } else {
-                        panic!("Expected a return statement")
-                    }
                 }
                 Err(msg) => {
                     assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
@@ -1909,116 +3488,133 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
     }
 
     #[test]
-    pub fn test_type_cast() {
-        for (text, expected) in vec![
+    pub fn test_lowered_for_loops_have_unique_node_ids() {
+        let text = "fn main() -> i64 {
+            let mut sum: i64 := 0;
+            for (x in [1, 2, 3]) {
+                mut sum := sum + x;
+            };
+            return sum;
+        }";
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let mut table = StringTable::new();
+        let main = table.insert("main".into());
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+
+        let parser = Parser::new(&logger);
+        let ast = number_nodes(&parser.parse(main, &tokens).unwrap().unwrap());
+        let module = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
+
+        // Every node keeps the ID it was given by the parser, and the nodes created by
+        // lowering the iterator protocol are given new IDs
+        let mut ids: Vec<_> = module.iter_preorder().map(|n| n.context().id()).collect();
+        let parsed: Vec<_> = ast.iter_preorder().map(|n| n.context().id()).collect();
+        assert!(parsed.iter().all(|id| ids.contains(id)));
+        let len = ids.len();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), len);
+    }
+
+    #[test]
+    pub fn test_options() {
+        for (line, text, expected) in vec![
             (
-                "fn main() -> i64 {
-                    let k: i32 := (1 + 5) as i32;
-                    return k as i64;
+                line!(),
+                "fn main() -> opt i64 {
+                    return some(5);
                 }",
-                Ok(vec![Type::I32, Type::I64]),
+                Ok(Type::Option(Box::new(Type::I64))),
             ),
             (
-                "fn main() -> i64 {
-                    let k: *const i64 := 0 as *const i64;
-                    return ^k;
+                line!(),
+                "fn main() -> opt i64 {
+                    return none;
                 }",
-                Ok(vec![
-                    Type::RawPointer(PointerMut::Const, Box::new(Type::I64)),
-                    Type::I64,
-                ]),
+                Ok(Type::Option(Box::new(Type::I64))),
             ),
-            // Cast from *mut to *const -> Ok
             (
+                line!(),
                 "fn main() -> i64 {
-                    let i: *mut i64 := null;
-                    let k: *const i64 := i as *const i64;
-                    return ^k;
+                    let o: opt i64 := some(5);
+                    return if (let some(x) := o) {x} else {0};
                 }",
-                Ok(vec![
-                    Type::RawPointer(PointerMut::Mut, Box::new(Type::I64)),
-                    Type::RawPointer(PointerMut::Const, Box::new(Type::I64)),
-                    Type::I64,
-                ]),
+                Ok(Type::I64),
             ),
-            // Cast from iX to *const -> Ok
             (
+                line!(),
                 "fn main() -> i64 {
-                    let k: *const i64 := 0i8 as *const i64;
-                    return ^k;
+                    let mut o: opt i64 := some(5);
+                    mut o := none;
+                    return first(o, 3);
+                }
+                fn first(a: opt i64, b: i64) -> i64 {
+                    return if (let some(x) := a) {x} else {b};
                 }",
-                Ok(vec![
-                    Type::RawPointer(PointerMut::Const, Box::new(Type::I64)),
-                    Type::I64,
-                ]),
+                Ok(Type::I64),
             ),
-            // Cast from fX to iY -> Ok
             (
-                "fn main() -> i64 {
-                    let k: f64 := 0.2;
-                    return k as i64;
+                line!(),
+                "fn main() -> opt bool {
+                    let b: bool := true;
+                    return if (b) {none} else {some(b)};
                 }",
-                Ok(vec![Type::F64, Type::I64]),
+                Ok(Type::Option(Box::new(Type::Bool))),
             ),
-            // Cast from iY to fX -> Ok
             (
-                "fn main() -> f64 {
-                    let k: i64 := 2;
-                    return k as f64;
+                line!(),
+                "fn main() -> i64 {
+                    let o: opt i64 := some(5);
+                    return o + 1;
                 }",
-                Ok(vec![Type::I64, Type::F64]),
+                Err("L3: + expected i64 but found opt i64 and i64"),
             ),
-            // Cast from iX to *mut -> Err
             (
+                line!(),
                 "fn main() -> i64 {
-                    let k: *mut i64 := 5 as *mut i64;
-                    return ^k;
-                }
-                struct MyStruct{}
-                ",
-                Err("L2: Invalid type cast"),
+                    let o: opt i64 := some(true);
+                    return 0;
+                }",
+                Err("L2: Bind expected opt i64 but got opt bool"),
             ),
-            // Cast from *const to *mut -> Err
             (
-                "fn main() -> i64 {
-                    let i: *const i64 := null;
-                    let k: *mut i64 := 5 as *mut i64;
-                    return ^k;
-                }
-                struct MyStruct{}
-                ",
-                Err("L3: Invalid type cast"),
+                line!(),
+                "fn main() -> bool {
+                    let o: opt i64 := some(5);
+                    return o == none;
+                }",
+                Err("L3: == expected opt i64 but found opt i64 and opt unknown"),
             ),
-            // Cast from fX to *m -> Err
             (
+                line!(),
                 "fn main() -> i64 {
-                    let k: *mut i64 := 5.0 as *mut f64;
-                    return ^k;
-                }
-                struct MyStruct{}
-                ",
-                Err("L2: Invalid type cast"),
-            ),
-            // Cast from *m to fX -> Err
-            (
-                "fn main() -> f64 {
-                    let k: *mut f64 := null;
-                    return k as f64;
-                }
-                struct MyStruct{}
-                ",
-                Err("L3: Invalid type cast"),
+                    if (let some(x) := 5) {};
+                    return 0;
+                }",
+                Err("L2: is_some expected an option but found i64"),
             ),
             (
-                "fn main() -> bool {
-                    let k: MyStruct := 5 as MyStruct;
-                    return k + 3;
-                }
-                struct MyStruct{}
-                ",
-                Err("L2: Invalid type cast"),
+                line!(),
+                "fn main() -> i64 {
+                    let x: i64 := {none; 0};
+                    return x;
+                }",
+                Err("L2: The type of this none, ok, or err cannot be inferred, it must be used where its option or result type is expected"),
             ),
         ] {
+            println!("Test L{}", line);
             let mut sm = SourceMap::new();
             sm.add_string(&text, "/test".into()).unwrap();
             let src = sm.get(0).unwrap().read().unwrap();
@@ -2038,20 +3634,15 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
                     let fn_main = module.get_functions()[0].to_routine().unwrap();
 
-                    // Validate the resolved type of each statement matches the expected resolved type
-                    fn_main
-                        .get_body()
-                        .iter()
-                        .enumerate()
-                        .for_each(|(idx, stm)| {
-                            assert_eq!(stm.context().ty(), expected_ty[idx], "Test Data: {}", text);
-                        });
+                    // Validate that the return statement is the correct type
+                    let ret_stm = fn_main.get_body().last().unwrap();
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
                 }
                 Err(msg) => {
                     assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
@@ -2061,35 +3652,90 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
     }
 
     #[test]
-    pub fn test_size_of() {
+    pub fn test_results() {
         for (line, text, expected) in vec![
             (
                 line!(),
-                "fn main() -> u64 {
-                    return size_of(i64);
+                "fn main() -> res i64, bool {
+                    return ok(5);
                 }",
-                Ok(Type::U64),
+                Ok(Type::Result(Box::new(Type::I64), Box::new(Type::Bool))),
             ),
             (
                 line!(),
-                "fn main() -> u64 {
-                    return size_of([i32; 13]);
+                "fn main() -> res i64, bool {
+                    return err(true);
                 }",
-                Ok(Type::U64),
+                Ok(Type::Result(Box::new(Type::I64), Box::new(Type::Bool))),
             ),
             (
                 line!(),
-                "fn main() -> u64 {
-                    return size_of(FakeType);
+                "fn main() -> res i64, bool {
+                    let x: i64 := check(1)?;
+                    return ok(x + 1);
+                }
+                fn check(a: i64) -> res i64, bool {
+                    return if (a > 0) {ok(a)} else {err(false)};
                 }",
-                Err("L2: Could not find item with the given path: $main::FakeType ($main::FakeType)"),
+                Ok(Type::Result(Box::new(Type::I64), Box::new(Type::Bool))),
             ),
             (
                 line!(),
                 "fn main() -> i64 {
-                    return size_of(f64);
+                    let r: res i64, bool := ok(5);
+                    return if (let ok(x) := r) {x} else {0};
                 }",
-                Err("L2: Return expected i64 but got u64"),
+                Ok(Type::I64),
+            ),
+            (
+                line!(),
+                "fn main() -> bool {
+                    let r: res i64, bool := err(true);
+                    return if (let err(e) := r) {e} else {false};
+                }",
+                Ok(Type::Bool),
+            ),
+            (
+                line!(),
+                "fn main() -> opt i64 {
+                    let o: opt i64 := some(1);
+                    let x: i64 := o?;
+                    return some(x);
+                }",
+                Ok(Type::Option(Box::new(Type::I64))),
+            ),
+            (
+                line!(),
+                "fn main() -> res i64, i64 {
+                    let r: res i64, bool := ok(1);
+                    let x: i64 := r?;
+                    return ok(x);
+                }",
+                Err("L3: ? cannot propagate res i64, bool out of a routine which returns res i64, i64"),
+            ),
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let o: opt i64 := none;
+                    return o?;
+                }",
+                Err("L3: ? cannot propagate opt i64 out of a routine which returns i64"),
+            ),
+            (
+                line!(),
+                "fn main() -> i64 {
+                    if (let ok(x) := 5) {};
+                    return 0;
+                }",
+                Err("L2: is_ok expected a result but found i64"),
+            ),
+            (
+                line!(),
+                "fn main() -> i64 {
+                    let x: i64 := {ok(1); 0};
+                    return x;
+                }",
+                Err("L2: The type of this none, ok, or err cannot be inferred, it must be used where its option or result type is expected"),
             ),
         ] {
             println!("Test L{}", line);
@@ -2112,20 +3758,15 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
                     let fn_main = module.get_functions()[0].to_routine().unwrap();
-
-                    // Validate that the return statement is the correct type
-                    let ret_stm = &fn_main.get_body()[0];
-                    assert_eq!(ret_stm.context().ty(), expected_ty);
-                    if let Statement::Return(r) = ret_stm {
-                        assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
-                    } else {
-                        panic!("Expected a return statement")
-                    }
+
+                    // Validate that the return statement is the correct type
+                    let ret_stm = fn_main.get_body().last().unwrap();
+                    assert_eq!(ret_stm.context().ty(), expected_ty);
                 }
                 Err(msg) => {
                     assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
@@ -2135,70 +3776,65 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
     }
 
     #[test]
-    pub fn test_array_at_index() {
+    pub fn test_const_fn() {
         for (line, text, expected) in vec![
             (
                 line!(),
                 "fn main() -> i64 {
-                    let a: [i64; 5] := [1, 2, 3, 4, 5];
-                    let k: i64 := a[0];
-                    return k * 3;
+                    return square(3);
+                }
+                const fn square(x: i64) -> i64 {
+                    return times(x, x);
+                }
+                const fn times(a: i64, b: i64) -> i64 {
+                    return a * b;
                 }",
                 Ok(Type::I64),
             ),
             (
                 line!(),
                 "fn main() -> i64 {
-                    let a: [i64; 5] := [[1, 2, 3, 4, 5]][0];
-                    let k: i64 := a[0];
-                    return k * 3;
+                    return square(3);
+                }
+                const fn square(x: i64) -> i64 {
+                    return times(x, x);
+                }
+                fn times(a: i64, b: i64) -> i64 {
+                    return a * b;
                 }",
-                Ok(Type::I64),
+                Err("L5: A const fn can only call other const fns, but times is not a const fn"),
             ),
             (
                 line!(),
                 "fn main() -> i64 {
-                    let a: [[i64; 1]; 1] := [[1]];
-                    let k: i64 := a[0][0];
-                    return k * 3;
-                }",
-                Ok(Type::I64),
-            ),
-            (
-                line!(),
-                "fn main() -> i8 {
-                    let a: [i8; 5] := [1i8, 2i8, 3i8, 4i8, 5i8];
-                    let k: i8 := a[0];
-                    return k * 3i8;
-                }",
-                Ok(Type::I8),
-            ),
-            (
-                line!(),
-                "fn main() -> u8 {
-                    let a: [u8; 5] := [1u8, 2u8, 3u8, 4u8, 5u8];
-                    let k: u8 := a[0i8];
-                    return k * 3u8;
-                }",
-                Ok(Type::U8),
+                    return ticks();
+                }
+                const fn ticks() -> i64 {
+                    return clock();
+                }
+                extern fn clock() -> i64;",
+                Err("L5: A const fn can only call other const fns, but clock is not a const fn"),
             ),
             (
                 line!(),
-                "fn main() -> bool {
-                    let a: [i64; 5] := [1, 2, 3, 4, 5];
-                    let k: i64 := a[false];
-                    return k * 3;
+                "fn main() -> i64 {
+                    return id(3);
+                }
+                const fn id(x: i64) -> i64 {
+                    let p: *const i64 := @const x;
+                    return ^p;
                 }",
-                Err("L3: Expected integral type for index but found bool"),
+                Err("L5: A const fn cannot use raw pointers, but found *const i64"),
             ),
             (
                 line!(),
-                "fn main() -> bool {
-                    let a: i64 := 1;
-                    let k: i64 := a[0];
-                    return k * 3;
+                "fn main() -> i64 {
+                    return 0;
+                }
+                const fn read(p: *const i64) -> i64 {
+                    return ^p;
                 }",
-                Err("L3: Expected array type on LHS of [] but found i64"),
+                Err("L4: A const fn cannot use raw pointers, but found *const i64"),
             ),
         ] {
             println!("Test L{}", line);
@@ -2221,30 +3857,15 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
                     let fn_main = module.get_functions()[0].to_routine().unwrap();
 
-                    let bind_stm = &fn_main.get_body()[1];
-                    assert_eq!(bind_stm.context().ty(), expected_ty);
-
-                    // validate that the RHS of the bind is the correct type
-                    if let Statement::Bind(b) = bind_stm {
-                        assert_eq!(b.get_rhs().get_type(), expected_ty);
-                    } else {
-                        panic!("Expected a bind statement");
-                    }
-
                     // Validate that the return statement is the correct type
-                    let ret_stm = &fn_main.get_body()[2];
+                    let ret_stm = fn_main.get_body().last().unwrap();
                     assert_eq!(ret_stm.context().ty(), expected_ty);
-                    if let Statement::Return(r) = ret_stm {
-                        assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
-                    } else {
-                        panic!("Expected a return statement")
-                    }
                 }
                 Err(msg) => {
                     assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
@@ -2318,7 +3939,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                     let k: Fake := 1;
                     return k;
                 }",
-                Err("L2: Could not find item with the given path: $main::Fake ($main::Fake)"),
+                Err("L2: Could not find item with the given path: Fake ($main::Fake)"),
             ),
             (
                 line!(),
@@ -2431,7 +4052,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
@@ -2593,7 +4214,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
@@ -2674,7 +4295,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
@@ -2701,6 +4322,182 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
         }
     }
 
+    #[test]
+    pub fn test_defer_statement() {
+        for (text, expected) in vec![
+            (
+                "fn main() -> i64 {
+                    let mut x: i64 := 1;
+                    defer {mut x := x + 1;};
+                    return x;
+                }",
+                Ok(()),
+            ),
+            (
+                "fn main() -> i64 {
+                    let mut x: i64 := 1;
+                    defer if (x > 0) {defer {mut x := 0;}; x} else {0};
+                    return x;
+                }",
+                Ok(()),
+            ),
+            (
+                "fn main() -> i64 {
+                    defer y;
+                    return 1;
+                }",
+                Err("L2: Could not find definition for y in this scope"),
+            ),
+            (
+                "fn main() -> i64 {
+                    defer {return 2;};
+                    return 1;
+                }",
+                Err("L2: A deferred expression cannot return, because it runs while the scope is exited"),
+            ),
+            (
+                "fn main() -> opt i64 {
+                    let o: opt i64 := some(1);
+                    defer {let x: i64 := o?;};
+                    return some(2);
+                }",
+                Err("L3: ? cannot be used in a deferred expression, because it runs while the scope is exited"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(()) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    let defer_stm = &fn_main.get_body()[1];
+                    assert!(matches!(defer_stm, Statement::Defer(_)));
+                    assert_eq!(defer_stm.get_type(), Type::Unit);
+                }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_break_and_continue() {
+        for (text, expected) in vec![
+            (
+                "fn main() -> i64 {
+                    while (true) {break;};
+                    return 0;
+                }",
+                Ok(()),
+            ),
+            (
+                "fn main() -> i64 {
+                    'outer: while (true) {
+                        while (true) {
+                            if (false) {continue 'outer;};
+                            break 'outer;
+                        };
+                    };
+                    return 0;
+                }",
+                Ok(()),
+            ),
+            (
+                "fn main() -> i64 {
+                    break;
+                    return 0;
+                }",
+                Err("L2: break can only be used within a loop"),
+            ),
+            (
+                "fn main() -> i64 {
+                    if (true) {continue;};
+                    return 0;
+                }",
+                Err("L2: continue can only be used within a loop"),
+            ),
+            (
+                "fn main() -> i64 {
+                    'a: while (true) {break 'b;};
+                    return 0;
+                }",
+                Err("L2: There is no enclosing loop labeled 'b"),
+            ),
+            (
+                "fn main() -> i64 {
+                    'a: while (true) {};
+                    while (true) {break 'a;};
+                    return 0;
+                }",
+                Err("L3: There is no enclosing loop labeled 'a"),
+            ),
+            (
+                "fn main() -> i64 {
+                    while (true) {defer {break;};};
+                    return 0;
+                }",
+                Err("L2: break can only be used within a loop\n    \
+                hint: a deferred expression cannot leave the loops which enclose the defer"),
+            ),
+            (
+                "fn main() -> i64 {
+                    defer while (true) {continue;};
+                    return 0;
+                }",
+                Ok(()),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(()) => {
+                    module.unwrap();
+                }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
+        }
+    }
+
     #[test]
     pub fn test_extern_calls() {
         for (text, expected) in vec![
@@ -2738,7 +4535,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                     return number(5i32, 10, 15i32, 8u8, \"hello\");
                 }
                 ",
-                Err("L4: One or more parameters have mismatching types for function $main::number: parameter 1 expected i64 but got i32"),
+                Err("L4: One or more parameters have mismatching types for function number(i: i64, ...) -> i32: parameter 1 (i) expected i64 but got i32"),
             ),
             (
                 "
@@ -2747,7 +4544,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                     return number();
                 }
                 ",
-                Err("L4: Function $main::number expects at least 1 parameters, but got 0"),
+                Err("L4: Function number expects at least 1 parameters, but got 0"),
             ),
             (
                 "
@@ -2756,7 +4553,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                     return number(5);
                 }
                 ",
-                Err("L4: Function $main::number expects at least 2 parameters, but got 1"),
+                Err("L4: Function number expects at least 2 parameters, but got 1"),
             ),
             (
                 "fn main() -> bool {
@@ -2788,6 +4585,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
             let module = resolve_types(
                 &ast,
                 main_mod, main_fn,
+                &table,
                 &logger,
             );
             match expected {
@@ -2867,7 +4665,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 }
                 fn add(a: i32, b: i32) -> i32 {return a + b;}
                 ",
-                Err("L2: One or more parameters have mismatching types for function $main::add: parameter 1 expected i32 but got i64"),
+                Err("L2: One or more parameters have mismatching types for function add(a: i32, b: i32) -> i32: parameter 1 (a) expected i32 but got i64"),
             ),
             (
                 "fn main() -> i64 {
@@ -2883,7 +4681,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 }
                 fn add(a: i64, b: i64) -> i64 {return a + b;}
                 ",
-                Err("L2: One or more parameters have mismatching types for function $main::add: parameter 1 expected i64 but got bool"),
+                Err("L2: One or more parameters have mismatching types for function add(a: i64, b: i64) -> i64: parameter 1 (a) expected i64 but got bool"),
             ),
             (
                 "fn main() -> i64 {
@@ -2891,7 +4689,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 }
                 fn add(a: i64, b: i64) -> i64 {return a + b;}
                 ",
-                Err("L2: One or more parameters have mismatching types for function $main::add: parameter 2 expected i64 but got bool"),
+                Err("L2: One or more parameters have mismatching types for function add(a: i64, b: i64) -> i64: parameter 2 (b) expected i64 but got bool"),
             ),
             (
                 "fn main() -> i64 {
@@ -2899,7 +4697,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 }
                 fn add(a: i64, b: i64) -> i64 {return a + b;}
                 ",
-                Err("L2: Incorrect number of parameters passed to routine: $main::add. Expected 2 but got 1"),
+                Err("L2: Incorrect number of parameters passed to routine: add. Expected 2 but got 1"),
             ),
             (
                 "fn main() -> i64 {
@@ -2907,7 +4705,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 }
                 fn add(a: i64, b: i64) -> i64 {return a + b;}
                 ",
-                Err("L2: Incorrect number of parameters passed to routine: $main::add. Expected 2 but got 3"),
+                Err("L2: Incorrect number of parameters passed to routine: add. Expected 2 but got 3"),
             ),
             (
                 "fn main() -> i64 {
@@ -2915,7 +4713,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 }
                 fn add(a: i64, b: i64) -> i64 {return a + b;}
                 ",
-                Err("L2: Incorrect number of parameters passed to routine: $main::add. Expected 2 but got 1"),
+                Err("L2: Incorrect number of parameters passed to routine: add. Expected 2 but got 1"),
             ),
             (
                 "fn main() -> i32 {
@@ -2939,7 +4737,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 }
                 fn number() -> i64 {return 5;}
                 ",
-                Err("L2: Could not find item with the given path: $main::bad_fun ($main::bad_fun)"),
+                Err("L2: Could not find item with the given path: bad_fun ($main::bad_fun)"),
             ),
         ] {
             let mut sm = SourceMap::new();
@@ -2963,6 +4761,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
             let module = resolve_types(
                 &ast,
                 main_mod, main_fn,
+                &table,
                 &logger,
             );
             match expected {
@@ -3038,7 +4837,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
@@ -3098,7 +4897,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                     return;
                 }
                 ",
-                Err("L2: If expression has mismatching arms: expected bool got i64"),
+                Err("L2: If expression has mismatching arms: expected bool got i64\n    L2: note: true arm\n    L2: note: false arm"),
             ),
             (
                 "fn main() {
@@ -3106,7 +4905,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                     return;
                 }
                 ",
-                Err("L2: If expression has mismatching arms: expected i64 got string"),
+                Err("L2: If expression has mismatching arms: expected i64 got string\n    L2: note: true arm\n    L2: note: false arm"),
             ),
             (
                 "fn main() {
@@ -3122,7 +4921,16 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                     return;
                 }
                 ",
-                Err("L2: If expression has mismatching arms: expected i64 got unit"),
+                Err("L2: If expression has mismatching arms: expected i64 got unit\n    L2: note: true arm\n    hint: an if without an else arm has type unit"),
+            ),
+            (
+                "fn main() {
+                    let x: i64 := 5;
+                    let p: *const i64 := if (true) {@const x} else {null};
+                    return;
+                }
+                ",
+                Err("L3: If expression has mismatching arms: expected *const i64 got null\n    L3: note: true arm\n    L3: note: false arm\n    hint: null is not coerced to the pointer type of the other arm"),
             ),
         ] {
             let mut sm = SourceMap::new();
@@ -3144,7 +4952,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let parser = Parser::new(&logger);
             let ast = parser.parse(main, &tokens).unwrap().unwrap();
-            let module = resolve_types(&ast, main_mod, main_fn, &logger);
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
             match expected {
                 Ok(expected_ty) => {
                     let module = module.unwrap();
@@ -3226,6 +5034,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
             let module = resolve_types(
                 &ast,
                 main_mod, main_fn,
+                &table,
                 &logger,
 
             );
@@ -3254,6 +5063,72 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
         }
     }
 
+    #[test]
+    pub fn test_for_expressions() {
+        for (text, expected) in vec![
+            (
+                "fn main() {
+                    for (i in 0..10) {i;};
+                    return;
+                }
+                ",
+                Ok(()),
+            ),
+            (
+                "fn main() {
+                    let n: u8 := 10u8;
+                    for (i: u8 in 0u8..n) {i;};
+                    return;
+                }
+                ",
+                Ok(()),
+            ),
+            (
+                "fn main() {
+                    for (i: u8 in 0..10u8) {i;};
+                    return;
+                }
+                ",
+                Err("L2: Bind expected u8 but got i64"),
+            ),
+            (
+                "fn main() {
+                    for (i in 0..true) {i;};
+                    return;
+                }
+                ",
+                Err("L2: < expected i64 but found i64 and bool"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(()) => assert!(module.is_ok(), "{}", text),
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
+        }
+    }
+
     #[test]
     pub fn test_struct_expression() {
         for (line, text, expected) in vec![
@@ -3334,7 +5209,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
             ),
             (
                 line!(),
-                "mod my_mod{struct MyStruct{x:i64}}
+                "mod my_mod{struct MyStruct{pub x:i64}}
                 fn test() -> my_mod::MyStruct 
                 {
                     let x: root::my_mod::MyStruct := self::my_mod::MyStruct{x: 1};
@@ -3344,7 +5219,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
             ),
             (
                 line!(),
-                "mod my_mod{struct MyStruct{x:i64}}
+                "mod my_mod{struct MyStruct{pub x:i64}}
                 mod fn_mod {
                     fn test() -> self::super::my_mod::MyStruct 
                     {
@@ -3401,7 +5276,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                     let y: i64 := test2(x);
                     return y;
                 }",
-                Err("L7: One or more parameters have mismatching types for function $main::test2: parameter 1 expected $main::MyStruct2 but got $main::MyStruct"),
+                Err("L7: One or more parameters have mismatching types for function test2(ms2: MyStruct2) -> i64: parameter 1 (ms2) expected MyStruct2 but got MyStruct"),
             ),
             (
                 line!(),
@@ -3412,12 +5287,12 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                     let x: root::MyStruct2 := self::MyStruct{x: 1};
                     return x;
                 }",
-                Err("L5: Bind expected $main::MyStruct2 but got $main::MyStruct"),
+                Err("L5: Bind expected MyStruct2 but got MyStruct"),
             ),
             (
                 line!(),
                 "struct MyStruct{x:i64} fn test() -> MyStruct {return MyStruct{x:false};}",
-                Err("L1: $main::MyStruct.x expects i64 but got bool"),
+                Err("L1: MyStruct.x expects i64 but got bool"),
             ),
             (
                 line!(),
@@ -3427,7 +5302,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
             (
                 line!(),
                 "struct MyStruct{x:i64} fn test() -> i64 {return MyStruct{x:5};}",
-                Err("L1: Return expected i64 but got $main::MyStruct"),
+                Err("L1: Return expected i64 but got MyStruct"),
             ),
         ] {
             println!("L{}", line);
@@ -3452,6 +5327,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
             let result = resolve_types(
                 &ast,
                 main_mod, main_fn,
+                &table,
                 &logger,
 
             );
@@ -3474,11 +5350,11 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 ("struct MyStruct{x:i64} struct MS2{ms:MyStruct} fn test(ms:MS2) -> MyStruct {return ms.ms;}",
                 Ok(())),
                 ("struct MyStruct{x:i64} fn test(ms:MyStruct) -> i64 {return ms.y;}",
-                Err("L1: $main::MyStruct does not have member y")),
+                Err("L1: MyStruct does not have member y")),
                 ("struct MyStruct{x:i64} fn test(ms:MyStruct) -> bool{return ms.x;}",
                 Err("L1: Return expected bool but got i64")),
                 ("struct MyStruct{x:i64} struct MS2{ms:MyStruct} fn test(ms:MS2) -> i64 {return ms.ms.y;}",
-                Err("L1: $main::MyStruct does not have member y")),
+                Err("L1: MyStruct does not have member y")),
                 ("struct MyStruct{x:i64} struct MS2{ms:MyStruct} fn test(ms:MS2) -> bool {return ms.ms.x;}",
                 Err("L1: Return expected bool but got i64")),
             ] {
@@ -3503,6 +5379,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 let result = resolve_types(
                     &ast,
                     main_mod, main_fn,
+                    &table,
                 &logger,
                 );
                 match expected {
@@ -3512,6 +5389,194 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
             }
     }
 
+    #[test]
+    pub fn test_field_visibility() {
+        for (text, expected) in vec![
+                ("mod geo {struct P{x:i64, pub y:i64}} fn test(p:geo::P) -> i64 {return p.y;}",
+                Ok(())),
+                ("mod geo {struct P{x:i64, pub y:i64}} fn test(p:geo::P) -> i64 {return p.x;}",
+                Err("L1: x is a private field of geo::P, it can only be used within the module which defines geo::P")),
+                ("mod geo {struct P{x:i64, pub y:i64}} fn test() -> geo::P {return geo::P{x: 1, y: 2};}",
+                Err("L1: x is a private field of geo::P, it can only be used within the module which defines geo::P")),
+                ("mod geo {
+                    struct P{x:i64}
+                    fn new() -> P {return P{x: 1};}
+                    fn get(p:P) -> i64 {return p.x;}
+                    mod inner { fn get(p:super::P) -> i64 {return p.x;} }
+                }",
+                Ok(())),
+                ("mod geo {struct P{x:i64} impl P { fn get(self) -> i64 {return self.x;} }}
+                fn test(p:geo::P) -> i64 {return p.get();}",
+                Ok(())),
+                ("mod geo {struct P{x:i64}} mod other {fn test(p:super::geo::P) -> i64 {return p.x;}}",
+                Err("L1: x is a private field of super::geo::P, it can only be used within the module which defines super::geo::P")),
+            ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
+                    .tokenize()
+                    .into_iter()
+                    .collect::<LResult>()
+                    .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let result = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(_) => assert!(result.is_ok(), "{} -> {:?}", text, result),
+                Err(msg) => assert_eq!(result.unwrap_err().fmt(&sm, &table).unwrap(), msg),
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_method_calls() {
+        for (text, expected) in vec![
+                ("struct Rect{w:i64, h:i64}
+                impl Rect { fn area(self) -> i64 {return self.w * self.h;} }
+                fn test(r:Rect) -> i64 {return r.area();}",
+                Ok(())),
+                ("struct Rect{w:i64, h:i64}
+                impl Rect {
+                    fn scaled(self, k:i64) -> i64 {return self.area() * k;}
+                    fn area(self) -> i64 {return self.w * self.h;}
+                }
+                fn test(r:Rect) -> i64 {return r.scaled(2);}",
+                Ok(())),
+                ("fn area() -> bool {return true;}
+                struct Rect{w:i64, h:i64}
+                impl Rect { fn area(self) -> i64 {return self.w * self.h;} }
+                fn test(r:Rect) -> i64 {return r.area();}",
+                Ok(())),
+                ("mod geo {
+                    struct Rect{w:i64, h:i64}
+                    impl Rect { fn area(self) -> i64 {return self.w * self.h;} }
+                }
+                fn test(r:geo::Rect) -> i64 {return r.area();}",
+                Ok(())),
+                ("struct Rect{w:i64, h:i64} fn test(r:Rect) -> i64 {return r.area();}",
+                Err("L1: Rect does not have method area")),
+                ("fn test(x:i64) -> i64 {return x.area();}",
+                Err("L1: Could not find definition for area in this scope")),
+                ("fn double(x:i64) -> i64 {return x * 2;}
+                fn test(x:i64) -> i64 {return x.double();}",
+                Ok(())),
+                ("fn add(x:i64, y:i64) -> i64 {return x + y;}
+                fn test(x:i64) -> i64 {return x.add(1).add(2);}",
+                Ok(())),
+                ("struct Rect{w:i64, h:i64}
+                fn area(r:Rect) -> i64 {return r.w * r.h;}
+                fn test(r:Rect) -> i64 {return r.area();}",
+                Ok(())),
+                ("mod geo { fn double(x:i64) -> i64 {return x * 2;} }
+                fn test(x:i64) -> i64 {return x.double();}",
+                Err("L2: Could not find definition for double in this scope")),
+                ("struct double{x:i64}
+                fn test(x:i64) -> i64 {return x.double();}",
+                Err("L2: double is not a routine")),
+                ("fn double(x:i64) -> i64 {return x * 2;}
+                fn test(x:bool) -> i64 {return x.double();}",
+                Err("L2: One or more parameters have mismatching types for function double(x: i64) -> i64: parameter 1 (x) expected i64 but got bool")),
+                ("struct Rect{w:i64, h:i64}
+                impl Rect { fn scaled(self, k:i64) -> i64 {return self.w * k;} }
+                fn test(r:Rect) -> i64 {return r.scaled(true);}",
+                Err("L3: One or more parameters have mismatching types for function Rect::scaled(self: Rect, k: i64) -> i64: parameter 2 (k) expected i64 but got bool")),
+                ("struct Rect{w:i64, h:i64}
+                impl Rect { fn area(self) -> bool {return self.w;} }",
+                Err("L2: Return expected bool but got i64")),
+            ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
+                    .tokenize()
+                    .into_iter()
+                    .collect::<LResult>()
+                    .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let result = resolve_types(
+                &ast,
+                main_mod, main_fn,
+                &table,
+                &logger,
+            );
+            match expected {
+                Ok(_) => assert!(result.is_ok(), "{} -> {:?}", text, result),
+                Err(msg) => assert_eq!(result.unwrap_err().fmt(&sm, &table).unwrap(), msg),
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_method_call_is_resolved_to_function() {
+        let text = "struct Rect{w:i64, h:i64}
+            impl Rect { fn area(self) -> i64 {return self.w * self.h;} }
+            fn test(r:Rect) -> i64 {return r.area();}";
+        let mut sm = SourceMap::new();
+        sm.add_string(&text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let mut table = StringTable::new();
+        let main = table.insert("main".into());
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+        let rect = table.insert("Rect".into());
+        let area = table.insert("area".into());
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+
+        let parser = Parser::new(&logger);
+        let ast = parser.parse(main, &tokens).unwrap().unwrap();
+        let module = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
+
+        let area_path: Path = vec![
+            Element::CanonicalRoot,
+            Element::Id(main),
+            Element::Id(rect),
+            Element::Id(area),
+        ]
+        .into();
+        let method = module.get_methods().next().unwrap();
+        assert_eq!(method.context().canonical_path(), &area_path);
+
+        let test = module.get_functions()[0].to_routine().unwrap();
+        match &test.get_body()[0] {
+            Statement::Return(r) => match r.get_value() {
+                Some(Expression::RoutineCall(ctx, call, path, args)) => {
+                    assert_eq!(*call, RoutineCall::Function);
+                    assert_eq!(**path, area_path);
+                    assert_eq!(args.len(), 1);
+                    assert_eq!(ctx.ty(), &Type::I64);
+                }
+                v => panic!("Expected a routine call but got {:?}", v),
+            },
+            s => panic!("Expected return but got {:?}", s),
+        }
+    }
+
     #[test]
     pub fn test_imported_functions() {
         for (line, text, import_func, expected) in vec![
@@ -3567,7 +5632,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 }
                 ",
                 (vec![], (Type::I64)),
-                Err("L3: Could not find item with the given path: $std::test2 ($std::test2)"),
+                Err("L3: Could not find item with the given path: test2 ($std::test2)"),
             ),
             (
                 line!(),
@@ -3578,7 +5643,7 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 }
                 ",
                 (vec![], (Type::I64)),
-                Err("L3: Incorrect number of parameters passed to routine: $std::test. Expected 0 but got 1"),
+                Err("L3: Incorrect number of parameters passed to routine: test. Expected 0 but got 1"),
             ),
             (
                 line!(),
@@ -3589,7 +5654,51 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
                 }
                 ",
                 (vec![Type::I64, Type::Bool], (Type::I64)),
-                Err("L3: One or more parameters have mismatching types for function $std::test: parameter 2 expected bool but got i64"),
+                Err("L3: One or more parameters have mismatching types for function test(a: i64, a: bool) -> i64: parameter 2 (a) expected bool but got i64"),
+            ),
+            (
+                line!(),
+                "fn test() -> i64 {
+                    return 0;
+                }
+                fn main() {
+                    let k: i64 := project::std::test();
+                    return;
+                }
+                ",
+                (vec![], (Type::I64)),
+                Err("L1-3: $std::test is defined in this project and is also imported\n    hint: rename this item so that it does not have the same path as the imported item"),
+            ),
+            (
+                line!(),
+                "extern fn test() -> i64;
+                ",
+                (vec![], (Type::I64)),
+                Err("L1: $std::test is defined in this project and is also imported\n    hint: rename this item so that it does not have the same path as the imported item"),
+            ),
+            (
+                line!(),
+                "struct test {
+                    x: i64,
+                }
+                ",
+                (vec![], (Type::I64)),
+                Err("L1-3: $std::test is defined in this project and is also imported\n    hint: rename this item so that it does not have the same path as the imported item"),
+            ),
+            (
+                line!(),
+                "mod inner {
+                    fn test() -> i64 {
+                        return 0;
+                    }
+                }
+                fn main() {
+                    let k: i64 := project::std::test() + inner::test();
+                    return;
+                }
+                ",
+                (vec![], (Type::I64)),
+                Ok(()),
             ),
         ] {
             let mut sm = SourceMap::new();
@@ -3598,8 +5707,6 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
 
             let mut table = StringTable::new();
             let std = table.insert("std".into());
-            let test = table.insert("test".into());
-            let a = table.insert("a".into());
             let main_mod = table.insert(MAIN_MODULE.into());
             let main_fn = table.insert("my_main".into());
 
@@ -3613,22 +5720,21 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
             let parser = Parser::new(&logger);
             let ast = parser.parse(std, &tokens).unwrap().unwrap();
 
-            let mut import_context = SemanticContext::new_local(0, new_ctx(), Type::Unit);
-            import_context.set_canonical_path(vec![Element::CanonicalRoot, Element::Id(std), Element::Id(test)].into());
-            let manifest = Manifest::new(&sm, &table, &vec![RoutineDef{
-                context: import_context,
-                def: RoutineDefType::Function,
-                name: test,
-                ret_ty: import_func.1.clone(),
-                params: import_func.0.iter().map(|p| Parameter::new(SemanticContext::new_local(0, new_ctx(), p.clone()), a, p)).collect(),
-                body: vec![],
-            }], &vec![]).unwrap();
-            let imports = manifest.to_import(&mut table).unwrap();
+            let b = build::Builder::new(&table, || SemanticContext::new_local(0, new_ctx(), Type::Unit));
+            let mut import_fn = import_func.0.iter()
+                .fold(b.fn_def("test"), |f, p| f.param("a", p.clone()))
+                .ret_ty(import_func.1.clone())
+                .build();
+            import_fn.context.set_canonical_path(b.path(&["project", "std", "test"]));
+            let manifest = Manifest::new(&sm, &table, &vec![import_fn], &vec![]).unwrap();
+            let imports = manifest.to_import(&table).unwrap();
             let result = resolve_types_with_imports(
                 &ast,
                 main_mod, main_fn,
                 &vec![imports],
+                &table,
                 &logger,
+                &Fuel::unlimited(),
             );
             match expected {
                 Ok(_) => assert!(result.is_ok(), "TL{}: {:?} got {:?}", line, expected, result.map_err(|e| e.fmt(&sm, &table))),
@@ -3636,4 +5742,288 @@ let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger).unwrap()
             }
         }
     }
+
+    #[test]
+    pub fn test_imported_routines_resolve_like_local_externs() {
+        // Resolves `text` with `$std::test(a: i64) -> i64` imported from a manifest
+        fn resolve_with_import(text: &str) -> Result<(), String> {
+            let mut sm = SourceMap::new();
+            sm.add_string(text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+
+            let b = build::Builder::new(&table, || {
+                SemanticContext::new_local(0, new_ctx(), Type::Unit)
+            });
+            let mut import_fn = b
+                .fn_def("test")
+                .param("a", Type::I64)
+                .ret_ty(Type::I64)
+                .build();
+            import_fn
+                .context
+                .set_canonical_path(b.path(&["project", "std", "test"]));
+            let manifest = Manifest::new(&sm, &table, &vec![import_fn], &vec![]).unwrap();
+            let imports = manifest.to_import(&table).unwrap();
+
+            let fuel = Fuel::unlimited();
+            resolve_types_with_imports(
+                &ast,
+                main_mod,
+                main_fn,
+                &vec![imports],
+                &table,
+                &logger,
+                &fuel,
+            )
+            .map(|_| ())
+            .map_err(|e| e.fmt(&sm, &table).unwrap())
+        }
+
+        // The extern is declared after `main` so that errors are reported on the same
+        // line for both programs
+        for (line, args) in vec![
+            (line!(), "5"),
+            (line!(), "true"),
+            (line!(), "5, 6"),
+            (line!(), ""),
+        ] {
+            let imported = format!(
+                "fn main() {{
+                    let k: i64 := project::std::test({});
+                    return;
+                }}",
+                args
+            );
+            let local = format!(
+                "fn main() {{
+                    let k: i64 := test({});
+                    return;
+                }}
+                extern fn test(a: i64) -> i64;",
+                args
+            );
+
+            // Errors name the routine by the path used to call it, which is the only
+            // difference between the two programs
+            assert_eq!(
+                resolve_with_import(&imported).map_err(|e| e.replace("project::std::", "")),
+                resolve_with_import(&local),
+                "L{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_coroutine_errors() {
+        for (text, expected) in vec![
+            (
+                "fn main() -> i64 {
+                    let c: co i64 := init number();
+                    return 0;
+                }
+                fn number() -> i64 {
+                    return 1;
+                }",
+                "L2: init expects a coroutine but number is fn () -> i64\n    hint: init is only used to create a coroutine instance, functions are called directly",
+            ),
+            (
+                "fn main() -> i64 {
+                    yret 5;
+                    return 0;
+                }",
+                "L2: yret can only be used within a coroutine, but main is a function\n    hint: use return to return a value from a function",
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let result = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            let err = result.unwrap_err();
+            assert_eq!(err.fmt(&sm, &table).unwrap(), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    pub fn test_coroutine_done() {
+        for (text, expected) in vec![
+            (
+                "fn main() -> bool {
+                    let c: co i64 := init number();
+                    return c.done;
+                }
+                co number() -> i64 {
+                    return 1;
+                }",
+                Ok(Type::Bool),
+            ),
+            (
+                "fn main() -> bool {
+                    let mut c: co i64 := init number();
+                    mut c.done := true;
+                    return c.done;
+                }
+                co number() -> i64 {
+                    return 1;
+                }",
+                Err("L3: c.done is not mutable"),
+            ),
+            (
+                "fn main() -> bool {
+                    let c: co i64 := init number();
+                    return c.finished;
+                }
+                co number() -> i64 {
+                    return 1;
+                }",
+                Err("L3: Member access invalid root type"),
+            ),
+        ] {
+            let mut sm = SourceMap::new();
+            sm.add_string(&text, "/test".into()).unwrap();
+            let src = sm.get(0).unwrap().read().unwrap();
+
+            let mut table = StringTable::new();
+            let main = table.insert("main".into());
+            let main_mod = table.insert(MAIN_MODULE.into());
+            let main_fn = table.insert("my_main".into());
+
+            let logger = Logger::new();
+            let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+                .unwrap()
+                .tokenize()
+                .into_iter()
+                .collect::<LResult>()
+                .unwrap();
+
+            let parser = Parser::new(&logger);
+            let ast = parser.parse(main, &tokens).unwrap().unwrap();
+            let module = resolve_types(&ast, main_mod, main_fn, &table, &logger);
+            match expected {
+                Ok(expected_ty) => {
+                    let module = module.unwrap();
+                    let fn_main = module.get_functions()[0].to_routine().unwrap();
+
+                    // `done` is a member of every coroutine instance
+                    if let Statement::Return(r) = &fn_main.get_body()[1] {
+                        assert_eq!(r.get_value().clone().unwrap().get_type(), expected_ty);
+                    } else {
+                        panic!("Expected a return statement")
+                    }
+                }
+                Err(msg) => {
+                    assert_eq!(module.unwrap_err().fmt(&sm, &table).unwrap(), msg);
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_yield_non_coroutine() {
+        let table = StringTable::new();
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+        let b = build::Builder::new(&table, new_ctx);
+
+        let module = b
+            .module(MAIN_MODULE)
+            .routine(
+                b.fn_def("main")
+                    .ret_ty(Type::I64)
+                    .body(vec![
+                        b.let_("x", Type::I64, b.i64(5)),
+                        b.ret(Some(b.yield_(b.var("x")))),
+                    ])
+                    .build(),
+            )
+            .build();
+
+        let logger = Logger::new();
+        let err = resolve_types(&module, main_mod, main_fn, &table, &logger).unwrap_err();
+        assert_eq!(
+            *err.inner(),
+            super::super::super::SemanticError::YieldInvalidType(Type::I64)
+        );
+        assert_eq!(
+            err.notes()[0].message(),
+            "yield resumes a coroutine instance, which is created by calling a coroutine with init"
+        );
+    }
+
+    #[test]
+    pub fn test_built_ast() {
+        let table = StringTable::new();
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+        let b = build::Builder::new(&table, new_ctx);
+
+        let module = b
+            .module(MAIN_MODULE)
+            .routine(
+                b.fn_def("add")
+                    .param("x", Type::I64)
+                    .param("y", Type::I64)
+                    .ret_ty(Type::I64)
+                    .body(vec![b.ret(Some(b.bin(
+                        BinaryOperator::Add,
+                        b.var("x"),
+                        b.var("y"),
+                    )))])
+                    .build(),
+            )
+            .submodule("inner", |m| {
+                m.routine(
+                    b.fn_def("test")
+                        .ret_ty(Type::I64)
+                        .body(vec![
+                            b.let_(
+                                "z",
+                                Type::I64,
+                                b.call(&["super", "add"], vec![b.i64(1), b.i64(2)]),
+                            ),
+                            b.ret(Some(b.var("z"))),
+                        ])
+                        .build(),
+                )
+            })
+            .build();
+
+        let logger = Logger::new();
+        let result = resolve_types(&module, main_mod, main_fn, &table, &logger).unwrap();
+        let inner = result.get_module(table.find("inner").unwrap()).unwrap();
+        let test = inner.get_functions()[0].to_routine().unwrap();
+        assert_eq!(test.get_body()[0].get_type(), Type::I64);
+    }
 }
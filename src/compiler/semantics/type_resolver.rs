@@ -4,30 +4,43 @@ use crate::compiler::Span;
 use crate::{
     compiler::{
         ast::*,
+        fuel::Fuel,
         import::Import,
         parser::ParserContext,
-        semantics::semanticnode::{SemanticAst, SemanticNode},
+        semantics::semanticnode::{number_lowered_nodes, SemanticAst, SemanticNode},
         semantics::symbol_table::*,
         CompilerError,
     },
-    StringId,
+    StringId, StringTable,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use super::semanticnode::Addressability;
 use super::TypeOk;
 use super::{
-    canonize::canonize_paths, semanticnode::SemanticContext, stack::SymbolTableScopeStack,
-    SemanticError, SemanticResult,
+    canonize::canonize_paths,
+    semanticnode::SemanticContext,
+    stack::SymbolTableScopeStack,
+    typed::{is_partly_unknown, TypedModule},
+    RoutineSignature, SemanticError, SemanticResult,
 };
 
 pub fn resolve_types(
     ast: &Module<ParserContext>,
     main_mod: StringId,
     main_fn: StringId,
+    st: &StringTable,
     logger: &Logger,
-) -> SemanticResult<Module<SemanticContext>> {
-    resolve_types_with_imports(ast, main_mod, main_fn, &vec![], logger)
+) -> SemanticResult<TypedModule> {
+    resolve_types_with_imports(
+        ast,
+        main_mod,
+        main_fn,
+        &vec![],
+        st,
+        logger,
+        &Fuel::unlimited(),
+    )
 }
 
 pub fn resolve_types_with_imports(
@@ -35,25 +48,328 @@ pub fn resolve_types_with_imports(
     main_mod: StringId,
     main_fn: StringId,
     imports: &[Import],
+    st: &StringTable,
+    logger: &Logger,
+    fuel: &Fuel,
+) -> SemanticResult<TypedModule> {
+    let sm_ast = prepare_module(ast, imports, logger)?;
+
+    let mut semantic =
+        TypeResolver::new(&sm_ast, imports, main_mod, main_fn, st, logger).with_fuel(fuel);
+
+    semantic.resolve_types()
+}
+
+/// Converts a parsed module into a semantic module, canonizes all of its paths, and
+/// adds the definitions of every item to the symbol table of the module which
+/// contains it.  The result is ready to be given to a [`TypeResolver`].
+pub(super) fn prepare_module(
+    ast: &Module<ParserContext>,
+    imports: &[Import],
     logger: &Logger,
 ) -> SemanticResult<Module<SemanticContext>> {
     let mut sa = SemanticAst::new();
     let mut sm_ast = sa.from_module(ast);
-    canonize_paths(&mut sm_ast, imports, logger)?; //TODO: Add a trace for this step
+    SymbolTable::add_uses_to_table(&mut sm_ast)?;
+    canonize_paths(&mut sm_ast, imports, logger)?;
     SymbolTable::add_item_defs_to_table(&mut sm_ast)
         .map_err(|e| CompilerError::new(Span::zero(), e))?;
+    check_import_conflicts(&sm_ast, imports)?;
+    let root_path = vec![Element::CanonicalRoot, Element::Id(sm_ast.get_name())].into();
+    check_uses(&sm_ast, &sm_ast, &root_path, imports)?;
+    Ok(sm_ast)
+}
 
-    let mut semantic = TypeResolver::new(&sm_ast, imports, main_mod, main_fn, logger);
+/// Returns an error if the path of a `use` declaration in `module`, or any of its
+/// submodules, does not refer to an item or module in the project or in an import.
+/// `module_path` is the canonical path of `module`.
+fn check_uses(
+    root: &Module<SemanticContext>,
+    module: &Module<SemanticContext>,
+    module_path: &Path,
+    imports: &[Import],
+) -> SemanticResult<()> {
+    for u in module.get_uses() {
+        let target = u
+            .get_path()
+            .to_canonical(module_path)
+            .map_err(|e| CompilerError::new(u.span(), e.into()))?;
+
+        let is_local = root.go_to_module(&target).is_some()
+            || root
+                .go_to_module(&target.parent())
+                .and_then(|parent| parent.get_item(target.item()?))
+                .is_some();
+
+        // An imported path refers to an item, or to a module which contains an item
+        let is_imported = imports
+            .iter()
+            .flat_map(|import| {
+                import
+                    .structs
+                    .iter()
+                    .map(|sd| sd.path())
+                    .chain(import.funcs.iter().map(|rd| rd.path()))
+            })
+            .any(|path| {
+                path.len() >= target.len() && path.iter().zip(target.iter()).all(|(a, b)| a == b)
+            });
 
-    semantic.resolve_types()
+        if !is_local && !is_imported {
+            return Err(CompilerError::new(
+                u.span(),
+                SemanticError::UseNotFound(u.get_path().clone()),
+            ));
+        }
+    }
+
+    for sub in module.get_modules() {
+        let mut sub_path = module_path.clone();
+        sub_path.push(Element::Id(sub.get_name()));
+        check_uses(root, sub, &sub_path, imports)?;
+    }
+
+    Ok(())
+}
+
+/// Returns an error if an item defined in `module` has the same canonical path as an
+/// imported item.  Otherwise, which of the two items a path refers to would depend upon
+/// how it was looked up.
+fn check_import_conflicts(
+    module: &Module<SemanticContext>,
+    imports: &[Import],
+) -> SemanticResult<()> {
+    let imported_paths = imports.iter().flat_map(|import| {
+        import
+            .structs
+            .iter()
+            .map(|sd| sd.path())
+            .chain(import.funcs.iter().map(|rd| rd.path()))
+    });
+
+    for path in imported_paths {
+        let local = path
+            .item()
+            .and_then(|item| Some((module.go_to_module(&path.parent())?, item)))
+            .and_then(|(parent, item)| parent.get_item(item));
+
+        if let Some(local) = local {
+            return Err(CompilerError::new(
+                local.span(),
+                SemanticError::ImportConflict(path.clone()),
+            )
+            .with_hint(
+                "rename this item so that it does not have the same path as the imported item",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// If `err` was caused by declaring a name which already exists in the same scope, then
+/// add a note which points to the previous declaration of that name.
+fn note_previous_declaration(
+    err: CompilerError<SemanticError>,
+    previous: Option<&Symbol>,
+) -> CompilerError<SemanticError> {
+    match previous.and_then(|p| p.span) {
+        Some(span) if matches!(err.inner(), SemanticError::AlreadyDeclared(_)) => {
+            err.with_note(span, "previous declaration here")
+        }
+        _ => err,
+    }
+}
+
+/// If `exp` is an array and `expected` is a slice with the same element type, then
+/// `exp` is wrapped in a cast to the slice, so that an array can be given wherever a
+/// slice is expected.  Otherwise, `exp` is returned unchanged, unless it is a `none`,
+/// `ok`, or `err` which takes the expected type (see [`coerce_partial`]).
+fn coerce(expected: &Type, exp: SemanticNode) -> SemanticNode {
+    match (expected, exp.get_type()) {
+        (Type::Slice(_), Type::Array(..)) if exp.get_type().can_cast_to(expected) => {
+            let ctx = exp.context().with_value_type(expected.clone());
+            Expression::TypeCast(ctx, Box::new(exp), Box::new(expected.clone()))
+        }
+        _ => coerce_partial(expected, exp),
+    }
+}
+
+/// `none` has the type `opt unknown`, `ok(x)` has the type `res T, unknown`, and `err(e)`
+/// has the type `res unknown, E` until they are used where an option or result is
+/// expected.  If `exp` is one of them, or a block, `if`, or constructor whose value holds
+/// one, and its type can be unified with `expected`, then it is given the unified type.
+fn coerce_partial(expected: &Type, exp: SemanticNode) -> SemanticNode {
+    if !is_partly_unknown(exp.get_type()) {
+        return exp;
+    }
+
+    let ty = match unify_partial(expected, exp.get_type()) {
+        Some(ty) => ty,
+        None => return exp,
+    };
+
+    match exp {
+        Expression::OptionNone(ctx) => Expression::OptionNone(ctx.with_type(ty)),
+        Expression::UnaryOp(ctx, op, value) => {
+            let inner = match (op, &ty) {
+                (UnaryOperator::OptionSome, Type::Option(inner))
+                | (UnaryOperator::ResultOk, Type::Result(inner, _))
+                | (UnaryOperator::ResultErr, Type::Result(_, inner)) => (**inner).clone(),
+                _ => return Expression::UnaryOp(ctx, op, value),
+            };
+            Expression::UnaryOp(
+                ctx.with_type(ty),
+                op,
+                Box::new(coerce_partial(&inner, *value)),
+            )
+        }
+        Expression::ExpressionBlock(ctx, body, Some(final_exp)) => Expression::ExpressionBlock(
+            ctx.with_type(ty.clone()),
+            body,
+            Some(Box::new(coerce_partial(&ty, *final_exp))),
+        ),
+        Expression::If {
+            context,
+            cond,
+            if_arm,
+            else_arm,
+        } => Expression::If {
+            context: context.with_type(ty.clone()),
+            cond,
+            if_arm: Box::new(coerce_partial(&ty, *if_arm)),
+            else_arm: else_arm.map(|e| Box::new(coerce_partial(&ty, *e))),
+        },
+        exp => exp,
+    }
+}
+
+/// Returns the type which both `a` and `b` can be, where an unknown type, within an option
+/// or result, can be any type.  Returns `None` if there is no such type.
+fn unify_partial(a: &Type, b: &Type) -> Option<Type> {
+    match (a, b) {
+        (Type::Unknown, ty) | (ty, Type::Unknown) => Some(ty.clone()),
+        (Type::Option(a), Type::Option(b)) => Some(Type::Option(Box::new(unify_partial(a, b)?))),
+        (Type::Result(a_ok, a_err), Type::Result(b_ok, b_err)) => Some(Type::Result(
+            Box::new(unify_partial(a_ok, b_ok)?),
+            Box::new(unify_partial(a_err, b_err)?),
+        )),
+        (a, b) if a == b => Some(a.clone()),
+        _ => None,
+    }
+}
+
+/// Returns the type of a binding, which the parser created without a type annotation (e.g.
+/// for the sequence that a `for` loop iterates over), whose value has the type `ty`.  An
+/// array is bound as a slice of it, so that the binding can be replaced with a part of the
+/// array.
+fn inferred_type(ty: &Type) -> Type {
+    match ty {
+        Type::Array(el_ty, _) => Type::Slice(el_ty.clone()),
+        ty => ty.clone(),
+    }
+}
+
+/// Returns `true` if a value of type `ty` is, or holds, a raw pointer.
+fn has_raw_pointer(ty: &Type) -> bool {
+    match ty {
+        Type::RawPointer(..) => true,
+        Type::Array(el_ty, _) | Type::Slice(el_ty) | Type::Option(el_ty) => has_raw_pointer(el_ty),
+        Type::Result(ok_ty, err_ty) => has_raw_pointer(ok_ty) || has_raw_pointer(err_ty),
+        _ => false,
+    }
+}
+
+/// Returns the canonical path of every `const fn`, including methods, in `module` and its
+/// submodules.
+fn const_fns(module: &Module<SemanticContext>) -> HashSet<Path> {
+    let functions = module
+        .deep_get_functions()
+        .into_iter()
+        .filter_map(|f| f.to_routine());
+    let structs = module.deep_get_structs();
+    let methods = structs.iter().flat_map(|sd| sd.get_methods().iter());
+
+    functions
+        .chain(methods)
+        .filter(|r| r.is_const())
+        .map(|r| r.context().canonical_path().clone())
+        .collect()
+}
+
+/// Returns the fields of every structure, defined in the project or imported, which are
+/// not `pub`, keyed by the canonical path of the structure.
+fn private_fields(
+    module: &Module<SemanticContext>,
+    imports: &[Import],
+) -> HashMap<Path, Vec<StringId>> {
+    let local = module.deep_get_structs().into_iter().map(|sd| {
+        let private = sd
+            .get_fields()
+            .iter()
+            .map(|f| f.name)
+            .filter(|f| !sd.is_public(*f))
+            .collect();
+        (sd.context().canonical_path().clone(), private)
+    });
+    let imported = imports
+        .iter()
+        .flat_map(|i| i.structs.iter())
+        .map(|sd| (sd.path().clone(), sd.private_fields().to_vec()));
+
+    local.chain(imported).collect()
+}
+
+/// Returns the canonical path of the routine generated by `#[derive(eq)]` for every
+/// structure in the project which derives `eq`, keyed by the canonical path of the
+/// structure.
+fn derived_eqs(module: &Module<SemanticContext>) -> HashMap<Path, Path> {
+    module
+        .deep_get_structs()
+        .into_iter()
+        .filter_map(|sd| {
+            let path = sd.context().canonical_path();
+            sd.get_derived_eq().map(|eq| {
+                let mut eq_path = path.parent();
+                eq_path.push(Element::Id(eq));
+                (path.clone(), eq_path)
+            })
+        })
+        .collect()
 }
 
 pub struct TypeResolver<'a> {
     symbols: SymbolTableScopeStack,
-    imported_symbols: HashMap<String, Symbol>,
+
+    /// The canonical paths of every `const fn` in the project
+    const_fns: HashSet<Path>,
+
+    /// The fields of each structure which can only be used within the module which
+    /// defines the structure
+    private_fields: HashMap<Path, Vec<StringId>>,
+
+    /// The routine which `==` calls to compare two values of each structure that
+    /// derives `eq`
+    derived_eqs: HashMap<Path, Path>,
+
+    /// `true` while the body of a `const fn` is being analyzed
+    in_const_fn: bool,
+
+    /// `true` while the expression of a `defer` statement is being analyzed
+    in_defer: bool,
+
+    /// The label, if any, of each loop which encloses the code being analyzed, from the
+    /// outermost loop to the innermost
+    loops: Vec<Option<StringId>>,
     main_fn: Path,
+
+    /// Used to name the methods which perform the steps of the iterator protocol
+    st: &'a StringTable,
     logger: &'a Logger<'a>,
     event_stack: EventStack,
+
+    /// Limits the work done by the resolver, which burns a unit for every expression
+    fuel: Option<&'a Fuel>,
 }
 
 impl<'a> TypeResolver<'a> {
@@ -62,26 +378,81 @@ impl<'a> TypeResolver<'a> {
         imports: &[Import],
         main_mod: StringId,
         main_fn: StringId,
+        st: &'a StringTable,
         logger: &'a Logger,
     ) -> TypeResolver<'a> {
         TypeResolver {
             symbols: SymbolTableScopeStack::new(root, imports),
-            imported_symbols: HashMap::new(),
+            const_fns: const_fns(root),
+            private_fields: private_fields(root, imports),
+            derived_eqs: derived_eqs(root),
+            in_const_fn: false,
+            in_defer: false,
+            loops: vec![],
             main_fn: vec![
                 Element::CanonicalRoot,
                 Element::Id(main_mod),
                 Element::Id(main_fn),
             ]
             .into(), // TODO: should get rid of this,
+            st,
             logger,
             event_stack: EventStack::new(),
+            fuel: None,
         }
     }
 
-    pub fn resolve_types(&mut self) -> SemanticResult<Module<SemanticContext>> {
+    /// Limits the work the resolver may do to the given fuel.
+    pub fn with_fuel(mut self, fuel: &'a Fuel) -> TypeResolver<'a> {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    pub fn resolve_types(&mut self) -> SemanticResult<TypedModule> {
         // TODO: I think that this is the problem, perhaps I should get rid of the concept
         // of the stack root?  I need root to be able to find items using the stack.
-        self.analyze_module(self.symbols.get_root())
+        let module = self.analyze_module(self.symbols.get_root())?;
+        let module = number_lowered_nodes(&module);
+        debug_assert!(
+            {
+                let mut ids = HashSet::new();
+                module.iter_preorder().all(|n| ids.insert(n.context().id()))
+            },
+            "Every node must have a unique ID after type resolution"
+        );
+        TypedModule::check(module)
+    }
+
+    /// Resolves the types of the single item with the given canonical path, rather than
+    /// the entire module tree.  The symbol tables of every module containing the item are
+    /// entered first, so the item is analyzed within the same scope as it would be by
+    /// [`TypeResolver::resolve_types`].
+    ///
+    /// Returns `None` if there is no item with the given path.
+    pub fn resolve_item(&mut self, path: &Path) -> Option<SemanticResult<Item<SemanticContext>>> {
+        let root = self.symbols.get_root();
+
+        // Find each module from the root down to the parent of the item
+        let mut modules = vec![root];
+        for el in path.parent().iter().skip(1) {
+            match el {
+                Element::Id(id) => modules.push(modules.last()?.get_module(*id)?),
+                _ => return None,
+            }
+        }
+        let item = modules.last()?.get_item(path.item()?)?;
+
+        for m in &modules {
+            self.symbols.enter_scope(m.context().sym().clone());
+        }
+
+        let result = self.analyze_item(item);
+
+        for _ in &modules {
+            self.symbols.leave_scope();
+        }
+
+        Some(result)
     }
 
     fn analyze_module(
@@ -89,6 +460,9 @@ impl<'a> TypeResolver<'a> {
         m: &Module<SemanticContext>,
     ) -> SemanticResult<Module<SemanticContext>> {
         let mut nmodule = Module::new(m.get_name(), m.context().clone());
+        for u in m.get_uses() {
+            nmodule.add_use(u.clone());
+        }
 
         self.symbols.enter_scope(nmodule.context().sym().clone());
 
@@ -127,11 +501,15 @@ impl<'a> TypeResolver<'a> {
     }
 
     fn analyze_item(&mut self, i: &Item<SemanticContext>) -> SemanticResult<Item<SemanticContext>> {
-        match i {
+        let result = match i {
             Item::Struct(s) => self.analyze_structdef(s).map(Item::Struct),
             Item::Routine(r) => self.analyze_routine(r).map(Item::Routine),
             Item::Extern(ex) => self.analyze_extern(ex).map(Item::Extern),
-        }
+        };
+
+        // Show paths in errors as the user would write them in the module containing this item
+        let module = i.context().canonical_path().parent();
+        result.map_err(|e| e.map_inner(|inner| inner.to_source_paths(&module)))
     }
 
     fn analyze_routine(
@@ -154,6 +532,16 @@ impl<'a> TypeResolver<'a> {
                 Self::validate_main_fn(routine)?;
             }
 
+            // A const fn can be evaluated at compile time, so it cannot be given or return
+            // a pointer
+            self.in_const_fn = routine.is_const();
+            if self.in_const_fn {
+                for p in params {
+                    self.check_const_fn_type(&p.ty, p.context().span())?;
+                }
+                self.check_const_fn_type(ret_ty, context.span())?;
+            }
+
             let mut ctx = context.with_type(ret_ty.clone());
 
             // Add parameters to symbol table
@@ -175,7 +563,12 @@ impl<'a> TypeResolver<'a> {
                     false,
                     resolved_param.span(),
                 )
-                .map_err(|e| CompilerError::new(p.span(), e))?;
+                .map_err(|e| {
+                    note_previous_declaration(
+                        CompilerError::new(p.span(), e),
+                        ctx.sym().get(p.name),
+                    )
+                })?;
 
                 self.record(&resolved_param, vec![]);
                 resolved_params.push(resolved_param);
@@ -190,10 +583,12 @@ impl<'a> TypeResolver<'a> {
             }
 
             let sym = self.symbols.leave_scope();
+            self.in_const_fn = false;
 
             Ok(RoutineDef {
                 context: ctx.with_sym(sym),
                 def: def.clone(),
+                is_const: routine.is_const,
                 name: *name,
                 params: resolved_params,
                 ret_ty: ret_ty.clone(),
@@ -225,8 +620,21 @@ impl<'a> TypeResolver<'a> {
 
             // Update the context with canonical path information and set the type to Type::Unit
             let ctx = struct_def.context().with_type(Type::Unit);
+            let mut resolved_struct = StructDef::new(struct_def.get_name(), ctx, resolved_fields);
+            *resolved_struct.get_public_fields_mut() = struct_def.get_public_fields().clone();
+            *resolved_struct.get_attributes_mut() = struct_def.get_attributes().clone();
+            if let Some(eq) = struct_def.get_derived_eq() {
+                resolved_struct.set_derived_eq(eq);
+            }
+
+            self.symbols.enter_scope(struct_def.context().sym().clone());
+            for m in struct_def.get_methods() {
+                let resolved_method = self.analyze_routine(m)?;
+                resolved_struct.get_methods_mut().push(resolved_method);
+            }
+            self.symbols.leave_scope();
 
-            Ok(StructDef::new(struct_def.get_name(), ctx, resolved_fields))
+            Ok(resolved_struct)
         });
         result.view(|e| self.record2(event, e, vec![]))
     }
@@ -252,11 +660,15 @@ impl<'a> TypeResolver<'a> {
             }
 
             // Update the context with canonical path information and set the type to Type::Unit
-            let name = ex.name().expect("Externs must have a name");
             let ctx = ex.context().with_type(ex.get_return_type().clone());
             let ret_ty = ctx.ty().clone();
 
-            Ok(Extern::new(name, ctx, params, ex.has_varargs, ret_ty))
+            // Cloning keeps the attributes and link name of the extern
+            let mut nex = ex.clone();
+            nex.context = ctx;
+            nex.params = params;
+            nex.ty = ret_ty;
+            Ok(nex)
         });
         result.view(|e| self.record2(event, e, vec![]))
     }
@@ -269,6 +681,10 @@ impl<'a> TypeResolver<'a> {
         let inner = match stmt {
             Bind(b) => Bind(Box::new(self.analyze_bind(b)?)),
             Mutate(b) => Mutate(Box::new(self.analyze_mutate(b)?)),
+            Discard(d) => Discard(Box::new(self.analyze_discard(d)?)),
+            Defer(d) => Defer(Box::new(self.analyze_defer(d)?)),
+            Break(b) => Break(Box::new(self.analyze_break(b)?)),
+            Continue(c) => Continue(Box::new(self.analyze_continue(c)?)),
             Return(x) => Return(Box::new(self.analyze_return(x)?)),
             YieldReturn(x) => YieldReturn(Box::new(self.analyze_yieldreturn(x)?)),
             Expression(e) => Expression(Box::new(self.analyze_expression(e)?)),
@@ -287,8 +703,12 @@ impl<'a> TypeResolver<'a> {
             {
                 // Check that the type from the type annotation exists
                 self.valid_type(bind.get_type(), ctx.span())?;
-                let ctx = ctx.with_type(bind.get_type().clone());
                 let rhs = self.analyze_expression(rhs)?;
+                let ctx = match bind.get_type() {
+                    Type::Unknown => ctx.with_type(inferred_type(rhs.get_type())),
+                    ty => ctx.with_type(ty.clone()),
+                };
+                let rhs = coerce(ctx.ty(), rhs);
                 if ctx.ty().can_be_assigned(rhs.get_type()) {
                     match self.symbols.add(
                         bind.get_id(),
@@ -310,7 +730,12 @@ impl<'a> TypeResolver<'a> {
                     ))
                 }
             }
-            .map_err(|e| CompilerError::new(ctx.span(), e))
+            .map_err(|e| {
+                note_previous_declaration(
+                    CompilerError::new(ctx.span(), e),
+                    self.symbols.lookup_local(bind.get_id()),
+                )
+            })
         });
         result.view(|e| self.record2(event, e, vec![]))
     }
@@ -321,7 +746,7 @@ impl<'a> TypeResolver<'a> {
     ) -> SemanticResult<Mutate<SemanticContext>> {
         let (event, result) = self.new_event().and_then(|| {
             let lhs = self.analyze_expression(mutate.get_lhs())?;
-            let rhs = self.analyze_expression(mutate.get_rhs())?;
+            let rhs = coerce(lhs.get_type(), self.analyze_expression(mutate.get_rhs())?);
             if lhs.context().is_mutable() {
                 if lhs.get_type().can_be_assigned(rhs.get_type()) {
                     let ctx = mutate.context().with_type(rhs.get_type().clone());
@@ -341,6 +766,89 @@ impl<'a> TypeResolver<'a> {
         result.view(|e| self.record2(event, e, vec![]))
     }
 
+    fn analyze_discard(
+        &mut self,
+        discard: &Discard<SemanticContext>,
+    ) -> SemanticResult<Discard<SemanticContext>> {
+        let (event, result) = self.new_event().and_then(|| {
+            let value = self.analyze_expression(discard.get_value())?;
+            let ctx = discard.context().with_type(Type::Unit);
+            Ok(Discard::new(ctx, value))
+        });
+        result.view(|e| self.record2(event, e, vec![]))
+    }
+
+    fn analyze_defer(
+        &mut self,
+        defer: &Defer<SemanticContext>,
+    ) -> SemanticResult<Defer<SemanticContext>> {
+        let (event, result) = self.new_event().and_then(|| {
+            // A deferred expression may itself contain a defer, so restore the outer state.
+            // The deferred expression runs as its scope is exited, so it cannot break out
+            // of or continue the loops which enclose the defer.
+            let in_defer = std::mem::replace(&mut self.in_defer, true);
+            let loops = std::mem::take(&mut self.loops);
+            let value = self.analyze_expression(defer.get_value());
+            self.in_defer = in_defer;
+            self.loops = loops;
+
+            let ctx = defer.context().with_type(Type::Unit);
+            Ok(Defer::new(ctx, value?))
+        });
+        result.view(|e| self.record2(event, e, vec![]))
+    }
+
+    fn analyze_break(
+        &mut self,
+        b: &Break<SemanticContext>,
+    ) -> SemanticResult<Break<SemanticContext>> {
+        let (event, result) = self.new_event().and_then(|| {
+            self.check_loop(b.get_label(), SemanticError::BreakOutsideLoop)
+                .map_err(|e| self.loop_error(b.span(), e))?;
+            let ctx = b.context().with_type(Type::Unit);
+            Ok(Break::new(ctx, b.get_label()))
+        });
+        result.view(|e| self.record2(event, e, vec![]))
+    }
+
+    fn analyze_continue(
+        &mut self,
+        c: &Continue<SemanticContext>,
+    ) -> SemanticResult<Continue<SemanticContext>> {
+        let (event, result) = self.new_event().and_then(|| {
+            self.check_loop(c.get_label(), SemanticError::ContinueOutsideLoop)
+                .map_err(|e| self.loop_error(c.span(), e))?;
+            let ctx = c.context().with_type(Type::Unit);
+            Ok(Continue::new(ctx, c.get_label()))
+        });
+        result.view(|e| self.record2(event, e, vec![]))
+    }
+
+    /// Checks that a `break` or `continue` with the given label has a loop to exit: the
+    /// innermost loop if there is no label, otherwise the innermost loop with that label.
+    fn check_loop(
+        &self,
+        label: Option<StringId>,
+        outside: SemanticError,
+    ) -> Result<(), SemanticError> {
+        match label {
+            None if self.loops.is_empty() => Err(outside),
+            None => Ok(()),
+            Some(label) if self.loops.contains(&Some(label)) => Ok(()),
+            Some(label) => Err(SemanticError::LoopLabelNotFound(label)),
+        }
+    }
+
+    /// Creates the error for a `break` or `continue` which has no loop to exit.
+    fn loop_error(&self, span: Span, e: SemanticError) -> CompilerError<SemanticError> {
+        let err = CompilerError::new(span, e);
+        if self.in_defer {
+            err.with_hint("a deferred expression cannot leave the loops which enclose the defer")
+        } else {
+            err
+        }
+    }
+
     fn analyze_yieldreturn(
         &mut self,
         yr: &YieldReturn<SemanticContext>,
@@ -362,10 +870,16 @@ impl<'a> TypeResolver<'a> {
             let current_func = self.symbols.get_current_fn().ok_or_else(|| {
                 CompilerError::new(yr.span(), SemanticError::YieldInvalidLocation)
             })?;
-            let (_, expected_ret_ty) = self
-                .symbols
-                .lookup_coroutine(current_func)
-                .map_err(|e| CompilerError::new(yr.span(), e))?;
+            let (_, expected_ret_ty) =
+                self.symbols
+                    .lookup_coroutine(current_func)
+                    .map_err(|e| match e {
+                        SemanticError::NotCoroutine(f) => {
+                            CompilerError::new(yr.span(), SemanticError::YieldReturnInFunction(f))
+                                .with_hint("use return to return a value from a function")
+                        }
+                        e => CompilerError::new(yr.span(), e),
+                    })?;
 
             if actual_ret_ty == expected_ret_ty {
                 let ctx = yr.context().with_type(actual_ret_ty);
@@ -386,15 +900,15 @@ impl<'a> TypeResolver<'a> {
         r: &Return<SemanticContext>,
     ) -> SemanticResult<Return<SemanticContext>> {
         let (event, result) = self.new_event().and_then(|| {
+            if self.in_defer {
+                return Err(CompilerError::new(r.span(), SemanticError::ReturnInDefer));
+            }
+
             // Get the actual expression and its type as it comes from the
             // source code written by the user.
-            let (actual_ret_exp, actual_ret_ty) = match r.get_value() {
-                None => (None, Type::Unit),
-                Some(exp) => {
-                    let exp = self.analyze_expression(exp)?;
-                    let exp_ty = exp.get_type().clone();
-                    (Some(exp), exp_ty)
-                }
+            let actual_ret_exp = match r.get_value() {
+                None => None,
+                Some(exp) => Some(self.analyze_expression(exp)?),
             };
 
             // Get the expected return type of the function that the return
@@ -409,9 +923,14 @@ impl<'a> TypeResolver<'a> {
                 .lookup_func_or_cor(current_func)
                 .map_err(|e| CompilerError::new(r.span(), e))?;
 
+            let actual_ret_exp = actual_ret_exp.map(|exp| coerce_partial(expected_ret_ty, exp));
+            let actual_ret_ty = actual_ret_exp
+                .as_ref()
+                .map_or(Type::Unit, |exp| exp.get_type().clone());
+
             // Check that the actual expression matches the expected return type
             // of the function
-            if actual_ret_ty == expected_ret_ty {
+            if actual_ret_ty == expected_ret_ty || actual_ret_ty == Type::Never {
                 let ctx = r.context().with_type(actual_ret_ty);
                 Ok(Return::new(ctx, actual_ret_exp))
             } else {
@@ -431,6 +950,12 @@ impl<'a> TypeResolver<'a> {
     /// error if there is a mismatch between the required type of an operand and
     /// the given type of an operand.
     fn analyze_expression(&mut self, ast: &SemanticNode) -> SemanticResult<SemanticNode> {
+        if let Some(fuel) = self.fuel {
+            fuel.burn(1).map_err(|e| {
+                CompilerError::new(ast.context().span(), SemanticError::ResourceExhausted(e))
+            })?;
+        }
+
         let mut refs = vec![];
         let (event, result) = self.new_event().and_then(|| {
         match &ast {
@@ -438,6 +963,11 @@ impl<'a> TypeResolver<'a> {
                 let ctx = ctx.with_type(Type::Null);
                 Ok(Expression::Null(ctx))
             }
+            Expression::OptionNone(ctx) => {
+                // The option type is taken from where the none is used (see coerce_partial)
+                let ctx = ctx.with_type(Type::Option(Box::new(Type::Unknown)));
+                Ok(Expression::OptionNone(ctx))
+            }
             Expression::U8(ctx, v) => {
                 let ctx = ctx.with_type(Type::U8);
                 Ok(Expression::U8(ctx, *v))
@@ -518,33 +1048,48 @@ impl<'a> TypeResolver<'a> {
                 array,
                 index,
             } => {
-                //  Check that the array value is an array type
+                //  Check that the array value is an array, slice, or range
                 let array = self.analyze_expression(array)?;
 
                 refs.push(array.span());
 
                 let el_ty = match array.context().ty() {
-                    Type::Array(el_ty, _) => Ok(*el_ty.clone()),
+                    Type::Array(el_ty, _) | Type::Slice(el_ty) | Type::Range(el_ty) => {
+                        Ok(*el_ty.clone())
+                    }
                     ty => Err(CompilerError::new(
                         ctx.span(),
                         SemanticError::ArrayIndexingInvalidType(ty.clone()),
                     )),
                 }?;
 
-                // Check that the index is an i64 type
+                // Check that the index is an integer or, to take a part of the sequence, a range
                 let n_index = self.analyze_expression(index)?;
-                if !n_index.context().ty().is_integral() {
+                let index_ty = n_index.context().ty();
+                if !index_ty.is_integral() && !matches!(index_ty, Type::Range(_)) {
                     return Err(CompilerError::new(
                         ctx.span(),
-                        SemanticError::ArrayIndexingInvalidIndexType(
-                            n_index.context().ty().clone(),
-                        ),
+                        SemanticError::ArrayIndexingInvalidIndexType(index_ty.clone()),
                     ));
                 }
 
                 // If the source expression is an addressable location or is mutable then copy that
-                // property
-                let ctx = if array.context().is_mutable() {
+                // property.  The elements of a slice are always in memory but a slice is only a
+                // view of them, so they cannot be mutated through it.  The elements of a range
+                // are computed from its bounds, so they are not in memory at all.
+                let ctx = if let Type::Range(_) = n_index.context().ty() {
+                    // Indexing with a range takes the elements from its start up to its end:
+                    // a slice of an array or a slice, or a range of a range
+                    let ty = match array.context().ty() {
+                        Type::Range(_) => Type::Range(Box::new(el_ty)),
+                        _ => Type::Slice(Box::new(el_ty)),
+                    };
+                    ctx.with_value_type(ty)
+                } else if let Type::Range(_) = array.context().ty() {
+                    ctx.with_value_type(el_ty)
+                } else if let Type::Slice(_) = array.context().ty() {
+                    ctx.with_type(el_ty).with_addressable(false)
+                } else if array.context().is_mutable() {
                     ctx.with_type(el_ty)
                         .with_addressable(true)
                 } else if array.context().is_addressable() {
@@ -565,12 +1110,48 @@ impl<'a> TypeResolver<'a> {
                 self.valid_type(ty.as_ref(), ctx.span())?;
                 Ok(Expression::SizeOf(ctx, ty.clone()))
             }
+            Expression::FieldsOf(ctx, ty) => {
+                // The names of the fields are already strings, but the name of each type
+                // can only be written after type resolution, so it is left as `name_of`
+                let fields = self.struct_fields(ty.as_ref(), ctx.span())?;
+                if fields.is_empty() {
+                    return err!(ctx.span(), SemanticError::ArrayInvalidSize(0));
+                }
+
+                let string_ctx = ctx.with_type(Type::StringLiteral);
+                let pair_ty = Type::Array(Box::new(Type::StringLiteral), 2);
+                let pairs: Vec<_> = fields
+                    .into_iter()
+                    .map(|(name, field_ty)| {
+                        let name = Expression::StringLiteral(string_ctx.clone(), name);
+                        let field_ty =
+                            Expression::NameOf(string_ctx.clone(), Box::new(field_ty));
+                        Expression::ArrayExpression(
+                            ctx.with_type(pair_ty.clone()),
+                            vec![name, field_ty],
+                            2,
+                        )
+                    })
+                    .collect();
+
+                let len = pairs.len();
+                let ctx = ctx.with_type(Type::Array(Box::new(pair_ty), len));
+                Ok(Expression::ArrayExpression(ctx, pairs, len))
+            }
+            Expression::MacroCall(..) => {
+                panic!("Macros must be expanded before types are resolved")
+            }
+            Expression::NameOf(ctx, ty) => {
+                let ctx = ctx.with_type(Type::StringLiteral);
+                self.valid_type(ty.as_ref(), ctx.span())?;
+                Ok(Expression::NameOf(ctx, ty.clone()))
+            }
             Expression::CustomType(ctx, name) => {
                 let ctx = ctx.with_type(Type::Custom(name.clone()));
                 Ok(Expression::CustomType(ctx, name.clone()))
             }
             Expression::IdentifierDeclare(ctx, name, p) => {
-                let ctx = ctx.with_type(p.clone());
+                let ctx = ctx.with_type((**p).clone());
                 Ok(Expression::IdentifierDeclare(ctx, *name, p.clone()))
             }
             Expression::Identifier(ctx, id) => {
@@ -602,6 +1183,8 @@ impl<'a> TypeResolver<'a> {
                         // Record the span of the struct definition as a reference for resolving the type of the member access
                         if let Some(s) = struct_def.span{ refs.push(s)};
 
+                        self.check_field_visible(struct_name, *member, ctx.span())?;
+
                         let member_ty = struct_def
                             .ty
                             .get_member(*member)
@@ -623,6 +1206,12 @@ impl<'a> TypeResolver<'a> {
 
                         Ok(Expression::MemberAccess(ctx, Box::new(src), *member))
                     }
+                    // `done` is true once the coroutine has returned.  It is set by the
+                    // coroutine, so it is neither addressable nor mutable.
+                    Type::Coroutine(_) if *member == self.st.insert("done".into()) => {
+                        let ctx = ctx.with_type(Type::Bool);
+                        Ok(Expression::MemberAccess(ctx, Box::new(src), *member))
+                    }
                     _ => Err(CompilerError::new(
                         ctx.span(),
                         SemanticError::MemberAccessInvalidRootType(src.get_type().clone()),
@@ -632,7 +1221,30 @@ impl<'a> TypeResolver<'a> {
             Expression::BinaryOp(ctx, op, l, r) => {
                 let (ty, l, r) = self.binary_op(*op, l, r)?;
                 let ctx = ctx.with_type(ty);
-                Ok(Expression::BinaryOp(ctx, *op, Box::new(l), Box::new(r)))
+
+                // Comparing two structures which derive `eq` calls their eq routine
+                let eq = match (op, l.get_type()) {
+                    (BinaryOperator::Eq | BinaryOperator::NEq, Type::Custom(path)) => {
+                        self.derived_eqs.get(path).cloned()
+                    }
+                    _ => None,
+                };
+                match eq {
+                    Some(eq) => {
+                        let call = Expression::RoutineCall(
+                            ctx.clone(),
+                            RoutineCall::Function,
+                            Box::new(eq),
+                            vec![l, r],
+                        );
+                        Ok(if *op == BinaryOperator::NEq {
+                            Expression::UnaryOp(ctx, UnaryOperator::Not, Box::new(call))
+                        } else {
+                            call
+                        })
+                    }
+                    None => Ok(Expression::BinaryOp(ctx, *op, Box::new(l), Box::new(r))),
+                }
             }
             Expression::UnaryOp(ctx, op, operand) => {
                 let (ty, addry, operand) = self.unary_op(*op, operand)?;
@@ -659,13 +1271,30 @@ impl<'a> TypeResolver<'a> {
                         .map(|e| self.analyze_expression(e))
                         .map_or(Ok(None), |r| r.map(|x| Some(Box::new(x))))?;
 
+                    // A none, ok, or err in one arm takes the type of the other arm
+                    let if_arm = match &else_arm {
+                        Some(else_arm) => coerce_partial(else_arm.get_type(), if_arm),
+                        None => if_arm,
+                    };
+                    let else_arm =
+                        else_arm.map(|e| Box::new(coerce_partial(if_arm.get_type(), *e)));
+
                     let else_arm_ty = else_arm
                         .as_ref()
                         .map(|e| e.get_type().clone())
                         .unwrap_or(Type::Unit);
 
-                    if if_arm.get_type() == else_arm_ty {
-                        let ctx = ctx.with_type(if_arm.get_type().clone());
+                    // An arm of type never diverges, so the if expression takes the type
+                    // of the other arm
+                    let if_ty = match (if_arm.get_type(), &else_arm_ty) {
+                        (Type::Never, ty) => Some(ty.clone()),
+                        (ty, Type::Never) => Some(ty.clone()),
+                        (ty, else_ty) if ty == else_ty => Some(ty.clone()),
+                        _ => None,
+                    };
+
+                    if let Some(if_ty) = if_ty {
+                        let ctx = ctx.with_type(if_ty);
                         Ok(Expression::If {
                             context: ctx,
                             cond: Box::new(cond),
@@ -673,13 +1302,29 @@ impl<'a> TypeResolver<'a> {
                             else_arm: else_arm,
                         })
                     } else {
-                        Err(CompilerError::new(
+                        let mut err = CompilerError::new(
                             ctx.span(),
                             SemanticError::IfExprMismatchArms(
                                 if_arm.get_type().clone(),
-                                else_arm_ty,
+                                else_arm_ty.clone(),
                             ),
-                        ))
+                        )
+                        .with_note(if_arm.span(), "true arm");
+                        err = match &else_arm {
+                            Some(else_arm) => err.with_note(else_arm.span(), "false arm"),
+                            None => err.with_hint("an if without an else arm has type unit"),
+                        };
+
+                        // null is only coerced to a pointer when it is assigned to or compared
+                        // with a pointer, so it will not take the type of the other arm
+                        match (if_arm.get_type(), &else_arm_ty) {
+                            (Type::Null, Type::RawPointer(..)) | (Type::RawPointer(..), Type::Null) => {
+                                Err(err.with_hint(
+                                    "null is not coerced to the pointer type of the other arm",
+                                ))
+                            }
+                            _ => Err(err),
+                        }
                     }
                 } else {
                     Err(CompilerError::new(
@@ -690,18 +1335,23 @@ impl<'a> TypeResolver<'a> {
             }
             Expression::While {
                 context: ctx,
+                label,
                 cond,
                 body,
-                ..
             } => {
                 let cond = self.analyze_expression(cond)?;
                 if cond.get_type() == Type::Bool {
-                    let body = self.analyze_expression(body)?;
+                    self.loops.push(*label);
+                    let body = self.analyze_expression(body);
+                    self.loops.pop();
+                    let body = body?;
 
-                    if body.get_type() == Type::Unit {
+                    // The body of a loop may diverge, but the loop itself may never run its body
+                    if body.get_type() == Type::Unit || body.get_type() == Type::Never {
                         let ctx = ctx.with_type(Type::Unit);
                         Ok(Expression::While {
                             context: ctx,
+                            label: *label,
                             cond: Box::new( cond),
                             body: Box::new(body),
                         })
@@ -726,11 +1376,29 @@ impl<'a> TypeResolver<'a> {
                         return Err(CompilerError::new(
                             ctx.span(),
                             SemanticError::YieldInvalidType(exp.get_type().clone()),
+                        )
+                        .with_hint(
+                            "yield resumes a coroutine instance, which is created by calling a coroutine with init",
                         ))
                     }
                 };
                 Ok(Expression::Yield(ctx, Box::new(exp)))
             }
+            Expression::RoutineCall(ctx, RoutineCall::Iterator(step), _, params) => {
+                // A structure is iterated by calling its methods, and every other sequence
+                // by builtin operations
+                let seq = params.first().expect("An iterator step must have a sequence");
+                let step = match self.analyze_expression(seq)?.get_type() {
+                    Type::Custom(_) => Expression::RoutineCall(
+                        ctx.clone(),
+                        RoutineCall::Method,
+                        Box::new(vec![Element::Id(self.iterator_method(*step))].into()),
+                        params.clone(),
+                    ),
+                    _ => Self::builtin_iterator_step(ctx, *step, seq),
+                };
+                self.analyze_expression(&step)
+            }
             Expression::RoutineCall(ctx, call, routine_path, params) => {
                 // test that the expressions passed to the function match the functions
                 // parameter types
@@ -741,6 +1409,20 @@ impl<'a> TypeResolver<'a> {
                     resolved_params.push(ty);
                 }
 
+                // A method is found in the structure of the value which it is called on and
+                // is then called like any other function
+                let (call, routine_path) = match call {
+                    RoutineCall::Method => (
+                        RoutineCall::Function,
+                        Box::new(
+                            self.method_path(routine_path, &resolved_params)
+                                .map_err(|e| CompilerError::new(ctx.span(), e))?,
+                        ),
+                    ),
+                    call => (*call, routine_path.clone()),
+                };
+                let routine_path = &routine_path;
+
                 // Check that the function being called exists
                 let (symbol, routine_canon_path) = self
                     .symbols
@@ -754,12 +1436,34 @@ impl<'a> TypeResolver<'a> {
                 let call = if symbol.is_extern {
                     RoutineCall::Extern
                 } else {
-                    *call
+                    call
                 };
 
+                // A const fn has no side effects (e.g. IO), so it can only call routines
+                // which also have none
+                if self.in_const_fn && !self.const_fns.contains(&routine_canon_path) {
+                    return Err(CompilerError::new(
+                        ctx.span(),
+                        SemanticError::ConstFnCallsNonConst(routine_canon_path),
+                    ));
+                }
+
                 let (expected_param_tys, has_varargs, ret_ty) = self
                     .extract_routine_type_info(symbol, &call, &routine_canon_path)
-                    .map_err(|e| CompilerError::new(ctx.span(), e))?;
+                    .map_err(|e| match e {
+                        SemanticError::InitOfNonCoroutine(..) => CompilerError::new(ctx.span(), e)
+                            .with_hint("init is only used to create a coroutine instance, functions are called directly"),
+                        e => CompilerError::new(ctx.span(), e),
+                    })?;
+
+                let resolved_params: Vec<_> = resolved_params
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, param)| match expected_param_tys.get(idx) {
+                        Some(ty) => coerce(ty, param),
+                        None => param,
+                    })
+                    .collect();
 
                 // Check that parameters are correct and if so, return the node annotated with
                 // semantic information
@@ -767,7 +1471,7 @@ impl<'a> TypeResolver<'a> {
                     Err(CompilerError::new(
                         ctx.span(),
                         SemanticError::RoutineCallWrongNumParams(
-                            routine_path.clone(),
+                            (**routine_path).clone(),
                             expected_param_tys.len(),
                             resolved_params.len(),
                         ),
@@ -776,7 +1480,7 @@ impl<'a> TypeResolver<'a> {
                     Err(CompilerError::new(
                         ctx.span(),
                         SemanticError::FunctionParamsNotEnough(
-                            routine_path.clone(),
+                            (**routine_path).clone(),
                             expected_param_tys.len(),
                             resolved_params.len(),
                         ),
@@ -787,14 +1491,30 @@ impl<'a> TypeResolver<'a> {
                         &resolved_params,
                         expected_param_tys,
                         has_varargs,
+                        &ret_ty,
                     ) {
+                        Err(SemanticError::RoutineParamTypeMismatch(path, mut sig, mismatches)) => {
+                            // Add the parameter names to the signature so that the user can
+                            // tell which parameters are wrong.
+                            if let Some(names) =
+                                self.symbols.lookup_param_names(&routine_canon_path)
+                            {
+                                for ((name, _), pname) in sig.params.iter_mut().zip(names) {
+                                    *name = Some(pname)
+                                }
+                            }
+                            Err(CompilerError::new(
+                                ctx.span(),
+                                SemanticError::RoutineParamTypeMismatch(path, sig, mismatches),
+                            ))
+                        }
                         Err(msg) => Err(CompilerError::new(ctx.span(), msg)),
                         Ok(()) => {
                             let ctx = ctx.with_type(ret_ty.clone());
                             Ok(Expression::RoutineCall(
                                 ctx,
                                 call,
-                                routine_canon_path,
+                                Box::new(routine_canon_path),
                                 resolved_params,
                             ))
                         }
@@ -811,7 +1531,17 @@ impl<'a> TypeResolver<'a> {
                     resolved_body.push(exp);
                 }
 
+                // A block which has no final expression and which contains a statement that
+                // diverges (e.g. a `return` or a `break`) will never produce a value
+                let diverges = resolved_body.iter().any(|stm| match stm {
+                    Statement::Return(_) | Statement::Break(_) | Statement::Continue(_) => true,
+                    Statement::Expression(e) => e.get_type() == Type::Never,
+                    Statement::Discard(d) => d.get_value().get_type() == Type::Never,
+                    _ => false,
+                });
+
                 let (final_exp, block_ty) = match final_exp {
+                    None if diverges => (None, Type::Never),
                     None => (None, Type::Unit),
                     Some(fe) => {
                         let fe = self.analyze_expression(fe)?;
@@ -857,7 +1587,8 @@ impl<'a> TypeResolver<'a> {
                         ctx.span(),
                         SemanticError::StructExprMemberNotFound(canonical_path.clone(), *pn),
                     ))?;
-                    let param = self.analyze_expression(pv)?;
+                    self.check_field_visible(&canonical_path, *pn, ctx.span())?;
+                    let param = coerce(member_ty, self.analyze_expression(pv)?);
                     if !member_ty.can_be_assigned(param.get_type()) {
                         return Err(CompilerError::new(
                             ctx.span(),
@@ -872,10 +1603,10 @@ impl<'a> TypeResolver<'a> {
                     resolved_params.push((*pn, param));
                 }
 
-                let ctx = ctx.with_type(Type::Custom(struct_name.clone()));
+                let ctx = ctx.with_type(Type::Custom((**struct_name).clone()));
                 Ok(Expression::StructExpression(
                     ctx,
-                    canonical_path,
+                    Box::new(canonical_path),
                     resolved_params,
                 ))
             }
@@ -886,7 +1617,7 @@ impl<'a> TypeResolver<'a> {
                 // casting within the type definitions.
                 if exp2.context().ty().can_cast_to(target) {
                     // 4. If it can, then update the type information for this node in the tree
-                    let ctx2 = ctx.with_type(target.clone());
+                    let ctx2 = ctx.with_type((**target).clone());
                     Ok(Expression::TypeCast(ctx2, Box::new(exp2), target.clone()))
                 } else {
                 // 3. If not, then return an error
@@ -898,9 +1629,22 @@ impl<'a> TypeResolver<'a> {
             },
         }
         });
+        let result = result.and_then(|exp| {
+            self.check_const_fn_type(exp.get_type(), exp.span())?;
+            Ok(exp)
+        });
         result.view(|e| self.record2(event, e, refs))
     }
 
+    /// Returns an error if a `const fn` is being analyzed and `ty` holds a raw pointer.
+    fn check_const_fn_type(&self, ty: &Type, span: Span) -> SemanticResult<()> {
+        if self.in_const_fn && has_raw_pointer(ty) {
+            err!(span, SemanticError::ConstFnUsesRawPointer(ty.clone()))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Check that the operand has the correct type for the given unary
     /// operator and return the type that the unary operation will resolve
     /// to.
@@ -980,6 +1724,189 @@ impl<'a> TypeResolver<'a> {
                     )),
                 }
             }
+            Length => {
+                if operand.get_type().is_string()
+                    || matches!(
+                        operand.get_type(),
+                        Type::Array(..) | Type::Slice(_) | Type::Range(_)
+                    )
+                {
+                    Ok((Type::U64, Addressability::Value, operand))
+                } else {
+                    Err(CompilerError::new(
+                        operand.span(),
+                        SemanticError::ExpectedSequence(op, operand.get_type().clone()),
+                    ))
+                }
+            }
+            OptionSome => Ok((
+                Type::Option(Box::new(operand.get_type().clone())),
+                Addressability::Value,
+                operand,
+            )),
+            OptionIsSome | OptionUnwrap => match operand.get_type() {
+                Type::Option(inner) => {
+                    let ty = if op == OptionIsSome {
+                        Type::Bool
+                    } else {
+                        *inner.clone()
+                    };
+                    Ok((ty, Addressability::Value, operand))
+                }
+                ty => Err(CompilerError::new(
+                    operand.span(),
+                    SemanticError::ExpectedOption(op, ty.clone()),
+                )),
+            },
+            ResultOk => Ok((
+                Type::Result(
+                    Box::new(operand.get_type().clone()),
+                    Box::new(Type::Unknown),
+                ),
+                Addressability::Value,
+                operand,
+            )),
+            ResultErr => Ok((
+                Type::Result(
+                    Box::new(Type::Unknown),
+                    Box::new(operand.get_type().clone()),
+                ),
+                Addressability::Value,
+                operand,
+            )),
+            ResultIsOk | ResultUnwrapOk | ResultUnwrapErr => match operand.get_type() {
+                Type::Result(ok, err) => {
+                    let ty = match op {
+                        ResultIsOk => Type::Bool,
+                        ResultUnwrapOk => *ok.clone(),
+                        _ => *err.clone(),
+                    };
+                    Ok((ty, Addressability::Value, operand))
+                }
+                ty => Err(CompilerError::new(
+                    operand.span(),
+                    SemanticError::ExpectedResult(op, ty.clone()),
+                )),
+            },
+            Try => {
+                // `?` may return from the routine, which a deferred expression cannot do
+                if self.in_defer {
+                    return Err(CompilerError::new(
+                        operand.span(),
+                        SemanticError::TryInDefer,
+                    ));
+                }
+                let ret_ty = self.current_return_type(operand.span())?;
+                self.try_op(operand, ret_ty)
+            }
+            ToLeBytes | ToBeBytes => {
+                // The width of usize and isize depends upon the target, so the number of
+                // bytes would too
+                let width = operand.get_type().bit_width();
+                if operand.get_type().is_integral() && width > 0 {
+                    let ty = Type::Array(Box::new(Type::U8), width as usize / 8);
+                    Ok((ty, Addressability::Value, operand))
+                } else {
+                    Err(CompilerError::new(
+                        operand.span(),
+                        SemanticError::ExpectedFixedWidthInteger(op, operand.get_type().clone()),
+                    ))
+                }
+            }
+            FromLeBytes | FromBeBytes => {
+                let ty = match operand.get_type() {
+                    Type::Array(el_ty, len) if **el_ty == Type::U8 => match *len {
+                        1 => Some(Type::U8),
+                        2 => Some(Type::U16),
+                        4 => Some(Type::U32),
+                        8 => Some(Type::U64),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match ty {
+                    Some(ty) => Ok((ty, Addressability::Value, operand)),
+                    None => Err(CompilerError::new(
+                        operand.span(),
+                        SemanticError::ExpectedByteArray(op, operand.get_type().clone()),
+                    )),
+                }
+            }
+        }
+    }
+
+    /// Returns an error if `field` of the structure with the canonical path `struct_path`
+    /// is not `pub` and the code being resolved is outside of the module which defines
+    /// the structure and its submodules.
+    fn check_field_visible(
+        &self,
+        struct_path: &Path,
+        field: StringId,
+        span: Span,
+    ) -> SemanticResult<()> {
+        let is_private = self
+            .private_fields
+            .get(struct_path)
+            .map_or(false, |fields| fields.contains(&field));
+        if !is_private {
+            return Ok(());
+        }
+
+        let current = self
+            .symbols
+            .to_path()
+            .ok_or_else(|| CompilerError::new(span, SemanticError::PathNotValid))?;
+        if current.starts_with(&struct_path.parent()) {
+            Ok(())
+        } else {
+            Err(CompilerError::new(
+                span,
+                SemanticError::PrivateField(struct_path.clone(), field),
+            ))
+        }
+    }
+
+    /// Returns the return type of the routine which is being resolved.
+    fn current_return_type(&self, span: Span) -> SemanticResult<Type> {
+        let current_func = self
+            .symbols
+            .get_current_fn()
+            .ok_or_else(|| CompilerError::new(span, SemanticError::ReturnInvalidLocation))?;
+        let (_, ret_ty) = self
+            .symbols
+            .lookup_func_or_cor(current_func)
+            .map_err(|e| CompilerError::new(span, e))?;
+        Ok(ret_ty.clone())
+    }
+
+    /// Checks that `operand`, which `?` is applied to, can be returned from a routine
+    /// which returns `ret_ty`: an option can be propagated out of a routine which returns
+    /// an option and a result can be propagated out of a routine which returns a result
+    /// with the same error type.  `?` evaluates to the value which the operand holds.
+    fn try_op(
+        &self,
+        operand: SemanticNode,
+        ret_ty: Type,
+    ) -> SemanticResult<(Type, Addressability, SemanticNode)> {
+        let operand = match &ret_ty {
+            Type::Result(_, ret_err) => coerce_partial(
+                &Type::Result(Box::new(Type::Unknown), ret_err.clone()),
+                operand,
+            ),
+            _ => operand,
+        };
+
+        match (operand.get_type(), &ret_ty) {
+            (Type::Option(inner), Type::Option(_)) => {
+                Ok((*inner.clone(), Addressability::Value, operand))
+            }
+            (Type::Result(ok, err), Type::Result(_, ret_err)) if err == ret_err => {
+                Ok((*ok.clone(), Addressability::Value, operand))
+            }
+            (ty, _) => Err(CompilerError::new(
+                operand.span(),
+                SemanticError::TryMismatch(ty.clone(), ret_ty),
+            )),
         }
     }
 
@@ -1019,12 +1946,46 @@ impl<'a> TypeResolver<'a> {
                     ))
                 }
             }
+            // Adding two strings concatenates them into a new String
+            Add if l.get_type().is_string() && r.get_type().is_string() => Ok((Type::String, l, r)),
             Add | Sub | Mul | Div => {
                 if l.get_type().is_number()
                     && r.get_type().is_number()
                     && l.get_type() == r.get_type()
                 {
                     Ok((l.get_type().clone(), l, r))
+                } else {
+                    let expected = if l.get_type().is_integral() {
+                        l.get_type().clone()
+                    } else if op == Add && l.get_type().is_string() {
+                        Type::String
+                    } else {
+                        Type::I64
+                    };
+                    Err(CompilerError::new(
+                        l.span(),
+                        SemanticError::OpExpected(
+                            op,
+                            expected,
+                            l.get_type().clone(),
+                            r.get_type().clone(),
+                        ),
+                    ))
+                }
+            }
+            WrappingAdd | WrappingSub | WrappingMul | SaturatingAdd | SaturatingSub | Range => {
+                // Explicit overflow behavior is only defined for integers, and the bounds of
+                // a range must be integers
+                if l.get_type().is_integral()
+                    && r.get_type().is_integral()
+                    && l.get_type() == r.get_type()
+                {
+                    let ty = if op == Range {
+                        Type::Range(Box::new(l.get_type().clone()))
+                    } else {
+                        l.get_type().clone()
+                    };
+                    Ok((ty, l, r))
                 } else {
                     let expected = if l.get_type().is_integral() {
                         l.get_type().clone()
@@ -1061,6 +2022,7 @@ impl<'a> TypeResolver<'a> {
                 if l.get_type().can_be_compared(r.get_type()) {
                     Ok((Type::Bool, l, r))
                 } else {
+                    Self::check_chained_comparison(op, &l, &r)?;
                     Err(CompilerError::new(
                         l.span(),
                         SemanticError::OpExpected(
@@ -1075,6 +2037,126 @@ impl<'a> TypeResolver<'a> {
         }
     }
 
+    /// Comparisons cannot be chained: `a < b < c` is parsed as `a < (b < c)`, which then
+    /// compares `a` with a bool. If either operand of the comparison `op` is itself a
+    /// comparison, this returns an error which explains how to combine the comparisons.
+    fn check_chained_comparison(
+        op: BinaryOperator,
+        l: &SemanticNode,
+        r: &SemanticNode,
+    ) -> SemanticResult<()> {
+        use BinaryOperator::*;
+
+        let is_comparison = |op| matches!(op, Eq | NEq | Ls | LsEq | Gr | GrEq);
+        let (inner, other, hint) = match (l, r) {
+            (_, Expression::BinaryOp(_, inner, ..)) if is_comparison(*inner) => {
+                (*inner, l.get_type(), format!("a {} b && b {} c", op, inner))
+            }
+            (Expression::BinaryOp(_, inner, ..), _) if is_comparison(*inner) => {
+                (*inner, r.get_type(), format!("a {} b && b {} c", inner, op))
+            }
+            _ => return Ok(()),
+        };
+
+        Err(CompilerError::new(
+            l.span(),
+            SemanticError::ChainedComparison(inner, op, other.clone()),
+        )
+        .with_hint(&format!("use && to check both comparisons: {}", hint)))
+    }
+
+    /// Returns the name of the method which performs `step` of the iterator protocol on a
+    /// structure.
+    fn iterator_method(&self, step: IteratorStep) -> StringId {
+        let name = match step {
+            IteratorStep::IsEmpty => "is_empty",
+            IteratorStep::First => "first",
+            IteratorStep::Rest => "rest",
+        };
+        self.st.insert(name.into())
+    }
+
+    /// Returns the builtin operation which performs `step` of the iterator protocol on
+    /// `seq`, a range, slice, or array.
+    fn builtin_iterator_step(
+        ctx: &SemanticContext,
+        step: IteratorStep,
+        seq: &SemanticNode,
+    ) -> SemanticNode {
+        let len = || {
+            Box::new(Expression::UnaryOp(
+                ctx.clone(),
+                UnaryOperator::Length,
+                Box::new(seq.clone()),
+            ))
+        };
+        let index = |index| Expression::ArrayAt {
+            context: ctx.clone(),
+            array: Box::new(seq.clone()),
+            index: Box::new(index),
+        };
+        match step {
+            IteratorStep::IsEmpty => Expression::BinaryOp(
+                ctx.clone(),
+                BinaryOperator::Eq,
+                len(),
+                Box::new(Expression::U64(ctx.clone(), 0)),
+            ),
+            IteratorStep::First => index(Expression::U64(ctx.clone(), 0)),
+            IteratorStep::Rest => index(Expression::BinaryOp(
+                ctx.clone(),
+                BinaryOperator::Range,
+                Box::new(Expression::U64(ctx.clone(), 1)),
+                len(),
+            )),
+        }
+    }
+
+    /// Returns the path of the routine which is called by the method named `method`, which
+    /// is called on the first of `params`.  If the receiver is a structure which has the
+    /// method, then this is the canonical path of that method.  Otherwise, the call is to
+    /// the function named `method` with the receiver as its first parameter, so `x.f(y)`
+    /// is the same as `f(x, y)`.
+    fn method_path(&self, method: &Path, params: &[SemanticNode]) -> Result<Path, SemanticError> {
+        let name = method.item().expect("Method calls must name the method");
+        let receiver_ty = params
+            .first()
+            .expect("Method calls must have a receiver")
+            .get_type();
+
+        let method_err = match receiver_ty {
+            Type::Custom(struct_path) => {
+                let mut method_path = struct_path.clone();
+                method_path.push(Element::Id(name));
+                match self.symbols.lookup_symbol_by_path(&method_path) {
+                    Ok(_) => return Ok(method_path),
+                    Err(_) => Some(SemanticError::MethodNotFound(struct_path.clone(), name)),
+                }
+            }
+            _ => None,
+        };
+
+        // Methods take precedence over functions, so a function is only called if the
+        // receiver does not have a method with the same name.  A receiver which is not a
+        // structure can only be given to a function, so if there is none, then the error
+        // is that no function was found at the path that was looked up.
+        match (self.symbols.lookup_symbol_by_path(method), method_err) {
+            (
+                Ok((
+                    Symbol {
+                        ty: Type::FunctionDef(..),
+                        ..
+                    },
+                    _,
+                )),
+                _,
+            ) => Ok(method.clone()),
+            (_, Some(err)) => Err(err),
+            (Ok(_), None) => Err(SemanticError::NotRoutine(name)),
+            (Err(err), None) => Err(err),
+        }
+    }
+
     fn get_current_path(&self) -> Result<Path, SemanticError> {
         self.symbols.to_path().ok_or(SemanticError::PathNotValid)
     }
@@ -1098,6 +2180,12 @@ impl<'a> TypeResolver<'a> {
                 ty: Type::CoroutineDef(pty, rty),
                 ..
             } if *call == RoutineCall::CoroutineInit => (pty, false, Type::Coroutine(rty.clone())),
+            _ if *call == RoutineCall::CoroutineInit => {
+                return Err(SemanticError::InitOfNonCoroutine(
+                    routine_path.clone(),
+                    symbol.ty.clone(),
+                ));
+            }
             _ => {
                 return Err(SemanticError::RoutineCallInvalidTarget(
                     *call,
@@ -1115,6 +2203,7 @@ impl<'a> TypeResolver<'a> {
         given: &'b [SemanticNode],
         expected_types: &'b [Type],
         has_varargs: HasVarArgs,
+        ret_ty: &Type,
     ) -> Result<(), SemanticError> {
         let mut mismatches = vec![];
         let mut idx = 0;
@@ -1135,8 +2224,14 @@ impl<'a> TypeResolver<'a> {
                 .iter()
                 .map(|(idx, got, expected)| (*idx, (*expected).clone(), (*got).clone()))
                 .collect();
+            let sig = RoutineSignature {
+                params: expected_types.iter().map(|ty| (None, ty.clone())).collect(),
+                has_varargs,
+                ret_ty: ret_ty.clone(),
+            };
             Err(SemanticError::RoutineParamTypeMismatch(
                 routine_path.clone(),
+                sig,
                 errors,
             ))
         }
@@ -1223,6 +2318,25 @@ impl<'a> TypeResolver<'a> {
             ));
     }
 
+    /// Returns the name and type of every field of the structure which the given
+    /// [`Type`] refers to.
+    fn struct_fields(&self, ty: &Type, span: Span) -> SemanticResult<Vec<(StringId, Type)>> {
+        match ty {
+            Type::Custom(type_name) => {
+                let (item, _) = self
+                    .symbols
+                    .lookup_symbol_by_path(type_name)
+                    .map_err(|e| CompilerError::new(span, e))?;
+
+                match &item.ty {
+                    Type::StructDef(fields) => Ok(fields.clone()),
+                    _ => err!(span, SemanticError::FieldsOfNonStruct(ty.clone())),
+                }
+            }
+            _ => err!(span, SemanticError::FieldsOfNonStruct(ty.clone())),
+        }
+    }
+
     /// Check that the given [`Type`] is valid. If it is a custom type, such as
     /// a structure, this will make sure that the path points to a valid item.
     fn valid_type(&self, ty: &Type, span: Span) -> SemanticResult<()> {
@@ -0,0 +1,72 @@
+//! The typed stage of the semantic AST.  Every phase after type resolution (the MIR
+//! transform and code generation) relies upon each node having a type, so they take a
+//! [`TypedModule`], which can only be made from a module that has been checked to have
+//! no unknown types, rather than a [`Module<SemanticContext>`].
+
+use std::ops::Deref;
+
+use crate::compiler::{
+    ast::{Module, Node, Type},
+    CompilerError,
+};
+
+use super::{semanticnode::SemanticContext, SemanticError, SemanticResult};
+
+/// A semantic module in which every node has been given a type.
+///
+/// The type resolver returns a `TypedModule`.  A phase which changes the module must
+/// take it apart with [`TypedModule::into_inner`] and check it again with
+/// [`TypedModule::check`], so a change can never hand code generation a node without a
+/// type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypedModule(Module<SemanticContext>);
+
+impl TypedModule {
+    /// Returns `module` as a `TypedModule` if every node in it has a type, and otherwise
+    /// an error at the first node which does not.
+    ///
+    /// A `none`, `ok`, or `err` which was never used where its type is expected is a
+    /// mistake in the user's program, rather than in the compiler, and is reported as
+    /// such.
+    pub fn check(module: Module<SemanticContext>) -> SemanticResult<TypedModule> {
+        match module
+            .iter_preorder()
+            .find(|n| *n.context().ty() == Type::Unknown || is_partly_unknown(n.context().ty()))
+        {
+            Some(node) if is_partly_unknown(node.context().ty()) => Err(CompilerError::new(
+                node.span(),
+                SemanticError::PartialTypeNotInferred,
+            )),
+            Some(node) => Err(CompilerError::new(
+                node.span(),
+                SemanticError::UnresolvedType(node.node_type()),
+            )),
+            None => Ok(TypedModule(module)),
+        }
+    }
+
+    /// Returns the module, so that it can be changed.  It must be checked again before
+    /// it can be given to code generation.
+    pub fn into_inner(self) -> Module<SemanticContext> {
+        self.0
+    }
+}
+
+impl Deref for TypedModule {
+    type Target = Module<SemanticContext>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Returns `true` if `ty` is an option or result, which holds an unknown type, because it
+/// is the type of a `none`, `ok`, or `err` which has not been given its full type.
+pub(super) fn is_partly_unknown(ty: &Type) -> bool {
+    let is_unknown = |ty: &Type| *ty == Type::Unknown || is_partly_unknown(ty);
+    match ty {
+        Type::Option(inner) => is_unknown(inner),
+        Type::Result(ok, err) => is_unknown(ok) || is_unknown(err),
+        _ => false,
+    }
+}
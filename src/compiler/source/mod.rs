@@ -2,6 +2,7 @@
 //! it's input data.  The [`Source`] module provides a common way to reference
 //! and retrieve the source code used by the compiler.
 
+use serde::{Deserialize, Serialize};
 use std::{
     fmt::{Display, Write},
     ops::AddAssign,
@@ -12,7 +13,7 @@ mod sourcechar;
 mod sourcemap;
 mod span;
 
-pub use source::{LineNumber, Source};
+pub use source::{Column, LineNumber, Source};
 pub use sourcechar::{SourceCharIter, SourceError};
 pub use sourcemap::{SourceMap, SourceMapEntry, SourceMapError};
 pub use span::{SourceIr, Span};
@@ -80,7 +81,7 @@ impl Display for SourceChar {
 /// code that has been input or generated for this Bramble project.  The [`SourceMap`]
 /// construct manages the the Offset indexing and will convert a given offset to
 /// the actual code source (e.g. the file name)
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize)]
 pub struct Offset(u32);
 
 impl Offset {
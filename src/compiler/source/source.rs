@@ -42,13 +42,19 @@ impl Index<usize> for Source {
 }
 
 /// A line number in a file
-#[derive(PartialEq, PartialOrd, Ord, Eq)]
+#[derive(Debug, PartialEq, PartialOrd, Ord, Eq)]
 pub struct LineNumber(u32);
 
 impl LineNumber {
     pub fn new(ln: u32) -> LineNumber {
         LineNumber(ln)
     }
+
+    /// Return the value of the line number as a u32.  This is for use with IO
+    /// layers (e.g. serializing to JSON)
+    pub fn as_u32(&self) -> u32 {
+        self.0
+    }
 }
 
 impl std::fmt::Display for LineNumber {
@@ -56,3 +62,27 @@ impl std::fmt::Display for LineNumber {
         f.write_fmt(format_args!("{}", self.0))
     }
 }
+
+/// A column in a line of a file, counted in characters from 1.  A tab advances the
+/// column to the next tab stop (see [`TAB_WIDTH`](super::sourcemap::TAB_WIDTH)), so that the
+/// column matches where an editor displays the character.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Ord, Eq)]
+pub struct Column(u32);
+
+impl Column {
+    pub fn new(col: u32) -> Column {
+        Column(col)
+    }
+
+    /// Return the value of the column as a u32.  This is for use with IO layers
+    /// (e.g. serializing to JSON)
+    pub fn as_u32(&self) -> u32 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}", self.0))
+    }
+}
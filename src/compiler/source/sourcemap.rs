@@ -1,12 +1,20 @@
 use std::{
     io::{Read, Seek, SeekFrom},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
-use super::{source::LineNumber, sourcechar::SourceCharIter, Offset, Source, SourceError, Span};
+use super::{
+    source::{Column, LineNumber},
+    sourcechar::SourceCharIter,
+    Offset, Source, SourceChar, SourceError, Span,
+};
 
 const MAX_SOURCE_SIZE: u32 = u32::MAX;
 
+/// The number of columns between tab stops.  When the column of a character is
+/// computed, a tab advances the column to the next tab stop.
+pub const TAB_WIDTH: u32 = 4;
+
 /// The SourceMap keeps a table of input source files and the range of teh Global
 /// Offset which maps to that source file.
 ///
@@ -34,6 +42,11 @@ pub struct SourceMap {
     /// The next added file will have this offset as its low offset and the
     /// offset_high will be incremented by the size of the added source file.
     offset_high: Offset,
+
+    /// Pairs of path prefixes: wherever the path of a source file is written out, a
+    /// path which starts with the first prefix is written with the second prefix in
+    /// its place.
+    remaps: Vec<(PathBuf, PathBuf)>,
 }
 
 impl SourceMap {
@@ -43,13 +56,47 @@ impl SourceMap {
         SourceMap {
             offset_high: Offset(0),
             map: Vec::new(),
+            remaps: Vec::new(),
+        }
+    }
+
+    /// Replaces the prefix `from` with `to` in the path of every source file, so
+    /// that paths which are specific to the machine compiling the program (e.g. the
+    /// directory that the project was checked out to) are not written out in any
+    /// diagnostics or output.  This applies to source files which have already been
+    /// added and to source files which are added later.  If several prefixes match a
+    /// path, then the one which was added last is used.
+    pub fn remap_path_prefix(&mut self, from: PathBuf, to: PathBuf) {
+        self.remaps.push((from, to));
+        for idx in 0..self.map.len() {
+            self.map[idx].remapped_path = self.remap(&self.map[idx].path);
         }
     }
 
+    fn remap(&self, path: &Path) -> PathBuf {
+        self.remaps
+            .iter()
+            .rev()
+            .find_map(|(from, to)| path.strip_prefix(from).ok().map(|rest| to.join(rest)))
+            .unwrap_or_else(|| path.into())
+    }
+
     /// Add a file as unit of source code to the [`SourceMap`].
+    ///
+    /// If the file has already been added, possibly through a different path, then
+    /// this will return [`SourceMapError::DuplicateFile`].
     pub fn add_file(&mut self, path: PathBuf) -> Result<(), SourceMapError> {
         let file = std::fs::File::open(&path)?;
 
+        let canonical = path.canonicalize()?;
+        for entry in &self.map {
+            if let SourceType::File(existing) = &entry.source {
+                if existing.canonicalize()? == canonical {
+                    return Err(SourceMapError::DuplicateFile(entry.path.clone(), path));
+                }
+            }
+        }
+
         let file_len = file.metadata()?.len();
         if file_len >= MAX_SOURCE_SIZE as u64 {
             return Err(SourceMapError::FileTooBig);
@@ -66,7 +113,8 @@ impl SourceMap {
         let src = SourceType::File(path.clone());
 
         // Add source file to the offset map
-        let entry = SourceMapEntry::new(low, high, src, path);
+        let remapped = self.remap(&path);
+        let entry = SourceMapEntry::new(low, high, src, path, remapped);
         self.map.push(entry);
 
         Ok(())
@@ -89,7 +137,8 @@ impl SourceMap {
         let src = SourceType::Text(text.into());
 
         // Add source file to the offset map
-        let entry = SourceMapEntry::new(low, high, src, path);
+        let remapped = self.remap(&path);
+        let entry = SourceMapEntry::new(low, high, src, path, remapped);
         self.map.push(entry);
 
         Ok(())
@@ -121,27 +170,58 @@ impl SourceMap {
         }
     }
 
-    /// Returns the file(s) a span covers
+    /// Returns the file(s) a span covers, with their remapped paths
     pub fn files(&self, span: Span) -> Vec<&PathBuf> {
         self.map
             .iter()
             .filter(|e| span.intersects(e.span))
-            .map(|e| &e.path)
+            .map(|e| &e.remapped_path)
             .collect()
     }
 
-    /// Returns the source code lines that a [`Span`] covers
+    /// Returns the source code lines that a [`Span`] covers, grouped by the
+    /// remapped path of their file
     pub fn lines_in_span(&self, span: Span) -> Vec<(&PathBuf, Vec<LineNumber>)> {
         // Get the list of files that the span covers
         self.files_in_span(span)
             .iter()
             .map(|file| {
                 let lines = file.lines_in_span(span);
-                (&file.path, lines)
+                (&file.remapped_path, lines)
             })
             .collect()
     }
 
+    /// Returns the remapped path of the file, and the line and column, of the
+    /// character at the given global offset.  Returns [`None`] if the offset is not in
+    /// any source file.
+    pub fn location(&self, offset: Offset) -> Option<(&PathBuf, LineNumber, Column)> {
+        self.map
+            .iter()
+            .find(|e| e.span.low() <= offset && offset < e.span.high())
+            .map(|e| {
+                let (line, column) = e.location(offset);
+                (&e.remapped_path, line, column)
+            })
+    }
+
+    /// Returns the global offset of the character at the given line and column of
+    /// a source file, which is the inverse of [`SourceMap::location`].  The file is
+    /// found by either its path or its remapped path; if no path is given, then the
+    /// first file is used.  The end of the last line is the end of the file.  Returns
+    /// [`None`] if there is no such file or no such line and column in it.
+    pub fn offset_at(
+        &self,
+        path: Option<&Path>,
+        line: LineNumber,
+        column: Column,
+    ) -> Option<Offset> {
+        self.map
+            .iter()
+            .find(|e| path.map_or(true, |p| e.path == p || e.remapped_path == p))
+            .and_then(|e| e.offset_at(line, column))
+    }
+
     /// Returns the text from the source code that the give [`Span`] covers.
     pub fn text_in_span(&self, span: Span) -> Result<String, SourceError> {
         let files = self.files_in_span(span);
@@ -173,14 +253,24 @@ pub struct SourceMapEntry {
     span: Span,
     source: SourceType,
     path: PathBuf,
+
+    /// The path of the source file after [remapping](SourceMap::remap_path_prefix)
+    remapped_path: PathBuf,
 }
 
 impl SourceMapEntry {
-    fn new(low: Offset, high: Offset, source: SourceType, path: PathBuf) -> SourceMapEntry {
+    fn new(
+        low: Offset,
+        high: Offset,
+        source: SourceType,
+        path: PathBuf,
+        remapped_path: PathBuf,
+    ) -> SourceMapEntry {
         SourceMapEntry {
             span: Span::new(low, high),
             source,
             path,
+            remapped_path,
         }
     }
 
@@ -190,6 +280,12 @@ impl SourceMapEntry {
         &self.path
     }
 
+    /// Get the path of the source code with any [remapped](SourceMap::remap_path_prefix)
+    /// prefix replaced.  This is the path which should be written to any output.
+    pub fn remapped_path(&self) -> &PathBuf {
+        &self.remapped_path
+    }
+
     /// Returns the [`Span`] that is assigned to this source file
     pub fn span(&self) -> Span {
         self.span
@@ -249,6 +345,24 @@ impl SourceMapEntry {
         Ok(text)
     }
 
+    /// Returns the global offset of the first character of every line in this
+    /// source.  The first line always starts at the start of the source, so the line
+    /// which contains an offset can be found with a binary search.
+    pub fn line_starts(&self) -> Result<Vec<Offset>, SourceError> {
+        let text = self.read()?;
+        let mut starts = vec![self.span.low()];
+        let mut stream = text.iter().peekable();
+        while let Some(c) = stream.next() {
+            if is_line_break(c, stream.peek()) {
+                // Line breaks are a single byte
+                let mut start = c.offset();
+                start += 1;
+                starts.push(start);
+            }
+        }
+        Ok(starts)
+    }
+
     /// Returns the lines that a span covers in the given file.
     /// Will return an empty vector if `span` does not intersect the file
     /// at all.
@@ -262,7 +376,7 @@ impl SourceMapEntry {
                 // Then from the start of the span until the end of the span or the file
                 // Count each new line and add it to the vector
                 let text = self.read().unwrap();
-                let mut stream = text.iter();
+                let mut stream = text.iter().peekable();
 
                 let mut line = 1;
                 let mut prev_line = 0;
@@ -275,7 +389,7 @@ impl SourceMapEntry {
                         }
                     }
 
-                    if *c == '\n' {
+                    if is_line_break(c, stream.peek()) {
                         line += 1;
                     }
                 }
@@ -284,6 +398,70 @@ impl SourceMapEntry {
         }
         lines
     }
+
+    /// Returns the line and column of the character at the given global offset,
+    /// which must be within this file.
+    fn location(&self, offset: Offset) -> (LineNumber, Column) {
+        let text = self.read().unwrap();
+        let mut stream = text.iter().peekable();
+
+        let mut line = 1;
+        let mut column = 1;
+        while let Some(c) = stream.next() {
+            if c.offset() >= offset {
+                break;
+            }
+
+            if is_line_break(c, stream.peek()) {
+                line += 1;
+                column = 1;
+            } else if *c == '\t' {
+                column = ((column - 1) / TAB_WIDTH + 1) * TAB_WIDTH + 1;
+            } else if *c != '\r' {
+                // The `\r` of a `\r\n` is part of the line break and has no width
+                column += 1;
+            }
+        }
+
+        (LineNumber::new(line), Column::new(column))
+    }
+
+    /// Returns the global offset of the character at the given line and column of
+    /// this file.  Columns are counted in the same way as [`SourceMapEntry::location`].
+    fn offset_at(&self, line: LineNumber, column: Column) -> Option<Offset> {
+        let (target_line, target_column) = (line.as_u32(), column.as_u32());
+        let text = self.read().unwrap();
+        let mut stream = text.iter().peekable();
+
+        let mut line = 1;
+        let mut column = 1;
+        while let Some(c) = stream.next() {
+            if line > target_line {
+                // The column is past the end of the line
+                return None;
+            } else if line == target_line && column >= target_column {
+                return Some(c.offset());
+            }
+
+            if is_line_break(c, stream.peek()) {
+                line += 1;
+                column = 1;
+            } else if *c == '\t' {
+                column = ((column - 1) / TAB_WIDTH + 1) * TAB_WIDTH + 1;
+            } else if *c != '\r' {
+                column += 1;
+            }
+        }
+
+        (line == target_line && column >= target_column).then(|| self.span.high())
+    }
+}
+
+/// Returns true if `c` ends a line.  A line can end with `\n`, `\r\n`, or a `\r`
+/// which is not followed by a `\n`.  `next` is the character after `c`, so that
+/// `\r\n` is counted as a single line break.
+fn is_line_break(c: &SourceChar, next: Option<&&SourceChar>) -> bool {
+    *c == '\n' || (*c == '\r' && next.map_or(true, |n| **n != '\n'))
 }
 
 #[derive(Debug)]
@@ -296,6 +474,31 @@ enum SourceType {
 pub enum SourceMapError {
     FileTooBig,
     Io(std::io::Error),
+
+    /// The same file was reached through two different paths (e.g. through a symlink).
+    /// Stores the path that the file was first added with and then the second path.
+    DuplicateFile(PathBuf, PathBuf),
+
+    /// A directory contains a link back to itself or one of its parent directories.
+    /// Stores the path of the link and the directory which it links to.
+    DirectoryCycle(PathBuf, PathBuf),
+}
+
+impl std::fmt::Display for SourceMapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceMapError::FileTooBig => f.write_str("Source file is too big"),
+            SourceMapError::Io(ioe) => f.write_fmt(format_args!("IO Error: {}", ioe)),
+            SourceMapError::DuplicateFile(first, second) => f.write_fmt(format_args!(
+                "{:?} and {:?} are the same file, a source file can only be included once",
+                first, second
+            )),
+            SourceMapError::DirectoryCycle(link, target) => f.write_fmt(format_args!(
+                "{:?} links to {:?}, which contains it, so the project would include itself",
+                link, target
+            )),
+        }
+    }
 }
 
 impl From<std::io::Error> for SourceMapError {
@@ -303,3 +506,155 @@ impl From<std::io::Error> for SourceMapError {
         Self::Io(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::compiler::source::{Column, LineNumber, Offset};
+
+    use super::SourceMap;
+
+    #[test]
+    fn lines_with_mixed_line_endings() {
+        // Lines end with `\n`, `\r\n`, and a lone `\r`
+        let text = "a\nb\r\nc\rd";
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+
+        for &(offset, line, column) in &[(0, 1, 1), (2, 2, 1), (3, 2, 2), (5, 3, 1), (7, 4, 1)] {
+            let (file, actual_line, actual_column) = sm.location(Offset::new(offset)).unwrap();
+            assert_eq!(file.to_str(), Some("/test"));
+            assert_eq!(actual_line, LineNumber::new(line), "offset {}", offset);
+            assert_eq!(actual_column, Column::new(column), "offset {}", offset);
+        }
+
+        let span = sm.span().unwrap();
+        let lines = sm.lines_in_span(span);
+        assert_eq!(lines[0].1, (1..=4).map(LineNumber::new).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn columns_with_tabs() {
+        let text = "\tx\n  \ty\n\t\tz\nab\tc";
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+
+        let column = |offset| sm.location(Offset::new(offset)).unwrap().2.as_u32();
+        // `\tx`: a tab goes to the first tab stop
+        assert_eq!(column(1), 5);
+        // `  \ty`: a tab after some spaces goes to the same tab stop
+        assert_eq!(column(6), 5);
+        // `\t\tz`: each tab goes to the next tab stop
+        assert_eq!(column(10), 9);
+        // `ab\tc`
+        assert_eq!(column(15), 5);
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        // `é` is two bytes but one column
+        let text = "\"é\" x";
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+
+        let (_, line, column) = sm.location(Offset::new(5)).unwrap();
+        assert_eq!(line, LineNumber::new(1));
+        assert_eq!(column, Column::new(5));
+    }
+
+    #[test]
+    fn location_in_second_file() {
+        let mut sm = SourceMap::new();
+        sm.add_string("a\r\n", "/first".into()).unwrap();
+        sm.add_string("b\r\n\tc", "/second".into()).unwrap();
+
+        let (file, line, column) = sm.location(Offset::new(7)).unwrap();
+        assert_eq!(file.to_str(), Some("/second"));
+        assert_eq!(line, LineNumber::new(2));
+        assert_eq!(column, Column::new(5));
+
+        assert!(sm.location(Offset::new(8)).is_none());
+    }
+
+    #[test]
+    fn offset_at() {
+        let mut sm = SourceMap::new();
+        sm.add_string("a\r\n", "/first".into()).unwrap();
+        sm.add_string("b\r\n\tc", "/second".into()).unwrap();
+
+        let offset = |path: Option<&str>, line, column| {
+            sm.offset_at(
+                path.map(std::path::Path::new),
+                LineNumber::new(line),
+                Column::new(column),
+            )
+        };
+        assert_eq!(offset(None, 1, 1), Some(Offset::new(0)));
+        assert_eq!(offset(Some("/second"), 1, 1), Some(Offset::new(3)));
+        // `\tc`: the tab is at column 1 and `c` is at the next tab stop
+        assert_eq!(offset(Some("/second"), 2, 5), Some(Offset::new(7)));
+        // The end of the last line is the end of the file
+        assert_eq!(offset(Some("/second"), 2, 6), Some(Offset::new(8)));
+
+        assert_eq!(offset(Some("/first"), 1, 3), None);
+        assert_eq!(offset(Some("/second"), 3, 1), None);
+        assert_eq!(offset(Some("/third"), 1, 1), None);
+
+        // Every location maps back to its offset, except for the `\n` of a `\r\n`,
+        // which has the same location as the `\r`
+        for &o in &[0, 1, 3, 4, 6, 7] {
+            let (file, line, column) = sm.location(Offset::new(o)).unwrap();
+            let file = file.clone();
+            assert_eq!(
+                sm.offset_at(Some(file.as_path()), line, column),
+                Some(Offset::new(o))
+            );
+        }
+    }
+
+    #[test]
+    fn line_starts() {
+        let mut sm = SourceMap::new();
+        sm.add_string("x", "/first".into()).unwrap();
+        sm.add_string("a\nbc\r\n\rd", "/second".into()).unwrap();
+
+        let starts = sm.get(1).unwrap().line_starts().unwrap();
+        assert_eq!(
+            starts,
+            [1, 3, 7, 8]
+                .iter()
+                .map(|o| Offset::new(*o))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn remap_path_prefix() {
+        let mut sm = SourceMap::new();
+        sm.add_string("a", "/home/me/proj/src/main.br".into())
+            .unwrap();
+        sm.remap_path_prefix("/home/me/proj".into(), "/proj".into());
+        sm.add_string("b", "/home/me/proj/src/lib.br".into())
+            .unwrap();
+        sm.add_string("c", "/home/me/projects/other.br".into())
+            .unwrap();
+        sm.remap_path_prefix("/home/me/proj/src".into(), "src".into());
+
+        let file = |offset| sm.location(Offset::new(offset)).unwrap().0.clone();
+        assert_eq!(file(0), PathBuf::from("src/main.br"));
+        assert_eq!(file(1), PathBuf::from("src/lib.br"));
+        // Prefixes are matched by whole components
+        assert_eq!(file(2), PathBuf::from("/home/me/projects/other.br"));
+
+        // The original path is still used to find the file
+        assert_eq!(
+            sm.get(0).unwrap().path(),
+            &PathBuf::from("/home/me/proj/src/main.br")
+        );
+        assert_eq!(
+            sm.get(0).unwrap().remapped_path(),
+            &PathBuf::from("src/main.br")
+        );
+    }
+}
@@ -2,6 +2,7 @@
 //! IR elements generated by the compiler to the code that they represent.
 
 use super::Offset;
+use serde::{Deserialize, Serialize};
 
 /// Trait that any IR type which derives from or represents source code
 /// must implement.  This trait contains functions for getting the [`Span`]
@@ -11,7 +12,7 @@ pub trait SourceIr {
     fn span(&self) -> Span;
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Span {
     /// The span starts at this position in the global source map
     low: Offset,
@@ -84,6 +85,16 @@ impl std::fmt::Display for Span {
     }
 }
 
+/// The span of a sequence of nodes covers the first node through the last node.
+impl<V: SourceIr> SourceIr for Vec<V> {
+    fn span(&self) -> Span {
+        match (self.first(), self.last()) {
+            (Some(first), Some(last)) => Span::cover(first.span(), last.span()),
+            _ => Span::zero(),
+        }
+    }
+}
+
 impl<V: SourceIr, E: SourceIr> SourceIr for Result<&V, &E> {
     fn span(&self) -> Span {
         match self {
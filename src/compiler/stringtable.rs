@@ -1,9 +1,25 @@
-use std::{cell::RefCell, collections::HashMap, fmt::Display};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, RwLock},
+};
 
 use crate::compiler::CompilerDisplayError;
 
 use super::SourceMap;
 
+/// The number of bits of a [`StringId`] which select the shard that the string is
+/// stored in.  Each shard has its own lock, so threads which insert different strings
+/// will usually not contend for the same lock.
+const SHARD_BITS: u32 = 4;
+
+/// The number of shards the string table is split into.
+const NUM_SHARDS: usize = 1 << SHARD_BITS;
+
+/// The number of bits of a [`StringId`] which index the string within its shard.
+const INDEX_BITS: u32 = 32 - SHARD_BITS;
+
 #[derive(Debug)]
 pub enum StringTableError {
     NotFound,
@@ -30,31 +46,55 @@ value.
 
 The user can provide a string ID and get the assoicated string value
 in return.
+
+The table can be shared between threads: the strings are split across a set
+of independently locked shards, so concurrent insertions only contend when
+they land in the same shard.  A [`StringId`] names the shard of its string and the
+position of the string within that shard, so every operation locks exactly one
+shard.
  */
 #[derive(Debug)]
 pub struct StringTable {
-    /// The next unique [`StringId`] which has not been assigned to a String
-    next_id: RefCell<StringId>,
+    /// Which shard a string is stored in is determined by its hash.
+    shards: Vec<RwLock<Shard>>,
+}
 
+/// The strings which hash to one shard of a [`StringTable`].
+#[derive(Debug, Default)]
+struct Shard {
     /// Table mapping raw strings to their [`StringId`]s. Used for converting
     /// strings read from source code into their [`StringId`].
-    table: RefCell<HashMap<String, StringId>>,
+    ids: HashMap<Arc<str>, StringId>,
+
+    /// The strings of the shard, in the order that they were added, which is the
+    /// order of their IDs.
+    strings: Vec<Arc<str>>,
 }
 
 impl Default for StringTable {
     fn default() -> Self {
-        Self {
-            next_id: Default::default(),
-            table: Default::default(),
-        }
+        Self::with_capacity(0)
     }
 }
 
 impl StringTable {
     pub fn new() -> StringTable {
+        Self::default()
+    }
+
+    /// Creates a string table which can hold at least `capacity` distinct strings
+    /// without reallocating.
+    pub fn with_capacity(capacity: usize) -> StringTable {
+        let per_shard = (capacity + NUM_SHARDS - 1) / NUM_SHARDS;
         StringTable {
-            next_id: RefCell::new(StringId::new()),
-            table: RefCell::new(HashMap::new()),
+            shards: (0..NUM_SHARDS)
+                .map(|_| {
+                    RwLock::new(Shard {
+                        ids: HashMap::with_capacity(per_shard),
+                        strings: Vec::with_capacity(per_shard),
+                    })
+                })
+                .collect(),
         }
     }
 
@@ -63,47 +103,99 @@ impl StringTable {
     /// simply return the already assigned ID for that string. Otherwise, it
     /// will add the string to the table and assign it a unique ID.
     pub fn insert(&self, s: String) -> StringId {
-        let mut table = self.table.borrow_mut();
-        *table
-            .entry(s)
-            .or_insert_with(|| self.next_id.borrow_mut().get_and_inc())
+        let shard_idx = shard_of(&s);
+        let shard = &self.shards[shard_idx];
+        if let Some(id) = shard.read().unwrap().ids.get(s.as_str()) {
+            return *id;
+        }
+
+        let mut shard = shard.write().unwrap();
+
+        // Another thread may have added the string before the write lock was acquired
+        if let Some(id) = shard.ids.get(s.as_str()) {
+            return *id;
+        }
+
+        let index = shard.strings.len();
+        assert!(index < 1 << INDEX_BITS, "String table shard is full");
+        let id = StringId(((shard_idx as u32) << INDEX_BITS) | index as u32);
+
+        let s: Arc<str> = s.into();
+        shard.strings.push(s.clone());
+        shard.ids.insert(s, id);
+        id
     }
 
     /// Search the string table for the given string and, if found, return the
     /// associated [`StringId`]. If not found, then return [`None`](Option::None).
     pub fn find(&self, s: &str) -> Option<StringId> {
-        let table = self.table.borrow();
-        table.get(s).copied()
+        self.shards[shard_of(s)].read().unwrap().ids.get(s).copied()
     }
 
     /// Given an ID, if it is assigned to a string, then return the associated
     /// string, otherwise, return None.
     pub fn get(&self, id: StringId) -> Result<String, StringTableError> {
-        let table = self.table.borrow();
-        for s in table.iter() {
-            if *s.1 == id {
-                return Ok(s.0.clone());
-            }
-        }
+        self.get_shared(id)
+            .map(|s| s.to_string())
+            .ok_or(StringTableError::NotFound)
+    }
+
+    /// Returns the number of distinct strings in the table.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().strings.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over every string in the table, in the order of their IDs.  Each shard
+    /// is only locked while a string is read from it, and the strings are shared with
+    /// the table rather than copied.
+    ///
+    /// Strings which are added to a shard after the iterator has passed it are not
+    /// visited.  Inserting the strings into an empty table in the order that they are
+    /// visited gives every string the same ID that it has in this table.
+    pub fn iter(&self) -> impl Iterator<Item = (StringId, Arc<str>)> + '_ {
+        (0..NUM_SHARDS).flat_map(move |shard_idx| {
+            (0..)
+                .map(move |index| StringId(((shard_idx as u32) << INDEX_BITS) | index))
+                .map_while(move |id| self.get_shared(id).map(|s| (id, s)))
+        })
+    }
 
-        Err(StringTableError::NotFound)
+    fn get_shared(&self, id: StringId) -> Option<Arc<str>> {
+        let shard = (id.0 >> INDEX_BITS) as usize;
+        let index = (id.0 & ((1 << INDEX_BITS) - 1)) as usize;
+        self.shards[shard]
+            .read()
+            .unwrap()
+            .strings
+            .get(index)
+            .cloned()
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Debug, Default, Hash, Eq)]
+/// Returns the shard which `s` is stored in.  This uses the FNV-1a hash, rather than
+/// the standard library's hasher, so that a string is always stored in the same shard
+/// and given the same ID, whichever build of the compiler inserts it.
+fn shard_of(s: &str) -> usize {
+    let hash = s.bytes().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    hash as usize % NUM_SHARDS
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Hash, Eq, Serialize, Deserialize)]
 pub struct StringId(u32);
 
 impl StringId {
     /// Create a new String ID and initialize it to 0
-    pub fn new() -> StringId {
-        Self::default()
-    }
-
-    /// Increment by one and return the value of the ID before the increment.
-    fn get_and_inc(&mut self) -> StringId {
-        let old = *self;
-        self.0 += 1;
-        old
+    pub const fn new() -> StringId {
+        StringId(0)
     }
 }
 
@@ -118,3 +210,67 @@ impl std::fmt::Display for StringId {
         f.write_fmt(format_args!("{}", self.0))
     }
 }
+
+#[cfg(test)]
+mod test_stringtable {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_get() {
+        let table = StringTable::with_capacity(4);
+        let a = table.insert("a".into());
+        let b = table.insert("b".into());
+
+        assert_ne!(a, b);
+        assert_eq!(table.insert("a".into()), a);
+        assert_eq!(table.find("b"), Some(b));
+        assert_eq!(table.find("c"), None);
+        assert_eq!(table.get(a).unwrap(), "a");
+        assert_eq!(table.len(), 2);
+
+        let mut strings: Vec<_> = table.iter().map(|(id, s)| (id, s.to_string())).collect();
+        strings.sort_by_key(|(id, _)| id.0);
+        let mut expected = vec![(a, "a".to_string()), (b, "b".to_string())];
+        expected.sort_by_key(|(id, _)| id.0);
+        assert_eq!(strings, expected);
+    }
+
+    #[test]
+    fn test_reinsert_in_iteration_order() {
+        let table = StringTable::new();
+        let ids: Vec<_> = (0..100).map(|i| table.insert(format!("s{}", i))).collect();
+
+        let copy = StringTable::new();
+        for (id, s) in table.iter() {
+            assert_eq!(copy.insert(s.to_string()), id);
+        }
+        for (i, id) in ids.iter().enumerate() {
+            assert_eq!(copy.get(*id).unwrap(), format!("s{}", i));
+        }
+    }
+
+    #[test]
+    fn test_concurrent_insert() {
+        let table = Arc::new(StringTable::new());
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let table = table.clone();
+                std::thread::spawn(move || {
+                    (0..100)
+                        .map(|i| table.insert(format!("s{}", i)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let ids: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+
+        // Every thread must have been given the same ID for the same string
+        assert!(ids.iter().all(|t| *t == ids[0]));
+        assert_eq!(table.len(), 100);
+        for (i, id) in ids[0].iter().enumerate() {
+            assert_eq!(table.get(*id).unwrap(), format!("s{}", i));
+        }
+    }
+}
@@ -0,0 +1,425 @@
+//! Checks of the invariants which each phase of the compiler promises to the phases
+//! which follow it.  A violation of one of these invariants is a bug in the compiler,
+//! not in the program being compiled; without these checks such a bug is only
+//! noticed, if at all, when a later phase panics or generates incorrect code.
+//!
+//! These checks are run after each phase when the compiler is given `--verify-ir`,
+//! which also runs LLVM's module verifier over the generated LLVM IR.
+
+use crate::{
+    compiler::{
+        ast::{Element, Expression, Module, Node, NodeType, Path, Statement, Type},
+        import::Import,
+        mir::{ir::BasicBlockId, MirProject},
+        semantics::semanticnode::SemanticContext,
+        source::SourceIr,
+        CompilerDisplay, CompilerDisplayError, CompilerError, SourceMap, Span,
+    },
+    StringTable,
+};
+
+/// An invariant of the compiler which was found to be violated.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InvariantError {
+    /// A node of the semantic AST does not have a type.
+    UnresolvedType(NodeType),
+
+    /// A path in the semantic AST is not canonical.
+    NonCanonicalPath(Path),
+
+    /// A path in the semantic AST does not refer to an item in the project or in
+    /// any of its imports.
+    UnresolvedPath(Path),
+
+    /// A basic block of the given MIR procedure does not have a terminator.
+    MissingTerminator(Path, BasicBlockId),
+}
+
+impl CompilerDisplay for InvariantError {
+    fn fmt(&self, sm: &SourceMap, st: &StringTable) -> Result<String, CompilerDisplayError> {
+        let msg = match self {
+            InvariantError::UnresolvedType(node) => {
+                format!("The type of this {} was not resolved", node)
+            }
+            InvariantError::NonCanonicalPath(path) => {
+                format!("{} is not a canonical path", path.fmt(sm, st)?)
+            }
+            InvariantError::UnresolvedPath(path) => {
+                format!("{} does not refer to any item", path.fmt(sm, st)?)
+            }
+            InvariantError::MissingTerminator(path, bb) => {
+                format!("{} in {} does not have a terminator", bb, path.fmt(sm, st)?)
+            }
+        };
+        Ok(format!("Invariant violated: {}", msg))
+    }
+}
+
+/// Checks that type resolution resolved the type of every node in the AST, that
+/// every path has been made canonical, and that every path refers to an item which
+/// exists.
+pub fn verify_semantic_ast(
+    m: &Module<SemanticContext>,
+    imports: &[Import],
+) -> Vec<CompilerError<InvariantError>> {
+    let mut verifier = SemanticVerifier {
+        root: m,
+        imports,
+        errors: vec![],
+    };
+    verifier.verify();
+    verifier.errors
+}
+
+/// Checks that every basic block of every procedure in the MIR has a terminator.
+pub fn verify_mir(project: &MirProject) -> Vec<CompilerError<InvariantError>> {
+    let mut errors = vec![];
+    for (_, proc) in project.function_iter() {
+        for (id, bb) in proc.bb_iter() {
+            if bb.get_term().is_none() {
+                errors.push(CompilerError::new(
+                    proc.span(),
+                    InvariantError::MissingTerminator(proc.path().clone(), id),
+                ))
+            }
+        }
+    }
+    errors
+}
+
+struct SemanticVerifier<'a> {
+    root: &'a Module<SemanticContext>,
+    imports: &'a [Import],
+    errors: Vec<CompilerError<InvariantError>>,
+}
+
+impl<'a> SemanticVerifier<'a> {
+    fn verify(&mut self) {
+        for node in self.root.iter_preorder() {
+            let ty = node.context().ty();
+            if *ty == Type::Unknown {
+                self.errors.push(CompilerError::new(
+                    node.span(),
+                    InvariantError::UnresolvedType(node.node_type()),
+                ))
+            }
+
+            for path in type_paths(ty) {
+                self.check_canonical(node.span(), path);
+            }
+        }
+
+        self.module(self.root)
+    }
+
+    fn module(&mut self, m: &'a Module<SemanticContext>) {
+        for sm in m.get_modules() {
+            self.module(sm)
+        }
+
+        let items = m.get_functions().iter().chain(m.get_coroutines());
+        for r in items.filter_map(|i| i.to_routine()).chain(m.get_methods()) {
+            for stm in r.get_body() {
+                self.statement(stm)
+            }
+        }
+    }
+
+    fn statement(&mut self, stm: &Statement<SemanticContext>) {
+        match stm {
+            Statement::Bind(b) => self.expression(b.get_rhs()),
+            Statement::Mutate(m) => {
+                self.expression(m.get_lhs());
+                self.expression(m.get_rhs())
+            }
+            Statement::YieldReturn(yr) => {
+                if let Some(v) = yr.get_value() {
+                    self.expression(v)
+                }
+            }
+            Statement::Return(r) => {
+                if let Some(v) = r.get_value() {
+                    self.expression(v)
+                }
+            }
+            Statement::Expression(e) => self.expression(e),
+            Statement::Discard(d) => self.expression(d.get_value()),
+            Statement::Defer(d) => self.expression(d.get_value()),
+            Statement::Break(_) | Statement::Continue(_) => (),
+        }
+    }
+
+    fn expression(&mut self, exp: &Expression<SemanticContext>) {
+        match exp {
+            Expression::ExpressionBlock(_, body, final_exp) => {
+                for stm in body {
+                    self.statement(stm)
+                }
+                if let Some(fe) = final_exp {
+                    self.expression(fe)
+                }
+            }
+            Expression::StructExpression(_, path, fields) => {
+                self.check_resolves(exp.span(), path);
+                for (_, fe) in fields {
+                    self.expression(fe)
+                }
+            }
+            Expression::ArrayExpression(_, elements, _) => {
+                for e in elements {
+                    self.expression(e)
+                }
+            }
+            Expression::RoutineCall(_, _, path, args) => {
+                self.check_resolves(exp.span(), path);
+                for a in args {
+                    self.expression(a)
+                }
+            }
+            Expression::ArrayAt { array, index, .. } => {
+                self.expression(array);
+                self.expression(index)
+            }
+            Expression::If {
+                cond,
+                if_arm,
+                else_arm,
+                ..
+            } => {
+                self.expression(cond);
+                self.expression(if_arm);
+                if let Some(ea) = else_arm {
+                    self.expression(ea)
+                }
+            }
+            Expression::While { cond, body, .. } => {
+                self.expression(cond);
+                self.expression(body)
+            }
+            Expression::BinaryOp(_, _, l, r) => {
+                self.expression(l);
+                self.expression(r)
+            }
+            Expression::MemberAccess(_, e, _)
+            | Expression::TypeCast(_, e, _)
+            | Expression::UnaryOp(_, _, e)
+            | Expression::Yield(_, e) => self.expression(e),
+            _ => (),
+        }
+    }
+
+    /// Returns true if `path` is canonical, otherwise records an error
+    fn check_canonical(&mut self, span: Span, path: &Path) -> bool {
+        if !path.is_canonical() {
+            self.errors.push(CompilerError::new(
+                span,
+                InvariantError::NonCanonicalPath(path.clone()),
+            ));
+        }
+        path.is_canonical()
+    }
+
+    fn check_resolves(&mut self, span: Span, path: &Path) {
+        if self.check_canonical(span, path) && !self.resolves(path) {
+            self.errors.push(CompilerError::new(
+                span,
+                InvariantError::UnresolvedPath(path.clone()),
+            ))
+        }
+    }
+
+    /// Returns true if `path` is the canonical path of an item in the project or of
+    /// an imported item.
+    fn resolves(&self, path: &Path) -> bool {
+        let in_project = match path.item() {
+            Some(item) if path.first() == Some(&Element::Id(self.root.get_name())) => self
+                .root
+                .go_to_module(&path.parent())
+                .map_or(false, |m| m.get_item(item).is_some()),
+            _ => false,
+        };
+
+        in_project
+            || self.imports.iter().any(|import| {
+                import.funcs.iter().any(|f| f.path() == path)
+                    || import.structs.iter().any(|s| s.path() == path)
+            })
+    }
+}
+
+/// Returns every path which is referred to by the given type.
+fn type_paths(ty: &Type) -> Vec<&Path> {
+    match ty {
+        Type::Custom(path) => vec![path],
+        Type::RawPointer(_, ty)
+        | Type::Array(ty, _)
+        | Type::Slice(ty)
+        | Type::Range(ty)
+        | Type::Option(ty)
+        | Type::Coroutine(ty) => type_paths(ty),
+        Type::Result(ty, err_ty) => type_paths(ty)
+            .into_iter()
+            .chain(type_paths(err_ty))
+            .collect(),
+        Type::StructDef(fields) => fields.iter().flat_map(|(_, ty)| type_paths(ty)).collect(),
+        Type::FunctionDef(params, ret_ty)
+        | Type::CoroutineDef(params, ret_ty)
+        | Type::ExternDecl(params, _, ret_ty) => params
+            .iter()
+            .chain(std::iter::once(ret_ty.as_ref()))
+            .flat_map(type_paths)
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compiler::{
+            ast::{
+                Context, Element, Expression, Item, Node, NodeType, Path, Statement, Type,
+                MAIN_MODULE,
+            },
+            diagnostics::Logger,
+            lexer::tokens::Token,
+            mir::{ir::Procedure, transform, MirProject},
+            parser::Parser,
+            semantics::{type_resolver::resolve_types, typed::TypedModule},
+            CompilerError, Lexer, SourceMap, Span,
+        },
+        StringId, StringTable,
+    };
+
+    use super::{verify_mir, verify_semantic_ast, InvariantError};
+
+    fn compile(text: &str, table: &mut StringTable) -> TypedModule {
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let main = table.insert("main".into());
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let parser = Parser::new(&logger);
+        let ast = parser.parse(main, &tokens).unwrap().unwrap();
+        resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap()
+    }
+
+    const PROGRAM: &str = "
+fn my_main() -> i64 {
+    let s: S := S{a: inner::id(1)};
+    let mut i: i64 := 0;
+    while (i < s.a) {
+        mut i := i + 1;
+    };
+    return if (i > 0) {i} else {0};
+}
+
+struct S {
+    a: i64,
+}
+
+mod inner {
+    fn id(n: i64) -> i64 {
+        return n;
+    }
+}
+";
+
+    #[test]
+    fn valid_program() {
+        let mut table = StringTable::new();
+        let module = compile(PROGRAM, &mut table);
+        assert_eq!(verify_semantic_ast(&module, &[]), vec![]);
+
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+        assert_eq!(verify_mir(&project), vec![]);
+    }
+
+    #[test]
+    fn unresolved_type() {
+        let mut table = StringTable::new();
+        let mut module = compile(PROGRAM, &mut table).into_inner();
+
+        let exp = match &mut module.get_functions_mut()[0] {
+            Item::Routine(r) => match &mut r.get_body_mut()[0] {
+                Statement::Bind(b) => b.get_rhs_mut(),
+                _ => panic!("Expected a bind statement"),
+            },
+            _ => panic!("Expected a function"),
+        };
+        let ctx = exp.get_context_mut();
+        *ctx = ctx.with_type(Type::Unknown);
+        let span = ctx.span();
+
+        assert_eq!(
+            verify_semantic_ast(&module, &[]),
+            vec![CompilerError::new(
+                span,
+                InvariantError::UnresolvedType(NodeType::Expression)
+            )]
+        );
+    }
+
+    #[test]
+    fn unresolved_path() {
+        let mut table = StringTable::new();
+        let mut module = compile(PROGRAM, &mut table).into_inner();
+        let missing = table.insert("missing".into());
+
+        let exp = match &mut module.get_functions_mut()[0] {
+            Item::Routine(r) => match &mut r.get_body_mut()[0] {
+                Statement::Bind(b) => b.get_rhs_mut(),
+                _ => panic!("Expected a bind statement"),
+            },
+            _ => panic!("Expected a function"),
+        };
+        let path: Path = match exp {
+            Expression::StructExpression(_, path, _) => {
+                let mut missing_path = path.parent();
+                missing_path.push(Element::Id(missing));
+                **path = missing_path.clone();
+                missing_path
+            }
+            _ => panic!("Expected a struct expression"),
+        };
+        let span = exp.context().span();
+
+        assert_eq!(
+            verify_semantic_ast(&module, &[]),
+            vec![CompilerError::new(
+                span,
+                InvariantError::UnresolvedPath(path)
+            )]
+        );
+    }
+
+    #[test]
+    fn missing_terminator() {
+        let mut project = MirProject::new();
+        let path: Path = vec![Element::CanonicalRoot, Element::Id(StringId::new())].into();
+        let ty = project.find_type(&Type::Unit).unwrap();
+        let mut func = Procedure::new(&path, vec![], ty, Span::zero());
+        let bb = func.new_bb();
+        project.add_func(func).unwrap();
+
+        assert_eq!(
+            verify_mir(&project),
+            vec![CompilerError::new(
+                Span::zero(),
+                InvariantError::MissingTerminator(path, bb)
+            )]
+        );
+    }
+}
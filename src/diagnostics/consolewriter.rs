@@ -9,6 +9,9 @@ use crate::{
 
 /// Writes Compiler events to the console. This Writer will use the [`SourceMap`]
 /// to convert Span information to human readable source code references.
+///
+/// Events are written to stderr, like every other diagnostic, so that a trace does
+/// not mix with the output of the compiler.
 pub struct ConsoleWriter<'a> {
     source_map: &'a SourceMap,
     string_table: &'a StringTable,
@@ -25,7 +28,7 @@ impl<'a> ConsoleWriter<'a> {
 
 impl<'a> Writer for ConsoleWriter<'a> {
     fn write_span(&self, field: &str, span: crate::compiler::Span) {
-        print!("{}: ", field);
+        eprint!("{}: ", field);
 
         // Get source code that the span covers
         let src = self.source_map.text_in_span(span).unwrap();
@@ -35,9 +38,9 @@ impl<'a> Writer for ConsoleWriter<'a> {
 
         let width = 20;
         if src.len() < width {
-            print!("[{}], ", src);
+            eprint!("[{}], ", src);
         } else {
-            print!(
+            eprint!(
                 "[{}...{}], ",
                 &src[0..width / 2],
                 &src[src.len() - width / 2..]
@@ -46,9 +49,9 @@ impl<'a> Writer for ConsoleWriter<'a> {
     }
 
     fn write_field(&self, label: &str, s: &dyn Writable) {
-        print!("{}: ", label);
+        eprint!("{}: ", label);
         s.write(self);
-        print!(", ");
+        eprint!(", ");
     }
 
     fn write(&self, s: &dyn Writable) {
@@ -56,28 +59,28 @@ impl<'a> Writer for ConsoleWriter<'a> {
     }
 
     fn start_event(&self) {
-        print!("{{");
+        eprint!("{{");
     }
 
     fn stop_event(&self) {
-        print!("}}\n");
+        eprint!("}}\n");
     }
 
     fn write_str(&self, s: &str) {
-        print!("{}", s);
+        eprint!("{}", s);
     }
 
     fn write_stringid(&self, s: crate::StringId) {
-        print!("{}", self.string_table.get(s).unwrap());
+        eprint!("{}", self.string_table.get(s).unwrap());
     }
 
     fn write_error(&self, e: &dyn crate::compiler::CompilerDisplay) {
         let s = e.fmt(self.source_map, self.string_table).unwrap();
-        print!("{}", s);
+        eprint!("{}", s);
     }
 
     fn write_text(&self, s: &str) {
-        print!("{}", s);
+        eprint!("{}", s);
     }
 
     fn write_path(&self, p: &crate::compiler::ast::Path) {
@@ -103,6 +106,6 @@ impl<'a> Writer for ConsoleWriter<'a> {
     }
 
     fn write_u64(&self, u: u64) {
-        print!("{}", u);
+        eprint!("{}", u);
     }
 }
@@ -174,7 +174,7 @@ impl From<&SourceMap> for JsonSourceMap {
         let mut map = vec![];
         for idx in 0..sm.len() {
             if let Some(entry) = sm.get(idx).map(|entry| JsonSourceMapEntry {
-                source: entry.path().clone(),
+                source: entry.remapped_path().clone(),
                 span: entry.span().into(),
             }) {
                 map.push(entry)
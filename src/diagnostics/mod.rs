@@ -1,5 +1,7 @@
 mod consolewriter;
 mod jsonwriter;
+mod reporter;
 
 pub use consolewriter::ConsoleWriter;
 pub use jsonwriter::*;
+pub use reporter::Reporter;
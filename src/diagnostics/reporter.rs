@@ -0,0 +1,195 @@
+use std::{
+    fmt::Display,
+    io::{Stderr, Write},
+    time::Duration,
+};
+
+use crate::{
+    compiler::{CompilerDisplay, SourceMap},
+    StringTable,
+};
+
+/// Reports errors, warnings, and other messages about the compilation to the user.
+///
+/// The compiler writes every diagnostic through a [`Reporter`] so that they all go to
+/// the same place: by default stderr, which leaves stdout for the output that the user
+/// asked for (e.g. object code written with `-o -`).
+pub struct Reporter<W: Write> {
+    /// Output target for the diagnostics
+    out: W,
+
+    /// The number of errors (or warnings) that will be written for each call, if 0
+    /// then every error is written
+    limit: usize,
+
+    /// If true, then the time taken by each stage of the compiler is written
+    timings: bool,
+}
+
+impl Reporter<Stderr> {
+    /// Creates a [`Reporter`] which writes to stderr.
+    pub fn stderr(limit: usize) -> Reporter<Stderr> {
+        Reporter::new(std::io::stderr(), limit)
+    }
+}
+
+impl<W: Write> Reporter<W> {
+    pub fn new(out: W, limit: usize) -> Reporter<W> {
+        Reporter {
+            out,
+            limit,
+            timings: false,
+        }
+    }
+
+    /// Sets whether the time taken by each stage of the compiler is written.
+    pub fn with_timings(mut self, timings: bool) -> Reporter<W> {
+        self.timings = timings;
+        self
+    }
+
+    /// Writes at most `limit` errors and then a summary of how many errors were not written.
+    pub fn errors<E: CompilerDisplay>(
+        &mut self,
+        errs: &[E],
+        sm: &SourceMap,
+        st: &StringTable,
+    ) -> std::io::Result<()> {
+        self.report("Error", "errors", errs, sm, st)
+    }
+
+    /// Writes at most `limit` warnings and then a summary of how many warnings were not written.
+    pub fn warnings<E: CompilerDisplay>(
+        &mut self,
+        warnings: &[E],
+        sm: &SourceMap,
+        st: &StringTable,
+    ) -> std::io::Result<()> {
+        self.report("Warning", "warnings", warnings, sm, st)
+    }
+
+    /// Writes a single error which does not refer to the source code (e.g. a file which
+    /// could not be written).
+    pub fn error<M: Display>(&mut self, msg: M) -> std::io::Result<()> {
+        writeln!(self.out, "Error: {}", msg)?;
+        self.out.flush()
+    }
+
+    /// Writes information about the compilation which the user asked for, but which is not
+    /// the output of the compiler (e.g. the target that code is generated for).
+    pub fn message<M: Display>(&mut self, msg: M) -> std::io::Result<()> {
+        writeln!(self.out, "{}", msg)?;
+        self.out.flush()
+    }
+
+    /// Writes how long the given stage of the compiler took, if timings are enabled.
+    pub fn timing(&mut self, stage: &str, duration: Duration) -> std::io::Result<()> {
+        if self.timings {
+            writeln!(self.out, "{}: {}", stage, duration.as_secs_f32())?;
+            self.out.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the output target, so that what was written to it can be inspected.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn report<E: CompilerDisplay>(
+        &mut self,
+        label: &str,
+        plural: &str,
+        items: &[E],
+        sm: &SourceMap,
+        st: &StringTable,
+    ) -> std::io::Result<()> {
+        let (shown, suppressed) = split_at_limit(items, self.limit);
+        for item in shown {
+            let msg = item
+                .fmt(sm, st)
+                .unwrap_or_else(|e| format!("<could not display {}: {:?}>", label, e));
+            writeln!(self.out, "{}: {}", label, msg)?;
+        }
+
+        if suppressed > 0 {
+            writeln!(self.out, "{} additional {} suppressed", suppressed, plural)?;
+        }
+
+        self.out.flush()
+    }
+}
+
+/// Returns the first `limit` items, which will be written, and the number of items after them,
+/// which will be suppressed.
+fn split_at_limit<E>(items: &[E], limit: usize) -> (&[E], usize) {
+    if limit == 0 || items.len() <= limit {
+        (items, 0)
+    } else {
+        (&items[..limit], items.len() - limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(limit: usize, errs: &[&str], warnings: &[&str]) -> String {
+        let sm = SourceMap::new();
+        let table = StringTable::new();
+        let errs: Vec<String> = errs.iter().map(|e| e.to_string()).collect();
+        let warnings: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+
+        let mut reporter = Reporter::new(vec![], limit);
+        reporter.errors(&errs, &sm, &table).unwrap();
+        reporter.warnings(&warnings, &sm, &table).unwrap();
+        String::from_utf8(reporter.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn test_errors_and_warnings() {
+        assert_eq!(
+            report(0, &["a", "b"], &["c"]),
+            "Error: a\nError: b\nWarning: c\n"
+        );
+    }
+
+    #[test]
+    fn test_limit() {
+        assert_eq!(
+            report(1, &["a", "b", "c"], &["d", "e"]),
+            "Error: a\n2 additional errors suppressed\nWarning: d\n1 additional warnings suppressed\n"
+        );
+        assert_eq!(
+            report(3, &["a", "b", "c"], &[]),
+            "Error: a\nError: b\nError: c\n"
+        );
+    }
+
+    #[test]
+    fn test_timings() {
+        let duration = Duration::from_millis(1500);
+
+        let mut reporter = Reporter::new(vec![], 0);
+        reporter.timing("Lexer", duration).unwrap();
+        assert_eq!(String::from_utf8(reporter.into_inner()).unwrap(), "");
+
+        let mut reporter = Reporter::new(vec![], 0).with_timings(true);
+        reporter.timing("Lexer", duration).unwrap();
+        assert_eq!(
+            String::from_utf8(reporter.into_inner()).unwrap(),
+            "Lexer: 1.5\n"
+        );
+    }
+
+    #[test]
+    fn test_messages() {
+        let mut reporter = Reporter::new(vec![], 0);
+        reporter.error("Failed to write file").unwrap();
+        reporter.message("target: x86_64").unwrap();
+        assert_eq!(
+            String::from_utf8(reporter.into_inner()).unwrap(),
+            "Error: Failed to write file\ntarget: x86_64\n"
+        );
+    }
+}
@@ -1,33 +1,154 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
-use crate::{result::NResult, Manifest};
+use crate::{compiler::SourceMapError, result::NResult, Manifest};
 use clap::ArgMatches;
 
 use super::cli::get_imports;
 
-pub fn get_files(path: &Path, ext: &str) -> Result<Vec<PathBuf>, std::io::Error> {
-    let mut files = vec![];
+/// What is found at a path given to a [`FileProvider`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileKind {
+    File,
+    Dir,
+}
+
+/// Provides every input that the compiler reads: the source files of a project and the
+/// manifests that it imports.  The compiler never reads from the filesystem directly,
+/// so a host which has no filesystem (e.g. a sandboxed service or wasm) can compile a
+/// project by giving its inputs in memory with a [`MemoryProvider`].
+pub trait FileProvider {
+    /// Returns the contents of the file at `path`.
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>>;
+
+    /// Returns whether `path` is a file or a directory, following symlinks.
+    fn kind(&self, path: &Path) -> std::io::Result<FileKind>;
+
+    /// Returns the path of every entry in the directory `path`, in any order.
+    fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>>;
+
+    /// Returns the canonical form of `path`, which is the same for every path that
+    /// reaches the same file or directory (e.g. through a symlink).
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf>;
+}
+
+/// Reads inputs from the filesystem.  This is the provider that `bramblec` uses.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FsProvider;
+
+impl FileProvider for FsProvider {
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn kind(&self, path: &Path) -> std::io::Result<FileKind> {
+        let fty = std::fs::metadata(path)?.file_type();
+        if fty.is_dir() {
+            Ok(FileKind::Dir)
+        } else {
+            Ok(FileKind::File)
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        std::fs::read_dir(path)?
+            .map(|e| e.map(|e| e.path()))
+            .collect()
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        path.canonicalize()
+    }
+}
+
+/// Holds every input in memory, keyed by its path.  A directory exists wherever a
+/// file has been added beneath it; there are no symlinks, so every path is canonical.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryProvider {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryProvider {
+    pub fn new() -> MemoryProvider {
+        MemoryProvider {
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a file with the given contents, replacing any file already at `path`.
+    pub fn add_file<P: Into<PathBuf>, C: Into<Vec<u8>>>(&mut self, path: P, contents: C) {
+        self.files.insert(path.into(), contents.into());
+    }
+
+    fn not_found(path: &Path) -> std::io::Error {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{} was not provided", path.display()),
+        )
+    }
+}
+
+impl FileProvider for MemoryProvider {
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| MemoryProvider::not_found(path))
+    }
+
+    fn kind(&self, path: &Path) -> std::io::Result<FileKind> {
+        if self.files.contains_key(path) {
+            Ok(FileKind::File)
+        } else if self.files.keys().any(|f| f != path && f.starts_with(path)) {
+            Ok(FileKind::Dir)
+        } else {
+            Err(MemoryProvider::not_found(path))
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        if self.kind(path)? != FileKind::Dir {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} is not a directory", path.display()),
+            ));
+        }
+
+        // The entries are the first component beneath `path` of every file within it
+        let mut entries: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter_map(|f| f.strip_prefix(path).ok()?.components().next())
+            .map(|c| path.join(c))
+            .collect();
+        entries.dedup();
+        Ok(entries)
+    }
+
+    fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+        self.kind(path).map(|_| path.to_path_buf())
+    }
+}
+
+/// Returns every source file, with the extension `ext`, in `path`.  If `path` is a
+/// directory, then this will recursively search it and its subdirectories, following
+/// symlinks.  The files are returned in the same order regardless of the order in which
+/// the filesystem lists them.
+pub fn get_files(
+    files: &dyn FileProvider,
+    path: &Path,
+    ext: &str,
+) -> Result<Vec<PathBuf>, SourceMapError> {
+    let mut found = vec![];
     match path.extension() {
         None => {
-            let dir = std::fs::read_dir(path)?;
-            for f in dir {
-                let f = f?;
-                let fty = f.file_type()?;
-                if fty.is_file() {
-                    match f.path().extension() {
-                        Some(ex) if ex.to_ascii_lowercase() == ext => {
-                            files.push(f.path());
-                        }
-                        _ => (),
-                    }
-                } else if fty.is_dir() {
-                    let mut sub_files = get_files(&f.path(), ext)?;
-                    files.append(&mut sub_files);
-                }
-            }
+            let mut ancestors = vec![];
+            get_files_in_dir(files, path, ext, &mut ancestors, &mut found)?;
         }
         Some(ex) if ex.to_ascii_lowercase() == "br" => {
-            files.push(path.to_path_buf());
+            found.push(path.to_path_buf());
         }
         Some(ex) => {
             return Err(std::io::Error::new(
@@ -37,19 +158,59 @@ pub fn get_files(path: &Path, ext: &str) -> Result<Vec<PathBuf>, std::io::Error>
                     ext,
                     ex.to_str().unwrap()
                 ),
-            ));
+            )
+            .into());
         }
     }
-    Ok(files)
+    Ok(found)
+}
+
+/// Adds every file in `dir` and its subdirectories to `files`. `ancestors` holds the
+/// canonical path of every directory which is being searched, so that a link back to
+/// one of them is reported as an error rather than followed forever.
+fn get_files_in_dir(
+    provider: &dyn FileProvider,
+    dir: &Path,
+    ext: &str,
+    ancestors: &mut Vec<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), SourceMapError> {
+    let canonical = provider.canonicalize(dir)?;
+    if let Some(target) = ancestors.iter().find(|a| **a == canonical) {
+        return Err(SourceMapError::DirectoryCycle(
+            dir.to_path_buf(),
+            target.clone(),
+        ));
+    }
+    ancestors.push(canonical);
+
+    let mut entries = provider.read_dir(dir)?;
+    entries.sort();
+
+    for entry in entries {
+        // Use the kind of the file that a symlink points to
+        match provider.kind(&entry)? {
+            FileKind::File => match entry.extension() {
+                Some(ex) if ex.to_ascii_lowercase() == ext => files.push(entry),
+                _ => (),
+            },
+            FileKind::Dir => get_files_in_dir(provider, &entry, ext, ancestors, files)?,
+        }
+    }
+
+    ancestors.pop();
+    Ok(())
 }
 
-pub fn read_manifests(args: &ArgMatches) -> NResult<Vec<Manifest>> {
+/// Reads the manifest of every project that is imported, including the standard library.
+pub fn read_manifests(args: &ArgMatches, files: &dyn FileProvider) -> NResult<Vec<Manifest>> {
     let imports: Vec<_> = get_imports(args)
         .into_iter()
         .map(|im| {
-            std::fs::File::open(im)
+            files
+                .read(Path::new(im))
                 .map_err(|e| format!("{}", e))
-                .and_then(|mut f| Manifest::read(&mut f).map_err(|e| format!("{}", e)))
+                .and_then(|text| Manifest::read(&mut &text[..]).map_err(|e| format!("{}", e)))
                 .map_err(|e| format!("Failed to import {}: {}", im, e))
         })
         .collect();
@@ -10,5 +10,7 @@ pub mod project;
 
 pub use cli::*;
 pub use compiler::{llvm, semantics::type_resolver::*, stringtable::*};
-pub use io::read_manifests;
-pub use project::{get_project_name, parse_project, tokenize_source_map, Manifest};
+pub use io::{read_manifests, FileProvider, FsProvider, MemoryProvider};
+pub use project::{
+    build_source_map, get_project_name, parse_project, tokenize_source_map, Manifest,
+};
@@ -0,0 +1,355 @@
+//! Saves the semantic AST of a project, or the MIR of a single procedure, to an archive
+//! which can be loaded again later, e.g. by an incremental build, by an external
+//! analysis tool, or while debugging the compiler.
+//!
+//! An archive is JSON.  It starts with a header that names the format, the version of
+//! the format, and what kind of IR the archive holds, followed by every string in the
+//! [`StringTable`] and then the IR itself.  The IR refers to strings by their
+//! [`StringId`](crate::StringId)s, so an archive is loaded into a new [`StringTable`]
+//! which assigns every string the same ID that it had when the archive was saved.
+//!
+//! The header is checked before the IR is read, so an archive written by a different
+//! version of the compiler is rejected rather than misread.  [`ARCHIVE_VERSION`] must
+//! be incremented whenever a change to the IR changes how it is serialized.
+
+use std::io::{Read, Write};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    compiler::{ast::Module, mir::ir::Procedure, semantics::semanticnode::SemanticContext},
+    StringTable,
+};
+
+/// Identifies a file as an archive of Bramble IR
+const ARCHIVE_FORMAT: &str = "bramble-ir";
+
+/// The version of the archive format which this compiler reads and writes
+pub const ARCHIVE_VERSION: u32 = 4;
+
+/// The kind of IR which an archive holds
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ArchiveKind {
+    SemanticAst,
+    MirProcedure,
+}
+
+impl std::fmt::Display for ArchiveKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveKind::SemanticAst => f.write_str("semantic AST"),
+            ArchiveKind::MirProcedure => f.write_str("MIR procedure"),
+        }
+    }
+}
+
+/// Errors that can occur when saving or loading an archive.
+#[derive(Debug)]
+pub enum ArchiveError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+
+    /// The file is not an archive of Bramble IR
+    NotAnArchive,
+
+    /// The archive was written with a version of the format which is not supported
+    UnsupportedVersion(u32),
+
+    /// The archive holds a different kind of IR than was asked for.  Stores the kind
+    /// that was expected and the kind that the archive holds.
+    WrongKind(ArchiveKind, ArchiveKind),
+
+    /// The same string is listed twice in the archive, so the IDs of the strings which
+    /// follow it cannot be restored
+    DuplicateString(String),
+}
+
+impl std::fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveError::Io(e) => write!(f, "IO Error: {}", e),
+            ArchiveError::Json(e) => write!(f, "Invalid archive: {}", e),
+            ArchiveError::NotAnArchive => f.write_str("File is not an archive of Bramble IR"),
+            ArchiveError::UnsupportedVersion(v) => write!(
+                f,
+                "Archive has version {}, but only version {} is supported",
+                v, ARCHIVE_VERSION
+            ),
+            ArchiveError::WrongKind(expected, found) => write!(
+                f,
+                "Expected an archive of a {}, but found a {}",
+                expected, found
+            ),
+            ArchiveError::DuplicateString(s) => {
+                write!(f, "Archive lists the string {:?} more than once", s)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for ArchiveError {
+    fn from(e: std::io::Error) -> Self {
+        ArchiveError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ArchiveError {
+    fn from(e: serde_json::Error) -> Self {
+        ArchiveError::Json(e)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Header {
+    format: String,
+    version: u32,
+    kind: ArchiveKind,
+}
+
+/// Only the header of an archive, so that it can be checked without reading the IR
+#[derive(Deserialize)]
+struct HeaderOnly {
+    header: Header,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Archive<T> {
+    header: Header,
+    strings: Vec<String>,
+    ir: T,
+}
+
+/// Writes the semantic AST of a project, and the strings which it uses, to an archive.
+pub fn save_module<W: Write>(
+    writer: W,
+    module: &Module<SemanticContext>,
+    st: &StringTable,
+) -> Result<(), ArchiveError> {
+    save(writer, ArchiveKind::SemanticAst, module, st)
+}
+
+/// Reads the semantic AST of a project from an archive, and returns it with a new
+/// [`StringTable`] which holds the strings that it uses.
+pub fn load_module<R: Read>(
+    reader: R,
+) -> Result<(Module<SemanticContext>, StringTable), ArchiveError> {
+    load(reader, ArchiveKind::SemanticAst)
+}
+
+/// Writes the MIR of a procedure, and the strings which it uses, to an archive.  The
+/// types of the procedure are the [`TypeId`](crate::compiler::mir::TypeId)s of the
+/// project which it was generated for.
+pub fn save_procedure<W: Write>(
+    writer: W,
+    procedure: &Procedure,
+    st: &StringTable,
+) -> Result<(), ArchiveError> {
+    save(writer, ArchiveKind::MirProcedure, procedure, st)
+}
+
+/// Reads the MIR of a procedure from an archive, and returns it with a new
+/// [`StringTable`] which holds the strings that it uses.
+pub fn load_procedure<R: Read>(reader: R) -> Result<(Procedure, StringTable), ArchiveError> {
+    load(reader, ArchiveKind::MirProcedure)
+}
+
+fn save<W: Write, T: Serialize>(
+    writer: W,
+    kind: ArchiveKind,
+    ir: &T,
+    st: &StringTable,
+) -> Result<(), ArchiveError> {
+    let archive = Archive {
+        header: Header {
+            format: ARCHIVE_FORMAT.into(),
+            version: ARCHIVE_VERSION,
+            kind,
+        },
+        strings: st.iter().map(|(_, s)| s.to_string()).collect(),
+        ir,
+    };
+    serde_json::to_writer(writer, &archive)?;
+    Ok(())
+}
+
+fn load<R: Read, T: DeserializeOwned>(
+    mut reader: R,
+    kind: ArchiveKind,
+) -> Result<(T, StringTable), ArchiveError> {
+    let mut text = vec![];
+    reader.read_to_end(&mut text)?;
+
+    let header = from_slice::<HeaderOnly>(&text)
+        .map_err(|_| ArchiveError::NotAnArchive)?
+        .header;
+    if header.format != ARCHIVE_FORMAT {
+        return Err(ArchiveError::NotAnArchive);
+    }
+    if header.version != ARCHIVE_VERSION {
+        return Err(ArchiveError::UnsupportedVersion(header.version));
+    }
+    if header.kind != kind {
+        return Err(ArchiveError::WrongKind(kind, header.kind));
+    }
+
+    let archive: Archive<T> = from_slice(&text)?;
+
+    // Strings are inserted in the order of their IDs, so each is given its original ID
+    let st = StringTable::with_capacity(archive.strings.len());
+    for s in archive.strings {
+        if st.find(&s).is_some() {
+            return Err(ArchiveError::DuplicateString(s));
+        }
+        st.insert(s);
+    }
+
+    Ok((archive.ir, st))
+}
+
+/// Deserializes JSON without a limit on how deeply it is nested, because the IR of a
+/// program is nested as deeply as its expressions are.
+fn from_slice<T: DeserializeOwned>(text: &[u8]) -> Result<T, serde_json::Error> {
+    let mut de = serde_json::Deserializer::from_slice(text);
+    de.disable_recursion_limit();
+    let value = T::deserialize(&mut de)?;
+    de.end()?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compiler::{
+            ast::MAIN_MODULE,
+            diagnostics::Logger,
+            lexer::{tokens::Token, LexerError},
+            parser::Parser,
+            semantics::typed::TypedModule,
+            transform, CompilerError, Lexer, MirProject, SourceMap,
+        },
+        resolve_types, StringTable,
+    };
+
+    use super::*;
+
+    type LResult = std::result::Result<Vec<Token>, CompilerError<LexerError>>;
+
+    const TEXT: &str = "
+        struct Point {x: i64, y: f64}
+
+        fn my_main() -> i64 {
+            let p: Point := Point{x: 3, y: 0.1};
+            let mut total: i64 := 0;
+            while (total < p.x) {
+                mut total := total + 1;
+            };
+            return if (total == 3) {total} else {0};
+        }
+        ";
+
+    fn compile(text: &str, table: &StringTable) -> TypedModule {
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let main = table.insert("main".into());
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<LResult>()
+            .unwrap();
+        let ast = Parser::new(&logger).parse(main, &tokens).unwrap().unwrap();
+        resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap()
+    }
+
+    fn strings(st: &StringTable) -> Vec<String> {
+        st.iter().map(|(_, s)| s.to_string()).collect()
+    }
+
+    #[test]
+    fn module_round_trip() {
+        let table = StringTable::new();
+        let module = compile(TEXT, &table);
+
+        let mut archive = vec![];
+        save_module(&mut archive, &module, &table).unwrap();
+        let (loaded, loaded_table) = load_module(&archive[..]).unwrap();
+
+        assert_eq!(loaded, *module);
+        assert_eq!(strings(&loaded_table), strings(&table));
+    }
+
+    #[test]
+    fn procedure_round_trip() {
+        let table = StringTable::new();
+        let module = compile(TEXT, &table);
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+
+        let (_, procedure) = project.function_iter().next().unwrap();
+        let mut archive = vec![];
+        save_procedure(&mut archive, procedure, &table).unwrap();
+        let (loaded, loaded_table) = load_procedure(&archive[..]).unwrap();
+
+        assert_eq!(&loaded, procedure);
+        assert_eq!(strings(&loaded_table), strings(&table));
+    }
+
+    #[test]
+    fn deeply_nested_round_trip() {
+        // Deeper than the default nesting limit of the JSON parser.  The compiler recurses
+        // once per level of the expression, so this runs on a thread with a stack which is
+        // large enough for an unoptimized build.
+        std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn(|| {
+                let sum = vec!["1"; 100].join(" + ");
+                let text = format!("fn my_main() -> i64 {{ return {}; }}", sum);
+                let table = StringTable::new();
+                let module = compile(&text, &table);
+
+                let mut archive = vec![];
+                save_module(&mut archive, &module, &table).unwrap();
+                let (loaded, _) = load_module(&archive[..]).unwrap();
+                assert_eq!(loaded, *module);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn header_is_checked() {
+        let table = StringTable::new();
+        let module = compile(TEXT, &table);
+        let mut archive = vec![];
+        save_module(&mut archive, &module, &table).unwrap();
+
+        match load_procedure(&archive[..]) {
+            Err(ArchiveError::WrongKind(ArchiveKind::MirProcedure, ArchiveKind::SemanticAst)) => (),
+            r => panic!("Expected a wrong kind error, got {:?}", r.map(|_| ())),
+        }
+
+        let text = String::from_utf8(archive).unwrap();
+        let version = format!("\"version\":{}", ARCHIVE_VERSION);
+        assert!(text.contains(&version));
+        let newer = text.replacen(&version, "\"version\":9999", 1);
+        match load_module(newer.as_bytes()) {
+            Err(ArchiveError::UnsupportedVersion(9999)) => (),
+            r => panic!(
+                "Expected an unsupported version error, got {:?}",
+                r.map(|_| ())
+            ),
+        }
+
+        match load_module(&b"{\"name\": \"not an archive\"}"[..]) {
+            Err(ArchiveError::NotAnArchive) => (),
+            r => panic!("Expected a not an archive error, got {:?}", r.map(|_| ())),
+        }
+    }
+}
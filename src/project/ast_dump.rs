@@ -0,0 +1,65 @@
+use serde::Serialize;
+
+use crate::{
+    compiler::{
+        ast::{Context, Node},
+        SourceMap,
+    },
+    StringTable,
+};
+
+use super::{symbols::SymbolSpan, ManifestError};
+
+/// The AST of a project as a tree of nodes.  This is written as JSON by
+/// `--emit ast-json` so that external tools can inspect the AST and refer to its
+/// nodes by their IDs.
+#[derive(Serialize, Debug)]
+pub struct AstDump {
+    root: AstNode,
+}
+
+/// A single node of the AST and all of its children.
+#[derive(Serialize, Debug, PartialEq)]
+struct AstNode {
+    id: u32,
+    kind: String,
+    name: Option<String>,
+    span: SymbolSpan,
+    children: Vec<AstNode>,
+}
+
+impl AstDump {
+    /// Converts the given node, and every node beneath it, into a tree.
+    pub fn extract<M: Context>(
+        node: &dyn Node<M>,
+        sm: &SourceMap,
+        st: &StringTable,
+    ) -> Result<Self, ManifestError> {
+        Ok(AstDump {
+            root: Self::extract_node(node, sm, st)?,
+        })
+    }
+
+    fn extract_node<M: Context>(
+        node: &dyn Node<M>,
+        sm: &SourceMap,
+        st: &StringTable,
+    ) -> Result<AstNode, ManifestError> {
+        Ok(AstNode {
+            id: node.context().id(),
+            kind: node.node_type().to_string(),
+            name: node.name().map(|name| st.get(name)).transpose()?,
+            span: SymbolSpan::new(node.context().span(), sm),
+            children: node
+                .children()
+                .into_iter()
+                .map(|c| Self::extract_node(c, sm, st))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Writes the tree to the given file as JSON
+    pub fn write(&self, file: &mut std::fs::File) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(file, self)
+    }
+}
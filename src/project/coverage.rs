@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compiler::{CompilerDisplay, CoverageMap, SourceMap},
+    StringTable,
+};
+
+use super::ManifestError;
+
+/// Relates each coverage counter of a project to the source code that it covers.
+/// This is written as JSON by `--coverage`, next to the object file, so that the
+/// counters which an instrumented program writes when it exits can be turned into
+/// a report of how many times each line of the project was run.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct CoverageMapping {
+    counters: Vec<CounterEntry>,
+}
+
+/// The source code covered by a single counter.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct CounterEntry {
+    function: String,
+    file: Option<String>,
+    start_line: u32,
+    end_line: u32,
+}
+
+impl CoverageMapping {
+    /// Converts the counters of the given [`CoverageMap`] into the lines which they
+    /// cover.  A counter whose span is not in the source map covers no lines.
+    pub fn extract(
+        map: &CoverageMap,
+        sm: &SourceMap,
+        st: &StringTable,
+    ) -> Result<Self, ManifestError> {
+        let counters = map
+            .iter()
+            .map(|c| {
+                let lines = sm.lines_in_span(c.span());
+                let (file, start_line, end_line) = match lines.first() {
+                    Some((file, lines)) => (
+                        Some(file.to_string_lossy().into_owned()),
+                        lines.iter().min().map_or(0, |l| l.as_u32()),
+                        lines.iter().max().map_or(0, |l| l.as_u32()),
+                    ),
+                    None => (None, 0, 0),
+                };
+                Ok(CounterEntry {
+                    function: c.path().fmt(sm, st)?,
+                    file,
+                    start_line,
+                    end_line,
+                })
+            })
+            .collect::<Result<_, ManifestError>>()?;
+        Ok(CoverageMapping { counters })
+    }
+
+    /// Writes the mapping to the given file as JSON
+    pub fn write(&self, file: &mut std::fs::File) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(file, self)
+    }
+
+    /// Reads a mapping that was written by [`CoverageMapping::write`]
+    pub fn read(file: &mut std::fs::File) -> Result<Self, serde_json::Error> {
+        serde_json::from_reader(file)
+    }
+
+    /// Sums the counters from every run which has been appended to a counters file.
+    /// Each run is a native endian `u64` for every counter in this mapping.
+    pub fn merge(&self, counts: &[u8]) -> Result<Vec<u64>, String> {
+        const WIDTH: usize = std::mem::size_of::<u64>();
+
+        let mut totals = vec![0u64; self.counters.len()];
+        let run_len = totals.len() * WIDTH;
+        if run_len == 0 || counts.len() % run_len != 0 {
+            return Err(format!(
+                "Counters file has {} bytes, which is not a whole number of runs of {} counters",
+                counts.len(),
+                totals.len()
+            ));
+        }
+
+        for run in counts.chunks(run_len) {
+            for (total, count) in totals.iter_mut().zip(run.chunks(WIDTH)) {
+                let mut bytes = [0u8; WIDTH];
+                bytes.copy_from_slice(count);
+                *total += u64::from_ne_bytes(bytes);
+            }
+        }
+
+        Ok(totals)
+    }
+
+    /// Formats how many times each line of each file was run, given the total of
+    /// every counter.  Each line is attributed to the counter with the smallest
+    /// range of lines which contains it, because a block nested inside of another
+    /// block is the one which runs that line.  Lines which are not covered by any
+    /// counter are marked with `-`, and lines which were never run with `#####`.
+    ///
+    /// `read_source` is used to read the text of each file in the mapping.
+    pub fn report<F>(&self, totals: &[u64], read_source: F) -> Result<String, String>
+    where
+        F: Fn(&str) -> std::io::Result<String>,
+    {
+        // The counters that cover each line of each file
+        let mut files: BTreeMap<&str, BTreeMap<u32, (u32, u64)>> = BTreeMap::new();
+        for (c, total) in self.counters.iter().zip(totals) {
+            let file = match &c.file {
+                Some(file) => files.entry(file.as_str()).or_default(),
+                None => continue,
+            };
+
+            let range = c.end_line - c.start_line;
+            for line in c.start_line..=c.end_line {
+                let entry = file.entry(line).or_insert((range, *total));
+                if range < entry.0 || (range == entry.0 && *total > entry.1) {
+                    *entry = (range, *total)
+                }
+            }
+        }
+
+        let mut report = String::new();
+        for (file, lines) in files {
+            let text = read_source(file).map_err(|e| format!("Could not read {}: {}", file, e))?;
+            report.push_str(&format!("{}\n", file));
+            for (idx, line) in text.lines().enumerate() {
+                let line_no = idx as u32 + 1;
+                let count = match lines.get(&line_no) {
+                    Some((_, 0)) => "#####".into(),
+                    Some((_, count)) => count.to_string(),
+                    None => "-".into(),
+                };
+                report.push_str(&format!("{:>9}:{:>5}:{}\n", count, line_no, line));
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compiler::{
+            ast::MAIN_MODULE, diagnostics::Logger, lexer::tokens::Token, parser::Parser,
+            semantics::type_resolver::resolve_types, transform, CoverageMap, Lexer, MirProject,
+            SourceMap,
+        },
+        StringTable,
+    };
+
+    use super::CoverageMapping;
+
+    const TEXT: &str = "fn my_main() -> i64 {
+    let x: i64 := 1;
+    if (x > 0) {
+        return 1;
+    };
+    return 0;
+}
+";
+
+    fn mapping() -> CoverageMapping {
+        let mut sm = SourceMap::new();
+        sm.add_string(TEXT, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let table = StringTable::new();
+        let main = table.insert("main".into());
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let parser = Parser::new(&logger);
+        let ast = parser.parse(main, &tokens).unwrap().unwrap();
+        let module = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
+
+        let mut project = MirProject::new();
+        transform::transform(&module, &[], &mut project).unwrap();
+        let map = CoverageMap::new(&project);
+        CoverageMapping::extract(&map, &sm, &table).unwrap()
+    }
+
+    #[test]
+    fn merge_runs() {
+        let mapping = mapping();
+        let n = mapping.counters.len();
+
+        let mut counts = vec![];
+        for run in 0..2u64 {
+            for c in 0..n as u64 {
+                counts.extend_from_slice(&(run + c).to_ne_bytes());
+            }
+        }
+
+        let totals = mapping.merge(&counts).unwrap();
+        assert_eq!(totals, (0..n as u64).map(|c| 2 * c + 1).collect::<Vec<_>>());
+
+        assert!(mapping.merge(&counts[1..]).is_err());
+    }
+
+    #[test]
+    fn report_lines() {
+        let mapping = mapping();
+
+        // Every block ran once, except for the blocks which contain `return 1`
+        let totals: Vec<u64> = mapping
+            .counters
+            .iter()
+            .map(|c| if c.start_line == 4 { 0 } else { 1 })
+            .collect();
+
+        let report = mapping
+            .report(&totals, |file| {
+                assert_eq!(file, "/test");
+                Ok(TEXT.into())
+            })
+            .unwrap();
+
+        let lines: Vec<_> = report.lines().collect();
+        assert_eq!(lines[0], "/test");
+        assert_eq!(lines[4], "    #####:    4:        return 1;");
+        assert_eq!(lines[6], "        1:    6:    return 0;");
+    }
+}
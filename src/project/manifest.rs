@@ -78,9 +78,9 @@ impl Manifest {
         Ok(Import { structs, funcs })
     }
 
-    /// Loads a manifest from the given file.
-    pub fn read(file: &mut std::fs::File) -> Result<Manifest, serde_yaml::Error> {
-        let manifest: Manifest = serde_yaml::from_reader(file)?;
+    /// Loads a manifest from the given reader, e.g. a file or an in-memory buffer.
+    pub fn read<R: std::io::Read>(reader: &mut R) -> Result<Manifest, serde_yaml::Error> {
+        let manifest: Manifest = serde_yaml::from_reader(reader)?;
         Ok(manifest)
     }
 
@@ -152,6 +152,11 @@ struct ManifestStructDef {
     name: String,
     canon_path: String,
     fields: Vec<(String, ManifestType)>,
+
+    /// The fields which are not `pub`.  Manifests written before fields could be `pub`
+    /// do not have this list, and every field of their structures can be used.
+    #[serde(default)]
+    private_fields: Vec<String>,
 }
 
 impl ManifestStructDef {
@@ -171,11 +176,18 @@ impl ManifestStructDef {
                 name.and_then(|name| fty.map(|fty| (name, fty)))
             })
             .collect::<Result<Vec<_>, ManifestError>>()?;
+        let private_fields = sd
+            .get_fields()
+            .iter()
+            .filter(|f| !sd.is_public(f.name))
+            .map(|f| st.get(f.name))
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok(ManifestStructDef {
             name,
             canon_path,
             fields,
+            private_fields,
         })
     }
 
@@ -187,7 +199,13 @@ impl ManifestStructDef {
             .map(|(fnm, fty)| Ok((st.insert(fnm.into()), fty.to_ty(st)?)))
             .collect::<Result<Vec<_>, ManifestError>>()?;
 
-        Ok(ImportStructDef::new(canon_path, fields))
+        let private_fields = self
+            .private_fields
+            .iter()
+            .map(|f| st.insert(f.into()))
+            .collect();
+
+        Ok(ImportStructDef::new(canon_path, fields).with_private_fields(private_fields))
     }
 }
 
@@ -202,10 +220,17 @@ enum ManifestType {
     I16,
     I32,
     I64,
+    USize,
+    ISize,
     F64,
     Bool,
     StringLiteral,
+    String,
     Array(Box<ManifestType>, usize),
+    Slice(Box<ManifestType>),
+    Range(Box<ManifestType>),
+    Option(Box<ManifestType>),
+    Result(Box<ManifestType>, Box<ManifestType>),
     Unit,
     Custom(String),
 }
@@ -221,10 +246,20 @@ impl ManifestType {
             Type::I16 => Self::I16,
             Type::I32 => Self::I32,
             Type::I64 => Self::I64,
+            Type::USize => Self::USize,
+            Type::ISize => Self::ISize,
             Type::F64 => Self::F64,
             Type::Bool => Self::Bool,
             Type::StringLiteral => Self::StringLiteral,
+            Type::String => Self::String,
             Type::Array(el_ty, sz) => Self::Array(Box::new(Self::from_ty(sm, st, el_ty)?), *sz),
+            Type::Slice(el_ty) => Self::Slice(Box::new(Self::from_ty(sm, st, el_ty)?)),
+            Type::Range(el_ty) => Self::Range(Box::new(Self::from_ty(sm, st, el_ty)?)),
+            Type::Option(el_ty) => Self::Option(Box::new(Self::from_ty(sm, st, el_ty)?)),
+            Type::Result(ty, err_ty) => Self::Result(
+                Box::new(Self::from_ty(sm, st, ty)?),
+                Box::new(Self::from_ty(sm, st, err_ty)?),
+            ),
             Type::Unit => Self::Unit,
             Type::Custom(p) => Self::Custom(path_to_string(sm, st, p)?),
             _ => return Err(ManifestError::CannotConvertType(ty.clone())),
@@ -243,10 +278,19 @@ impl ManifestType {
             ManifestType::I16 => Type::I16,
             ManifestType::I32 => Type::I32,
             ManifestType::I64 => Type::I64,
+            ManifestType::USize => Type::USize,
+            ManifestType::ISize => Type::ISize,
             ManifestType::F64 => Type::F64,
             ManifestType::Bool => Type::Bool,
             ManifestType::StringLiteral => Type::StringLiteral,
+            ManifestType::String => Type::String,
             ManifestType::Array(el_ty, sz) => Type::Array(Box::new(el_ty.to_ty(st)?), *sz),
+            ManifestType::Slice(el_ty) => Type::Slice(Box::new(el_ty.to_ty(st)?)),
+            ManifestType::Range(el_ty) => Type::Range(Box::new(el_ty.to_ty(st)?)),
+            ManifestType::Option(el_ty) => Type::Option(Box::new(el_ty.to_ty(st)?)),
+            ManifestType::Result(ty, err_ty) => {
+                Type::Result(Box::new(ty.to_ty(st)?), Box::new(err_ty.to_ty(st)?))
+            }
             ManifestType::Unit => Type::Unit,
             ManifestType::Custom(p) => Type::Custom(string_to_path(st, p)?),
         };
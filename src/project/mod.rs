@@ -1,8 +1,15 @@
+pub mod archive;
+pub mod ast_dump;
+pub mod coverage;
 pub mod manifest;
 pub mod project;
+pub mod symbols;
 
+pub use ast_dump::AstDump;
+pub use coverage::CoverageMapping;
 pub use manifest::Manifest;
 pub use project::*;
+pub use symbols::SymbolList;
 
 use crate::{
     compiler::{ast::Type, CompilerDisplay, CompilerDisplayError, SourceError, SourceMap},
@@ -1,12 +1,20 @@
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
-use crate::io::get_files;
+use crate::compiler::ast::{Context, Node};
+
+use crate::io::{get_files, FileKind, FileProvider};
 use crate::{
     compiler::{
         ast::Module,
         diagnostics::Logger,
+        format_span,
+        fuel::Fuel,
         lexer::{tokens::Token, LexerError},
-        parser::{Parser, ParserContext, ParserError},
+        parser::{
+            apply_link_names, expand_derives, expand_macros, number_nodes, Parser, ParserContext,
+            ParserError,
+        },
         CompilerDisplay, CompilerDisplayError, CompilerError, Source, SourceMap, SourceMapError,
         Span,
     },
@@ -18,7 +26,10 @@ pub enum ProjectError {
     NoAstGenerated,
     InvalidPath,
     ParserError(ParserError),
-    EmptyProject,
+
+    /// Two source files define a module with the same path.  Stores the name of the
+    /// module and the spans of the two definitions.
+    DuplicateModule(StringId, Span, Span),
 }
 
 impl From<CompilerError<ParserError>> for CompilerError<ProjectError> {
@@ -34,17 +45,54 @@ impl CompilerDisplay for ProjectError {
             ProjectError::NoAstGenerated => Ok("No AST Generated by Parser".into()),
             ProjectError::InvalidPath => Ok("Invalid compilation unit: path was empty".into()),
             ProjectError::ParserError(pe) => pe.fmt(sm, st),
-            ProjectError::EmptyProject => Ok("No source code in project".into()),
+            ProjectError::DuplicateModule(name, first, second) => {
+                let first_file = sm.files(*first);
+                let second_file = sm.files(*second);
+                if first_file == second_file {
+                    Ok(format!(
+                        "Module {} is defined twice in {:?}",
+                        st.get(*name)?,
+                        first_file
+                            .first()
+                            .ok_or(CompilerDisplayError::StringIdNotFound)?
+                    ))
+                } else {
+                    Ok(format!(
+                        "Module {} is defined in both {:?} and {:?}",
+                        st.get(*name)?,
+                        first_file
+                            .first()
+                            .ok_or(CompilerDisplayError::StringIdNotFound)?,
+                        second_file
+                            .first()
+                            .ok_or(CompilerDisplayError::StringIdNotFound)?
+                    ))
+                }
+            }
         }
     }
 }
 
+/// The input path which means that the source code is read from stdin
+pub const STDIN_PATH: &str = "-";
+
+/// The name of the project, and of its only module, when the source code is read from stdin
+pub const STDIN_PROJECT_NAME: &str = "stdin";
+
+/// Returns true if the given input path means that the source code is read from stdin
+pub fn is_stdin(src: &Path) -> bool {
+    src == Path::new(STDIN_PATH)
+}
+
 /// Given the path to a source, return the name that should be used
 /// for the project.
 /// If the path is a file, then return the file name (without extension)
 /// If the path is a directory, then return the name of the directory
-pub fn get_project_name(src: &Path) -> Result<&str, String> {
-    if src.is_file() || src.is_dir() {
+/// If the path is `-`, then the source is read from stdin and the project is named `stdin`
+pub fn get_project_name<'a>(files: &dyn FileProvider, src: &'a Path) -> Result<&'a str, String> {
+    if is_stdin(src) {
+        Ok(STDIN_PROJECT_NAME)
+    } else if files.kind(src).is_ok() {
         src.file_stem()
             .and_then(|name| name.to_str())
             .ok_or_else(|| "Could not extract name from given path".into())
@@ -53,6 +101,24 @@ pub fn get_project_name(src: &Path) -> Result<&str, String> {
     }
 }
 
+/// Converts the name of a project into the name which is used for the files that are
+/// generated when it is compiled.  Every character other than an ASCII letter, digit,
+/// `_`, or `-` is replaced with `_`, so that the name is valid on any filesystem, and
+/// letters are lowercased, so that two projects whose names differ only by case write
+/// to the same files on case sensitive and case insensitive filesystems alike.
+pub fn output_file_stem(project_name: &str) -> String {
+    project_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
 fn create_module_path<'a>(
     module: &'a mut Module<ParserContext>,
     path: &[StringId],
@@ -83,24 +149,60 @@ type Project<T> = Vec<CompilationUnit<T>>;
 pub struct CompilationUnit<T> {
     path: Vec<String>,
     data: T,
+
+    /// The span of the source file which this unit was read from
+    span: Span,
+}
+
+impl<T> CompilationUnit<T> {
+    /// Returns the contents of this unit (e.g. its tokens)
+    pub fn data(&self) -> &T {
+        &self.data
+    }
 }
 
 /// Given the location of source file(s) this function will read the file
 /// or files and construct the [`SourceMap`] for the project.
 ///
-/// If `src_path` is a directory, this will recursively read every file in
-/// that directory and its subdirectories.  If it is a file, it will read
-/// only that file.
+/// If a path in `src_paths` is a directory, this will recursively read every
+/// file in that directory and its subdirectories.  If it is a file, it will read
+/// only that file.  If it is `-`, then it will read the source code from stdin.  The
+/// files of each path are added in the order that the paths are given.
+///
+/// Every file is read through `files`, and the source map holds the text of each file.
+/// If the same file is reached through two paths, then this will return
+/// [`SourceMapError::DuplicateFile`].
 pub fn build_source_map(
-    src_path: &std::path::Path,
+    files: &dyn FileProvider,
+    src_paths: &[&std::path::Path],
     ext: &str,
 ) -> Result<SourceMap, SourceMapError> {
     let mut sm = SourceMap::new();
 
-    let mut files = get_files(src_path, ext)?;
-    files.sort(); // simplifies testing across platforms by making the source map consistent
-    for file in files {
-        sm.add_file(file)?;
+    // The canonical path of every file which has been added, with the path it was added by
+    let mut added: Vec<(PathBuf, PathBuf)> = vec![];
+
+    for src_path in src_paths {
+        if is_stdin(src_path) {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text)?;
+            sm.add_string(&text, format!("{}.{}", STDIN_PROJECT_NAME, ext).into())?;
+            continue;
+        }
+
+        let mut paths = get_files(files, src_path, ext)?;
+        paths.sort(); // simplifies testing across platforms by making the source map consistent
+        for path in paths {
+            let canonical = files.canonicalize(&path)?;
+            if let Some((_, first)) = added.iter().find(|(c, _)| *c == canonical) {
+                return Err(SourceMapError::DuplicateFile(first.clone(), path));
+            }
+
+            let text = String::from_utf8(files.read(&path)?)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+            sm.add_string(&text, path.clone())?;
+            added.push((canonical, path));
+        }
     }
 
     Ok(sm)
@@ -116,32 +218,53 @@ pub fn parse_project(
     source_map: &SourceMap,
     string_table: &StringTable,
     logger: &Logger,
+    fuel: &Fuel,
 ) -> Result<Module<ParserContext>, Vec<CompilerError<ProjectError>>> {
-    // The root module spans the entire source code space
-    let root_span = source_map
-        .span()
-        .ok_or_else(|| vec![CompilerError::new(Span::zero(), ProjectError::EmptyProject)])?;
+    // The root module spans the entire source code space, which is empty if every
+    // source file is empty
+    let root_span = source_map.span().unwrap_or_else(Span::zero);
 
     let mut root = Module::new(root_module, ParserContext::new(root_span));
     let mut errors = vec![];
     for src_tokens in token_sets {
-        match parse_src_tokens(src_tokens, string_table, logger) {
-            Ok(ast) => append_module(string_table, &mut root, ast),
+        match parse_src_tokens(src_tokens, string_table, logger, fuel)
+            .and_then(|ast| append_module(string_table, &mut root, ast))
+        {
+            Ok(()) => (),
             Err(e) => errors.push(e),
         }
     }
-    if errors.is_empty() {
-        Ok(root)
-    } else {
-        Err(errors)
+    if !errors.is_empty() {
+        return Err(errors);
     }
+
+    // Macros are expanded first so that a derived routine never contains a macro call.
+    // Routines generated for `#[derive]` attributes are numbered like any other node
+    expand_macros(&mut root, string_table, logger, fuel).map_err(|e| vec![e.into()])?;
+    expand_derives(&mut root, string_table).map_err(|e| vec![e.into()])?;
+    apply_link_names(&mut root, string_table).map_err(|e| vec![e.into()])?;
+    Ok(number_nodes(&root))
+}
+
+/// Returns true if neither the given module nor any of its submodules defines an item,
+/// e.g. because every source file is empty or contains only whitespace and comments.
+pub fn is_empty_project<M: Context>(m: &Module<M>) -> bool {
+    m.get_functions().is_empty()
+        && m.get_coroutines().is_empty()
+        && m.get_structs().is_empty()
+        && m.get_externs().is_empty()
+        && m.get_modules().iter().all(is_empty_project)
 }
 
 /// For each compilation unit in the [`SourceMap`], tokenize, and add to a vector
 /// of tokenized compilation units.
+///
+/// The module path of each unit is derived from the location of its file relative to
+/// the input in `src_paths` which contains the file.
 pub fn tokenize_source_map(
+    files: &dyn FileProvider,
     sourcemap: &SourceMap,
-    src_path: &std::path::Path,
+    src_paths: &[&std::path::Path],
     string_table: &StringTable,
     logger: &Logger,
 ) -> Result<Vec<CompilationUnit<Vec<Token>>>, Vec<CompilerError<LexerError>>> {
@@ -151,13 +274,21 @@ pub fn tokenize_source_map(
     for idx in 0..sourcemap.len() {
         let entry = sourcemap.get(idx).unwrap();
 
-        // Derive the logical path within the project
-        let module_path = file_path_to_module_path(entry.path(), src_path);
+        // Derive the logical path within the project, from the innermost input that
+        // contains the file
+        let base = src_paths
+            .iter()
+            .map(|p| input_base(files, p))
+            .filter(|base| entry.path().starts_with(base))
+            .max_by_key(|base| base.components().count())
+            .expect("Source file is not in any of the project's inputs");
+        let module_path = file_path_to_module_path(entry.path(), base);
 
         // Create a compilation Unit from the SourceCharIter
         let src = CompilationUnit {
             path: module_path,
             data: entry.read().unwrap(),
+            span: entry.span(),
         };
 
         // Get the Token Set and add to the Vector of token sets
@@ -168,6 +299,28 @@ pub fn tokenize_source_map(
     Ok(project_token_sets)
 }
 
+/// Renders the tokens of every compilation unit with one token on each line, for
+/// `--emit tokens`.  The tokens of each unit follow a header with the unit's module
+/// path, and each token is prefixed by the line that it is on.
+pub fn print_tokens(
+    token_sets: &[CompilationUnit<Vec<Token>>],
+    sm: &SourceMap,
+    st: &StringTable,
+) -> Result<String, CompilerDisplayError> {
+    let mut text = String::new();
+    for unit in token_sets {
+        text.push_str(&format!("# {}\n", unit.path.join("::")));
+        for token in &unit.data {
+            text.push_str(&format!(
+                "{}: {}\n",
+                format_span(sm, token.span),
+                token.fmt(sm, st)?
+            ));
+        }
+    }
+    Ok(text)
+}
+
 /// Tokenizes a stream of unicode characters.
 fn tokenize_source(
     src: CompilationUnit<Source>,
@@ -192,6 +345,7 @@ fn tokenize_source(
         Ok(CompilationUnit {
             path: src.path,
             data: tokens,
+            span: src.span,
         })
     } else {
         let errors: Vec<_> = errors
@@ -216,14 +370,26 @@ fn parse_src_tokens(
     src_tokens: CompilationUnit<Vec<Token>>,
     string_table: &StringTable,
     logger: &Logger,
+    fuel: &Fuel,
 ) -> Result<CompilationUnit<Module<ParserContext>>, CompilerError<ProjectError>> {
-    let parser = Parser::new(logger);
+    let parser = Parser::new(logger).with_fuel(fuel);
     if let Some((name, parent_path)) = src_tokens.path.split_last() {
         let name = string_table.insert(name.into());
+
+        // A file which contains only whitespace and comments is an empty module
+        if src_tokens.data.is_empty() {
+            return Ok(CompilationUnit {
+                path: parent_path.to_owned(),
+                data: Module::new(name, ParserContext::new(src_tokens.span)),
+                span: src_tokens.span,
+            });
+        }
+
         match parser.parse(name, &src_tokens.data) {
             Ok(Some(ast)) => Ok(CompilationUnit {
                 path: parent_path.to_owned(),
                 data: ast,
+                span: src_tokens.span,
             }),
             Ok(None) => Err(CompilerError::new(
                 Span::zero(),
@@ -243,7 +409,7 @@ fn append_module(
     string_table: &StringTable,
     root: &mut Module<ParserContext>,
     src_ast: CompilationUnit<Module<ParserContext>>,
-) {
+) -> Result<(), CompilerError<ProjectError>> {
     let parent = if src_ast.path.is_empty() {
         root
     } else {
@@ -254,18 +420,74 @@ fn append_module(
             .collect();
         create_module_path(root, &path).unwrap()
     };
-    parent.add_module(src_ast.data);
+    add_module(parent, src_ast.data)
+}
+
+/// Adds `module` as a child of `parent`.
+///
+/// A module which was created to hold the files in a directory (see [`create_module_path`])
+/// is merged with a module of the same name that is defined by a source file, so that
+/// `foo.br` and `foo/bar.br` define `foo` and `foo::bar`.  Any other module which has the
+/// same name as an existing child of `parent` is a duplicate.
+fn add_module(
+    parent: &mut Module<ParserContext>,
+    module: Module<ParserContext>,
+) -> Result<(), CompilerError<ProjectError>> {
+    let existing = match parent.get_module_mut(module.get_name()) {
+        Some(existing) => existing,
+        None => {
+            parent.add_module(module);
+            return Ok(());
+        }
+    };
+
+    let (target, directory) = if is_directory_module(existing) {
+        let directory = std::mem::replace(existing, module);
+        (existing, directory)
+    } else if is_directory_module(&module) {
+        (existing, module)
+    } else {
+        return Err(CompilerError::new(
+            module.context().span(),
+            ProjectError::DuplicateModule(
+                module.get_name(),
+                existing.context().span(),
+                module.context().span(),
+            ),
+        )
+        .with_note(existing.context().span(), "first defined here"));
+    };
+
+    for sub in directory.get_modules().iter().cloned() {
+        add_module(target, sub)?;
+    }
+
+    Ok(())
 }
 
-fn file_path_to_module_path(file: &Path, src_path: &Path) -> Vec<String> {
-    let base = if src_path.is_dir() {
+/// Returns true if this module was created to hold the files of a directory, rather than
+/// being defined in source code.
+fn is_directory_module(m: &Module<ParserContext>) -> bool {
+    m.context().span() == Span::zero()
+}
+
+/// Returns the directory which the module paths of the files in the given input are
+/// relative to: the input itself if it is a directory, otherwise the directory which
+/// contains it.
+fn input_base<'a>(files: &dyn FileProvider, src_path: &'a Path) -> &'a Path {
+    if is_stdin(src_path) {
+        // The source read from stdin is named as if it were a file in the working directory
+        Path::new("")
+    } else if matches!(files.kind(src_path), Ok(FileKind::Dir)) {
         src_path
     } else {
         src_path
             .parent()
             .expect("Given a file which is also the root of the directory structure.")
-    };
+    }
+}
 
+fn file_path_to_module_path(file: &Path, base: &Path) -> Vec<String> {
     let rel_path = file.strip_prefix(&base).unwrap();
 
     let mut p: Vec<String> = rel_path
@@ -283,3 +505,338 @@ fn truncate_extension(path: &mut Vec<String>, ext: &str) {
         _ => (),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::compiler::semantics::semanticnode::SemanticAst;
+    use crate::io::{FsProvider, MemoryProvider};
+
+    use super::*;
+
+    /// Builds a project from the given (file path, source) pairs, which are relative
+    /// to the `/proj` directory.
+    fn parse_files(
+        files: &[(&str, &str)],
+    ) -> (
+        Result<Module<ParserContext>, Vec<CompilerError<ProjectError>>>,
+        SourceMap,
+        StringTable,
+    ) {
+        let mut sm = SourceMap::new();
+        for (path, text) in files {
+            sm.add_string(text, PathBuf::from("/proj").join(path))
+                .unwrap();
+        }
+
+        let table = StringTable::new();
+        let logger = Logger::new();
+        let tokens = tokenize_source_map(
+            &FsProvider,
+            &sm,
+            &[Path::new("/proj/main.br")],
+            &table,
+            &logger,
+        )
+        .unwrap();
+        let root = table.insert("proj".into());
+        let result = parse_project(root, tokens, &sm, &table, &logger, &Fuel::unlimited());
+        (result, sm, table)
+    }
+
+    #[test]
+    fn test_file_and_directory_with_same_name_are_merged() {
+        let (result, _, table) = parse_files(&[
+            ("foo/bar.br", "fn b() {return;}"),
+            ("foo.br", "fn a() {return;}"),
+        ]);
+
+        let root = result.unwrap();
+        assert_eq!(root.get_modules().len(), 1);
+
+        let foo = root.get_module(table.insert("foo".into())).unwrap();
+        assert!(foo.get_item(table.insert("a".into())).is_some());
+
+        let bar = foo.get_module(table.insert("bar".into())).unwrap();
+        assert!(bar.get_item(table.insert("b".into())).is_some());
+    }
+
+    #[test]
+    fn test_duplicate_module() {
+        let (result, sm, table) = parse_files(&[
+            ("a/b.br", "fn f() {return;}"),
+            ("a.br", "mod b {\n fn g() {return;}\n}"),
+        ]);
+
+        let errs = result.unwrap_err();
+        let msgs: Vec<_> = errs.iter().map(|e| e.fmt(&sm, &table).unwrap()).collect();
+        assert_eq!(
+            msgs,
+            vec![
+                "L1: Module b is defined in both \"/proj/a.br\" and \"/proj/a/b.br\"\n    L1-3: note: first defined here"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stdin_input() {
+        let stdin = Path::new(STDIN_PATH);
+        assert_eq!(get_project_name(&FsProvider, stdin), Ok(STDIN_PROJECT_NAME));
+
+        // Source read from stdin is added to the source map as it is by `build_source_map`
+        let mut sm = SourceMap::new();
+        sm.add_string("fn my_main() -> i64 {return 0;}", "stdin.br".into())
+            .unwrap();
+        let table = StringTable::new();
+        let logger = Logger::new();
+        let tokens = tokenize_source_map(&FsProvider, &sm, &[stdin], &table, &logger).unwrap();
+
+        let paths: Vec<_> = tokens.iter().map(|u| u.path.join("/")).collect();
+        assert_eq!(paths, vec![STDIN_PROJECT_NAME]);
+    }
+
+    #[test]
+    fn test_multiple_inputs() {
+        let dir = std::env::temp_dir().join(format!("bramble-inputs-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lib/io")).unwrap();
+        std::fs::write(dir.join("main.br"), "fn my_main() -> i64 {return 0;}").unwrap();
+        std::fs::write(dir.join("lib/io/write.br"), "fn write() {return;}").unwrap();
+        let main = dir.join("main.br");
+        let lib = dir.join("lib");
+
+        let sm = build_source_map(&FsProvider, &[&main, &lib], "br").unwrap();
+        let table = StringTable::new();
+        let logger = Logger::new();
+        let tokens =
+            tokenize_source_map(&FsProvider, &sm, &[&main, &lib], &table, &logger).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Each file's module mirrors its location within the input that contains it
+        let paths: Vec<_> = tokens.iter().map(|u| u.path.join("/")).collect();
+        assert_eq!(paths, vec!["main", "io/write"]);
+
+        let root = parse_project(
+            table.insert("main".into()),
+            tokens,
+            &sm,
+            &table,
+            &logger,
+            &Fuel::unlimited(),
+        )
+        .unwrap();
+        let main = root.get_module(table.insert("main".into())).unwrap();
+        assert!(main.get_item(table.insert("my_main".into())).is_some());
+        let write = root
+            .get_module(table.insert("io".into()))
+            .and_then(|io| io.get_module(table.insert("write".into())))
+            .unwrap();
+        assert!(write.get_item(table.insert("write".into())).is_some());
+    }
+
+    #[test]
+    fn test_in_memory_inputs() {
+        let mut files = MemoryProvider::new();
+        files.add_file("/proj/main.br", "fn my_main() -> i64 {return 0;}");
+        files.add_file("/proj/io/write.br", "fn write() {return;}");
+        files.add_file("/proj/notes.txt", "not source code");
+        let proj = Path::new("/proj");
+
+        assert_eq!(get_project_name(&files, proj), Ok("proj"));
+        assert!(get_project_name(&files, Path::new("/missing")).is_err());
+
+        // Nothing is read from the filesystem, where none of these files exist
+        let sm = build_source_map(&files, &[proj], "br").unwrap();
+        let paths: Vec<_> = (0..sm.len())
+            .map(|i| sm.get(i).unwrap().path().clone())
+            .collect();
+        assert_eq!(paths, vec![proj.join("io/write.br"), proj.join("main.br")]);
+
+        let table = StringTable::new();
+        let logger = Logger::new();
+        let tokens = tokenize_source_map(&files, &sm, &[proj], &table, &logger).unwrap();
+        let paths: Vec<_> = tokens.iter().map(|u| u.path.join("/")).collect();
+        assert_eq!(paths, vec!["io/write", "main"]);
+
+        let root = parse_project(
+            table.insert("proj".into()),
+            tokens,
+            &sm,
+            &table,
+            &logger,
+            &Fuel::unlimited(),
+        )
+        .unwrap();
+        let main = root.get_module(table.insert("main".into())).unwrap();
+        assert!(main.get_item(table.insert("my_main".into())).is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_source_file_included_twice() {
+        let dir = std::env::temp_dir().join(format!("bramble-dup-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub/a.br"), "fn a() {return;}").unwrap();
+        std::os::unix::fs::symlink(dir.join("sub/a.br"), dir.join("b.br")).unwrap();
+
+        let result = build_source_map(&FsProvider, &[&dir], "br");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        match result {
+            Err(SourceMapError::DuplicateFile(first, second)) => {
+                assert_eq!(first, dir.join("b.br"));
+                assert_eq!(second, dir.join("sub/a.br"));
+            }
+            _ => panic!("Expected a duplicate file error"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_directory_cycle() {
+        let dir = std::env::temp_dir().join(format!("bramble-cycle-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub/a.br"), "fn a() {return;}").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("sub/loop")).unwrap();
+        let canonical = dir.canonicalize().unwrap();
+
+        let result = build_source_map(&FsProvider, &[&dir], "br");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        match result {
+            Err(SourceMapError::DirectoryCycle(link, target)) => {
+                assert_eq!(link, dir.join("sub/loop"));
+                assert_eq!(target, canonical);
+            }
+            _ => panic!("Expected a directory cycle error"),
+        }
+    }
+
+    #[test]
+    fn test_empty_files_are_empty_modules() {
+        for text in ["", "  \n\t\r\n", "// Nothing here yet\n", "\u{feff}"] {
+            let (result, _, table) = parse_files(&[("main.br", text), ("main/inner.br", text)]);
+            let root = result.unwrap();
+
+            assert!(is_empty_project(&root), "{:?}", text);
+            let main = root.get_module(table.insert("main".into())).unwrap();
+            assert!(main.get_module(table.insert("inner".into())).is_some());
+        }
+    }
+
+    #[test]
+    fn test_project_with_empty_file() {
+        let (result, _, table) = parse_files(&[
+            ("main.br", "fn my_main() -> i64 {\r\n    return 0;\r\n}\r\n"),
+            ("main/inner.br", ""),
+        ]);
+        let root = result.unwrap();
+
+        assert!(!is_empty_project(&root));
+        let main = root.get_module(table.insert("main".into())).unwrap();
+        assert!(main.get_item(table.insert("my_main".into())).is_some());
+    }
+
+    #[test]
+    fn test_file_with_byte_order_mark_and_crlf() {
+        let (result, sm, table) = parse_files(&[(
+            "main.br",
+            "\u{feff}fn my_main() -> i64 {\r\n    return 0;\r\n}\r\nfn f() -> i64 {\r\n    return;\r\n}\r\n",
+        )]);
+        let root = result.unwrap();
+        let main = root.get_module(table.insert("main".into())).unwrap();
+        let f = main.get_item(table.insert("f".into())).unwrap();
+
+        assert_eq!(format_span(&sm, f.context().span()), "L4-6");
+    }
+
+    #[test]
+    fn test_node_ids() {
+        let files = [
+            (
+                "main.br",
+                "fn my_main() -> i64 {\n    let x: i64 := 1;\n    return x + 2;\n}\n",
+            ),
+            (
+                "main/inner.br",
+                "struct S { a: i64 }\nfn f(s: S) -> i64 { return s.a; }\n",
+            ),
+        ];
+        let (result, _, _) = parse_files(&files);
+        let root = result.unwrap();
+        let ids: Vec<_> = root.iter_preorder().map(|n| n.context().id()).collect();
+
+        // Every node has a unique, non-zero ID
+        let mut unique = ids.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), ids.len());
+        assert!(!ids.contains(&0));
+
+        // Parsing the same source again assigns the same IDs
+        let (again, _, _) = parse_files(&files);
+        let again: Vec<_> = again
+            .unwrap()
+            .iter_preorder()
+            .map(|n| n.context().id())
+            .collect();
+        assert_eq!(again, ids);
+
+        // Semantic nodes keep the IDs of the parser nodes they were created from
+        let sm_ast = SemanticAst::new().from_module(&root);
+        let sm_ids: Vec<_> = sm_ast.iter_preorder().map(|n| n.context().id()).collect();
+        assert_eq!(sm_ids, ids);
+    }
+
+    #[test]
+    fn test_print_tokens() {
+        let mut sm = SourceMap::new();
+        sm.add_string("fn f() {\n    return;\n}", PathBuf::from("/proj/main.br"))
+            .unwrap();
+        sm.add_string("struct S {}", PathBuf::from("/proj/main/inner.br"))
+            .unwrap();
+
+        let table = StringTable::new();
+        let logger = Logger::new();
+        let tokens = tokenize_source_map(
+            &FsProvider,
+            &sm,
+            &[Path::new("/proj/main.br")],
+            &table,
+            &logger,
+        )
+        .unwrap();
+
+        assert_eq!(
+            print_tokens(&tokens, &sm, &table).unwrap(),
+            "# main
+L1: fn
+L1: identifier f
+L1: (
+L1: )
+L1: {
+L2: return
+L2: ;
+L3: }
+# main::inner
+L1: struct
+L1: identifier S
+L1: {
+L1: }
+"
+        );
+    }
+
+    #[test]
+    fn test_output_file_stem() {
+        for (name, expected) in [
+            ("main", "main"),
+            ("my_project-2", "my_project-2"),
+            ("MyProject", "myproject"),
+            ("my project.v2", "my_project_v2"),
+            ("caf\u{e9}", "caf_"),
+        ] {
+            assert_eq!(output_file_stem(name), expected, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use serde::Serialize;
+
+use crate::{
+    compiler::{
+        ast::{Context, Element, Module, Node, Path, Type},
+        semantics::semanticnode::SemanticContext,
+        CompilerDisplay, SourceMap, Span,
+    },
+    StringTable,
+};
+
+use super::ManifestError;
+
+/// Lists every item which is defined in a project after semantic analysis. This is
+/// written as JSON by `--emit symbols` so that other tools (e.g. editors) can find the
+/// items of a project without having to parse Bramble.
+#[derive(Serialize, Debug)]
+pub struct SymbolList {
+    symbols: Vec<SymbolEntry>,
+}
+
+impl SymbolList {
+    /// Lists the given module, every item defined in the module, and, recursively,
+    /// every submodule.
+    pub fn extract(
+        module: &Module<SemanticContext>,
+        sm: &SourceMap,
+        st: &StringTable,
+    ) -> Result<Self, ManifestError> {
+        let mut symbols = vec![];
+        let path = vec![Element::CanonicalRoot, Element::Id(module.get_name())].into();
+        Self::extract_module(module, &path, sm, st, &mut symbols)?;
+        Ok(SymbolList { symbols })
+    }
+
+    fn extract_module(
+        module: &Module<SemanticContext>,
+        path: &Path,
+        sm: &SourceMap,
+        st: &StringTable,
+        symbols: &mut Vec<SymbolEntry>,
+    ) -> Result<(), ManifestError> {
+        symbols.push(SymbolEntry::new(
+            path,
+            module.context().id(),
+            SymbolKind::Module,
+            None,
+            module.context().span(),
+            sm,
+            st,
+        )?);
+
+        for s in module.context().sym().table() {
+            let mut item_path = path.clone();
+            item_path.push(Element::Id(s.name));
+
+            let kind = match s.ty {
+                Type::FunctionDef(..) => SymbolKind::Function,
+                Type::CoroutineDef(..) => SymbolKind::Coroutine,
+                Type::StructDef(..) => SymbolKind::Struct,
+                Type::ExternDecl(..) => SymbolKind::Extern,
+                _ => return Err(ManifestError::CannotConvertType(s.ty.clone())),
+            };
+
+            // Every item in a module's symbol table was added with the span of its
+            // definition
+            let span = s.span.expect("Item has no span");
+            let id = module
+                .get_item(s.name)
+                .map(|item| item.context().id())
+                .expect("Item is not defined in its module");
+            symbols.push(SymbolEntry::new(
+                &item_path,
+                id,
+                kind,
+                Some(&s.ty),
+                span,
+                sm,
+                st,
+            )?);
+        }
+
+        for m in module.get_modules() {
+            let mut sub_path = path.clone();
+            sub_path.push(Element::Id(m.get_name()));
+            Self::extract_module(m, &sub_path, sm, st, symbols)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the list of symbols to the given file as JSON
+    pub fn write(&self, file: &mut std::fs::File) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(file, self)
+    }
+}
+
+/// A single item from a project.
+#[derive(Serialize, Debug, PartialEq)]
+struct SymbolEntry {
+    path: String,
+
+    /// The ID of the AST node which defines the item
+    id: u32,
+    kind: SymbolKind,
+    #[serde(rename = "type")]
+    ty: Option<String>,
+    span: SymbolSpan,
+    visibility: Visibility,
+}
+
+impl SymbolEntry {
+    fn new(
+        path: &Path,
+        id: u32,
+        kind: SymbolKind,
+        ty: Option<&Type>,
+        span: Span,
+        sm: &SourceMap,
+        st: &StringTable,
+    ) -> Result<Self, ManifestError> {
+        Ok(SymbolEntry {
+            path: path.fmt(sm, st)?,
+            id,
+            kind,
+            ty: ty.map(|ty| ty.fmt(sm, st)).transpose()?,
+            span: SymbolSpan::new(span, sm),
+            visibility: Visibility::Public,
+        })
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum SymbolKind {
+    Module,
+    Function,
+    Coroutine,
+    Struct,
+    Extern,
+}
+
+/// Bramble does not have visibility modifiers, so every item can be referred to from
+/// any module.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Visibility {
+    Public,
+}
+
+/// Where an item or node is defined: the file, line, and column which it starts at
+/// and its global offsets.
+#[derive(Serialize, Debug, PartialEq)]
+pub(super) struct SymbolSpan {
+    file: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    low: u32,
+    high: u32,
+}
+
+impl SymbolSpan {
+    pub(super) fn new(span: Span, sm: &SourceMap) -> SymbolSpan {
+        let (file, line, column) = match sm.location(span.low()) {
+            Some((file, line, column)) => (
+                Some(file.to_string_lossy().into_owned()),
+                Some(line.as_u32()),
+                Some(column.as_u32()),
+            ),
+            None => (None, None, None),
+        };
+
+        SymbolSpan {
+            file,
+            line,
+            column,
+            low: span.low().as_u32(),
+            high: span.high().as_u32(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        compiler::{
+            ast::MAIN_MODULE,
+            diagnostics::Logger,
+            lexer::tokens::Token,
+            parser::{number_nodes, Parser},
+            semantics::type_resolver::resolve_types,
+            Lexer, SourceMap,
+        },
+        StringTable,
+    };
+
+    use super::SymbolList;
+
+    #[test]
+    fn extract_symbols() {
+        let text = "fn my_main() -> i64 {
+    return 0;
+}
+
+struct S {
+    a: i64,
+}
+
+extern fn puts(s: string) -> i32;
+
+mod inner {
+    fn id(n: i64) -> i64 {
+        return n;
+    }
+}
+";
+        let mut sm = SourceMap::new();
+        sm.add_string(text, "/test".into()).unwrap();
+        let src = sm.get(0).unwrap().read().unwrap();
+
+        let mut table = StringTable::new();
+        let main = table.insert("main".into());
+        let main_mod = table.insert(MAIN_MODULE.into());
+        let main_fn = table.insert("my_main".into());
+
+        let logger = Logger::new();
+        let tokens: Vec<Token> = Lexer::new(src, &mut table, &logger)
+            .unwrap()
+            .tokenize()
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let parser = Parser::new(&logger);
+        let ast = number_nodes(&parser.parse(main, &tokens).unwrap().unwrap());
+        let module = resolve_types(&ast, main_mod, main_fn, &table, &logger).unwrap();
+
+        let symbols = SymbolList::extract(&module, &sm, &table).unwrap();
+        let actual = serde_json::to_value(&symbols).unwrap();
+
+        assert_eq!(
+            actual,
+            json!({
+                "symbols": [
+                    {
+                        "path": "$main",
+                        "id": 1,
+                        "kind": "module",
+                        "type": null,
+                        "span": {"file": "/test", "line": 1, "column": 1, "low": 0, "high": 164},
+                        "visibility": "public",
+                    },
+                    {
+                        "path": "$main::my_main",
+                        "id": 7,
+                        "kind": "function",
+                        "type": "fn () -> i64",
+                        "span": {"file": "/test", "line": 1, "column": 1, "low": 0, "high": 37},
+                        "visibility": "public",
+                    },
+                    {
+                        "path": "$main::S",
+                        "id": 10,
+                        "kind": "struct",
+                        "type": "StructDef(a: i64)",
+                        "span": {"file": "/test", "line": 5, "column": 1, "low": 39, "high": 63},
+                        "visibility": "public",
+                    },
+                    {
+                        "path": "$main::puts",
+                        "id": 12,
+                        "kind": "extern",
+                        "type": "extern fn (string) -> i32",
+                        "span": {"file": "/test", "line": 9, "column": 1, "low": 65, "high": 98},
+                        "visibility": "public",
+                    },
+                    {
+                        "path": "$main::inner",
+                        "id": 2,
+                        "kind": "module",
+                        "type": null,
+                        "span": {"file": "/test", "line": 11, "column": 1, "low": 100, "high": 164},
+                        "visibility": "public",
+                    },
+                    {
+                        "path": "$main::inner::id",
+                        "id": 3,
+                        "kind": "function",
+                        "type": "fn (i64) -> i64",
+                        "span": {"file": "/test", "line": 12, "column": 5, "low": 116, "high": 162},
+                        "visibility": "public",
+                    },
+                ]
+            })
+        );
+    }
+}
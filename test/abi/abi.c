@@ -0,0 +1,121 @@
+/*
+    C functions which are linked with the ABI test.  These check that Bramble
+    functions can call, and be called by, code compiled by a C compiler.
+*/
+#include <stdbool.h>
+#include <stdint.h>
+
+/* Matches the layout of `project::callee::lib::Pair` */
+struct Pair {
+    int64_t a;
+    int32_t b;
+};
+
+/* Matches the layout of `project::callee::lib::Mixed` */
+struct Mixed {
+    uint8_t x;
+    double y;
+    int16_t z[3];
+};
+
+/* Bramble functions are named by their canonical path, joined with `_` */
+int64_t callee_lib_widths(uint8_t a, int8_t b, uint16_t c, int16_t d, uint32_t e, int32_t f,
+                          uint64_t g, int64_t h);
+double callee_lib_spill(int64_t a, double b, int64_t c, double d, int64_t e, double f,
+                        int64_t g, double h, int64_t i, double j, int64_t k, double l,
+                        int64_t m, double n, int64_t o, double p, int64_t q, double r);
+
+/* Structures are passed by pointer and returned through an out parameter, which is
+   the first parameter */
+void callee_lib_swap(struct Pair *out, const struct Pair *p);
+int64_t callee_lib_sum_pair(const struct Pair *p);
+
+/* The out parameter is the C ABI's hidden return pointer, so a structure which is too
+   large to be returned in registers can be returned by value */
+struct Mixed callee_lib_mixed(uint8_t x, double y);
+
+/* Narrow integers and bools are extended to a full register when passed or returned */
+int8_t callee_lib_negate_i8(int8_t a);
+int32_t callee_lib_widen_u16(uint16_t a);
+bool callee_lib_invert(bool b);
+
+int64_t abi_c_widths(uint8_t a, int8_t b, uint16_t c, int16_t d, uint32_t e, int32_t f,
+                     uint64_t g, int64_t h) {
+    return (int64_t)a + b + c + d + e + f + (int64_t)g + h;
+}
+
+double abi_c_spill(int64_t a, double b, int64_t c, double d, int64_t e, double f,
+                   int64_t g, double h, int64_t i, double j, int64_t k, double l,
+                   int64_t m, double n, int64_t o, double p, int64_t q, double r) {
+    int64_t ints = a + c + e + g + i + k + m + o + q;
+    return (double)ints + b + d + f + h + j + l + n + p + r;
+}
+
+int32_t abi_c_widen_i8(int8_t a) {
+    return a;
+}
+
+int32_t abi_c_widen_u16(uint16_t a) {
+    return a;
+}
+
+int8_t abi_c_negate_i8(int8_t a) {
+    return -a;
+}
+
+uint8_t abi_c_double_u8(uint8_t a) {
+    return a * 2;
+}
+
+int16_t abi_c_negate_i16(int16_t a) {
+    return -a;
+}
+
+bool abi_c_not(bool b) {
+    return !b;
+}
+
+/* Calls Bramble functions from C and returns the number of calls which returned the
+   wrong result */
+int64_t abi_c_call_bramble(void) {
+    int64_t failures = 0;
+
+    if (callee_lib_widths(1, -2, 3, -4, 5, -6, 7, -8) != -4) {
+        failures++;
+    }
+
+    if (callee_lib_spill(1, 0.5, 2, 0.5, 3, 0.5, 4, 0.5, 5, 0.5, 6, 0.5, 7, 0.5, 8, 0.5, 9,
+                         0.5) != 49.5) {
+        failures++;
+    }
+
+    struct Pair p = {10, 20};
+    struct Pair s = {0, 0};
+    callee_lib_swap(&s, &p);
+    if (s.a != 20 || s.b != 10) {
+        failures++;
+    }
+
+    if (callee_lib_sum_pair(&p) != 30) {
+        failures++;
+    }
+
+    struct Mixed m = callee_lib_mixed(7, 1.5);
+    if (m.x != 7 || m.y != 1.5 || m.z[0] != 1 || m.z[2] != 3) {
+        failures++;
+    }
+
+    if (callee_lib_negate_i8(5) != -5) {
+        failures++;
+    }
+
+    if (callee_lib_widen_u16(65535) != 65535) {
+        failures++;
+    }
+
+    if (callee_lib_invert(false) != true) {
+        failures++;
+    }
+
+    return failures;
+}
@@ -0,0 +1,78 @@
+#!/bin/sh
+
+#   Benchmark of the time taken to compile programs with many large expressions.
+#
+#   A synthetic program is generated with many functions which each declare variables,
+#   build structures, call routines, and cast values.  These are the expressions whose
+#   payloads are boxed to keep AST nodes small, so the type resolver, which copies nodes
+#   as it rebuilds the AST, has less to copy.  The program is compiled with the LLVM code
+#   generator and the time and peak memory (maximum resident set size) of the compiler are
+#   reported.
+#
+#   Usage: ./bench-ast-size.sh [functions] [statements per function]
+
+num_fns=${1:-2000}
+num_stms=${2:-32}
+
+build_dir=./target/bench
+target=release
+
+if [[ $OSTYPE == "darwin"* ]]; then
+	platform=machos
+	time_cmd="/usr/bin/time -l"
+else
+	platform=linux
+	time_cmd="/usr/bin/time -v"
+fi
+
+rm -rf ${build_dir}
+mkdir -p ${build_dir}/src
+
+# Generate the synthetic program
+src=${build_dir}/src/main.br
+
+echo "fn my_main() -> i64 {" >${src}
+echo "    return 0;" >>${src}
+echo "}" >>${src}
+echo "" >>${src}
+echo "struct Point {" >>${src}
+echo "    x: i64," >>${src}
+echo "    y: i64," >>${src}
+echo "}" >>${src}
+echo "" >>${src}
+echo "fn add(a: i64, b: i64) -> i64 {" >>${src}
+echo "    return a + b;" >>${src}
+echo "}" >>${src}
+echo "" >>${src}
+
+i=0
+while [ $i -lt $num_fns ]; do
+	echo "fn step${i}(n: i64) -> i64 {" >>${src}
+	echo "    let mut sum: i64 := n;" >>${src}
+	j=0
+	while [ $j -lt $num_stms ]; do
+		echo "    let p${j}: Point := Point{x: add(sum, ${j}), y: (${j}i32 as i64) * 2};" >>${src}
+		echo "    mut sum := add(p${j}.x, p${j}.y);" >>${src}
+		j=$((j + 1))
+	done
+	echo "    return sum;" >>${src}
+	echo "}" >>${src}
+	echo "" >>${src}
+	i=$((i + 1))
+done
+
+echo "Generated $num_fns functions with $num_stms statements ($(wc -c <${src}) bytes)"
+
+cargo build --release --bin bramblec || exit 1
+
+${time_cmd} ../target/${target}/bramblec --llvm -p ${platform} -i ${src} -o ${build_dir}/output.obj \
+	>${build_dir}/stdout 2>${build_dir}/time.log
+result=$?
+
+if [ $result -ne 0 ]; then
+	echo "Compilation failed:"
+	cat ${build_dir}/stdout
+	exit $result
+fi
+
+grep -E "Elapsed|Maximum resident|maximum resident" ${build_dir}/time.log
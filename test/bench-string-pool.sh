@@ -0,0 +1,68 @@
+#!/bin/sh
+
+#   Benchmark of the memory used to compile programs with many large string literals.
+#
+#   A synthetic program is generated with many functions which each contain several unique
+#   string literals.  The program is compiled with the AST code generator and the time and
+#   peak memory (maximum resident set size) of the compiler are reported.  String literals
+#   are added to the string pool as each function is generated, so peak memory should grow
+#   with the total size of the literals and not with a second copy of all of them.
+#
+#   Usage: ./bench-string-pool.sh [functions] [literals per function] [literal length]
+
+num_fns=${1:-2000}
+num_lits=${2:-8}
+lit_len=${3:-1024}
+
+build_dir=./target/bench
+target=release
+
+if [[ $OSTYPE == "darwin"* ]]; then
+	platform=machos
+	time_cmd="/usr/bin/time -l"
+else
+	platform=linux
+	time_cmd="/usr/bin/time -v"
+fi
+
+rm -rf ${build_dir}
+mkdir -p ${build_dir}/src
+
+# Generate the synthetic program
+src=${build_dir}/src/main.br
+padding=$(head -c ${lit_len} /dev/zero | tr '\0' 'x')
+
+echo "fn my_main() -> i64 {" >${src}
+echo "    return 0;" >>${src}
+echo "}" >>${src}
+echo "" >>${src}
+
+i=0
+while [ $i -lt $num_fns ]; do
+	echo "fn f${i}() -> string {" >>${src}
+	j=0
+	while [ $j -lt $num_lits ]; do
+		echo "    let s${j}: string := \"${i}_${j}_${padding}\";" >>${src}
+		j=$((j + 1))
+	done
+	echo "    return s0;" >>${src}
+	echo "}" >>${src}
+	echo "" >>${src}
+	i=$((i + 1))
+done
+
+echo "Generated $num_fns functions with $num_lits literals of $lit_len characters ($(wc -c <${src}) bytes)"
+
+cargo build --release --bin bramblec || exit 1
+
+${time_cmd} ../target/${target}/bramblec --llvm -p ${platform} -i ${src} -o ${build_dir}/output.obj \
+	>${build_dir}/stdout 2>${build_dir}/time.log
+result=$?
+
+if [ $result -ne 0 ]; then
+	echo "Compilation failed:"
+	cat ${build_dir}/stdout
+	exit $result
+fi
+
+grep -E "Elapsed|Maximum resident|maximum resident" ${build_dir}/time.log
@@ -0,0 +1,37 @@
+
+define i64 @array_at_array_at_my_main() {
+entry:
+  %result = call i64 @array_at_array_at_test(i64 1)
+  ret i64 %result
+}
+
+define i64 @array_at_array_at_test(i64 %0) {
+entry:
+  %i = alloca i64, align 8
+  store i64 %0, i64* %i, align 4
+  %1 = alloca [3 x i64], align 8
+  %2 = getelementptr [3 x i64], [3 x i64]* %1, i64 0, i64 0
+  store i64 1, i64* %2, align 4
+  %3 = getelementptr [3 x i64], [3 x i64]* %1, i64 0, i64 1
+  store i64 2, i64* %3, align 4
+  %4 = getelementptr [3 x i64], [3 x i64]* %1, i64 0, i64 2
+  store i64 3, i64* %4, align 4
+  %a = alloca [3 x i64], align 8
+  %5 = bitcast [3 x i64]* %a to i8*
+  %6 = bitcast [3 x i64]* %1 to i8*
+  call void @llvm.memcpy.p0i8.p0i8.i64(i8* align 8 %5, i8* align 8 %6, i64 mul nuw (i64 ptrtoint (i64* getelementptr (i64, i64* null, i32 1) to i64), i64 3), i1 false)
+  %i1 = load i64, i64* %i, align 4
+  %7 = getelementptr [3 x i64], [3 x i64]* %a, i64 0, i64 %i1
+  %8 = load i64, i64* %7, align 4
+  ret i64 %8
+}
+
+define i64 @main() {
+entry:
+  %user_main = call i64 @array_at_array_at_my_main()
+  ret i64 %user_main
+}
+
+; Function Attrs: argmemonly nounwind willreturn
+declare void @llvm.memcpy.p0i8.p0i8.i64(i8* noalias nocapture writeonly, i8* noalias nocapture readonly, i64, i1 immarg)
+
@@ -0,0 +1,37 @@
+
+define i64 @array_at_array_at_my_main() {
+BB0:
+  %_0 = alloca i64, align 8
+  %0 = call i64 @array_at_array_at_test(i64 1)
+  store i64 %0, i64* %_0, align 4
+  %1 = load i64, i64* %_0, align 4
+  ret i64 %1
+}
+
+define i64 @array_at_array_at_test(i64 %i) {
+BB0:
+  %a_0 = alloca [3 x i64], align 8
+  %_0 = alloca [3 x i64], align 8
+  %0 = getelementptr [3 x i64], [3 x i64]* %_0, i64 0, i64 0
+  store i64 1, i64* %0, align 4
+  %1 = getelementptr [3 x i64], [3 x i64]* %_0, i64 0, i64 1
+  store i64 2, i64* %1, align 4
+  %2 = getelementptr [3 x i64], [3 x i64]* %_0, i64 0, i64 2
+  store i64 3, i64* %2, align 4
+  %3 = bitcast [3 x i64]* %a_0 to i8*
+  %4 = bitcast [3 x i64]* %_0 to i8*
+  call void @llvm.memcpy.p0i8.p0i8.i64(i8* align 8 %3, i8* align 8 %4, i64 mul nuw (i64 ptrtoint (i64* getelementptr (i64, i64* null, i32 1) to i64), i64 3), i1 false)
+  %5 = getelementptr [3 x i64], [3 x i64]* %a_0, i64 0, i64 %i
+  %6 = load i64, i64* %5, align 4
+  ret i64 %6
+}
+
+; Function Attrs: argmemonly nounwind willreturn
+declare void @llvm.memcpy.p0i8.p0i8.i64(i8* noalias nocapture writeonly, i8* noalias nocapture readonly, i64, i1 immarg)
+
+define i64 @main() {
+entry:
+  %user_main = call i64 @array_at_array_at_my_main()
+  ret i64 %user_main
+}
+
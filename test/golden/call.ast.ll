@@ -0,0 +1,36 @@
+
+define i64 @call_call_my_main() {
+entry:
+  %result = call i64 @call_call_test(i64 1, i64 2)
+  ret i64 %result
+}
+
+define i64 @call_call_test(i64 %0, i64 %1) {
+entry:
+  %a = alloca i64, align 8
+  store i64 %0, i64* %a, align 4
+  %b = alloca i64, align 8
+  store i64 %1, i64* %b, align 4
+  %a1 = load i64, i64* %a, align 4
+  %b2 = load i64, i64* %b, align 4
+  %result = call i64 @call_call_add(i64 %a1, i64 %b2)
+  ret i64 %result
+}
+
+define i64 @call_call_add(i64 %0, i64 %1) {
+entry:
+  %a = alloca i64, align 8
+  store i64 %0, i64* %a, align 4
+  %b = alloca i64, align 8
+  store i64 %1, i64* %b, align 4
+  %a1 = load i64, i64* %a, align 4
+  %b2 = load i64, i64* %b, align 4
+  %2 = add i64 %a1, %b2
+  ret i64 %2
+}
+
+define i64 @main() {
+entry:
+  %user_main = call i64 @call_call_my_main()
+  ret i64 %user_main
+}
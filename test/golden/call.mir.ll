@@ -0,0 +1,33 @@
+
+define i64 @call_call_my_main() {
+BB0:
+  %_0 = alloca i64, align 8
+  %0 = call i64 @call_call_test(i64 1, i64 2)
+  store i64 %0, i64* %_0, align 4
+  %1 = load i64, i64* %_0, align 4
+  ret i64 %1
+}
+
+define i64 @call_call_test(i64 %a, i64 %b) {
+BB0:
+  %_0 = alloca i64, align 8
+  %0 = call i64 @call_call_add(i64 %a, i64 %b)
+  store i64 %0, i64* %_0, align 4
+  %1 = load i64, i64* %_0, align 4
+  ret i64 %1
+}
+
+define i64 @call_call_add(i64 %a, i64 %b) {
+BB0:
+  %_0 = alloca i64, align 8
+  %0 = add i64 %a, %b
+  store i64 %0, i64* %_0, align 4
+  %1 = load i64, i64* %_0, align 4
+  ret i64 %1
+}
+
+define i64 @main() {
+entry:
+  %user_main = call i64 @call_call_my_main()
+  ret i64 %user_main
+}
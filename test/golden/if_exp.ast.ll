@@ -0,0 +1,34 @@
+
+define i64 @if_exp_if_exp_my_main() {
+entry:
+  %result = call i64 @if_exp_if_exp_test(i64 4)
+  ret i64 %result
+}
+
+define i64 @if_exp_if_exp_test(i64 %0) {
+entry:
+  %x = alloca i64, align 8
+  store i64 %0, i64* %x, align 4
+  %x1 = load i64, i64* %x, align 4
+  %1 = icmp slt i64 %x1, 10
+  br i1 %1, label %then, label %else
+
+then:                                             ; preds = %entry
+  %x2 = load i64, i64* %x, align 4
+  %2 = mul i64 %x2, 2
+  br label %merge
+
+else:                                             ; preds = %entry
+  %x3 = load i64, i64* %x, align 4
+  br label %merge
+
+merge:                                            ; preds = %else, %then
+  %phi = phi i64 [ %2, %then ], [ %x3, %else ]
+  ret i64 %phi
+}
+
+define i64 @main() {
+entry:
+  %user_main = call i64 @if_exp_if_exp_my_main()
+  ret i64 %user_main
+}
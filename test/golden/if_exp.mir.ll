@@ -0,0 +1,41 @@
+
+define i64 @if_exp_if_exp_my_main() {
+BB0:
+  %_0 = alloca i64, align 8
+  %0 = call i64 @if_exp_if_exp_test(i64 4)
+  store i64 %0, i64* %_0, align 4
+  %1 = load i64, i64* %_0, align 4
+  ret i64 %1
+}
+
+define i64 @if_exp_if_exp_test(i64 %x) {
+BB0:
+  %_0 = alloca i1, align 1
+  %_1 = alloca i64, align 8
+  %_2 = alloca i64, align 8
+  %0 = icmp slt i64 %x, 10
+  store i1 %0, i1* %_0, align 1
+  %1 = load i1, i1* %_0, align 1
+  br i1 %1, label %BB1, label %BB2
+
+BB1:                                              ; preds = %BB0
+  %2 = mul i64 %x, 2
+  store i64 %2, i64* %_2, align 4
+  %3 = load i64, i64* %_2, align 4
+  store i64 %3, i64* %_1, align 4
+  br label %BB3
+
+BB2:                                              ; preds = %BB0
+  store i64 %x, i64* %_1, align 4
+  br label %BB3
+
+BB3:                                              ; preds = %BB2, %BB1
+  %4 = load i64, i64* %_1, align 4
+  ret i64 %4
+}
+
+define i64 @main() {
+entry:
+  %user_main = call i64 @if_exp_if_exp_my_main()
+  ret i64 %user_main
+}
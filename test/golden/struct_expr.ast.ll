@@ -0,0 +1,40 @@
+
+%struct_expr_struct_expr_Point = type { i64, i64 }
+
+define i64 @struct_expr_struct_expr_my_main() {
+entry:
+  %result = call i64 @struct_expr_struct_expr_test(i64 1, i64 2)
+  ret i64 %result
+}
+
+define i64 @struct_expr_struct_expr_test(i64 %0, i64 %1) {
+entry:
+  %a = alloca i64, align 8
+  store i64 %0, i64* %a, align 4
+  %b = alloca i64, align 8
+  store i64 %1, i64* %b, align 4
+  %2 = alloca %struct_expr_struct_expr_Point, align 8
+  %a1 = load i64, i64* %a, align 4
+  %3 = getelementptr inbounds %struct_expr_struct_expr_Point, %struct_expr_struct_expr_Point* %2, i32 0, i32 0
+  store i64 %a1, i64* %3, align 4
+  %b2 = load i64, i64* %b, align 4
+  %4 = getelementptr inbounds %struct_expr_struct_expr_Point, %struct_expr_struct_expr_Point* %2, i32 0, i32 1
+  store i64 %b2, i64* %4, align 4
+  %p = alloca %struct_expr_struct_expr_Point, align 8
+  %5 = bitcast %struct_expr_struct_expr_Point* %p to i8*
+  %6 = bitcast %struct_expr_struct_expr_Point* %2 to i8*
+  call void @llvm.memcpy.p0i8.p0i8.i64(i8* align 8 %5, i8* align 8 %6, i64 mul nuw (i64 ptrtoint (i64* getelementptr (i64, i64* null, i32 1) to i64), i64 2), i1 false)
+  %7 = getelementptr inbounds %struct_expr_struct_expr_Point, %struct_expr_struct_expr_Point* %p, i32 0, i32 1
+  %8 = load i64, i64* %7, align 4
+  ret i64 %8
+}
+
+define i64 @main() {
+entry:
+  %user_main = call i64 @struct_expr_struct_expr_my_main()
+  ret i64 %user_main
+}
+
+; Function Attrs: argmemonly nounwind willreturn
+declare void @llvm.memcpy.p0i8.p0i8.i64(i8* noalias nocapture writeonly, i8* noalias nocapture readonly, i64, i1 immarg)
+
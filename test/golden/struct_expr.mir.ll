@@ -0,0 +1,37 @@
+
+%struct_expr_struct_expr_Point = type { i64, i64 }
+
+define i64 @struct_expr_struct_expr_my_main() {
+BB0:
+  %_0 = alloca i64, align 8
+  %0 = call i64 @struct_expr_struct_expr_test(i64 1, i64 2)
+  store i64 %0, i64* %_0, align 4
+  %1 = load i64, i64* %_0, align 4
+  ret i64 %1
+}
+
+define i64 @struct_expr_struct_expr_test(i64 %a, i64 %b) {
+BB0:
+  %p_0 = alloca %struct_expr_struct_expr_Point, align 8
+  %_0 = alloca %struct_expr_struct_expr_Point, align 8
+  %0 = getelementptr inbounds %struct_expr_struct_expr_Point, %struct_expr_struct_expr_Point* %_0, i32 0, i32 0
+  store i64 %a, i64* %0, align 4
+  %1 = getelementptr inbounds %struct_expr_struct_expr_Point, %struct_expr_struct_expr_Point* %_0, i32 0, i32 1
+  store i64 %b, i64* %1, align 4
+  %2 = bitcast %struct_expr_struct_expr_Point* %p_0 to i8*
+  %3 = bitcast %struct_expr_struct_expr_Point* %_0 to i8*
+  call void @llvm.memcpy.p0i8.p0i8.i64(i8* align 8 %2, i8* align 8 %3, i64 mul nuw (i64 ptrtoint (i64* getelementptr (i64, i64* null, i32 1) to i64), i64 2), i1 false)
+  %4 = getelementptr inbounds %struct_expr_struct_expr_Point, %struct_expr_struct_expr_Point* %p_0, i32 0, i32 1
+  %5 = load i64, i64* %4, align 4
+  ret i64 %5
+}
+
+; Function Attrs: argmemonly nounwind willreturn
+declare void @llvm.memcpy.p0i8.p0i8.i64(i8* noalias nocapture writeonly, i8* noalias nocapture readonly, i64, i1 immarg)
+
+define i64 @main() {
+entry:
+  %user_main = call i64 @struct_expr_struct_expr_my_main()
+  ret i64 %user_main
+}
+
@@ -0,0 +1,37 @@
+
+define i64 @while_exp_while_exp_my_main() {
+entry:
+  %result = call i64 @while_exp_while_exp_test(i64 5)
+  ret i64 %result
+}
+
+define i64 @while_exp_while_exp_test(i64 %0) {
+entry:
+  %n = alloca i64, align 8
+  store i64 %0, i64* %n, align 4
+  %x = alloca i64, align 8
+  store i64 0, i64* %x, align 4
+  br label %while_cond
+
+while_cond:                                       ; preds = %while_body, %entry
+  %x1 = load i64, i64* %x, align 4
+  %n2 = load i64, i64* %n, align 4
+  %1 = icmp slt i64 %x1, %n2
+  br i1 %1, label %while_body, label %while_end
+
+while_body:                                       ; preds = %while_cond
+  %x3 = load i64, i64* %x, align 4
+  %2 = add i64 %x3, 1
+  store i64 %2, i64* %x, align 4
+  br label %while_cond
+
+while_end:                                        ; preds = %while_cond
+  %x4 = load i64, i64* %x, align 4
+  ret i64 %x4
+}
+
+define i64 @main() {
+entry:
+  %user_main = call i64 @while_exp_while_exp_my_main()
+  ret i64 %user_main
+}
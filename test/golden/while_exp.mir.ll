@@ -0,0 +1,43 @@
+
+define i64 @while_exp_while_exp_my_main() {
+BB0:
+  %_0 = alloca i64, align 8
+  %0 = call i64 @while_exp_while_exp_test(i64 5)
+  store i64 %0, i64* %_0, align 4
+  %1 = load i64, i64* %_0, align 4
+  ret i64 %1
+}
+
+define i64 @while_exp_while_exp_test(i64 %n) {
+BB0:
+  %x_0 = alloca i64, align 8
+  %_0 = alloca i1, align 1
+  %_1 = alloca i64, align 8
+  store i64 0, i64* %x_0, align 4
+  br label %BB1
+
+BB1:                                              ; preds = %BB2, %BB0
+  %0 = load i64, i64* %x_0, align 4
+  %1 = icmp slt i64 %0, %n
+  store i1 %1, i1* %_0, align 1
+  %2 = load i1, i1* %_0, align 1
+  br i1 %2, label %BB2, label %BB3
+
+BB2:                                              ; preds = %BB1
+  %3 = load i64, i64* %x_0, align 4
+  %4 = add i64 %3, 1
+  store i64 %4, i64* %_1, align 4
+  %5 = load i64, i64* %_1, align 4
+  store i64 %5, i64* %x_0, align 4
+  br label %BB1
+
+BB3:                                              ; preds = %BB1
+  %6 = load i64, i64* %x_0, align 4
+  ret i64 %6
+}
+
+define i64 @main() {
+entry:
+  %user_main = call i64 @while_exp_while_exp_my_main()
+  ret i64 %user_main
+}
@@ -0,0 +1,107 @@
+#!/bin/sh
+
+#   Tests the Bramble calling convention across separately compiled units.
+#
+#   The functions in `./abi/callee` are compiled into one object file and the program in
+#   `./abi/caller.br` is compiled into another, which imports `callee` through its manifest.
+#   Both are then linked with `./abi/abi.c`, which calls Bramble functions from C and is called
+#   from Bramble.  Every combination of code generator (AST and MIR) for the callee and the
+#   caller is tested, so the two code generators must agree on the convention described in
+#   `design/internals/calling-convention.md`.
+
+num_tests=0
+num_pass=0
+
+std_dir=./target/std
+build_dir=./target/abi
+target=debug
+
+if [[ $OSTYPE == "darwin"* ]]; then
+	platform=machos
+	link_flags=""
+else
+	platform=linux
+	link_flags="-no-pie -fno-pie"
+fi
+
+build_std() {
+	rm -rf ${std_dir}
+	mkdir -p ${std_dir}
+
+	../target/${target}/bramblec --mir-beta --llvm -p ${platform} -i ../bramble/std -o ${std_dir}/std.obj --manifest >${std_dir}/stdout 2>/dev/null
+	mv ./target/std.manifest ${std_dir}/.
+}
+
+# Compiles the callee with the pipeline named by $1 and the caller with the pipeline named
+# by $2, then links and runs the program.
+run_test() {
+	callee=$1
+	caller=$2
+	out=${build_dir}/${callee}-${caller}
+	rm -rf ${out}
+	mkdir -p ${out}
+
+	callee_flags=""
+	if [[ ${callee} == "mir" ]]; then
+		callee_flags="--mir-beta"
+	fi
+	caller_flags=""
+	if [[ ${caller} == "mir" ]]; then
+		caller_flags="--mir-beta"
+	fi
+
+	../target/${target}/bramblec ${callee_flags} --llvm -p ${platform} -i ./abi/callee -o ${out}/callee.obj --manifest >${out}/stdout 2>/dev/null
+	mv ./target/callee.manifest ${out}/.
+	../target/${target}/bramblec ${caller_flags} --llvm -p ${platform} --import ${std_dir}/std.manifest,${out}/callee.manifest -i ./abi/caller.br -o ${out}/caller.obj >>${out}/stdout 2>/dev/null
+
+	if [[ -f ${out}/callee.obj && -f ${out}/caller.obj ]]; then
+		gcc ${link_flags} -w ${std_dir}/std.obj ${out}/callee.obj ${out}/caller.obj ./abi/abi.c -g -o ${out}/output -m64 >/dev/null 2>&1
+		if [[ $? -eq 0 ]]; then
+			timeout 5s "${out}/output" >>${out}/stdout
+		else
+			echo "Build failed" >>${out}/stdout
+		fi
+	fi
+
+	result=$(diff ${out}/stdout ./abi/caller.br.out)
+	if [[ $? -eq 0 ]]; then
+		((num_pass = num_pass + 1))
+		echo "callee: ${callee}, caller: ${caller}: Pass"
+	else
+		echo "callee: ${callee}, caller: ${caller}: Fail"
+		echo "${result}"
+		echo "\n-------------"
+	fi
+}
+
+if [[ ${target} == "debug" ]]; then
+	cargo build
+else
+	cargo build --${target}
+fi
+
+if [ $? -eq 0 ]; then
+	start_time=$SECONDS
+
+	mkdir -p ./target
+
+	echo "Building STD Library"
+	build_std
+
+	echo "Running ABI Tests"
+	for callee in ast mir; do
+		for caller in ast mir; do
+			((num_tests = num_tests + 1))
+			run_test $callee $caller
+		done
+	done
+
+	stop_time=$SECONDS
+	duration=$((stop_time - start_time))
+	echo ""
+	echo "${num_pass}/${num_tests} Tests Passed in ${duration}secs"
+
+	if [ ${num_pass} -ne ${num_tests} ]; then
+		exit 1
+	fi
+fi
@@ -0,0 +1,154 @@
+#!/bin/sh
+
+#   Differential test between the two code generation pipelines.
+#
+#   Every Bramble integration test program is compiled twice: once by generating LLVM IR
+#   directly from the AST and once by generating LLVM IR from the MIR (`--mir-beta`).  Both
+#   programs are then run and the test passes if the compiler output, the program output,
+#   and the exit code are identical for both pipelines.  This catches places where the
+#   two lowering paths have drifted apart, even when a test has no expected output.  A
+#   test fails if either pipeline cannot compile or link it.
+#
+#   Strings and the `opt` and `res` types are not supported by the MIR pipeline, and
+#   coroutines are only supported by the MIR pipeline, so any test which uses them is
+#   skipped.  So is every test which `test.sh` compiles from the AST (it has a `.ast`
+#   file) and every test which expects the MIR to reject the program (its expected
+#   output mentions `--mir-beta`).
+
+num_tests=0
+num_pass=0
+num_skip=0
+
+# Matches the source of a test which uses a feature that the MIR does not support
+unsupported='"|\bString\b|\bopt\b|\bres\b'
+
+std_dir=./target/std
+build_dir=./target/diff
+target=debug
+
+if [[ $OSTYPE == "darwin"* ]]; then
+	platform=machos
+	link_flags=""
+else
+	platform=linux
+	link_flags="-no-pie -fno-pie"
+fi
+
+build_std() {
+	rm -rf ${std_dir}
+	mkdir -p ${std_dir}
+
+	../target/${target}/bramblec --mir-beta --llvm -p ${platform} -i ../bramble/std -o ${std_dir}/std.obj --manifest >${std_dir}/stdout 2>/dev/null
+	mv ./target/std.manifest ./target/std/.
+}
+
+# Compiles and runs a test with the given pipeline.  The output of the compiler and of the
+# program are written to `${out}/stdout`, the diagnostics of the compiler to `${out}/stderr`,
+# and the exit code of the program to `${out}/exit`.  Returns 1 if the test could not be
+# compiled or linked.
+compile_and_run() {
+	test=$1
+	out=$2
+	flags=$3
+	input="./src/${test}.in"
+
+	mkdir -p ${out}
+	echo "not run" >${out}/exit
+	../target/${target}/bramblec ${flags} --llvm -p ${platform} --import ${std_dir}/std.manifest -i ./src/${test} -o ${out}/output.obj >${out}/stdout 2>${out}/stderr
+
+	if [ ! -f "${out}/output.obj" ]; then
+		echo "Compile failed" >>${out}/stderr
+		return 1
+	fi
+
+	gcc ${link_flags} -w ${std_dir}/std.obj ${out}/output.obj -g -o ${out}/output -m64 >>${out}/stderr 2>&1
+	if [[ $? -ne 0 ]]; then
+		echo "Build failed" >>${out}/stderr
+		return 1
+	fi
+
+	if [[ -f $input ]]; then
+		timeout 5s "${out}/output" <$input >>${out}/stdout
+	else
+		timeout 5s "${out}/output" >>${out}/stdout
+	fi
+	echo $? >${out}/exit
+}
+
+run_test() {
+	rm -rf ${build_dir}
+	mkdir -p ${build_dir}
+	test=$1
+
+	compile_and_run $test ${build_dir}/ast ""
+	ast_result=$?
+	compile_and_run $test ${build_dir}/mir "--mir-beta"
+	mir_result=$?
+
+	stdout=$(diff ${build_dir}/ast/stdout ${build_dir}/mir/stdout)
+	stdout_result=$?
+	exit_code=$(diff ${build_dir}/ast/exit ${build_dir}/mir/exit 2>&1)
+	exit_result=$?
+
+	if [[ $ast_result -eq 0 && $mir_result -eq 0 && $stdout_result -eq 0 && $exit_result -eq 0 ]]; then
+		((num_pass = num_pass + 1))
+		echo "${test} Pass"
+	else
+		echo "${test}: Fail"
+		if [[ $ast_result -ne 0 ]]; then
+			echo "AST pipeline failed:"
+			cat ${build_dir}/ast/stderr
+		fi
+		if [[ $mir_result -ne 0 ]]; then
+			echo "MIR pipeline failed:"
+			cat ${build_dir}/mir/stderr
+		fi
+		if [[ $stdout_result -ne 0 ]]; then
+			echo "Output differs (AST < > MIR):"
+			echo "${stdout}"
+		fi
+		if [[ $exit_result -ne 0 ]]; then
+			echo "Exit code differs (AST < > MIR):"
+			echo "${exit_code}"
+		fi
+		echo "\n-------------"
+	fi
+}
+
+if [[ ${target} == "debug" ]]; then
+	cargo build
+else
+	cargo build --${target}
+fi
+
+if [ $? -eq 0 ]; then
+	start_time=$SECONDS
+
+	mkdir -p ./target
+
+	echo "Building STD Library"
+	build_std
+
+	echo "Running Differential Tests"
+	tests=$(find ./src | grep "\.br$" | grep -v "coroutine" | sort | sed 's/\.\/src\///')
+	for test in ${tests[@]}; do
+		if grep -qE "${unsupported}" ./src/${test} || [[ -f ./src/${test}.ast ]] \
+			|| grep -qs "mir-beta" ./src/${test}.out; then
+			((num_skip = num_skip + 1))
+			echo "${test} Skipped"
+			continue
+		fi
+
+		((num_tests = num_tests + 1))
+		run_test $test
+	done
+
+	stop_time=$SECONDS
+	duration=$((stop_time - start_time))
+	echo ""
+	echo "${num_pass}/${num_tests} Tests Passed in ${duration}secs (${num_skip} skipped)"
+
+	if [ ${num_pass} -ne ${num_tests} ]; then
+		exit 1
+	fi
+fi
@@ -0,0 +1,130 @@
+#!/bin/sh
+
+#   Golden file tests of the LLVM IR generated for each language construct.
+#
+#   Every program in `./golden` is a minimal use of a single construct (an if expression,
+#   a while loop, a structure expression, a call, or indexing into an array).  Each program
+#   is compiled with both code generators and the LLVM IR which is emitted is normalized and
+#   compared to the expected IR which is checked in next to the program: `<test>.ast.ll`
+#   for the AST code generator and `<test>.mir.ll` for the MIR code generator.  When a
+#   change to a code generator changes the code for a construct, the test fails with a diff
+#   of exactly what changed.
+#
+#   The IR is normalized by removing everything which depends on the machine running the
+#   tests: the module header, the target, attribute groups, and metadata.
+#
+#   A program which has no expected IR fails.  Run with `--bless` to write the IR which is
+#   generated now as the expected IR, for new programs and for programs whose IR has changed
+#   on purpose, then review the diff and check it in.
+
+num_tests=0
+num_pass=0
+num_blessed=0
+
+build_dir=./target/golden
+target=debug
+bless=0
+
+if [[ $1 == "--bless" ]]; then
+	bless=1
+fi
+
+if [[ $OSTYPE == "darwin"* ]]; then
+	platform=machos
+else
+	platform=linux
+fi
+
+# Removes every line and annotation of the LLVM IR in $1 which depends on the machine
+# running the tests.
+normalize() {
+	grep -v -E '^(; ModuleID|source_filename|target datalayout|target triple|attributes #|!)' $1 |
+		sed -E -e 's/ #[0-9]+//g' -e 's/, ![a-z.]+ ![0-9]+//g'
+}
+
+# Compiles the program $1 with the pipeline named by $2 and compares the normalized LLVM IR
+# to the expected IR.
+run_test() {
+	test=$1
+	pipeline=$2
+	out=${build_dir}/${test}/${pipeline}
+	expected=./golden/${test}.${pipeline}.ll
+
+	flags=""
+	if [[ ${pipeline} == "mir" ]]; then
+		flags="--mir-beta"
+	fi
+
+	rm -rf ${out}
+	mkdir -p ${out}
+	../target/${target}/bramblec ${flags} --llvm -p ${platform} --emit llvm-ir \
+		-i ./golden/${test}.br --out-dir ${out} -o ${out}/output.obj >${out}/stdout 2>/dev/null
+
+	if [[ ! -f ${out}/${test}.ll ]]; then
+		echo "${test} (${pipeline}): Fail"
+		echo "No LLVM IR was emitted:"
+		cat ${out}/stdout
+		echo "\n-------------"
+		return
+	fi
+
+	normalize ${out}/${test}.ll >${out}/normalized.ll
+
+	if [[ ${bless} -eq 1 ]]; then
+		cp ${out}/normalized.ll ${expected}
+		((num_blessed = num_blessed + 1))
+		echo "${test} (${pipeline}): Blessed"
+		return
+	fi
+
+	if [[ ! -f ${expected} ]]; then
+		echo "${test} (${pipeline}): Fail"
+		echo "No expected IR in ${expected}; run with --bless to write it"
+		echo "\n-------------"
+		return
+	fi
+
+	result=$(diff ${expected} ${out}/normalized.ll)
+	if [[ $? -eq 0 ]]; then
+		((num_pass = num_pass + 1))
+		echo "${test} (${pipeline}): Pass"
+	else
+		echo "${test} (${pipeline}): Fail"
+		echo "Generated IR differs (expected < > generated):"
+		echo "${result}"
+		echo "\n-------------"
+	fi
+}
+
+if [[ ${target} == "debug" ]]; then
+	cargo build
+else
+	cargo build --${target}
+fi
+
+if [ $? -eq 0 ]; then
+	start_time=$SECONDS
+
+	mkdir -p ./target
+
+	echo "Running Golden File Tests"
+	tests=$(find ./golden -name "*.br" | sort | sed -e 's/\.\/golden\///' -e 's/\.br$//')
+	for test in ${tests[@]}; do
+		for pipeline in ast mir; do
+			((num_tests = num_tests + 1))
+			run_test $test $pipeline
+		done
+	done
+
+	stop_time=$SECONDS
+	duration=$((stop_time - start_time))
+	echo ""
+	echo "${num_pass}/${num_tests} Tests Passed in ${duration}secs"
+	if [ ${num_blessed} -ne 0 ]; then
+		echo "Wrote the expected IR of ${num_blessed} tests; review it and check it in"
+	fi
+
+	if [ $((num_pass + num_blessed)) -ne ${num_tests} ]; then
+		exit 1
+	fi
+fi
@@ -0,0 +1,97 @@
+#!/bin/sh
+
+#   Tests that builds are reproducible across checkouts.
+#
+#   A project is copied into two different directories and each copy is compiled, with both
+#   code generators, using an absolute path to the copy and `--remap-path-prefix` to map the
+#   directory of the copy to the same path.  The test passes if every file which the two
+#   builds write (object code, LLVM IR, symbols, the trace and source map, and the compiler
+#   output) is identical, which means that no path specific to the machine is written out.
+
+num_tests=0
+num_pass=0
+
+std_dir=./target/std
+build_dir=./target/repro
+target=debug
+project=./src/projects/nested
+
+if [[ $OSTYPE == "darwin"* ]]; then
+	platform=machos
+else
+	platform=linux
+fi
+
+build_std() {
+	rm -rf ${std_dir}
+	mkdir -p ${std_dir}
+
+	../target/${target}/bramblec --mir-beta --llvm -p ${platform} -i ../bramble/std -o ${std_dir}/std.obj --manifest >${std_dir}/stdout 2>/dev/null
+	mv ./target/std.manifest ${std_dir}/.
+}
+
+# Copies the project into the checkout named by $2 and compiles it with the pipeline named
+# by $1.  Every output is written to `${build_dir}/$1/$2/out`.
+build() {
+	pipeline=$1
+	checkout=$(pwd)/${build_dir}/${pipeline}/$2
+	rm -rf ${checkout}
+	mkdir -p ${checkout}/out
+	cp -r ${project} ${checkout}/proj
+
+	flags=""
+	if [[ ${pipeline} == "mir" ]]; then
+		flags="--mir-beta"
+	fi
+
+	../target/${target}/bramblec ${flags} --llvm -p ${platform} --import ${std_dir}/std.manifest \
+		--emit llvm-ir,symbols --json-trace --remap-path-prefix ${checkout}=/build \
+		-i ${checkout}/proj --out-dir ${checkout}/out -o ${checkout}/out/proj.obj \
+		>${checkout}/out/stdout 2>/dev/null
+}
+
+run_test() {
+	pipeline=$1
+	build ${pipeline} first
+	build ${pipeline} second
+
+	result=$(diff -r ${build_dir}/${pipeline}/first/out ${build_dir}/${pipeline}/second/out)
+	if [[ $? -eq 0 && -f ${build_dir}/${pipeline}/first/out/proj.obj ]]; then
+		((num_pass = num_pass + 1))
+		echo "${pipeline}: Pass"
+	else
+		echo "${pipeline}: Fail"
+		echo "${result}"
+		echo "\n-------------"
+	fi
+}
+
+if [[ ${target} == "debug" ]]; then
+	cargo build
+else
+	cargo build --${target}
+fi
+
+if [ $? -eq 0 ]; then
+	start_time=$SECONDS
+
+	mkdir -p ./target
+
+	echo "Building STD Library"
+	build_std
+
+	echo "Running Reproducible Build Tests"
+	for pipeline in ast mir; do
+		((num_tests = num_tests + 1))
+		run_test $pipeline
+	done
+
+	stop_time=$SECONDS
+	duration=$((stop_time - start_time))
+	echo ""
+	echo "${num_pass}/${num_tests} Tests Passed in ${duration}secs"
+
+	if [ ${num_pass} -ne ${num_tests} ]; then
+		exit 1
+	fi
+fi
@@ -0,0 +1,116 @@
+#!/bin/sh
+
+#   Tests that the compiler keeps diagnostics and output on separate streams.
+#
+#   Every diagnostic (errors, warnings, traces and timings) must be written to stderr, so that
+#   stdout only contains output which the user asked for.  Each test compiles a program which
+#   is read from stdin and checks what was written to each stream.
+
+num_tests=0
+num_pass=0
+
+out_dir=./target/streams
+target=debug
+
+if [[ $OSTYPE == "darwin"* ]]; then
+	platform=machos
+else
+	platform=linux
+fi
+
+# Compiles the program in $1, read from stdin, with the remaining arguments as extra flags.
+# The streams are written to `${out_dir}/stdout` and `${out_dir}/stderr`.
+compile() {
+	src=$1
+	shift
+	echo "${src}" | ../target/${target}/bramblec --llvm -p ${platform} -i - --out-dir ${out_dir} "$@" \
+		>${out_dir}/stdout 2>${out_dir}/stderr
+}
+
+# Records the result of a test named $1, which passed if $2 is 0.
+check() {
+	((num_tests = num_tests + 1))
+	if [[ $2 -eq 0 ]]; then
+		((num_pass = num_pass + 1))
+		echo "$1: Pass"
+	else
+		echo "$1: Fail"
+		echo "stdout:"
+		cat ${out_dir}/stdout
+		echo "stderr:"
+		cat ${out_dir}/stderr
+		echo "\n-------------"
+	fi
+}
+
+test_error() {
+	compile "fn my_main() -> i64 {return true;}"
+	[[ $? -ne 0 && ! -s ${out_dir}/stdout ]] && grep -q "^Error: " ${out_dir}/stderr
+	check "error" $?
+}
+
+test_warning() {
+	compile "fn my_main() -> i64 {five(); return 0;} fn five() -> i64 {return 5;}"
+	[[ $? -eq 0 && ! -s ${out_dir}/stdout ]] && grep -q "^Warning: " ${out_dir}/stderr
+	check "warning" $?
+}
+
+test_trace() {
+	compile "fn my_main() -> i64 {return 0;}" --trace
+	[[ $? -eq 0 && ! -s ${out_dir}/stdout && -s ${out_dir}/stderr ]]
+	check "trace" $?
+}
+
+test_timings() {
+	src="fn my_main() -> i64 {return 0;}"
+	compile "${src}"
+	[[ $? -eq 0 && ! -s ${out_dir}/stderr ]]
+	quiet=$?
+	compile "${src}" --timings
+	[[ $? -eq 0 && ${quiet} -eq 0 && ! -s ${out_dir}/stdout ]] && grep -q "^Lexer: " ${out_dir}/stderr
+	check "timings" $?
+}
+
+test_mir_to_file() {
+	compile "fn my_main() -> i64 {return 0;}" --mir-beta --emit mir
+	[[ $? -eq 0 && ! -s ${out_dir}/stdout && -n $(find ${out_dir} -name "*.mir" -size +0) ]]
+	check "mir to file" $?
+}
+
+test_object_to_stdout() {
+	src="fn my_main() -> i64 {return 0;}"
+	compile "${src}" -o ${out_dir}/expected.obj
+	compile "${src}" -o -
+	[[ $? -eq 0 ]] && cmp -s ${out_dir}/stdout ${out_dir}/expected.obj
+	check "object to stdout" $?
+}
+
+if [[ ${target} == "debug" ]]; then
+	cargo build
+else
+	cargo build --${target}
+fi
+
+if [ $? -eq 0 ]; then
+	start_time=$SECONDS
+
+	rm -rf ${out_dir}
+	mkdir -p ${out_dir}
+
+	echo "Running Stream Tests"
+	test_error
+	test_warning
+	test_trace
+	test_timings
+	test_mir_to_file
+	test_object_to_stdout
+
+	stop_time=$SECONDS
+	duration=$((stop_time - start_time))
+	echo ""
+	echo "${num_pass}/${num_tests} Tests Passed in ${duration}secs"
+
+	if [ ${num_pass} -ne ${num_tests} ]; then
+		exit 1
+	fi
+fi
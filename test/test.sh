@@ -12,6 +12,13 @@
 #
 #   If a test requires input, then put each input, in order, in a `.in` file with each input on
 #   a separate line.
+#
+#   Tests are compiled with the MIR code generator (`--mir-beta`).  A test of a feature which
+#   the MIR does not support yet is compiled from the AST instead if there is an empty `.ast`
+#   file next to it.
+#
+#   If a test is expected to fail to compile, then its expected output is the diagnostics which
+#   the compiler writes to stderr.
 
 num_tests=0
 num_pass=0
@@ -39,10 +46,21 @@ run_test() {
 	input="./src/${test}.in"
 	built=1
 
+	mir_beta="--mir-beta"
+	if [[ -f "./src/${test}.ast" ]]; then
+		mir_beta=""
+	fi
+
 	if [[ $OSTYPE == "linux-gnu"* ]]; then
-		../target/${target}/bramblec --mir-beta --llvm -p linux --emit=llvm-ir --import ${std_dir}/std.manifest --json-trace -i ./src/${test} -o ${build_dir}/output.obj >${build_dir}/stdout 2>/dev/null
+		../target/${target}/bramblec ${mir_beta} --llvm -p linux --emit=llvm-ir --import ${std_dir}/std.manifest --json-trace -i ./src/${test} -o ${build_dir}/output.obj >${build_dir}/stdout 2>${build_dir}/stderr
 	elif [[ $OSTYPE == "darwin"* ]]; then
-		../target/${target}/bramblec --mir-beta --llvm -p machos --import ${std_dir}/std.manifest --json-trace -i ./src/${test} -o ${build_dir}/output.obj >${build_dir}/stdout 2>/dev/null
+		../target/${target}/bramblec ${mir_beta} --llvm -p machos --import ${std_dir}/std.manifest --json-trace -i ./src/${test} -o ${build_dir}/output.obj >${build_dir}/stdout 2>${build_dir}/stderr
+	fi
+
+	# The compiler writes its diagnostics to stderr, so when compilation fails they are the
+	# output which is compared with the expected output
+	if [ ! -f "${build_dir}/output.obj" ]; then
+		cat ${build_dir}/stderr >>${build_dir}/stdout
 	fi
 
 	# If there were no compilation errors then run the assembler and linker
@@ -104,10 +122,10 @@ run_fail_test() {
 
 	if [[ $OSTYPE == "linux-gnu"* ]]; then
 		../target/${target}/bramblec --llvm -p linux -i ../bramble/std -o ./target/std.obj >./target/stdout 2>/dev/null
-		../target/${target}/bramblec --llvm -p linux -i ./src/${test} -o ./target/output.obj >./target/stdout 2>/dev/null
+		../target/${target}/bramblec --llvm -p linux -i ./src/${test} -o ./target/output.obj >./target/stdout 2>./target/stderr
 	elif [[ $OSTYPE == "darwin"* ]]; then
 		../target/${target}/bramblec --llvm -p machos -i ../bramble/std -o ./target/std.obj >./target/stdout 2>/dev/null
-		../target/${target}/bramblec --llvm -p machos -i ./src/${test} -o ./target/output.obj >./target/stdout 2>/dev/null
+		../target/${target}/bramblec --llvm -p machos -i ./src/${test} -o ./target/output.obj >./target/stdout 2>./target/stderr
 	fi
 
 	# If there were no compilation errors then run the assembler and linker
@@ -120,8 +138,7 @@ run_fail_test() {
 			built=$?
 		else
 			# If we can't figure out the OS, then just try the Linux build steps
-			nasm -g -f elf64 ../bramble/linux/llvm/std/io.asm -l ./target/std_io_llvm.lst -o ./target/std_io_llvm.obj >assembler.log
-			gcc -no-pie -fno-pie -w ./target/std_io_llvm.obj ./target/output.obj -g -o ./target/output -m64 2>&1 >gcc.log
+			gcc -no-pie -fno-pie -w ./target/std.obj ./target/output.obj -g -o ./target/output -m64 2>&1 >gcc.log
 			built=$?
 		fi
 
@@ -131,6 +148,9 @@ run_fail_test() {
 		else
 			echo "${test}: Fail"
 		fi
+	else
+		echo "${test}: Fail"
+		cat ./target/stderr
 	fi
 }
 
@@ -149,7 +169,7 @@ if [ $? -eq 0 ]; then
 	build_std
 
 	echo "Running Tests"
-	tests=$(find ./src | grep "\.out" | grep -v "coroutine" | sort | sed 's/\.\/src\/\(.*\)\.out/\1/')
+	tests=$(find ./src | grep "\.out" | sort | sed 's/\.\/src\/\(.*\)\.out/\1/')
 	for test in ${tests[@]}; do
 		((num_tests = num_tests + 1))
 		run_test $test
@@ -157,7 +177,7 @@ if [ $? -eq 0 ]; then
 
 	echo ""
 	echo "Test Failure Cases"
-	tests=$(find ./src | grep "\.fail" | grep -v "coroutine" | sort | sed 's/\.\/src\/\(.*\)\.fail/\1/')
+	tests=$(find ./src | grep "\.fail" | sort | sed 's/\.\/src\/\(.*\)\.fail/\1/')
 	for test in ${tests[@]}; do
 		((num_tests = num_tests + 1))
 		run_fail_test $test
@@ -6,6 +6,9 @@
 #  1. The Bramble language test suite
 #  2. The Bramble project import test suitee
 #  3. The Bramble fuzzy syntax correctness test suite
+#  4. The stdout/stderr separation test suite
+#  5. The golden file tests of the generated LLVM IR
+#  6. The differential tests between the AST and MIR code generation pipelines
 
 ret=0
 cargo test --release
@@ -30,4 +33,19 @@ if [[ $? -ne 0 ]]; then
     ret=$(($ret + 1))
 fi
 
+./test-streams.sh
+if [[ $? -ne 0 ]]; then 
+    ret=$(($ret + 1))
+fi
+
+./test-golden.sh
+if [[ $? -ne 0 ]]; then 
+    ret=$(($ret + 1))
+fi
+
+./test-differential.sh
+if [[ $? -ne 0 ]]; then 
+    ret=$(($ret + 1))
+fi
+
 exit $ret
\ No newline at end of file
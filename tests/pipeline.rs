@@ -0,0 +1,156 @@
+//! Compiles programs through the library's [`Pipeline`] with custom passes registered,
+//! the way that a program which embeds the compiler would.
+
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+use bramble_lang::{
+    build_source_map,
+    compiler::{
+        ast::{Context, Item, Module, Node},
+        diagnostics::Logger,
+        fuel::Fuel,
+        parser::ParserContext,
+        passes::{AstPass, MirPass, PassDiagnostics, PassRegistry, SemanticPass},
+        pipeline::{CompileError, Pipeline},
+        semantics::semanticnode::SemanticContext,
+        MirProject, SourceMap,
+    },
+    parse_project, tokenize_source_map, MemoryProvider, StringTable,
+};
+
+const PROGRAM: &str = "
+fn my_main() -> i64 {
+    return helper(2);
+}
+
+fn helper(x: i64) -> i64 {
+    return x * 2;
+}
+";
+
+/// Reads and parses a project whose only file is `main.br`.
+fn load(text: &str) -> (SourceMap, StringTable, Module<ParserContext>) {
+    let mut files = MemoryProvider::new();
+    files.add_file("/proj/main.br", text);
+    let proj = Path::new("/proj");
+
+    let sm = build_source_map(&files, &[proj], "br").unwrap();
+    let table = StringTable::new();
+    let logger = Logger::new();
+    let tokens = tokenize_source_map(&files, &sm, &[proj], &table, &logger).unwrap();
+    let root = parse_project(
+        table.insert("proj".into()),
+        tokens,
+        &sm,
+        &table,
+        &logger,
+        &Fuel::unlimited(),
+    )
+    .unwrap();
+    (sm, table, root)
+}
+
+/// Warns about every function in the program.
+struct WarnFunctions;
+
+impl SemanticPass for WarnFunctions {
+    fn name(&self) -> &str {
+        "warn-functions"
+    }
+
+    fn run(
+        &mut self,
+        module: &mut Module<SemanticContext>,
+        _: &StringTable,
+        diags: &mut PassDiagnostics,
+    ) {
+        for f in module.deep_get_functions() {
+            diags.warn(f.context().span(), "found a function")
+        }
+    }
+}
+
+/// Records how many procedures are in the MIR.
+struct CountProcedures(Rc<RefCell<Option<usize>>>);
+
+impl MirPass for CountProcedures {
+    fn name(&self) -> &str {
+        "count-procedures"
+    }
+
+    fn run(&mut self, project: &mut MirProject, _: &StringTable, _: &mut PassDiagnostics) {
+        *self.0.borrow_mut() = Some(project.function_iter().count())
+    }
+}
+
+/// Rejects every program which has a function named `helper`.
+struct RejectHelper;
+
+impl AstPass for RejectHelper {
+    fn name(&self) -> &str {
+        "reject-helper"
+    }
+
+    fn run(
+        &mut self,
+        module: &mut Module<ParserContext>,
+        st: &StringTable,
+        diags: &mut PassDiagnostics,
+    ) {
+        let helper = st.insert("helper".into());
+        for item in module.deep_get_functions() {
+            if let Item::Routine(r) = item {
+                if r.get_name() == helper {
+                    diags.error(r.context().span(), "helper is not allowed")
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn registered_passes_are_run() {
+    let (sm, table, root) = load(PROGRAM);
+
+    let procedures = Rc::new(RefCell::new(None));
+    let mut passes = PassRegistry::new();
+    passes.register_semantic_pass(WarnFunctions);
+    passes.register_mir_pass(CountProcedures(procedures.clone()));
+
+    let logger = Logger::new();
+    let mut pipeline = Pipeline::new(&mut passes, &sm, &table, &logger);
+    let main_fn = table.insert("my_main".into());
+    assert!(pipeline.compile(root, main_fn, 64).is_ok());
+
+    let warnings = pipeline.take_warnings();
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings
+        .iter()
+        .all(|w| w.inner().pass() == "warn-functions"));
+    assert!(pipeline.take_warnings().is_empty());
+
+    assert_eq!(*procedures.borrow(), Some(2));
+}
+
+#[test]
+fn pass_error_stops_the_pipeline() {
+    let (sm, table, root) = load(PROGRAM);
+
+    let procedures = Rc::new(RefCell::new(None));
+    let mut passes = PassRegistry::new();
+    passes.register_ast_pass(RejectHelper);
+    passes.register_mir_pass(CountProcedures(procedures.clone()));
+
+    let logger = Logger::new();
+    let mut pipeline = Pipeline::new(&mut passes, &sm, &table, &logger);
+    let main_fn = table.insert("my_main".into());
+    match pipeline.compile(root, main_fn, 64) {
+        Err(CompileError::Pass(errs)) => {
+            assert_eq!(errs.len(), 1);
+            assert_eq!(errs[0].inner().message(), "helper is not allowed");
+        }
+        r => panic!("Expected a pass error, got {:?}", r.map(|_| ())),
+    }
+
+    assert_eq!(*procedures.borrow(), None);
+}